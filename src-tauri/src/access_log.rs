@@ -0,0 +1,142 @@
+// access_log.rs - Recently viewed items for brainbox
+// Every time an item is opened a row is logged, which feeds a cross-vault "recent" list and an optional
+// frequency boost in search ranking. Only ids and timestamps are stored, never content.
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::error::BrainboxError;
+use crate::search::SearchResult;
+
+/// Reopening the same item within this many seconds isn't logged again
+const DEDUPE_SECONDS: i64 = 60;
+/// Oldest rows are pruned beyond this many entries
+const MAX_ENTRIES: i64 = 5000;
+/// Only accesses this recent count towards the search boost
+const BOOST_WINDOW_DAYS: i64 = 90;
+/// Score multiplier per ln(1 + opens); 20 opens roughly doubles an item's score
+const BOOST_WEIGHT: f32 = 0.33;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RecentItem {
+    pub item_id: i64,
+    pub vault_id: i64,
+    pub vault_name: String,
+    /// None when the item's vault is password-protected and not unlocked
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item_type: Option<String>,
+    pub locked: bool,
+    pub last_accessed_at: String,
+    pub access_count: i64,
+}
+
+pub fn create_table(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS item_access_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            item_id INTEGER NOT NULL REFERENCES vault_items(id) ON DELETE CASCADE,
+            accessed_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_item_access_log_item ON item_access_log(item_id, accessed_at)",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Log that an item was opened
+pub fn record(conn: &Connection, item_id: i64) -> Result<(), BrainboxError> {
+    create_table(conn)?;
+    let now = chrono::Utc::now();
+    let cutoff = (now - chrono::Duration::seconds(DEDUPE_SECONDS)).to_rfc3339();
+    let recent: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM item_access_log WHERE item_id = ?1 AND accessed_at > ?2",
+        params![item_id, cutoff],
+        |row| row.get(0),
+    )?;
+    if recent > 0 {
+        return Ok(());
+    }
+    let inserted = conn.execute(
+        "INSERT INTO item_access_log (item_id, accessed_at)
+         SELECT ?1, ?2 WHERE EXISTS (SELECT 1 FROM vault_items WHERE id = ?1 AND deleted_at IS NULL)",
+        params![item_id, now.to_rfc3339()],
+    )?;
+    if inserted == 0 {
+        return Err(BrainboxError::NotFound(format!("Item {} not found", item_id)));
+    }
+    conn.execute(
+        "DELETE FROM item_access_log WHERE id <= (SELECT MAX(id) FROM item_access_log) - ?1",
+        [MAX_ENTRIES],
+    )?;
+    Ok(())
+}
+
+/// Most recently opened items across all vaults, newest first. Titles of items in password-protected
+/// vaults are withheld unless the vault is in `unlocked_vault_ids`.
+pub fn list_recent(conn: &Connection, limit: usize, unlocked_vault_ids: &[i64]) -> Result<Vec<RecentItem>, BrainboxError> {
+    create_table(conn)?;
+    let mut stmt = conn.prepare(
+        "SELECT l.item_id, i.vault_id, v.name, v.has_password, i.title, i.item_type, MAX(l.accessed_at), COUNT(*)
+         FROM item_access_log l
+         JOIN vault_items i ON i.id = l.item_id AND i.deleted_at IS NULL
+         JOIN vaults v ON v.id = i.vault_id AND v.deleted_at IS NULL
+         GROUP BY l.item_id
+         ORDER BY MAX(l.accessed_at) DESC
+         LIMIT ?1",
+    )?;
+    let rows = stmt.query_map([limit as i64], |row| {
+        let vault_id: i64 = row.get(1)?;
+        let has_password: bool = row.get(3)?;
+        let locked = has_password && !unlocked_vault_ids.contains(&vault_id);
+        Ok(RecentItem {
+            item_id: row.get(0)?,
+            vault_id,
+            vault_name: row.get(2)?,
+            title: if locked { None } else { row.get(4)? },
+            item_type: if locked { None } else { row.get(5)? },
+            locked,
+            last_accessed_at: row.get(6)?,
+            access_count: row.get(7)?,
+        })
+    })?;
+    Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+}
+
+pub fn clear(conn: &Connection) -> Result<(), BrainboxError> {
+    create_table(conn)?;
+    conn.execute("DELETE FROM item_access_log", [])?;
+    Ok(())
+}
+
+/// Item id of a search document: backend documents use the bare id, frontend ones "vault-{v}-item-{id}"
+fn item_id_of(doc_id: &str) -> Option<i64> {
+    doc_id.rsplit("-item-").next().and_then(|id| id.parse().ok())
+}
+
+/// Scale scores by how often each item was opened recently and re-sort
+pub fn boost_results(conn: &Connection, results: &mut [SearchResult]) -> Result<(), BrainboxError> {
+    create_table(conn)?;
+    let since = (chrono::Utc::now() - chrono::Duration::days(BOOST_WINDOW_DAYS)).to_rfc3339();
+    let mut stmt = conn.prepare(
+        "SELECT item_id, COUNT(*) FROM item_access_log WHERE accessed_at > ?1 GROUP BY item_id",
+    )?;
+    let counts: HashMap<i64, i64> = stmt
+        .query_map([since], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+    if counts.is_empty() {
+        return Ok(());
+    }
+    for result in results.iter_mut() {
+        if let Some(count) = item_id_of(&result.id).and_then(|id| counts.get(&id)) {
+            result.score *= 1.0 + BOOST_WEIGHT * (1.0 + *count as f32).ln();
+        }
+    }
+    results.sort_by(|a, b| b.score.total_cmp(&a.score));
+    Ok(())
+}
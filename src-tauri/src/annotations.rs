@@ -0,0 +1,291 @@
+// annotations.rs - Highlights and notes on items for brainbox
+// An annotation quotes a passage of an item, optionally with a note and a color, plus an anchor saying where the
+// passage sits in whatever form the reader that made it uses (offsets, a text-quote selector). Quote, note and
+// anchor are sealed under the vault's content key like the item. Annotations travel inside their item in sync and
+// export: every change bumps the item's updated_at, and whichever copy of the item wins brings its annotations.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::envelope;
+use crate::error::BrainboxError;
+use crate::sync::SyncAnnotation;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Annotation {
+    pub id: i64,
+    pub uuid: String,
+    pub item_id: i64,
+    pub quote: String,
+    pub note: Option<String>,
+    /// #rgb or #rrggbb
+    pub color: Option<String>,
+    pub anchor: Option<serde_json::Value>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// Fields the frontend sets when creating or editing an annotation
+#[derive(Debug, Deserialize, Clone)]
+pub struct AnnotationInput {
+    pub quote: String,
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub anchor: Option<serde_json::Value>,
+}
+
+pub fn create_table(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS annotations (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            uuid TEXT NOT NULL UNIQUE,
+            item_id INTEGER NOT NULL REFERENCES vault_items(id) ON DELETE CASCADE,
+            quote BLOB NOT NULL,
+            note BLOB,
+            color TEXT,
+            anchor BLOB,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_annotations_item ON annotations(item_id)", [])?;
+    Ok(())
+}
+
+const COLUMNS: &str = "id, uuid, item_id, quote, note, color, anchor, created_at, updated_at";
+
+/// A row as stored, still sealed
+struct Row {
+    id: i64,
+    uuid: String,
+    item_id: i64,
+    quote: Vec<u8>,
+    note: Option<Vec<u8>>,
+    color: Option<String>,
+    anchor: Option<Vec<u8>>,
+    created_at: String,
+    updated_at: String,
+}
+
+fn from_row(row: &rusqlite::Row) -> rusqlite::Result<Row> {
+    Ok(Row {
+        id: row.get(0)?,
+        uuid: row.get(1)?,
+        item_id: row.get(2)?,
+        quote: row.get(3)?,
+        note: row.get(4)?,
+        color: row.get(5)?,
+        anchor: row.get(6)?,
+        created_at: row.get(7)?,
+        updated_at: row.get(8)?,
+    })
+}
+
+fn open_text(key: &[u8; 32], sealed: &[u8]) -> Result<String, BrainboxError> {
+    String::from_utf8(envelope::open(key, sealed)?).map_err(|_| BrainboxError::Crypto("Invalid UTF-8".to_string()))
+}
+
+impl Row {
+    fn open(self, key: &[u8; 32]) -> Result<Annotation, BrainboxError> {
+        let anchor = match &self.anchor {
+            Some(sealed) => Some(serde_json::from_str(&open_text(key, sealed)?)?),
+            None => None,
+        };
+        Ok(Annotation {
+            id: self.id,
+            uuid: self.uuid,
+            item_id: self.item_id,
+            quote: open_text(key, &self.quote)?,
+            note: self.note.as_deref().map(|note| open_text(key, note)).transpose()?,
+            color: self.color,
+            anchor,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+        })
+    }
+}
+
+/// An input checked and ready to store
+struct Sealed {
+    quote: Vec<u8>,
+    note: Option<Vec<u8>>,
+    color: Option<String>,
+    anchor: Option<Vec<u8>>,
+}
+
+fn seal_input(key: &[u8; 32], input: &AnnotationInput) -> Result<Sealed, BrainboxError> {
+    if input.quote.trim().is_empty() {
+        return Err(BrainboxError::InvalidInput("An annotation needs the text it highlights".to_string()));
+    }
+    let color = input.color.as_deref().map(str::trim).filter(|c| !c.is_empty());
+    if let Some(color) = color {
+        if !crate::vault::is_valid_color(color) {
+            return Err(BrainboxError::InvalidInput(format!("Invalid color: {}", color)));
+        }
+    }
+    let note = input.note.as_deref().map(str::trim).filter(|n| !n.is_empty());
+    let anchor = input.anchor.as_ref().filter(|a| !a.is_null()).map(serde_json::to_string).transpose()?;
+    Ok(Sealed {
+        quote: envelope::seal(key, input.quote.as_bytes())?,
+        note: note.map(|n| envelope::seal(key, n.as_bytes())).transpose()?,
+        color: color.map(str::to_lowercase),
+        anchor: anchor.map(|a| envelope::seal(key, a.as_bytes())).transpose()?,
+    })
+}
+
+/// Bump the item so the change goes out with it on the next sync
+fn touch_item(conn: &Connection, item_id: i64, now: &str) -> Result<(), BrainboxError> {
+    conn.execute("UPDATE vault_items SET updated_at = ?1 WHERE id = ?2", params![now, item_id])?;
+    Ok(())
+}
+
+/// An item's annotations in the order they were made
+pub fn list(conn: &Connection, item_id: i64, key: &[u8; 32]) -> Result<Vec<Annotation>, BrainboxError> {
+    create_table(conn)?;
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM annotations WHERE item_id = ?1 ORDER BY created_at, id", COLUMNS))?;
+    let rows = stmt.query_map([item_id], from_row)?.collect::<rusqlite::Result<Vec<Row>>>()?;
+    rows.into_iter().map(|row| row.open(key)).collect()
+}
+
+pub fn get(conn: &Connection, annotation_id: i64, key: &[u8; 32]) -> Result<Annotation, BrainboxError> {
+    create_table(conn)?;
+    conn.query_row(&format!("SELECT {} FROM annotations WHERE id = ?1", COLUMNS), [annotation_id], from_row)
+        .optional()?
+        .ok_or_else(|| BrainboxError::NotFound(format!("Annotation {} not found", annotation_id)))?
+        .open(key)
+}
+
+/// The item an annotation belongs to, so callers can find the vault key
+pub fn item_id(conn: &Connection, annotation_id: i64) -> Result<i64, BrainboxError> {
+    create_table(conn)?;
+    conn.query_row("SELECT item_id FROM annotations WHERE id = ?1", [annotation_id], |row| row.get(0))
+        .optional()?
+        .ok_or_else(|| BrainboxError::NotFound(format!("Annotation {} not found", annotation_id)))
+}
+
+pub fn create(conn: &Connection, item_id: i64, key: &[u8; 32], input: &AnnotationInput) -> Result<Annotation, BrainboxError> {
+    create_table(conn)?;
+    let sealed = seal_input(key, input)?;
+    let now = chrono::Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO annotations (uuid, item_id, quote, note, color, anchor, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)",
+        params![Uuid::new_v4().to_string(), item_id, sealed.quote, sealed.note, sealed.color, sealed.anchor, now],
+    )?;
+    let id = conn.last_insert_rowid();
+    touch_item(conn, item_id, &now)?;
+    get(conn, id, key)
+}
+
+pub fn update(conn: &Connection, annotation_id: i64, key: &[u8; 32], input: &AnnotationInput) -> Result<Annotation, BrainboxError> {
+    let item_id = item_id(conn, annotation_id)?;
+    let sealed = seal_input(key, input)?;
+    let now = chrono::Utc::now().to_rfc3339();
+    conn.execute(
+        "UPDATE annotations SET quote = ?1, note = ?2, color = ?3, anchor = ?4, updated_at = ?5 WHERE id = ?6",
+        params![sealed.quote, sealed.note, sealed.color, sealed.anchor, now, annotation_id],
+    )?;
+    touch_item(conn, item_id, &now)?;
+    get(conn, annotation_id, key)
+}
+
+/// Delete an annotation; returns the item it was on
+pub fn delete(conn: &Connection, annotation_id: i64) -> Result<i64, BrainboxError> {
+    let item_id = item_id(conn, annotation_id)?;
+    conn.execute("DELETE FROM annotations WHERE id = ?1", [annotation_id])?;
+    touch_item(conn, item_id, &chrono::Utc::now().to_rfc3339())?;
+    Ok(item_id)
+}
+
+fn to_sync(annotation: Annotation) -> SyncAnnotation {
+    SyncAnnotation {
+        uuid: annotation.uuid,
+        quote: annotation.quote,
+        note: annotation.note,
+        color: annotation.color,
+        anchor: annotation.anchor,
+        created_at: annotation.created_at,
+        updated_at: annotation.updated_at,
+    }
+}
+
+/// Every annotation in a vault, decrypted and keyed by item id (for sync and export)
+pub fn by_vault(conn: &Connection, vault_id: i64, key: &[u8; 32]) -> Result<HashMap<i64, Vec<SyncAnnotation>>, BrainboxError> {
+    create_table(conn)?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM annotations WHERE item_id IN (SELECT id FROM vault_items WHERE vault_id = ?1) ORDER BY created_at, id",
+        COLUMNS
+    ))?;
+    let rows = stmt.query_map([vault_id], from_row)?.collect::<rusqlite::Result<Vec<Row>>>()?;
+    let mut out: HashMap<i64, Vec<SyncAnnotation>> = HashMap::new();
+    for row in rows {
+        let item_id = row.item_id;
+        out.entry(item_id).or_default().push(to_sync(row.open(key)?));
+    }
+    Ok(out)
+}
+
+/// Replace an item's annotations with ones read from sync or an import. UUIDs and timestamps are kept; `fresh_uuids`
+/// gives each a new UUID instead, for copies of items that keep their original.
+pub fn set_for_item(conn: &Connection, item_id: i64, key: &[u8; 32], annotations: &[SyncAnnotation], fresh_uuids: bool) -> Result<(), BrainboxError> {
+    create_table(conn)?;
+    conn.execute("DELETE FROM annotations WHERE item_id = ?1", [item_id])?;
+    for annotation in annotations {
+        let input = AnnotationInput {
+            quote: annotation.quote.clone(),
+            note: annotation.note.clone(),
+            color: annotation.color.clone().filter(|c| crate::vault::is_valid_color(c)),
+            anchor: annotation.anchor.clone(),
+        };
+        let Ok(sealed) = seal_input(key, &input) else { continue };
+        let uuid = if fresh_uuids { Uuid::new_v4().to_string() } else { annotation.uuid.clone() };
+        conn.execute(
+            "INSERT OR REPLACE INTO annotations (uuid, item_id, quote, note, color, anchor, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![uuid, item_id, sealed.quote, sealed.note, sealed.color, sealed.anchor, annotation.created_at, annotation.updated_at],
+        )?;
+    }
+    Ok(())
+}
+
+/// Quotes and notes of an item's annotations, for the search index
+pub fn search_text(conn: &Connection, item_id: i64, key: &[u8; 32]) -> Result<Option<String>, BrainboxError> {
+    let parts: Vec<String> = list(conn, item_id, key)?
+        .into_iter()
+        .flat_map(|annotation| std::iter::once(annotation.quote).chain(annotation.note))
+        .collect();
+    Ok((!parts.is_empty()).then(|| parts.join("\n")))
+}
+
+fn reseal(conn: &Connection, filter: &str, id: i64, old_key: &[u8; 32], new_key: &[u8; 32]) -> Result<usize, BrainboxError> {
+    create_table(conn)?;
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM annotations WHERE {}", COLUMNS, filter))?;
+    let rows = stmt.query_map([id], from_row)?.collect::<rusqlite::Result<Vec<Row>>>()?;
+    let reseal = |sealed: &[u8]| -> Result<Vec<u8>, BrainboxError> { envelope::seal(new_key, &envelope::open(old_key, sealed)?) };
+    for row in &rows {
+        conn.execute(
+            "UPDATE annotations SET quote = ?1, note = ?2, anchor = ?3 WHERE id = ?4",
+            params![
+                reseal(&row.quote)?,
+                row.note.as_deref().map(reseal).transpose()?,
+                row.anchor.as_deref().map(reseal).transpose()?,
+                row.id
+            ],
+        )?;
+    }
+    Ok(rows.len())
+}
+
+/// Re-seal a vault's annotations from one key to another, inside the caller's transaction (envelope upgrade)
+pub fn reseal_vault(conn: &Connection, vault_id: i64, old_key: &[u8; 32], new_key: &[u8; 32]) -> Result<usize, BrainboxError> {
+    reseal(conn, "item_id IN (SELECT id FROM vault_items WHERE vault_id = ?1)", vault_id, old_key, new_key)
+}
+
+/// Re-seal one item's annotations for the vault it is moving to
+pub fn reseal_item(conn: &Connection, item_id: i64, old_key: &[u8; 32], new_key: &[u8; 32]) -> Result<usize, BrainboxError> {
+    reseal(conn, "item_id = ?1", item_id, old_key, new_key)
+}
@@ -0,0 +1,151 @@
+// autostart.rs - Launch brainbox on login
+// Windows uses the per-user Run key, macOS a LaunchAgent and Linux an XDG autostart entry; none need admin rights
+
+use rusqlite::Connection;
+use std::path::PathBuf;
+
+use crate::error::BrainboxError;
+use crate::settings;
+
+/// Argument added to the login entry so setup() can tell an autostart launch from a manual one
+pub const AUTOSTART_ARG: &str = "--autostart";
+const START_MINIMIZED_KEY: &str = "start_minimized";
+
+#[cfg(target_os = "windows")]
+const RUN_KEY: &str = "Software\\Microsoft\\Windows\\CurrentVersion\\Run";
+#[cfg(target_os = "macos")]
+const LAUNCH_AGENT_LABEL: &str = "com.brainbox.app";
+
+/// Path the OS should launch. AppImages run from a temporary mount, so use the AppImage file itself.
+pub fn launch_path() -> Result<PathBuf, BrainboxError> {
+    if let Some(appimage) = std::env::var_os("APPIMAGE") {
+        return Ok(PathBuf::from(appimage));
+    }
+    Ok(std::env::current_exe()?)
+}
+
+pub fn is_autostart_launch() -> bool {
+    std::env::args().any(|arg| arg == AUTOSTART_ARG)
+}
+
+#[cfg(target_os = "macos")]
+fn launch_agent_path() -> Result<PathBuf, BrainboxError> {
+    Ok(dirs::home_dir()
+        .ok_or("Failed to get home dir")?
+        .join("Library/LaunchAgents")
+        .join(format!("{}.plist", LAUNCH_AGENT_LABEL)))
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn autostart_desktop_path() -> Result<PathBuf, BrainboxError> {
+    Ok(dirs::config_dir().ok_or("Failed to get config dir")?.join("autostart").join("brainbox.desktop"))
+}
+
+#[cfg(target_os = "windows")]
+pub fn set_enabled(enabled: bool) -> Result<(), BrainboxError> {
+    use winreg::enums::*;
+    use winreg::RegKey;
+
+    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+    let (run, _) = hkcu.create_subkey(RUN_KEY)?;
+    if enabled {
+        let exe = launch_path()?;
+        run.set_value("brainbox", &format!("\"{}\" {}", exe.display(), AUTOSTART_ARG))?;
+    } else {
+        // Already absent is fine
+        let _ = run.delete_value("brainbox");
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+pub fn is_enabled() -> Result<bool, BrainboxError> {
+    use winreg::enums::*;
+    use winreg::RegKey;
+
+    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+    Ok(hkcu
+        .open_subkey(RUN_KEY)
+        .and_then(|run| run.get_value::<String, _>("brainbox"))
+        .is_ok())
+}
+
+#[cfg(target_os = "macos")]
+pub fn set_enabled(enabled: bool) -> Result<(), BrainboxError> {
+    let path = launch_agent_path()?;
+    if !enabled {
+        if path.exists() {
+            std::fs::remove_file(&path)?;
+        }
+        return Ok(());
+    }
+    let exe = launch_path()?;
+    let plist = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+  <key>Label</key>
+  <string>{}</string>
+  <key>ProgramArguments</key>
+  <array>
+    <string>{}</string>
+    <string>{}</string>
+  </array>
+  <key>RunAtLoad</key>
+  <true/>
+</dict>
+</plist>
+"#,
+        LAUNCH_AGENT_LABEL,
+        exe.display(),
+        AUTOSTART_ARG
+    );
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, plist)?;
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+pub fn is_enabled() -> Result<bool, BrainboxError> {
+    Ok(launch_agent_path()?.exists())
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+pub fn set_enabled(enabled: bool) -> Result<(), BrainboxError> {
+    let path = autostart_desktop_path()?;
+    if !enabled {
+        if path.exists() {
+            std::fs::remove_file(&path)?;
+        }
+        return Ok(());
+    }
+    let exe = launch_path()?;
+    let desktop = format!(
+        "[Desktop Entry]\nType=Application\nName=brainbox\nExec=\"{}\" {}\nTerminal=false\nX-GNOME-Autostart-enabled=true\n",
+        exe.display(),
+        AUTOSTART_ARG
+    );
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, desktop)?;
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+pub fn is_enabled() -> Result<bool, BrainboxError> {
+    Ok(autostart_desktop_path()?.exists())
+}
+
+/// Whether an autostart launch should stay hidden in the tray
+pub fn is_start_minimized_enabled(conn: &Connection) -> Result<bool, BrainboxError> {
+    Ok(settings::get_bool(conn, START_MINIMIZED_KEY)?)
+}
+
+pub fn set_start_minimized(conn: &Connection, enabled: bool) -> Result<(), BrainboxError> {
+    settings::set_bool(conn, START_MINIMIZED_KEY, enabled)?;
+    Ok(())
+}
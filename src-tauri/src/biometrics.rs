@@ -0,0 +1,317 @@
+// biometrics.rs - Touch ID / Windows Hello vault unlock for brainbox
+// A vault's key can be kept wrapped by a secret the OS only releases after a successful biometric check, so a vault
+// opens without typing its password. The database holds only the wrapped key; the secret never leaves the OS store.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use zeroize::Zeroizing;
+
+use crate::envelope;
+use crate::error::BrainboxError;
+use crate::keystore::{self, VaultKey};
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BiometricStatus {
+    /// Whether this device has usable Touch ID / Windows Hello
+    pub available: bool,
+    /// Whether the vault has biometric unlock set up
+    pub enabled: bool,
+}
+
+pub fn create_table(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS biometric_keys (
+            vault_id INTEGER PRIMARY KEY REFERENCES vaults(id) ON DELETE CASCADE,
+            wrapped_key BLOB NOT NULL,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+fn unavailable() -> BrainboxError {
+    BrainboxError::InvalidInput("Biometric unlock is not available on this device".to_string())
+}
+
+fn wrapped_key(conn: &Connection, vault_id: i64) -> Result<Option<Vec<u8>>, BrainboxError> {
+    create_table(conn)?;
+    Ok(conn
+        .query_row("SELECT wrapped_key FROM biometric_keys WHERE vault_id = ?1", [vault_id], |row| row.get(0))
+        .optional()?)
+}
+
+pub fn status(conn: &Connection, vault_id: i64) -> Result<BiometricStatus, BrainboxError> {
+    Ok(BiometricStatus {
+        available: platform::available(),
+        enabled: wrapped_key(conn, vault_id)?.is_some(),
+    })
+}
+
+/// Prompt for biometrics and store `key` wrapped by the resulting secret. The caller verifies the key first.
+pub fn enable(conn: &Connection, vault_id: i64, key: &[u8; 32]) -> Result<(), BrainboxError> {
+    if !platform::available() {
+        return Err(unavailable());
+    }
+    create_table(conn)?;
+    let secret = Zeroizing::new(platform::enroll(vault_id)?);
+    conn.execute(
+        "INSERT OR REPLACE INTO biometric_keys (vault_id, wrapped_key, created_at) VALUES (?1, ?2, ?3)",
+        params![vault_id, envelope::seal(&secret, key)?, chrono::Utc::now().to_rfc3339()],
+    )?;
+    Ok(())
+}
+
+/// Prompt for biometrics and return the vault key stored by `enable`
+pub fn unlock(conn: &Connection, vault_id: i64) -> Result<VaultKey, BrainboxError> {
+    let wrapped = wrapped_key(conn, vault_id)?
+        .ok_or_else(|| BrainboxError::NotFound("Biometric unlock is not enabled for this vault".to_string()))?;
+    let secret = Zeroizing::new(platform::authenticate(vault_id)?);
+    keystore::key_from_bytes(envelope::open(&secret, &wrapped)?)
+}
+
+/// Forget the wrapped key and the OS secret, e.g. after the vault password changes
+pub fn disable(conn: &Connection, vault_id: i64) -> Result<(), BrainboxError> {
+    create_table(conn)?;
+    conn.execute("DELETE FROM biometric_keys WHERE vault_id = ?1", [vault_id])?;
+    platform::remove(vault_id);
+    Ok(())
+}
+
+/// Windows Hello: a per-vault KeyCredential signs a fixed challenge and the signature is hashed into the secret.
+/// Hello keys are RSA with PKCS#1 v1.5 padding, so the signature is the same every time.
+#[cfg(windows)]
+mod platform {
+    use sha2::{Digest, Sha256};
+    use windows::core::{Array, HSTRING};
+    use windows::Security::Credentials::{KeyCredential, KeyCredentialCreationOption, KeyCredentialManager, KeyCredentialStatus};
+    use windows::Security::Cryptography::CryptographicBuffer;
+
+    use crate::error::BrainboxError;
+
+    const CHALLENGE: &[u8] = b"brainbox biometric unlock v1";
+
+    fn credential_name(vault_id: i64) -> HSTRING {
+        HSTRING::from(format!("brainbox-vault-{}", vault_id))
+    }
+
+    fn win_err(e: windows::core::Error) -> BrainboxError {
+        BrainboxError::Crypto(format!("Windows Hello error: {}", e))
+    }
+
+    fn cancelled() -> BrainboxError {
+        BrainboxError::Crypto("Windows Hello verification was cancelled or failed".to_string())
+    }
+
+    pub fn available() -> bool {
+        KeyCredentialManager::IsSupportedAsync().and_then(|op| op.get()).unwrap_or(false)
+    }
+
+    fn secret_from(credential: &KeyCredential) -> Result<[u8; 32], BrainboxError> {
+        let challenge = CryptographicBuffer::CreateFromByteArray(CHALLENGE).map_err(win_err)?;
+        let signed = credential.RequestSignAsync(&challenge).and_then(|op| op.get()).map_err(win_err)?;
+        if signed.Status().map_err(win_err)? != KeyCredentialStatus::Success {
+            return Err(cancelled());
+        }
+        let mut signature = Array::<u8>::new();
+        CryptographicBuffer::CopyToByteArray(&signed.Result().map_err(win_err)?, &mut signature).map_err(win_err)?;
+        Ok(Sha256::digest(&signature[..]).into())
+    }
+
+    pub fn enroll(vault_id: i64) -> Result<[u8; 32], BrainboxError> {
+        let result = KeyCredentialManager::RequestCreateAsync(&credential_name(vault_id), KeyCredentialCreationOption::ReplaceExisting)
+            .and_then(|op| op.get())
+            .map_err(win_err)?;
+        if result.Status().map_err(win_err)? != KeyCredentialStatus::Success {
+            return Err(cancelled());
+        }
+        secret_from(&result.Credential().map_err(win_err)?)
+    }
+
+    pub fn authenticate(vault_id: i64) -> Result<[u8; 32], BrainboxError> {
+        let result = KeyCredentialManager::OpenAsync(&credential_name(vault_id))
+            .and_then(|op| op.get())
+            .map_err(win_err)?;
+        match result.Status().map_err(win_err)? {
+            KeyCredentialStatus::Success => secret_from(&result.Credential().map_err(win_err)?),
+            KeyCredentialStatus::NotFound => Err(BrainboxError::NotFound("Windows Hello key for this vault is missing".to_string())),
+            _ => Err(cancelled()),
+        }
+    }
+
+    pub fn remove(vault_id: i64) {
+        let _ = KeyCredentialManager::DeleteAsync(&credential_name(vault_id)).and_then(|op| op.get());
+    }
+}
+
+/// macOS: a random secret in the data protection keychain, under an access control that requires Touch ID with the
+/// fingers enrolled when it was stored. The keychain runs the biometric check itself before releasing the secret.
+#[cfg(target_os = "macos")]
+mod platform {
+    use block2::RcBlock;
+    use core_foundation::base::{CFType, CFTypeRef, OSStatus, TCFType};
+    use core_foundation::boolean::CFBoolean;
+    use core_foundation::data::{CFData, CFDataRef};
+    use core_foundation::dictionary::CFDictionary;
+    use core_foundation::string::{CFString, CFStringRef};
+    use objc2::msg_send;
+    use objc2::rc::{autoreleasepool, Retained};
+    use objc2::runtime::{AnyClass, AnyObject, Bool};
+    use rand::{rngs::OsRng, RngCore};
+    use security_framework::access_control::{ProtectionMode, SecAccessControl};
+    use security_framework::passwords::delete_generic_password;
+    use security_framework::passwords_options::PasswordOptions;
+    use security_framework_sys::access_control::kSecAccessControlBiometryCurrentSet;
+    use security_framework_sys::base::{errSecAuthFailed, errSecItemNotFound, errSecSuccess};
+    use security_framework_sys::item::{
+        kSecAttrAccessControl, kSecReturnData, kSecUseAuthenticationContext, kSecUseDataProtectionKeychain, kSecValueData,
+    };
+    use security_framework_sys::keychain_item::{SecItemAdd, SecItemCopyMatching, SecItemDelete};
+    use std::ffi::CString;
+
+    use crate::error::BrainboxError;
+
+    #[link(name = "LocalAuthentication", kind = "framework")]
+    extern "C" {}
+
+    const KEYCHAIN_SERVICE: &str = "brainbox.biometric-unlock";
+    /// LAPolicyDeviceOwnerAuthenticationWithBiometrics
+    const POLICY_BIOMETRICS: isize = 1;
+    /// errSecUserCanceled
+    const USER_CANCELED: OSStatus = -128;
+
+    fn cancelled() -> BrainboxError {
+        BrainboxError::Crypto("Touch ID verification was cancelled or failed".to_string())
+    }
+
+    fn context() -> Option<Retained<AnyObject>> {
+        let class = AnyClass::get(c"LAContext")?;
+        Some(unsafe { msg_send![class, new] })
+    }
+
+    pub fn available() -> bool {
+        let Some(context) = context() else { return false };
+        let ok: Bool = unsafe {
+            msg_send![&context, canEvaluatePolicy: POLICY_BIOMETRICS, error: std::ptr::null_mut::<*mut AnyObject>()]
+        };
+        ok.as_bool()
+    }
+
+    /// Call `f` with `reason` as an NSString
+    fn with_reason(reason: &str, f: impl FnOnce(*mut AnyObject)) -> Result<(), BrainboxError> {
+        let string_class = AnyClass::get(c"NSString").ok_or_else(super::unavailable)?;
+        let reason = CString::new(reason).map_err(|_| cancelled())?;
+        autoreleasepool(|_| unsafe {
+            let reason: *mut AnyObject = msg_send![string_class, stringWithUTF8String: reason.as_ptr()];
+            f(reason);
+        });
+        Ok(())
+    }
+
+    /// Show the Touch ID prompt and wait for the user
+    fn evaluate(reason: &str) -> Result<(), BrainboxError> {
+        let context = context().ok_or_else(super::unavailable)?;
+        let (tx, rx) = std::sync::mpsc::channel();
+        let reply = RcBlock::new(move |success: Bool, _error: *mut AnyObject| {
+            let _ = tx.send(success.as_bool());
+        });
+        with_reason(reason, |reason| unsafe {
+            let _: () = msg_send![&context, evaluatePolicy: POLICY_BIOMETRICS, localizedReason: reason, reply: &*reply];
+        })?;
+        if rx.recv().unwrap_or(false) {
+            Ok(())
+        } else {
+            Err(cancelled())
+        }
+    }
+
+    fn keychain_err(e: security_framework::base::Error) -> BrainboxError {
+        BrainboxError::Crypto(format!("Keychain error: {}", e))
+    }
+
+    fn check(status: OSStatus) -> Result<(), BrainboxError> {
+        match status {
+            errSecSuccess => Ok(()),
+            errSecItemNotFound => Err(BrainboxError::NotFound("Touch ID secret for this vault is missing".to_string())),
+            USER_CANCELED | errSecAuthFailed => Err(cancelled()),
+            _ => Err(keychain_err(security_framework::base::Error::from_code(status))),
+        }
+    }
+
+    fn key(key: CFStringRef) -> CFString {
+        unsafe { CFString::wrap_under_get_rule(key) }
+    }
+
+    /// The vault's keychain item. Access control only applies in the data protection keychain.
+    fn query(vault_id: i64) -> PasswordOptions {
+        let mut options = PasswordOptions::new_generic_password(KEYCHAIN_SERVICE, &vault_id.to_string());
+        options.query.push((key(unsafe { kSecUseDataProtectionKeychain }), CFBoolean::true_value().into_CFType()));
+        options
+    }
+
+    pub fn enroll(vault_id: i64) -> Result<[u8; 32], BrainboxError> {
+        evaluate("enable Touch ID unlock for this vault")?;
+        remove(vault_id);
+        let mut secret = [0u8; 32];
+        OsRng.fill_bytes(&mut secret);
+        let access = SecAccessControl::create_with_protection(
+            Some(ProtectionMode::AccessibleWhenPasscodeSetThisDeviceOnly),
+            kSecAccessControlBiometryCurrentSet,
+        )
+        .map_err(keychain_err)?;
+        let mut options = query(vault_id);
+        options.query.push((key(unsafe { kSecAttrAccessControl }), access.into_CFType()));
+        options.query.push((key(unsafe { kSecValueData }), CFData::from_buffer(&secret).into_CFType()));
+        let params = CFDictionary::from_CFType_pairs(&options.query);
+        check(unsafe { SecItemAdd(params.as_concrete_TypeRef(), std::ptr::null_mut()) })?;
+        Ok(secret)
+    }
+
+    pub fn authenticate(vault_id: i64) -> Result<[u8; 32], BrainboxError> {
+        let context = context().ok_or_else(super::unavailable)?;
+        with_reason("unlock your vault", |reason| unsafe {
+            let _: () = msg_send![&context, setLocalizedReason: reason];
+        })?;
+        let mut options = query(vault_id);
+        options.query.push((key(unsafe { kSecReturnData }), CFBoolean::true_value().into_CFType()));
+        options.query.push((
+            key(unsafe { kSecUseAuthenticationContext }),
+            unsafe { CFType::wrap_under_get_rule(Retained::as_ptr(&context) as CFTypeRef) },
+        ));
+        let params = CFDictionary::from_CFType_pairs(&options.query);
+        // Blocks while the keychain shows the Touch ID prompt
+        let mut data: CFTypeRef = std::ptr::null();
+        check(unsafe { SecItemCopyMatching(params.as_concrete_TypeRef(), &mut data) })?;
+        if data.is_null() {
+            return Err(BrainboxError::Crypto("Invalid keychain secret".to_string()));
+        }
+        let data = unsafe { CFData::wrap_under_create_rule(data as CFDataRef) };
+        data.bytes().try_into().map_err(|_| BrainboxError::Crypto("Invalid keychain secret".to_string()))
+    }
+
+    pub fn remove(vault_id: i64) {
+        let params = CFDictionary::from_CFType_pairs(&query(vault_id).query);
+        unsafe { SecItemDelete(params.as_concrete_TypeRef()) };
+        // Secrets from before access control was used are plain items in the login keychain
+        let _ = delete_generic_password(KEYCHAIN_SERVICE, &vault_id.to_string());
+    }
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+mod platform {
+    use crate::error::BrainboxError;
+
+    pub fn available() -> bool {
+        false
+    }
+
+    pub fn enroll(_vault_id: i64) -> Result<[u8; 32], BrainboxError> {
+        Err(super::unavailable())
+    }
+
+    pub fn authenticate(_vault_id: i64) -> Result<[u8; 32], BrainboxError> {
+        Err(super::unavailable())
+    }
+
+    pub fn remove(_vault_id: i64) {}
+}
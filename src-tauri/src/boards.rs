@@ -0,0 +1,381 @@
+// boards.rs - Kanban boards for brainbox vaults
+// A board is a named set of ordered columns (e.g. Backlog / Reading / Done) over a vault's items. An item sits in
+// at most one column per board, at a position within that column. Boards and columns are soft-deleted and carry
+// UUIDs so sync can merge them like collections; moving an item bumps its updated_at so placements sync with it.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::error::BrainboxError;
+
+/// Columns a new board starts with when none are given
+pub const DEFAULT_COLUMNS: [&str; 3] = ["Backlog", "Reading", "Done"];
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Board {
+    pub id: i64,
+    pub vault_id: i64,
+    pub uuid: String,
+    pub name: String,
+    pub sort_order: Option<i64>,
+    pub created_at: String,
+    pub updated_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<String>,
+    pub columns: Vec<BoardColumn>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BoardColumn {
+    pub id: i64,
+    pub board_id: i64,
+    pub uuid: String,
+    pub name: String,
+    pub sort_order: i64,
+    pub created_at: String,
+    pub updated_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<String>,
+    /// Items in the column, in order; trashed items are left out
+    pub item_ids: Vec<i64>,
+}
+
+pub fn create_tables(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS boards (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            vault_id INTEGER NOT NULL REFERENCES vaults(id) ON DELETE CASCADE,
+            uuid TEXT NOT NULL UNIQUE,
+            name TEXT NOT NULL,
+            sort_order INTEGER,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            deleted_at TEXT
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS board_columns (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            board_id INTEGER NOT NULL REFERENCES boards(id) ON DELETE CASCADE,
+            uuid TEXT NOT NULL UNIQUE,
+            name TEXT NOT NULL,
+            sort_order INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            deleted_at TEXT
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS board_items (
+            board_id INTEGER NOT NULL REFERENCES boards(id) ON DELETE CASCADE,
+            column_id INTEGER NOT NULL REFERENCES board_columns(id) ON DELETE CASCADE,
+            item_id INTEGER NOT NULL REFERENCES vault_items(id) ON DELETE CASCADE,
+            position INTEGER NOT NULL,
+            PRIMARY KEY (board_id, item_id)
+        )",
+        [],
+    )?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_board_items_column ON board_items(column_id, position)", [])?;
+    Ok(())
+}
+
+const BOARD_COLUMNS: &str = "id, vault_id, uuid, name, sort_order, created_at, updated_at, deleted_at";
+const COLUMN_COLUMNS: &str = "id, board_id, uuid, name, sort_order, created_at, updated_at, deleted_at";
+
+fn board_from_row(row: &rusqlite::Row) -> rusqlite::Result<Board> {
+    Ok(Board {
+        id: row.get(0)?,
+        vault_id: row.get(1)?,
+        uuid: row.get(2)?,
+        name: row.get(3)?,
+        sort_order: row.get(4)?,
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+        deleted_at: row.get(7)?,
+        columns: Vec::new(),
+    })
+}
+
+fn column_from_row(row: &rusqlite::Row) -> rusqlite::Result<BoardColumn> {
+    Ok(BoardColumn {
+        id: row.get(0)?,
+        board_id: row.get(1)?,
+        uuid: row.get(2)?,
+        name: row.get(3)?,
+        sort_order: row.get(4)?,
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+        deleted_at: row.get(7)?,
+        item_ids: Vec::new(),
+    })
+}
+
+/// Sync compares vault timestamps, so board changes mark the vault as changed
+fn touch_vault(conn: &Connection, board_id: i64, now: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE vaults SET updated_at = ?1 WHERE id = (SELECT vault_id FROM boards WHERE id = ?2)",
+        params![now, board_id],
+    )?;
+    Ok(())
+}
+
+fn load_columns(conn: &Connection, board: &mut Board, include_deleted: bool) -> Result<(), BrainboxError> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM board_columns WHERE board_id = ?1 {} ORDER BY sort_order, id",
+        COLUMN_COLUMNS,
+        if include_deleted { "" } else { "AND deleted_at IS NULL" }
+    ))?;
+    board.columns = stmt.query_map([board.id], column_from_row)?.collect::<rusqlite::Result<_>>()?;
+    let mut items = conn.prepare(
+        "SELECT b.item_id FROM board_items b JOIN vault_items i ON i.id = b.item_id
+         WHERE b.column_id = ?1 AND i.deleted_at IS NULL ORDER BY b.position, b.item_id",
+    )?;
+    for column in &mut board.columns {
+        column.item_ids = items.query_map([column.id], |row| row.get(0))?.collect::<rusqlite::Result<_>>()?;
+    }
+    Ok(())
+}
+
+fn query_boards(conn: &Connection, vault_id: i64, include_deleted: bool) -> Result<Vec<Board>, BrainboxError> {
+    create_tables(conn)?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM boards WHERE vault_id = ?1 {} \
+         ORDER BY CASE WHEN sort_order IS NULL THEN 1 ELSE 0 END, sort_order, name COLLATE NOCASE",
+        BOARD_COLUMNS,
+        if include_deleted { "" } else { "AND deleted_at IS NULL" }
+    ))?;
+    let mut boards: Vec<Board> = stmt.query_map([vault_id], board_from_row)?.collect::<rusqlite::Result<_>>()?;
+    for board in &mut boards {
+        load_columns(conn, board, include_deleted)?;
+    }
+    Ok(boards)
+}
+
+/// A vault's boards with their columns and the items on them
+pub fn list(conn: &Connection, vault_id: i64) -> Result<Vec<Board>, BrainboxError> {
+    query_boards(conn, vault_id, false)
+}
+
+/// A vault's boards and columns including soft-deleted ones (for sync)
+pub fn list_all_for_sync(conn: &Connection, vault_id: i64) -> Result<Vec<Board>, BrainboxError> {
+    query_boards(conn, vault_id, true)
+}
+
+pub fn get(conn: &Connection, board_id: i64) -> Result<Board, BrainboxError> {
+    create_tables(conn)?;
+    let mut board = conn
+        .query_row(
+            &format!("SELECT {} FROM boards WHERE id = ?1 AND deleted_at IS NULL", BOARD_COLUMNS),
+            [board_id],
+            board_from_row,
+        )
+        .optional()?
+        .ok_or_else(|| BrainboxError::NotFound(format!("Board {} not found", board_id)))?;
+    load_columns(conn, &mut board, false)?;
+    Ok(board)
+}
+
+pub fn get_by_uuid(conn: &Connection, uuid: &str) -> Result<Option<Board>, BrainboxError> {
+    create_tables(conn)?;
+    Ok(conn
+        .query_row(&format!("SELECT {} FROM boards WHERE uuid = ?1", BOARD_COLUMNS), [uuid], board_from_row)
+        .optional()?)
+}
+
+fn get_column(conn: &Connection, column_id: i64) -> Result<BoardColumn, BrainboxError> {
+    create_tables(conn)?;
+    conn.query_row(
+        &format!("SELECT {} FROM board_columns WHERE id = ?1 AND deleted_at IS NULL", COLUMN_COLUMNS),
+        [column_id],
+        column_from_row,
+    )
+    .optional()?
+    .ok_or_else(|| BrainboxError::NotFound(format!("Board column {} not found", column_id)))
+}
+
+pub fn get_column_by_uuid(conn: &Connection, uuid: &str) -> Result<Option<BoardColumn>, BrainboxError> {
+    create_tables(conn)?;
+    Ok(conn
+        .query_row(&format!("SELECT {} FROM board_columns WHERE uuid = ?1", COLUMN_COLUMNS), [uuid], column_from_row)
+        .optional()?)
+}
+
+/// Insert a board row with a given UUID and timestamps; used for new boards and boards arriving from sync or import
+pub fn insert_board(conn: &Connection, vault_id: i64, uuid: &str, name: &str, sort_order: Option<i64>, created_at: &str, updated_at: &str) -> Result<i64, BrainboxError> {
+    create_tables(conn)?;
+    conn.execute(
+        "INSERT INTO boards (vault_id, uuid, name, sort_order, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![vault_id, uuid, name, sort_order, created_at, updated_at],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Insert a column row with a given UUID and timestamps
+pub fn insert_column(conn: &Connection, board_id: i64, uuid: &str, name: &str, sort_order: i64, created_at: &str, updated_at: &str) -> Result<i64, BrainboxError> {
+    create_tables(conn)?;
+    conn.execute(
+        "INSERT INTO board_columns (board_id, uuid, name, sort_order, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![board_id, uuid, name, sort_order, created_at, updated_at],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Create a board with the given columns, or the default ones when empty
+pub fn create(conn: &Connection, vault_id: i64, name: &str, columns: &[String]) -> Result<Board, BrainboxError> {
+    let now = chrono::Utc::now().to_rfc3339();
+    let board_id = insert_board(conn, vault_id, &Uuid::new_v4().to_string(), name, None, &now, &now)?;
+    let columns: Vec<String> = if columns.is_empty() {
+        DEFAULT_COLUMNS.iter().map(|c| c.to_string()).collect()
+    } else {
+        columns.to_vec()
+    };
+    for (i, column) in columns.iter().enumerate() {
+        insert_column(conn, board_id, &Uuid::new_v4().to_string(), column, i as i64, &now, &now)?;
+    }
+    touch_vault(conn, board_id, &now)?;
+    get(conn, board_id)
+}
+
+pub fn rename(conn: &Connection, board_id: i64, name: &str) -> Result<(), BrainboxError> {
+    get(conn, board_id)?;
+    let now = chrono::Utc::now().to_rfc3339();
+    conn.execute("UPDATE boards SET name = ?1, updated_at = ?2 WHERE id = ?3", params![name, now, board_id])?;
+    touch_vault(conn, board_id, &now)?;
+    Ok(())
+}
+
+/// Mark items changed so their placement change syncs
+fn touch_items(conn: &Connection, where_clause: &str, id: i64, now: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        &format!("UPDATE vault_items SET updated_at = ?1 WHERE id IN (SELECT item_id FROM board_items WHERE {} = ?2)", where_clause),
+        params![now, id],
+    )?;
+    Ok(())
+}
+
+/// Soft-delete a board and its columns; items on it are taken off
+pub fn delete(conn: &Connection, board_id: i64) -> Result<(), BrainboxError> {
+    get(conn, board_id)?;
+    let now = chrono::Utc::now().to_rfc3339();
+    touch_items(conn, "board_id", board_id, &now)?;
+    conn.execute("DELETE FROM board_items WHERE board_id = ?1", [board_id])?;
+    conn.execute(
+        "UPDATE board_columns SET deleted_at = ?1, updated_at = ?1 WHERE board_id = ?2 AND deleted_at IS NULL",
+        params![now, board_id],
+    )?;
+    conn.execute("UPDATE boards SET deleted_at = ?1, updated_at = ?1 WHERE id = ?2", params![now, board_id])?;
+    touch_vault(conn, board_id, &now)?;
+    Ok(())
+}
+
+/// Add a column at the end of a board
+pub fn add_column(conn: &Connection, board_id: i64, name: &str) -> Result<Board, BrainboxError> {
+    let board = get(conn, board_id)?;
+    let now = chrono::Utc::now().to_rfc3339();
+    let next = board.columns.iter().map(|c| c.sort_order + 1).max().unwrap_or(0);
+    insert_column(conn, board_id, &Uuid::new_v4().to_string(), name, next, &now, &now)?;
+    touch_vault(conn, board_id, &now)?;
+    get(conn, board_id)
+}
+
+pub fn rename_column(conn: &Connection, column_id: i64, name: &str) -> Result<(), BrainboxError> {
+    let column = get_column(conn, column_id)?;
+    let now = chrono::Utc::now().to_rfc3339();
+    conn.execute("UPDATE board_columns SET name = ?1, updated_at = ?2 WHERE id = ?3", params![name, now, column_id])?;
+    touch_vault(conn, column.board_id, &now)?;
+    Ok(())
+}
+
+/// Soft-delete a column; its items are taken off the board
+pub fn delete_column(conn: &Connection, column_id: i64) -> Result<(), BrainboxError> {
+    let column = get_column(conn, column_id)?;
+    let now = chrono::Utc::now().to_rfc3339();
+    touch_items(conn, "column_id", column_id, &now)?;
+    conn.execute("DELETE FROM board_items WHERE column_id = ?1", [column_id])?;
+    conn.execute("UPDATE board_columns SET deleted_at = ?1, updated_at = ?1 WHERE id = ?2", params![now, column_id])?;
+    touch_vault(conn, column.board_id, &now)?;
+    Ok(())
+}
+
+/// Reorder a board's columns; `column_ids` lists them in their new order
+pub fn reorder_columns(conn: &Connection, board_id: i64, column_ids: &[i64]) -> Result<Board, BrainboxError> {
+    let board = get(conn, board_id)?;
+    if column_ids.iter().any(|id| !board.columns.iter().any(|c| c.id == *id)) {
+        return Err(BrainboxError::InvalidInput("Column does not belong to this board".to_string()));
+    }
+    let now = chrono::Utc::now().to_rfc3339();
+    for (i, id) in column_ids.iter().enumerate() {
+        conn.execute(
+            "UPDATE board_columns SET sort_order = ?1, updated_at = ?2 WHERE id = ?3",
+            params![i as i64, now, id],
+        )?;
+    }
+    touch_vault(conn, board_id, &now)?;
+    get(conn, board_id)
+}
+
+/// Place an item in a column at `position` (the end when None), renumbering the column.
+/// With no column the item is taken off `board_id`.
+pub fn move_item(conn: &Connection, item_id: i64, board_id: i64, column_id: Option<i64>, position: Option<usize>) -> Result<(), BrainboxError> {
+    get(conn, board_id)?;
+    if let Some(column_id) = column_id {
+        if get_column(conn, column_id)?.board_id != board_id {
+            return Err(BrainboxError::InvalidInput("Column does not belong to this board".to_string()));
+        }
+    }
+    let now = chrono::Utc::now().to_rfc3339();
+    conn.execute("DELETE FROM board_items WHERE board_id = ?1 AND item_id = ?2", params![board_id, item_id])?;
+    if let Some(column_id) = column_id {
+        let mut stmt = conn.prepare("SELECT item_id FROM board_items WHERE column_id = ?1 ORDER BY position, item_id")?;
+        let mut order: Vec<i64> = stmt.query_map([column_id], |row| row.get(0))?.collect::<rusqlite::Result<_>>()?;
+        order.insert(position.unwrap_or(order.len()).min(order.len()), item_id);
+        conn.execute(
+            "INSERT INTO board_items (board_id, column_id, item_id, position) VALUES (?1, ?2, ?3, 0)",
+            params![board_id, column_id, item_id],
+        )?;
+        for (i, id) in order.iter().enumerate() {
+            conn.execute(
+                "UPDATE board_items SET position = ?1 WHERE column_id = ?2 AND item_id = ?3",
+                params![i as i64, column_id, id],
+            )?;
+        }
+    }
+    conn.execute("UPDATE vault_items SET updated_at = ?1 WHERE id = ?2", params![now, item_id])?;
+    touch_vault(conn, board_id, &now)?;
+    Ok(())
+}
+
+/// Column UUID and position of every placed item in a vault, keyed by item id (for sync and export)
+pub fn placements_by_vault(conn: &Connection, vault_id: i64) -> Result<HashMap<i64, Vec<(String, i64)>>, BrainboxError> {
+    create_tables(conn)?;
+    let mut stmt = conn.prepare(
+        "SELECT b.item_id, c.uuid, b.position FROM board_items b
+         JOIN board_columns c ON c.id = b.column_id JOIN boards d ON d.id = b.board_id
+         WHERE d.vault_id = ?1 ORDER BY d.id",
+    )?;
+    let mut out: HashMap<i64, Vec<(String, i64)>> = HashMap::new();
+    let rows = stmt.query_map([vault_id], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, i64>(2)?)))?;
+    for row in rows {
+        let (item_id, column_uuid, position) = row?;
+        out.entry(item_id).or_default().push((column_uuid, position));
+    }
+    Ok(out)
+}
+
+/// Replace an item's placements with ones read from sync or an import, given as (board id, column id, position).
+/// Positions are kept as they are; ties sort by item id.
+pub fn set_placements(conn: &Connection, item_id: i64, placements: &[(i64, i64, i64)]) -> Result<(), BrainboxError> {
+    create_tables(conn)?;
+    conn.execute("DELETE FROM board_items WHERE item_id = ?1", [item_id])?;
+    for (board_id, column_id, position) in placements {
+        conn.execute(
+            "INSERT OR REPLACE INTO board_items (board_id, column_id, item_id, position) VALUES (?1, ?2, ?3, ?4)",
+            params![board_id, column_id, item_id, position],
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,652 @@
+// calendar.rs - Calendar (.ics) import for brainbox
+// Each event in an iCalendar file or feed becomes a note with its time, place, attendees and description.
+// Importing the same calendar again updates those notes instead of adding copies. Optionally a meeting-notes item
+// is made from a template shortly before each event; the reminder scheduler does that. Event details are sealed
+// with the vault key so the scheduler can fill in the template without decrypting the event note.
+// Times with a TZID are read as local time, since no time zone database ships with the app. Recurring events
+// import their first occurrence only.
+
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::envelope;
+use crate::error::BrainboxError;
+use crate::templates::{self, Template};
+use crate::vault::{self, VaultItem};
+
+/// Largest calendar file or feed read
+pub const MAX_ICS_BYTES: u64 = 20 * 1024 * 1024;
+/// Minutes before an event its meeting notes are made, when the caller doesn't say
+pub const DEFAULT_NOTES_LEAD_MINUTES: i64 = 10;
+pub const MAX_NOTES_LEAD_MINUTES: i64 = 24 * 60;
+
+pub const MEETING_NOTES_EVENT: &str = "meeting-notes-created";
+
+/// One VEVENT
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct IcsEvent {
+    pub uid: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recurrence_id: Option<String>,
+    pub summary: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub organizer: Option<String>,
+    #[serde(default)]
+    pub attendees: Vec<String>,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    #[serde(default)]
+    pub all_day: bool,
+    #[serde(default)]
+    pub cancelled: bool,
+}
+
+impl IcsEvent {
+    /// Identifies the event within its vault; a moved occurrence of a recurring event is its own event
+    fn key(&self) -> String {
+        match &self.recurrence_id {
+            Some(recurrence_id) => format!("{}#{}", self.uid, recurrence_id),
+            None => self.uid.clone(),
+        }
+    }
+
+    fn title(&self) -> String {
+        if self.summary.trim().is_empty() { "Untitled event".to_string() } else { self.summary.trim().to_string() }
+    }
+
+    /// When the event happens, in local time
+    fn when(&self) -> String {
+        let start = self.starts_at.with_timezone(&Local);
+        let end = self.ends_at.with_timezone(&Local);
+        if self.all_day {
+            let last_day = (end - Duration::days(1)).date_naive();
+            if last_day > start.date_naive() {
+                format!("{} – {} (all day)", start.format("%Y-%m-%d"), last_day.format("%Y-%m-%d"))
+            } else {
+                format!("{} (all day)", start.format("%Y-%m-%d"))
+            }
+        } else if end == start {
+            start.format("%Y-%m-%d %H:%M").to_string()
+        } else if start.date_naive() == end.date_naive() {
+            format!("{} – {}", start.format("%Y-%m-%d %H:%M"), end.format("%H:%M"))
+        } else {
+            format!("{} – {}", start.format("%Y-%m-%d %H:%M"), end.format("%Y-%m-%d %H:%M"))
+        }
+    }
+
+    /// The event as a note
+    fn to_markdown(&self) -> String {
+        let mut out = format!("# {}\n\n**When:** {}\n", self.title(), self.when());
+        if self.cancelled {
+            out.push_str("**Status:** Cancelled\n");
+        }
+        if let Some(location) = &self.location {
+            out.push_str(&format!("**Where:** {}\n", location));
+        }
+        if let Some(organizer) = &self.organizer {
+            out.push_str(&format!("**Organizer:** {}\n", organizer));
+        }
+        if !self.attendees.is_empty() {
+            out.push_str(&format!("**Attendees:** {}\n", self.attendees.join(", ")));
+        }
+        if let Some(url) = &self.url {
+            out.push_str(&format!("**Link:** {}\n", url));
+        }
+        if let Some(description) = self.description.as_deref().map(str::trim).filter(|d| !d.is_empty()) {
+            out.push_str(&format!("\n{}\n", description));
+        }
+        out
+    }
+}
+
+// --- Parsing ---
+
+/// A content line: name, parameters and value
+struct Property {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, v)| v.as_str())
+    }
+}
+
+/// Join folded lines (continuations start with a space or tab)
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        match line.strip_prefix(' ').or_else(|| line.strip_prefix('\t')) {
+            Some(rest) if !lines.is_empty() => lines.last_mut().unwrap().push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+fn parse_line(line: &str) -> Option<Property> {
+    // The value starts at the first colon outside a quoted parameter
+    let mut quoted = false;
+    let colon = line.char_indices().find(|&(_, c)| {
+        if c == '"' {
+            quoted = !quoted;
+        }
+        c == ':' && !quoted
+    })?.0;
+    let mut parts = line[..colon].split(';');
+    let name = parts.next()?.trim().to_ascii_uppercase();
+    let params = parts
+        .filter_map(|p| p.split_once('='))
+        .map(|(k, v)| (k.trim().to_string(), v.trim().trim_matches('"').to_string()))
+        .collect();
+    Some(Property { name, params, value: line[colon + 1..].to_string() })
+}
+
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out
+}
+
+fn is_utc_zone(tzid: &str) -> bool {
+    matches!(tzid.to_ascii_uppercase().as_str(), "UTC" | "GMT" | "Z" | "ETC/UTC" | "ETC/GMT" | "UNIVERSAL")
+}
+
+fn local_to_utc(at: NaiveDateTime) -> Option<DateTime<Utc>> {
+    Local.from_local_datetime(&at).earliest().map(|at| at.with_timezone(&Utc))
+}
+
+/// A DATE or DATE-TIME value; true when it is a date (an all-day event)
+fn parse_time(property: &Property) -> Option<(DateTime<Utc>, bool)> {
+    let value = property.value.trim();
+    if value.len() == 8 || property.param("VALUE").is_some_and(|v| v.eq_ignore_ascii_case("DATE")) {
+        let date = NaiveDate::parse_from_str(value.get(..8)?, "%Y%m%d").ok()?;
+        return Some((local_to_utc(date.and_hms_opt(0, 0, 0)?)?, true));
+    }
+    let at = NaiveDateTime::parse_from_str(value.trim_end_matches(['Z', 'z']), "%Y%m%dT%H%M%S").ok()?;
+    let utc = value.ends_with(['Z', 'z']) || property.param("TZID").is_some_and(is_utc_zone);
+    let at = if utc { Utc.from_utc_datetime(&at) } else { local_to_utc(at)? };
+    Some((at, false))
+}
+
+/// An ISO 8601 duration such as PT1H30M or P1D
+fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let mut rest = value.strip_prefix('P')?;
+    let mut total = Duration::zero();
+    let mut in_time = false;
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('T') {
+            in_time = true;
+            rest = after;
+            continue;
+        }
+        let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+        let amount: i64 = rest[..digits].parse().ok()?;
+        total += match (rest.as_bytes()[digits], in_time) {
+            (b'W', _) => Duration::weeks(amount),
+            (b'D', _) => Duration::days(amount),
+            (b'H', true) => Duration::hours(amount),
+            (b'M', true) => Duration::minutes(amount),
+            (b'S', true) => Duration::seconds(amount),
+            _ => return None,
+        };
+        rest = &rest[digits + 1..];
+    }
+    Some(if negative { -total } else { total })
+}
+
+/// Attendee or organizer: their name when given, else the address
+fn person(property: &Property) -> Option<String> {
+    let name = property.param("CN").map(str::trim).filter(|n| !n.is_empty());
+    let address = property.value.trim();
+    let address = match address.get(..7) {
+        Some(scheme) if scheme.eq_ignore_ascii_case("mailto:") => &address[7..],
+        _ => address,
+    };
+    name.or(Some(address).filter(|a| !a.is_empty())).map(str::to_string)
+}
+
+fn fallback_uid(summary: &str, starts_at: &DateTime<Utc>) -> String {
+    let digest = Sha256::digest(format!("{}\n{}", summary, starts_at.to_rfc3339()).as_bytes());
+    digest.iter().take(16).map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Events in an iCalendar text. Events without a start are skipped.
+pub fn parse(text: &str) -> Vec<IcsEvent> {
+    let mut events = Vec::new();
+    // Components inside the current event (alarms) whose properties aren't the event's
+    let mut nested = 0usize;
+    let mut current: Option<Vec<Property>> = None;
+    for line in unfold(text) {
+        let Some(property) = parse_line(&line) else { continue };
+        let component = property.value.trim().to_ascii_uppercase();
+        match (property.name.as_str(), current.as_mut()) {
+            ("BEGIN", None) if component == "VEVENT" => current = Some(Vec::new()),
+            ("BEGIN", Some(_)) => nested += 1,
+            ("END", Some(_)) if nested > 0 => nested -= 1,
+            ("END", Some(_)) if component == "VEVENT" => {
+                if let Some(event) = current.take().and_then(build_event) {
+                    events.push(event);
+                }
+            }
+            (_, Some(properties)) if nested == 0 => properties.push(property),
+            _ => {}
+        }
+    }
+    events
+}
+
+fn build_event(properties: Vec<Property>) -> Option<IcsEvent> {
+    let mut event = IcsEvent::default();
+    let mut start = None;
+    let mut end = None;
+    let mut duration = None;
+    for property in &properties {
+        match property.name.as_str() {
+            "UID" => event.uid = property.value.trim().to_string(),
+            "RECURRENCE-ID" => event.recurrence_id = Some(property.value.trim().to_string()),
+            "SUMMARY" => event.summary = unescape(&property.value),
+            "DESCRIPTION" => event.description = Some(unescape(&property.value)),
+            "LOCATION" => event.location = Some(unescape(&property.value)).filter(|l| !l.trim().is_empty()),
+            "URL" => event.url = Some(property.value.trim().to_string()).filter(|u| !u.is_empty()),
+            "ORGANIZER" => event.organizer = person(property),
+            "ATTENDEE" => event.attendees.extend(person(property)),
+            "STATUS" => event.cancelled = property.value.trim().eq_ignore_ascii_case("CANCELLED"),
+            "DTSTART" => start = parse_time(property),
+            "DTEND" => end = parse_time(property).map(|(at, _)| at),
+            "DURATION" => duration = parse_duration(&property.value),
+            _ => {}
+        }
+    }
+    let (starts_at, all_day) = start?;
+    event.starts_at = starts_at;
+    event.all_day = all_day;
+    // Without an end, an event lasts its duration, a whole day, or no time at all
+    event.ends_at = end
+        .or_else(|| duration.map(|d| starts_at + d))
+        .unwrap_or(if all_day { starts_at + Duration::days(1) } else { starts_at })
+        .max(starts_at);
+    if event.uid.is_empty() {
+        event.uid = fallback_uid(&event.summary, &starts_at);
+    }
+    Some(event)
+}
+
+// --- Reading ---
+
+/// The text of a calendar file, or of a feed at an http(s) or webcal URL
+pub fn read_source(source: &str) -> Result<String, BrainboxError> {
+    let source = source.trim();
+    let lower = source.to_ascii_lowercase();
+    let bytes = if lower.starts_with("http://") || lower.starts_with("https://") || lower.starts_with("webcal://") {
+        let url = match lower.strip_prefix("webcal://") {
+            Some(_) => format!("https://{}", &source["webcal://".len()..]),
+            None => source.to_string(),
+        };
+        let resp = crate::http::send(crate::http::client().get(&url))?;
+        if !resp.status().is_success() {
+            return Err(BrainboxError::Network(format!("Request failed with status {}", resp.status())));
+        }
+        if resp.content_length().is_some_and(|len| len > MAX_ICS_BYTES) {
+            return Err(BrainboxError::InvalidInput("Calendar is too large to import".to_string()));
+        }
+        resp.bytes()?.to_vec()
+    } else {
+        if std::fs::metadata(source)?.len() > MAX_ICS_BYTES {
+            return Err(BrainboxError::InvalidInput("Calendar is too large to import".to_string()));
+        }
+        std::fs::read(source)?
+    };
+    if bytes.len() as u64 > MAX_ICS_BYTES {
+        return Err(BrainboxError::InvalidInput("Calendar is too large to import".to_string()));
+    }
+    let text = String::from_utf8_lossy(&bytes).to_string();
+    if !text.contains("BEGIN:VCALENDAR") {
+        return Err(BrainboxError::InvalidInput("Not an iCalendar (.ics) file".to_string()));
+    }
+    Ok(text)
+}
+
+// --- Storage ---
+
+pub fn create_table(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS calendar_events (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            vault_id INTEGER NOT NULL,
+            event_key TEXT NOT NULL,
+            item_id INTEGER NOT NULL REFERENCES vault_items(id) ON DELETE CASCADE,
+            source TEXT NOT NULL,
+            starts_at TEXT NOT NULL,
+            ends_at TEXT NOT NULL,
+            details BLOB NOT NULL,
+            fingerprint TEXT NOT NULL,
+            notes_template_id INTEGER,
+            notes_lead_minutes INTEGER,
+            notes_at TEXT,
+            notes_item_id INTEGER REFERENCES vault_items(id) ON DELETE SET NULL,
+            imported_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            UNIQUE(vault_id, event_key)
+        )",
+        [],
+    )?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_calendar_events_notes_at ON calendar_events(notes_at)", [])?;
+    Ok(())
+}
+
+/// Make meeting notes from a template this many minutes before each event
+#[derive(Debug, Clone)]
+pub struct MeetingNotesOptions {
+    pub template_id: i64,
+    pub lead_minutes: i64,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct IcsImportReport {
+    pub events: usize,
+    pub created: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    /// Events whose note was deleted here, and new events that are already cancelled
+    pub skipped: usize,
+    /// Events that will get meeting notes
+    pub notes_scheduled: usize,
+}
+
+/// An event already imported into the vault
+struct StoredEvent {
+    id: i64,
+    item_id: i64,
+    fingerprint: String,
+    /// Its note is in the trash
+    deleted: bool,
+}
+
+fn fingerprint(title: &str, content: &str) -> String {
+    Sha256::digest(format!("{}\n{}", title, content).as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Import `text`, read from `source`, into a vault. Returns the count of each outcome.
+pub fn import(
+    conn: &Connection,
+    vault_id: i64,
+    key: &[u8; 32],
+    source: &str,
+    text: &str,
+    notes: Option<&MeetingNotesOptions>,
+) -> Result<IcsImportReport, BrainboxError> {
+    VaultItem::create_table(conn)?;
+    create_table(conn)?;
+    let events = parse(text);
+    let mut report = IcsImportReport { events: events.len(), ..Default::default() };
+    let mut to_index = Vec::new();
+
+    conn.execute("BEGIN IMMEDIATE", [])?;
+    let imported = (|| -> Result<(), BrainboxError> {
+        let now = Utc::now();
+        for event in &events {
+            let title = event.title();
+            let content = event.to_markdown();
+            let print = fingerprint(&title, &content);
+            let details = envelope::seal(key, &serde_json::to_vec(event)?)?;
+            let existing = conn
+                .query_row(
+                    "SELECT e.id, e.item_id, e.fingerprint, i.deleted_at IS NOT NULL FROM calendar_events e
+                     JOIN vault_items i ON i.id = e.item_id WHERE e.vault_id = ?1 AND e.event_key = ?2",
+                    params![vault_id, event.key()],
+                    |row| Ok(StoredEvent { id: row.get(0)?, item_id: row.get(1)?, fingerprint: row.get(2)?, deleted: row.get(3)? }),
+                )
+                .optional()?;
+            let event_id = match existing {
+                // Deleted here on purpose; importing again doesn't bring it back
+                Some(stored) if stored.deleted => {
+                    report.skipped += 1;
+                    continue;
+                }
+                Some(StoredEvent { id: event_id, item_id, fingerprint: old_print, .. }) => {
+                    if old_print == print {
+                        report.unchanged += 1;
+                    } else {
+                        VaultItem::update_title(conn, item_id, &title)?;
+                        VaultItem::update_content(conn, item_id, &content, key)?;
+                        to_index.push(item_id);
+                        report.updated += 1;
+                    }
+                    conn.execute(
+                        "UPDATE calendar_events SET source = ?1, starts_at = ?2, ends_at = ?3, details = ?4, fingerprint = ?5, updated_at = ?6 WHERE id = ?7",
+                        params![source, event.starts_at.to_rfc3339(), event.ends_at.to_rfc3339(), details, print, now.to_rfc3339(), event_id],
+                    )?;
+                    event_id
+                }
+                None if event.cancelled => {
+                    report.skipped += 1;
+                    continue;
+                }
+                None => {
+                    let item = VaultItem::insert(conn, vault_id, &title, &content, "note", key)?;
+                    conn.execute(
+                        "INSERT INTO calendar_events (vault_id, event_key, item_id, source, starts_at, ends_at, details, fingerprint, imported_at, updated_at)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?9)",
+                        params![vault_id, event.key(), item.id, source, event.starts_at.to_rfc3339(), event.ends_at.to_rfc3339(), details, print, now.to_rfc3339()],
+                    )?;
+                    to_index.push(item.id);
+                    report.created += 1;
+                    conn.last_insert_rowid()
+                }
+            };
+
+            if event.cancelled {
+                conn.execute("UPDATE calendar_events SET notes_at = NULL WHERE id = ?1 AND notes_item_id IS NULL", [event_id])?;
+            } else if let Some(notes) = notes.filter(|_| event.ends_at > now) {
+                let notes_at = event.starts_at - Duration::minutes(notes.lead_minutes);
+                let scheduled = conn.execute(
+                    "UPDATE calendar_events SET notes_template_id = ?1, notes_lead_minutes = ?2, notes_at = ?3 WHERE id = ?4 AND notes_item_id IS NULL",
+                    params![notes.template_id, notes.lead_minutes, notes_at.to_rfc3339(), event_id],
+                )?;
+                report.notes_scheduled += scheduled;
+            } else {
+                // A rescheduled event keeps its meeting notes the same time ahead of it
+                let lead: Option<i64> = conn.query_row(
+                    "SELECT notes_lead_minutes FROM calendar_events WHERE id = ?1 AND notes_template_id IS NOT NULL AND notes_item_id IS NULL",
+                    [event_id],
+                    |row| row.get(0),
+                ).optional()?.flatten();
+                if let Some(lead) = lead {
+                    let notes_at = event.starts_at - Duration::minutes(lead);
+                    conn.execute("UPDATE calendar_events SET notes_at = ?1 WHERE id = ?2", params![notes_at.to_rfc3339(), event_id])?;
+                }
+            }
+        }
+        Ok(())
+    })();
+    match imported {
+        Ok(()) => conn.execute("COMMIT", [])?,
+        Err(e) => {
+            let _ = conn.execute("ROLLBACK", []);
+            return Err(e);
+        }
+    };
+
+    for item_id in to_index {
+        index_item(conn, item_id, key)?;
+    }
+    Ok(report)
+}
+
+fn index_item(conn: &Connection, item_id: i64, key: &[u8; 32]) -> Result<(), BrainboxError> {
+    let item = VaultItem::get_by_id(conn, item_id)?;
+    let content = String::from_utf8(envelope::open(key, &item.content)?)
+        .map_err(|_| BrainboxError::Crypto("Invalid UTF-8".to_string()))?;
+    let item_type = item.item_type.clone().unwrap_or_else(|| vault::infer_item_type(&content).to_string());
+    let _ = crate::search::index_document(crate::search::DocumentInput {
+        id: item.id.to_string(),
+        title: item.title,
+        content,
+        item_type,
+        created_at: item.created_at,
+        updated_at: item.updated_at,
+        tags: crate::tags::get(conn, item_id)?,
+        vault_id: Some(item.vault_id),
+        ..Default::default()
+    });
+    Ok(())
+}
+
+// --- Meeting notes ---
+
+/// Template placeholders filled from the event, on top of the built-in date and time ones
+fn template_variables(event: &IcsEvent, event_uuid: Option<&str>) -> HashMap<String, String> {
+    let mut variables = HashMap::new();
+    let start = event.starts_at.with_timezone(&Local);
+    let end = event.ends_at.with_timezone(&Local);
+    variables.insert("title".to_string(), event.title());
+    variables.insert("when".to_string(), event.when());
+    variables.insert("start".to_string(), start.format("%Y-%m-%d %H:%M").to_string());
+    variables.insert("end".to_string(), end.format("%Y-%m-%d %H:%M").to_string());
+    variables.insert("location".to_string(), event.location.clone().unwrap_or_default());
+    variables.insert("organizer".to_string(), event.organizer.clone().unwrap_or_default());
+    variables.insert("attendees".to_string(), event.attendees.join(", "));
+    variables.insert(
+        "attendee_list".to_string(),
+        event.attendees.iter().map(|a| format!("- {}", a)).collect::<Vec<_>>().join("\n"),
+    );
+    variables.insert("description".to_string(), event.description.clone().unwrap_or_default().trim().to_string());
+    variables.insert("url".to_string(), event.url.clone().unwrap_or_default());
+    if let Some(uuid) = event_uuid {
+        variables.insert("event".to_string(), format!("[{}](brainbox://item/{})", event.title(), uuid));
+    }
+    variables
+}
+
+/// An event whose meeting notes are due
+struct DueNotes {
+    event_id: i64,
+    vault_id: i64,
+    event_item_id: i64,
+    template_id: Option<i64>,
+    details: Vec<u8>,
+    event_uuid: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[allow(non_snake_case)]
+struct MeetingNotesEvent {
+    itemId: i64,
+    vaultId: i64,
+    eventItemId: i64,
+    title: String,
+}
+
+/// Make the meeting notes that are due. Events in locked vaults wait until the vault is unlocked, as long as
+/// they haven't ended.
+pub fn create_due_meeting_notes(app: &AppHandle) -> Result<(), String> {
+    let conn = crate::db::open().map_err(|e| e.to_string())?;
+    create_table(&conn).map_err(|e| e.to_string())?;
+    let now = Utc::now().to_rfc3339();
+    let due: Vec<DueNotes> = {
+        let mut stmt = conn
+            .prepare(
+                "SELECT e.id, e.vault_id, e.item_id, e.notes_template_id, e.details, i.uuid FROM calendar_events e
+                 JOIN vault_items i ON i.id = e.item_id
+                 WHERE e.notes_at IS NOT NULL AND e.notes_at <= ?1 AND e.ends_at > ?1 AND e.notes_item_id IS NULL AND i.deleted_at IS NULL
+                 ORDER BY e.notes_at",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([&now], |row| {
+                Ok(DueNotes {
+                    event_id: row.get(0)?,
+                    vault_id: row.get(1)?,
+                    event_item_id: row.get(2)?,
+                    template_id: row.get(3)?,
+                    details: row.get(4)?,
+                    event_uuid: row.get(5)?,
+                })
+            })
+            .map_err(|e| e.to_string())?;
+        rows.collect::<rusqlite::Result<_>>().map_err(|e| e.to_string())?
+    };
+    for DueNotes { event_id, vault_id, event_item_id, template_id, details, event_uuid } in due {
+        let Ok(kek) = crate::resolve_key(&conn, vault_id, None) else { continue };
+        let created = (|| -> Result<Option<VaultItem>, BrainboxError> {
+            let key = envelope::content_key(&conn, vault_id, &kek)?;
+            let Some(template) = template_id.map(|id| Template::get_by_id(&conn, id)).transpose()?.flatten() else {
+                return Ok(None);
+            };
+            let event: IcsEvent = serde_json::from_slice(&envelope::open(&key, &details)?)?;
+            let variables = template_variables(&event, event_uuid.as_deref());
+            let title = templates::render(&template.title, &variables);
+            let title = if title.trim().is_empty() { event.title() } else { title.trim().to_string() };
+            let content = templates::render(&template.content, &variables);
+            let item_type = template
+                .item_type
+                .filter(|t| vault::is_valid_item_type(t))
+                .unwrap_or_else(|| vault::infer_item_type(&content).to_string());
+            let item = VaultItem::insert(&conn, vault_id, &title, &content, &item_type, &key)?;
+            index_item(&conn, item.id, &key)?;
+            Ok(Some(item))
+        })();
+        match created {
+            Ok(Some(item)) => {
+                conn.execute(
+                    "UPDATE calendar_events SET notes_item_id = ?1 WHERE id = ?2",
+                    params![item.id, event_id],
+                )
+                .map_err(|e| e.to_string())?;
+                let _ = app
+                    .notification()
+                    .builder()
+                    .title(crate::i18n::tr("Meeting notes ready"))
+                    .body(&item.title)
+                    .show();
+                let _ = app.emit(MEETING_NOTES_EVENT, MeetingNotesEvent {
+                    itemId: item.id,
+                    vaultId: vault_id,
+                    eventItemId: event_item_id,
+                    title: item.title.clone(),
+                });
+            }
+            // The template was deleted; nothing to make the notes from
+            Ok(None) => {
+                conn.execute("UPDATE calendar_events SET notes_at = NULL WHERE id = ?1", [event_id])
+                    .map_err(|e| e.to_string())?;
+            }
+            Err(e) => {
+                tracing::warn!("Failed to create meeting notes for calendar event {}: {}", event_id, e);
+                conn.execute("UPDATE calendar_events SET notes_at = NULL WHERE id = ?1", [event_id])
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+    }
+    Ok(())
+}
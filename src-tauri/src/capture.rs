@@ -8,7 +8,6 @@ use std::path::PathBuf;
 // Windows-specific imports
 #[cfg(target_os = "windows")]
 use {
-    dirs,
     image::RgbaImage,
     screenshots::Screen,
     whoami,
@@ -21,13 +20,12 @@ use {
 // Cross-platform imports for non-Windows platforms
 #[cfg(not(target_os = "windows"))]
 use {
-    dirs,
     image::RgbaImage,
     screenshots::Screen,
     whoami,
 };
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CaptureMetadata {
     pub timestamp: DateTime<Local>,
     pub app_name: String,
@@ -76,7 +74,7 @@ pub mod windows_capture {
         let (app_name, window_title) = get_focused_window_info()?;
         let user = whoami::username();
         let timestamp = Local::now();
-        let screenshot_dir = dirs::data_local_dir()?.join("brainbox").join("captures");
+        let screenshot_dir = crate::data_dir::root()?.join("brainbox").join("captures");
         std::fs::create_dir_all(&screenshot_dir).ok()?;
         let filename = format!("{}_{}.png", app_name, timestamp.format("%Y%m%d_%H%M%S"));
         let screenshot_path = screenshot_dir.join(filename);
@@ -138,7 +136,7 @@ pub mod cross_platform_capture {
     pub fn capture_screenshot_and_metadata() -> Option<CaptureMetadata> {
         let user = whoami::username();
         let timestamp = Local::now();
-        let screenshot_dir = dirs::data_local_dir()?.join("brainbox").join("captures");
+        let screenshot_dir = crate::data_dir::root()?.join("brainbox").join("captures");
         std::fs::create_dir_all(&screenshot_dir).ok()?;
         let filename = format!("capture_{}.png", timestamp.format("%Y%m%d_%H%M%S"));
         let screenshot_path = screenshot_dir.join(filename);
@@ -0,0 +1,144 @@
+// capture_cleanup.rs - Orphaned capture files for brainbox
+// Screenshots and synced captures stay on disk after the items using them are deleted. A file is an orphan when no
+// item (trashed items included) mentions its name in its image, summary or content. Content is encrypted, so a
+// cleanup only runs once every vault with items can be read; a dry run lists candidates and the vaults it couldn't read.
+
+use rusqlite::Connection;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use crate::envelope;
+use crate::error::BrainboxError;
+use crate::keystore::VaultKey;
+use crate::vault::{Vault, VaultItem};
+
+/// Files this new may belong to a capture that hasn't been saved as an item yet
+const GRACE_PERIOD_SECS: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct OrphanCapture {
+    pub path: String,
+    pub file_name: String,
+    pub size_bytes: u64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct OrphanCaptureReport {
+    pub orphans: Vec<OrphanCapture>,
+    pub total_bytes: u64,
+    /// Files deleted; 0 for a dry run
+    pub removed: usize,
+    pub bytes_freed: u64,
+    /// Vaults whose item content couldn't be checked because they are locked
+    pub locked_vaults: Vec<String>,
+    pub dry_run: bool,
+}
+
+/// Folders holding capture files: screenshots and captures brought in by sync
+fn capture_dirs() -> Vec<PathBuf> {
+    let Some(app_dir) = crate::data_dir::root() else { return Vec::new() };
+    vec![app_dir.join("brainbox").join("captures"), app_dir.join("brainbox_captures")]
+}
+
+/// Text of every item that could refer to a capture file, and the names of vaults that couldn't be read
+fn referencing_text(
+    conn: &Connection,
+    key_for: &dyn Fn(&Vault) -> Option<VaultKey>,
+) -> Result<(Vec<String>, Vec<String>), BrainboxError> {
+    Vault::create_table(conn)?;
+    VaultItem::create_table(conn)?;
+    let mut texts = Vec::new();
+    let mut locked = Vec::new();
+    for vault in Vault::list_all_for_sync(conn)? {
+        let items = VaultItem::list_all_by_vault(conn, vault.id)?;
+        if items.is_empty() {
+            continue;
+        }
+        let key = key_for(&vault).and_then(|kek| envelope::content_key(conn, vault.id, &kek).ok());
+        if key.is_none() {
+            locked.push(vault.name.clone());
+        }
+        for item in items {
+            texts.extend(item.image);
+            texts.extend(item.summary);
+            if let Some(key) = &key {
+                if let Ok(plain) = envelope::open(key, &item.content) {
+                    texts.push(String::from_utf8_lossy(&plain).into_owned());
+                }
+            }
+        }
+    }
+    Ok((texts, locked))
+}
+
+/// Capture files no item refers to. `key_for` returns the unlocked key of a vault, if available.
+pub fn find(conn: &Connection, key_for: &dyn Fn(&Vault) -> Option<VaultKey>) -> Result<OrphanCaptureReport, BrainboxError> {
+    let (texts, locked_vaults) = referencing_text(conn, key_for)?;
+    let now = std::time::SystemTime::now();
+    let mut seen = HashSet::new();
+    let mut orphans = Vec::new();
+    for dir in capture_dirs() {
+        let Ok(entries) = std::fs::read_dir(&dir) else { continue };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(meta) = entry.metadata() else { continue };
+            let Some(file_name) = path.file_name().and_then(|n| n.to_str()).map(str::to_string) else { continue };
+            let recent = meta
+                .modified()
+                .ok()
+                .and_then(|m| now.duration_since(m).ok())
+                .is_none_or(|age| age.as_secs() < GRACE_PERIOD_SECS);
+            if !meta.is_file() || recent || !seen.insert(path.clone()) {
+                continue;
+            }
+            if texts.iter().any(|t| t.contains(&file_name)) {
+                continue;
+            }
+            orphans.push(OrphanCapture {
+                path: path.to_string_lossy().into_owned(),
+                file_name,
+                size_bytes: meta.len(),
+            });
+        }
+    }
+    orphans.sort_by_key(|o| std::cmp::Reverse(o.size_bytes));
+    Ok(OrphanCaptureReport {
+        total_bytes: orphans.iter().map(|o| o.size_bytes).sum(),
+        orphans,
+        removed: 0,
+        bytes_freed: 0,
+        locked_vaults,
+        dry_run: true,
+    })
+}
+
+/// Delete orphaned capture files, or only report them with `dry_run`
+pub fn clean(
+    conn: &Connection,
+    key_for: &dyn Fn(&Vault) -> Option<VaultKey>,
+    dry_run: bool,
+) -> Result<OrphanCaptureReport, BrainboxError> {
+    let mut report = find(conn, key_for)?;
+    report.dry_run = dry_run;
+    if dry_run {
+        return Ok(report);
+    }
+    if !report.locked_vaults.is_empty() {
+        return Err(BrainboxError::InvalidInput(format!(
+            "Unlock these vaults first so their notes can be checked: {}",
+            report.locked_vaults.join(", ")
+        )));
+    }
+    for orphan in &report.orphans {
+        match std::fs::remove_file(&orphan.path) {
+            Ok(()) => {
+                report.removed += 1;
+                report.bytes_freed += orphan.size_bytes;
+            }
+            Err(e) => tracing::warn!("Failed to remove orphaned capture {}: {}", orphan.path, e),
+        }
+    }
+    tracing::info!("Removed {} orphaned captures, {} bytes freed", report.removed, report.bytes_freed);
+    Ok(report)
+}
@@ -0,0 +1,263 @@
+// capture_rules.rs - Rules applied to incoming captures in brainbox
+// A rule matches a capture by domain, a title regex and/or content keywords; every condition it sets must hold.
+// The first enabled match (lowest sort order) picks the vault, adds tags and can ask for a summary or an archived
+// copy of the page. Rules run in the backend, so captures from the browser are sorted without opening the app.
+
+use regex::RegexBuilder;
+use rusqlite::{params, Connection, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::error::BrainboxError;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CaptureRule {
+    pub id: i64,
+    pub name: String,
+    pub enabled: bool,
+    pub sort_order: i64,
+    /// Matches this host and its subdomains, e.g. "github.com"
+    pub domain: Option<String>,
+    /// Case-insensitive regex tested against the title
+    pub title_pattern: Option<String>,
+    /// Any of these words in the title, URL or content
+    pub keywords: Vec<String>,
+    pub target_vault_id: Option<i64>,
+    pub tags: Vec<String>,
+    pub auto_summarize: bool,
+    /// Fetch the page's metadata and save a Wayback Machine snapshot
+    pub auto_archive: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// Fields the frontend sends when creating or editing a rule
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CaptureRuleInput {
+    pub name: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub sort_order: Option<i64>,
+    #[serde(default)]
+    pub domain: Option<String>,
+    #[serde(default)]
+    pub title_pattern: Option<String>,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    #[serde(default)]
+    pub target_vault_id: Option<i64>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub auto_summarize: bool,
+    #[serde(default)]
+    pub auto_archive: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+const RULE_COLUMNS: &str = "id, name, enabled, sort_order, domain, title_pattern, keywords, target_vault_id, tags, auto_summarize, auto_archive, created_at, updated_at";
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+impl CaptureRuleInput {
+    /// Trimmed copy, rejecting rules without a condition or with a bad regex
+    fn validated(&self) -> std::result::Result<CaptureRuleInput, BrainboxError> {
+        let mut input = self.clone();
+        input.name = input.name.trim().to_string();
+        input.domain = non_empty(input.domain).map(|d| d.trim_start_matches("www.").to_lowercase());
+        input.title_pattern = non_empty(input.title_pattern);
+        input.keywords = input.keywords.iter().map(|k| k.trim().to_lowercase()).filter(|k| !k.is_empty()).collect();
+        input.tags = crate::tags::normalize(&input.tags);
+        if input.name.is_empty() {
+            return Err(BrainboxError::InvalidInput("Rule name cannot be empty".to_string()));
+        }
+        if input.domain.is_none() && input.title_pattern.is_none() && input.keywords.is_empty() {
+            return Err(BrainboxError::InvalidInput("A rule needs a domain, title pattern or keyword".to_string()));
+        }
+        if let Some(pattern) = &input.title_pattern {
+            RegexBuilder::new(pattern)
+                .case_insensitive(true)
+                .build()
+                .map_err(|e| BrainboxError::InvalidInput(format!("Invalid title pattern: {}", e)))?;
+        }
+        Ok(input)
+    }
+}
+
+impl CaptureRule {
+    pub fn create_table(conn: &Connection) -> Result<()> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS capture_rules (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                enabled INTEGER NOT NULL DEFAULT 1,
+                sort_order INTEGER NOT NULL DEFAULT 0,
+                domain TEXT,
+                title_pattern TEXT,
+                keywords TEXT NOT NULL DEFAULT '[]',
+                target_vault_id INTEGER REFERENCES vaults(id) ON DELETE SET NULL,
+                tags TEXT NOT NULL DEFAULT '[]',
+                auto_summarize INTEGER NOT NULL DEFAULT 0,
+                auto_archive INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+        Ok(())
+    }
+
+    fn from_row(row: &rusqlite::Row) -> Result<CaptureRule> {
+        let keywords: String = row.get(6)?;
+        let tags: String = row.get(8)?;
+        Ok(CaptureRule {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            enabled: row.get(2)?,
+            sort_order: row.get(3)?,
+            domain: row.get(4)?,
+            title_pattern: row.get(5)?,
+            keywords: serde_json::from_str(&keywords).unwrap_or_default(),
+            target_vault_id: row.get(7)?,
+            tags: serde_json::from_str(&tags).unwrap_or_default(),
+            auto_summarize: row.get(9)?,
+            auto_archive: row.get(10)?,
+            created_at: row.get(11)?,
+            updated_at: row.get(12)?,
+        })
+    }
+
+    /// All rules in the order they are tried
+    pub fn list(conn: &Connection) -> Result<Vec<CaptureRule>> {
+        Self::create_table(conn)?;
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM capture_rules ORDER BY sort_order ASC, id ASC", RULE_COLUMNS))?;
+        let rows = stmt.query_map([], Self::from_row)?;
+        rows.collect()
+    }
+
+    pub fn get_by_id(conn: &Connection, rule_id: i64) -> Result<Option<CaptureRule>> {
+        Self::create_table(conn)?;
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM capture_rules WHERE id = ?1", RULE_COLUMNS))?;
+        let mut rows = stmt.query([rule_id])?;
+        if let Some(row) = rows.next()? {
+            Ok(Some(Self::from_row(row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// New rules go last unless a sort order is given
+    pub fn insert(conn: &Connection, input: &CaptureRuleInput) -> std::result::Result<CaptureRule, BrainboxError> {
+        Self::create_table(conn)?;
+        let input = input.validated()?;
+        let now = chrono::Utc::now().to_rfc3339();
+        let sort_order = match input.sort_order {
+            Some(order) => order,
+            None => conn.query_row("SELECT COALESCE(MAX(sort_order), -1) + 1 FROM capture_rules", [], |row| row.get(0))?,
+        };
+        conn.execute(
+            "INSERT INTO capture_rules (name, enabled, sort_order, domain, title_pattern, keywords, target_vault_id, tags, auto_summarize, auto_archive, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![
+                input.name,
+                input.enabled,
+                sort_order,
+                input.domain,
+                input.title_pattern,
+                serde_json::to_string(&input.keywords)?,
+                input.target_vault_id,
+                serde_json::to_string(&input.tags)?,
+                input.auto_summarize,
+                input.auto_archive,
+                now,
+                now
+            ],
+        )?;
+        Self::get_by_id(conn, conn.last_insert_rowid())?
+            .ok_or_else(|| BrainboxError::NotFound("Capture rule not found".to_string()))
+    }
+
+    pub fn update(conn: &Connection, rule_id: i64, input: &CaptureRuleInput) -> std::result::Result<CaptureRule, BrainboxError> {
+        let existing = Self::get_by_id(conn, rule_id)?
+            .ok_or_else(|| BrainboxError::NotFound("Capture rule not found".to_string()))?;
+        let input = input.validated()?;
+        conn.execute(
+            "UPDATE capture_rules SET name = ?1, enabled = ?2, sort_order = ?3, domain = ?4, title_pattern = ?5, keywords = ?6, target_vault_id = ?7, tags = ?8, auto_summarize = ?9, auto_archive = ?10, updated_at = ?11 WHERE id = ?12",
+            params![
+                input.name,
+                input.enabled,
+                input.sort_order.unwrap_or(existing.sort_order),
+                input.domain,
+                input.title_pattern,
+                serde_json::to_string(&input.keywords)?,
+                input.target_vault_id,
+                serde_json::to_string(&input.tags)?,
+                input.auto_summarize,
+                input.auto_archive,
+                chrono::Utc::now().to_rfc3339(),
+                rule_id
+            ],
+        )?;
+        Self::get_by_id(conn, rule_id)?.ok_or_else(|| BrainboxError::NotFound("Capture rule not found".to_string()))
+    }
+
+    pub fn delete(conn: &Connection, rule_id: i64) -> Result<usize> {
+        Self::create_table(conn)?;
+        conn.execute("DELETE FROM capture_rules WHERE id = ?1", [rule_id])
+    }
+
+    /// The editable fields of this rule, as they would be sent to recreate it
+    pub fn to_input(&self) -> CaptureRuleInput {
+        CaptureRuleInput {
+            name: self.name.clone(),
+            enabled: self.enabled,
+            sort_order: Some(self.sort_order),
+            domain: self.domain.clone(),
+            title_pattern: self.title_pattern.clone(),
+            keywords: self.keywords.clone(),
+            target_vault_id: self.target_vault_id,
+            tags: self.tags.clone(),
+            auto_summarize: self.auto_summarize,
+            auto_archive: self.auto_archive,
+        }
+    }
+
+    /// Whether every condition this rule sets holds for the capture
+    pub fn matches(&self, url: &str, title: &str, content: &str) -> bool {
+        if let Some(domain) = &self.domain {
+            let host = reqwest::Url::parse(url.trim())
+                .ok()
+                .and_then(|u| u.host_str().map(|h| h.trim_start_matches("www.").to_lowercase()));
+            let Some(host) = host else { return false };
+            if host != *domain && !host.ends_with(&format!(".{}", domain)) {
+                return false;
+            }
+        }
+        if let Some(pattern) = &self.title_pattern {
+            let matched = RegexBuilder::new(pattern)
+                .case_insensitive(true)
+                .build()
+                .map(|re| re.is_match(title))
+                .unwrap_or(false);
+            if !matched {
+                return false;
+            }
+        }
+        if !self.keywords.is_empty() {
+            let haystack = format!("{}\n{}\n{}", title, url, content).to_lowercase();
+            if !self.keywords.iter().any(|k| haystack.contains(k.as_str())) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// The first enabled rule matching a capture
+pub fn evaluate(conn: &Connection, url: &str, title: &str, content: &str) -> std::result::Result<Option<CaptureRule>, BrainboxError> {
+    Ok(CaptureRule::list(conn)?.into_iter().find(|rule| rule.enabled && rule.matches(url, title, content)))
+}
@@ -0,0 +1,347 @@
+// cli.rs - Headless command line interface for brainbox
+// `brainbox --cli <command>` adds, searches, lists, exports and syncs without opening a window, for scripts and
+// cron jobs. It works on the same database and search index as the app, so it can run while the app is open.
+// Only vaults without a password can be read or written; password vaults stay locked outside the app.
+
+use std::collections::HashMap;
+
+use crate::error::BrainboxError;
+use crate::progress::Operation;
+use crate::search::SearchFilters;
+use crate::vault::{Vault, VaultItem};
+use crate::{data_dir, db, inbox, saved_searches, search, sync};
+
+/// Runs the CLI instead of the app when present in the arguments
+pub const CLI_ARG: &str = "--cli";
+
+const USAGE: &str = "Usage: brainbox --cli <command> [options]
+
+Commands:
+  add <text or url> [--title TITLE] [--vault ID] [--tag TAG]...
+                       Save an item (into the Inbox unless --vault is given)
+  search <query> [--vault ID] [--limit N] [--json]
+                       Search items
+  list [--vault ID] [--json]
+                       List vaults, or the items of one vault
+  export [--vault ID]... [--output FILE]
+                       Export vaults as JSON (all vaults without a password by default)
+  sync [pull|push]     Pull then push through the configured sync backend, or only one direction
+
+Global options such as --data-dir and --portable work as they do for the app.";
+
+/// Exit codes: 0 success, 1 failure, 2 bad usage
+struct UsageError(String);
+
+enum CliError {
+    Usage(UsageError),
+    Failed(BrainboxError),
+}
+
+impl From<BrainboxError> for CliError {
+    fn from(e: BrainboxError) -> Self {
+        CliError::Failed(e)
+    }
+}
+
+impl From<UsageError> for CliError {
+    fn from(e: UsageError) -> Self {
+        CliError::Usage(e)
+    }
+}
+
+impl From<rusqlite::Error> for CliError {
+    fn from(e: rusqlite::Error) -> Self {
+        CliError::Failed(e.into())
+    }
+}
+
+/// Options and positional arguments of one command
+struct Args {
+    positional: Vec<String>,
+    options: Vec<(String, String)>,
+    flags: Vec<String>,
+}
+
+impl Args {
+    /// `with_value` lists the options that take a value; other `--x` arguments are flags
+    fn parse(args: &[String], with_value: &[&str]) -> Result<Args, UsageError> {
+        let mut parsed = Args { positional: Vec::new(), options: Vec::new(), flags: Vec::new() };
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            let Some(name) = arg.strip_prefix("--") else {
+                parsed.positional.push(arg.clone());
+                continue;
+            };
+            if let Some((name, value)) = name.split_once('=') {
+                parsed.options.push((name.to_string(), value.to_string()));
+            } else if with_value.contains(&name) {
+                let value = iter.next().ok_or_else(|| UsageError(format!("--{} needs a value", name)))?;
+                parsed.options.push((name.to_string(), value.clone()));
+            } else {
+                parsed.flags.push(name.to_string());
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options.iter().rev().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+
+    fn all(&self, name: &str) -> Vec<String> {
+        self.options.iter().filter(|(n, _)| n == name).map(|(_, v)| v.clone()).collect()
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|f| f == name)
+    }
+
+    fn vault_ids(&self) -> Result<Vec<i64>, UsageError> {
+        self.all("vault")
+            .iter()
+            .map(|v| v.parse().map_err(|_| UsageError(format!("Invalid vault id: {}", v))))
+            .collect()
+    }
+
+    fn vault_id(&self) -> Result<Option<i64>, UsageError> {
+        Ok(self.vault_ids()?.pop())
+    }
+}
+
+/// Whether this launch asked for the CLI
+pub fn requested() -> bool {
+    std::env::args().skip(1).any(|arg| arg == CLI_ARG)
+}
+
+/// Run the command given after --cli and return the process exit code
+pub fn run() -> i32 {
+    attach_console();
+    let args: Vec<String> = std::env::args().skip(1).filter(|arg| arg != CLI_ARG).collect();
+    let args = strip_global_options(args);
+    let Some((command, rest)) = args.split_first() else {
+        println!("{}", USAGE);
+        return 2;
+    };
+    let result = match command.as_str() {
+        "add" => add(rest),
+        "search" => search_items(rest),
+        "list" => list(rest),
+        "export" => export(rest),
+        "sync" => sync_now(rest),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            return 0;
+        }
+        other => Err(UsageError(format!("Unknown command: {}", other)).into()),
+    };
+    match result {
+        Ok(()) => 0,
+        Err(CliError::Usage(UsageError(message))) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            2
+        }
+        Err(CliError::Failed(e)) => {
+            eprintln!("Error: {}", e);
+            1
+        }
+    }
+}
+
+/// Options data_dir reads itself, so commands don't see them
+fn strip_global_options(args: Vec<String>) -> Vec<String> {
+    let mut kept = Vec::new();
+    let mut iter = args.into_iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--data-dir" {
+            iter.next();
+        } else if !(arg.starts_with("--data-dir=") || arg == "--portable") {
+            kept.push(arg);
+        }
+    }
+    kept
+}
+
+/// Release builds are GUI programs on Windows; borrow the console of the shell that started us
+#[cfg(target_os = "windows")]
+fn attach_console() {
+    use windows::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+    unsafe {
+        let _ = AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn attach_console() {}
+
+fn open_search() -> Result<(), BrainboxError> {
+    let index_dir = data_dir::root().ok_or("Failed to get app data dir")?.join("search_index");
+    std::fs::create_dir_all(&index_dir)?;
+    search::init_search_service(&index_dir).map_err(|e| BrainboxError::Search(e.to_string()))
+}
+
+fn print_json<T: serde::Serialize>(value: &T) -> Result<(), BrainboxError> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+fn add(args: &[String]) -> Result<(), CliError> {
+    let args = Args::parse(args, &["title", "vault", "tag"])?;
+    let content = args.positional.join(" ");
+    if content.trim().is_empty() {
+        return Err(UsageError("Nothing to add".to_string()).into());
+    }
+    let conn = db::open()?;
+    let vault_id = match args.vault_id()? {
+        Some(id) => id,
+        None => inbox::ensure_vault(&conn)?,
+    };
+    if let Err(e) = open_search() {
+        eprintln!("Warning: search index unavailable, the item won't be searchable until the index is rebuilt: {}", e);
+    }
+    let item = inbox::save(&conn, vault_id, args.option("title").unwrap_or(""), content.trim(), &args.all("tag"))?;
+    println!("{}", item.id);
+    Ok(())
+}
+
+fn search_items(args: &[String]) -> Result<(), CliError> {
+    let args = Args::parse(args, &["vault", "limit"])?;
+    let query = args.positional.join(" ");
+    if query.trim().is_empty() {
+        return Err(UsageError("Give a search query".to_string()).into());
+    }
+    let limit = match args.option("limit") {
+        Some(limit) => limit.parse().map_err(|_| UsageError(format!("Invalid limit: {}", limit)))?,
+        None => saved_searches::DEFAULT_RESULT_LIMIT,
+    };
+    open_search()?;
+    let conn = db::open()?;
+    let filters = SearchFilters { vault_id: args.vault_id()?, ..Default::default() };
+    let results = saved_searches::run_filtered(&conn, &query, &filters, limit.clamp(1, 1000))?;
+    if args.flag("json") {
+        print_json(&results)?;
+    } else {
+        for result in results {
+            println!("{}\t{}\t{}", result.id, result.metadata.item_type, result.title);
+        }
+    }
+    Ok(())
+}
+
+#[derive(serde::Serialize)]
+struct VaultSummary {
+    id: i64,
+    name: String,
+    items: i64,
+    has_password: bool,
+}
+
+#[derive(serde::Serialize)]
+struct ItemSummary {
+    id: i64,
+    item_type: Option<String>,
+    title: String,
+    updated_at: String,
+}
+
+fn list(args: &[String]) -> Result<(), CliError> {
+    let args = Args::parse(args, &["vault"])?;
+    let conn = db::open()?;
+    Vault::create_table(&conn)?;
+    VaultItem::create_table(&conn)?;
+    match args.vault_id()? {
+        None => {
+            let mut vaults = Vec::new();
+            for vault in Vault::list(&conn)? {
+                let items = VaultItem::count_by_vault(&conn, vault.id)?;
+                vaults.push(VaultSummary { id: vault.id, name: vault.name, items, has_password: vault.has_password });
+            }
+            if args.flag("json") {
+                print_json(&vaults)?;
+            } else {
+                for vault in vaults {
+                    let lock = if vault.has_password { "\tlocked" } else { "" };
+                    println!("{}\t{}\t{} items{}", vault.id, vault.name, vault.items, lock);
+                }
+            }
+        }
+        Some(vault_id) => {
+            Vault::get_by_id(&conn, vault_id)?.ok_or_else(|| BrainboxError::NotFound(format!("Vault {} not found", vault_id)))?;
+            let items: Vec<ItemSummary> = VaultItem::list_by_vault(&conn, vault_id)?
+                .into_iter()
+                .map(|item| ItemSummary { id: item.id, item_type: item.item_type, title: item.title, updated_at: item.updated_at })
+                .collect();
+            if args.flag("json") {
+                print_json(&items)?;
+            } else {
+                for item in items {
+                    println!("{}\t{}\t{}", item.id, item.item_type.as_deref().unwrap_or("note"), item.title);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn export(args: &[String]) -> Result<(), CliError> {
+    let args = Args::parse(args, &["vault", "output"])?;
+    let conn = db::open()?;
+    Vault::create_table(&conn)?;
+    let mut vault_ids = args.vault_ids()?;
+    if vault_ids.is_empty() {
+        let vaults = Vault::list(&conn)?;
+        for vault in vaults.iter().filter(|v| v.has_password) {
+            eprintln!("Skipping password-protected vault {}", vault.name);
+        }
+        vault_ids = vaults.into_iter().filter(|v| !v.has_password).map(|v| v.id).collect();
+    }
+    let keys = vec![None; vault_ids.len()];
+    let data = crate::build_export(&conn, &vault_ids, keys, &Operation::silent())?;
+    let json = serde_json::to_string_pretty(&data).map_err(BrainboxError::from)?;
+    match args.option("output") {
+        Some(path) => {
+            std::fs::write(path, json).map_err(BrainboxError::from)?;
+            eprintln!("Exported {} vaults to {}", vault_ids.len(), path);
+        }
+        None => println!("{}", json),
+    }
+    Ok(())
+}
+
+fn sync_now(args: &[String]) -> Result<(), CliError> {
+    let args = Args::parse(args, &[])?;
+    let (pull, push) = match args.positional.first().map(String::as_str) {
+        None => (true, true),
+        Some("pull") => (true, false),
+        Some("push") => (false, true),
+        Some(other) => return Err(UsageError(format!("Unknown sync direction: {}", other)).into()),
+    };
+    let conn = db::open()?;
+    if let Err(e) = open_search() {
+        eprintln!("Warning: search index unavailable, pulled items won't be searchable until the index is rebuilt: {}", e);
+    }
+    let op = Operation::silent();
+    if pull {
+        let result = sync::sync_import(&conn, HashMap::new(), &op).map_err(BrainboxError::Sync)?;
+        println!("Pulled {} items in {} vaults", result.imported_items, result.imported_vaults);
+        for name in result.skipped_vaults {
+            eprintln!("Skipped password-protected vault {}", name);
+        }
+        for failure in result.failed_vaults {
+            eprintln!("Vault {} failed: {}", failure.name, failure.error);
+        }
+        for warning in result.warnings {
+            eprintln!("Warning: {}", warning);
+        }
+    }
+    if push {
+        let result = sync::sync_export(&conn, HashMap::new(), &op).map_err(BrainboxError::Sync)?;
+        println!("Pushed {} items in {} vaults", result.exported_items, result.exported_vaults);
+        for name in result.skipped_vaults {
+            eprintln!("Skipped password-protected vault {}", name);
+        }
+        for warning in result.warnings {
+            eprintln!("Warning: {}", warning);
+        }
+    }
+    Ok(())
+}
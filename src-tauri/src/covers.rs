@@ -0,0 +1,123 @@
+// covers.rs - Vault cover image files for brainbox
+// Covers used to be stored as URLs or whole base64 data URLs inside the vaults table. Picked files and pasted images
+// are now downscaled, compressed and kept in a covers folder; the vault row only references "covers/<file>".
+
+use base64::Engine;
+use image::{codecs::jpeg::JpegEncoder, imageops::FilterType};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+use crate::error::BrainboxError;
+use crate::image_cache;
+
+/// Prefix of cover references in the vaults table, and the bbimg:// path they are served under
+pub const COVER_PREFIX: &str = "covers/";
+/// Covers are shown at card size; anything larger is scaled down to fit
+const MAX_COVER_DIMENSION: u32 = 1280;
+const JPEG_QUALITY: u8 = 82;
+/// Largest source file accepted
+const MAX_SOURCE_BYTES: u64 = 25 * 1024 * 1024;
+
+pub fn cover_dir() -> Option<PathBuf> {
+    Some(crate::data_dir::root()?.join("brainbox").join("covers"))
+}
+
+/// File name of a stored cover reference ("covers/<file>")
+pub fn file_name(cover: &str) -> Option<&str> {
+    cover.strip_prefix(COVER_PREFIX)
+}
+
+/// Resize and compress an image into the covers folder; returns its reference
+pub fn store_bytes(bytes: &[u8]) -> Result<String, BrainboxError> {
+    let mut img = image::load_from_memory(bytes)
+        .map_err(|e| BrainboxError::InvalidInput(format!("Unsupported image: {}", e)))?;
+    if img.width() > MAX_COVER_DIMENSION || img.height() > MAX_COVER_DIMENSION {
+        img = img.resize(MAX_COVER_DIMENSION, MAX_COVER_DIMENSION, FilterType::Triangle);
+    }
+    // Covers are photos and gradients, so lossy JPEG keeps them small; transparency is flattened
+    let rgb = img.to_rgb8();
+    let mut out = Vec::new();
+    JpegEncoder::new_with_quality(&mut out, JPEG_QUALITY)
+        .encode_image(&rgb)
+        .map_err(|e| BrainboxError::Other(format!("Failed to encode cover: {}", e)))?;
+
+    let dir = cover_dir().ok_or("Failed to get app data dir")?;
+    std::fs::create_dir_all(&dir)?;
+    // Content-addressed, so the same cover synced from two devices is one file
+    let name = format!("{:x}.jpg", Sha256::digest(&out));
+    let path = dir.join(&name);
+    if !path.exists() {
+        image_cache::write_atomic(&path, &out)?;
+    }
+    Ok(format!("{}{}", COVER_PREFIX, name))
+}
+
+pub fn store_file(path: &Path) -> Result<String, BrainboxError> {
+    let size = std::fs::metadata(path)?.len();
+    if size > MAX_SOURCE_BYTES {
+        return Err(BrainboxError::InvalidInput("Cover image is larger than 25 MB".to_string()));
+    }
+    store_bytes(&std::fs::read(path)?)
+}
+
+fn decode_data_url(value: &str) -> Option<Vec<u8>> {
+    let rest = value.strip_prefix("data:image/")?;
+    let (header, data) = rest.split_once(',')?;
+    // SVG covers (e.g. generated gradients) can't be rasterized here and are small anyway
+    if !header.ends_with(";base64") || header.starts_with("svg") {
+        return None;
+    }
+    base64::engine::general_purpose::STANDARD.decode(data.trim()).ok()
+}
+
+/// What to store for a cover set from the frontend: raster data URLs become files, anything else is kept as is
+pub fn normalize(cover: Option<&str>) -> Result<Option<String>, BrainboxError> {
+    let Some(cover) = cover.map(str::trim).filter(|c| !c.is_empty()) else {
+        return Ok(None);
+    };
+    match decode_data_url(cover) {
+        Some(bytes) => Ok(Some(store_bytes(&bytes)?)),
+        None => Ok(Some(cover.to_string())),
+    }
+}
+
+/// URL the webview can load for a stored cover
+pub fn display_url(cover: Option<String>) -> Option<String> {
+    let cover = cover?;
+    match file_name(&cover) {
+        Some(name) => Some(image_cache::asset_url(&format!("{}{}", COVER_PREFIX, name))),
+        None => Some(cover),
+    }
+}
+
+/// Self-contained form for export files: cover files are embedded as data URLs
+pub fn portable(cover: Option<String>) -> Option<String> {
+    let cover = cover?;
+    let Some(bytes) = file_name(&cover).and_then(|name| std::fs::read(cover_dir()?.join(name)).ok()) else {
+        return Some(cover);
+    };
+    Some(format!(
+        "data:image/jpeg;base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    ))
+}
+
+/// Copy cover files between the local covers folder and another folder (sync), skipping files already present.
+/// Returns the number of files copied.
+pub fn copy_missing(from: &Path, to: &Path) -> std::io::Result<usize> {
+    if !from.exists() {
+        return Ok(0);
+    }
+    std::fs::create_dir_all(to)?;
+    let mut copied = 0;
+    for entry in std::fs::read_dir(from)?.flatten() {
+        let path = entry.path();
+        let Some(name) = path.file_name() else { continue };
+        let dest = to.join(name);
+        if path.is_file() && !dest.exists() {
+            std::fs::copy(&path, &dest)?;
+            copied += 1;
+        }
+    }
+    Ok(copied)
+}
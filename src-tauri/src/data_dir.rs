@@ -0,0 +1,264 @@
+// data_dir.rs - Location of brainbox's data on disk
+// Everything lives under one root folder: the database, the search index and the brainbox and brainbox_captures
+// folders. The root is the OS local data folder unless a pointer file there names another one, which is how data
+// on another drive is found again at the next launch. A --data-dir flag overrides both for a single run, and
+// portable mode keeps everything in a folder beside the executable so brainbox can run from a USB stick.
+
+use lazy_static::lazy_static;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::BrainboxError;
+
+/// Holds the path of a custom data folder, in the OS local data folder
+const POINTER_FILE: &str = "brainbox-data-dir";
+/// `--data-dir <path>` or `--data-dir=<path>`
+const DATA_DIR_ARG: &str = "--data-dir";
+/// Portable mode is on when this flag is passed or a file of this name sits next to the executable
+const PORTABLE_ARG: &str = "--portable";
+const PORTABLE_FLAG_FILE: &str = "portable.flag";
+/// Where portable data goes, beside the executable
+const PORTABLE_DIR: &str = "brainbox-data";
+const DB_FILE: &str = "brainbox.sqlite";
+/// What brainbox keeps under the root besides the database
+const DIRS: &[&str] = &["search_index", "brainbox", "brainbox_captures"];
+
+lazy_static! {
+    static ref ROOT: Option<PathBuf> = resolve();
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct MigrationReport {
+    pub path: String,
+    pub bytes_copied: u64,
+    /// Old files that couldn't be removed after copying
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DataDirInfo {
+    pub path: String,
+    pub default_path: String,
+    /// Data is somewhere other than the default folder
+    pub custom: bool,
+    /// Set by --data-dir for this run, so it can't be changed from inside the app
+    pub from_command_line: bool,
+    /// Data lives beside the executable
+    pub portable: bool,
+}
+
+fn pointer_path() -> Option<PathBuf> {
+    Some(dirs::data_local_dir()?.join(POINTER_FILE))
+}
+
+/// The folder given with --data-dir, relative paths taken from the working directory
+fn from_args() -> Option<PathBuf> {
+    let mut args = std::env::args().skip(1);
+    let value = loop {
+        let arg = args.next()?;
+        if arg == DATA_DIR_ARG {
+            break args.next()?;
+        }
+        if let Some(value) = arg.strip_prefix(DATA_DIR_ARG).and_then(|rest| rest.strip_prefix('=')) {
+            break value.to_string();
+        }
+    };
+    let path = PathBuf::from(value.trim());
+    if path.as_os_str().is_empty() {
+        return None;
+    }
+    if path.is_absolute() {
+        Some(path)
+    } else {
+        Some(std::env::current_dir().ok()?.join(path))
+    }
+}
+
+/// The folder the executable was launched from; for an AppImage, the folder holding the AppImage
+fn exe_dir() -> Option<PathBuf> {
+    Some(crate::autostart::launch_path().ok()?.parent()?.to_path_buf())
+}
+
+/// The portable data folder, when portable mode is on
+fn portable_dir() -> Option<PathBuf> {
+    let exe_dir = exe_dir()?;
+    let enabled = std::env::args().skip(1).any(|arg| arg == PORTABLE_ARG) || exe_dir.join(PORTABLE_FLAG_FILE).exists();
+    enabled.then(|| exe_dir.join(PORTABLE_DIR))
+}
+
+fn from_pointer() -> Option<PathBuf> {
+    pointer_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|path| PathBuf::from(path.trim()))
+        .filter(|path| path.is_absolute())
+}
+
+fn resolve() -> Option<PathBuf> {
+    let root = from_args().or_else(portable_dir).or_else(from_pointer).or_else(dirs::data_local_dir)?;
+    if let Err(e) = fs::create_dir_all(&root) {
+        tracing::error!("Data folder {} is unavailable: {}", root.display(), e);
+    }
+    Some(root)
+}
+
+/// The folder holding all brainbox data. Fixed for the life of the process; a move takes effect on restart.
+pub fn root() -> Option<PathBuf> {
+    ROOT.clone()
+}
+
+pub fn info() -> Result<DataDirInfo, BrainboxError> {
+    let root = root().ok_or("Failed to get app data dir")?;
+    let default = dirs::data_local_dir().ok_or("Failed to get app data dir")?;
+    Ok(DataDirInfo {
+        path: root.to_string_lossy().to_string(),
+        default_path: default.to_string_lossy().to_string(),
+        custom: root != default,
+        from_command_line: from_args().is_some(),
+        portable: from_args().is_none() && portable_dir().is_some(),
+    })
+}
+
+fn check_not_overridden() -> Result<(), BrainboxError> {
+    if from_args().is_some() {
+        return Err(BrainboxError::InvalidInput(format!(
+            "brainbox was started with {}; change the flag to use another folder",
+            DATA_DIR_ARG
+        )));
+    }
+    if portable_dir().is_some() {
+        return Err(BrainboxError::InvalidInput(
+            "Portable data stays beside the executable; copy the folder to move it".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Use `path`, or the default folder when None, from the next launch on without moving anything,
+/// e.g. to open data kept on an external drive
+pub fn switch_to(path: Option<&Path>) -> Result<PathBuf, BrainboxError> {
+    check_not_overridden()?;
+    let target = match path {
+        Some(path) => {
+            if !path.is_absolute() {
+                return Err(BrainboxError::InvalidInput("Choose a full folder path".to_string()));
+            }
+            fs::create_dir_all(path)?;
+            path.canonicalize()?
+        }
+        None => dirs::data_local_dir().ok_or("Failed to get app data dir")?,
+    };
+    write_pointer(&target)?;
+    tracing::info!("Data folder set to {} from the next launch", target.display());
+    Ok(target)
+}
+
+fn db_files(root: &Path) -> Vec<PathBuf> {
+    ["", "-wal", "-shm"].iter().map(|suffix| root.join(format!("{}{}", DB_FILE, suffix))).collect()
+}
+
+/// Every file and folder brainbox keeps on disk, including the pointer to a custom root.
+/// A portable copy never touches the host's pointer.
+pub fn data_paths() -> Result<Vec<PathBuf>, BrainboxError> {
+    let root = root().ok_or("Failed to get app data dir")?;
+    let mut paths: Vec<PathBuf> = DIRS.iter().map(|dir| root.join(dir)).collect();
+    paths.extend(db_files(&root));
+    if portable_dir().is_none() {
+        paths.extend(pointer_path().filter(|path| path.exists()));
+    }
+    Ok(paths)
+}
+
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<u64> {
+    fs::create_dir_all(to)?;
+    let mut bytes = 0;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            bytes += copy_dir(&entry.path(), &target)?;
+        } else {
+            bytes += fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(bytes)
+}
+
+fn remove(path: &Path) -> std::io::Result<()> {
+    if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// Point future launches at `root`, or back at the default folder
+fn write_pointer(root: &Path) -> Result<(), BrainboxError> {
+    let pointer = pointer_path().ok_or("Failed to get app data dir")?;
+    if Some(root) == dirs::data_local_dir().as_deref() {
+        if pointer.exists() {
+            fs::remove_file(&pointer)?;
+        }
+        return Ok(());
+    }
+    let tmp = pointer.with_extension("tmp");
+    fs::write(&tmp, root.to_string_lossy().as_bytes())?;
+    fs::rename(&tmp, &pointer)?;
+    Ok(())
+}
+
+/// Copy all data to `new_root`, switch to it and remove the old copy. The search index is closed first and the
+/// app must restart afterwards, since open connections still point at the old location.
+pub fn migrate(new_root: &Path) -> Result<MigrationReport, BrainboxError> {
+    check_not_overridden()?;
+    let old_root = root().ok_or("Failed to get app data dir")?;
+    if !new_root.is_absolute() {
+        return Err(BrainboxError::InvalidInput("Choose a full folder path".to_string()));
+    }
+    fs::create_dir_all(new_root)?;
+    let new_root = new_root.canonicalize()?;
+    let old_root = old_root.canonicalize().unwrap_or(old_root);
+    if new_root == old_root {
+        return Err(BrainboxError::InvalidInput("Data is already in this folder".to_string()));
+    }
+    if DIRS.iter().any(|dir| new_root.starts_with(old_root.join(dir))) {
+        return Err(BrainboxError::InvalidInput("Choose a folder outside brainbox's data".to_string()));
+    }
+    if new_root.join(DB_FILE).exists() || DIRS.iter().any(|dir| new_root.join(dir).exists()) {
+        return Err(BrainboxError::InvalidInput("That folder already contains brainbox data".to_string()));
+    }
+
+    tracing::info!("Moving data from {} to {}", old_root.display(), new_root.display());
+    crate::search::close_search_service();
+    let mut bytes_copied = 0;
+    let copied = (|| -> Result<(), BrainboxError> {
+        for dir in DIRS {
+            let from = old_root.join(dir);
+            if from.is_dir() {
+                bytes_copied += copy_dir(&from, &new_root.join(dir))?;
+            }
+        }
+        // A consistent snapshot that includes anything still in the WAL
+        let target = new_root.join(DB_FILE);
+        crate::db::open()?.execute("VACUUM INTO ?1", [target.to_string_lossy()])?;
+        bytes_copied += fs::metadata(&target)?.len();
+        write_pointer(&new_root)
+    })();
+    if let Err(e) = copied {
+        // Leave the old data in charge and clean up the partial copy
+        for path in DIRS.iter().map(|dir| new_root.join(dir)).chain(db_files(&new_root)) {
+            let _ = remove(&path);
+        }
+        return Err(e);
+    }
+
+    let mut warnings = Vec::new();
+    for path in DIRS.iter().map(|dir| old_root.join(dir)).chain(db_files(&old_root)) {
+        if path.exists() {
+            if let Err(e) = remove(&path) {
+                warnings.push(format!("Could not remove {}: {}", path.display(), e));
+            }
+        }
+    }
+    Ok(MigrationReport { path: new_root.to_string_lossy().to_string(), bytes_copied, warnings })
+}
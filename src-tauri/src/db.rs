@@ -0,0 +1,87 @@
+// db.rs - SQLite connection setup for brainbox
+// Every connection gets a busy timeout and foreign keys; the database file itself is switched to WAL once at startup
+
+use rusqlite::Connection;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::error::BrainboxError;
+
+/// How long a connection waits on a lock held by another connection before failing with "database is locked"
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub fn db_path() -> Result<PathBuf, BrainboxError> {
+    Ok(crate::data_dir::root().ok_or("Failed to get app data dir")?.join("brainbox.sqlite"))
+}
+
+/// Per-connection settings; SQLite doesn't persist these
+pub fn configure(conn: &Connection) -> rusqlite::Result<()> {
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    conn.pragma_update(None, "foreign_keys", "ON")?;
+    Ok(())
+}
+
+/// Open the brainbox database with the standard connection settings
+pub fn open() -> Result<Connection, BrainboxError> {
+    let conn = Connection::open(db_path()?)?;
+    configure(&conn)?;
+    Ok(conn)
+}
+
+/// One-time switch of an existing database to WAL journaling, so readers no longer block writers.
+/// WAL mode is stored in the database file, so this is a no-op after the first run.
+pub fn migrate_to_wal() -> Result<(), BrainboxError> {
+    let conn = open()?;
+    let mode: String = conn.query_row("PRAGMA journal_mode", [], |row| row.get(0))?;
+    if mode.eq_ignore_ascii_case("wal") {
+        return Ok(());
+    }
+    let mode: String = conn.query_row("PRAGMA journal_mode = WAL", [], |row| row.get(0))?;
+    if mode.eq_ignore_ascii_case("wal") {
+        tracing::info!("Switched database to WAL journal mode");
+    } else {
+        tracing::warn!("Could not enable WAL journal mode, database stays in {} mode", mode);
+    }
+    Ok(())
+}
+
+/// Setting that turns on compaction after a purge removes many items
+pub const AUTO_COMPACT_KEY: &str = "auto_compact_after_purge";
+/// Purges removing at least this many items compact the database afterwards, when enabled
+pub const AUTO_COMPACT_MIN_ITEMS: usize = 200;
+
+#[derive(Debug, serde::Serialize, Clone)]
+pub struct CompactResult {
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+    pub bytes_freed: u64,
+}
+
+fn allocated_bytes(conn: &Connection) -> rusqlite::Result<u64> {
+    let pages: i64 = conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+    let page_size: i64 = conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+    Ok((pages * page_size) as u64)
+}
+
+pub fn auto_compact_enabled(conn: &Connection) -> Result<bool, BrainboxError> {
+    Ok(crate::settings::get_bool(conn, AUTO_COMPACT_KEY)?)
+}
+
+/// Rebuild the database file without free pages, refresh query planner statistics and truncate the WAL.
+/// `stage` is called with "vacuum", "optimize" and "checkpoint" as each step starts.
+pub fn compact(conn: &Connection, mut stage: impl FnMut(&str)) -> Result<CompactResult, BrainboxError> {
+    let bytes_before = allocated_bytes(conn)?;
+    stage("vacuum");
+    conn.execute_batch("VACUUM")?;
+    stage("optimize");
+    conn.execute_batch("PRAGMA optimize")?;
+    stage("checkpoint");
+    conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+    let bytes_after = allocated_bytes(conn)?;
+    tracing::info!("Compacted database from {} to {} bytes", bytes_before, bytes_after);
+    Ok(CompactResult {
+        bytes_before,
+        bytes_after,
+        bytes_freed: bytes_before.saturating_sub(bytes_after),
+    })
+}
@@ -0,0 +1,194 @@
+// deeplink.rs - brainbox:// deep links and launch arguments
+// Supports brainbox://capture?url=..&title=..[&selection=..&selection_html=..], brainbox://item/<uuid>, brainbox://vault/<uuid>, brainbox://search?q=..
+// and brainbox://action/<id> for the jump list's quick actions.
+// Plain arguments (`brainbox "some text"` or `brainbox file.pdf`) are captures too. Links that arrive before the
+// main window has loaded are queued and delivered from on_page_load
+
+use std::path::Path;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use crate::inbox::Selection;
+use crate::vault::{Vault, VaultItem};
+
+pub const SCHEME_PREFIX: &str = "brainbox://";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeepLink {
+    Capture { url: String, title: String, selection: Option<Selection> },
+    Item { uuid: String },
+    Vault { uuid: String },
+    Search { query: String },
+    /// One of quick_actions::ACTIONS
+    Action { id: String },
+    /// Text given on the command line
+    Text { text: String },
+    /// Existing files given on the command line, as absolute paths
+    Files { paths: Vec<String> },
+}
+
+/// Flags the app reads itself, with whether each takes a value; they aren't captured
+const APP_FLAGS: &[(&str, bool)] = &[
+    (crate::autostart::AUTOSTART_ARG, false),
+    ("--portable", false),
+    ("--data-dir", true),
+    (crate::cli::CLI_ARG, false),
+];
+
+/// Deep links waiting for the main window to finish loading
+#[derive(Default)]
+pub struct DeepLinkState {
+    ready: Mutex<bool>,
+    pending: Mutex<Vec<DeepLink>>,
+}
+
+pub fn query_param(query: &str, name: &str) -> Option<String> {
+    query.split('&').find_map(|param| {
+        let mut parts = param.splitn(2, '=');
+        match (parts.next(), parts.next()) {
+            (Some(key), Some(val)) if key == name => {
+                // Form-encoded queries use '+' for spaces
+                Some(urlencoding::decode(&val.replace('+', " ")).unwrap_or_default().to_string())
+            }
+            _ => None,
+        }
+    })
+}
+
+pub fn parse(url: &str) -> Option<DeepLink> {
+    let rest = url.trim().strip_prefix(SCHEME_PREFIX)?;
+    let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let path = path.trim_end_matches('/');
+    match path.split_once('/') {
+        None if path == "capture" => Some(DeepLink::Capture {
+            url: query_param(query, "url").unwrap_or_default(),
+            title: query_param(query, "title").unwrap_or_default(),
+            selection: Selection::from_query(query),
+        }),
+        None if path == "search" => Some(DeepLink::Search { query: query_param(query, "q").unwrap_or_default() }),
+        Some(("item", uuid)) if !uuid.is_empty() => Some(DeepLink::Item { uuid: uuid.to_string() }),
+        Some(("vault", uuid)) if !uuid.is_empty() => Some(DeepLink::Vault { uuid: uuid.to_string() }),
+        Some(("action", id)) if crate::quick_actions::find(id).is_some() => Some(DeepLink::Action { id: id.to_string() }),
+        _ => None,
+    }
+}
+
+/// Find the first brainbox:// link among command-line arguments
+pub fn find_in_args<S: AsRef<str>>(args: &[S]) -> Option<String> {
+    args.iter()
+        .map(|arg| arg.as_ref())
+        .find(|arg| arg.starts_with(SCHEME_PREFIX))
+        .map(|arg| arg.to_string())
+}
+
+/// What a launch was asked to open: a brainbox:// link, else the files or text among the arguments.
+/// `args` excludes the program name; relative paths are taken from `cwd`.
+pub fn from_args<S: AsRef<str>>(args: &[S], cwd: Option<&Path>) -> Option<DeepLink> {
+    if let Some(url) = find_in_args(args) {
+        return parse(&url);
+    }
+    let mut words = Vec::new();
+    let mut iter = args.iter().map(|arg| arg.as_ref());
+    while let Some(arg) = iter.next() {
+        let flag = APP_FLAGS.iter().find(|(flag, _)| arg == *flag || arg.starts_with(&format!("{}=", flag)));
+        match flag {
+            Some((flag, true)) if arg == *flag => {
+                iter.next();
+            }
+            Some(_) => {}
+            None => words.push(arg),
+        }
+    }
+    if words.is_empty() {
+        return None;
+    }
+    let paths: Vec<_> = words
+        .iter()
+        .map(|word| match cwd {
+            Some(cwd) => cwd.join(word),
+            None => Path::new(word).to_path_buf(),
+        })
+        .collect();
+    if paths.iter().all(|path| path.is_absolute() && path.is_file()) {
+        return Some(DeepLink::Files { paths: paths.iter().map(|p| p.display().to_string()).collect() });
+    }
+    let text = words.join(" ");
+    (!text.trim().is_empty()).then(|| DeepLink::Text { text: text.trim().to_string() })
+}
+
+/// Send one link to the main window as its frontend event
+fn deliver<R: Runtime>(app: &AppHandle<R>, link: DeepLink) {
+    let saved = match &link {
+        DeepLink::Capture { url, title, selection } => crate::inbox::receive_capture(app, url, title, selection.as_ref()),
+        DeepLink::Text { text } => crate::inbox::receive_capture(app, text, "", None),
+        DeepLink::Files { paths } => crate::inbox::receive_files(app, paths),
+        _ => false,
+    };
+    if saved {
+        return;
+    }
+    let Some(window) = app.get_webview_window("main") else { return };
+    let _ = window.show();
+    let _ = window.set_focus();
+    match link {
+        DeepLink::Capture { url, title, selection } => {
+            let _ = window.emit("capture-from-protocol", crate::inbox::capture_event(&url, &title, selection.as_ref()));
+        }
+        DeepLink::Search { query } => {
+            let _ = window.emit("deep-link-search", serde_json::json!({ "query": query }));
+        }
+        DeepLink::Text { text } => {
+            let _ = window.emit("capture-from-args", serde_json::json!({ "text": text }));
+        }
+        DeepLink::Files { paths } => {
+            // The frontend picks a vault and imports them with import_dropped_files
+            let _ = window.emit("files-from-args", serde_json::json!({ "paths": paths }));
+        }
+        DeepLink::Action { id } => crate::quick_actions::run(app, &id),
+        DeepLink::Item { uuid } => {
+            let item = crate::db::open().ok().and_then(|conn| VaultItem::get_by_uuid(&conn, &uuid).ok().flatten());
+            match item {
+                Some(item) if item.deleted_at.is_none() => {
+                    let _ = window.emit("open-item", serde_json::json!({ "itemId": item.id, "vaultId": item.vault_id }));
+                }
+                _ => tracing::warn!("Deep link to unknown item {}", uuid),
+            }
+        }
+        DeepLink::Vault { uuid } => {
+            let vault = crate::db::open().ok().and_then(|conn| Vault::get_by_uuid(&conn, &uuid).ok().flatten());
+            match vault {
+                Some(vault) if vault.deleted_at.is_none() => {
+                    let _ = window.emit("open-vault", serde_json::json!({ "vaultId": vault.id }));
+                }
+                _ => tracing::warn!("Deep link to unknown vault {}", uuid),
+            }
+        }
+    }
+}
+
+/// Handle the arguments of a launch, this one or one forwarded by a second instance: deliver the link or capture
+/// they carry now, or queue it until the main window has loaded
+pub fn handle_args<R: Runtime, S: AsRef<str>>(app: &AppHandle<R>, args: &[S], cwd: Option<&Path>) {
+    let Some(link) = from_args(args, cwd) else {
+        if let Some(url) = find_in_args(args) {
+            tracing::warn!("Ignoring unsupported deep link: {}", url);
+        }
+        return;
+    };
+    let Some(state) = app.try_state::<DeepLinkState>() else { return };
+    if *state.ready.lock().unwrap() && app.get_webview_window("main").is_some() {
+        deliver(app, link);
+    } else {
+        state.pending.lock().unwrap().push(link);
+    }
+}
+
+/// Called when the main window finishes loading: deliver everything queued so far
+pub fn flush_pending<R: Runtime>(app: &AppHandle<R>) {
+    let Some(state) = app.try_state::<DeepLinkState>() else { return };
+    *state.ready.lock().unwrap() = true;
+    let pending: Vec<DeepLink> = state.pending.lock().unwrap().drain(..).collect();
+    for link in pending {
+        deliver(app, link);
+    }
+}
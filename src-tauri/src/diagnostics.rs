@@ -0,0 +1,299 @@
+// diagnostics.rs - Crash reports and diagnostic bundles for brainbox
+// A panic hook writes a crash report next to the logs, and a marker file left behind by a session that never
+// reached a clean exit produces one at the next launch. `create_bundle` zips the recent logs and crash reports
+// with system, database, settings and search index details into one file to attach to a GitHub issue. Setting
+// secrets are redacted and no item content is included, though log lines may mention titles or URLs.
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use crate::error::BrainboxError;
+use crate::zip_writer::ZipWriter;
+
+const CRASH_DIR: &str = "crash_reports";
+/// Present while the app runs; still there at startup means the last session ended unexpectedly
+const SESSION_MARKER: &str = "session.json";
+/// Crash reports kept on disk and put in a bundle
+const MAX_CRASH_REPORTS: usize = 10;
+/// Newest log files put in a bundle, and how much of the end of each
+const BUNDLE_LOG_FILES: usize = 3;
+const BUNDLE_LOG_BYTES: u64 = 2 * 1024 * 1024;
+const REDACTED: &str = "[redacted]";
+
+#[derive(Serialize, Deserialize)]
+struct Session {
+    started_at: String,
+    version: String,
+    pid: u32,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct CrashReport {
+    pub file_name: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DiagnosticBundle {
+    pub path: String,
+    pub bytes: u64,
+    pub files: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct SystemInfo {
+    app_version: &'static str,
+    os: &'static str,
+    os_version: Option<String>,
+    arch: &'static str,
+    data_dir: Option<crate::data_dir::DataDirInfo>,
+    created_at: String,
+}
+
+#[derive(Serialize)]
+struct DatabaseInfo {
+    sqlite_version: String,
+    user_version: i64,
+    journal_mode: String,
+    page_size: i64,
+    page_count: i64,
+    freelist_count: i64,
+    /// Rows per table, brainbox's tables only
+    tables: Vec<(String, i64)>,
+}
+
+#[derive(Serialize)]
+struct SearchIndexInfo {
+    schema_version: u32,
+    stats: Option<crate::search::SearchIndexStats>,
+    error: Option<String>,
+}
+
+fn brainbox_dir() -> Option<PathBuf> {
+    Some(crate::data_dir::root()?.join("brainbox"))
+}
+
+fn crash_dir() -> Option<PathBuf> {
+    Some(brainbox_dir()?.join(CRASH_DIR))
+}
+
+fn session_path() -> Option<PathBuf> {
+    Some(brainbox_dir()?.join(SESSION_MARKER))
+}
+
+/// OS release as reported by the system's own tools, when they are there
+fn os_version() -> Option<String> {
+    #[cfg(target_os = "windows")]
+    let output = std::process::Command::new("cmd").args(["/C", "ver"]).output();
+    #[cfg(target_os = "macos")]
+    let output = std::process::Command::new("sw_vers").arg("-productVersion").output();
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let output = std::process::Command::new("uname").arg("-sr").output();
+    let version = String::from_utf8_lossy(&output.ok()?.stdout).trim().to_string();
+    (!version.is_empty()).then_some(version)
+}
+
+fn header(kind: &str) -> String {
+    format!(
+        "brainbox {} crash report\nVersion: {}\nOS: {} {} ({})\nTime: {}\n",
+        kind,
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        os_version().unwrap_or_default(),
+        std::env::consts::ARCH,
+        chrono::Utc::now().to_rfc3339()
+    )
+}
+
+/// Write a crash report, pruning the oldest beyond MAX_CRASH_REPORTS
+fn write_report(body: &str) -> Option<PathBuf> {
+    let dir = crash_dir()?;
+    std::fs::create_dir_all(&dir).ok()?;
+    let path = dir.join(format!("crash-{}.txt", chrono::Utc::now().format("%Y%m%d-%H%M%S%.3f")));
+    std::fs::write(&path, body).ok()?;
+    let reports = report_files();
+    for old in reports.iter().take(reports.len().saturating_sub(MAX_CRASH_REPORTS)) {
+        let _ = std::fs::remove_file(old);
+    }
+    Some(path)
+}
+
+/// Crash report files, oldest first
+fn report_files() -> Vec<PathBuf> {
+    let Some(dir) = crash_dir() else { return Vec::new() };
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+        .unwrap_or_default();
+    files.retain(|path| path.extension().is_some_and(|ext| ext == "txt"));
+    // Names carry the time, so sorting by name sorts by age
+    files.sort();
+    files
+}
+
+/// Record panics in a crash report before the default hook prints them. Call once, early in startup.
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let thread = std::thread::current();
+        let body = format!(
+            "{}Thread: {}\n\n{}\n\nBacktrace:\n{}\n",
+            header("panic"),
+            thread.name().unwrap_or("unnamed"),
+            info,
+            std::backtrace::Backtrace::force_capture()
+        );
+        match write_report(&body) {
+            Some(path) => tracing::error!("Panic: {} (crash report at {})", info, path.display()),
+            None => tracing::error!("Panic: {}", info),
+        }
+        default_hook(info);
+    }));
+}
+
+/// Note that a session is running, first reporting the previous one if it never exited cleanly
+pub fn start_session() {
+    let Some(path) = session_path() else { return };
+    if let Ok(previous) = std::fs::read_to_string(&path) {
+        let previous: Option<Session> = serde_json::from_str(&previous).ok();
+        let details = previous.map_or_else(String::new, |session| {
+            format!("Previous session: version {}, started {}, pid {}\n", session.version, session.started_at, session.pid)
+        });
+        let body = format!("{}\nThe previous session ended without a clean exit.\n{}", header("unexpected exit"), details);
+        if let Some(report) = write_report(&body) {
+            tracing::warn!("The last session ended unexpectedly; crash report at {}", report.display());
+        }
+    }
+    let session = Session {
+        started_at: chrono::Utc::now().to_rfc3339(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        pid: std::process::id(),
+    };
+    let written = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(&path, serde_json::to_vec(&session).unwrap_or_default()));
+    if let Err(e) = written {
+        tracing::warn!("Failed to write session marker: {}", e);
+    }
+}
+
+/// Called on a clean exit
+pub fn end_session() {
+    if let Some(path) = session_path() {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+pub fn list_crash_reports() -> Vec<CrashReport> {
+    report_files()
+        .iter()
+        .rev()
+        .filter_map(|path| {
+            let modified = std::fs::metadata(path).and_then(|meta| meta.modified()).ok()?;
+            Some(CrashReport {
+                file_name: path.file_name()?.to_string_lossy().to_string(),
+                created_at: chrono::DateTime::<chrono::Utc>::from(modified).to_rfc3339(),
+            })
+        })
+        .collect()
+}
+
+fn database_info(conn: &Connection) -> Result<DatabaseInfo, BrainboxError> {
+    let pragma = |name: &str| conn.query_row(&format!("PRAGMA {}", name), [], |row| row.get::<_, i64>(0));
+    let names: Vec<String> = conn
+        .prepare("SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name")?
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    let mut tables = Vec::new();
+    for name in names {
+        let count = conn.query_row(&format!("SELECT COUNT(*) FROM \"{}\"", name.replace('"', "\"\"")), [], |row| row.get(0))?;
+        tables.push((name, count));
+    }
+    Ok(DatabaseInfo {
+        sqlite_version: conn.query_row("SELECT sqlite_version()", [], |row| row.get(0))?,
+        user_version: pragma("user_version")?,
+        journal_mode: conn.query_row("PRAGMA journal_mode", [], |row| row.get(0))?,
+        page_size: pragma("page_size")?,
+        page_count: pragma("page_count")?,
+        freelist_count: pragma("freelist_count")?,
+        tables,
+    })
+}
+
+/// Every stored setting, with secrets replaced
+fn redacted_settings(conn: &Connection) -> Result<std::collections::BTreeMap<String, String>, BrainboxError> {
+    Ok(crate::settings::get_all(conn)?
+        .into_iter()
+        .map(|(key, value)| {
+            let value = if crate::settings::is_secret(&key) { REDACTED.to_string() } else { value };
+            (key, value)
+        })
+        .collect())
+}
+
+/// The last `max` bytes of a file
+fn tail(path: &Path, max: u64) -> std::io::Result<Vec<u8>> {
+    let mut file = std::fs::File::open(path)?;
+    let len = file.metadata()?.len();
+    file.seek(SeekFrom::Start(len.saturating_sub(max)))?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Zip everything useful for a bug report into `folder`, or the Downloads folder by default
+pub fn create_bundle(conn: &Connection, folder: Option<&Path>) -> Result<DiagnosticBundle, BrainboxError> {
+    let folder = match folder {
+        Some(folder) => folder.to_path_buf(),
+        None => dirs::download_dir().or_else(crate::data_dir::root).ok_or("Failed to find a folder for the bundle")?,
+    };
+    std::fs::create_dir_all(&folder)?;
+    let path = folder.join(format!("brainbox-diagnostics-{}.zip", chrono::Local::now().format("%Y%m%d-%H%M%S")));
+
+    let mut files: Vec<(String, Vec<u8>)> = Vec::new();
+    let system = SystemInfo {
+        app_version: env!("CARGO_PKG_VERSION"),
+        os: std::env::consts::OS,
+        os_version: os_version(),
+        arch: std::env::consts::ARCH,
+        data_dir: crate::data_dir::info().ok(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+    files.push(("system.json".to_string(), serde_json::to_vec_pretty(&system)?));
+    files.push(("database.json".to_string(), serde_json::to_vec_pretty(&database_info(conn)?)?));
+    files.push(("settings.json".to_string(), serde_json::to_vec_pretty(&redacted_settings(conn)?)?));
+    let search = match crate::search::index_stats() {
+        Ok(stats) => SearchIndexInfo { schema_version: crate::search::SCHEMA_VERSION, stats: Some(stats), error: None },
+        Err(e) => SearchIndexInfo { schema_version: crate::search::SCHEMA_VERSION, stats: None, error: Some(e.to_string()) },
+    };
+    files.push(("search_index.json".to_string(), serde_json::to_vec_pretty(&search)?));
+    if let Ok(usage) = crate::storage::usage() {
+        files.push(("storage.json".to_string(), serde_json::to_vec_pretty(&usage)?));
+    }
+    let logs = crate::logging::log_files().unwrap_or_default();
+    for log in logs.iter().rev().take(BUNDLE_LOG_FILES) {
+        match tail(log, BUNDLE_LOG_BYTES) {
+            Ok(bytes) => files.push((format!("logs/{}", log.file_name().unwrap_or_default().to_string_lossy()), bytes)),
+            Err(e) => tracing::warn!("Left {} out of the diagnostic bundle: {}", log.display(), e),
+        }
+    }
+    for report in report_files() {
+        if let Ok(bytes) = std::fs::read(&report) {
+            files.push((format!("crash_reports/{}", report.file_name().unwrap_or_default().to_string_lossy()), bytes));
+        }
+    }
+
+    let mut zip = ZipWriter::new(std::io::BufWriter::new(std::fs::File::create(&path)?));
+    for (name, bytes) in &files {
+        zip.add(name, bytes)?;
+    }
+    zip.finish()?;
+    tracing::info!("Wrote diagnostic bundle to {}", path.display());
+    Ok(DiagnosticBundle {
+        bytes: std::fs::metadata(&path)?.len(),
+        path: path.to_string_lossy().to_string(),
+        files: files.into_iter().map(|(name, _)| name).collect(),
+    })
+}
@@ -0,0 +1,400 @@
+// digest.rs - Weekly AI digest for brainbox
+// Once a week at the configured day and time, or at the next launch when the app was closed then, the items added
+// in the past seven days are summarised by a local Ollama model into a digest note: recurring themes, links worth
+// revisiting and tasks still open. The digest goes into a chosen vault. Only vaults whose key is available (no
+// password, or unlocked) are read, and the target vault must be one of them. The frontend keeps its AI settings to
+// itself, so the model the digest uses is stored here.
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveTime, TimeZone, Utc, Weekday};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::time::Duration as StdDuration;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::envelope;
+use crate::error::BrainboxError;
+use crate::http;
+use crate::ollama_options::{self, GenerationOptions, RequestOptions};
+use crate::settings;
+use crate::token_budget;
+use crate::vault::{self, Vault, VaultItem};
+
+const ENABLED_KEY: &str = "digest.enabled";
+const VAULT_KEY: &str = "digest.vault_id";
+const MODEL_KEY: &str = "digest.model";
+const BASE_URL_KEY: &str = "digest.base_url";
+const WEEKDAY_KEY: &str = "digest.weekday";
+const TIME_KEY: &str = "digest.time";
+const LAST_RUN_KEY: &str = "digest.last_run";
+const LAST_ERROR_KEY: &str = "digest.last_error";
+
+pub const WEEKDAYS: &[&str] = &["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+pub const DEFAULT_BASE_URL: &str = "http://127.0.0.1:11434";
+pub const DIGEST_EVENT: &str = "digest-created";
+/// Longest range a digest covers
+pub const MAX_RANGE_DAYS: i64 = 92;
+/// Items described to the model; a busy week is cut to the newest
+const MAX_ITEMS: usize = 150;
+const MAX_OPEN_TASKS: usize = 40;
+/// Characters of each item's text given to the model
+const SNIPPET_CHARS: usize = 280;
+const GENERATE_TIMEOUT: StdDuration = StdDuration::from_secs(300);
+const CHECK_INTERVAL: StdDuration = StdDuration::from_secs(300);
+
+const PROMPT: &str = "You are writing a weekly digest of a personal knowledge base. Below are the items added during \
+the period, then the tasks still open. Write the digest in Markdown with three sections: \"## Themes\" (the topics \
+that came up, each with a sentence on what was collected), \"## Notable links\" (the most useful links, as Markdown \
+links with a short reason) and \"## Open tasks\" (what is still to do, soonest due first). Be concise, use only the \
+items given, and leave out a section that would be empty.\n\n";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DigestConfig {
+    pub enabled: bool,
+    /// Vault the digests are saved in
+    pub vault_id: Option<i64>,
+    pub model: String,
+    pub base_url: Option<String>,
+    /// One of WEEKDAYS
+    pub weekday: String,
+    /// Local time of day, "HH:MM"
+    pub time: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DigestStatus {
+    #[serde(flatten)]
+    pub config: DigestConfig,
+    pub last_run: Option<String>,
+    pub last_error: Option<String>,
+    pub next_run: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DigestResult {
+    /// The new note; None when nothing was added in the range
+    pub item_id: Option<i64>,
+    pub vault_id: i64,
+    pub title: String,
+    pub since: String,
+    pub until: String,
+    pub items: usize,
+    pub open_tasks: usize,
+    /// Password-protected vaults that were locked, so were left out
+    pub skipped_vaults: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct GenerateRequest<'a> {
+    model: &'a str,
+    prompt: &'a str,
+    stream: bool,
+    #[serde(flatten)]
+    options: RequestOptions,
+}
+
+#[derive(Deserialize)]
+struct GenerateResponse {
+    response: String,
+}
+
+fn parse_time(time: &str) -> Result<NaiveTime, BrainboxError> {
+    NaiveTime::parse_from_str(time.trim(), "%H:%M")
+        .map_err(|_| BrainboxError::InvalidInput(format!("Invalid time of day: {} (use HH:MM)", time)))
+}
+
+fn parse_weekday(weekday: &str) -> Result<Weekday, BrainboxError> {
+    WEEKDAYS
+        .iter()
+        .position(|day| *day == weekday)
+        .and_then(|index| Weekday::try_from(index as u8).ok())
+        .ok_or_else(|| BrainboxError::InvalidInput(format!("Unknown weekday: {}", weekday)))
+}
+
+pub fn config(conn: &Connection) -> Result<DigestConfig, BrainboxError> {
+    Ok(DigestConfig {
+        enabled: settings::get_bool(conn, ENABLED_KEY)?,
+        vault_id: settings::get_parsed(conn, VAULT_KEY)?,
+        model: settings::get_or_default(conn, MODEL_KEY)?.unwrap_or_default(),
+        base_url: settings::get(conn, BASE_URL_KEY)?.filter(|url| !url.trim().is_empty()),
+        weekday: settings::get_or_default(conn, WEEKDAY_KEY)?.unwrap_or_else(|| WEEKDAYS[0].to_string()),
+        time: settings::get_or_default(conn, TIME_KEY)?.unwrap_or_default(),
+    })
+}
+
+fn last_run(conn: &Connection) -> Result<Option<DateTime<Utc>>, BrainboxError> {
+    Ok(settings::get(conn, LAST_RUN_KEY)?
+        .and_then(|at| DateTime::parse_from_rfc3339(&at).ok())
+        .map(|at| at.with_timezone(&Utc)))
+}
+
+/// The latest slot at or before `now`, on `weekday` at `time`
+fn latest_slot(weekday: Weekday, time: NaiveTime, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    let back = (now.weekday().num_days_from_monday() + 7 - weekday.num_days_from_monday()) % 7;
+    let day = now.date_naive() - Duration::days(back as i64);
+    let slot = Local.from_local_datetime(&day.and_time(time)).earliest()?;
+    if slot <= now { Some(slot) } else { Local.from_local_datetime(&(day - Duration::days(7)).and_time(time)).earliest() }
+}
+
+/// When the next digest is due: a slot missed since the last run is due now; the first one waits for its slot
+fn next_run(config: &DigestConfig, last_run: Option<DateTime<Utc>>, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    if !config.enabled || config.vault_id.is_none() || config.model.trim().is_empty() {
+        return None;
+    }
+    let slot = latest_slot(parse_weekday(&config.weekday).ok()?, parse_time(&config.time).ok()?, now)?;
+    match last_run {
+        Some(last) if last < slot.with_timezone(&Utc) => Some(slot),
+        _ => Some(slot + Duration::days(7)),
+    }
+}
+
+pub fn status(conn: &Connection) -> Result<DigestStatus, BrainboxError> {
+    let config = config(conn)?;
+    let last = last_run(conn)?;
+    Ok(DigestStatus {
+        next_run: next_run(&config, last, Local::now()).map(|at| at.to_rfc3339()),
+        last_run: last.map(|at| at.to_rfc3339()),
+        last_error: settings::get(conn, LAST_ERROR_KEY)?,
+        config,
+    })
+}
+
+pub fn set_config(conn: &Connection, config: &DigestConfig) -> Result<DigestStatus, BrainboxError> {
+    parse_weekday(&config.weekday)?;
+    let time = parse_time(&config.time)?;
+    if let Some(vault_id) = config.vault_id {
+        Vault::get_by_id(conn, vault_id)?
+            .filter(|v| v.deleted_at.is_none())
+            .ok_or_else(|| BrainboxError::NotFound(format!("Vault {} not found", vault_id)))?;
+    }
+    let model = config.model.trim();
+    if config.enabled && config.vault_id.is_none() {
+        return Err(BrainboxError::InvalidInput("Choose a vault for the digests".to_string()));
+    }
+    if config.enabled && model.is_empty() {
+        return Err(BrainboxError::InvalidInput("Choose a model to write the digests".to_string()));
+    }
+    settings::set_bool(conn, ENABLED_KEY, config.enabled)?;
+    match config.vault_id {
+        Some(vault_id) => settings::set_value(conn, VAULT_KEY, vault_id)?,
+        None => settings::delete(conn, VAULT_KEY)?,
+    }
+    settings::set(conn, MODEL_KEY, model)?;
+    match config.base_url.as_deref().map(str::trim).filter(|url| !url.is_empty()) {
+        Some(url) => settings::set(conn, BASE_URL_KEY, url.trim_end_matches('/'))?,
+        None => settings::delete(conn, BASE_URL_KEY)?,
+    }
+    settings::set(conn, WEEKDAY_KEY, &config.weekday)?;
+    settings::set(conn, TIME_KEY, &time.format("%H:%M").to_string())?;
+    status(conn)
+}
+
+/// One line describing an item for the model
+fn describe(vault_name: &str, item: &VaultItem, content: &str, tags: &[String]) -> String {
+    let item_type = item.item_type.clone().unwrap_or_else(|| vault::infer_item_type(content).to_string());
+    let snippet: String = content.split_whitespace().collect::<Vec<_>>().join(" ").chars().take(SNIPPET_CHARS).collect();
+    let mut line = format!("- [{}] \"{}\" in {}", item_type, item.title, vault_name);
+    if item_type == "url" {
+        line.push_str(&format!(" <{}>", content.trim()));
+    }
+    if !tags.is_empty() {
+        line.push_str(&format!(" #{}", tags.join(" #")));
+    }
+    if !snippet.is_empty() && item_type != "url" {
+        line.push_str(&format!(": {}", snippet));
+    }
+    line
+}
+
+fn ask(base_url: &str, model: &str, prompt: &str, options: &GenerationOptions) -> Result<String, BrainboxError> {
+    let body = GenerateRequest { model, prompt, stream: false, options: options.request() };
+    let resp = http::send(
+        http::client()
+            .post(format!("{}/api/generate", base_url))
+            .json(&body)
+            .timeout(GENERATE_TIMEOUT),
+    )?;
+    if !resp.status().is_success() {
+        return Err(BrainboxError::Network(format!("Ollama returned status {}", resp.status())));
+    }
+    let response: GenerateResponse = resp.json()?;
+    Ok(response.response.trim().to_string())
+}
+
+/// Write a digest of the items created between `since` and `until` into `vault_id`
+pub fn generate(
+    conn: &Connection,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+    vault_id: i64,
+    model: &str,
+    base_url: &str,
+) -> Result<DigestResult, BrainboxError> {
+    if until <= since {
+        return Err(BrainboxError::InvalidInput("The digest range ends before it starts".to_string()));
+    }
+    if until - since > Duration::days(MAX_RANGE_DAYS) {
+        return Err(BrainboxError::InvalidInput(format!("A digest covers at most {} days", MAX_RANGE_DAYS)));
+    }
+    if model.trim().is_empty() {
+        return Err(BrainboxError::InvalidInput("Choose a model to write the digest".to_string()));
+    }
+    let target_key = crate::resolve_key(conn, vault_id, None)?;
+    let target_key = envelope::content_key(conn, vault_id, &target_key)?;
+    VaultItem::create_table(conn)?;
+
+    let (since_text, until_text) = (since.to_rfc3339(), until.to_rfc3339());
+    let mut added: Vec<(String, String)> = Vec::new();
+    let mut tasks: Vec<(Option<String>, String)> = Vec::new();
+    let mut sources = Vec::new();
+    let mut skipped_vaults = Vec::new();
+    for vault in Vault::list(conn)? {
+        let Ok(kek) = crate::resolve_key(conn, vault.id, None) else {
+            skipped_vaults.push(vault.name);
+            continue;
+        };
+        let key = envelope::content_key(conn, vault.id, &kek)?;
+        for item in VaultItem::list_by_vault(conn, vault.id)? {
+            let in_range = item.created_at >= since_text && item.created_at < until_text;
+            let open_task = item.item_type.as_deref() == Some("task") && item.completed_at.is_none();
+            if !in_range && !open_task {
+                continue;
+            }
+            let Ok(plaintext) = envelope::open(&key, &item.content) else { continue };
+            let content = String::from_utf8_lossy(&plaintext).to_string();
+            if open_task {
+                let due = item.due_at.as_deref().map(|due| format!(" (due {})", &due[..due.len().min(10)])).unwrap_or_default();
+                tasks.push((item.due_at.clone(), format!("- \"{}\" in {}{}", item.title, vault.name, due)));
+            }
+            if in_range {
+                let tags = crate::tags::get(conn, item.id)?;
+                added.push((item.created_at.clone(), describe(&vault.name, &item, &content, &tags)));
+                if let Some(uuid) = &item.uuid {
+                    sources.push((item.created_at.clone(), format!("- [{}](brainbox://item/{})", item.title, uuid)));
+                }
+            }
+        }
+    }
+
+    let since_day = since.with_timezone(&Local).format("%Y-%m-%d");
+    let until_day = (until - Duration::seconds(1)).with_timezone(&Local).format("%Y-%m-%d");
+    let title = format!("Digest {} – {}", since_day, until_day);
+    let mut result = DigestResult {
+        item_id: None,
+        vault_id,
+        title: title.clone(),
+        since: since_text,
+        until: until_text,
+        items: added.len(),
+        open_tasks: tasks.len(),
+        skipped_vaults,
+    };
+    if added.is_empty() {
+        return Ok(result);
+    }
+
+    added.sort_by(|a, b| b.0.cmp(&a.0));
+    added.truncate(MAX_ITEMS);
+    // Tasks with a due date first, soonest first
+    tasks.sort_by(|a, b| (a.0.is_none(), &a.0).cmp(&(b.0.is_none(), &b.0)));
+    tasks.truncate(MAX_OPEN_TASKS);
+    let mut open_tasks = String::from("\nOpen tasks:\n");
+    for (_, line) in &tasks {
+        open_tasks.push_str(line);
+        open_tasks.push('\n');
+    }
+    let mut prompt = format!("{}Items added from {} to {}:\n", PROMPT, since_day, until_day);
+    // Newest items first until the model's window is full, keeping room for the open tasks
+    let options = ollama_options::resolve(conn, "digest", None)?;
+    let mut budget = token_budget::prompt_budget(model, options.num_ctx)
+        .saturating_sub(token_budget::count(&prompt, model) + token_budget::count(&open_tasks, model));
+    for (_, line) in &added {
+        let tokens = token_budget::count(line, model) + 1;
+        if tokens > budget {
+            break;
+        }
+        budget -= tokens;
+        prompt.push_str(line);
+        prompt.push('\n');
+    }
+    prompt.push_str(&open_tasks);
+    let digest = ask(base_url, model, &prompt, &options)?;
+
+    sources.sort_by(|a, b| b.0.cmp(&a.0));
+    let sources: Vec<String> = sources.into_iter().map(|(_, line)| line).collect();
+    let content = format!("# {}\n\n{}\n\n## Items this digest covers\n\n{}\n", title, digest, sources.join("\n"));
+    let item = VaultItem::insert(conn, vault_id, &title, &content, "note", &target_key)?;
+    let _ = crate::search::index_document(crate::search::DocumentInput {
+        id: item.id.to_string(),
+        title: item.title.clone(),
+        content,
+        item_type: "note".to_string(),
+        created_at: item.created_at.clone(),
+        updated_at: item.updated_at.clone(),
+        vault_id: Some(vault_id),
+        ..Default::default()
+    });
+    result.item_id = Some(item.id);
+    Ok(result)
+}
+
+/// The past week's digest with the saved settings, whether or not the schedule is on; records the outcome
+pub fn run_and_record(conn: &Connection) -> Result<DigestResult, BrainboxError> {
+    let config = config(conn)?;
+    let result = config
+        .vault_id
+        .ok_or_else(|| BrainboxError::InvalidInput("Choose a vault for the digests".to_string()))
+        .and_then(|vault_id| {
+            let until = Utc::now();
+            let base_url = config.base_url.as_deref().unwrap_or(DEFAULT_BASE_URL);
+            generate(conn, until - Duration::days(7), until, vault_id, &config.model, base_url)
+        });
+    settings::set(conn, LAST_RUN_KEY, &Utc::now().to_rfc3339())?;
+    match &result {
+        Ok(_) => settings::delete(conn, LAST_ERROR_KEY)?,
+        Err(e) => settings::set(conn, LAST_ERROR_KEY, &e.to_string())?,
+    }
+    result
+}
+
+fn run_if_due(app: &AppHandle) -> Result<(), BrainboxError> {
+    let conn = crate::db::open()?;
+    let config = config(&conn)?;
+    let Some(due) = next_run(&config, last_run(&conn)?, Local::now()) else { return Ok(()) };
+    if due > Local::now() {
+        return Ok(());
+    }
+    match run_and_record(&conn) {
+        Ok(result) => {
+            if let Some(item_id) = result.item_id {
+                let _ = app
+                    .notification()
+                    .builder()
+                    .title(crate::i18n::tr("Weekly digest ready"))
+                    .body(&result.title)
+                    .show();
+                let _ = app.emit(DIGEST_EVENT, serde_json::json!({ "itemId": item_id, "vaultId": result.vault_id }));
+            }
+        }
+        Err(e) => {
+            tracing::error!("Weekly digest failed: {}", e);
+            let _ = app
+                .notification()
+                .builder()
+                .title(crate::i18n::tr("Weekly digest failed"))
+                .body(e.localized_message())
+                .show();
+        }
+    }
+    Ok(())
+}
+
+/// Check every few minutes whether a digest is due
+pub fn spawn_scheduler(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        if let Err(e) = run_if_due(&app) {
+            tracing::error!("Weekly digest check failed: {}", e);
+        }
+        std::thread::sleep(CHECK_INTERVAL);
+    });
+}
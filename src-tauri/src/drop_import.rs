@@ -0,0 +1,237 @@
+// drop_import.rs - Import files dropped onto the brainbox window
+// Text/markdown become notes, images are copied into the image cache with a thumbnail, PDFs have their text
+// extracted for search, and .webloc/.url shortcuts become URL items. Each file is imported independently.
+
+use regex::Regex;
+use rusqlite::Connection;
+use serde::Serialize;
+use std::path::Path;
+
+use crate::error::BrainboxError;
+use crate::exif;
+use crate::image_cache;
+use crate::thumbnails;
+use crate::vault::VaultItem;
+
+const TEXT_EXTENSIONS: &[&str] = &["txt", "text", "md", "markdown", "mdown", "mkd"];
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp", "bmp", "tif", "tiff"];
+/// Largest text file imported as a note, in bytes
+const MAX_TEXT_BYTES: u64 = 5 * 1024 * 1024;
+/// Largest image or PDF read into memory, in bytes
+const MAX_BINARY_BYTES: u64 = 50 * 1024 * 1024;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DroppedFileResult {
+    pub path: String,
+    /// Item created for the file; None when it was skipped or failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item_id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnail: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// What an item will be created from, before anything is written
+struct Prepared {
+    title: String,
+    content: String,
+    item_type: &'static str,
+    image: Option<String>,
+    thumbnail: Option<String>,
+    /// Text indexed for search when it differs from the item content
+    search_text: Option<String>,
+    /// EXIF read from an image before it was re-encoded
+    exif: Option<exif::ImageMetadata>,
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+        .unwrap_or_default()
+}
+
+fn file_title(path: &Path) -> String {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .map(|stem| stem.to_string())
+        .unwrap_or_else(|| "Untitled".to_string())
+}
+
+fn read_limited(path: &Path, max_bytes: u64) -> Result<Vec<u8>, BrainboxError> {
+    let metadata = std::fs::metadata(path)
+        .map_err(|_| BrainboxError::NotFound(format!("File not found: {}", path.display())))?;
+    if !metadata.is_file() {
+        return Err(BrainboxError::InvalidInput("Folders can't be imported".to_string()));
+    }
+    if metadata.len() > max_bytes {
+        return Err(BrainboxError::InvalidInput(format!("File is larger than {} MB", max_bytes / (1024 * 1024))));
+    }
+    Ok(std::fs::read(path)?)
+}
+
+/// URL from a Windows .url shortcut (INI format with a URL= line)
+fn url_from_shortcut(text: &str) -> Option<String> {
+    text.lines()
+        .map(|line| line.trim())
+        .find_map(|line| line.strip_prefix("URL="))
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty())
+}
+
+/// URL from a macOS .webloc file. Older files are XML plists; newer ones are binary plists,
+/// where the URL is still stored as a plain ASCII string, so scanning the bytes covers both.
+fn url_from_webloc(bytes: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(bytes);
+    let xml = Regex::new(r"<key>URL</key>\s*<string>([^<]+)</string>").ok()?;
+    if let Some(caps) = xml.captures(&text) {
+        return Some(caps[1].trim().replace("&amp;", "&"));
+    }
+    let any = Regex::new(r"[a-zA-Z][a-zA-Z0-9+.-]*://[\x21-\x7e]+").ok()?;
+    any.find(&text).map(|m| m.as_str().to_string())
+}
+
+fn prepare_text(path: &Path) -> Result<Prepared, BrainboxError> {
+    let bytes = read_limited(path, MAX_TEXT_BYTES)?;
+    // Strip a UTF-8 BOM, which editors on Windows like to add
+    let content = String::from_utf8_lossy(bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(&bytes)).to_string();
+    Ok(Prepared {
+        title: file_title(path),
+        content,
+        item_type: "note",
+        image: None,
+        thumbnail: None,
+        search_text: None,
+        exif: None,
+    })
+}
+
+fn prepare_image(path: &Path) -> Result<Prepared, BrainboxError> {
+    let bytes = read_limited(path, MAX_BINARY_BYTES)?;
+    // Content-addressed so dropping the same image twice reuses one copy
+    let file_name = image_cache::cache_image_bytes(&bytes)?;
+    let copy = image_cache::cache_dir().ok_or("Failed to get app data dir")?.join(&file_name);
+    let image = image_cache::asset_url(&file_name);
+    // The item is still useful without a thumbnail; grid views fall back to the full image
+    let thumbnail = thumbnails::get_or_create(&copy, thumbnails::DEFAULT_THUMBNAIL_SIZE)
+        .map_err(|e| tracing::warn!("Thumbnail for dropped image {} failed: {}", path.display(), e))
+        .ok();
+    let title = file_title(path);
+    Ok(Prepared {
+        content: format!("![{}]({})", title, image),
+        title,
+        item_type: "image",
+        image: Some(image),
+        thumbnail,
+        search_text: None,
+        exif: exif::read(&bytes),
+    })
+}
+
+fn prepare_pdf(path: &Path) -> Result<Prepared, BrainboxError> {
+    let bytes = read_limited(path, MAX_BINARY_BYTES)?;
+    // Image-only or malformed PDFs still import; they just aren't searchable by content
+    let text = match pdf_extract::extract_text_from_mem(&bytes) {
+        Ok(text) => text.trim().to_string(),
+        Err(e) => {
+            tracing::warn!("Text extraction failed for {}: {}", path.display(), e);
+            String::new()
+        }
+    };
+    let source = path.display().to_string();
+    let content = if text.is_empty() { source } else { format!("{}\n\n{}", source, text) };
+    Ok(Prepared {
+        title: file_title(path),
+        content,
+        item_type: "file",
+        image: None,
+        thumbnail: None,
+        search_text: Some(text),
+        exif: None,
+    })
+}
+
+fn prepare_shortcut(path: &Path, ext: &str) -> Result<Prepared, BrainboxError> {
+    let bytes = read_limited(path, MAX_TEXT_BYTES)?;
+    let url = if ext == "url" {
+        url_from_shortcut(&String::from_utf8_lossy(&bytes))
+    } else {
+        url_from_webloc(&bytes)
+    };
+    let url = url.ok_or_else(|| BrainboxError::InvalidInput("Shortcut contains no URL".to_string()))?;
+    Ok(Prepared {
+        title: file_title(path),
+        content: url,
+        item_type: "url",
+        image: None,
+        thumbnail: None,
+        search_text: None,
+        exif: None,
+    })
+}
+
+fn prepare(path: &Path) -> Result<Prepared, BrainboxError> {
+    let ext = extension(path);
+    match ext.as_str() {
+        e if TEXT_EXTENSIONS.contains(&e) => prepare_text(path),
+        e if IMAGE_EXTENSIONS.contains(&e) => prepare_image(path),
+        "pdf" => prepare_pdf(path),
+        "url" | "webloc" => prepare_shortcut(path, &ext),
+        _ => Err(BrainboxError::InvalidInput(format!("Unsupported file type: .{}", ext))),
+    }
+}
+
+fn import_one(conn: &Connection, path: &Path, vault_id: i64, key: &[u8; 32]) -> Result<(VaultItem, Option<String>), BrainboxError> {
+    let prepared = prepare(path)?;
+    let item = VaultItem::insert(conn, vault_id, &prepared.title, &prepared.content, prepared.item_type, key)?;
+    if let Some(image) = &prepared.image {
+        VaultItem::update_image(conn, item.id, Some(image))?;
+    }
+    let mut created_at = item.created_at.clone();
+    if let Some(metadata) = &prepared.exif {
+        exif::apply(conn, item.id, metadata, key)?;
+        created_at = metadata.taken_at.clone().unwrap_or(created_at);
+    }
+    // Best-effort: index in search immediately
+    let _ = crate::search::index_document(crate::search::DocumentInput {
+        id: item.id.to_string(),
+        title: prepared.title,
+        content: prepared.search_text.unwrap_or(prepared.content),
+        item_type: prepared.item_type.to_string(),
+        created_at,
+        updated_at: item.updated_at.clone(),
+        path: Some(path.display().to_string()),
+        vault_id: Some(vault_id),
+        ..Default::default()
+    });
+    Ok((item, prepared.thumbnail))
+}
+
+/// Import each dropped path into `vault_id`; one bad file doesn't stop the rest
+pub fn import_files(conn: &Connection, paths: &[String], vault_id: i64, key: &[u8; 32]) -> Vec<DroppedFileResult> {
+    paths
+        .iter()
+        .map(|path| match import_one(conn, Path::new(path), vault_id, key) {
+            Ok((item, thumbnail)) => DroppedFileResult {
+                path: path.clone(),
+                item_id: Some(item.id),
+                item_type: item.item_type,
+                thumbnail,
+                error: None,
+            },
+            Err(e) => {
+                tracing::warn!("Failed to import dropped file {}: {}", path, e);
+                DroppedFileResult {
+                    path: path.clone(),
+                    item_id: None,
+                    item_type: None,
+                    thumbnail: None,
+                    error: Some(e.to_string()),
+                }
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,141 @@
+// duplicates.rs - Exact-duplicate items for brainbox
+// Items store a keyed hash of their plaintext, so repeated captures and imports of the same note or link can be
+// found without decrypting a whole vault each time. Merging keeps one item, fills in metadata it lacks from the
+// others and moves the others to the trash.
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::error::BrainboxError;
+use crate::reminders::Reminder;
+use crate::vault::{self, VaultItem};
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DuplicateItem {
+    pub id: i64,
+    pub title: String,
+    pub item_type: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub collection_id: Option<i64>,
+}
+
+/// Items with identical content, oldest first
+#[derive(Debug, Serialize, Clone)]
+pub struct DuplicateGroup {
+    pub items: Vec<DuplicateItem>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct MergeResult {
+    pub kept_id: i64,
+    pub removed_ids: Vec<i64>,
+}
+
+/// Hash items that don't have a content hash yet, e.g. ones saved before hashing existed
+fn backfill(conn: &Connection, vault_id: i64, key: &[u8; 32]) -> Result<usize, BrainboxError> {
+    let mut stmt = conn.prepare(
+        "SELECT id, content FROM vault_items WHERE vault_id = ?1 AND deleted_at IS NULL AND content_hash IS NULL",
+    )?;
+    let missing: Vec<(i64, Vec<u8>)> = stmt
+        .query_map([vault_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+    for (id, content) in &missing {
+        let plain = crate::envelope::open(key, content)?;
+        VaultItem::set_content_hash(conn, *id, &vault::content_hash(key, &String::from_utf8_lossy(&plain)))?;
+    }
+    Ok(missing.len())
+}
+
+/// Groups of two or more non-deleted items in a vault with the same content. `key` is the vault's content key.
+pub fn find(conn: &Connection, vault_id: i64, key: &[u8; 32]) -> Result<Vec<DuplicateGroup>, BrainboxError> {
+    VaultItem::create_table(conn)?;
+    backfill(conn, vault_id, key)?;
+    let mut stmt = conn.prepare(
+        "SELECT id, title, item_type, created_at, updated_at, collection_id, content_hash FROM vault_items \
+         WHERE vault_id = ?1 AND deleted_at IS NULL AND content_hash IN ( \
+             SELECT content_hash FROM vault_items WHERE vault_id = ?1 AND deleted_at IS NULL \
+             GROUP BY content_hash HAVING COUNT(*) > 1) \
+         ORDER BY content_hash, created_at ASC, id ASC",
+    )?;
+    let rows = stmt.query_map([vault_id], |row| {
+        Ok((
+            row.get::<_, String>(6)?,
+            DuplicateItem {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                item_type: row.get(2)?,
+                created_at: row.get(3)?,
+                updated_at: row.get(4)?,
+                collection_id: row.get(5)?,
+            },
+        ))
+    })?;
+    let mut groups: Vec<(String, DuplicateGroup)> = Vec::new();
+    for row in rows {
+        let (hash, item) = row?;
+        match groups.last_mut() {
+            Some((last, group)) if *last == hash => group.items.push(item),
+            _ => groups.push((hash, DuplicateGroup { items: vec![item] })),
+        }
+    }
+    Ok(groups.into_iter().map(|(_, group)| group).collect())
+}
+
+/// Keep the first of `ids` and trash the rest. All items must be in one vault and share content.
+/// The kept item takes over reminders, and any image, summary, collection or due date it lacks.
+pub fn merge(conn: &Connection, ids: &[i64]) -> Result<MergeResult, BrainboxError> {
+    let Some((&kept_id, others)) = ids.split_first().filter(|(_, rest)| !rest.is_empty()) else {
+        return Err(BrainboxError::InvalidInput("Select at least two items to merge".to_string()));
+    };
+    VaultItem::create_table(conn)?;
+    Reminder::create_table(conn)?;
+    let hash_of = |id: i64| -> Result<(i64, Option<String>), BrainboxError> {
+        conn.query_row(
+            "SELECT vault_id, content_hash FROM vault_items WHERE id = ?1 AND deleted_at IS NULL",
+            [id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => BrainboxError::NotFound(format!("Item {} not found", id)),
+            e => e.into(),
+        })
+    };
+    let kept = hash_of(kept_id)?;
+    if kept.1.is_none() {
+        return Err(BrainboxError::InvalidInput("Look for duplicates in this vault before merging".to_string()));
+    }
+    for &id in others {
+        if id == kept_id || hash_of(id)? != kept {
+            return Err(BrainboxError::InvalidInput("Only items with identical content can be merged".to_string()));
+        }
+    }
+
+    conn.execute("BEGIN IMMEDIATE", [])?;
+    let merged = (|| -> Result<(), BrainboxError> {
+        for &id in others {
+            conn.execute(
+                "UPDATE vault_items SET \
+                    image = COALESCE(image, (SELECT image FROM vault_items WHERE id = ?2)), \
+                    summary = COALESCE(summary, (SELECT summary FROM vault_items WHERE id = ?2)), \
+                    collection_id = COALESCE(collection_id, (SELECT collection_id FROM vault_items WHERE id = ?2)), \
+                    due_at = COALESCE(due_at, (SELECT due_at FROM vault_items WHERE id = ?2)) \
+                 WHERE id = ?1",
+                params![kept_id, id],
+            )?;
+            conn.execute("UPDATE reminders SET item_id = ?1 WHERE item_id = ?2", params![kept_id, id])?;
+            VaultItem::delete(conn, id)?;
+        }
+        Ok(())
+    })();
+    match merged {
+        Ok(()) => conn.execute("COMMIT", [])?,
+        Err(e) => {
+            let _ = conn.execute("ROLLBACK", []);
+            return Err(e);
+        }
+    };
+    for &id in others {
+        let _ = crate::search::delete_document(id.to_string());
+    }
+    Ok(MergeResult { kept_id, removed_ids: others.to_vec() })
+}
@@ -0,0 +1,102 @@
+// emergency.rs - Panic lock and data wipe for brainbox
+// An emergency lock forgets every unlocked vault key and hides the app in one step, optionally dropping
+// password-protected vaults from the search index so their text can't be found until they are unlocked again.
+// A full wipe deletes the database and every brainbox folder, and requires typing a confirmation phrase.
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::error::BrainboxError;
+use crate::keystore;
+use crate::settings;
+use crate::vault::Vault;
+
+/// Setting that makes the hotkey also clear locked vaults from the search index
+const CLEAR_INDEX_SETTING: &str = "emergency_lock.clear_index";
+/// Phrase `wipe_all_data` must be called with
+pub const WIPE_CONFIRMATION: &str = "DELETE ALL MY DATA";
+/// Emitted after an emergency lock so the frontend drops its unlocked state
+pub const LOCKED_EVENT: &str = "emergency-locked";
+
+#[derive(Debug, Serialize, Clone)]
+pub struct EmergencyLockResult {
+    /// Search documents removed for password-protected vaults
+    pub documents_removed: u64,
+}
+
+pub fn clear_index_setting() -> Result<bool, BrainboxError> {
+    let conn = crate::db::open()?;
+    Ok(settings::get_bool(&conn, CLEAR_INDEX_SETTING)?)
+}
+
+pub fn set_clear_index_setting(enabled: bool) -> Result<(), BrainboxError> {
+    let conn = crate::db::open()?;
+    settings::set_bool(&conn, CLEAR_INDEX_SETTING, enabled)?;
+    Ok(())
+}
+
+fn hide_windows(app: &AppHandle) {
+    for window in app.webview_windows().values() {
+        let _ = window.hide();
+    }
+}
+
+/// Forget all vault keys and hide every window; with `clear_index`, also drop password-protected vaults from search.
+/// `None` uses the saved setting.
+pub fn lock(app: &AppHandle, clear_index: Option<bool>) -> Result<EmergencyLockResult, BrainboxError> {
+    // Keys and windows first, so a failure further down can't leave anything open
+    keystore::clear();
+    hide_windows(app);
+    let _ = app.emit(LOCKED_EVENT, ());
+
+    let clear_index = match clear_index {
+        Some(clear) => clear,
+        None => clear_index_setting().unwrap_or(false),
+    };
+    let mut documents_removed = 0;
+    if clear_index {
+        let conn = crate::db::open()?;
+        Vault::create_table(&conn)?;
+        for vault in Vault::list(&conn)?.into_iter().filter(|v| v.has_password) {
+            match crate::search::delete_documents_by_vault(vault.id) {
+                Ok(removed) => documents_removed += removed,
+                Err(e) => tracing::warn!("Failed to clear search index for vault {}: {}", vault.id, e),
+            }
+        }
+    }
+    tracing::info!("Emergency lock: all vaults locked, {} search documents removed", documents_removed);
+    Ok(EmergencyLockResult { documents_removed })
+}
+
+/// Delete all brainbox data and quit. Nothing is recoverable afterwards, so the exact phrase is required.
+pub fn wipe_all_data(app: &AppHandle, confirmation_phrase: &str) -> Result<(), BrainboxError> {
+    if confirmation_phrase.trim() != WIPE_CONFIRMATION {
+        return Err(BrainboxError::InvalidInput(format!(
+            "Type \"{}\" to confirm deleting all data",
+            WIPE_CONFIRMATION
+        )));
+    }
+    tracing::warn!("Wiping all brainbox data");
+    keystore::clear();
+    hide_windows(app);
+    crate::search::close_search_service();
+
+    let mut failed = Vec::new();
+    for path in crate::data_dir::data_paths()? {
+        let result = if path.is_dir() {
+            std::fs::remove_dir_all(&path)
+        } else if path.exists() {
+            std::fs::remove_file(&path)
+        } else {
+            continue;
+        };
+        if let Err(e) = result {
+            failed.push(format!("{}: {}", path.display(), e));
+        }
+    }
+    if !failed.is_empty() {
+        return Err(BrainboxError::Other(format!("Some data could not be deleted: {}", failed.join("; "))));
+    }
+    app.exit(0);
+    Ok(())
+}
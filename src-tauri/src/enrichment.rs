@@ -0,0 +1,292 @@
+// enrichment.rs - Background URL metadata enrichment for brainbox
+// URL items are queued (e.g. after a bookmark import) and fetched one at a time at a configurable rate,
+// so hundreds of imports don't hammer sites or block the UI. Results are stored and applied to the items.
+
+use lazy_static::lazy_static;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::thread::Thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::error::BrainboxError;
+use crate::settings;
+use crate::vault::VaultItem;
+
+const RATE_KEY: &str = "enrichment.requests_per_minute";
+pub const DEFAULT_REQUESTS_PER_MINUTE: u32 = 20;
+const MAX_REQUESTS_PER_MINUTE: u32 = 120;
+/// Failed fetches are retried on later passes until this many attempts
+const MAX_ATTEMPTS: i64 = 3;
+/// How long the worker sleeps when the queue is empty (enqueueing wakes it early)
+const IDLE_POLL: Duration = Duration::from_secs(60);
+
+lazy_static! {
+    static ref WORKER: Mutex<Option<Thread>> = Mutex::new(None);
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EnrichmentRequest {
+    pub item_id: i64,
+    pub url: String,
+}
+
+/// Stored result of enriching one item
+#[derive(Debug, Serialize, Clone)]
+pub struct EnrichedMetadata {
+    pub item_id: i64,
+    pub url: String,
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub favicon: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub site_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enriched_at: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct EnrichmentStatus {
+    pub pending: i64,
+    pub done: i64,
+    pub failed: i64,
+}
+
+#[derive(Serialize, Clone)]
+#[allow(non_snake_case)]
+struct EnrichmentProgressEvent {
+    itemId: i64,
+    status: String,
+    pending: i64,
+    done: i64,
+    failed: i64,
+}
+
+pub fn create_table(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS url_enrichment (
+            item_id INTEGER PRIMARY KEY REFERENCES vault_items(id) ON DELETE CASCADE,
+            url TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'pending',
+            attempts INTEGER NOT NULL DEFAULT 0,
+            last_error TEXT,
+            title TEXT,
+            description TEXT,
+            image TEXT,
+            favicon TEXT,
+            site_name TEXT,
+            queued_at TEXT NOT NULL,
+            enriched_at TEXT
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_url_enrichment_status ON url_enrichment(status, queued_at)",
+        [],
+    )?;
+    Ok(())
+}
+
+pub fn get_rate(conn: &Connection) -> Result<u32, BrainboxError> {
+    Ok(settings::get_parsed(conn, RATE_KEY)?
+        .filter(|rate| *rate > 0)
+        .unwrap_or(DEFAULT_REQUESTS_PER_MINUTE))
+}
+
+pub fn set_rate(conn: &Connection, requests_per_minute: u32) -> Result<(), BrainboxError> {
+    if requests_per_minute == 0 || requests_per_minute > MAX_REQUESTS_PER_MINUTE {
+        return Err(BrainboxError::InvalidInput(format!(
+            "Rate must be between 1 and {} requests per minute",
+            MAX_REQUESTS_PER_MINUTE
+        )));
+    }
+    settings::set_value(conn, RATE_KEY, requests_per_minute)?;
+    Ok(())
+}
+
+/// Queue items for enrichment (re-queueing ones already processed); returns how many were queued
+pub fn enqueue(conn: &Connection, requests: &[EnrichmentRequest]) -> Result<usize, BrainboxError> {
+    create_table(conn)?;
+    let now = chrono::Utc::now().to_rfc3339();
+    let mut queued = 0;
+    conn.execute("BEGIN IMMEDIATE", [])?;
+    for request in requests {
+        let url = request.url.trim();
+        if !(url.starts_with("http://") || url.starts_with("https://")) {
+            continue;
+        }
+        let result = conn.execute(
+            "INSERT INTO url_enrichment (item_id, url, status, attempts, queued_at)
+             SELECT ?1, ?2, 'pending', 0, ?3 WHERE EXISTS (SELECT 1 FROM vault_items WHERE id = ?1)
+             ON CONFLICT(item_id) DO UPDATE SET url = excluded.url, status = 'pending', attempts = 0,
+                last_error = NULL, queued_at = excluded.queued_at",
+            params![request.item_id, url, now],
+        );
+        match result {
+            Ok(n) => queued += n,
+            Err(e) => {
+                let _ = conn.execute("ROLLBACK", []);
+                return Err(e.into());
+            }
+        }
+    }
+    conn.execute("COMMIT", [])?;
+    wake();
+    Ok(queued)
+}
+
+pub fn status(conn: &Connection) -> Result<EnrichmentStatus, BrainboxError> {
+    create_table(conn)?;
+    let mut stmt = conn.prepare("SELECT status, COUNT(*) FROM url_enrichment GROUP BY status")?;
+    let mut rows = stmt.query([])?;
+    let mut status = EnrichmentStatus::default();
+    while let Some(row) = rows.next()? {
+        let count: i64 = row.get(1)?;
+        match row.get::<_, String>(0)?.as_str() {
+            "pending" => status.pending = count,
+            "done" => status.done = count,
+            "failed" => status.failed = count,
+            _ => {}
+        }
+    }
+    Ok(status)
+}
+
+pub fn get(conn: &Connection, item_id: i64) -> Result<Option<EnrichedMetadata>, BrainboxError> {
+    create_table(conn)?;
+    Ok(conn
+        .query_row(
+            "SELECT item_id, url, status, title, description, image, favicon, site_name, last_error, enriched_at
+             FROM url_enrichment WHERE item_id = ?1",
+            [item_id],
+            |row| {
+                Ok(EnrichedMetadata {
+                    item_id: row.get(0)?,
+                    url: row.get(1)?,
+                    status: row.get(2)?,
+                    title: row.get(3)?,
+                    description: row.get(4)?,
+                    image: row.get(5)?,
+                    favicon: row.get(6)?,
+                    site_name: row.get(7)?,
+                    last_error: row.get(8)?,
+                    enriched_at: row.get(9)?,
+                })
+            },
+        )
+        .optional()?)
+}
+
+/// Fill in what the item is missing: a title that is empty or just the URL, and an image
+fn apply_to_item(conn: &Connection, item_id: i64, url: &str, metadata: &crate::UrlMetadata) -> Result<(), BrainboxError> {
+    let item = VaultItem::get_by_id(conn, item_id)?;
+    if let Some(title) = metadata.title.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+        let current = item.title.trim();
+        if current.is_empty() || current == url || current == metadata.final_url {
+            VaultItem::update_title(conn, item_id, title)?;
+        }
+    }
+    if item.image.is_none() {
+        if let Some(image) = &metadata.image {
+            VaultItem::update_image(conn, item_id, Some(image))?;
+        }
+    }
+    Ok(())
+}
+
+/// Enrich the oldest pending item. Returns false when the queue is empty.
+fn process_next(app: &AppHandle) -> Result<bool, BrainboxError> {
+    let conn = crate::db::open()?;
+    create_table(&conn)?;
+    let next: Option<(i64, String, i64)> = conn
+        .query_row(
+            "SELECT item_id, url, attempts FROM url_enrichment WHERE status = 'pending' ORDER BY queued_at, item_id LIMIT 1",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()?;
+    let Some((item_id, url, attempts)) = next else { return Ok(false) };
+
+    let now = chrono::Utc::now().to_rfc3339();
+    let outcome = match crate::fetch_metadata(&url) {
+        Ok(metadata) => {
+            conn.execute(
+                "UPDATE url_enrichment SET status = 'done', attempts = ?1, last_error = NULL, title = ?2, description = ?3,
+                    image = ?4, favicon = ?5, site_name = ?6, enriched_at = ?7 WHERE item_id = ?8",
+                params![
+                    attempts + 1,
+                    metadata.title,
+                    metadata.description,
+                    metadata.image,
+                    metadata.favicon,
+                    metadata.site_name,
+                    now,
+                    item_id
+                ],
+            )?;
+            if let Err(e) = apply_to_item(&conn, item_id, &url, &metadata) {
+                tracing::warn!("Failed to apply metadata to item {}: {}", item_id, e);
+            }
+            "done"
+        }
+        Err(e) => {
+            let next_status = if attempts + 1 >= MAX_ATTEMPTS { "failed" } else { "pending" };
+            tracing::debug!("Enrichment of item {} failed (attempt {}): {}", item_id, attempts + 1, e);
+            // Move retries to the back of the queue so one bad site doesn't stall the rest
+            conn.execute(
+                "UPDATE url_enrichment SET status = ?1, attempts = ?2, last_error = ?3, queued_at = ?4 WHERE item_id = ?5",
+                params![next_status, attempts + 1, e.to_string(), now, item_id],
+            )?;
+            next_status
+        }
+    };
+
+    let counts = status(&conn)?;
+    let _ = app.emit(
+        "enrichment-progress",
+        EnrichmentProgressEvent {
+            itemId: item_id,
+            status: outcome.to_string(),
+            pending: counts.pending,
+            done: counts.done,
+            failed: counts.failed,
+        },
+    );
+    Ok(true)
+}
+
+/// Start the background worker; it sleeps between requests according to the configured rate
+pub fn spawn_worker(app: AppHandle) {
+    let handle = std::thread::spawn(move || loop {
+        match process_next(&app) {
+            Ok(true) => {
+                let rate = crate::db::open()
+                    .and_then(|conn| get_rate(&conn))
+                    .unwrap_or(DEFAULT_REQUESTS_PER_MINUTE);
+                std::thread::sleep(Duration::from_millis(60_000 / rate as u64));
+            }
+            Ok(false) => std::thread::park_timeout(IDLE_POLL),
+            Err(e) => {
+                tracing::error!("Enrichment queue failed: {}", e);
+                std::thread::park_timeout(IDLE_POLL);
+            }
+        }
+    });
+    *WORKER.lock().unwrap() = Some(handle.thread().clone());
+}
+
+/// Wake the worker after new items were queued
+fn wake() {
+    if let Some(worker) = WORKER.lock().unwrap().as_ref() {
+        worker.unpark();
+    }
+}
@@ -0,0 +1,416 @@
+// entities.rs - People, companies and projects mentioned in notes for brainbox
+// An extraction pass finds what each note mentions and records it, so the frontend can build a page per entity
+// listing every note that names it. Email addresses, linked sites and @mentions are found with patterns; people,
+// companies and projects need a language model, so they are only found when the caller names a local Ollama model.
+// Entity names come from vault content and are sealed with the vault key; a keyed hash of each name lets
+// mentions of the same entity be grouped without decrypting.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::envelope;
+use crate::error::BrainboxError;
+use crate::http;
+use crate::ollama_options::{GenerationOptions, RequestOptions};
+use crate::token_budget;
+use crate::vault::{self, VaultItem};
+
+pub const KINDS: &[&str] = &["person", "company", "project", "email", "domain", "mention"];
+
+pub fn is_valid_kind(kind: &str) -> bool {
+    KINDS.contains(&kind)
+}
+
+/// Longest entity name kept; anything longer is a phrase the model got wrong
+const MAX_NAME_CHARS: usize = 80;
+/// Most entities of each kind taken from the model per note
+const MAX_MODEL_ENTITIES: usize = 25;
+const EXTRACT_TIMEOUT: Duration = Duration::from_secs(120);
+
+const PROMPT: &str = "List the people, companies and projects this note mentions by name. Use each name as written, \
+without titles or duplicates, and leave a list empty when there are none. \
+Reply with JSON only: {\"people\": [\"...\"], \"companies\": [\"...\"], \"projects\": [\"...\"]}\n\nNote:\n";
+
+lazy_static! {
+    static ref EMAIL: Regex = Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}").unwrap();
+    static ref URL_HOST: Regex = Regex::new(r#"https?://(?:[^\s/@<>"']+@)?([A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)+)"#).unwrap();
+    static ref MENTION: Regex = Regex::new(r"(?:^|[^\w@.])@([A-Za-z0-9_][A-Za-z0-9_-]{0,38})").unwrap();
+}
+
+/// A local Ollama model to find people, companies and projects with
+#[derive(Debug, Clone)]
+pub struct Model {
+    pub base_url: String,
+    pub model: String,
+    pub options: GenerationOptions,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Entity {
+    pub id: i64,
+    pub kind: String,
+    pub name: String,
+    /// Notes that mention it
+    pub item_count: i64,
+    /// Mentions across those notes
+    pub mention_count: i64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct EntityItem {
+    pub item_id: i64,
+    pub title: String,
+    pub item_type: Option<String>,
+    pub updated_at: String,
+    pub mentions: i64,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct ExtractionReport {
+    /// Notes read this pass
+    pub scanned: usize,
+    /// Notes unchanged since they were last read the same way
+    pub unchanged: usize,
+    /// Entities the vault has afterwards
+    pub entities: usize,
+}
+
+#[derive(Serialize)]
+struct GenerateRequest<'a> {
+    model: &'a str,
+    prompt: String,
+    stream: bool,
+    format: &'a str,
+    #[serde(flatten)]
+    options: RequestOptions,
+}
+
+#[derive(Deserialize)]
+struct GenerateResponse {
+    response: String,
+}
+
+#[derive(Deserialize, Default)]
+struct ModelReply {
+    #[serde(default)]
+    people: Vec<String>,
+    #[serde(default)]
+    companies: Vec<String>,
+    #[serde(default)]
+    projects: Vec<String>,
+}
+
+pub fn create_tables(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS entities (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            vault_id INTEGER NOT NULL REFERENCES vaults(id) ON DELETE CASCADE,
+            kind TEXT NOT NULL,
+            name_hash TEXT NOT NULL,
+            name BLOB NOT NULL,
+            created_at TEXT NOT NULL,
+            UNIQUE(vault_id, kind, name_hash)
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS entity_mentions (
+            entity_id INTEGER NOT NULL REFERENCES entities(id) ON DELETE CASCADE,
+            item_id INTEGER NOT NULL REFERENCES vault_items(id) ON DELETE CASCADE,
+            count INTEGER NOT NULL,
+            PRIMARY KEY (entity_id, item_id)
+        ) WITHOUT ROWID",
+        [],
+    )?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_entity_mentions_item ON entity_mentions(item_id)", [])?;
+    // What each note was last read with, so unchanged notes aren't sent to the model again
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS entity_scans (
+            item_id INTEGER PRIMARY KEY REFERENCES vault_items(id) ON DELETE CASCADE,
+            content_hash TEXT NOT NULL,
+            model TEXT,
+            scanned_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Collapse whitespace and cut overlong names; None for names not worth keeping
+fn clean_name(name: &str) -> Option<String> {
+    let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+    let name = name.trim_matches(|c: char| c.is_ascii_punctuation() && c != '&' && c != '+').to_string();
+    (name.chars().count() >= 2 && name.chars().count() <= MAX_NAME_CHARS).then_some(name)
+}
+
+/// A kind, a name as found and the times it occurs
+type Found = (&'static str, String, i64);
+
+/// Every pattern match in `text`, one per occurrence
+fn pattern_entities(text: &str) -> Vec<Found> {
+    let mut found = Vec::new();
+    for m in EMAIL.find_iter(text) {
+        found.push(("email", m.as_str().to_lowercase(), 1));
+    }
+    for caps in URL_HOST.captures_iter(text) {
+        let host = caps[1].to_lowercase();
+        found.push(("domain", host.strip_prefix("www.").unwrap_or(&host).to_string(), 1));
+    }
+    for caps in MENTION.captures_iter(text) {
+        let handle = caps[1].trim_end_matches(['-', '_']);
+        if !handle.is_empty() {
+            found.push(("mention", format!("@{}", handle.to_lowercase()), 1));
+        }
+    }
+    found
+}
+
+/// Ask the model for the people, companies and projects in `text`
+fn model_entities(model: &Model, text: &str) -> Result<Vec<Found>, BrainboxError> {
+    // Long notes are cut to the model's window, which only misses names near the end
+    let budget = token_budget::prompt_budget(&model.model, model.options.num_ctx)
+        .saturating_sub(token_budget::count(PROMPT, &model.model));
+    let prompt = token_budget::truncate(text, &model.model, budget);
+    let body = GenerateRequest {
+        model: &model.model,
+        prompt: format!("{}{}", PROMPT, prompt),
+        stream: false,
+        format: "json",
+        options: model.options.request(),
+    };
+    let resp = http::send(
+        http::client()
+            .post(format!("{}/api/generate", model.base_url))
+            .json(&body)
+            .timeout(EXTRACT_TIMEOUT),
+    )?;
+    if !resp.status().is_success() {
+        return Err(BrainboxError::Network(format!("Ollama returned status {}", resp.status())));
+    }
+    let response: GenerateResponse = resp.json()?;
+    // A reply that isn't the JSON asked for finds nothing rather than failing the pass
+    let reply = serde_json::from_str::<ModelReply>(&response.response).unwrap_or_default();
+    // The model doesn't count; occurrences in the note rank entities, and a name it reworded counts once
+    let text_lower = text.to_lowercase();
+    let mut found = Vec::new();
+    for (kind, names) in [("person", reply.people), ("company", reply.companies), ("project", reply.projects)] {
+        for name in names.into_iter().take(MAX_MODEL_ENTITIES) {
+            let count = (text_lower.matches(&name.trim().to_lowercase()).count() as i64).max(1);
+            found.push((kind, name, count));
+        }
+    }
+    Ok(found)
+}
+
+fn name_hash(key: &[u8; 32], kind: &str, name: &str) -> String {
+    vault::content_hash(key, &format!("entity:{}:{}", kind, name.to_lowercase()))
+}
+
+/// The entity's id, adding it to the vault if it's new
+fn ensure_entity(conn: &Connection, vault_id: i64, key: &[u8; 32], kind: &str, name: &str) -> Result<i64, BrainboxError> {
+    let hash = name_hash(key, kind, name);
+    let existing: Option<i64> = conn
+        .query_row(
+            "SELECT id FROM entities WHERE vault_id = ?1 AND kind = ?2 AND name_hash = ?3",
+            params![vault_id, kind, hash],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if let Some(id) = existing {
+        return Ok(id);
+    }
+    conn.execute(
+        "INSERT INTO entities (vault_id, kind, name_hash, name, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![vault_id, kind, hash, envelope::seal(key, name.as_bytes())?, chrono::Utc::now().to_rfc3339()],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Replace what a note is recorded as mentioning. Returns how many distinct entities it mentions.
+fn record(conn: &Connection, item: &VaultItem, key: &[u8; 32], found: Vec<Found>) -> Result<usize, BrainboxError> {
+    // Keyed by lowercased name so "Acme" and "ACME" are one entity, named as first seen
+    let mut counts: HashMap<(&str, String), (String, i64)> = HashMap::new();
+    for (kind, name, count) in found {
+        let Some(name) = clean_name(&name) else { continue };
+        counts.entry((kind, name.to_lowercase())).or_insert((name, 0)).1 += count;
+    }
+    conn.execute("DELETE FROM entity_mentions WHERE item_id = ?1", [item.id])?;
+    for ((kind, _), (name, count)) in &counts {
+        let entity_id = ensure_entity(conn, item.vault_id, key, kind, name)?;
+        conn.execute(
+            "INSERT INTO entity_mentions (entity_id, item_id, count) VALUES (?1, ?2, ?3)",
+            params![entity_id, item.id, count],
+        )?;
+    }
+    Ok(counts.len())
+}
+
+/// Drop entities no note mentions any more
+fn remove_orphans(conn: &Connection, vault_id: i64) -> Result<(), BrainboxError> {
+    conn.execute(
+        "DELETE FROM entities WHERE vault_id = ?1 AND id NOT IN (SELECT entity_id FROM entity_mentions)",
+        [vault_id],
+    )?;
+    Ok(())
+}
+
+/// Read one note. Returns None when it was already read the same way since it last changed and `force` is off;
+/// a pattern-only pass doesn't replace what a model found in an unchanged note.
+fn extract_one(
+    conn: &Connection,
+    item: &VaultItem,
+    key: &[u8; 32],
+    model: Option<&Model>,
+    force: bool,
+) -> Result<Option<usize>, BrainboxError> {
+    let content = String::from_utf8(envelope::open(key, &item.content)?)
+        .map_err(|_| BrainboxError::Crypto("Invalid UTF-8".to_string()))?;
+    let hash = vault::content_hash(key, &format!("{}\n{}", item.title, content));
+    let last: Option<(String, Option<String>)> = conn
+        .query_row("SELECT content_hash, model FROM entity_scans WHERE item_id = ?1", [item.id], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .optional()?;
+    if let Some((last_hash, last_model)) = &last {
+        let same_way = match model {
+            Some(model) => last_model.as_deref() == Some(model.model.as_str()),
+            None => true,
+        };
+        if !force && *last_hash == hash && same_way {
+            return Ok(None);
+        }
+    }
+
+    let text = format!("{}\n{}", item.title, content);
+    let mut found = pattern_entities(&text);
+    if let Some(model) = model {
+        found.extend(model_entities(model, &text)?);
+    }
+    let count = record(conn, item, key, found)?;
+    conn.execute(
+        "INSERT OR REPLACE INTO entity_scans (item_id, content_hash, model, scanned_at) VALUES (?1, ?2, ?3, ?4)",
+        params![item.id, hash, model.map(|m| m.model.as_str()), chrono::Utc::now().to_rfc3339()],
+    )?;
+    Ok(Some(count))
+}
+
+/// Find the entities one note mentions; returns how many distinct ones it has
+pub fn extract_item(conn: &Connection, item_id: i64, key: &[u8; 32], model: Option<&Model>) -> Result<usize, BrainboxError> {
+    create_tables(conn)?;
+    let item = VaultItem::get_by_id(conn, item_id)?;
+    let count = extract_one(conn, &item, key, model, true)?.unwrap_or_default();
+    remove_orphans(conn, item.vault_id)?;
+    Ok(count)
+}
+
+/// Find the entities mentioned across a vault, skipping notes that haven't changed since they were last read
+pub fn extract_vault(
+    conn: &Connection,
+    vault_id: i64,
+    key: &[u8; 32],
+    model: Option<&Model>,
+    force: bool,
+) -> Result<ExtractionReport, BrainboxError> {
+    VaultItem::create_table(conn)?;
+    create_tables(conn)?;
+    let mut report = ExtractionReport::default();
+    for item in VaultItem::list_by_vault(conn, vault_id)? {
+        match extract_one(conn, &item, key, model, force) {
+            Ok(Some(_)) => report.scanned += 1,
+            Ok(None) => report.unchanged += 1,
+            // An unreachable model fails every note, so stop; skip notes that can't be read
+            Err(e @ BrainboxError::Network(_)) => return Err(e),
+            Err(e) => tracing::warn!("Skipping entity extraction for item {}: {}", item.id, e),
+        }
+    }
+    remove_orphans(conn, vault_id)?;
+    report.entities = conn.query_row("SELECT COUNT(*) FROM entities WHERE vault_id = ?1", [vault_id], |row| row.get::<_, i64>(0))? as usize;
+    Ok(report)
+}
+
+/// A vault's entities, most mentioned first. Notes in the trash don't count.
+pub fn list(conn: &Connection, vault_id: i64, key: &[u8; 32], kind: Option<&str>) -> Result<Vec<Entity>, BrainboxError> {
+    create_tables(conn)?;
+    let mut stmt = conn.prepare(
+        "SELECT e.id, e.kind, e.name, COUNT(m.item_id), SUM(m.count) FROM entities e
+         JOIN entity_mentions m ON m.entity_id = e.id
+         JOIN vault_items i ON i.id = m.item_id AND i.deleted_at IS NULL
+         WHERE e.vault_id = ?1 AND (?2 IS NULL OR e.kind = ?2)
+         GROUP BY e.id ORDER BY COUNT(m.item_id) DESC, SUM(m.count) DESC, e.id",
+    )?;
+    let rows = stmt
+        .query_map(params![vault_id, kind], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, Vec<u8>>(2)?, row.get::<_, i64>(3)?, row.get::<_, i64>(4)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let mut entities = Vec::with_capacity(rows.len());
+    for (id, kind, name, item_count, mention_count) in rows {
+        let name = String::from_utf8(envelope::open(key, &name)?).map_err(|_| BrainboxError::Crypto("Invalid UTF-8".to_string()))?;
+        entities.push(Entity { id, kind, name, item_count, mention_count });
+    }
+    Ok(entities)
+}
+
+/// The vault an entity belongs to, so callers can check its key
+pub fn vault_id(conn: &Connection, entity_id: i64) -> Result<i64, BrainboxError> {
+    create_tables(conn)?;
+    conn.query_row("SELECT vault_id FROM entities WHERE id = ?1", [entity_id], |row| row.get(0))
+        .optional()?
+        .ok_or_else(|| BrainboxError::NotFound(format!("Entity {} not found", entity_id)))
+}
+
+/// Notes that mention an entity, those mentioning it most first
+pub fn items_for(conn: &Connection, entity_id: i64) -> Result<Vec<EntityItem>, BrainboxError> {
+    create_tables(conn)?;
+    let mut stmt = conn.prepare(
+        "SELECT i.id, i.title, i.item_type, i.updated_at, m.count FROM entity_mentions m
+         JOIN vault_items i ON i.id = m.item_id
+         WHERE m.entity_id = ?1 AND i.deleted_at IS NULL
+         ORDER BY m.count DESC, i.updated_at DESC",
+    )?;
+    let items = stmt
+        .query_map([entity_id], |row| {
+            Ok(EntityItem {
+                item_id: row.get(0)?,
+                title: row.get(1)?,
+                item_type: row.get(2)?,
+                updated_at: row.get(3)?,
+                mentions: row.get(4)?,
+            })
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(items)
+}
+
+/// Forget what a note mentions, e.g. when it moves to another vault; the next pass reads it again
+pub fn forget_item(conn: &Connection, item_id: i64) -> Result<(), BrainboxError> {
+    create_tables(conn)?;
+    let vault_id: Option<i64> = conn
+        .query_row(
+            "SELECT DISTINCT e.vault_id FROM entity_mentions m JOIN entities e ON e.id = m.entity_id WHERE m.item_id = ?1",
+            [item_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    conn.execute("DELETE FROM entity_mentions WHERE item_id = ?1", [item_id])?;
+    conn.execute("DELETE FROM entity_scans WHERE item_id = ?1", [item_id])?;
+    if let Some(vault_id) = vault_id {
+        remove_orphans(conn, vault_id)?;
+    }
+    Ok(())
+}
+
+/// Forget a vault's entities, e.g. when its key changes and the name hashes with it
+pub fn forget_vault(conn: &Connection, vault_id: i64) -> Result<(), BrainboxError> {
+    create_tables(conn)?;
+    conn.execute("DELETE FROM entities WHERE vault_id = ?1", [vault_id])?;
+    conn.execute(
+        "DELETE FROM entity_scans WHERE item_id IN (SELECT id FROM vault_items WHERE vault_id = ?1)",
+        [vault_id],
+    )?;
+    Ok(())
+}
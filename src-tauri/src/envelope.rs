@@ -0,0 +1,177 @@
+// envelope.rs - Envelope encryption of vault items for brainbox
+// Items used to be encrypted directly with the password-derived key, so a password change re-encrypted every item.
+// Each vault now has a random master key wrapped by the password key, and every item is sealed with its own random
+// data key wrapped by that master key. Changing the password only rewraps the master key.
+
+use chacha20poly1305::{aead::{Aead, KeyInit}, Key, XChaCha20Poly1305, XNonce};
+use rand::{rngs::OsRng, RngCore};
+use rusqlite::{params, Connection, OptionalExtension};
+use zeroize::Zeroizing;
+
+use crate::error::BrainboxError;
+use crate::keystore::VaultKey;
+
+/// Prefix of enveloped ciphertexts; anything else is the legacy nonce + ciphertext under the key itself
+const MAGIC: &[u8; 4] = b"BBE1";
+const NONCE_LEN: usize = 24;
+/// A wrapped 32-byte key: nonce, ciphertext and 16-byte tag
+const WRAPPED_KEY_LEN: usize = NONCE_LEN + 32 + 16;
+
+pub fn create_table(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS vault_keys (
+            vault_id INTEGER PRIMARY KEY REFERENCES vaults(id) ON DELETE CASCADE,
+            wrapped_key BLOB NOT NULL,
+            created_at TEXT NOT NULL,
+            rotated_at TEXT
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+fn random_key() -> VaultKey {
+    let mut key = Zeroizing::new([0u8; 32]);
+    OsRng.fill_bytes(&mut *key);
+    key
+}
+
+/// XChaCha20-Poly1305 with the random nonce prefixed
+fn encrypt(key: &[u8; 32], plaintext: &[u8]) -> Result<Vec<u8>, BrainboxError> {
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(key));
+    let mut nonce_bytes = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce_bytes);
+    let ciphertext = cipher
+        .encrypt(XNonce::from_slice(&nonce_bytes), plaintext)
+        .map_err(|_| BrainboxError::Crypto("Encryption failed".to_string()))?;
+    let mut out = nonce_bytes.to_vec();
+    out.extend(ciphertext);
+    Ok(out)
+}
+
+fn decrypt(key: &[u8; 32], data: &[u8]) -> Result<Vec<u8>, BrainboxError> {
+    if data.len() < NONCE_LEN {
+        return Err(BrainboxError::Crypto("Invalid ciphertext".to_string()));
+    }
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(key));
+    cipher
+        .decrypt(XNonce::from_slice(&data[..NONCE_LEN]), &data[NONCE_LEN..])
+        .map_err(|_| BrainboxError::InvalidKey)
+}
+
+fn unwrap_key(key: &[u8; 32], wrapped: &[u8]) -> Result<VaultKey, BrainboxError> {
+    let bytes = Zeroizing::new(decrypt(key, wrapped)?);
+    let unwrapped: [u8; 32] = bytes
+        .as_slice()
+        .try_into()
+        .map_err(|_| BrainboxError::Crypto("Invalid wrapped key".to_string()))?;
+    Ok(Zeroizing::new(unwrapped))
+}
+
+/// Encrypt under a fresh data key, stored alongside the ciphertext wrapped by `key`
+pub fn seal(key: &[u8; 32], plaintext: &[u8]) -> Result<Vec<u8>, BrainboxError> {
+    let data_key = random_key();
+    let mut out = MAGIC.to_vec();
+    out.extend(encrypt(key, &*data_key)?);
+    out.extend(encrypt(&data_key, plaintext)?);
+    Ok(out)
+}
+
+/// Decrypt a sealed value, or a legacy one encrypted directly with `key`
+pub fn open(key: &[u8; 32], data: &[u8]) -> Result<Vec<u8>, BrainboxError> {
+    if let Some(rest) = data.strip_prefix(MAGIC.as_slice()) {
+        if rest.len() >= WRAPPED_KEY_LEN + NONCE_LEN {
+            if let Ok(data_key) = unwrap_key(key, &rest[..WRAPPED_KEY_LEN]) {
+                return decrypt(&data_key, &rest[WRAPPED_KEY_LEN..]);
+            }
+        }
+    }
+    // A legacy nonce can start with the magic bytes by chance, so fall through rather than fail
+    decrypt(key, data)
+}
+
+fn wrapped_master_key(conn: &Connection, vault_id: i64) -> Result<Option<Vec<u8>>, BrainboxError> {
+    create_table(conn)?;
+    Ok(conn
+        .query_row("SELECT wrapped_key FROM vault_keys WHERE vault_id = ?1", [vault_id], |row| row.get(0))
+        .optional()?)
+}
+
+pub fn has_master_key(conn: &Connection, vault_id: i64) -> Result<bool, BrainboxError> {
+    Ok(wrapped_master_key(conn, vault_id)?.is_some())
+}
+
+/// Key a vault's items are sealed with: its master key unwrapped by the password key `kek`,
+/// or `kek` itself for vaults that predate envelope encryption
+pub fn content_key(conn: &Connection, vault_id: i64, kek: &[u8; 32]) -> Result<VaultKey, BrainboxError> {
+    match wrapped_master_key(conn, vault_id)? {
+        Some(wrapped) => unwrap_key(kek, &wrapped),
+        None => Ok(Zeroizing::new(*kek)),
+    }
+}
+
+/// Give a vault a new master key wrapped by `kek`, returning it for sealing the vault's items
+pub fn create_master_key(conn: &Connection, vault_id: i64, kek: &[u8; 32]) -> Result<VaultKey, BrainboxError> {
+    create_table(conn)?;
+    let master_key = random_key();
+    conn.execute(
+        "INSERT OR REPLACE INTO vault_keys (vault_id, wrapped_key, created_at, rotated_at) VALUES (?1, ?2, ?3, NULL)",
+        params![vault_id, encrypt(kek, &*master_key)?, chrono::Utc::now().to_rfc3339()],
+    )?;
+    Ok(master_key)
+}
+
+/// Wrap a vault's master key under a new password key; item content is untouched
+pub fn rewrap(conn: &Connection, vault_id: i64, old_kek: &[u8; 32], new_kek: &[u8; 32]) -> Result<(), BrainboxError> {
+    let wrapped = wrapped_master_key(conn, vault_id)?
+        .ok_or_else(|| BrainboxError::NotFound(format!("Vault {} has no master key", vault_id)))?;
+    let master_key = unwrap_key(old_kek, &wrapped)?;
+    conn.execute(
+        "UPDATE vault_keys SET wrapped_key = ?1, rotated_at = ?2 WHERE vault_id = ?3",
+        params![encrypt(new_kek, &*master_key)?, chrono::Utc::now().to_rfc3339(), vault_id],
+    )?;
+    Ok(())
+}
+
+/// One-time move of a legacy vault to envelope encryption: every item's content and preview, including trashed
+/// items, is re-sealed under a new master key. Meant to run inside the caller's transaction. Returns the item count.
+pub fn upgrade(
+    conn: &Connection,
+    vault_id: i64,
+    kek: &[u8; 32],
+    mut progress: impl FnMut(usize, usize),
+) -> Result<usize, BrainboxError> {
+    if has_master_key(conn, vault_id)? {
+        return Ok(0);
+    }
+    let mut stmt = conn.prepare("SELECT id, content, preview FROM vault_items WHERE vault_id = ?1")?;
+    let items: Vec<(i64, Vec<u8>, Option<Vec<u8>>)> = stmt
+        .query_map([vault_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+    let master_key = create_master_key(conn, vault_id, kek)?;
+    let total = items.len();
+    for (done, (id, content, preview)) in items.into_iter().enumerate() {
+        if done % 25 == 0 {
+            progress(done, total);
+        }
+        let content = seal(&master_key, &open(kek, &content)?)?;
+        // A preview that no longer decrypts is dropped; it is regenerated on the next read
+        let preview = preview
+            .and_then(|p| open(kek, &p).ok())
+            .map(|p| seal(&master_key, &p))
+            .transpose()?;
+        conn.execute(
+            "UPDATE vault_items SET content = ?1, preview = ?2 WHERE id = ?3",
+            params![content, preview, id],
+        )?;
+    }
+    crate::annotations::reseal_vault(conn, vault_id, kek, &master_key)?;
+    crate::exif::reseal_vault(conn, vault_id, kek, &master_key)?;
+    // Entity names are hashed with the content key and image analyses sealed with it; the next passes redo them
+    crate::entities::forget_vault(conn, vault_id)?;
+    crate::image_labels::forget_vault(conn, vault_id)?;
+    progress(total, total);
+    // Content hashes are keyed by the old key; they are recomputed when duplicates are next looked for
+    let _ = conn.execute("UPDATE vault_items SET content_hash = NULL WHERE vault_id = ?1", [vault_id]);
+    Ok(total)
+}
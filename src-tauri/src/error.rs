@@ -0,0 +1,162 @@
+// error.rs - Error type returned by brainbox commands
+// Serialized to the frontend as { code, message, details?, retryable } so the UI can react to specific failures.
+// The message is translated into the active locale; Display, used for logs, stays in English.
+
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+use crate::i18n::{tr, trf};
+
+#[derive(Debug, thiserror::Error)]
+pub enum BrainboxError {
+    /// Wrong vault password or key
+    #[error("Invalid password or key")]
+    InvalidKey,
+    /// Too many wrong passwords; holds the seconds until the vault accepts another attempt
+    #[error("Too many failed attempts, try again in {0} seconds")]
+    VaultLocked(i64),
+    #[error("{0}")]
+    NotFound(String),
+    /// Bad arguments from the caller
+    #[error("{0}")]
+    InvalidInput(String),
+    /// SQLite is busy or locked by another connection; safe to retry
+    #[error("Database is busy, please try again")]
+    DatabaseLocked(#[source] rusqlite::Error),
+    #[error("Database error: {0}")]
+    Database(#[source] rusqlite::Error),
+    #[error("Network error: {0}")]
+    Network(String),
+    #[error("File system error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Encryption error: {0}")]
+    Crypto(String),
+    #[error("Search error: {0}")]
+    Search(String),
+    #[error("Sync error: {0}")]
+    Sync(String),
+    /// Checking, downloading or verifying an app update failed
+    #[error("Update error: {0}")]
+    Update(String),
+    /// The user cancelled a long-running operation; anything it wrote was rolled back
+    #[error("Operation cancelled")]
+    Cancelled,
+    #[error("{0}")]
+    Other(String),
+}
+
+impl BrainboxError {
+    /// Stable machine-readable code for the frontend
+    pub fn code(&self) -> &'static str {
+        match self {
+            BrainboxError::InvalidKey => "invalid_key",
+            BrainboxError::VaultLocked(_) => "vault_locked",
+            BrainboxError::NotFound(_) => "not_found",
+            BrainboxError::InvalidInput(_) => "invalid_input",
+            BrainboxError::DatabaseLocked(_) => "database_locked",
+            BrainboxError::Database(_) => "database",
+            BrainboxError::Network(_) => "network",
+            BrainboxError::Io(_) => "io",
+            BrainboxError::Crypto(_) => "crypto",
+            BrainboxError::Search(_) => "search",
+            BrainboxError::Sync(_) => "sync",
+            BrainboxError::Update(_) => "update",
+            BrainboxError::Cancelled => "cancelled",
+            BrainboxError::Other(_) => "internal",
+        }
+    }
+
+    /// Whether retrying the same call may succeed
+    pub fn retryable(&self) -> bool {
+        matches!(self, BrainboxError::DatabaseLocked(_) | BrainboxError::Network(_))
+    }
+
+    /// The message in the active locale
+    pub fn localized_message(&self) -> String {
+        match self {
+            BrainboxError::InvalidKey => tr("Invalid password or key"),
+            BrainboxError::VaultLocked(seconds) => {
+                trf("Too many failed attempts, try again in {} seconds", &[&seconds.to_string()])
+            }
+            BrainboxError::NotFound(message) | BrainboxError::InvalidInput(message) | BrainboxError::Other(message) => {
+                tr(message)
+            }
+            BrainboxError::DatabaseLocked(_) => tr("Database is busy, please try again"),
+            BrainboxError::Database(e) => trf("Database error: {}", &[&e.to_string()]),
+            BrainboxError::Network(message) => trf("Network error: {}", &[message]),
+            BrainboxError::Io(e) => trf("File system error: {}", &[&e.to_string()]),
+            BrainboxError::Crypto(message) => trf("Encryption error: {}", &[&tr(message)]),
+            BrainboxError::Search(message) => trf("Search error: {}", &[&tr(message)]),
+            BrainboxError::Sync(message) => trf("Sync error: {}", &[&tr(message)]),
+            BrainboxError::Update(message) => trf("Update error: {}", &[&tr(message)]),
+            BrainboxError::Cancelled => tr("Operation cancelled"),
+        }
+    }
+
+    /// Underlying error text, when the message alone hides it
+    fn details(&self) -> Option<String> {
+        match self {
+            BrainboxError::DatabaseLocked(e) => Some(e.to_string()),
+            _ => None,
+        }
+    }
+}
+
+impl Serialize for BrainboxError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let details = self.details();
+        let mut state = serializer.serialize_struct("BrainboxError", if details.is_some() { 4 } else { 3 })?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.localized_message())?;
+        if let Some(details) = details {
+            state.serialize_field("details", &details)?;
+        }
+        state.serialize_field("retryable", &self.retryable())?;
+        state.end()
+    }
+}
+
+impl From<rusqlite::Error> for BrainboxError {
+    fn from(e: rusqlite::Error) -> Self {
+        match e {
+            rusqlite::Error::QueryReturnedNoRows => BrainboxError::NotFound("Record not found".to_string()),
+            rusqlite::Error::SqliteFailure(ref err, _)
+                if matches!(err.code, rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked) =>
+            {
+                BrainboxError::DatabaseLocked(e)
+            }
+            e => BrainboxError::Database(e),
+        }
+    }
+}
+
+impl From<reqwest::Error> for BrainboxError {
+    fn from(e: reqwest::Error) -> Self {
+        BrainboxError::Network(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for BrainboxError {
+    fn from(e: serde_json::Error) -> Self {
+        BrainboxError::InvalidInput(format!("Invalid JSON: {}", e))
+    }
+}
+
+impl From<tauri::Error> for BrainboxError {
+    fn from(e: tauri::Error) -> Self {
+        BrainboxError::Other(e.to_string())
+    }
+}
+
+// Helpers and modules that still report plain strings
+impl From<String> for BrainboxError {
+    fn from(e: String) -> Self {
+        BrainboxError::Other(e)
+    }
+}
+
+impl From<&str> for BrainboxError {
+    fn from(e: &str) -> Self {
+        BrainboxError::Other(e.to_string())
+    }
+}
@@ -0,0 +1,363 @@
+// exif.rs - Photo metadata for brainbox image items
+// EXIF is read from the original bytes (JPEG, PNG, WebP or TIFF) before images are re-encoded for the cache,
+// which drops it. The taken-at time, camera and GPS position are stored per item, sealed under the vault's content
+// key like the item, and the camera and coordinates are added to the item's search text so photos can be found by them.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::envelope;
+use crate::error::BrainboxError;
+
+const TAG_MAKE: u16 = 0x010F;
+const TAG_MODEL: u16 = 0x0110;
+const TAG_DATE_TIME: u16 = 0x0132;
+const TAG_EXIF_IFD: u16 = 0x8769;
+const TAG_GPS_IFD: u16 = 0x8825;
+const TAG_DATE_TIME_ORIGINAL: u16 = 0x9003;
+const TAG_OFFSET_TIME_ORIGINAL: u16 = 0x9011;
+const TAG_GPS_LATITUDE_REF: u16 = 1;
+const TAG_GPS_LATITUDE: u16 = 2;
+const TAG_GPS_LONGITUDE_REF: u16 = 3;
+const TAG_GPS_LONGITUDE: u16 = 4;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ImageMetadata {
+    /// When the photo was taken (RFC 3339)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub taken_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub camera_make: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub camera_model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latitude: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub longitude: Option<f64>,
+}
+
+impl ImageMetadata {
+    fn is_empty(&self) -> bool {
+        self.taken_at.is_none() && self.camera_make.is_none() && self.camera_model.is_none() && self.latitude.is_none()
+    }
+
+    /// "Canon EOS R5", without the maker repeated when the model already names it
+    pub fn camera(&self) -> Option<String> {
+        match (&self.camera_make, &self.camera_model) {
+            (Some(make), Some(model)) if model.to_lowercase().starts_with(&make.to_lowercase()) => Some(model.clone()),
+            (Some(make), Some(model)) => Some(format!("{} {}", make, model)),
+            (make, model) => make.clone().or(model.clone()),
+        }
+    }
+
+    /// Text added to the search index: camera and coordinates
+    pub fn search_text(&self) -> String {
+        let mut parts = Vec::new();
+        parts.extend(self.camera());
+        if let (Some(lat), Some(lon)) = (self.latitude, self.longitude) {
+            parts.push(format!("{:.5}, {:.5}", lat, lon));
+        }
+        parts.join("\n")
+    }
+}
+
+/// Reader over a TIFF structure, the container EXIF data uses
+struct Tiff<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+}
+
+struct Entry {
+    tag: u16,
+    kind: u16,
+    count: usize,
+    /// Where the value starts: inline in the entry when it fits in four bytes, else at the stored offset
+    value_at: usize,
+}
+
+impl<'a> Tiff<'a> {
+    fn new(data: &'a [u8]) -> Option<Self> {
+        let little_endian = match data.get(..4)? {
+            b"II*\0" => true,
+            b"MM\0*" => false,
+            _ => return None,
+        };
+        Some(Tiff { data, little_endian })
+    }
+
+    fn u16(&self, at: usize) -> Option<u16> {
+        let bytes: [u8; 2] = self.data.get(at..at + 2)?.try_into().ok()?;
+        Some(if self.little_endian { u16::from_le_bytes(bytes) } else { u16::from_be_bytes(bytes) })
+    }
+
+    fn u32(&self, at: usize) -> Option<u32> {
+        let bytes: [u8; 4] = self.data.get(at..at + 4)?.try_into().ok()?;
+        Some(if self.little_endian { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) })
+    }
+
+    fn ifd(&self, offset: usize) -> Vec<Entry> {
+        let Some(count) = self.u16(offset) else { return Vec::new() };
+        (0..count as usize)
+            .filter_map(|i| {
+                let at = offset + 2 + i * 12;
+                let kind = self.u16(at + 2)?;
+                let count = self.u32(at + 4)? as usize;
+                let unit = match kind {
+                    3 => 2,
+                    4 | 9 => 4,
+                    5 | 10 => 8,
+                    _ => 1,
+                };
+                let value_at = if unit * count <= 4 { at + 8 } else { self.u32(at + 8)? as usize };
+                Some(Entry { tag: self.u16(at)?, kind, count, value_at })
+            })
+            .collect()
+    }
+
+    fn ascii(&self, entry: &Entry) -> Option<String> {
+        let bytes = self.data.get(entry.value_at..entry.value_at + entry.count)?;
+        let text = String::from_utf8_lossy(bytes).trim_matches(char::from(0)).trim().to_string();
+        (!text.is_empty()).then_some(text)
+    }
+
+    fn rationals(&self, entry: &Entry) -> Option<Vec<f64>> {
+        if entry.kind != 5 {
+            return None;
+        }
+        (0..entry.count)
+            .map(|i| {
+                let at = entry.value_at + i * 8;
+                let (num, den) = (self.u32(at)?, self.u32(at + 4)?);
+                (den != 0).then(|| num as f64 / den as f64)
+            })
+            .collect()
+    }
+
+    fn pointer(&self, entries: &[Entry], tag: u16) -> Option<usize> {
+        let entry = entries.iter().find(|e| e.tag == tag)?;
+        Some(self.u32(entry.value_at)? as usize)
+    }
+}
+
+/// Some writers keep the JPEG "Exif\0\0" prefix in PNG and WebP chunks
+fn strip_exif_header(data: &[u8]) -> &[u8] {
+    data.strip_prefix(b"Exif\0\0").unwrap_or(data)
+}
+
+/// The TIFF block holding EXIF inside a JPEG, PNG, WebP or TIFF file
+fn find_tiff(bytes: &[u8]) -> Option<&[u8]> {
+    if bytes.starts_with(b"II*\0") || bytes.starts_with(b"MM\0*") {
+        return Some(bytes);
+    }
+    if bytes.starts_with(&[0xFF, 0xD8]) {
+        let mut at = 2;
+        while at + 4 <= bytes.len() && bytes[at] == 0xFF {
+            let marker = bytes[at + 1];
+            let len = u16::from_be_bytes([bytes[at + 2], bytes[at + 3]]) as usize;
+            // Start of scan: image data follows, no more metadata segments
+            if marker == 0xDA || len < 2 {
+                break;
+            }
+            let segment = bytes.get(at + 4..at + 2 + len)?;
+            if marker == 0xE1 && segment.starts_with(b"Exif\0\0") {
+                return Some(&segment[6..]);
+            }
+            at += 2 + len;
+        }
+        return None;
+    }
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        let mut at = 8;
+        while at + 8 <= bytes.len() {
+            let len = u32::from_be_bytes(bytes[at..at + 4].try_into().ok()?) as usize;
+            let kind = &bytes[at + 4..at + 8];
+            let data = bytes.get(at + 8..at + 8 + len)?;
+            if kind == b"eXIf" {
+                return Some(strip_exif_header(data));
+            }
+            if kind == b"IDAT" || kind == b"IEND" {
+                break;
+            }
+            at += 12 + len;
+        }
+        return None;
+    }
+    if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") {
+        let mut at = 12;
+        while at + 8 <= bytes.len() {
+            let len = u32::from_le_bytes(bytes[at + 4..at + 8].try_into().ok()?) as usize;
+            let data = bytes.get(at + 8..at + 8 + len)?;
+            if &bytes[at..at + 4] == b"EXIF" {
+                return Some(strip_exif_header(data));
+            }
+            at += 8 + len + (len % 2);
+        }
+    }
+    None
+}
+
+/// EXIF "2023:06:01 14:22:10", with an optional "+02:00" offset; without one the time is taken as local
+fn parse_exif_date(value: &str, offset: Option<&str>) -> Option<String> {
+    use chrono::TimeZone;
+    let naive = chrono::NaiveDateTime::parse_from_str(value.trim(), "%Y:%m:%d %H:%M:%S").ok()?;
+    let utc = match offset.and_then(|o| chrono::DateTime::parse_from_str(&format!("{} {}", value.trim(), o.trim()), "%Y:%m:%d %H:%M:%S %:z").ok()) {
+        Some(dt) => dt.with_timezone(&chrono::Utc),
+        None => chrono::Local.from_local_datetime(&naive).earliest()?.with_timezone(&chrono::Utc),
+    };
+    Some(utc.to_rfc3339())
+}
+
+fn gps_coordinate(tiff: &Tiff, entries: &[Entry], value_tag: u16, ref_tag: u16, negative_ref: &str, max: f64) -> Option<f64> {
+    let parts = tiff.rationals(entries.iter().find(|e| e.tag == value_tag)?)?;
+    let degrees = parts.first()? + parts.get(1).unwrap_or(&0.0) / 60.0 + parts.get(2).unwrap_or(&0.0) / 3600.0;
+    let reference = entries.iter().find(|e| e.tag == ref_tag).and_then(|e| tiff.ascii(e)).unwrap_or_default();
+    let value = if reference.eq_ignore_ascii_case(negative_ref) { -degrees } else { degrees };
+    (value.abs() <= max && value.is_finite()).then_some(value)
+}
+
+/// EXIF metadata from an image file's bytes; None when it has none
+pub fn read(bytes: &[u8]) -> Option<ImageMetadata> {
+    let tiff = Tiff::new(find_tiff(bytes)?)?;
+    let ifd0 = tiff.ifd(tiff.u32(4)? as usize);
+    let ascii = |entries: &[Entry], tag: u16| entries.iter().find(|e| e.tag == tag).and_then(|e| tiff.ascii(e));
+
+    let mut metadata = ImageMetadata {
+        camera_make: ascii(&ifd0, TAG_MAKE),
+        camera_model: ascii(&ifd0, TAG_MODEL),
+        ..Default::default()
+    };
+    if let Some(exif) = tiff.pointer(&ifd0, TAG_EXIF_IFD).map(|at| tiff.ifd(at)) {
+        metadata.taken_at = ascii(&exif, TAG_DATE_TIME_ORIGINAL)
+            .and_then(|date| parse_exif_date(&date, ascii(&exif, TAG_OFFSET_TIME_ORIGINAL).as_deref()));
+    }
+    if metadata.taken_at.is_none() {
+        metadata.taken_at = ascii(&ifd0, TAG_DATE_TIME).and_then(|date| parse_exif_date(&date, None));
+    }
+    if let Some(gps) = tiff.pointer(&ifd0, TAG_GPS_IFD).map(|at| tiff.ifd(at)) {
+        let latitude = gps_coordinate(&tiff, &gps, TAG_GPS_LATITUDE, TAG_GPS_LATITUDE_REF, "S", 90.0);
+        let longitude = gps_coordinate(&tiff, &gps, TAG_GPS_LONGITUDE, TAG_GPS_LONGITUDE_REF, "W", 180.0);
+        // A position needs both coordinates; cameras without a fix write zeros
+        if let (Some(lat), Some(lon)) = (latitude, longitude) {
+            if lat != 0.0 || lon != 0.0 {
+                metadata.latitude = Some(lat);
+                metadata.longitude = Some(lon);
+            }
+        }
+    }
+    (!metadata.is_empty()).then_some(metadata)
+}
+
+pub fn create_table(conn: &Connection) -> rusqlite::Result<()> {
+    let existing: Option<String> = conn
+        .query_row("SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'image_metadata'", [], |row| row.get(0))
+        .optional()?;
+    // Metadata stored in the clear before it was sealed waits in image_metadata_plain until its vault is unlocked
+    if existing.is_some_and(|sql| sql.contains("latitude REAL")) {
+        conn.execute("ALTER TABLE image_metadata RENAME TO image_metadata_plain", [])?;
+    }
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS image_metadata (
+            item_id INTEGER PRIMARY KEY REFERENCES vault_items(id) ON DELETE CASCADE,
+            metadata BLOB NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// `key` is the content key of the item's vault
+pub fn save(conn: &Connection, item_id: i64, metadata: &ImageMetadata, key: &[u8; 32]) -> Result<(), BrainboxError> {
+    create_table(conn)?;
+    conn.execute(
+        "INSERT OR REPLACE INTO image_metadata (item_id, metadata) VALUES (?1, ?2)",
+        params![item_id, envelope::seal(key, serde_json::to_string(metadata)?.as_bytes())?],
+    )?;
+    Ok(())
+}
+
+pub fn get(conn: &Connection, item_id: i64, key: &[u8; 32]) -> Result<Option<ImageMetadata>, BrainboxError> {
+    create_table(conn)?;
+    let sealed: Option<Vec<u8>> = conn
+        .query_row("SELECT metadata FROM image_metadata WHERE item_id = ?1", [item_id], |row| row.get(0))
+        .optional()?;
+    match sealed {
+        Some(sealed) => Ok(Some(serde_json::from_slice(&envelope::open(key, &sealed)?)?)),
+        None => seal_plain(conn, item_id, key),
+    }
+}
+
+/// Seal an item's metadata left from before it was sealed, and drop the clear copy
+fn seal_plain(conn: &Connection, item_id: i64, key: &[u8; 32]) -> Result<Option<ImageMetadata>, BrainboxError> {
+    let has_plain: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'image_metadata_plain')",
+        [],
+        |row| row.get(0),
+    )?;
+    if !has_plain {
+        return Ok(None);
+    }
+    let metadata = conn
+        .query_row(
+            "SELECT taken_at, camera_make, camera_model, latitude, longitude FROM image_metadata_plain WHERE item_id = ?1",
+            [item_id],
+            |row| {
+                Ok(ImageMetadata {
+                    taken_at: row.get(0)?,
+                    camera_make: row.get(1)?,
+                    camera_model: row.get(2)?,
+                    latitude: row.get(3)?,
+                    longitude: row.get(4)?,
+                })
+            },
+        )
+        .optional()?;
+    let Some(metadata) = metadata else { return Ok(None) };
+    save(conn, item_id, &metadata, key)?;
+    conn.execute("DELETE FROM image_metadata_plain WHERE item_id = ?1", [item_id])?;
+    // Rows of deleted items went with them, so an empty table means every vault has been sealed
+    let left: i64 = conn.query_row("SELECT COUNT(*) FROM image_metadata_plain", [], |row| row.get(0))?;
+    if left == 0 {
+        conn.execute("DROP TABLE image_metadata_plain", [])?;
+    }
+    Ok(Some(metadata))
+}
+
+/// Store metadata read from an image and date the item by when the photo was taken
+pub fn apply(conn: &Connection, item_id: i64, metadata: &ImageMetadata, key: &[u8; 32]) -> Result<(), BrainboxError> {
+    save(conn, item_id, metadata, key)?;
+    if let Some(taken_at) = &metadata.taken_at {
+        crate::vault::VaultItem::set_created_at(conn, item_id, taken_at)?;
+    }
+    Ok(())
+}
+
+/// Search text for an image item's stored metadata, used when the item is indexed
+pub fn search_text(conn: &Connection, item_id: i64, key: &[u8; 32]) -> Option<String> {
+    let text = get(conn, item_id, key).ok()??.search_text();
+    (!text.is_empty()).then_some(text)
+}
+
+fn reseal(conn: &Connection, filter: &str, id: i64, old_key: &[u8; 32], new_key: &[u8; 32]) -> Result<usize, BrainboxError> {
+    create_table(conn)?;
+    let mut stmt = conn.prepare(&format!("SELECT item_id, metadata FROM image_metadata WHERE {}", filter))?;
+    let rows = stmt
+        .query_map([id], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, Vec<u8>>(1)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    for (item_id, sealed) in &rows {
+        conn.execute(
+            "UPDATE image_metadata SET metadata = ?1 WHERE item_id = ?2",
+            params![envelope::seal(new_key, &envelope::open(old_key, sealed)?)?, item_id],
+        )?;
+    }
+    Ok(rows.len())
+}
+
+/// Re-seal the metadata of a vault's images under its new content key
+pub fn reseal_vault(conn: &Connection, vault_id: i64, old_key: &[u8; 32], new_key: &[u8; 32]) -> Result<usize, BrainboxError> {
+    reseal(conn, "item_id IN (SELECT id FROM vault_items WHERE vault_id = ?1)", vault_id, old_key, new_key)
+}
+
+/// Re-seal one item's metadata for the vault it is moving to
+pub fn reseal_item(conn: &Connection, item_id: i64, old_key: &[u8; 32], new_key: &[u8; 32]) -> Result<usize, BrainboxError> {
+    reseal(conn, "item_id = ?1", item_id, old_key, new_key)
+}
@@ -0,0 +1,179 @@
+// favicons.rs - Local favicon cache for brainbox
+// Each site's own favicon is fetched once, stored per host as a small WebP and served over bbimg://,
+// so rendering link cards works offline and doesn't tell a third-party service which sites are saved
+
+use regex::Regex;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::io::Read;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::error::BrainboxError;
+use crate::image_cache;
+
+/// Path prefix under which the bbimg:// protocol serves cached favicons
+pub const FAVICON_PREFIX: &str = "favicons/";
+/// Favicons are stored at this size, enough for high-DPI 32px display
+const FAVICON_SIZE: u32 = 64;
+const MAX_ICON_BYTES: u64 = 1024 * 1024;
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+/// Hosts without a usable favicon aren't asked again until this many days have passed
+const RETRY_FAILED_DAYS: i64 = 7;
+
+pub fn create_table(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS favicons (
+            host TEXT PRIMARY KEY,
+            file_name TEXT,
+            source_url TEXT,
+            fetched_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+pub fn cache_dir() -> Option<PathBuf> {
+    Some(crate::data_dir::root()?.join("brainbox").join("favicons"))
+}
+
+/// Lowercased host of a URL, or the input itself when it is already a bare host
+pub fn normalize_host(input: &str) -> Option<String> {
+    let input = input.trim();
+    let host = if input.contains("://") {
+        reqwest::Url::parse(input).ok()?.host_str()?.to_string()
+    } else {
+        input.split(['/', ':']).next()?.to_string()
+    };
+    let host = host.trim_end_matches('.').to_lowercase();
+    (!host.is_empty()).then_some(host)
+}
+
+/// Cache file name for a host; anything outside [a-z0-9.-] is replaced so the name is always safe to serve
+fn file_name_for(host: &str) -> String {
+    let safe: String = host
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '_' })
+        .collect();
+    format!("{}.webp", safe.trim_start_matches('.'))
+}
+
+/// Icon URLs declared by a page's <link rel="...icon..."> tags, most specific first, resolved against `base`
+pub fn icon_links(html: &str, base: &reqwest::Url) -> Vec<String> {
+    let (Ok(re_link), Ok(re_rel), Ok(re_href)) = (
+        Regex::new(r"(?i)<link\b[^>]*>"),
+        Regex::new(r#"(?i)\brel\s*=\s*["']([^"']+)["']"#),
+        Regex::new(r#"(?i)\bhref\s*=\s*["']([^"']+)["']"#),
+    ) else {
+        return Vec::new();
+    };
+    let mut icons: Vec<(u8, String)> = re_link
+        .find_iter(html)
+        .filter_map(|tag| {
+            let tag = tag.as_str();
+            let rel = re_rel.captures(tag)?[1].to_lowercase();
+            let href = re_href.captures(tag)?[1].trim().replace("&amp;", "&");
+            // SVG icons can't be rasterized here and data: URLs aren't worth a request
+            if href.starts_with("data:") || href.to_lowercase().ends_with(".svg") {
+                return None;
+            }
+            let rank = match rel.split_whitespace().collect::<Vec<_>>().as_slice() {
+                ["icon"] => 0,
+                ["shortcut", "icon"] => 1,
+                r if r.contains(&"apple-touch-icon") => 2,
+                r if r.contains(&"icon") => 3,
+                _ => return None,
+            };
+            Some((rank, base.join(&href).ok()?.to_string()))
+        })
+        .collect();
+    icons.sort_by_key(|(rank, _)| *rank);
+    icons.into_iter().map(|(_, url)| url).collect()
+}
+
+fn download(url: &str) -> Result<Vec<u8>, BrainboxError> {
+    let resp = crate::http::send(crate::http::client().get(url).timeout(FETCH_TIMEOUT))?;
+    if !resp.status().is_success() {
+        return Err(BrainboxError::Network(format!("Favicon request failed with status {}", resp.status())));
+    }
+    let mut bytes = Vec::new();
+    resp.take(MAX_ICON_BYTES + 1).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 > MAX_ICON_BYTES {
+        return Err(BrainboxError::InvalidInput("Favicon is too large".to_string()));
+    }
+    Ok(bytes)
+}
+
+/// Find the site's favicon: icons declared on the home page first, then the conventional /favicon.ico
+fn fetch_icon(host: &str) -> Result<(String, Vec<u8>), BrainboxError> {
+    let home = reqwest::Url::parse(&format!("https://{}/", host))
+        .map_err(|_| BrainboxError::InvalidInput(format!("Invalid host: {}", host)))?;
+    let mut candidates = match crate::http::send(crate::http::client().get(home.clone()).timeout(FETCH_TIMEOUT)) {
+        Ok(resp) if resp.status().is_success() => {
+            let base = resp.url().clone();
+            icon_links(&resp.text().unwrap_or_default(), &base)
+        }
+        _ => Vec::new(),
+    };
+    if let Ok(ico) = home.join("/favicon.ico") {
+        candidates.push(ico.to_string());
+    }
+    for url in candidates {
+        match download(&url).and_then(|bytes| image_cache::to_webp(&bytes, FAVICON_SIZE)) {
+            Ok(webp) => return Ok((url, webp)),
+            Err(e) => tracing::debug!("Favicon candidate {} unusable: {}", url, e),
+        }
+    }
+    Err(BrainboxError::NotFound(format!("No favicon found for {}", host)))
+}
+
+/// Local URL of the favicon for `host`, fetching it on first use. None when the site has no usable icon.
+pub fn get_or_fetch(conn: &Connection, host: &str) -> Result<Option<String>, BrainboxError> {
+    let host = normalize_host(host).ok_or_else(|| BrainboxError::InvalidInput("Invalid host".to_string()))?;
+    create_table(conn)?;
+    let dir = cache_dir().ok_or("Failed to get app data dir")?;
+
+    let cached: Option<(Option<String>, String)> = conn
+        .query_row(
+            "SELECT file_name, fetched_at FROM favicons WHERE host = ?1",
+            [&host],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+    match cached {
+        Some((Some(file_name), _)) if dir.join(&file_name).exists() => {
+            return Ok(Some(image_cache::asset_url(&format!("{}{}", FAVICON_PREFIX, file_name))));
+        }
+        Some((None, fetched_at)) => {
+            let recent = chrono::DateTime::parse_from_rfc3339(&fetched_at)
+                .map(|t| chrono::Utc::now() - t.with_timezone(&chrono::Utc) < chrono::Duration::days(RETRY_FAILED_DAYS))
+                .unwrap_or(false);
+            if recent {
+                return Ok(None);
+            }
+        }
+        _ => {}
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    match fetch_icon(&host) {
+        Ok((source_url, webp)) => {
+            std::fs::create_dir_all(&dir)?;
+            let file_name = file_name_for(&host);
+            image_cache::write_atomic(&dir.join(&file_name), &webp)?;
+            conn.execute(
+                "INSERT OR REPLACE INTO favicons (host, file_name, source_url, fetched_at) VALUES (?1, ?2, ?3, ?4)",
+                params![host, file_name, source_url, now],
+            )?;
+            Ok(Some(image_cache::asset_url(&format!("{}{}", FAVICON_PREFIX, file_name))))
+        }
+        Err(e) => {
+            tracing::debug!("Favicon lookup for {} failed: {}", host, e);
+            conn.execute(
+                "INSERT OR REPLACE INTO favicons (host, file_name, source_url, fetched_at) VALUES (?1, NULL, NULL, ?2)",
+                params![host, now],
+            )?;
+            Ok(None)
+        }
+    }
+}
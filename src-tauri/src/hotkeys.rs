@@ -0,0 +1,176 @@
+// hotkeys.rs - Global hotkeys for brainbox
+// Each named action can have its own shortcut; bindings are saved in the settings table and restored at startup
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+use crate::error::BrainboxError;
+use crate::settings;
+
+pub const QUICK_CAPTURE: &str = "quick_capture";
+pub const TOGGLE_WINDOW: &str = "toggle_window";
+pub const QUICK_SWITCHER: &str = "quick_switcher";
+pub const EMERGENCY_LOCK: &str = "emergency_lock";
+
+/// (action, event emitted when pressed, default hotkey)
+const ACTIONS: &[(&str, &str, Option<&str>)] = &[
+    (QUICK_CAPTURE, "capture-hotkey-pressed", Some("Alt+Shift+B")),
+    ("screenshot", "screenshot-hotkey-pressed", None),
+    ("clipboard_capture", "clipboard-capture-hotkey-pressed", None),
+    (TOGGLE_WINDOW, "toggle-window-hotkey-pressed", None),
+    ("quick_search", "quick-search-hotkey-pressed", None),
+    (QUICK_SWITCHER, "quick-switcher-hotkey-pressed", Some("Alt+Shift+Space")),
+    (EMERGENCY_LOCK, "emergency-lock-hotkey-pressed", None),
+];
+
+/// Settings key prefix; an empty value means the user cleared the binding
+pub const SETTING_PREFIX: &str = "hotkey.";
+
+/// Hotkey bound to each action, kept in memory so bindings can be swapped
+#[derive(Default)]
+pub struct HotkeyState {
+    bindings: Mutex<HashMap<String, String>>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ActionHotkey {
+    pub action: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hotkey: Option<String>,
+    /// Event emitted to the frontend when the hotkey is pressed
+    pub event: String,
+}
+
+fn action_event(action: &str) -> Option<&'static str> {
+    ACTIONS.iter().find(|(name, _, _)| *name == action).map(|(_, event, _)| *event)
+}
+
+fn parse(hotkey: &str) -> Result<Shortcut, BrainboxError> {
+    hotkey
+        .parse()
+        .map_err(|e| BrainboxError::InvalidInput(format!("Invalid shortcut: {e}")))
+}
+
+fn save_binding(action: &str, hotkey: &str) -> Result<(), BrainboxError> {
+    let conn = crate::db::open()?;
+    settings::set(&conn, &format!("{}{}", SETTING_PREFIX, action), hotkey)?;
+    Ok(())
+}
+
+/// Run the built-in behaviour for an action, then notify the frontend
+fn on_pressed(app: &AppHandle, action: &str, event: &str) {
+    if action == EMERGENCY_LOCK {
+        if let Err(e) = crate::emergency::lock(app, None) {
+            tracing::error!("Emergency lock failed: {}", e);
+        }
+        let _ = app.emit(event, ());
+        return;
+    }
+    if action == QUICK_SWITCHER {
+        if let Err(e) = crate::switcher::toggle(app) {
+            tracing::error!("Failed to open quick switcher: {}", e);
+        }
+        let _ = app.emit(event, ());
+        return;
+    }
+    if let Some(window) = app.get_webview_window("main") {
+        if action == TOGGLE_WINDOW {
+            if window.is_visible().unwrap_or(false) && window.is_focused().unwrap_or(false) {
+                let _ = window.hide();
+            } else {
+                let _ = window.show();
+                let _ = window.set_focus();
+            }
+        } else {
+            let _ = window.show();
+            let _ = window.set_focus();
+        }
+    }
+    let _ = app.emit(event, ());
+}
+
+fn bind(app: &AppHandle, state: &HotkeyState, action: &str, hotkey: &str) -> Result<(), BrainboxError> {
+    let event = action_event(action)
+        .ok_or_else(|| BrainboxError::InvalidInput(format!("Unknown hotkey action: {}", action)))?;
+    let shortcut = parse(hotkey)?;
+    let mut bindings = state.bindings.lock().unwrap();
+    if let Some((other, _)) = bindings
+        .iter()
+        .find(|(other, bound)| other.as_str() != action && parse(bound).ok() == Some(shortcut))
+    {
+        return Err(BrainboxError::InvalidInput(format!("{} is already used by {}", hotkey, other)));
+    }
+
+    let global_shortcut = app.global_shortcut();
+    if let Some(prev) = bindings.remove(action) {
+        if let Ok(prev) = parse(&prev) {
+            let _ = global_shortcut.unregister(prev);
+        }
+    }
+    let action_name = action.to_string();
+    global_shortcut
+        .on_shortcut(shortcut, move |app, _shortcut, event_info| {
+            if event_info.state() == ShortcutState::Pressed {
+                on_pressed(app, &action_name, event);
+            }
+        })
+        .map_err(|e| BrainboxError::Other(format!("Failed to register hotkey: {e}")))?;
+    bindings.insert(action.to_string(), hotkey.to_string());
+    Ok(())
+}
+
+/// Bind `hotkey` to `action`, replacing its previous hotkey, and save the binding
+pub fn register(app: &AppHandle, state: &HotkeyState, action: &str, hotkey: &str) -> Result<(), BrainboxError> {
+    bind(app, state, action, hotkey)?;
+    save_binding(action, hotkey)
+}
+
+/// Remove the hotkey for `action` and remember that it was cleared
+pub fn unregister(app: &AppHandle, state: &HotkeyState, action: &str) -> Result<(), BrainboxError> {
+    if action_event(action).is_none() {
+        return Err(BrainboxError::InvalidInput(format!("Unknown hotkey action: {}", action)));
+    }
+    if let Some(prev) = state.bindings.lock().unwrap().remove(action) {
+        if let Ok(prev) = parse(&prev) {
+            let _ = app.global_shortcut().unregister(prev);
+        }
+    }
+    save_binding(action, "")
+}
+
+pub fn list(state: &HotkeyState) -> Vec<ActionHotkey> {
+    let bindings = state.bindings.lock().unwrap();
+    ACTIONS
+        .iter()
+        .map(|(action, event, _)| ActionHotkey {
+            action: action.to_string(),
+            hotkey: bindings.get(*action).cloned(),
+            event: event.to_string(),
+        })
+        .collect()
+}
+
+/// Register saved bindings, falling back to each action's default (run once at startup)
+pub fn restore(app: &AppHandle, state: &HotkeyState) {
+    let saved = crate::db::open()
+        .ok()
+        .and_then(|conn| settings::get_all(&conn).ok())
+        .map(|all| all.into_iter().collect::<HashMap<_, _>>())
+        .unwrap_or_default();
+    for (action, _, default) in ACTIONS {
+        let hotkey = match saved.get(&format!("{}{}", SETTING_PREFIX, action)) {
+            Some(saved) if saved.is_empty() => continue,
+            Some(saved) => saved.as_str(),
+            None => match default {
+                Some(default) => default,
+                None => continue,
+            },
+        };
+        if let Err(e) = bind(app, state, action, hotkey) {
+            tracing::warn!("Could not register {} hotkey {}: {}", action, hotkey, e);
+        }
+    }
+}
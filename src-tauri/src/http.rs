@@ -0,0 +1,193 @@
+// http.rs - Shared HTTP clients for brainbox
+// One pooled client per kind of request instead of a fresh Client per command, with consistent timeouts,
+// a configurable user agent and retry with exponential backoff for transient failures
+
+use lazy_static::lazy_static;
+use reqwest::blocking::{RequestBuilder, Response};
+use reqwest::StatusCode;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+use std::time::Duration;
+
+use crate::error::BrainboxError;
+use crate::settings;
+
+const USER_AGENT_KEY: &str = "http.user_agent";
+const TIMEOUT_KEY: &str = "http.timeout_secs";
+const MAX_RETRIES_KEY: &str = "http.max_retries";
+
+pub const DEFAULT_TIMEOUT_SECS: u64 = 30;
+pub const DEFAULT_MAX_RETRIES: u32 = 2;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+/// Delay before the first retry; doubled for each further attempt
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(8);
+
+pub fn default_user_agent() -> String {
+    format!("Mozilla/5.0 (compatible; brainbox/{})", env!("CARGO_PKG_VERSION"))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpSettings {
+    pub user_agent: String,
+    /// Overall timeout for ordinary requests; streaming and downloads only use the connect timeout
+    pub timeout_secs: u64,
+    pub max_retries: u32,
+}
+
+impl Default for HttpSettings {
+    fn default() -> Self {
+        HttpSettings {
+            user_agent: default_user_agent(),
+            timeout_secs: DEFAULT_TIMEOUT_SECS,
+            max_retries: DEFAULT_MAX_RETRIES,
+        }
+    }
+}
+
+pub fn load_settings(conn: &Connection) -> Result<HttpSettings, BrainboxError> {
+    let defaults = HttpSettings::default();
+    Ok(HttpSettings {
+        user_agent: settings::get(conn, USER_AGENT_KEY)?
+            .filter(|ua| !ua.trim().is_empty())
+            .unwrap_or(defaults.user_agent),
+        timeout_secs: settings::get_parsed(conn, TIMEOUT_KEY)?
+            .filter(|secs| *secs > 0)
+            .unwrap_or(defaults.timeout_secs),
+        max_retries: settings::get_parsed(conn, MAX_RETRIES_KEY)?
+            .unwrap_or(defaults.max_retries),
+    })
+}
+
+/// Persist settings and rebuild the shared clients so they take effect immediately
+pub fn save_settings(conn: &Connection, settings: &HttpSettings) -> Result<(), BrainboxError> {
+    if settings.timeout_secs == 0 {
+        return Err(BrainboxError::InvalidInput("Timeout must be at least one second".to_string()));
+    }
+    settings::set(conn, USER_AGENT_KEY, settings.user_agent.trim())?;
+    settings::set_value(conn, TIMEOUT_KEY, settings.timeout_secs)?;
+    settings::set_value(conn, MAX_RETRIES_KEY, settings.max_retries)?;
+    *CLIENTS.write().unwrap() = Some(Clients::build(load_settings(conn)?)?);
+    Ok(())
+}
+
+struct Clients {
+    settings: HttpSettings,
+    blocking: reqwest::blocking::Client,
+    /// No overall timeout, for streamed responses that can legitimately run for minutes
+    streaming: reqwest::blocking::Client,
+    /// Async clients for code already running on the async runtime (the updater)
+    async_default: reqwest::Client,
+    async_streaming: reqwest::Client,
+}
+
+impl Clients {
+    fn build(settings: HttpSettings) -> Result<Self, BrainboxError> {
+        let timeout = Duration::from_secs(settings.timeout_secs);
+        let blocking_builder = || {
+            reqwest::blocking::Client::builder()
+                .user_agent(settings.user_agent.clone())
+                .connect_timeout(CONNECT_TIMEOUT)
+                .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+                .redirect(reqwest::redirect::Policy::limited(10))
+        };
+        let async_builder = || {
+            reqwest::Client::builder()
+                .user_agent(settings.user_agent.clone())
+                .connect_timeout(CONNECT_TIMEOUT)
+                .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+                .redirect(reqwest::redirect::Policy::limited(10))
+        };
+        Ok(Clients {
+            blocking: blocking_builder().timeout(timeout).build()?,
+            streaming: blocking_builder().timeout(None).build()?,
+            async_default: async_builder().timeout(timeout).build()?,
+            async_streaming: async_builder().build()?,
+            settings,
+        })
+    }
+}
+
+lazy_static! {
+    static ref CLIENTS: RwLock<Option<Clients>> = RwLock::new(None);
+}
+
+/// Run `f` with the shared clients, building them from saved settings on first use
+fn with_clients<T>(f: impl FnOnce(&Clients) -> T) -> T {
+    if let Some(clients) = CLIENTS.read().unwrap().as_ref() {
+        return f(clients);
+    }
+    let mut guard = CLIENTS.write().unwrap();
+    let clients = guard.get_or_insert_with(|| {
+        let settings = crate::db::open()
+            .and_then(|conn| load_settings(&conn))
+            .unwrap_or_default();
+        // Only fails if the TLS backend can't initialize; the defaults are the best fallback
+        Clients::build(settings.clone()).unwrap_or_else(|e| {
+            tracing::error!("Failed to build HTTP client: {}", e);
+            Clients::build(HttpSettings::default()).expect("default HTTP client")
+        })
+    });
+    f(clients)
+}
+
+/// Pooled blocking client with the configured timeout. Cheap to call; clients share one connection pool.
+pub fn client() -> reqwest::blocking::Client {
+    with_clients(|c| c.blocking.clone())
+}
+
+/// Blocking client without an overall timeout, for streamed responses
+pub fn streaming_client() -> reqwest::blocking::Client {
+    with_clients(|c| c.streaming.clone())
+}
+
+pub fn async_client() -> reqwest::Client {
+    with_clients(|c| c.async_default.clone())
+}
+
+/// Async client without an overall timeout, for large downloads
+pub fn async_streaming_client() -> reqwest::Client {
+    with_clients(|c| c.async_streaming.clone())
+}
+
+fn is_transient_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+fn is_transient_error(e: &reqwest::Error) -> bool {
+    e.is_timeout() || e.is_connect()
+}
+
+/// Send a request, retrying connection failures, timeouts and 429/502/503/504 responses with exponential backoff.
+/// Requests whose body can't be cloned (streams) are sent once.
+pub fn send(request: RequestBuilder) -> reqwest::Result<Response> {
+    let max_retries = with_clients(|c| c.settings.max_retries);
+    let mut delay = RETRY_BASE_DELAY;
+    let mut attempt = 0;
+    let mut pending = request;
+    loop {
+        let retry = if attempt < max_retries { pending.try_clone() } else { None };
+        let result = pending.send();
+        let Some(next) = retry else { return result };
+        let transient = match &result {
+            Ok(resp) => is_transient_status(resp.status()),
+            Err(e) => is_transient_error(e),
+        };
+        if !transient {
+            return result;
+        }
+        attempt += 1;
+        tracing::debug!("Transient HTTP failure, retrying in {:?} (attempt {} of {})", delay, attempt, max_retries);
+        std::thread::sleep(delay);
+        delay = (delay * 2).min(MAX_RETRY_DELAY);
+        pending = next;
+    }
+}
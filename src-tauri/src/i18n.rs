@@ -0,0 +1,279 @@
+// i18n.rs - Translations of the text brainbox's backend shows to users
+// Strings are looked up by their English text, gettext style, in small embedded tables; anything missing from a
+// table, including messages built at runtime, stays in English. The "locale" setting picks the language, with
+// "system" following the OS. Tray labels, notifications, conflict titles and error messages go through `tr`.
+
+use lazy_static::lazy_static;
+use rusqlite::Connection;
+use serde::Serialize;
+use std::sync::RwLock;
+
+use crate::error::BrainboxError;
+use crate::settings;
+
+pub const LOCALE_KEY: &str = "locale";
+/// Follow the OS language
+pub const SYSTEM_LOCALE: &str = "system";
+const FALLBACK: &str = "en";
+
+type Table = &'static [(&'static str, &'static str)];
+
+/// (code, name in its own language, translations from English)
+const LOCALES: &[(&str, &str, Table)] = &[
+    ("en", "English", &[]),
+    ("de", "Deutsch", DE),
+    ("es", "Español", ES),
+    ("fr", "Français", FR),
+];
+
+lazy_static! {
+    static ref ACTIVE: RwLock<&'static str> = RwLock::new(FALLBACK);
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct LocaleInfo {
+    /// The saved setting: a locale code or "system"
+    pub setting: String,
+    /// The locale strings are shown in
+    pub active: String,
+    /// (code, name) of every locale with translations
+    pub available: Vec<(String, String)>,
+}
+
+/// Two-letter language of a locale name such as "de_DE.UTF-8" or "fr-CA", when brainbox has it
+fn supported(name: &str) -> Option<&'static str> {
+    let language = name.trim().split(['_', '-', '.', '@']).next()?.to_lowercase();
+    LOCALES.iter().map(|(code, _, _)| *code).find(|code| *code == language)
+}
+
+#[cfg(target_os = "windows")]
+fn system_locale() -> Option<String> {
+    use windows::Win32::Globalization::GetUserDefaultLocaleName;
+    let mut buffer = [0u16; 85];
+    let len = unsafe { GetUserDefaultLocaleName(&mut buffer) };
+    // The length includes the terminating null
+    (len > 1).then(|| String::from_utf16_lossy(&buffer[..len as usize - 1]))
+}
+
+#[cfg(target_os = "macos")]
+fn system_locale() -> Option<String> {
+    use objc2::msg_send;
+    use objc2::runtime::{AnyClass, AnyObject};
+    let class = AnyClass::get(c"NSLocale")?;
+    unsafe {
+        let locale: *mut AnyObject = msg_send![class, currentLocale];
+        let identifier: *mut AnyObject = msg_send![locale, localeIdentifier];
+        if identifier.is_null() {
+            return None;
+        }
+        let utf8: *const std::ffi::c_char = msg_send![identifier, UTF8String];
+        (!utf8.is_null()).then(|| std::ffi::CStr::from_ptr(utf8).to_string_lossy().to_string())
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn system_locale() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+}
+
+/// The locale a setting value stands for
+fn resolve(setting: &str) -> &'static str {
+    if setting == SYSTEM_LOCALE {
+        system_locale().as_deref().and_then(supported).unwrap_or(FALLBACK)
+    } else {
+        supported(setting).unwrap_or(FALLBACK)
+    }
+}
+
+fn saved_setting(conn: &Connection) -> Result<String, BrainboxError> {
+    Ok(settings::get_or_default(conn, LOCALE_KEY)?.unwrap_or_else(|| SYSTEM_LOCALE.to_string()))
+}
+
+/// Pick up the saved locale; call once at startup
+pub fn init(conn: &Connection) {
+    match saved_setting(conn) {
+        Ok(setting) => *ACTIVE.write().unwrap() = resolve(&setting),
+        Err(e) => tracing::warn!("Failed to read the locale setting: {}", e),
+    }
+}
+
+pub fn active() -> &'static str {
+    *ACTIVE.read().unwrap()
+}
+
+pub fn info(conn: &Connection) -> Result<LocaleInfo, BrainboxError> {
+    Ok(LocaleInfo {
+        setting: saved_setting(conn)?,
+        active: active().to_string(),
+        available: LOCALES.iter().map(|(code, name, _)| (code.to_string(), name.to_string())).collect(),
+    })
+}
+
+/// Save and switch to a locale code, or "system"
+pub fn set_locale(conn: &Connection, setting: &str) -> Result<LocaleInfo, BrainboxError> {
+    let setting = setting.trim();
+    if setting != SYSTEM_LOCALE && !LOCALES.iter().any(|(code, _, _)| *code == setting) {
+        return Err(BrainboxError::InvalidInput(format!("Unsupported locale: {}", setting)));
+    }
+    settings::set(conn, LOCALE_KEY, setting)?;
+    *ACTIVE.write().unwrap() = resolve(setting);
+    info(conn)
+}
+
+/// `text` in the active locale
+pub fn tr(text: &str) -> String {
+    let active = active();
+    LOCALES
+        .iter()
+        .find(|(code, _, _)| *code == active)
+        .and_then(|(_, _, table)| table.iter().find(|(english, _)| *english == text))
+        .map_or(text, |(_, translated)| translated)
+        .to_string()
+}
+
+/// Translate a template, then fill its `{}` placeholders in order
+pub fn trf(template: &str, args: &[&str]) -> String {
+    let translated = tr(template);
+    let mut parts = translated.split("{}");
+    let mut out = parts.next().unwrap_or_default().to_string();
+    for (index, part) in parts.enumerate() {
+        out.push_str(args.get(index).copied().unwrap_or_default());
+        out.push_str(part);
+    }
+    out
+}
+
+const DE: Table = &[
+    ("Show Brainbox", "Brainbox anzeigen"),
+    ("Hide to Tray", "In den Infobereich minimieren"),
+    ("Quick Capture", "Schnellerfassung"),
+    ("Capture Screenshot", "Bildschirmfoto aufnehmen"),
+    ("Sync Now", "Jetzt synchronisieren"),
+    ("Recent Items", "Zuletzt verwendet"),
+    ("Quit", "Beenden"),
+    ("Untitled", "Ohne Titel"),
+    ("New Note", "Neue Notiz"),
+    ("Search", "Suchen"),
+    ("brainbox quick switcher", "brainbox-Schnellwechsler"),
+    ("Sync complete", "Synchronisierung abgeschlossen"),
+    ("Vaults exported to the sync folder", "Tresore in den Synchronisierungsordner exportiert"),
+    ("Sync failed", "Synchronisierung fehlgeschlagen"),
+    ("Scheduled export failed", "Geplanter Export fehlgeschlagen"),
+    ("Weekly digest ready", "Wochenübersicht bereit"),
+    ("Weekly digest failed", "Wochenübersicht fehlgeschlagen"),
+    ("Reminder", "Erinnerung"),
+    ("Task due", "Aufgabe fällig"),
+    ("Meeting notes ready", "Besprechungsnotizen bereit"),
+    ("brainbox update available", "brainbox-Update verfügbar"),
+    (
+        "Version {} is ready to install from Settings > Updates",
+        "Version {} kann unter Einstellungen > Updates installiert werden",
+    ),
+    ("[Conflict]", "[Konflikt]"),
+    ("Invalid password or key", "Falsches Passwort oder falscher Schlüssel"),
+    ("Too many failed attempts, try again in {} seconds", "Zu viele Fehlversuche, erneut versuchen in {} Sekunden"),
+    ("Database is busy, please try again", "Die Datenbank ist beschäftigt, bitte erneut versuchen"),
+    ("Database error: {}", "Datenbankfehler: {}"),
+    ("Network error: {}", "Netzwerkfehler: {}"),
+    ("File system error: {}", "Dateisystemfehler: {}"),
+    ("Encryption error: {}", "Verschlüsselungsfehler: {}"),
+    ("Search error: {}", "Suchfehler: {}"),
+    ("Sync error: {}", "Synchronisierungsfehler: {}"),
+    ("Update error: {}", "Update-Fehler: {}"),
+    ("Operation cancelled", "Vorgang abgebrochen"),
+    ("Record not found", "Eintrag nicht gefunden"),
+    ("Failed to get app data dir", "Der Datenordner der App ist nicht verfügbar"),
+    ("Search service not initialized", "Die Suche ist noch nicht bereit"),
+];
+
+const ES: Table = &[
+    ("Show Brainbox", "Mostrar Brainbox"),
+    ("Hide to Tray", "Ocultar en la bandeja"),
+    ("Quick Capture", "Captura rápida"),
+    ("Capture Screenshot", "Capturar pantalla"),
+    ("Sync Now", "Sincronizar ahora"),
+    ("Recent Items", "Elementos recientes"),
+    ("Quit", "Salir"),
+    ("Untitled", "Sin título"),
+    ("New Note", "Nueva nota"),
+    ("Search", "Buscar"),
+    ("brainbox quick switcher", "Selector rápido de brainbox"),
+    ("Sync complete", "Sincronización completada"),
+    ("Vaults exported to the sync folder", "Bóvedas exportadas a la carpeta de sincronización"),
+    ("Sync failed", "Error de sincronización"),
+    ("Scheduled export failed", "Error en la exportación programada"),
+    ("Weekly digest ready", "Resumen semanal listo"),
+    ("Weekly digest failed", "Error en el resumen semanal"),
+    ("Reminder", "Recordatorio"),
+    ("Task due", "Tarea pendiente"),
+    ("Meeting notes ready", "Notas de la reunión listas"),
+    ("brainbox update available", "Actualización de brainbox disponible"),
+    (
+        "Version {} is ready to install from Settings > Updates",
+        "La versión {} está lista para instalarse desde Ajustes > Actualizaciones",
+    ),
+    ("[Conflict]", "[Conflicto]"),
+    ("Invalid password or key", "Contraseña o clave incorrecta"),
+    (
+        "Too many failed attempts, try again in {} seconds",
+        "Demasiados intentos fallidos, inténtalo de nuevo en {} segundos",
+    ),
+    ("Database is busy, please try again", "La base de datos está ocupada, inténtalo de nuevo"),
+    ("Database error: {}", "Error de base de datos: {}"),
+    ("Network error: {}", "Error de red: {}"),
+    ("File system error: {}", "Error del sistema de archivos: {}"),
+    ("Encryption error: {}", "Error de cifrado: {}"),
+    ("Search error: {}", "Error de búsqueda: {}"),
+    ("Sync error: {}", "Error de sincronización: {}"),
+    ("Update error: {}", "Error de actualización: {}"),
+    ("Operation cancelled", "Operación cancelada"),
+    ("Record not found", "Registro no encontrado"),
+    ("Failed to get app data dir", "No se encuentra la carpeta de datos de la aplicación"),
+    ("Search service not initialized", "La búsqueda aún no está lista"),
+];
+
+const FR: Table = &[
+    ("Show Brainbox", "Afficher Brainbox"),
+    ("Hide to Tray", "Réduire dans la zone de notification"),
+    ("Quick Capture", "Capture rapide"),
+    ("Capture Screenshot", "Capture d'écran"),
+    ("Sync Now", "Synchroniser maintenant"),
+    ("Recent Items", "Éléments récents"),
+    ("Quit", "Quitter"),
+    ("Untitled", "Sans titre"),
+    ("New Note", "Nouvelle note"),
+    ("Search", "Rechercher"),
+    ("brainbox quick switcher", "Sélecteur rapide brainbox"),
+    ("Sync complete", "Synchronisation terminée"),
+    ("Vaults exported to the sync folder", "Coffres exportés vers le dossier de synchronisation"),
+    ("Sync failed", "Échec de la synchronisation"),
+    ("Scheduled export failed", "Échec de l'export planifié"),
+    ("Weekly digest ready", "Résumé hebdomadaire prêt"),
+    ("Weekly digest failed", "Échec du résumé hebdomadaire"),
+    ("Reminder", "Rappel"),
+    ("Task due", "Tâche à échéance"),
+    ("Meeting notes ready", "Notes de réunion prêtes"),
+    ("brainbox update available", "Mise à jour de brainbox disponible"),
+    (
+        "Version {} is ready to install from Settings > Updates",
+        "La version {} peut être installée depuis Réglages > Mises à jour",
+    ),
+    ("[Conflict]", "[Conflit]"),
+    ("Invalid password or key", "Mot de passe ou clé incorrect"),
+    ("Too many failed attempts, try again in {} seconds", "Trop de tentatives échouées, réessayez dans {} secondes"),
+    ("Database is busy, please try again", "La base de données est occupée, veuillez réessayer"),
+    ("Database error: {}", "Erreur de base de données : {}"),
+    ("Network error: {}", "Erreur réseau : {}"),
+    ("File system error: {}", "Erreur du système de fichiers : {}"),
+    ("Encryption error: {}", "Erreur de chiffrement : {}"),
+    ("Search error: {}", "Erreur de recherche : {}"),
+    ("Sync error: {}", "Erreur de synchronisation : {}"),
+    ("Update error: {}", "Erreur de mise à jour : {}"),
+    ("Operation cancelled", "Opération annulée"),
+    ("Record not found", "Enregistrement introuvable"),
+    ("Failed to get app data dir", "Le dossier de données de l'application est introuvable"),
+    ("Search service not initialized", "La recherche n'est pas encore prête"),
+];
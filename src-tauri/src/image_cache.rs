@@ -0,0 +1,201 @@
+// image_cache.rs - Local copies of item images for brainbox
+// Remote images are downloaded, downscaled, re-encoded as WebP under the app data dir and served over the bbimg:// protocol
+
+use image::{codecs::webp::WebPEncoder, imageops::FilterType, ColorType};
+use rusqlite::{params, Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::error::BrainboxError;
+
+/// URI scheme the webview uses to load cached images
+pub const SCHEME: &str = "bbimg";
+/// Largest download accepted, in bytes
+const MAX_DOWNLOAD_BYTES: u64 = 10 * 1024 * 1024;
+/// Images larger than this on either side are scaled down before caching
+const MAX_DIMENSION: u32 = 1600;
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(20);
+/// Path prefix under which the protocol serves generated thumbnails
+pub const THUMBNAIL_PREFIX: &str = "thumbs/";
+
+pub fn create_table(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS cached_images (
+            file_name TEXT PRIMARY KEY,
+            source_url TEXT NOT NULL,
+            byte_size INTEGER NOT NULL,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+pub fn cache_dir() -> Option<PathBuf> {
+    Some(crate::data_dir::root()?.join("brainbox").join("images"))
+}
+
+/// URL the webview loads a cached file from. Windows (WebView2) exposes custom schemes as http://<scheme>.localhost
+pub fn asset_url(path: &str) -> String {
+    if cfg!(target_os = "windows") {
+        format!("http://{}.localhost/{}", SCHEME, path)
+    } else {
+        format!("{}://localhost/{}", SCHEME, path)
+    }
+}
+
+/// Cached file name referenced by an image URL, if it points at the local cache
+fn local_file_name(url: &str) -> Option<&str> {
+    let rest = url
+        .strip_prefix(&format!("{}://localhost/", SCHEME))
+        .or_else(|| url.strip_prefix(&format!("http://{}.localhost/", SCHEME)))?;
+    is_safe_file_name(rest).then_some(rest)
+}
+
+pub fn is_local_url(url: &str) -> bool {
+    local_file_name(url).is_some()
+}
+
+/// Bytes of a cached image referenced by a local image URL
+pub fn read_local(url: &str) -> Option<Vec<u8>> {
+    std::fs::read(cache_dir()?.join(local_file_name(url)?)).ok()
+}
+
+fn is_safe_file_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '_' || c == '-')
+}
+
+/// The original remote URL for an image that was cached, so exports and sync stay portable across devices
+pub fn portable_image(conn: &Connection, image: Option<String>) -> Option<String> {
+    let image = image?;
+    let Some(file_name) = local_file_name(&image) else {
+        return Some(image);
+    };
+    let source: Option<String> = create_table(conn)
+        .and_then(|_| {
+            conn.query_row(
+                "SELECT source_url FROM cached_images WHERE file_name = ?1",
+                [file_name],
+                |row| row.get(0),
+            )
+            .optional()
+        })
+        .ok()
+        .flatten();
+    Some(source.unwrap_or(image))
+}
+
+fn download(url: &str) -> Result<Vec<u8>, BrainboxError> {
+    let resp = crate::http::send(crate::http::client().get(url).timeout(DOWNLOAD_TIMEOUT))?;
+    if !resp.status().is_success() {
+        return Err(BrainboxError::Network(format!("Image download failed with status {}", resp.status())));
+    }
+    if resp.content_length().is_some_and(|len| len > MAX_DOWNLOAD_BYTES) {
+        return Err(BrainboxError::InvalidInput("Image is larger than 10 MB".to_string()));
+    }
+    let mut bytes = Vec::new();
+    resp.take(MAX_DOWNLOAD_BYTES + 1).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 > MAX_DOWNLOAD_BYTES {
+        return Err(BrainboxError::InvalidInput("Image is larger than 10 MB".to_string()));
+    }
+    Ok(bytes)
+}
+
+/// Decode, downscale to fit `max_dimension` and re-encode as lossless WebP
+pub fn to_webp(bytes: &[u8], max_dimension: u32) -> Result<Vec<u8>, BrainboxError> {
+    let mut img = image::load_from_memory(bytes)
+        .map_err(|e| BrainboxError::InvalidInput(format!("Unsupported image: {}", e)))?;
+    if img.width() > max_dimension || img.height() > max_dimension {
+        img = img.resize(max_dimension, max_dimension, FilterType::Triangle);
+    }
+    let rgba = img.to_rgba8();
+    let mut out = Vec::new();
+    WebPEncoder::new_lossless(&mut out)
+        .encode(rgba.as_raw(), rgba.width(), rgba.height(), ColorType::Rgba8)
+        .map_err(|e| BrainboxError::Other(format!("Failed to encode image: {}", e)))?;
+    Ok(out)
+}
+
+/// Write to a temp file first so a crash never leaves a truncated image behind
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, bytes)?;
+    std::fs::rename(&tmp, path)
+}
+
+/// Cache image bytes from a local file (content-addressed, so the same image is stored once) and return the file name
+pub fn cache_image_bytes(bytes: &[u8]) -> Result<String, BrainboxError> {
+    let dir = cache_dir().ok_or("Failed to get app data dir")?;
+    std::fs::create_dir_all(&dir)?;
+    let file_name = format!("{:x}.webp", Sha256::digest(bytes));
+    let path = dir.join(&file_name);
+    if !path.exists() {
+        write_atomic(&path, &to_webp(bytes, MAX_DIMENSION)?)?;
+    }
+    Ok(file_name)
+}
+
+/// Download `url` into the cache (reusing an existing copy of the same URL) and return its local URL, with the
+/// image's EXIF when it was downloaded now (re-encoding drops it, so a reused copy has none)
+pub fn cache_remote_image(conn: &Connection, url: &str) -> Result<(String, Option<crate::exif::ImageMetadata>), BrainboxError> {
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        return Err(BrainboxError::InvalidInput("Only http(s) images can be cached".to_string()));
+    }
+    create_table(conn)?;
+    let dir = cache_dir().ok_or("Failed to get app data dir")?;
+    std::fs::create_dir_all(&dir)?;
+
+    let file_name = format!("{:x}.webp", Sha256::digest(url.as_bytes()));
+    let path = dir.join(&file_name);
+    let mut metadata = None;
+    if !path.exists() {
+        let bytes = download(url)?;
+        metadata = crate::exif::read(&bytes);
+        let webp = to_webp(&bytes, MAX_DIMENSION)?;
+        write_atomic(&path, &webp)?;
+        conn.execute(
+            "INSERT OR REPLACE INTO cached_images (file_name, source_url, byte_size, created_at) VALUES (?1, ?2, ?3, ?4)",
+            params![file_name, url, webp.len() as i64, chrono::Utc::now().to_rfc3339()],
+        )?;
+    }
+    Ok((asset_url(&file_name), metadata))
+}
+
+fn respond(status: u16, content_type: &str, body: Vec<u8>) -> tauri::http::Response<Cow<'static, [u8]>> {
+    let mut builder = tauri::http::Response::builder()
+        .status(status)
+        .header("Content-Type", content_type);
+    // File names are content-addressed by source URL (favicons by host), so a served file rarely changes
+    if status == 200 {
+        builder = builder.header("Cache-Control", "max-age=31536000, immutable");
+    }
+    builder
+        .body(Cow::<'static, [u8]>::Owned(body))
+        .unwrap_or_else(|_| tauri::http::Response::new(Cow::Borrowed(&[][..])))
+}
+
+/// Handler for the bbimg:// protocol; only serves files from the image cache, thumbnail, favicon and cover directories
+pub fn handle_request(request: &tauri::http::Request<Vec<u8>>) -> tauri::http::Response<Cow<'static, [u8]>> {
+    let path = request.uri().path().trim_start_matches('/');
+    let (dir, name) = if let Some(name) = path.strip_prefix(THUMBNAIL_PREFIX) {
+        (crate::thumbnails::thumbnail_dir(), name)
+    } else if let Some(name) = path.strip_prefix(crate::favicons::FAVICON_PREFIX) {
+        (crate::favicons::cache_dir(), name)
+    } else if let Some(name) = path.strip_prefix(crate::covers::COVER_PREFIX) {
+        (crate::covers::cover_dir(), name)
+    } else {
+        (cache_dir(), path)
+    };
+    if !is_safe_file_name(name) {
+        return respond(400, "text/plain", b"Invalid image path".to_vec());
+    }
+    match dir.map(|dir| std::fs::read(dir.join(name))) {
+        Some(Ok(bytes)) => respond(200, if name.ends_with(".jpg") { "image/jpeg" } else { "image/webp" }, bytes),
+        _ => respond(404, "text/plain", b"Image not found".to_vec()),
+    }
+}
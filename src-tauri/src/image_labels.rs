@@ -0,0 +1,217 @@
+// image_labels.rs - Recognised text and object labels for brainbox image items
+// An optional local vision model (any Ollama model that accepts images, such as llava) reads the text in a photo
+// and names what it shows, so searching "receipt" or "whiteboard" finds the right images. The text and labels
+// are indexed with the item and sealed under the vault's content key like it. Labels are added to the item's tags,
+// which are stored in the clear, only in vaults without a password.
+
+use base64::Engine;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::envelope;
+use crate::error::BrainboxError;
+use crate::http;
+use crate::image_cache;
+use crate::ollama_options::{self, GenerationOptions, RequestOptions};
+use crate::vault::{Vault, VaultItem};
+
+/// Most labels kept per image
+const MAX_LABELS: usize = 5;
+/// Longest label accepted as a tag; anything longer is a sentence, not a label
+const MAX_LABEL_CHARS: usize = 32;
+/// Vision models are slower than text generation, more so while the model loads
+const ANALYZE_TIMEOUT: Duration = Duration::from_secs(180);
+
+const PROMPT: &str = "Transcribe any text visible in this image, then give up to five short lowercase labels for what it \
+shows (for example receipt, whiteboard, document, screenshot, diagram, person, food). \
+Reply with JSON only: {\"text\": \"...\", \"labels\": [\"...\"]}";
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ImageAnalysis {
+    /// Text read from the image, empty when it has none
+    pub text: String,
+    pub labels: Vec<String>,
+    pub model: String,
+    pub analyzed_at: String,
+}
+
+#[derive(Serialize)]
+struct VisionRequest<'a> {
+    model: &'a str,
+    prompt: &'a str,
+    images: Vec<String>,
+    stream: bool,
+    format: &'a str,
+    #[serde(flatten)]
+    options: RequestOptions,
+}
+
+#[derive(Deserialize)]
+struct VisionResponse {
+    response: String,
+}
+
+#[derive(Deserialize, Default)]
+struct ModelReply {
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    labels: Vec<String>,
+}
+
+pub fn create_table(conn: &Connection) -> rusqlite::Result<()> {
+    let existing: Option<String> = conn
+        .query_row("SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'image_analysis'", [], |row| row.get(0))
+        .optional()?;
+    // Analyses stored before they were sealed are dropped; `pending` finds their images again
+    if existing.is_some_and(|sql| sql.contains("text TEXT")) {
+        conn.execute("DROP TABLE image_analysis", [])?;
+    }
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS image_analysis (
+            item_id INTEGER PRIMARY KEY REFERENCES vault_items(id) ON DELETE CASCADE,
+            text BLOB NOT NULL,
+            labels BLOB NOT NULL,
+            model TEXT NOT NULL,
+            analyzed_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Ask the vision model for the text in an image and labels for it
+fn analyze(base_url: &str, model: &str, bytes: &[u8], options: &GenerationOptions) -> Result<(String, Vec<String>), BrainboxError> {
+    // Cached images are WebP, which not every model runtime decodes, so send PNG
+    let decoded = image::load_from_memory(bytes).map_err(|e| BrainboxError::InvalidInput(format!("Unreadable image: {}", e)))?;
+    let mut png = Vec::new();
+    decoded
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageOutputFormat::Png)
+        .map_err(|e| BrainboxError::Other(format!("Failed to encode image: {}", e)))?;
+    let body = VisionRequest {
+        model,
+        prompt: PROMPT,
+        images: vec![base64::engine::general_purpose::STANDARD.encode(&png)],
+        stream: false,
+        format: "json",
+        options: options.request(),
+    };
+    let resp = http::send(
+        http::client()
+            .post(format!("{}/api/generate", base_url))
+            .json(&body)
+            .timeout(ANALYZE_TIMEOUT),
+    )?;
+    if !resp.status().is_success() {
+        return Err(BrainboxError::Network(format!("Ollama returned status {}", resp.status())));
+    }
+    let response: VisionResponse = resp.json()?;
+    // Small models sometimes answer with prose despite the JSON format; keep it as the text
+    let reply = serde_json::from_str::<ModelReply>(&response.response)
+        .unwrap_or_else(|_| ModelReply { text: response.response.clone(), labels: Vec::new() });
+    let labels = crate::tags::normalize(&reply.labels)
+        .into_iter()
+        .filter(|label| label.chars().count() <= MAX_LABEL_CHARS && !label.contains('\n'))
+        .take(MAX_LABELS)
+        .collect();
+    Ok((reply.text.trim().to_string(), labels))
+}
+
+fn save(conn: &Connection, item_id: i64, key: &[u8; 32], analysis: &ImageAnalysis) -> Result<(), BrainboxError> {
+    create_table(conn)?;
+    conn.execute(
+        "INSERT OR REPLACE INTO image_analysis (item_id, text, labels, model, analyzed_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            item_id,
+            envelope::seal(key, analysis.text.as_bytes())?,
+            envelope::seal(key, serde_json::to_string(&analysis.labels)?.as_bytes())?,
+            analysis.model,
+            analysis.analyzed_at
+        ],
+    )?;
+    Ok(())
+}
+
+fn open_text(key: &[u8; 32], sealed: &[u8]) -> Result<String, BrainboxError> {
+    String::from_utf8(envelope::open(key, sealed)?).map_err(|_| BrainboxError::Crypto("Invalid UTF-8".to_string()))
+}
+
+/// `key` is the content key of the item's vault
+pub fn get(conn: &Connection, item_id: i64, key: &[u8; 32]) -> Result<Option<ImageAnalysis>, BrainboxError> {
+    create_table(conn)?;
+    let row = conn
+        .query_row(
+            "SELECT text, labels, model, analyzed_at FROM image_analysis WHERE item_id = ?1",
+            [item_id],
+            |row| Ok((row.get::<_, Vec<u8>>(0)?, row.get::<_, Vec<u8>>(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?)),
+        )
+        .optional()?;
+    let Some((text, labels, model, analyzed_at)) = row else { return Ok(None) };
+    Ok(Some(ImageAnalysis {
+        text: open_text(key, &text)?,
+        labels: serde_json::from_str(&open_text(key, &labels)?).unwrap_or_default(),
+        model,
+        analyzed_at,
+    }))
+}
+
+/// Analyze the cached image of an item, store the result sealed with `key` (the vault's content key) and add the
+/// labels to the item's tags unless the vault has a password
+pub fn analyze_item(conn: &Connection, item_id: i64, key: &[u8; 32], base_url: &str, model: &str) -> Result<ImageAnalysis, BrainboxError> {
+    let item = VaultItem::get_by_id(conn, item_id)?;
+    let image = item.image.ok_or_else(|| BrainboxError::InvalidInput("Item has no image".to_string()))?;
+    let bytes = image_cache::read_local(&image)
+        .ok_or_else(|| BrainboxError::InvalidInput("Cache the item's image before analyzing it".to_string()))?;
+    let options = ollama_options::resolve(conn, "image_labels", None)?;
+    let (text, labels) = analyze(base_url, model, &bytes, &options)?;
+    let analysis = ImageAnalysis { text, labels, model: model.to_string(), analyzed_at: chrono::Utc::now().to_rfc3339() };
+    save(conn, item_id, key, &analysis)?;
+    if Vault::get_by_id(conn, item.vault_id)?.is_some_and(|vault| !vault.has_password) {
+        crate::tags::add(conn, item_id, &analysis.labels)?;
+    }
+    Ok(analysis)
+}
+
+/// Image items in a vault that have a cached image but haven't been analyzed yet
+pub fn pending(conn: &Connection, vault_id: i64) -> Result<Vec<i64>, BrainboxError> {
+    create_table(conn)?;
+    let mut stmt = conn.prepare(
+        "SELECT id, image FROM vault_items
+         WHERE vault_id = ?1 AND item_type = 'image' AND image IS NOT NULL AND deleted_at IS NULL
+           AND id NOT IN (SELECT item_id FROM image_analysis)
+         ORDER BY id",
+    )?;
+    let rows = stmt
+        .query_map([vault_id], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(rows.into_iter().filter(|(_, image)| image_cache::is_local_url(image)).map(|(id, _)| id).collect())
+}
+
+/// Search text for an image item's recognised text and labels, used when the item is indexed
+pub fn search_text(conn: &Connection, item_id: i64, key: &[u8; 32]) -> Option<String> {
+    let analysis = get(conn, item_id, key).ok()??;
+    let text = [analysis.text, analysis.labels.join(" ")]
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+    (!text.is_empty()).then_some(text)
+}
+
+/// Drop an item's analysis, e.g. when it moves to a vault with another key; it is redone on the next pass
+pub fn forget_item(conn: &Connection, item_id: i64) -> Result<(), BrainboxError> {
+    create_table(conn)?;
+    conn.execute("DELETE FROM image_analysis WHERE item_id = ?1", [item_id])?;
+    Ok(())
+}
+
+/// Drop the analyses of a vault's items, e.g. when its content key changes
+pub fn forget_vault(conn: &Connection, vault_id: i64) -> Result<(), BrainboxError> {
+    create_table(conn)?;
+    conn.execute(
+        "DELETE FROM image_analysis WHERE item_id IN (SELECT id FROM vault_items WHERE vault_id = ?1)",
+        [vault_id],
+    )?;
+    Ok(())
+}
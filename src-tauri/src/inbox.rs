@@ -0,0 +1,334 @@
+// inbox.rs - Inbox for quick captures in brainbox
+// Captures from the browser extension, brainbox:// links and the HTTP capture endpoint can go straight into a
+// designated Inbox vault instead of asking for a vault each time. Items are sorted out later with `triage`, which
+// moves them into their real vault (re-encrypting under that vault's key) and tags them. Capture rules
+// (capture_rules.rs) can file a capture directly into a vault instead, where that vault's capture tags and
+// auto-summarize setting apply as well.
+
+use rusqlite::Connection;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Runtime};
+
+use crate::envelope;
+use crate::error::BrainboxError;
+use crate::settings;
+use crate::vault::{self, Vault, VaultItem, VaultSettings, AUTO_SUMMARIZE_SETTING, CAPTURE_TAGS_SETTING};
+
+/// Whether captures skip the capture dialog and land in the Inbox
+const ENABLED_KEY: &str = "inbox.enabled";
+const VAULT_KEY: &str = "inbox.vault_id";
+const INBOX_NAME: &str = "Inbox";
+/// Emitted with the new item count whenever the Inbox changes
+pub const UPDATED_EVENT: &str = "inbox-updated";
+/// Emitted for captures whose capture rule asks for an AI summary
+pub const AUTO_SUMMARIZE_EVENT: &str = "capture-auto-summarize";
+/// Largest capture request body read by the HTTP endpoint
+pub const MAX_CAPTURE_BODY_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Text highlighted on the page when it was captured by the browser extension
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Selection {
+    pub text: String,
+    /// The highlight's markup, used when no plain text came with it
+    pub html: Option<String>,
+}
+
+impl Selection {
+    /// The `selection` and `selection_html` parameters of a capture link or request, if either is set
+    pub fn from_query(query: &str) -> Option<Selection> {
+        let text = crate::deeplink::query_param(query, "selection").filter(|text| !text.trim().is_empty());
+        let html = crate::deeplink::query_param(query, "selection_html").filter(|html| !html.trim().is_empty());
+        (text.is_some() || html.is_some()).then(|| Selection { text: text.unwrap_or_default(), html })
+    }
+
+    /// The highlight as a markdown quote, kept line for line, followed by its source and the capture time
+    pub fn to_markdown(&self, url: &str, title: &str) -> String {
+        let text = match &self.html {
+            Some(html) if self.text.trim().is_empty() => {
+                crate::rich_text::html_to_markdown(html, &mut |src| Some(src.to_string())).markdown
+            }
+            _ => self.text.clone(),
+        };
+        let quote: Vec<String> = text
+            .trim_end()
+            .lines()
+            .map(|line| if line.is_empty() { ">".to_string() } else { format!("> {}", line) })
+            .collect();
+        let source = match (title.trim(), url.trim()) {
+            ("", "") => String::new(),
+            ("", url) => format!("<{}>, ", url),
+            (title, "") => format!("{}, ", title),
+            (title, url) => format!("[{}]({}), ", title.replace(['[', ']'], ""), url),
+        };
+        format!("{}\n\n— {}captured {}\n", quote.join("\n"), source, chrono::Local::now().format("%Y-%m-%d %H:%M"))
+    }
+}
+
+/// Payload of the "capture-from-protocol" event that opens the capture dialog
+pub fn capture_event(url: &str, title: &str, selection: Option<&Selection>) -> serde_json::Value {
+    match selection {
+        Some(selection) => serde_json::json!({
+            "url": url,
+            "title": title,
+            "selection": selection.text,
+            "selectionHtml": selection.html,
+            "content": selection.to_markdown(url, title),
+        }),
+        None => serde_json::json!({ "url": url, "title": title }),
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct InboxItem {
+    pub id: i64,
+    pub title: String,
+    pub content: String,
+    pub item_type: String,
+    pub created_at: String,
+    pub image: Option<String>,
+}
+
+pub fn is_enabled(conn: &Connection) -> Result<bool, BrainboxError> {
+    Ok(settings::get_bool(conn, ENABLED_KEY)?)
+}
+
+pub fn set_enabled(conn: &Connection, enabled: bool) -> Result<(), BrainboxError> {
+    settings::set_bool(conn, ENABLED_KEY, enabled)?;
+    Ok(())
+}
+
+/// The Inbox vault id, if one has been created and not deleted
+pub fn vault_id(conn: &Connection) -> Result<Option<i64>, BrainboxError> {
+    Vault::create_table(conn)?;
+    let Some(id) = settings::get(conn, VAULT_KEY)?.and_then(|v| v.parse::<i64>().ok()) else { return Ok(None) };
+    Ok(Vault::get_by_id(conn, id)?.filter(|v| v.deleted_at.is_none()).map(|v| v.id))
+}
+
+/// The Inbox vault, created as a passwordless vault on first use
+pub fn ensure_vault(conn: &Connection) -> Result<i64, BrainboxError> {
+    if let Some(id) = vault_id(conn)? {
+        return Ok(id);
+    }
+    let now = chrono::Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO vaults (name, encrypted_password, created_at, cover_image, has_password, uuid, updated_at, icon) VALUES (?1, ?2, ?3, NULL, 0, ?4, ?5, ?6)",
+        rusqlite::params![INBOX_NAME, Vec::<u8>::new(), now, uuid::Uuid::new_v4().to_string(), now, "📥"],
+    )?;
+    let id = conn.last_insert_rowid();
+    envelope::create_master_key(conn, id, &crate::derive_key_from_password("", &id.to_string(), 100_000))?;
+    settings::set(conn, VAULT_KEY, &id.to_string())?;
+    Ok(id)
+}
+
+fn content_key(conn: &Connection, vault_id: i64) -> Result<crate::VaultKey, BrainboxError> {
+    let kek = crate::resolve_key(conn, vault_id, None)?;
+    envelope::content_key(conn, vault_id, &kek)
+}
+
+/// Number of items waiting in the Inbox
+pub fn count(conn: &Connection) -> Result<i64, BrainboxError> {
+    match vault_id(conn)? {
+        Some(id) => {
+            VaultItem::create_table(conn)?;
+            Ok(VaultItem::count_by_vault(conn, id)?)
+        }
+        None => Ok(0),
+    }
+}
+
+/// Save a capture into the Inbox and index it
+pub fn add(conn: &Connection, title: &str, content: &str) -> Result<VaultItem, BrainboxError> {
+    let vault_id = ensure_vault(conn)?;
+    save(conn, vault_id, title, content, &[])
+}
+
+/// Save a capture into `vault_id` with `tags` and index it
+pub fn save(conn: &Connection, vault_id: i64, title: &str, content: &str, tags: &[String]) -> Result<VaultItem, BrainboxError> {
+    VaultItem::create_table(conn)?;
+    let key = content_key(conn, vault_id)?;
+    let item_type = vault::infer_item_type(content).to_string();
+    let title = if title.trim().is_empty() { content.trim() } else { title.trim() };
+    let item = VaultItem::insert(conn, vault_id, title, content, &item_type, &key)?;
+    let tags = if tags.is_empty() { Vec::new() } else { crate::tags::add(conn, item.id, tags)? };
+    let _ = crate::search::index_document(crate::search::DocumentInput {
+        id: item.id.to_string(),
+        title: item.title.clone(),
+        content: content.to_string(),
+        item_type,
+        created_at: item.created_at.clone(),
+        updated_at: item.updated_at.clone(),
+        tags,
+        vault_id: Some(vault_id),
+        ..Default::default()
+    });
+    Ok(item)
+}
+
+/// Inbox items, newest first
+pub fn list(conn: &Connection) -> Result<Vec<InboxItem>, BrainboxError> {
+    let Some(vault_id) = vault_id(conn)? else { return Ok(Vec::new()) };
+    VaultItem::create_table(conn)?;
+    let key = content_key(conn, vault_id)?;
+    let mut items = Vec::new();
+    for item in VaultItem::list_page_by_vault(conn, vault_id, vault::ItemSort::Created, Some(true), 0, None)? {
+        let content = String::from_utf8(envelope::open(&key, &item.content)?)
+            .map_err(|_| BrainboxError::Crypto("Invalid UTF-8".to_string()))?;
+        items.push(InboxItem {
+            id: item.id,
+            title: item.title,
+            item_type: item.item_type.unwrap_or_else(|| vault::infer_item_type(&content).to_string()),
+            content,
+            created_at: item.created_at,
+            image: item.image,
+        });
+    }
+    Ok(items)
+}
+
+/// Move an Inbox item into `target_vault_id`, re-encrypted under that vault's key, and add `tags` to it
+pub fn triage(conn: &Connection, item_id: i64, target_vault_id: i64, tags: &[String]) -> Result<(), BrainboxError> {
+    VaultItem::create_table(conn)?;
+    let inbox_id = vault_id(conn)?.ok_or_else(|| BrainboxError::NotFound("There is no Inbox".to_string()))?;
+    let item = VaultItem::get_by_id(conn, item_id)?;
+    if item.vault_id != inbox_id || item.deleted_at.is_some() {
+        return Err(BrainboxError::InvalidInput("Item is not in the Inbox".to_string()));
+    }
+    Vault::get_by_id(conn, target_vault_id)?
+        .filter(|v| v.deleted_at.is_none() && v.id != inbox_id)
+        .ok_or_else(|| BrainboxError::NotFound(format!("Vault {} not found", target_vault_id)))?;
+    let inbox_key = content_key(conn, inbox_id)?;
+    let content = String::from_utf8(envelope::open(&inbox_key, &item.content)?)
+        .map_err(|_| BrainboxError::Crypto("Invalid UTF-8".to_string()))?;
+    let target_key = content_key(conn, target_vault_id)?;
+    let item_type = item.item_type.clone().unwrap_or_else(|| vault::infer_item_type(&content).to_string());
+
+    conn.execute("BEGIN IMMEDIATE", [])?;
+    let moved = (|| -> Result<Vec<String>, BrainboxError> {
+        VaultItem::move_to_vault(conn, item_id, target_vault_id)?;
+        VaultItem::update_content(conn, item_id, &content, &target_key)?;
+        crate::annotations::reseal_item(conn, item_id, &inbox_key, &target_key)?;
+        crate::exif::reseal_item(conn, item_id, &inbox_key, &target_key)?;
+        crate::entities::forget_item(conn, item_id)?;
+        crate::image_labels::forget_item(conn, item_id)?;
+        crate::tags::add(conn, item_id, tags)
+    })();
+    let tags = match moved {
+        Ok(tags) => {
+            conn.execute("COMMIT", [])?;
+            tags
+        }
+        Err(e) => {
+            let _ = conn.execute("ROLLBACK", []);
+            return Err(e);
+        }
+    };
+    let updated = VaultItem::get_by_id(conn, item_id)?;
+    let _ = crate::search::index_document(crate::search::DocumentInput {
+        id: item_id.to_string(),
+        title: updated.title,
+        content,
+        item_type,
+        created_at: updated.created_at,
+        updated_at: updated.updated_at,
+        tags,
+        vault_id: Some(target_vault_id),
+        ..Default::default()
+    });
+    Ok(())
+}
+
+/// Tell the UI the Inbox changed
+pub fn notify<R: Runtime>(app: &AppHandle<R>, conn: &Connection) {
+    let _ = app.emit(UPDATED_EVENT, serde_json::json!({ "count": count(conn).unwrap_or(0) }));
+}
+
+/// Save a link capture without asking: into the vault picked by a matching capture rule, or the Inbox when
+/// that is turned on. With a selection the item holds the highlighted text as a quote instead of just the link.
+/// Returns false when the capture should be shown in the capture dialog instead.
+pub fn receive_capture<R: Runtime>(app: &AppHandle<R>, url: &str, title: &str, selection: Option<&Selection>) -> bool {
+    let Ok(conn) = crate::db::open() else { return false };
+    let url = url.trim();
+    if url.is_empty() && selection.is_none() {
+        return false;
+    }
+    let content = match selection {
+        Some(selection) => selection.to_markdown(url, title),
+        None => url.to_string(),
+    };
+    let title = if title.trim().is_empty() && selection.is_some() { url } else { title };
+    let inbox_enabled = is_enabled(&conn).unwrap_or(false);
+    let rule = crate::capture_rules::evaluate(&conn, url, title, &content).unwrap_or_else(|e| {
+        tracing::warn!("Failed to evaluate capture rules: {}", e);
+        None
+    });
+    let mut tags = rule.as_ref().map(|r| r.tags.clone()).unwrap_or_default();
+
+    // A rule's vault wins; a locked or missing one falls back to the Inbox
+    let target = rule.as_ref().and_then(|r| r.target_vault_id).filter(|id| {
+        Vault::get_by_id(&conn, *id).ok().flatten().is_some_and(|v| v.deleted_at.is_none())
+            && crate::resolve_key(&conn, *id, None).is_ok()
+    });
+    // The target vault's own capture defaults add to the rule's
+    let mut vault_summarizes = false;
+    if let Some(vault_id) = target {
+        let setting = |key| VaultSettings::get(&conn, vault_id, key).ok().flatten();
+        tags.extend(setting(CAPTURE_TAGS_SETTING).and_then(|v| serde_json::from_str::<Vec<String>>(&v).ok()).unwrap_or_default());
+        vault_summarizes = setting(AUTO_SUMMARIZE_SETTING).as_deref() == Some("true");
+    }
+    let saved = match target {
+        Some(vault_id) => save(&conn, vault_id, title, &content, &tags).map(|item| (item, false)),
+        None if inbox_enabled => ensure_vault(&conn).and_then(|id| save(&conn, id, title, &content, &tags)).map(|item| (item, true)),
+        None => return false,
+    };
+    let (item, to_inbox) = match saved {
+        Ok(saved) => saved,
+        Err(e) => {
+            tracing::warn!("Failed to save capture: {}", e);
+            return false;
+        }
+    };
+
+    if rule.as_ref().is_some_and(|r| r.auto_summarize) || vault_summarizes {
+        // Summaries come from the frontend's AI provider
+        let _ = app.emit(AUTO_SUMMARIZE_EVENT, serde_json::json!({ "itemId": item.id, "vaultId": item.vault_id }));
+    }
+    if let Some(rule) = &rule {
+        if rule.auto_archive && !url.is_empty() {
+            let request = crate::enrichment::EnrichmentRequest { item_id: item.id, url: url.to_string() };
+            if let Err(e) = crate::enrichment::enqueue(&conn, &[request]) {
+                tracing::warn!("Failed to queue capture for archiving: {}", e);
+            }
+            crate::wayback::archive_item_in_background(item.id, url.to_string());
+        }
+    }
+    if to_inbox {
+        notify(app, &conn);
+    }
+    true
+}
+
+/// Import files passed on the command line into the Inbox when it is turned on. Returns false when the app
+/// should ask which vault they go in instead.
+pub fn receive_files<R: Runtime>(app: &AppHandle<R>, paths: &[String]) -> bool {
+    let Ok(conn) = crate::db::open() else { return false };
+    if !is_enabled(&conn).unwrap_or(false) {
+        return false;
+    }
+    let imported = ensure_vault(&conn).and_then(|vault_id| {
+        let key = content_key(&conn, vault_id)?;
+        Ok(crate::drop_import::import_files(&conn, paths, vault_id, &key))
+    });
+    match imported {
+        Ok(results) => {
+            for failed in results.iter().filter(|r| r.error.is_some()) {
+                tracing::warn!("Could not capture {}: {}", failed.path, failed.error.as_deref().unwrap_or_default());
+            }
+            notify(app, &conn);
+            true
+        }
+        Err(e) => {
+            tracing::warn!("Failed to capture files: {}", e);
+            false
+        }
+    }
+}
@@ -0,0 +1,122 @@
+// instance.rs - Forwarding launches to the brainbox instance that is already running
+// The single-instance plugin does this on macOS and Linux. It is disabled on Windows, so there a second launch hands
+// its arguments to the running app through the local capture server and exits. A token the running app writes to
+// the data folder keeps web pages, which can reach localhost too, from forwarding anything, or from posting
+// captures unless they come from a browser extension.
+
+use base64::Engine;
+use lazy_static::lazy_static;
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// Where the running app listens for browser captures and forwarded launches
+pub const CAPTURE_SERVER_ADDR: &str = "127.0.0.1:51234";
+pub const FORWARD_PATH: &str = "/forward";
+const TOKEN_HEADER: &str = "X-Brainbox-Token";
+const TOKEN_FILE: &str = "instance-token";
+/// Origins browsers give extension pages; web pages can't send these
+const EXTENSION_ORIGINS: &[&str] = &["chrome-extension://", "moz-extension://", "safari-web-extension://"];
+/// Forwarded arguments are small; anything bigger isn't from brainbox
+const MAX_BODY_BYTES: u64 = 64 * 1024;
+const FORWARD_TIMEOUT: Duration = Duration::from_millis(800);
+
+lazy_static! {
+    static ref TOKEN: String = {
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes);
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+    };
+}
+
+#[derive(Serialize, Deserialize)]
+struct Forwarded {
+    args: Vec<String>,
+    cwd: Option<String>,
+}
+
+fn token_path() -> Option<PathBuf> {
+    Some(crate::data_dir::root()?.join("brainbox").join(TOKEN_FILE))
+}
+
+/// Let later launches find this instance; called once the capture server is listening
+pub fn publish_token() {
+    let Some(path) = token_path() else { return };
+    let written = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(&path, TOKEN.as_bytes()));
+    if let Err(e) = written {
+        tracing::warn!("Failed to write instance token: {}", e);
+    }
+}
+
+fn has_token(request: &tiny_http::Request) -> bool {
+    request
+        .headers()
+        .iter()
+        .any(|header| header.field.equiv(TOKEN_HEADER) && header.value.as_str() == TOKEN.as_str())
+}
+
+/// Whether a capture POST came from brainbox's browser extension or a local tool holding the token, rather
+/// than a form on some web page
+pub fn may_post_capture(request: &tiny_http::Request) -> bool {
+    has_token(request)
+        || request.headers().iter().any(|header| {
+            header.field.equiv("Origin") && EXTENSION_ORIGINS.iter().any(|scheme| header.value.as_str().starts_with(scheme))
+        })
+}
+
+/// Handle a launch forwarded to FORWARD_PATH; returns the HTTP status to answer with
+pub fn handle_forward(app: &AppHandle, request: &mut tiny_http::Request) -> u16 {
+    if *request.method() != tiny_http::Method::Post {
+        return 405;
+    }
+    if !has_token(request) {
+        return 403;
+    }
+    let mut body = Vec::new();
+    if request.as_reader().take(MAX_BODY_BYTES).read_to_end(&mut body).is_err() {
+        return 400;
+    }
+    let Ok(forwarded) = serde_json::from_slice::<Forwarded>(&body) else { return 400 };
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+    crate::deeplink::handle_args(app, &forwarded.args, forwarded.cwd.as_deref().map(Path::new));
+    204
+}
+
+/// Hand this launch's capture or deep link to a running instance. Returns true when one took it; launches with
+/// nothing to forward, or with no instance running, start the app as usual.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub fn forward_to_running() -> bool {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let cwd = std::env::current_dir().ok();
+    if crate::deeplink::from_args(&args, cwd.as_deref()).is_none() {
+        return false;
+    }
+    let Some(token) = token_path().and_then(|path| std::fs::read_to_string(path).ok()) else { return false };
+    let Ok(body) = serde_json::to_vec(&Forwarded { args, cwd: cwd.map(|c| c.display().to_string()) }) else { return false };
+    let Ok(addr) = CAPTURE_SERVER_ADDR.parse() else { return false };
+    let Ok(mut stream) = TcpStream::connect_timeout(&addr, FORWARD_TIMEOUT) else { return false };
+    let _ = stream.set_read_timeout(Some(FORWARD_TIMEOUT));
+    let head = format!(
+        "POST {} HTTP/1.1\r\nHost: {}\r\n{}: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        FORWARD_PATH,
+        CAPTURE_SERVER_ADDR,
+        TOKEN_HEADER,
+        token.trim(),
+        body.len()
+    );
+    if stream.write_all(head.as_bytes()).and_then(|_| stream.write_all(&body)).is_err() {
+        return false;
+    }
+    let mut status_line = [0u8; 12];
+    stream.read_exact(&mut status_line).is_ok() && status_line.ends_with(b" 204")
+}
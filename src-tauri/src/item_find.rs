@@ -0,0 +1,113 @@
+// item_find.rs - Find within a single note for brainbox
+// The editor's find bar used to fetch the whole note and scan it in JavaScript, which stalls on very large notes.
+// The backend decrypts the note and returns only where the matches are. Offsets count UTF-16 code units, the way
+// JavaScript indexes strings, so the editor can select them as they are.
+
+use serde::Serialize;
+
+/// Matches returned when the caller doesn't pass a limit
+pub const DEFAULT_LIMIT: usize = 1000;
+pub const MAX_LIMIT: usize = 10_000;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct FindMatch {
+    /// UTF-16 offset of the first matched character
+    pub start: usize,
+    /// UTF-16 offset just past the match
+    pub end: usize,
+    /// 1-based line the match starts on
+    pub line: usize,
+    /// UTF-16 offset of the match from the start of its line
+    pub column: usize,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct FindResult {
+    pub matches: Vec<FindMatch>,
+    /// More matches exist than were returned
+    pub truncated: bool,
+}
+
+/// Lowercased characters of `text`, each with the index of the character it came from
+fn fold(text: &str) -> (Vec<char>, Vec<usize>) {
+    let mut folded = Vec::with_capacity(text.len());
+    let mut origin = Vec::with_capacity(text.len());
+    for (index, c) in text.chars().enumerate() {
+        for lower in c.to_lowercase() {
+            folded.push(lower);
+            origin.push(index);
+        }
+    }
+    (folded, origin)
+}
+
+/// Non-overlapping matches as (first, past-the-end) character indices
+fn char_ranges(content: &str, query: &str, case_sensitive: bool, limit: usize) -> (Vec<(usize, usize)>, bool) {
+    let (haystack, origin) = if case_sensitive {
+        let chars: Vec<char> = content.chars().collect();
+        let origin = (0..chars.len()).collect();
+        (chars, origin)
+    } else {
+        fold(content)
+    };
+    let needle: Vec<char> = if case_sensitive { query.chars().collect() } else { fold(query).0 };
+    let mut ranges = Vec::new();
+    if needle.is_empty() || needle.len() > haystack.len() {
+        return (ranges, false);
+    }
+    let mut at = 0;
+    while at + needle.len() <= haystack.len() {
+        if haystack[at..at + needle.len()] == needle[..] {
+            if ranges.len() == limit {
+                return (ranges, true);
+            }
+            ranges.push((origin[at], origin[at + needle.len() - 1] + 1));
+            at += needle.len();
+        } else {
+            at += 1;
+        }
+    }
+    (ranges, false)
+}
+
+/// Where `query` occurs in `content`, at most `limit` matches from the top
+pub fn find(content: &str, query: &str, case_sensitive: bool, limit: usize) -> FindResult {
+    let (ranges, truncated) = char_ranges(content, query, case_sensitive, limit);
+    let mut matches: Vec<FindMatch> =
+        ranges.iter().map(|_| FindMatch { start: 0, end: 0, line: 0, column: 0 }).collect();
+
+    // Every character index a match needs converted, walked through once in order
+    let mut wanted: Vec<(usize, usize, bool)> = Vec::with_capacity(ranges.len() * 2);
+    for (slot, (start, end)) in ranges.iter().enumerate() {
+        wanted.push((*start, slot, true));
+        wanted.push((*end, slot, false));
+    }
+    wanted.sort_unstable();
+    let mut wanted = wanted.into_iter().peekable();
+
+    let (mut utf16, mut line, mut line_start) = (0, 1, 0);
+    let mut chars = content.chars();
+    let mut index = 0;
+    while let Some(&(char_index, slot, is_start)) = wanted.peek() {
+        if char_index == index {
+            let found = &mut matches[slot];
+            if is_start {
+                found.start = utf16;
+                found.line = line;
+                found.column = utf16 - line_start;
+            } else {
+                found.end = utf16;
+            }
+            wanted.next();
+            continue;
+        }
+        let Some(c) = chars.next() else { break };
+        utf16 += c.len_utf16();
+        index += 1;
+        if c == '\n' {
+            line += 1;
+            line_start = utf16;
+        }
+    }
+    FindResult { matches, truncated }
+}
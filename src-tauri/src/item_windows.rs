@@ -0,0 +1,172 @@
+// item_windows.rs - Notes opened in windows of their own
+// Each window shows one item and is labelled "item-<id>", which is how the backend finds the windows open for an
+// item when it changes. The open windows and their places on screen are saved whenever one opens or closes and
+// when the app quits, and reopened at the next launch.
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Listener, Manager, WebviewUrl, WebviewWindow, WebviewWindowBuilder, WindowEvent};
+
+use crate::error::BrainboxError;
+use crate::settings;
+use crate::vault::{Vault, VaultItem};
+
+const LABEL_PREFIX: &str = "item-";
+/// JSON list of the windows open when last saved
+const SETTING_KEY: &str = "item_windows.open";
+/// Emitted to an item's windows with `{ itemId }` after the item changes
+pub const UPDATED_EVENT: &str = "item-updated";
+const DEFAULT_WIDTH: f64 = 520.0;
+const DEFAULT_HEIGHT: f64 = 640.0;
+
+/// A window to reopen, in logical pixels
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct SavedWindow {
+    item_id: i64,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[allow(non_snake_case)]
+struct ItemUpdatedEvent {
+    itemId: i64,
+}
+
+fn label(item_id: i64) -> String {
+    format!("{}{}", LABEL_PREFIX, item_id)
+}
+
+fn item_id(label: &str) -> Option<i64> {
+    label.strip_prefix(LABEL_PREFIX)?.parse().ok()
+}
+
+/// Items shown in a window of their own, with the window
+fn open_windows(app: &AppHandle) -> Vec<(i64, WebviewWindow)> {
+    app.webview_windows()
+        .into_iter()
+        .filter_map(|(label, window)| Some((item_id(&label)?, window)))
+        .collect()
+}
+
+/// Ids of the items open in their own windows
+pub fn list(app: &AppHandle) -> Vec<i64> {
+    let mut ids: Vec<i64> = open_windows(app).into_iter().map(|(id, _)| id).collect();
+    ids.sort_unstable();
+    ids
+}
+
+fn geometry(item_id: i64, window: &WebviewWindow) -> Option<SavedWindow> {
+    let scale = window.scale_factor().ok()?;
+    let position = window.outer_position().ok()?.to_logical::<f64>(scale);
+    let size = window.inner_size().ok()?.to_logical::<f64>(scale);
+    Some(SavedWindow { item_id, x: position.x, y: position.y, width: size.width, height: size.height })
+}
+
+/// Remember the open windows, leaving out one that is closing
+fn save(app: &AppHandle, closing: Option<i64>) {
+    let windows: Vec<SavedWindow> = open_windows(app)
+        .into_iter()
+        .filter(|(id, _)| Some(*id) != closing)
+        .filter_map(|(id, window)| geometry(id, &window))
+        .collect();
+    let saved = serde_json::to_string(&windows)
+        .map_err(BrainboxError::from)
+        .and_then(|json| Ok(settings::set(&crate::db::open()?, SETTING_KEY, &json)?));
+    if let Err(e) = saved {
+        tracing::warn!("Failed to save open note windows: {}", e);
+    }
+}
+
+/// Called as the app quits, while every window is still open
+pub fn save_on_quit(app: &AppHandle) {
+    save(app, None);
+}
+
+fn build(app: &AppHandle, item: &VaultItem, saved: Option<&SavedWindow>) -> Result<WebviewWindow, BrainboxError> {
+    let url = format!("index.html?window=item&itemId={}&vaultId={}", item.id, item.vault_id);
+    let title = if item.title.trim().is_empty() { crate::i18n::tr("Untitled") } else { item.title.trim().to_string() };
+    let mut builder = WebviewWindowBuilder::new(app, label(item.id), WebviewUrl::App(url.into()))
+        .title(title)
+        .min_inner_size(280.0, 200.0);
+    builder = match saved {
+        Some(saved) => builder.inner_size(saved.width, saved.height).position(saved.x, saved.y),
+        None => builder.inner_size(DEFAULT_WIDTH, DEFAULT_HEIGHT),
+    };
+    let window = builder.build()?;
+    let handle = app.clone();
+    let item_id = item.id;
+    window.on_window_event(move |event| {
+        if let WindowEvent::Destroyed = event {
+            // Windows closing because the app quits stay in the list for the next launch
+            if !crate::lifecycle::is_quitting() {
+                save(&handle, Some(item_id));
+            }
+        }
+    });
+    Ok(window)
+}
+
+/// Open an item in a window of its own, or bring its window forward if it already has one
+pub fn open(app: &AppHandle, conn: &Connection, item_id: i64) -> Result<(), BrainboxError> {
+    if let Some(window) = app.get_webview_window(&label(item_id)) {
+        let _ = window.unminimize();
+        window.show()?;
+        window.set_focus()?;
+        return Ok(());
+    }
+    let item = VaultItem::get_by_id(conn, item_id)?;
+    if item.deleted_at.is_some() {
+        return Err(BrainboxError::NotFound(format!("Item {} not found", item_id)));
+    }
+    build(app, &item, None)?.set_focus()?;
+    save(app, None);
+    Ok(())
+}
+
+/// Reopen the windows saved at the last quit. Items that were deleted since, or whose vault needs a password
+/// nobody has entered yet, are dropped.
+pub fn restore(app: &AppHandle) {
+    let Ok(conn) = crate::db::open() else { return };
+    let saved: Vec<SavedWindow> = settings::get(&conn, SETTING_KEY)
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    if saved.is_empty() {
+        return;
+    }
+    for window in &saved {
+        let Ok(item) = VaultItem::get_by_id(&conn, window.item_id) else { continue };
+        let locked = Vault::get_by_id(&conn, item.vault_id).ok().flatten().is_none_or(|vault| vault.has_password);
+        if item.deleted_at.is_some() || locked {
+            continue;
+        }
+        if let Err(e) = build(app, &item, Some(window)) {
+            tracing::warn!("Failed to reopen the window for item {}: {}", item.id, e);
+        }
+    }
+    save(app, None);
+}
+
+/// Tell an item's windows it changed. Changes the frontend reports without an item id go to every note window.
+fn route_change(app: &AppHandle, payload: &str) {
+    let changed = serde_json::from_str::<serde_json::Value>(payload).ok().and_then(|payload| {
+        let id = payload.get("itemId")?;
+        id.as_i64().or_else(|| id.as_str()?.parse().ok())
+    });
+    for (id, window) in open_windows(app) {
+        if changed.is_none_or(|changed| changed == id) {
+            let _ = app.emit_to(window.label(), UPDATED_EVENT, ItemUpdatedEvent { itemId: id });
+        }
+    }
+}
+
+/// Forward item changes to the note windows
+pub fn init(app: &AppHandle) {
+    let handle = app.clone();
+    // The frontend emits this after creating, editing or deleting items
+    app.listen_any("items-changed", move |event| route_change(&handle, event.payload()));
+}
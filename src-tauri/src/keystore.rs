@@ -0,0 +1,61 @@
+// keystore.rs - In-memory vault keys for brainbox
+// Vault keys used to be derived in the webview and sent with every command, leaving copies in JS memory and in each
+// command's argument buffers. Keys now live here once a vault is unlocked, in buffers that are wiped when dropped,
+// and commands look them up by vault id. Nothing is persisted; quitting or locking forgets them. A vault's
+// in-memory search index lives exactly as long as its key, so it is built and dropped from here.
+
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::error::BrainboxError;
+
+/// A 32-byte key that is zeroed when dropped
+pub type VaultKey = Zeroizing<[u8; 32]>;
+
+lazy_static! {
+    static ref KEYS: Mutex<HashMap<i64, VaultKey>> = Mutex::new(HashMap::new());
+}
+
+/// Take ownership of key bytes received from the frontend, wiping the original buffer
+pub fn key_from_bytes(mut bytes: Vec<u8>) -> Result<VaultKey, BrainboxError> {
+    if bytes.len() != 32 {
+        bytes.zeroize();
+        return Err(BrainboxError::InvalidInput("Key must be 32 bytes".to_string()));
+    }
+    let mut key = Zeroizing::new([0u8; 32]);
+    key.copy_from_slice(&bytes);
+    bytes.zeroize();
+    Ok(key)
+}
+
+pub fn insert(vault_id: i64, key: &[u8; 32]) {
+    if let Ok(mut keys) = KEYS.lock() {
+        keys.insert(vault_id, Zeroizing::new(*key));
+    }
+    crate::search::open_session(vault_id);
+}
+
+/// Copy of an unlocked vault's key
+pub fn get(vault_id: i64) -> Option<VaultKey> {
+    KEYS.lock().ok()?.get(&vault_id).cloned()
+}
+
+pub fn remove(vault_id: i64) {
+    if let Ok(mut keys) = KEYS.lock() {
+        keys.remove(&vault_id);
+    }
+    crate::search::close_session(vault_id);
+}
+
+pub fn clear() {
+    if let Ok(mut keys) = KEYS.lock() {
+        keys.clear();
+    }
+    crate::search::close_all_sessions();
+}
+
+pub fn unlocked_vault_ids() -> Vec<i64> {
+    KEYS.lock().map(|keys| keys.keys().copied().collect()).unwrap_or_default()
+}
@@ -1,169 +1,244 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
+mod error;
+mod db;
 mod search;
 mod capture;
 mod vault;
 mod sync;
+mod tasks;
+mod reminders;
+mod templates;
+mod logging;
+mod image_cache;
+mod thumbnails;
+mod hotkeys;
+mod switcher;
+mod deeplink;
+mod tray;
+mod autostart;
+mod lifecycle;
+mod drop_import;
+mod updater;
+mod http;
+mod enrichment;
+mod favicons;
+mod transcripts;
+mod site_content;
+mod saved_searches;
+mod tokenizers;
+mod access_log;
+mod covers;
+mod envelope;
+mod lockout;
+mod biometrics;
+mod keystore;
+mod emergency;
+mod share;
+mod sync_server;
+mod sync_captures;
+mod capture_cleanup;
+mod storage;
+mod progress;
+mod duplicates;
+mod tags;
+mod inbox;
+mod capture_rules;
+mod wayback;
+mod note_import;
+mod rich_text;
+mod exif;
+mod image_labels;
+mod boards;
+mod settings;
+mod data_dir;
+mod cli;
+mod instance;
+mod quick_actions;
+mod os_search;
+mod item_windows;
+mod zip_writer;
+mod diagnostics;
+mod i18n;
+mod scheduled_export;
+mod tombstones;
+mod sync_manifest;
+mod item_find;
+mod markdown;
+mod annotations;
+mod calendar;
+mod entities;
+mod digest;
+mod prompts;
+mod token_budget;
+mod ollama_options;
 
 use std::path::Path;
 use std::process::Command;
-use std::sync::Mutex;
 use tauri::Manager;
 use pbkdf2::pbkdf2_hmac;
 use sha2::Sha256;
 use rand::{rngs::OsRng, RngCore};
 
-#[cfg(target_os = "windows")]
-use tauri::Runtime;
-
 // Only import what's actually used
 #[cfg(target_os = "windows")]
 use urlencoding;
 
 use tauri::State;
 
-// Store the current hotkey in memory
-struct HotkeyState {
-    current_hotkey: Mutex<Option<String>>,
-}
-
-// Queue for pending protocol captures when the window isn't ready yet
-struct ProtocolState {
-    pending: Mutex<Option<(String, String)>>, // (url, title)
-}
-
-// Keep the tray icon alive (otherwise events may not fire)
-struct TrayState {
-    tray: Mutex<Option<tauri::tray::TrayIcon>>,
-}
-
-// FIX: Import the required trait for global_shortcut()
-use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+use hotkeys::HotkeyState;
 use tauri::Emitter;
 
 use vault::Vault;
+use error::BrainboxError;
+use keystore::VaultKey;
+use zeroize::Zeroizing;
 use dirs;
 use tiny_http::{Server, Response};
 
+/// Run blocking work (SQLite, crypto, blocking HTTP) on the blocking thread pool so the UI stays responsive
+async fn run_blocking<T, F>(f: F) -> Result<T, BrainboxError>
+where
+    F: FnOnce() -> Result<T, BrainboxError> + Send + 'static,
+    T: Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(f).await?
+}
+
 #[tauri::command]
 fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
 #[tauri::command]
-fn register_capture_hotkey(app: tauri::AppHandle, state: State<HotkeyState>, hotkey: String) -> Result<(), String> {
-    let global_shortcut = app.global_shortcut();
-    // Unregister previous hotkey if any
-    if let Some(prev) = state.current_hotkey.lock().unwrap().clone() {
-        let shortcut: Shortcut = prev.parse().map_err(|e| format!("Invalid shortcut: {e}"))?;
-        let _ = global_shortcut.unregister(shortcut);
-    }
-    // Register new hotkey
-    let shortcut: Shortcut = hotkey.parse().map_err(|e| format!("Invalid shortcut: {e}"))?;
-    let app_clone = app.clone();
-    global_shortcut.on_shortcut(shortcut, move |_app, _shortcut, _event| {
-        // Focus the main window when the hotkey is pressed
-        if let Some(window) = app_clone.get_webview_window("main") {
-            let _ = window.set_focus();
-        }
-        let _ = app_clone.emit("capture-hotkey-pressed", ());
-    }).map_err(|e| format!("Failed to register hotkey: {e}"))?;
-    *state.current_hotkey.lock().unwrap() = Some(hotkey);
-    Ok(())
+fn register_capture_hotkey(app: tauri::AppHandle, state: State<HotkeyState>, hotkey: String) -> Result<(), BrainboxError> {
+    hotkeys::register(&app, &state, hotkeys::QUICK_CAPTURE, &hotkey)
 }
 
 #[tauri::command]
-fn unregister_capture_hotkey(app: tauri::AppHandle, state: State<HotkeyState>) -> Result<(), String> {
-    let global_shortcut = app.global_shortcut();
-    if let Some(prev) = state.current_hotkey.lock().unwrap().clone() {
-        let shortcut: Shortcut = prev.parse().map_err(|e| format!("Invalid shortcut: {e}"))?;
-        let _ = global_shortcut.unregister(shortcut);
-        *state.current_hotkey.lock().unwrap() = None;
-    }
-    Ok(())
+fn unregister_capture_hotkey(app: tauri::AppHandle, state: State<HotkeyState>) -> Result<(), BrainboxError> {
+    hotkeys::unregister(&app, &state, hotkeys::QUICK_CAPTURE)
+}
+
+/// Bind a hotkey to a named action (quick_capture, screenshot, clipboard_capture, toggle_window, quick_search)
+#[tauri::command]
+fn register_action_hotkey(app: tauri::AppHandle, state: State<HotkeyState>, action: String, hotkey: String) -> Result<(), BrainboxError> {
+    hotkeys::register(&app, &state, &action, &hotkey)
 }
 
 #[tauri::command]
-fn create_vault(name: String, password: String, has_password: Option<bool>) -> Result<Vault, String> {
-    let db_path = dirs::data_local_dir().ok_or("Failed to get app data dir")?.join("brainbox.sqlite");
-    let conn = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
-    Vault::create_table(&conn).map_err(|e| e.to_string())?;
+fn unregister_action_hotkey(app: tauri::AppHandle, state: State<HotkeyState>, action: String) -> Result<(), BrainboxError> {
+    hotkeys::unregister(&app, &state, &action)
+}
 
-    // Determine if this vault should have password protection
-    // Default to false (no password) if not specified
-    let should_have_password = has_password.unwrap_or(false) && !password.is_empty();
+#[tauri::command]
+fn list_action_hotkeys(state: State<HotkeyState>) -> Vec<hotkeys::ActionHotkey> {
+    hotkeys::list(&state)
+}
 
-    let now = chrono::Utc::now().to_rfc3339();
-    let new_uuid = uuid::Uuid::new_v4().to_string();
-    conn.execute(
-        "INSERT INTO vaults (name, encrypted_password, created_at, cover_image, has_password, uuid, updated_at) VALUES (?1, ?2, ?3, NULL, ?4, ?5, ?6)",
-        rusqlite::params![name, Vec::<u8>::new(), now, should_have_password, new_uuid, now],
-    ).map_err(|e| e.to_string())?;
+#[tauri::command]
+async fn create_vault(name: String, password: String, has_password: Option<bool>) -> Result<Vault, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        Vault::create_table(&conn)?;
 
-    let id = conn.last_insert_rowid();
+        // Determine if this vault should have password protection
+        // Default to false (no password) if not specified
+        let should_have_password = has_password.unwrap_or(false) && !password.is_empty();
 
-    let encrypted = if should_have_password {
-        let key = derive_key_from_password(&password, &id.to_string(), 100_000);
-        let enc = encrypt_password(&key, &password)?;
+        let now = chrono::Utc::now().to_rfc3339();
+        let new_uuid = uuid::Uuid::new_v4().to_string();
         conn.execute(
-            "UPDATE vaults SET encrypted_password = ?1 WHERE id = ?2",
-            rusqlite::params![enc.clone(), id],
-        ).map_err(|e| e.to_string())?;
-        enc
-    } else {
-        Vec::new()
-    };
+            "INSERT INTO vaults (name, encrypted_password, created_at, cover_image, has_password, uuid, updated_at) VALUES (?1, ?2, ?3, NULL, ?4, ?5, ?6)",
+            rusqlite::params![name, Vec::<u8>::new(), now, should_have_password, new_uuid, now],
+        )?;
 
-    Ok(Vault {
-        id,
-        name,
-        encrypted_password: encrypted,
-        created_at: now.clone(),
-        cover_image: None,
-        has_password: should_have_password,
-        uuid: Some(new_uuid),
-        updated_at: Some(now),
-        deleted_at: None,
-    })
+        let id = conn.last_insert_rowid();
+
+        // Passwordless vaults use the key derived from an empty password, as the frontend does
+        let key = derive_key_from_password(if should_have_password { &password } else { "" }, &id.to_string(), 100_000);
+        envelope::create_master_key(&conn, id, &key)?;
+        let encrypted = if should_have_password {
+            let enc = encrypt_password(&key, &password)?;
+            conn.execute(
+                "UPDATE vaults SET encrypted_password = ?1 WHERE id = ?2",
+                rusqlite::params![enc.clone(), id],
+            )?;
+            enc
+        } else {
+            Vec::new()
+        };
+
+        Ok(Vault {
+            id,
+            name,
+            encrypted_password: encrypted,
+            created_at: now.clone(),
+            cover_image: None,
+            has_password: should_have_password,
+            uuid: Some(new_uuid),
+            updated_at: Some(now),
+            deleted_at: None,
+            color: None,
+            icon: None,
+            description: None,
+            sort_order: None,
+            pinned: false,
+        })
+    }).await
 }
 
 #[tauri::command]
-fn list_vaults() -> Result<Vec<Vault>, String> {
-    let db_path = dirs::data_local_dir().ok_or("Failed to get app data dir")?.join("brainbox.sqlite");
-    let conn = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
-    Vault::create_table(&conn).map_err(|e| e.to_string())?;
-    Vault::list(&conn).map_err(|e| e.to_string())
+async fn list_vaults() -> Result<Vec<Vault>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        Vault::create_table(&conn)?;
+        let mut vaults = Vault::list(&conn)?;
+        for vault in &mut vaults {
+            vault.cover_image = covers::display_url(vault.cover_image.take());
+        }
+        Ok(vaults)
+    }).await
 }
 
 use crate::search::{search, index_document, delete_document};
 
 // --- Add Tauri commands for vault items ---
-use crate::vault::VaultItem;
+use crate::vault::{VaultItem, Collection};
 // use crate::vault::Vault as VaultModel; // unused
 
 #[tauri::command]
-fn add_vault_item(vault_id: i64, title: String, content: String, key: Vec<u8>) -> Result<VaultItem, String> {
-    let db_path = dirs::data_local_dir().ok_or("Failed to get app data dir")?.join("brainbox.sqlite");
-    let conn = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
-    VaultItem::create_table(&conn).map_err(|e| e.to_string())?;
-    if key.len() != 32 {
-        return Err("Key must be 32 bytes".to_string());
-    }
-    let mut arr = [0u8; 32];
-    arr.copy_from_slice(&key);
-    let item = VaultItem::insert(&conn, vault_id, &title, &content, &arr).map_err(|e| e.to_string())?;
-    // Best-effort: index in search immediately
-    let item_type = if content.starts_with("http://") || content.starts_with("https://") { "url" } else { "note" };
-    let _ = crate::search::index_document(
-        item.id.to_string(),
-        title.clone(),
-        content.clone(),
-        item_type.to_string(),
-        item.created_at.clone(),
-        item.updated_at.clone(),
-        None,
-        vec![],
-    );
-    Ok(item)
+async fn add_vault_item(vault_id: i64, title: String, content: String, key: Option<Vec<u8>>, collection_id: Option<i64>, item_type: Option<String>) -> Result<VaultItem, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        VaultItem::create_table(&conn)?;
+        let arr = resolve_key(&conn, vault_id, key)?;
+        let arr = envelope::content_key(&conn, vault_id, &arr)?;
+        if let Some(cid) = collection_id {
+            ensure_collection_in_vault(&conn, cid, vault_id)?;
+        }
+        let item_type = match item_type {
+            Some(t) if vault::is_valid_item_type(&t) => t,
+            Some(t) => return Err(BrainboxError::InvalidInput(format!("Unknown item type: {}", t))),
+            None => vault::infer_item_type(&content).to_string(),
+        };
+        let mut item = VaultItem::insert(&conn, vault_id, &title, &content, &item_type, &arr)?;
+        if collection_id.is_some() {
+            VaultItem::set_collection(&conn, item.id, collection_id)?;
+            item.collection_id = collection_id;
+        }
+        // Best-effort: index in search immediately
+        let _ = crate::search::index_document(crate::search::DocumentInput {
+            id: item.id.to_string(),
+            title: title.clone(),
+            content: content.clone(),
+            item_type,
+            created_at: item.created_at.clone(),
+            updated_at: item.updated_at.clone(),
+            vault_id: Some(vault_id),
+            ..Default::default()
+        });
+        Ok(item)
+    }).await
 }
 
 #[derive(serde::Serialize)]
@@ -181,28 +256,67 @@ struct VaultItemOut {
     #[allow(dead_code)]
     #[serde(skip_serializing_if = "Option::is_none")]
     sort_order: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    collection_id: Option<i64>,
+    item_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    due_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    completed_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    read_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    archive_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    icon: Option<String>,
+    word_count: i64,
+    char_count: i64,
+    reading_time_minutes: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    preview: Option<String>,
 }
 
-fn decrypt_content(key: &[u8; 32], encrypted: &[u8]) -> Result<String, String> {
-    use chacha20poly1305::{aead::Aead, KeyInit, XChaCha20Poly1305, Key, XNonce};
-    if encrypted.len() < 24 { return Err("Invalid ciphertext".into()); }
-    let mut nonce_bytes = [0u8; 24];
-    nonce_bytes.copy_from_slice(&encrypted[..24]);
-    let nonce = XNonce::from_slice(&nonce_bytes);
-    let cipher = XChaCha20Poly1305::new(Key::from_slice(key));
-    let plaintext = cipher
-        .decrypt(nonce, &encrypted[24..])
-        .map_err(|_| "Decryption failed".to_string())?;
-    String::from_utf8(plaintext).map_err(|_| "Invalid UTF-8".to_string())
+/// Stored word/character counts for an item, computing and saving them from the decrypted content if missing
+fn item_text_stats(conn: &rusqlite::Connection, it: &VaultItem, content: &str) -> (i64, i64) {
+    match (it.word_count, it.char_count) {
+        (Some(words), Some(chars)) => (words, chars),
+        _ => {
+            let (words, chars) = vault::text_stats(content);
+            let _ = VaultItem::set_text_stats(conn, it.id, words, chars);
+            (words, chars)
+        }
+    }
 }
 
-fn derive_key_from_password(password: &str, salt: &str, iterations: u32) -> [u8; 32] {
-    let mut key = [0u8; 32];
-    pbkdf2_hmac::<Sha256>(password.as_bytes(), salt.as_bytes(), iterations, &mut key);
+fn decrypt_content(key: &[u8; 32], encrypted: &[u8]) -> Result<String, BrainboxError> {
+    let plaintext = envelope::open(key, encrypted)?;
+    String::from_utf8(plaintext).map_err(|_| BrainboxError::Crypto("Invalid UTF-8".to_string()))
+}
+
+fn derive_key_from_password(password: &str, salt: &str, iterations: u32) -> VaultKey {
+    let mut key = Zeroizing::new([0u8; 32]);
+    pbkdf2_hmac::<Sha256>(password.as_bytes(), salt.as_bytes(), iterations, &mut *key);
     key
 }
 
-fn encrypt_password(key: &[u8; 32], password: &str) -> Result<Vec<u8>, String> {
+/// Key for a vault command: the one passed in, else the vault's unlocked key from the keystore,
+/// else the empty-password key for vaults without a password
+fn resolve_key(conn: &rusqlite::Connection, vault_id: i64, key: Option<Vec<u8>>) -> Result<VaultKey, BrainboxError> {
+    if let Some(bytes) = key {
+        return keystore::key_from_bytes(bytes);
+    }
+    if let Some(key) = keystore::get(vault_id) {
+        return Ok(key);
+    }
+    if !vault_has_password(conn, vault_id)? {
+        return Ok(derive_key_from_password("", &vault_id.to_string(), 100_000));
+    }
+    Err(BrainboxError::InvalidKey)
+}
+
+fn encrypt_password(key: &[u8; 32], password: &str) -> Result<Vec<u8>, BrainboxError> {
     use chacha20poly1305::{aead::Aead, KeyInit, XChaCha20Poly1305, Key, XNonce};
     let cipher = XChaCha20Poly1305::new(Key::from_slice(key));
     let mut nonce_bytes = [0u8; 24];
@@ -211,28 +325,27 @@ fn encrypt_password(key: &[u8; 32], password: &str) -> Result<Vec<u8>, String> {
     let nonce = XNonce::from_slice(&nonce_bytes);
     let ciphertext = cipher
         .encrypt(nonce, password.as_bytes())
-        .map_err(|_| "Encryption failed".to_string())?;
+        .map_err(|_| BrainboxError::Crypto("Encryption failed".to_string()))?;
     let mut encrypted = nonce_bytes.to_vec();
     encrypted.extend(ciphertext);
     Ok(encrypted)
 }
 
 /// Check if a vault has password protection
-fn vault_has_password(conn: &rusqlite::Connection, vault_id: i64) -> Result<bool, String> {
-    Vault::create_table(conn).map_err(|e| e.to_string())?;
+fn vault_has_password(conn: &rusqlite::Connection, vault_id: i64) -> Result<bool, BrainboxError> {
+    Vault::create_table(conn)?;
     let mut stmt = conn
-        .prepare("SELECT has_password FROM vaults WHERE id = ?1")
-        .map_err(|e| e.to_string())?;
+        .prepare("SELECT has_password FROM vaults WHERE id = ?1")?;
     let has_pw: i64 = match stmt.query_row([vault_id], |row| row.get(0)) {
         Ok(val) => val,
-        Err(rusqlite::Error::QueryReturnedNoRows) => return Err("Vault not found".to_string()),
-        Err(e) => return Err(e.to_string()),
+        Err(rusqlite::Error::QueryReturnedNoRows) => return Err(BrainboxError::NotFound("Vault not found".to_string())),
+        Err(e) => return Err(e.into()),
     };
     Ok(has_pw != 0)
 }
 
-fn verify_vault_key(conn: &rusqlite::Connection, vault_id: i64, key: &[u8; 32]) -> Result<(), String> {
-    Vault::create_table(conn).map_err(|e| e.to_string())?;
+fn verify_vault_key(conn: &rusqlite::Connection, vault_id: i64, key: &[u8; 32]) -> Result<(), BrainboxError> {
+    Vault::create_table(conn)?;
 
     // Check if vault has password protection
     if !vault_has_password(conn, vault_id)? {
@@ -241,44 +354,272 @@ fn verify_vault_key(conn: &rusqlite::Connection, vault_id: i64, key: &[u8; 32])
     }
 
     let mut stmt = conn
-        .prepare("SELECT encrypted_password FROM vaults WHERE id = ?1")
-        .map_err(|e| e.to_string())?;
+        .prepare("SELECT encrypted_password FROM vaults WHERE id = ?1")?;
     let encrypted: Vec<u8> = match stmt.query_row([vault_id], |row| row.get(0)) {
         Ok(val) => val,
-        Err(rusqlite::Error::QueryReturnedNoRows) => return Err("Vault not found".to_string()),
-        Err(e) => return Err(e.to_string()),
+        Err(rusqlite::Error::QueryReturnedNoRows) => return Err(BrainboxError::NotFound("Vault not found".to_string())),
+        Err(e) => return Err(e.into()),
     };
-    decrypt_content(key, &encrypted)
-        .map(|_| ())
-        .map_err(|_| "Invalid password".to_string())
+    if decrypt_content(key, &encrypted).is_err() {
+        return Err(BrainboxError::InvalidKey);
+    }
+    Ok(())
 }
 
-#[tauri::command]
-fn verify_vault_password(vault_id: i64, key: Vec<u8>) -> Result<(), String> {
-    let db_path = dirs::data_local_dir().ok_or("Failed to get app data dir")?.join("brainbox.sqlite");
-    let conn = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
-    Vault::create_table(&conn).map_err(|e| e.to_string())?;
-    if key.len() != 32 { return Err("Key must be 32 bytes".into()); }
-    let mut arr = [0u8; 32];
-    arr.copy_from_slice(&key);
-    verify_vault_key(&conn, vault_id, &arr)?;
+/// Check a key derived from a password the user just typed. These are the attempts the lockout counts; keys
+/// already in the keystore are checked with `verify_vault_key`, so a locked-out vault that is unlocked keeps working.
+fn verify_password_attempt(conn: &rusqlite::Connection, vault_id: i64, key: &[u8; 32]) -> Result<(), BrainboxError> {
+    if !vault_has_password(conn, vault_id)? {
+        return Ok(());
+    }
+    let lockout = lockout::check(conn, vault_id)?;
+    if let Err(e) = verify_vault_key(conn, vault_id, key) {
+        if matches!(e, BrainboxError::InvalidKey) {
+            lockout::record_failure(conn, vault_id)?;
+        }
+        return Err(e);
+    }
+    if lockout.failed_attempts > 0 {
+        lockout::reset(conn, vault_id)?;
+    }
     Ok(())
 }
 
 #[tauri::command]
-fn list_vault_items(vault_id: i64, key: Vec<u8>) -> Result<Vec<VaultItemOut>, String> {
-    let db_path = dirs::data_local_dir().ok_or("Failed to get app data dir")?.join("brainbox.sqlite");
-    let conn = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
-    VaultItem::create_table(&conn).map_err(|e| e.to_string())?;
-    if key.len() != 32 { return Err("Key must be 32 bytes".into()); }
-    let mut arr = [0u8; 32];
-    arr.copy_from_slice(&key);
-    verify_vault_key(&conn, vault_id, &arr)?;
-    let items = VaultItem::list_by_vault(&conn, vault_id).map_err(|e| e.to_string())?;
-    let mut out = Vec::with_capacity(items.len());
-    for it in items.into_iter() {
+async fn verify_vault_password(vault_id: i64, key: Option<Vec<u8>>) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        Vault::create_table(&conn)?;
+        let arr = resolve_key(&conn, vault_id, key)?;
+        verify_password_attempt(&conn, vault_id, &arr)?;
+        Ok(())
+    }).await
+}
+
+/// Derive and check a vault's key from its password and keep it in the backend keystore,
+/// so later commands for the vault don't need a key passed
+#[tauri::command]
+async fn unlock_vault(vault_id: i64, password: String) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let password = Zeroizing::new(password);
+        let conn = db::open()?;
+        Vault::create_table(&conn)?;
+        let key = derive_key_from_password(&password, &vault_id.to_string(), 100_000);
+        verify_password_attempt(&conn, vault_id, &key)?;
+        keystore::insert(vault_id, &key);
+        Ok(())
+    }).await
+}
+
+/// Forget a vault's key; commands for it fail until it is unlocked again
+#[tauri::command]
+fn lock_vault(vault_id: i64) {
+    keystore::remove(vault_id);
+}
+
+#[tauri::command]
+fn lock_all_vaults() {
+    keystore::clear();
+}
+
+#[tauri::command]
+fn list_unlocked_vaults() -> Vec<i64> {
+    keystore::unlocked_vault_ids()
+}
+
+/// Lock every vault and hide the app at once. `clear_index` defaults to the saved emergency-lock setting.
+#[tauri::command]
+async fn emergency_lock(app: tauri::AppHandle, clear_index: Option<bool>) -> Result<emergency::EmergencyLockResult, BrainboxError> {
+    run_blocking(move || emergency::lock(&app, clear_index)).await
+}
+
+#[tauri::command]
+async fn get_emergency_lock_clears_index() -> Result<bool, BrainboxError> {
+    run_blocking(emergency::clear_index_setting).await
+}
+
+#[tauri::command]
+async fn set_emergency_lock_clears_index(enabled: bool) -> Result<(), BrainboxError> {
+    run_blocking(move || emergency::set_clear_index_setting(enabled)).await
+}
+
+/// Permanently delete all brainbox data and quit; `confirmation_phrase` must match exactly
+#[tauri::command]
+async fn wipe_all_data(app: tauri::AppHandle, confirmation_phrase: String) -> Result<(), BrainboxError> {
+    run_blocking(move || emergency::wipe_all_data(&app, &confirmation_phrase)).await
+}
+
+/// Whether Touch ID / Windows Hello is available and set up for a vault
+#[tauri::command]
+async fn get_biometric_status(vault_id: i64) -> Result<biometrics::BiometricStatus, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        biometrics::status(&conn, vault_id)
+    }).await
+}
+
+/// Keep the vault key wrapped by an OS biometric secret; shows the OS prompt
+#[tauri::command]
+async fn enable_biometric_unlock(vault_id: i64, key: Option<Vec<u8>>) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        Vault::create_table(&conn)?;
+        let arr = resolve_key(&conn, vault_id, key)?;
+        verify_vault_key(&conn, vault_id, &arr)?;
+        biometrics::enable(&conn, vault_id, &arr)
+    }).await
+}
+
+/// Unlock a vault with the key released by a successful biometric check
+#[tauri::command]
+async fn unlock_with_biometrics(vault_id: i64) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        Vault::create_table(&conn)?;
+        lockout::check(&conn, vault_id)?;
+        let key = biometrics::unlock(&conn, vault_id)?;
+        if let Err(e) = verify_vault_key(&conn, vault_id, &key) {
+            // The password changed since biometrics were set up; the stored key is useless now
+            if matches!(e, BrainboxError::InvalidKey) {
+                let _ = biometrics::disable(&conn, vault_id);
+            }
+            return Err(e);
+        }
+        keystore::insert(vault_id, &key);
+        Ok(())
+    }).await
+}
+
+#[tauri::command]
+async fn disable_biometric_unlock(vault_id: i64) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        biometrics::disable(&conn, vault_id)
+    }).await
+}
+
+/// Failed password attempts for a vault and how long until it accepts another, for showing a cooldown
+#[tauri::command]
+async fn get_vault_lockout_status(vault_id: i64) -> Result<lockout::LockoutStatus, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        Vault::create_table(&conn)?;
+        Vault::get_by_id(&conn, vault_id)?.ok_or_else(|| BrainboxError::NotFound("Vault not found".to_string()))?;
+        lockout::status(&conn, vault_id)
+    }).await
+}
+
+/// List items in a vault. Without options this returns every item in manual order with full content.
+/// `offset`/`limit` page through the results, `sort` is one of manual/created/updated/title, and
+/// `metadata_only` skips decrypting content (content is returned empty) for fast list views.
+#[tauri::command]
+async fn list_vault_items(
+    vault_id: i64,
+    key: Option<Vec<u8>>,
+    offset: Option<i64>,
+    limit: Option<i64>,
+    sort: Option<String>,
+    descending: Option<bool>,
+    metadata_only: Option<bool>,
+) -> Result<Vec<VaultItemOut>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        VaultItem::create_table(&conn)?;
+        let arr = resolve_key(&conn, vault_id, key)?;
+        verify_vault_key(&conn, vault_id, &arr)?;
+        let arr = envelope::content_key(&conn, vault_id, &arr)?;
+        let sort = match sort.as_deref() {
+            Some(s) => vault::ItemSort::parse(s).ok_or_else(|| format!("Unknown sort: {}", s))?,
+            None => vault::ItemSort::Manual,
+        };
+        let metadata_only = metadata_only.unwrap_or(false);
+        let items = VaultItem::list_page_by_vault(&conn, vault_id, sort, descending, offset.unwrap_or(0), limit)?;
+        let mut out = Vec::with_capacity(items.len());
+        for it in items.into_iter() {
+            let (content, word_count, char_count, item_type, preview) = match (metadata_only, &it.preview) {
+                // Fast path: only the short encrypted preview is decrypted
+                (true, Some(enc_preview)) => {
+                    let preview = decrypt_content(&arr, enc_preview)?;
+                    let item_type = it.item_type.clone().unwrap_or_else(|| vault::infer_item_type(&preview).to_string());
+                    (String::new(), it.word_count.unwrap_or(0), it.char_count.unwrap_or(0), item_type, preview)
+                }
+                _ => {
+                    let content = decrypt_content(&arr, &it.content)?;
+                    let (word_count, char_count) = item_text_stats(&conn, &it, &content);
+                    let item_type = it.item_type.clone().unwrap_or_else(|| vault::infer_item_type(&content).to_string());
+                    if it.preview.is_none() {
+                        let _ = VaultItem::set_preview(&conn, it.id, &content, &arr);
+                    }
+                    let preview = vault::make_preview(&content);
+                    // Items without a stored preview are decrypted once to generate it, but still omit content
+                    let content = if metadata_only { String::new() } else { content };
+                    (content, word_count, char_count, item_type, preview)
+                }
+            };
+            out.push(VaultItemOut {
+                id: it.id,
+                vault_id: it.vault_id,
+                title: it.title,
+                content,
+                created_at: it.created_at,
+                updated_at: it.updated_at,
+                image: it.image,
+                summary: it.summary,
+                sort_order: it.sort_order,
+                collection_id: it.collection_id,
+                item_type,
+                due_at: it.due_at,
+                completed_at: it.completed_at,
+                read_at: it.read_at,
+                archive_url: it.archive_url,
+                color: it.color,
+                icon: it.icon,
+                word_count,
+                char_count,
+                reading_time_minutes: vault::reading_time_minutes(word_count),
+                preview: Some(preview),
+            });
+        }
+        Ok(out)
+    }).await
+}
+
+/// Full decrypted content of one item, for opening a note from a metadata-only list
+#[tauri::command]
+async fn get_item_content(item_id: i64, key: Option<Vec<u8>>) -> Result<String, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        VaultItem::create_table(&conn)?;
+        let it = VaultItem::get_by_id(&conn, item_id)?;
+        let arr = resolve_key(&conn, it.vault_id, key)?;
+        let arr = envelope::content_key(&conn, it.vault_id, &arr)?;
+        decrypt_content(&arr, &it.content)
+    }).await
+}
+
+/// Number of (non-deleted) items in a vault, for paging
+#[tauri::command]
+async fn count_vault_items(vault_id: i64) -> Result<i64, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        VaultItem::create_table(&conn)?;
+        VaultItem::count_by_vault(&conn, vault_id).map_err(BrainboxError::from)
+    }).await
+}
+
+#[tauri::command]
+async fn get_vault_item(item_id: i64, key: Option<Vec<u8>>) -> Result<VaultItemOut, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        crate::vault::VaultItem::create_table(&conn)?;
+        let it = crate::vault::VaultItem::get_by_id(&conn, item_id)?;
+        let arr = resolve_key(&conn, it.vault_id, key)?;
+        let arr = envelope::content_key(&conn, it.vault_id, &arr)?;
         let content = decrypt_content(&arr, &it.content)?;
-        out.push(VaultItemOut {
+        let (word_count, char_count) = item_text_stats(&conn, &it, &content);
+        let item_type = it.item_type.unwrap_or_else(|| vault::infer_item_type(&content).to_string());
+        let preview = vault::make_preview(&content);
+        Ok(VaultItemOut {
             id: it.id,
             vault_id: it.vault_id,
             title: it.title,
@@ -288,581 +629,3272 @@ fn list_vault_items(vault_id: i64, key: Vec<u8>) -> Result<Vec<VaultItemOut>, St
             image: it.image,
             summary: it.summary,
             sort_order: it.sort_order,
-        });
-    }
-    Ok(out)
+            collection_id: it.collection_id,
+            item_type,
+            due_at: it.due_at,
+            completed_at: it.completed_at,
+            read_at: it.read_at,
+            archive_url: it.archive_url,
+            color: it.color,
+            icon: it.icon,
+            word_count,
+            char_count,
+            reading_time_minutes: vault::reading_time_minutes(word_count),
+            preview: Some(preview),
+        })
+    }).await
 }
 
+/// Find `query` in one note without sending its content to the webview; offsets are in UTF-16 code units
 #[tauri::command]
-fn get_vault_item(item_id: i64, key: Vec<u8>) -> Result<VaultItemOut, String> {
-    let db_path = dirs::data_local_dir().ok_or("Failed to get app data dir")?.join("brainbox.sqlite");
-    let conn = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
-    crate::vault::VaultItem::create_table(&conn).map_err(|e| e.to_string())?;
-    if key.len() != 32 { return Err("Key must be 32 bytes".into()); }
-    let mut arr = [0u8; 32];
-    arr.copy_from_slice(&key);
-    let it = crate::vault::VaultItem::get_by_id(&conn, item_id).map_err(|e| e.to_string())?;
+async fn find_in_item(item_id: i64, key: Option<Vec<u8>>, query: String, case_sensitive: Option<bool>, limit: Option<usize>) -> Result<item_find::FindResult, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        let (_, content) = read_item_content(&conn, item_id, key)?;
+        let limit = limit.unwrap_or(item_find::DEFAULT_LIMIT).min(item_find::MAX_LIMIT);
+        Ok(item_find::find(&content, &query, case_sensitive.unwrap_or(false), limit))
+    }).await
+}
+
+/// Decrypted content of an item, with the item
+fn read_item_content(conn: &rusqlite::Connection, item_id: i64, key: Option<Vec<u8>>) -> Result<(VaultItem, String), BrainboxError> {
+    let it = VaultItem::get_by_id(conn, item_id)?;
+    let arr = resolve_key(conn, it.vault_id, key)?;
+    let arr = envelope::content_key(conn, it.vault_id, &arr)?;
     let content = decrypt_content(&arr, &it.content)?;
-    Ok(VaultItemOut {
-        id: it.id,
-        vault_id: it.vault_id,
-        title: it.title,
-        content,
-        created_at: it.created_at,
-        updated_at: it.updated_at,
-        image: it.image,
-        summary: it.summary,
-        sort_order: it.sort_order,
-    })
+    Ok((it, content))
+}
+
+/// Headings of a note as a tree, for the outline pane
+#[tauri::command]
+async fn parse_item_outline(item_id: i64, key: Option<Vec<u8>>) -> Result<Vec<markdown::Heading>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        let (_, content) = read_item_content(&conn, item_id, key)?;
+        Ok(markdown::outline(&content))
+    }).await
+}
+
+/// Outline, checkboxes, links and code blocks of a note. Links to other notes carry their item id.
+#[tauri::command]
+async fn parse_item_markdown(item_id: i64, key: Option<Vec<u8>>) -> Result<markdown::MarkdownStructure, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        let (it, content) = read_item_content(&conn, item_id, key)?;
+        let mut structure = markdown::parse(&content);
+        markdown::resolve_links(&conn, it.vault_id, &mut structure.links)?;
+        Ok(structure)
+    }).await
+}
+
+/// Languages used in a vault's code blocks, with block and item counts
+#[tauri::command]
+async fn get_vault_code_languages(vault_id: i64, key: Option<Vec<u8>>) -> Result<Vec<markdown::CodeLanguageCount>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        let arr = resolve_key(&conn, vault_id, key)?;
+        let arr = envelope::content_key(&conn, vault_id, &arr)?;
+        let mut notes = Vec::new();
+        for it in VaultItem::list_by_vault(&conn, vault_id)? {
+            notes.push(decrypt_content(&arr, &it.content)?);
+        }
+        Ok(markdown::code_language_stats(notes.iter().map(|note| note.as_str())))
+    }).await
+}
+
+// --- Annotations ---
+
+/// Content key of the vault an item is in
+fn item_content_key(conn: &rusqlite::Connection, item_id: i64, key: Option<Vec<u8>>) -> Result<VaultKey, BrainboxError> {
+    let it = VaultItem::get_by_id(conn, item_id)?;
+    let arr = resolve_key(conn, it.vault_id, key)?;
+    envelope::content_key(conn, it.vault_id, &arr)
+}
+
+/// Highlights on an item, oldest first
+#[tauri::command]
+async fn list_item_annotations(item_id: i64, key: Option<Vec<u8>>) -> Result<Vec<annotations::Annotation>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        let arr = item_content_key(&conn, item_id, key)?;
+        annotations::list(&conn, item_id, &arr)
+    }).await
+}
+
+#[tauri::command]
+async fn add_annotation(item_id: i64, key: Option<Vec<u8>>, annotation: annotations::AnnotationInput) -> Result<annotations::Annotation, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        let arr = item_content_key(&conn, item_id, key.clone())?;
+        let created = annotations::create(&conn, item_id, &arr, &annotation)?;
+        reindex_item(&conn, item_id, key, None)?;
+        Ok(created)
+    }).await
+}
+
+#[tauri::command]
+async fn update_annotation(annotation_id: i64, key: Option<Vec<u8>>, annotation: annotations::AnnotationInput) -> Result<annotations::Annotation, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        let item_id = annotations::item_id(&conn, annotation_id)?;
+        let arr = item_content_key(&conn, item_id, key.clone())?;
+        let updated = annotations::update(&conn, annotation_id, &arr, &annotation)?;
+        reindex_item(&conn, item_id, key, None)?;
+        Ok(updated)
+    }).await
+}
+
+#[tauri::command]
+async fn delete_annotation(annotation_id: i64, key: Option<Vec<u8>>) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        let item_id = annotations::delete(&conn, annotation_id)?;
+        // A locked vault keeps the old text in its index until it is next unlocked or rebuilt
+        if let Err(e) = reindex_item(&conn, item_id, key, None) {
+            tracing::debug!("Not re-indexing item {} after deleting an annotation: {}", item_id, e);
+        }
+        Ok(())
+    }).await
+}
+
+/// Word/character totals for a vault. `since` (RFC 3339) limits the stats to items updated after it.
+#[tauri::command]
+async fn get_writing_stats(vault_id: i64, since: Option<String>) -> Result<vault::WritingStats, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        let since = match since {
+            Some(s) => Some(
+                chrono::DateTime::parse_from_rfc3339(&s)
+                    .map_err(|_| format!("Invalid date: {}", s))?
+                    .with_timezone(&chrono::Utc)
+                    .to_rfc3339(),
+            ),
+            None => None,
+        };
+        VaultItem::writing_stats(&conn, vault_id, since.as_deref()).map_err(BrainboxError::from)
+    }).await
+}
+
+#[tauri::command]
+async fn delete_vault(vault_id: i64) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        Vault::delete(&conn, vault_id)?;
+        keystore::remove(vault_id);
+        // Best-effort: deleted vaults shouldn't keep showing up in search
+        if let Err(e) = search::delete_documents_by_vault(vault_id) {
+            tracing::warn!("Failed to remove vault {} from the search index: {}", vault_id, e);
+        }
+        Ok(())
+    }).await
+}
+
+/// Save the dashboard order; ids not listed keep their current position value
+#[tauri::command]
+async fn update_vaults_order(ordered_ids: Vec<i64>) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        Vault::update_order(&conn, &ordered_ids).map_err(BrainboxError::from)
+    }).await
+}
+
+#[tauri::command]
+async fn set_vault_pinned(vault_id: i64, pinned: bool) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        Vault::set_pinned(&conn, vault_id, pinned).map_err(BrainboxError::from)
+    }).await
+}
+
+#[tauri::command]
+async fn rename_vault(vault_id: i64, name: String) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        Vault::rename(&conn, vault_id, &name).map_err(BrainboxError::from)
+    }).await
+}
+
+#[tauri::command]
+async fn update_vault_cover(vault_id: i64, cover_image: Option<String>) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        let cover = covers::normalize(cover_image.as_deref())?;
+        Vault::update_cover_image(&conn, vault_id, cover.as_deref()).map_err(BrainboxError::from)
+    }).await
+}
+
+/// Card accent color as #rgb/#rrggbb; None clears it
+#[tauri::command]
+async fn update_vault_color(vault_id: i64, color: Option<String>) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let color = color.map(|c| c.trim().to_lowercase()).filter(|c| !c.is_empty());
+        if let Some(c) = &color {
+            if !vault::is_valid_color(c) {
+                return Err(BrainboxError::InvalidInput(format!("Invalid color: {}", c)));
+            }
+        }
+        let conn = db::open()?;
+        Vault::update_color(&conn, vault_id, color.as_deref()).map_err(BrainboxError::from)
+    }).await
+}
+
+/// Card icon: an emoji or the name of a builtin icon; None clears it
+#[tauri::command]
+async fn update_vault_icon(vault_id: i64, icon: Option<String>) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let icon = icon.map(|i| i.trim().to_string()).filter(|i| !i.is_empty());
+        if icon.as_ref().is_some_and(|i| i.chars().count() > vault::MAX_ICON_LEN) {
+            return Err(BrainboxError::InvalidInput("Icon is too long".to_string()));
+        }
+        let conn = db::open()?;
+        Vault::update_icon(&conn, vault_id, icon.as_deref()).map_err(BrainboxError::from)
+    }).await
+}
+
+#[tauri::command]
+async fn update_vault_description(vault_id: i64, description: Option<String>) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let description = description.map(|d| d.trim().to_string()).filter(|d| !d.is_empty());
+        if description.as_ref().is_some_and(|d| d.chars().count() > vault::MAX_DESCRIPTION_LEN) {
+            return Err(BrainboxError::InvalidInput(format!(
+                "Description must be at most {} characters",
+                vault::MAX_DESCRIPTION_LEN
+            )));
+        }
+        let conn = db::open()?;
+        Vault::update_description(&conn, vault_id, description.as_deref()).map_err(BrainboxError::from)
+    }).await
+}
+
+fn check_vault_setting_key(key: &str) -> Result<(), BrainboxError> {
+    if !vault::is_valid_setting_key(key) {
+        return Err(BrainboxError::InvalidInput(format!("Invalid setting key: {}", key)));
+    }
+    Ok(())
+}
+
+/// A per-vault preference such as "default_sort", "default_view", "capture_tags" or "auto_summarize"
+#[tauri::command]
+async fn get_vault_setting(vault_id: i64, key: String) -> Result<Option<String>, BrainboxError> {
+    run_blocking(move || {
+        check_vault_setting_key(&key)?;
+        let conn = db::open()?;
+        vault::VaultSettings::get(&conn, vault_id, &key).map_err(BrainboxError::from)
+    }).await
+}
+
+/// Every preference set for a vault
+#[tauri::command]
+async fn get_vault_settings(vault_id: i64) -> Result<HashMap<String, String>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        vault::VaultSettings::get_all(&conn, vault_id).map_err(BrainboxError::from)
+    }).await
+}
+
+/// Set a per-vault preference (synced with the vault); None clears it. "capture_tags" takes a JSON array of tags.
+#[tauri::command]
+async fn set_vault_setting(vault_id: i64, key: String, value: Option<String>) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        check_vault_setting_key(&key)?;
+        if value.as_ref().is_some_and(|v| v.len() > vault::MAX_SETTING_VALUE_LEN) {
+            return Err(BrainboxError::InvalidInput("Setting value is too long".to_string()));
+        }
+        if key == vault::CAPTURE_TAGS_SETTING {
+            if let Some(v) = &value {
+                serde_json::from_str::<Vec<String>>(v)
+                    .map_err(|_| BrainboxError::InvalidInput("capture_tags must be a JSON array of strings".to_string()))?;
+            }
+        }
+        let conn = db::open()?;
+        Vault::get_by_id(&conn, vault_id)?
+            .ok_or_else(|| BrainboxError::NotFound(format!("Vault {} not found", vault_id)))?;
+        vault::VaultSettings::set(&conn, vault_id, &key, value.as_deref()).map_err(BrainboxError::from)
+    }).await
+}
+
+/// Use an image file as the vault cover; it is resized and copied into the covers folder. Returns its display URL.
+#[tauri::command]
+async fn set_vault_cover_file(vault_id: i64, path: String) -> Result<String, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        let cover = covers::store_file(std::path::Path::new(&path))?;
+        Vault::update_cover_image(&conn, vault_id, Some(&cover))?;
+        covers::display_url(Some(cover)).ok_or_else(|| BrainboxError::Other("Failed to store cover".to_string()))
+    }).await
+}
+
+#[tauri::command]
+async fn delete_vault_item(item_id: i64) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        VaultItem::create_table(&conn)?;
+        VaultItem::delete(&conn, item_id)?;
+        Ok(())
+    }).await
+}
+
+#[tauri::command]
+async fn get_item_tags(item_id: i64) -> Result<Vec<String>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        tags::get(&conn, item_id)
+    }).await
+}
+
+/// Replace an item's tags; returns them normalized
+#[tauri::command]
+async fn set_item_tags(item_id: i64, tags: Vec<String>) -> Result<Vec<String>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        VaultItem::get_by_id(&conn, item_id)?;
+        tags::set(&conn, item_id, &tags)
+    }).await
+}
+
+/// Items waiting in the Inbox, newest first
+#[tauri::command]
+async fn list_inbox_items() -> Result<Vec<inbox::InboxItem>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        inbox::list(&conn)
+    }).await
+}
+
+/// Number of Inbox items, for the badge
+#[tauri::command]
+async fn get_inbox_count() -> Result<i64, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        inbox::count(&conn)
+    }).await
+}
+
+/// Quick capture into the Inbox without picking a vault
+#[tauri::command]
+async fn add_inbox_item(app: tauri::AppHandle, title: String, content: String) -> Result<VaultItem, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        let item = inbox::add(&conn, &title, &content)?;
+        inbox::notify(&app, &conn);
+        Ok(item)
+    }).await
+}
+
+/// File an Inbox item into a vault, adding `tags`. The target vault must be unlocked.
+#[tauri::command]
+async fn triage_item(app: tauri::AppHandle, item_id: i64, target_vault_id: i64, tags: Option<Vec<String>>) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        inbox::triage(&conn, item_id, target_vault_id, &tags.unwrap_or_default())?;
+        inbox::notify(&app, &conn);
+        Ok(())
+    }).await
+}
+
+/// Whether browser and brainbox:// captures go straight to the Inbox
+#[tauri::command]
+async fn get_inbox_enabled() -> Result<bool, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        inbox::is_enabled(&conn)
+    }).await
+}
+
+#[tauri::command]
+async fn set_inbox_enabled(enabled: bool) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        if enabled {
+            inbox::ensure_vault(&conn)?;
+        }
+        inbox::set_enabled(&conn, enabled)
+    }).await
+}
+
+/// Capture rules in the order they are tried
+#[tauri::command]
+async fn list_capture_rules() -> Result<Vec<capture_rules::CaptureRule>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        capture_rules::CaptureRule::list(&conn).map_err(BrainboxError::from)
+    }).await
+}
+
+fn check_rule_vault(conn: &rusqlite::Connection, rule: &capture_rules::CaptureRuleInput) -> Result<(), BrainboxError> {
+    if let Some(vault_id) = rule.target_vault_id {
+        Vault::get_by_id(conn, vault_id)?
+            .filter(|v| v.deleted_at.is_none())
+            .ok_or_else(|| BrainboxError::NotFound(format!("Vault {} not found", vault_id)))?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn create_capture_rule(rule: capture_rules::CaptureRuleInput) -> Result<capture_rules::CaptureRule, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        check_rule_vault(&conn, &rule)?;
+        capture_rules::CaptureRule::insert(&conn, &rule)
+    }).await
+}
+
+#[tauri::command]
+async fn update_capture_rule(rule_id: i64, rule: capture_rules::CaptureRuleInput) -> Result<capture_rules::CaptureRule, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        check_rule_vault(&conn, &rule)?;
+        capture_rules::CaptureRule::update(&conn, rule_id, &rule)
+    }).await
+}
+
+#[tauri::command]
+async fn delete_capture_rule(rule_id: i64) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        capture_rules::CaptureRule::delete(&conn, rule_id)?;
+        Ok(())
+    }).await
+}
+
+/// Groups of items in a vault with exactly the same content
+#[tauri::command]
+async fn find_duplicate_items(vault_id: i64, key: Option<Vec<u8>>) -> Result<Vec<duplicates::DuplicateGroup>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        let arr = resolve_key(&conn, vault_id, key)?;
+        verify_vault_key(&conn, vault_id, &arr)?;
+        let arr = envelope::content_key(&conn, vault_id, &arr)?;
+        duplicates::find(&conn, vault_id, &arr)
+    }).await
+}
+
+/// Keep the first item of a duplicate group and move the others to the trash
+#[tauri::command]
+async fn merge_duplicates(ids: Vec<i64>) -> Result<duplicates::MergeResult, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        duplicates::merge(&conn, &ids)
+    }).await
+}
+
+#[tauri::command]
+async fn update_vault_items_order(vault_id: i64, ordered_ids: Vec<i64>) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        VaultItem::update_order(&conn, vault_id, &ordered_ids).map_err(BrainboxError::from)
+    }).await
+}
+
+#[tauri::command]
+async fn update_vault_item_title(item_id: i64, title: String) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        VaultItem::update_title(&conn, item_id, &title).map_err(BrainboxError::from)
+    }).await
+}
+
+#[tauri::command]
+async fn move_vault_item(item_id: i64, target_vault_id: i64) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        VaultItem::move_to_vault(&conn, item_id, target_vault_id)?;
+        image_labels::forget_item(&conn, item_id)?;
+        entities::forget_item(&conn, item_id)
+    }).await
+}
+
+#[tauri::command]
+async fn update_vault_item_image(item_id: i64, image: Option<String>) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        VaultItem::create_table(&conn)?;
+        VaultItem::update_image(&conn, item_id, image.as_deref()).map_err(BrainboxError::from)
+    }).await
+}
+
+/// Download an item's remote image into the local cache and point the item at the cached copy
+#[tauri::command]
+async fn cache_item_image(item_id: i64, key: Option<Vec<u8>>) -> Result<String, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        VaultItem::create_table(&conn)?;
+        let item = VaultItem::get_by_id(&conn, item_id)?;
+        let image = item.image.ok_or_else(|| BrainboxError::InvalidInput("Item has no image".to_string()))?;
+        if image_cache::is_local_url(&image) {
+            return Ok(image);
+        }
+        let (local, metadata) = image_cache::cache_remote_image(&conn, &image)?;
+        VaultItem::update_image(&conn, item_id, Some(&local))?;
+        // Photo metadata is searchable once the item is next indexed; it is sealed, so it is kept only while the
+        // vault is unlocked
+        if let Some(metadata) = metadata.filter(|_| item.item_type.as_deref() == Some("image")) {
+            if let Ok(arr) = item_content_key(&conn, item_id, key) {
+                exif::apply(&conn, item_id, &metadata, &arr)?;
+            }
+        }
+        Ok(local)
+    }).await
+}
+
+/// Camera, taken-at time and GPS position read from an image item's EXIF, if it had any
+#[tauri::command]
+async fn get_image_metadata(item_id: i64, key: Option<Vec<u8>>) -> Result<Option<exif::ImageMetadata>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        let arr = item_content_key(&conn, item_id, key)?;
+        exif::get(&conn, item_id, &arr)
+    }).await
+}
+
+/// Re-index an item after something indexed along with it changed, such as an image's recognised text and labels or
+/// the item's annotations. `fallback_type` is used for items without a stored type, which are otherwise inferred.
+fn reindex_item(conn: &rusqlite::Connection, item_id: i64, key: Option<Vec<u8>>, fallback_type: Option<&str>) -> Result<(), BrainboxError> {
+    let it = VaultItem::get_by_id(conn, item_id)?;
+    let arr = resolve_key(conn, it.vault_id, key)?;
+    let arr = envelope::content_key(conn, it.vault_id, &arr)?;
+    let content = decrypt_content(&arr, &it.content)?;
+    let item_type = it.item_type.clone()
+        .unwrap_or_else(|| fallback_type.unwrap_or_else(|| vault::infer_item_type(&content)).to_string());
+    let _ = crate::search::index_document(crate::search::DocumentInput {
+        id: item_id.to_string(),
+        title: it.title.clone(),
+        content,
+        item_type,
+        created_at: it.created_at.clone(),
+        updated_at: it.updated_at.clone(),
+        tags: tags::get(conn, item_id)?,
+        vault_id: Some(it.vault_id),
+        ..Default::default()
+    });
+    Ok(())
+}
+
+/// Read the text in an item's image and label what it shows with a local vision model; labels become tags
+#[tauri::command]
+async fn analyze_item_image(item_id: i64, model: String, base_url: Option<String>, key: Option<Vec<u8>>) -> Result<image_labels::ImageAnalysis, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        let arr = item_content_key(&conn, item_id, key.clone())?;
+        let analysis = image_labels::analyze_item(&conn, item_id, &arr, &sanitize_base_url(base_url), &model)?;
+        reindex_item(&conn, item_id, key, Some("image"))?;
+        Ok(analysis)
+    }).await
+}
+
+/// Analyze every image item in a vault that hasn't been analyzed yet; returns how many were
+#[tauri::command]
+async fn analyze_vault_images(vault_id: i64, model: String, base_url: Option<String>, key: Option<Vec<u8>>) -> Result<usize, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        // Fail early on a locked vault rather than after analyzing everything
+        let arr = resolve_key(&conn, vault_id, key.clone())?;
+        let arr = envelope::content_key(&conn, vault_id, &arr)?;
+        let base = sanitize_base_url(base_url);
+        let mut analyzed = 0;
+        for item_id in image_labels::pending(&conn, vault_id)? {
+            match image_labels::analyze_item(&conn, item_id, &arr, &base, &model) {
+                Ok(_) => {
+                    reindex_item(&conn, item_id, key.clone(), Some("image"))?;
+                    analyzed += 1;
+                }
+                // An unreachable model fails every item, so stop; skip images that can't be read
+                Err(e @ BrainboxError::Network(_)) => return Err(e),
+                Err(e) => tracing::warn!("Skipping image analysis for item {}: {}", item_id, e),
+            }
+        }
+        Ok(analyzed)
+    }).await
+}
+
+/// Stored text and labels for an image item, if it has been analyzed
+#[tauri::command]
+async fn get_image_analysis(item_id: i64, key: Option<Vec<u8>>) -> Result<Option<image_labels::ImageAnalysis>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        let arr = item_content_key(&conn, item_id, key)?;
+        image_labels::get(&conn, item_id, &arr)
+    }).await
+}
+
+/// URL of a small cached thumbnail (default 256px) for a screenshot or image attachment
+#[tauri::command]
+async fn get_thumbnail(path: String, size: Option<u32>) -> Result<String, BrainboxError> {
+    run_blocking(move || {
+        thumbnails::get_or_create(Path::new(&path), size.unwrap_or(thumbnails::DEFAULT_THUMBNAIL_SIZE))
+    }).await
+}
+
+/// Import files dropped onto the window into a vault; returns one result per path
+#[tauri::command]
+async fn import_dropped_files(paths: Vec<String>, vault_id: i64, key: Option<Vec<u8>>) -> Result<Vec<drop_import::DroppedFileResult>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        VaultItem::create_table(&conn)?;
+        let arr = resolve_key(&conn, vault_id, key)?;
+        let arr = envelope::content_key(&conn, vault_id, &arr)?;
+        Ok(drop_import::import_files(&conn, &paths, vault_id, &arr))
+    }).await
+}
+
+#[tauri::command]
+async fn update_vault_item_content(item_id: i64, content: String, key: Option<Vec<u8>>) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        crate::vault::VaultItem::create_table(&conn)?;
+        let vault_id = crate::vault::VaultItem::get_by_id(&conn, item_id)?.vault_id;
+        let arr = resolve_key(&conn, vault_id, key)?;
+        let arr = envelope::content_key(&conn, vault_id, &arr)?;
+        crate::vault::VaultItem::update_content(&conn, item_id, &content, &arr)?;
+        // Best-effort: update search index
+        let it = crate::vault::VaultItem::get_by_id(&conn, item_id)?;
+        let item_type = it.item_type.clone().unwrap_or_else(|| vault::infer_item_type(&content).to_string());
+        let _ = crate::search::index_document(crate::search::DocumentInput {
+            id: item_id.to_string(),
+            title: it.title.clone(),
+            content: content.clone(),
+            item_type,
+            created_at: it.created_at.clone(),
+            updated_at: it.updated_at.clone(),
+            vault_id: Some(it.vault_id),
+            ..Default::default()
+        });
+        Ok(())
+    }).await
+}
+
+/// Change an item's type (note/url/image/file/task) and re-index it under the new type
+#[tauri::command]
+async fn update_vault_item_type(item_id: i64, item_type: String, key: Option<Vec<u8>>) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        VaultItem::create_table(&conn)?;
+        if !vault::is_valid_item_type(&item_type) {
+            return Err(BrainboxError::InvalidInput(format!("Unknown item type: {}", item_type)));
+        }
+        let arr = resolve_key(&conn, VaultItem::get_by_id(&conn, item_id)?.vault_id, key)?;
+        VaultItem::update_item_type(&conn, item_id, &item_type)?;
+        // Best-effort: re-index so type filters see the change
+        let it = VaultItem::get_by_id(&conn, item_id)?;
+        let arr = envelope::content_key(&conn, it.vault_id, &arr)?;
+        if let Ok(content) = decrypt_content(&arr, &it.content) {
+            let _ = crate::search::index_document(crate::search::DocumentInput {
+                id: item_id.to_string(),
+                title: it.title.clone(),
+                content,
+                item_type,
+                created_at: it.created_at.clone(),
+                updated_at: it.updated_at.clone(),
+                vault_id: Some(it.vault_id),
+                ..Default::default()
+            });
+        }
+        Ok(())
+    }).await
+}
+
+/// Item label color as #rgb/#rrggbb; None clears it
+#[tauri::command]
+async fn update_item_color(item_id: i64, color: Option<String>) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let color = color.map(|c| c.trim().to_lowercase()).filter(|c| !c.is_empty());
+        if let Some(c) = &color {
+            if !vault::is_valid_color(c) {
+                return Err(BrainboxError::InvalidInput(format!("Invalid color: {}", c)));
+            }
+        }
+        let conn = db::open()?;
+        VaultItem::get_by_id(&conn, item_id)?;
+        VaultItem::set_color(&conn, item_id, color.as_deref()).map_err(BrainboxError::from)
+    }).await
+}
+
+/// Item icon: an emoji or the name of a builtin icon; None clears it
+#[tauri::command]
+async fn update_item_icon(item_id: i64, icon: Option<String>) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let icon = icon.map(|i| i.trim().to_string()).filter(|i| !i.is_empty());
+        if icon.as_ref().is_some_and(|i| i.chars().count() > vault::MAX_ICON_LEN) {
+            return Err(BrainboxError::InvalidInput("Icon is too long".to_string()));
+        }
+        let conn = db::open()?;
+        VaultItem::get_by_id(&conn, item_id)?;
+        VaultItem::set_icon(&conn, item_id, icon.as_deref()).map_err(BrainboxError::from)
+    }).await
+}
+
+/// Keep an item out of sync and shares even though its vault syncs, or let it sync again
+#[tauri::command]
+async fn set_item_no_sync(item_id: i64, no_sync: bool) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        VaultItem::get_by_id(&conn, item_id)?;
+        VaultItem::set_no_sync(&conn, item_id, no_sync).map_err(BrainboxError::from)
+    }).await
+}
+
+/// Set or clear the due date of an item (RFC 3339 or YYYY-MM-DD)
+#[tauri::command]
+async fn set_item_due_date(item_id: i64, due_at: Option<String>) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        VaultItem::create_table(&conn)?;
+        let due_at = match due_at.as_deref().map(str::trim) {
+            Some(d) if !d.is_empty() => Some(tasks::normalize_due_date(d)?),
+            _ => None,
+        };
+        VaultItem::set_due_date(&conn, item_id, due_at.as_deref()).map_err(BrainboxError::from)
+    }).await
+}
+
+#[tauri::command]
+async fn set_item_completed(item_id: i64, completed: bool) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        VaultItem::create_table(&conn)?;
+        VaultItem::set_completed(&conn, item_id, completed).map_err(BrainboxError::from)
+    }).await
+}
+
+#[tauri::command]
+async fn mark_item_read(item_id: i64) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        VaultItem::set_read(&conn, item_id, true).map_err(BrainboxError::from)
+    }).await
+}
+
+#[tauri::command]
+async fn mark_item_unread(item_id: i64) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        VaultItem::set_read(&conn, item_id, false).map_err(BrainboxError::from)
+    }).await
+}
+
+/// Save `url` to the Wayback Machine; with `item_id`, the snapshot is stored on that item
+#[tauri::command]
+async fn archive_to_wayback(url: String, item_id: Option<i64>) -> Result<wayback::WaybackSnapshot, BrainboxError> {
+    run_blocking(move || {
+        let snapshot = wayback::save(&url)?;
+        if let Some(item_id) = item_id {
+            wayback::store(&db::open()?, item_id, &snapshot)?;
+        }
+        Ok(snapshot)
+    }).await
+}
+
+/// Latest existing Wayback Machine snapshot of `url`; with `item_id`, a found snapshot is stored on that item
+#[tauri::command]
+async fn get_wayback_snapshot(url: String, item_id: Option<i64>) -> Result<Option<wayback::WaybackSnapshot>, BrainboxError> {
+    run_blocking(move || {
+        let snapshot = wayback::lookup(&url)?;
+        if let (Some(item_id), Some(snapshot)) = (item_id, &snapshot) {
+            wayback::store(&db::open()?, item_id, snapshot)?;
+        }
+        Ok(snapshot)
+    }).await
+}
+
+#[derive(serde::Serialize)]
+struct UnreadItem {
+    id: i64,
+    vault_id: i64,
+    title: String,
+    created_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    image: Option<String>,
+}
+
+/// The read-later queue of a vault: URL items not yet marked read, oldest first
+#[tauri::command]
+async fn list_unread(vault_id: i64) -> Result<Vec<UnreadItem>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        let items = VaultItem::list_unread(&conn, vault_id)?;
+        Ok(items
+            .into_iter()
+            .map(|it| UnreadItem { id: it.id, vault_id: it.vault_id, title: it.title, created_at: it.created_at, image: it.image })
+            .collect())
+    }).await
+}
+
+/// Unread URL item count per vault id; vaults with nothing unread are left out
+#[tauri::command]
+async fn get_unread_counts() -> Result<std::collections::HashMap<i64, i64>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        Ok(VaultItem::unread_counts(&conn)?.into_iter().collect())
+    }).await
+}
+
+#[derive(serde::Serialize)]
+struct UpcomingTask {
+    id: i64,
+    vault_id: i64,
+    title: String,
+    due_at: String,
+    overdue: bool,
+}
+
+/// Open tasks due within the next `days` days, including overdue ones, across all vaults
+#[tauri::command]
+async fn list_upcoming_tasks(days: i64) -> Result<Vec<UpcomingTask>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        let now = chrono::Utc::now();
+        let until = (now + chrono::Duration::days(days.max(0))).to_rfc3339();
+        let now = now.to_rfc3339();
+        let items = VaultItem::list_upcoming_tasks(&conn, &until)?;
+        Ok(items
+            .into_iter()
+            .filter_map(|it| {
+                let due_at = it.due_at?;
+                Some(UpcomingTask {
+                    id: it.id,
+                    vault_id: it.vault_id,
+                    title: it.title,
+                    overdue: due_at < now,
+                    due_at,
+                })
+            })
+            .collect())
+    }).await
+}
+
+/// Markdown checkboxes in an item's content
+#[tauri::command]
+async fn get_item_tasks(item_id: i64, key: Option<Vec<u8>>) -> Result<Vec<tasks::TaskCheckbox>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        VaultItem::create_table(&conn)?;
+        let it = VaultItem::get_by_id(&conn, item_id)?;
+        let arr = resolve_key(&conn, it.vault_id, key)?;
+        let arr = envelope::content_key(&conn, it.vault_id, &arr)?;
+        let content = decrypt_content(&arr, &it.content)?;
+        Ok(tasks::parse_checkboxes(&content))
+    }).await
+}
+
+/// Toggle the checkbox on `line` of an item's content and save it; returns the updated checkboxes
+#[tauri::command]
+async fn toggle_item_checkbox(item_id: i64, line: usize, key: Option<Vec<u8>>) -> Result<Vec<tasks::TaskCheckbox>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        VaultItem::create_table(&conn)?;
+        let it = VaultItem::get_by_id(&conn, item_id)?;
+        let arr = resolve_key(&conn, it.vault_id, key)?;
+        let arr = envelope::content_key(&conn, it.vault_id, &arr)?;
+        let content = decrypt_content(&arr, &it.content)?;
+        let updated = tasks::toggle_checkbox(&content, line).ok_or("No checkbox on that line")?;
+        VaultItem::update_content(&conn, item_id, &updated, &arr)?;
+        Ok(tasks::parse_checkboxes(&updated))
+    }).await
+}
+
+/// Schedule a reminder for an item. `repeat` is one of reminders::REPEAT_RULES.
+#[tauri::command]
+async fn set_reminder(item_id: i64, remind_at: String, repeat: Option<String>) -> Result<reminders::Reminder, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        VaultItem::create_table(&conn)?;
+        VaultItem::get_by_id(&conn, item_id).map_err(|_| "Item not found".to_string())?;
+        let remind_at = reminders::normalize_remind_at(&remind_at)?;
+        let repeat = match repeat {
+            Some(r) if r == "none" => None,
+            Some(r) if reminders::is_valid_repeat_rule(&r) => Some(r),
+            Some(r) => return Err(BrainboxError::InvalidInput(format!("Unknown repeat rule: {}", r))),
+            None => None,
+        };
+        reminders::Reminder::insert(&conn, item_id, &remind_at, repeat.as_deref()).map_err(BrainboxError::from)
+    }).await
+}
+
+/// Active (not dismissed) reminders, optionally for a single item
+#[tauri::command]
+async fn list_reminders(item_id: Option<i64>) -> Result<Vec<reminders::Reminder>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        VaultItem::create_table(&conn)?;
+        reminders::Reminder::list_active(&conn, item_id).map_err(BrainboxError::from)
+    }).await
+}
+
+#[tauri::command]
+async fn dismiss_reminder(reminder_id: i64) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        reminders::Reminder::dismiss(&conn, reminder_id).map_err(BrainboxError::from)
+    }).await
+}
+
+#[tauri::command]
+async fn create_template(name: String, title: String, content: String, item_type: Option<String>) -> Result<templates::Template, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        if name.trim().is_empty() {
+            return Err(BrainboxError::InvalidInput("Template name cannot be empty".to_string()));
+        }
+        if let Some(t) = item_type.as_deref() {
+            if !vault::is_valid_item_type(t) {
+                return Err(BrainboxError::InvalidInput(format!("Unknown item type: {}", t)));
+            }
+        }
+        templates::Template::insert(&conn, name.trim(), &title, &content, item_type.as_deref()).map_err(BrainboxError::from)
+    }).await
+}
+
+#[tauri::command]
+async fn list_templates() -> Result<Vec<templates::Template>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        templates::Template::list(&conn).map_err(BrainboxError::from)
+    }).await
+}
+
+#[tauri::command]
+async fn delete_template(template_id: i64) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        templates::Template::delete(&conn, template_id)?;
+        Ok(())
+    }).await
+}
+
+/// Create a new item from a template, substituting {{placeholders}} in its title and content.
+/// The frontend supplies values such as clipboard and url through `variables`.
+#[tauri::command]
+async fn create_item_from_template(
+    vault_id: i64,
+    template_id: i64,
+    key: Option<Vec<u8>>,
+    variables: Option<HashMap<String, String>>,
+) -> Result<VaultItem, BrainboxError> {
+    let (title, content, item_type) = run_blocking(move || {
+        let conn = db::open()?;
+        let template = templates::Template::get_by_id(&conn, template_id)
+            ?
+            .ok_or("Template not found")?;
+        let variables = variables.unwrap_or_default();
+        let title = templates::render(&template.title, &variables);
+        let content = templates::render(&template.content, &variables);
+        Ok((title, content, template.item_type))
+    }).await?;
+    add_vault_item(vault_id, title, content, key, None, item_type).await
+}
+
+// --- Prompts ---
+
+fn check_prompt(input: &prompts::PromptInput) -> Result<(), BrainboxError> {
+    if input.name.trim().is_empty() {
+        return Err(BrainboxError::InvalidInput("Prompt name cannot be empty".to_string()));
+    }
+    if input.template.trim().is_empty() {
+        return Err(BrainboxError::InvalidInput("Prompt text cannot be empty".to_string()));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn create_prompt(prompt: prompts::PromptInput) -> Result<prompts::Prompt, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        check_prompt(&prompt)?;
+        prompts::Prompt::insert(&conn, &prompt).map_err(BrainboxError::from)
+    }).await
+}
+
+#[tauri::command]
+async fn update_prompt(prompt_id: i64, prompt: prompts::PromptInput) -> Result<prompts::Prompt, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        check_prompt(&prompt)?;
+        prompts::Prompt::update(&conn, prompt_id, &prompt)?
+            .ok_or_else(|| BrainboxError::NotFound(format!("Prompt {} not found", prompt_id)))
+    }).await
+}
+
+#[tauri::command]
+async fn list_prompts() -> Result<Vec<prompts::Prompt>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        prompts::Prompt::list(&conn).map_err(BrainboxError::from)
+    }).await
+}
+
+#[tauri::command]
+async fn delete_prompt(prompt_id: i64) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        prompts::Prompt::delete(&conn, prompt_id)?;
+        Ok(())
+    }).await
+}
+
+/// Run a saved prompt on items, streaming the answer as "ollama-stream" events under `stream_id`. `keys` match
+/// `item_ids`; items without a key use their vault's unlocked key. The prompt's own model wins over `model`.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn run_prompt(
+    app: tauri::AppHandle,
+    prompt_id: i64,
+    item_ids: Vec<i64>,
+    keys: Option<Vec<Option<Vec<u8>>>>,
+    model: Option<String>,
+    base_url: Option<String>,
+    stream_id: String,
+    options: Option<ollama_options::GenerationOptions>,
+) -> Result<String, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        let options = ollama_options::resolve(&conn, "prompts", options)?;
+        let prompt = prompts::Prompt::get_by_id(&conn, prompt_id)?
+            .ok_or_else(|| BrainboxError::NotFound(format!("Prompt {} not found", prompt_id)))?;
+        let keys = keys.unwrap_or_else(|| vec![None; item_ids.len()]);
+        if keys.len() != item_ids.len() {
+            return Err(BrainboxError::InvalidInput("Item IDs and keys must have the same length".to_string()));
+        }
+        let mut items = Vec::with_capacity(item_ids.len());
+        for (item_id, key) in item_ids.iter().zip(keys) {
+            let (item, content) = read_item_content(&conn, *item_id, key)?;
+            items.push(prompts::PromptItem { title: item.title, content });
+        }
+        let model = prompt
+            .model
+            .clone()
+            .or(model.filter(|m| !m.trim().is_empty()))
+            .ok_or_else(|| BrainboxError::InvalidInput("Choose a model to run the prompt".to_string()))?;
+        let text = prompts::fill(&prompt.template, &items, prompt.system.as_deref(), &model, options.num_ctx);
+        let body = OllamaGenerateRequest {
+            model: &model,
+            prompt: &text,
+            stream: true,
+            system: prompt.system.as_deref(),
+            options: options.request(),
+        };
+        stream_generate(&app, &sanitize_base_url(base_url), &body, &stream_id, |_| Ok(()))
+    }).await
+}
+
+// --- Entities ---
+
+fn entity_model(conn: &rusqlite::Connection, model: Option<String>, base_url: Option<String>) -> Result<Option<entities::Model>, BrainboxError> {
+    let Some(model) = model.filter(|m| !m.trim().is_empty()) else { return Ok(None) };
+    let options = ollama_options::resolve(conn, "entities", None)?;
+    Ok(Some(entities::Model { base_url: sanitize_base_url(base_url), model, options }))
+}
+
+/// Find the emails, sites and @mentions across a vault's notes, and with `model` (a local Ollama model) the people,
+/// companies and projects too. Notes unchanged since the last pass are skipped unless `force` is set.
+#[tauri::command]
+async fn extract_entities(
+    vault_id: i64,
+    key: Option<Vec<u8>>,
+    model: Option<String>,
+    base_url: Option<String>,
+    force: Option<bool>,
+) -> Result<entities::ExtractionReport, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        let arr = resolve_key(&conn, vault_id, key)?;
+        let arr = envelope::content_key(&conn, vault_id, &arr)?;
+        entities::extract_vault(&conn, vault_id, &arr, entity_model(&conn, model, base_url)?.as_ref(), force.unwrap_or(false))
+    }).await
+}
+
+/// Find the entities one note mentions, e.g. after it was saved; returns how many it has
+#[tauri::command]
+async fn extract_item_entities(item_id: i64, key: Option<Vec<u8>>, model: Option<String>, base_url: Option<String>) -> Result<usize, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        let arr = item_content_key(&conn, item_id, key)?;
+        entities::extract_item(&conn, item_id, &arr, entity_model(&conn, model, base_url)?.as_ref())
+    }).await
+}
+
+/// A vault's entities, most mentioned first; `kind` is one of person, company, project, email, domain or mention
+#[tauri::command]
+async fn list_entities(vault_id: i64, key: Option<Vec<u8>>, kind: Option<String>) -> Result<Vec<entities::Entity>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        if let Some(kind) = kind.as_deref().filter(|k| !entities::is_valid_kind(k)) {
+            return Err(BrainboxError::InvalidInput(format!("Unknown entity kind: {}", kind)));
+        }
+        let arr = resolve_key(&conn, vault_id, key)?;
+        let arr = envelope::content_key(&conn, vault_id, &arr)?;
+        entities::list(&conn, vault_id, &arr, kind.as_deref())
+    }).await
+}
+
+/// Notes that mention an entity, for its topic page
+#[tauri::command]
+async fn list_items_for_entity(entity_id: i64, key: Option<Vec<u8>>) -> Result<Vec<entities::EntityItem>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        // A locked vault's entities stay hidden until it is unlocked
+        resolve_key(&conn, entities::vault_id(&conn, entity_id)?, key)?;
+        entities::items_for(&conn, entity_id)
+    }).await
+}
+
+// --- Calendar import ---
+
+/// Import the events of an .ics file, or of a feed at an http(s) or webcal URL, as notes in a vault. Importing the
+/// same calendar again updates those notes. With `notes_template_id`, a note is made from that template
+/// `notes_lead_minutes` before each upcoming event.
+#[tauri::command]
+async fn import_ics(
+    source: String,
+    vault_id: i64,
+    key: Option<Vec<u8>>,
+    notes_template_id: Option<i64>,
+    notes_lead_minutes: Option<i64>,
+) -> Result<calendar::IcsImportReport, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        let arr = resolve_key(&conn, vault_id, key)?;
+        let arr = envelope::content_key(&conn, vault_id, &arr)?;
+        let notes = match notes_template_id {
+            Some(template_id) => {
+                templates::Template::get_by_id(&conn, template_id)?
+                    .ok_or_else(|| BrainboxError::NotFound("Template not found".to_string()))?;
+                let lead_minutes = notes_lead_minutes
+                    .unwrap_or(calendar::DEFAULT_NOTES_LEAD_MINUTES)
+                    .clamp(0, calendar::MAX_NOTES_LEAD_MINUTES);
+                Some(calendar::MeetingNotesOptions { template_id, lead_minutes })
+            }
+            None => None,
+        };
+        let text = calendar::read_source(&source)?;
+        calendar::import(&conn, vault_id, &arr, source.trim(), &text, notes.as_ref())
+    }).await
+}
+
+// --- Search suggestions ---
+
+/// Completions for the search box from indexed titles and tags
+#[tauri::command]
+async fn suggest_search_terms(prefix: String, limit: Option<usize>) -> Result<Vec<search::SearchSuggestion>, BrainboxError> {
+    let limit = limit.unwrap_or(8).clamp(1, 50);
+    run_blocking(move || search::suggest(&prefix, limit)).await
+}
+
+#[tauri::command]
+async fn get_recent_searches() -> Result<Vec<String>, BrainboxError> {
+    run_blocking(|| {
+        let conn = db::open()?;
+        saved_searches::recent_queries(&conn)
+    }).await
+}
+
+#[tauri::command]
+async fn add_recent_search(query: String) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        saved_searches::record_recent_query(&conn, &query)
+    }).await
+}
+
+#[tauri::command]
+async fn clear_recent_searches() -> Result<(), BrainboxError> {
+    run_blocking(|| {
+        let conn = db::open()?;
+        saved_searches::clear_recent_queries(&conn)
+    }).await
+}
+
+// --- Search languages ---
+
+#[tauri::command]
+async fn get_search_languages() -> Result<tokenizers::SearchLanguages, BrainboxError> {
+    run_blocking(|| {
+        let conn = db::open()?;
+        tokenizers::get_languages(&conn)
+    }).await
+}
+
+/// Change the title/content tokenizers. Returns true when the index was cleared and the frontend must rebuild it.
+#[tauri::command]
+async fn set_search_languages(languages: tokenizers::SearchLanguages) -> Result<bool, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        tokenizers::set_languages(&conn, &languages)?;
+        search::apply_languages(&languages)
+    }).await
+}
+
+#[tauri::command]
+fn search_index_needs_rebuild() -> bool {
+    search::needs_rebuild()
+}
+
+#[tauri::command]
+fn mark_search_index_rebuilt() {
+    search::mark_rebuilt();
+}
+
+// --- Access history ---
+
+/// Log that an item was opened (feeds the recent list and the search boost)
+#[tauri::command]
+async fn record_item_access(item_id: i64) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        access_log::record(&conn, item_id)
+    }).await
+}
+
+/// Recently opened items across vaults; pass the ids of unlocked password-protected vaults to see their titles
+#[tauri::command]
+async fn list_recent_items(limit: Option<usize>, unlocked_vault_ids: Option<Vec<i64>>) -> Result<Vec<access_log::RecentItem>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        access_log::list_recent(&conn, limit.unwrap_or(20).min(200), &unlocked_vault_ids.unwrap_or_default())
+    }).await
+}
+
+#[tauri::command]
+async fn clear_access_history() -> Result<(), BrainboxError> {
+    run_blocking(|| {
+        let conn = db::open()?;
+        access_log::clear(&conn)
+    }).await
+}
+
+// --- Search index maintenance ---
+
+#[tauri::command]
+async fn get_search_index_stats() -> Result<search::SearchIndexStats, BrainboxError> {
+    run_blocking(search::index_stats).await
+}
+
+/// Merge index segments and purge deleted documents (can take a while on large indexes)
+#[tauri::command]
+async fn optimize_search_index() -> Result<search::SearchIndexStats, BrainboxError> {
+    run_blocking(search::optimize_index).await
+}
+
+/// What the index on disk keeps of password-protected vaults: "title_only" or "none"
+#[tauri::command]
+async fn get_locked_vault_indexing() -> Result<String, BrainboxError> {
+    run_blocking(|| {
+        let conn = db::open()?;
+        search::locked_vault_indexing(&conn)
+    }).await
+}
+
+/// Change the locked vault policy and rewrite those vaults' documents on disk; returns how many were written
+#[tauri::command]
+async fn set_locked_vault_indexing(policy: String) -> Result<u64, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        search::set_locked_vault_indexing(&conn, &policy)
+    }).await
+}
+
+// --- Saved searches ---
+
+#[tauri::command]
+async fn create_saved_search(name: String, query: String, filters: Option<search::SearchFilters>) -> Result<saved_searches::SavedSearch, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        if name.trim().is_empty() {
+            return Err(BrainboxError::InvalidInput("Saved search name cannot be empty".to_string()));
+        }
+        let filters = filters.unwrap_or_default();
+        if let Some(t) = filters.item_type.as_deref() {
+            if !vault::is_valid_item_type(t) {
+                return Err(BrainboxError::InvalidInput(format!("Unknown item type: {}", t)));
+            }
+        }
+        saved_searches::SavedSearch::insert(&conn, name.trim(), query.trim(), &filters).map_err(BrainboxError::from)
+    }).await
+}
+
+#[tauri::command]
+async fn list_saved_searches() -> Result<Vec<saved_searches::SavedSearch>, BrainboxError> {
+    run_blocking(|| {
+        let conn = db::open()?;
+        saved_searches::SavedSearch::list(&conn).map_err(BrainboxError::from)
+    }).await
+}
+
+#[tauri::command]
+async fn update_saved_search(search_id: i64, name: String, query: String, filters: Option<search::SearchFilters>) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        if name.trim().is_empty() {
+            return Err(BrainboxError::InvalidInput("Saved search name cannot be empty".to_string()));
+        }
+        let updated = saved_searches::SavedSearch::update(&conn, search_id, name.trim(), query.trim(), &filters.unwrap_or_default())?;
+        if updated == 0 {
+            return Err(BrainboxError::NotFound("Saved search not found".to_string()));
+        }
+        Ok(())
+    }).await
+}
+
+#[tauri::command]
+async fn delete_saved_search(search_id: i64) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        saved_searches::SavedSearch::delete(&conn, search_id)?;
+        Ok(())
+    }).await
+}
+
+/// Search with structured filters (type, tags, vault, dates, color) without saving the search first
+#[tauri::command]
+async fn search_with_filters(query: String, filters: search::SearchFilters, limit: Option<usize>) -> Result<Vec<search::SearchResult>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        let limit = limit.unwrap_or(saved_searches::DEFAULT_RESULT_LIMIT).clamp(1, 1000);
+        saved_searches::run_filtered(&conn, &query, &filters, limit)
+    }).await
+}
+
+/// Evaluate a saved search against the current index
+#[tauri::command]
+async fn run_saved_search(search_id: i64, limit: Option<usize>) -> Result<Vec<search::SearchResult>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        let saved = saved_searches::SavedSearch::get_by_id(&conn, search_id)?
+            .ok_or_else(|| BrainboxError::NotFound("Saved search not found".to_string()))?;
+        let limit = limit.unwrap_or(saved_searches::DEFAULT_RESULT_LIMIT).clamp(1, 1000);
+        saved_searches::run(&conn, &saved, limit)
+    }).await
+}
+
+#[tauri::command]
+async fn update_vault_item_summary(item_id: i64, summary: String) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        VaultItem::create_table(&conn)?;
+        VaultItem::update_summary(&conn, item_id, &summary).map_err(BrainboxError::from)
+    }).await
+}
+
+// --- Collections (nested folders inside a vault) ---
+
+/// Check that a collection exists, isn't deleted, and belongs to the given vault
+fn ensure_collection_in_vault(conn: &rusqlite::Connection, collection_id: i64, vault_id: i64) -> Result<Collection, BrainboxError> {
+    match Collection::get_by_id(conn, collection_id)? {
+        Some(c) if c.deleted_at.is_none() && c.vault_id == vault_id => Ok(c),
+        Some(c) if c.deleted_at.is_none() => Err(BrainboxError::InvalidInput(format!("Collection {} belongs to a different vault", c.id))),
+        _ => Err(BrainboxError::NotFound("Collection not found".to_string())),
+    }
+}
+
+#[tauri::command]
+async fn create_collection(vault_id: i64, name: String, parent_id: Option<i64>) -> Result<Collection, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        Vault::create_table(&conn)?;
+        Collection::create_table(&conn)?;
+        if name.trim().is_empty() {
+            return Err(BrainboxError::InvalidInput("Collection name cannot be empty".to_string()));
+        }
+        if let Some(pid) = parent_id {
+            ensure_collection_in_vault(&conn, pid, vault_id)?;
+        }
+        Collection::insert(&conn, vault_id, parent_id, name.trim()).map_err(BrainboxError::from)
+    }).await
+}
+
+#[tauri::command]
+async fn list_collections(vault_id: i64) -> Result<Vec<Collection>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        Collection::list_by_vault(&conn, vault_id).map_err(BrainboxError::from)
+    }).await
+}
+
+#[tauri::command]
+async fn rename_collection(collection_id: i64, name: String) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        if name.trim().is_empty() {
+            return Err(BrainboxError::InvalidInput("Collection name cannot be empty".to_string()));
+        }
+        Collection::rename(&conn, collection_id, name.trim()).map_err(BrainboxError::from)
+    }).await
+}
+
+/// Move a collection under another collection of the same vault (None = vault root)
+#[tauri::command]
+async fn move_collection(collection_id: i64, parent_id: Option<i64>) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        let collection = Collection::get_by_id(&conn, collection_id)
+            ?
+            .ok_or("Collection not found")?;
+        if let Some(pid) = parent_id {
+            ensure_collection_in_vault(&conn, pid, collection.vault_id)?;
+            if Collection::is_descendant(&conn, collection_id, pid)? {
+                return Err(BrainboxError::InvalidInput("Cannot move a collection into itself or one of its children".to_string()));
+            }
+        }
+        Collection::move_to_parent(&conn, collection_id, parent_id).map_err(BrainboxError::from)
+    }).await
+}
+
+/// Delete a collection and its children; contained items move up to the parent
+#[tauri::command]
+async fn delete_collection(collection_id: i64) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        Collection::delete(&conn, collection_id).map_err(BrainboxError::from)
+    }).await
+}
+
+#[tauri::command]
+async fn update_collections_order(vault_id: i64, ordered_ids: Vec<i64>) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        Collection::update_order(&conn, vault_id, &ordered_ids).map_err(BrainboxError::from)
+    }).await
+}
+
+/// Place an item in a collection of its vault (None = vault root)
+#[tauri::command]
+async fn move_item_to_collection(item_id: i64, collection_id: Option<i64>) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        VaultItem::create_table(&conn)?;
+        let item = VaultItem::get_by_id(&conn, item_id)?;
+        if let Some(cid) = collection_id {
+            ensure_collection_in_vault(&conn, cid, item.vault_id)?;
+        }
+        VaultItem::set_collection(&conn, item_id, collection_id).map_err(BrainboxError::from)
+    }).await
+}
+
+fn board_name(name: &str, what: &str) -> Result<String, BrainboxError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(BrainboxError::InvalidInput(format!("{} name cannot be empty", what)));
+    }
+    Ok(name.to_string())
+}
+
+/// A vault's kanban boards, each with its columns and the items placed in them
+#[tauri::command]
+async fn list_boards(vault_id: i64) -> Result<Vec<boards::Board>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        boards::list(&conn, vault_id)
+    }).await
+}
+
+/// Create a board; without `columns` it starts with Backlog, Reading and Done
+#[tauri::command]
+async fn create_board(vault_id: i64, name: String, columns: Option<Vec<String>>) -> Result<boards::Board, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        Vault::get_by_id(&conn, vault_id)?
+            .ok_or_else(|| BrainboxError::NotFound(format!("Vault {} not found", vault_id)))?;
+        let columns = columns.unwrap_or_default().iter().map(|c| board_name(c, "Column")).collect::<Result<Vec<_>, _>>()?;
+        boards::create(&conn, vault_id, &board_name(&name, "Board")?, &columns)
+    }).await
+}
+
+#[tauri::command]
+async fn rename_board(board_id: i64, name: String) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        boards::rename(&conn, board_id, &board_name(&name, "Board")?)
+    }).await
+}
+
+/// Delete a board; its items stay in the vault
+#[tauri::command]
+async fn delete_board(board_id: i64) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        boards::delete(&conn, board_id)
+    }).await
+}
+
+#[tauri::command]
+async fn add_board_column(board_id: i64, name: String) -> Result<boards::Board, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        boards::add_column(&conn, board_id, &board_name(&name, "Column")?)
+    }).await
+}
+
+#[tauri::command]
+async fn rename_board_column(column_id: i64, name: String) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        boards::rename_column(&conn, column_id, &board_name(&name, "Column")?)
+    }).await
+}
+
+/// Delete a column; its items are taken off the board
+#[tauri::command]
+async fn delete_board_column(column_id: i64) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        boards::delete_column(&conn, column_id)
+    }).await
+}
+
+#[tauri::command]
+async fn reorder_board_columns(board_id: i64, column_ids: Vec<i64>) -> Result<boards::Board, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        boards::reorder_columns(&conn, board_id, &column_ids)
+    }).await
+}
+
+/// Place an item in a board column at `position` (the end when omitted); no column takes it off the board
+#[tauri::command]
+async fn move_item_to_column(item_id: i64, board_id: i64, column_id: Option<i64>, position: Option<usize>) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        VaultItem::create_table(&conn)?;
+        let item = VaultItem::get_by_id(&conn, item_id)?;
+        if boards::get(&conn, board_id)?.vault_id != item.vault_id {
+            return Err(BrainboxError::InvalidInput("Board is not in the item's vault".to_string()));
+        }
+        boards::move_item(&conn, item_id, board_id, column_id, position)
+    }).await
+}
+
+/// Export vault data structure
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ExportedVault {
+    /// Lets a merge import find the vault again; absent in exports from older versions
+    #[serde(default)]
+    uuid: Option<String>,
+    name: String,
+    created_at: String,
+    cover_image: Option<String>,
+    #[serde(default)]
+    color: Option<String>,
+    #[serde(default)]
+    icon: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    items: Vec<ExportedItem>,
+    #[serde(default)]
+    collections: Vec<ExportedCollection>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    boards: Vec<ExportedBoard>,
+}
+
+/// Collections are linked by their UUIDs inside the export file; import assigns fresh ones
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ExportedCollection {
+    uuid: String,
+    parent_uuid: Option<String>,
+    name: String,
+    sort_order: Option<i64>,
+}
+
+/// Boards and their columns are linked by UUID as well; columns are listed in board order
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ExportedBoard {
+    uuid: String,
+    name: String,
+    sort_order: Option<i64>,
+    columns: Vec<ExportedBoardColumn>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ExportedBoardColumn {
+    uuid: String,
+    name: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ExportedItem {
+    #[serde(default)]
+    uuid: Option<String>,
+    title: String,
+    content: String, // plaintext content
+    created_at: String,
+    updated_at: String,
+    image: Option<String>,
+    summary: Option<String>,
+    #[serde(default)]
+    collection_uuid: Option<String>,
+    #[serde(default)]
+    item_type: Option<String>,
+    #[serde(default)]
+    due_at: Option<String>,
+    #[serde(default)]
+    completed_at: Option<String>,
+    #[serde(default)]
+    read_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    icon: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    board_placements: Vec<sync::SyncBoardPlacement>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    annotations: Vec<sync::SyncAnnotation>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ExportData {
+    version: String,
+    exported_at: String,
+    vaults: Vec<ExportedVault>,
+}
+
+/// Export vaults to JSON (decrypts all items)
+#[tauri::command]
+async fn export_vaults(
+    app: tauri::AppHandle,
+    vault_ids: Vec<i64>,
+    keys: Option<Vec<Option<Vec<u8>>>>,
+    operation_id: Option<String>,
+) -> Result<String, BrainboxError> {
+    run_blocking(move || {
+        let op = progress::Operation::new(&app, progress::EXPORT_EVENT, operation_id);
+        // Vaults without a key passed use the unlocked key from the keystore
+        let keys = keys.unwrap_or_else(|| vec![None; vault_ids.len()]);
+        let conn = db::open()?;
+        let export_data = build_export(&conn, &vault_ids, keys, &op)?;
+        serde_json::to_string_pretty(&export_data).map_err(BrainboxError::from)
+    }).await
+}
+
+/// Decrypt and collect `vault_ids` for an export, each opened with the matching key
+fn build_export(
+    conn: &rusqlite::Connection,
+    vault_ids: &[i64],
+    keys: Vec<Option<Vec<u8>>>,
+    op: &progress::Operation,
+) -> Result<ExportData, BrainboxError> {
+    if vault_ids.len() != keys.len() {
+        return Err(BrainboxError::InvalidInput("Vault IDs and keys must have the same length".to_string()));
+    }
+
+    Vault::create_table(conn)?;
+    VaultItem::create_table(conn)?;
+
+    let mut exported_vaults = Vec::new();
+
+    for (vault_id, key) in vault_ids.iter().zip(keys) {
+        let arr = resolve_key(conn, *vault_id, key)?;
+        let arr = envelope::content_key(conn, *vault_id, &arr)?;
+
+        // Get vault info
+        let vault = Vault::get_by_id(conn, *vault_id)?
+            .ok_or_else(|| BrainboxError::NotFound(format!("Vault {} not found", vault_id)))?;
+
+        let collections = Collection::list_by_vault(conn, *vault_id)?;
+        let collection_uuids: HashMap<i64, String> = collections.iter().map(|c| (c.id, c.uuid.clone())).collect();
+        let exported_collections = collections
+            .iter()
+            .map(|c| ExportedCollection {
+                uuid: c.uuid.clone(),
+                parent_uuid: c.parent_id.and_then(|pid| collection_uuids.get(&pid).cloned()),
+                name: c.name.clone(),
+                sort_order: c.sort_order,
+            })
+            .collect();
+
+        let exported_boards = boards::list(conn, *vault_id)?
+            .into_iter()
+            .map(|b| ExportedBoard {
+                uuid: b.uuid,
+                name: b.name,
+                sort_order: b.sort_order,
+                columns: b.columns.into_iter().map(|c| ExportedBoardColumn { uuid: c.uuid, name: c.name }).collect(),
+            })
+            .collect();
+        let mut placements = boards::placements_by_vault(conn, *vault_id)?;
+        let mut annotations = annotations::by_vault(conn, *vault_id, &arr)?;
+
+        // Get and decrypt items
+        let items = VaultItem::list_by_vault(conn, *vault_id)?;
+        let mut exported_items = Vec::new();
+
+        let total = items.len();
+        for (done, item) in items.into_iter().enumerate() {
+            op.check()?;
+            op.progress(&vault.name, done, total);
+            let content = decrypt_content(&arr, &item.content)?;
+            let item_type = item.item_type.unwrap_or_else(|| vault::infer_item_type(&content).to_string());
+            let tags = tags::get(conn, item.id)?;
+            exported_items.push(ExportedItem {
+                uuid: item.uuid,
+                title: item.title,
+                content,
+                created_at: item.created_at,
+                updated_at: item.updated_at,
+                image: image_cache::portable_image(conn, item.image),
+                summary: item.summary,
+                collection_uuid: item.collection_id.and_then(|cid| collection_uuids.get(&cid).cloned()),
+                item_type: Some(item_type),
+                due_at: item.due_at,
+                completed_at: item.completed_at,
+                read_at: item.read_at,
+                color: item.color,
+                icon: item.icon,
+                tags,
+                board_placements: placements.remove(&item.id).unwrap_or_default().into_iter()
+                    .map(|(column_uuid, position)| sync::SyncBoardPlacement { column_uuid, position })
+                    .collect(),
+                annotations: annotations.remove(&item.id).unwrap_or_default(),
+            });
+        }
+
+        exported_vaults.push(ExportedVault {
+            uuid: vault.uuid,
+            name: vault.name,
+            created_at: vault.created_at,
+            cover_image: covers::portable(vault.cover_image),
+            color: vault.color,
+            icon: vault.icon,
+            description: vault.description,
+            items: exported_items,
+            collections: exported_collections,
+            boards: exported_boards,
+        });
+    }
+
+    Ok(ExportData {
+        version: "1.0".to_string(),
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        vaults: exported_vaults,
+    })
+}
+
+/// Hash identifying an item's title and content, so merging skips items that are already in the vault
+fn import_hash(title: &str, content: &str) -> [u8; 32] {
+    use sha2::Digest;
+    let mut hasher = Sha256::new();
+    hasher.update(title.as_bytes());
+    hasher.update([0u8]);
+    hasher.update(content.as_bytes());
+    hasher.finalize().into()
+}
+
+/// An existing vault a merge import adds to: matched by UUID, then by name
+fn find_merge_target(conn: &rusqlite::Connection, vault: &ExportedVault) -> Result<Option<Vault>, BrainboxError> {
+    if let Some(uuid) = &vault.uuid {
+        if let Some(found) = Vault::get_by_uuid(conn, uuid)?.filter(|v| v.deleted_at.is_none()) {
+            return Ok(Some(found));
+        }
+    }
+    let name = vault.name.trim();
+    Ok(Vault::list(conn)?.into_iter().find(|v| v.name.trim().eq_ignore_ascii_case(name)))
+}
+
+/// Import vaults from JSON. By default every vault in the file becomes a new vault; with `merge`, vaults matching
+/// an existing one by UUID or name are added to instead, skipping items already there by UUID or title and content.
+/// Existing vaults must be unlocked or open with `password`. Runs in one transaction, so a failure or
+/// cancellation through `cancel_operation` imports nothing.
+#[tauri::command]
+async fn import_vaults(
+    app: tauri::AppHandle,
+    json_data: String,
+    password: String,
+    merge: Option<bool>,
+    operation_id: Option<String>,
+) -> Result<Vec<i64>, BrainboxError> {
+    run_blocking(move || {
+        let export_data: ExportData = serde_json::from_str(&json_data)
+            .map_err(|e| format!("Invalid export format: {}", e))?;
+
+        let conn = db::open()?;
+        let op = progress::Operation::new(&app, progress::IMPORT_EVENT, operation_id);
+        import_export_data(&conn, &op, export_data, &password, merge.unwrap_or(false))
+    }).await
+}
+
+/// Import a Joplin JEX export: each top-level notebook becomes a vault protected by `password`, sub-notebooks
+/// become collections and Joplin tags become item tags. With `merge`, notebooks and notes imported before are skipped.
+#[tauri::command]
+async fn import_joplin(
+    app: tauri::AppHandle,
+    path: String,
+    password: String,
+    merge: Option<bool>,
+    operation_id: Option<String>,
+) -> Result<Vec<i64>, BrainboxError> {
+    run_blocking(move || {
+        let export_data = note_import::parse_joplin(std::path::Path::new(&path))?;
+        let conn = db::open()?;
+        let op = progress::Operation::new(&app, progress::IMPORT_EVENT, operation_id);
+        import_export_data(&conn, &op, export_data, &password, merge.unwrap_or(false))
+    }).await
+}
+
+/// Import a Standard Notes decrypted backup into a "Standard Notes" vault, keeping tags and timestamps
+#[tauri::command]
+async fn import_standard_notes(
+    app: tauri::AppHandle,
+    path: String,
+    password: String,
+    merge: Option<bool>,
+    operation_id: Option<String>,
+) -> Result<Vec<i64>, BrainboxError> {
+    run_blocking(move || {
+        let export_data = note_import::parse_standard_notes(std::path::Path::new(&path))?;
+        let conn = db::open()?;
+        let op = progress::Operation::new(&app, progress::IMPORT_EVENT, operation_id);
+        import_export_data(&conn, &op, export_data, &password, merge.unwrap_or(false))
+    }).await
+}
+
+/// Import an Apple Notes or OneNote export (a folder of HTML, .docx, markdown or text notes, or a single file).
+/// Top-level folders become vaults protected by `password`, nested folders collections; images become attachments.
+#[tauri::command]
+async fn import_notes_folder(
+    app: tauri::AppHandle,
+    path: String,
+    password: String,
+    merge: Option<bool>,
+    operation_id: Option<String>,
+) -> Result<Vec<i64>, BrainboxError> {
+    run_blocking(move || {
+        let export_data = note_import::parse_notes_folder(std::path::Path::new(&path))?;
+        let conn = db::open()?;
+        let op = progress::Operation::new(&app, progress::IMPORT_EVENT, operation_id);
+        import_export_data(&conn, &op, export_data, &password, merge.unwrap_or(false))
+    }).await
+}
+
+/// Write parsed export data into the database; shared by the JSON import and the third-party importers.
+/// Runs in one transaction, so an error or cancellation imports nothing.
+fn import_export_data(
+    conn: &rusqlite::Connection,
+    op: &progress::Operation,
+    export_data: ExportData,
+    password: &str,
+    merge: bool,
+) -> Result<Vec<i64>, BrainboxError> {
+    Vault::create_table(conn)?;
+    VaultItem::create_table(conn)?;
+    Collection::create_table(conn)?;
+    tags::create_table(conn)?;
+
+    // Open the vaults being merged into before the transaction, so a wrong password stays counted after the rollback
+    let mut merge_keys: HashMap<i64, VaultKey> = HashMap::new();
+    if merge {
+        for vault in &export_data.vaults {
+            let Some(existing) = find_merge_target(conn, vault)? else { continue };
+            if merge_keys.contains_key(&existing.id) {
+                continue;
+            }
+            let arr = match resolve_key(conn, existing.id, None) {
+                Ok(arr) => arr,
+                Err(_) => {
+                    let arr = derive_key_from_password(password, &existing.id.to_string(), 100_000);
+                    verify_password_attempt(conn, existing.id, &arr).map_err(|e| match e {
+                        BrainboxError::InvalidKey => {
+                            BrainboxError::InvalidInput(format!("Unlock \"{}\" before merging into it", existing.name))
+                        }
+                        e => e,
+                    })?;
+                    arr
+                }
+            };
+            merge_keys.insert(existing.id, arr);
+        }
+    }
+
+    conn.execute("BEGIN IMMEDIATE", [])?;
+    // Name of the vault being imported, for the error when one fails
+    let mut current_vault = String::new();
+    let imported = (|| -> Result<Vec<i64>, BrainboxError> {
+        let mut imported_vault_ids = Vec::new();
+
+        for vault in export_data.vaults {
+            current_vault = vault.name.clone();
+            let target = if merge { find_merge_target(conn, &vault)? } else { None };
+            let mut collection_ids: HashMap<String, i64> = HashMap::new();
+            let mut known_uuids: std::collections::HashSet<String> = std::collections::HashSet::new();
+            let mut known_hashes: std::collections::HashSet<[u8; 32]> = std::collections::HashSet::new();
+
+            let (vault_id, key) = if let Some(existing) = target {
+                // A vault created earlier in this import has its key in the keystore
+                let arr = match merge_keys.get(&existing.id) {
+                    Some(arr) => arr.clone(),
+                    None => resolve_key(conn, existing.id, None)?,
+                };
+                let key = envelope::content_key(conn, existing.id, &arr)?;
+                // Trashed items count as present too, so a merge doesn't bring back what was deleted
+                for item in VaultItem::list_all_by_vault(conn, existing.id)? {
+                    if let Ok(content) = decrypt_content(&key, &item.content) {
+                        known_hashes.insert(import_hash(&item.title, &content));
+                    }
+                    known_uuids.extend(item.uuid);
+                }
+                let existing_collections = Collection::list_by_vault(conn, existing.id)?;
+                for collection in &vault.collections {
+                    let found = existing_collections
+                        .iter()
+                        .find(|c| c.uuid == collection.uuid)
+                        .or_else(|| existing_collections.iter().find(|c| c.name == collection.name));
+                    if let Some(found) = found {
+                        collection_ids.insert(collection.uuid.clone(), found.id);
+                    }
+                }
+                (existing.id, key)
+            } else {
+                // Create new vault with UUID; a merge import keeps the file's UUID so importing again finds it
+                let now = chrono::Utc::now().to_rfc3339();
+                let new_uuid = vault.uuid.clone()
+                    .filter(|u| merge && Vault::get_by_uuid(conn, u).ok().flatten().is_none())
+                    .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+                // Embedded covers go back into the covers folder; a cover that can't be decoded is kept as it was
+                let cover_image = covers::normalize(vault.cover_image.as_deref()).unwrap_or(vault.cover_image.clone());
+                conn.execute(
+                    "INSERT INTO vaults (name, encrypted_password, created_at, cover_image, uuid, updated_at, has_password, color, icon, description) VALUES (?1, ?2, ?3, ?4, ?5, ?6, 1, ?7, ?8, ?9)",
+                    rusqlite::params![vault.name, Vec::<u8>::new(), now, cover_image, new_uuid, now, vault.color, vault.icon, vault.description],
+                )?;
+
+                let vault_id = conn.last_insert_rowid();
+
+                // Derive key for this vault
+                let key = derive_key_from_password(password, &vault_id.to_string(), 100_000);
+
+                // Encrypt and store password verification
+                let encrypted_password = encrypt_password(&key, password)?;
+                conn.execute(
+                    "UPDATE vaults SET encrypted_password = ?1 WHERE id = ?2",
+                    rusqlite::params![encrypted_password, vault_id],
+                )?;
+                (vault_id, envelope::create_master_key(conn, vault_id, &key)?)
+            };
+            imported_vault_ids.push(vault_id);
+
+            // Recreate missing collections with fresh UUIDs, then link parents once every row exists
+            let mut created_collections = Vec::new();
+            for collection in &vault.collections {
+                if collection_ids.contains_key(&collection.uuid) {
+                    continue;
+                }
+                let created = Collection::insert(conn, vault_id, None, &collection.name)?;
+                collection_ids.insert(collection.uuid.clone(), created.id);
+                created_collections.push(collection);
+            }
+            for collection in created_collections {
+                let id = collection_ids[&collection.uuid];
+                let parent_id = collection.parent_uuid.as_ref().and_then(|p| collection_ids.get(p)).copied();
+                conn.execute(
+                    "UPDATE collections SET parent_id = ?1, sort_order = ?2 WHERE id = ?3",
+                    rusqlite::params![parent_id, collection.sort_order, id],
+                )?;
+            }
+
+            // Boards get fresh UUIDs too; a merge reuses boards and columns found by UUID or name
+            let existing_boards = boards::list(conn, vault_id)?;
+            let mut board_columns: HashMap<String, (i64, i64)> = HashMap::new();
+            for board in &vault.boards {
+                let now = chrono::Utc::now().to_rfc3339();
+                let found = existing_boards
+                    .iter()
+                    .find(|b| b.uuid == board.uuid)
+                    .or_else(|| existing_boards.iter().find(|b| b.name == board.name));
+                let board_id = match found {
+                    Some(found) => found.id,
+                    None => boards::insert_board(conn, vault_id, &uuid::Uuid::new_v4().to_string(), &board.name, board.sort_order, &now, &now)?,
+                };
+                let existing_columns = found.map(|b| b.columns.as_slice()).unwrap_or_default();
+                for (i, column) in board.columns.iter().enumerate() {
+                    let column_id = match existing_columns.iter().find(|c| c.uuid == column.uuid || c.name == column.name) {
+                        Some(found) => found.id,
+                        None => boards::insert_column(conn, board_id, &uuid::Uuid::new_v4().to_string(), &column.name, i as i64, &now, &now)?,
+                    };
+                    board_columns.insert(column.uuid.clone(), (board_id, column_id));
+                }
+            }
+
+            // Import items
+            let total = vault.items.len();
+            for (done, item) in vault.items.into_iter().enumerate() {
+                op.check()?;
+                op.progress(&vault.name, done, total);
+                if merge {
+                    let hash = import_hash(&item.title, &item.content);
+                    if item.uuid.as_ref().is_some_and(|u| known_uuids.contains(u)) || !known_hashes.insert(hash) {
+                        continue;
+                    }
+                }
+                let encrypted = envelope::seal(&key, item.content.as_bytes())?;
+                let content_hash = vault::content_hash(&key, &item.content);
+
+                // Merging keeps the item's UUID unless another vault already uses it
+                let item_uuid = item.uuid.clone()
+                    .filter(|u| merge && VaultItem::get_by_uuid(conn, u).ok().flatten().is_none())
+                    .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+                let collection_id = item.collection_uuid.as_ref().and_then(|u| collection_ids.get(u)).copied();
+                let (word_count, char_count) = vault::text_stats(&item.content);
+                let item_type = item.item_type
+                    .filter(|t| vault::is_valid_item_type(t))
+                    .unwrap_or_else(|| vault::infer_item_type(&item.content).to_string());
+                conn.execute(
+                    "INSERT INTO vault_items (vault_id, title, content, created_at, updated_at, image, summary, uuid, collection_id, item_type, due_at, completed_at, word_count, char_count, content_hash, read_at, color, icon) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
+                    rusqlite::params![
+                        vault_id,
+                        item.title,
+                        encrypted,
+                        item.created_at,
+                        item.updated_at,
+                        item.image,
+                        item.summary,
+                        item_uuid,
+                        collection_id,
+                        item_type,
+                        item.due_at,
+                        item.completed_at,
+                        word_count,
+                        char_count,
+                        content_hash,
+                        item.read_at,
+                        item.color,
+                        item.icon
+                    ],
+                )?;
+                let item_id = conn.last_insert_rowid();
+                if !item.tags.is_empty() {
+                    tags::add(conn, item_id, &item.tags)?;
+                }
+                let placements: Vec<(i64, i64, i64)> = item.board_placements
+                    .iter()
+                    .filter_map(|p| board_columns.get(&p.column_uuid).map(|&(board_id, column_id)| (board_id, column_id, p.position)))
+                    .collect();
+                if !placements.is_empty() {
+                    boards::set_placements(conn, item_id, &placements)?;
+                }
+                if !item.annotations.is_empty() {
+                    let copied = item.uuid.as_deref() != Some(item_uuid.as_str());
+                    annotations::set_for_item(conn, item_id, &key, &item.annotations, copied)?;
+                }
+                known_uuids.insert(item_uuid);
+            }
+        }
+
+        Ok(imported_vault_ids)
+    })();
+    match imported {
+        Ok(ids) => {
+            conn.execute("COMMIT", [])?;
+            Ok(ids)
+        }
+        Err(e) => {
+            let _ = conn.execute("ROLLBACK", []);
+            tracing::warn!("Import failed at vault '{}', rolled back: {}", current_vault, e);
+            Err(match e {
+                BrainboxError::Cancelled | BrainboxError::DatabaseLocked(_) | BrainboxError::InvalidInput(_) => e,
+                e => BrainboxError::Other(format!(
+                    "Importing vault \"{}\" failed, so nothing was imported: {}",
+                    current_vault, e
+                )),
+            })
+        }
+    }
+}
+
+/// Deep copy of a vault: collections and items get fresh UUIDs and are re-encrypted under the new password.
+/// An empty `new_password` makes the copy passwordless. Returns the new vault.
+#[tauri::command]
+async fn duplicate_vault(vault_id: i64, key: Option<Vec<u8>>, new_name: String, new_password: String) -> Result<Vault, BrainboxError> {
+    run_blocking(move || {
+        let new_name = new_name.trim().to_string();
+        if new_name.is_empty() {
+            return Err(BrainboxError::InvalidInput("Vault name is required".to_string()));
+        }
+
+        let conn = db::open()?;
+        Vault::create_table(&conn)?;
+        VaultItem::create_table(&conn)?;
+        Collection::create_table(&conn)?;
+        let arr = resolve_key(&conn, vault_id, key)?;
+        verify_vault_key(&conn, vault_id, &arr)?;
+        let arr = envelope::content_key(&conn, vault_id, &arr)?;
+        let source = Vault::get_by_id(&conn, vault_id)?
+            .filter(|v| v.deleted_at.is_none())
+            .ok_or_else(|| BrainboxError::NotFound(format!("Vault {} not found", vault_id)))?;
+        // Decrypt everything up front so a bad key fails before anything is written
+        let mut items = Vec::new();
+        for item in VaultItem::list_by_vault(&conn, vault_id)? {
+            let content = decrypt_content(&arr, &item.content)?;
+            items.push((item, content));
+        }
+        let collections = Collection::list_by_vault(&conn, vault_id)?;
+
+        let mut copied: Vec<(i64, String)> = Vec::with_capacity(items.len());
+        conn.execute("BEGIN IMMEDIATE", [])?;
+        let copy = (|| -> Result<i64, BrainboxError> {
+            let now = chrono::Utc::now().to_rfc3339();
+            let has_password = !new_password.is_empty();
+            conn.execute(
+                "INSERT INTO vaults (name, encrypted_password, created_at, cover_image, has_password, uuid, updated_at, color, icon, description) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                rusqlite::params![
+                    new_name,
+                    Vec::<u8>::new(),
+                    now,
+                    source.cover_image,
+                    has_password,
+                    uuid::Uuid::new_v4().to_string(),
+                    now,
+                    source.color,
+                    source.icon,
+                    source.description
+                ],
+            )?;
+            let new_id = conn.last_insert_rowid();
+            let new_key = derive_key_from_password(&new_password, &new_id.to_string(), 100_000);
+            if has_password {
+                conn.execute(
+                    "UPDATE vaults SET encrypted_password = ?1 WHERE id = ?2",
+                    rusqlite::params![encrypt_password(&new_key, &new_password)?, new_id],
+                )?;
+            }
+            let new_key = envelope::create_master_key(&conn, new_id, &new_key)?;
+
+            // Collections first (parents linked once every copy exists), then items into their copied collection
+            let mut collection_ids: HashMap<i64, i64> = HashMap::new();
+            for collection in &collections {
+                let created = Collection::insert(&conn, new_id, None, &collection.name)?;
+                collection_ids.insert(collection.id, created.id);
+            }
+            for collection in &collections {
+                let parent_id = collection.parent_id.and_then(|p| collection_ids.get(&p)).copied();
+                conn.execute(
+                    "UPDATE collections SET parent_id = ?1, sort_order = ?2 WHERE id = ?3",
+                    rusqlite::params![parent_id, collection.sort_order, collection_ids[&collection.id]],
+                )?;
+            }
+            for (item, content) in &items {
+                let item_type = item.item_type.clone().unwrap_or_else(|| vault::infer_item_type(content).to_string());
+                let created = VaultItem::insert(&conn, new_id, &item.title, content, &item_type, &new_key)?;
+                conn.execute(
+                    "UPDATE vault_items SET created_at = ?1, updated_at = ?2, image = ?3, summary = ?4, sort_order = ?5,
+                        collection_id = ?6, due_at = ?7, completed_at = ?8, read_at = ?9, color = ?11, icon = ?12 WHERE id = ?10",
+                    rusqlite::params![
+                        item.created_at,
+                        item.updated_at,
+                        item.image,
+                        item.summary,
+                        item.sort_order,
+                        item.collection_id.and_then(|c| collection_ids.get(&c)).copied(),
+                        item.due_at,
+                        item.completed_at,
+                        item.read_at,
+                        created.id,
+                        item.color,
+                        item.icon
+                    ],
+                )?;
+                copied.push((created.id, item_type));
+            }
+            Ok(new_id)
+        })();
+        let new_id = match copy {
+            Ok(id) => {
+                conn.execute("COMMIT", [])?;
+                id
+            }
+            Err(e) => {
+                let _ = conn.execute("ROLLBACK", []);
+                return Err(e);
+            }
+        };
+
+        // Best-effort: make the copy searchable right away
+        for ((item, content), (copy_id, item_type)) in items.into_iter().zip(copied) {
+            let _ = crate::search::index_document(crate::search::DocumentInput {
+                id: copy_id.to_string(),
+                title: item.title,
+                content,
+                item_type,
+                created_at: item.created_at,
+                updated_at: item.updated_at,
+                vault_id: Some(new_id),
+                ..Default::default()
+            });
+        }
+
+        let mut vault = Vault::get_by_id(&conn, new_id)?
+            .ok_or_else(|| BrainboxError::NotFound(format!("Vault {} not found", new_id)))?;
+        vault.cover_image = covers::display_url(vault.cover_image.take());
+        Ok(vault)
+    }).await
+}
+
+/// Write a vault to `dest` as an encrypted bundle another brainbox can import with `recipient_password`
+#[tauri::command]
+async fn share_vault(vault_id: i64, key: Option<Vec<u8>>, recipient_password: String, dest: String) -> Result<share::ShareResult, BrainboxError> {
+    run_blocking(move || {
+        let recipient_password = Zeroizing::new(recipient_password);
+        let conn = db::open()?;
+        Vault::create_table(&conn)?;
+        VaultItem::create_table(&conn)?;
+        Collection::create_table(&conn)?;
+        let arr = resolve_key(&conn, vault_id, key)?;
+        verify_vault_key(&conn, vault_id, &arr)?;
+        let arr = envelope::content_key(&conn, vault_id, &arr)?;
+        let vault = Vault::get_by_id(&conn, vault_id)?
+            .filter(|v| v.deleted_at.is_none())
+            .ok_or_else(|| BrainboxError::NotFound(format!("Vault {} not found", vault_id)))?;
+        share::write_bundle(&conn, &vault, &arr, &recipient_password, Path::new(&dest))
+    }).await
+}
+
+/// A self-contained HTML page with the item encrypted under `passphrase`, openable in any browser
+#[tauri::command]
+async fn share_item(item_id: i64, key: Option<Vec<u8>>, passphrase: String) -> Result<String, BrainboxError> {
+    run_blocking(move || {
+        let passphrase = Zeroizing::new(passphrase);
+        let conn = db::open()?;
+        VaultItem::create_table(&conn)?;
+        let it = VaultItem::get_by_id(&conn, item_id)?;
+        let arr = resolve_key(&conn, it.vault_id, key)?;
+        let arr = envelope::content_key(&conn, it.vault_id, &arr)?;
+        let content = Zeroizing::new(decrypt_content(&arr, &it.content)?);
+        share::item_html(&it, &content, &passphrase)
+    }).await
+}
+
+/// Import a bundle written by `share_vault`. A vault imported from an earlier share is updated in place (it must be
+/// unlocked, or use `password` as its own password); otherwise a new vault is created with `password`.
+#[tauri::command]
+async fn import_shared_vault(path: String, password: String) -> Result<share::SharedImportResult, BrainboxError> {
+    run_blocking(move || {
+        let password = Zeroizing::new(password);
+        let shared = share::read_bundle(Path::new(&path), &password)?;
+
+        let conn = db::open()?;
+        Vault::create_table(&conn)?;
+        VaultItem::create_table(&conn)?;
+        Collection::create_table(&conn)?;
+        let local = Vault::get_by_uuid(&conn, &shared.uuid)?;
+        let existing = match local.as_ref().filter(|v| v.deleted_at.is_none()) {
+            Some(vault) => {
+                let arr = match resolve_key(&conn, vault.id, None) {
+                    Ok(arr) => arr,
+                    Err(_) => {
+                        let arr = derive_key_from_password(&password, &vault.id.to_string(), 100_000);
+                        verify_password_attempt(&conn, vault.id, &arr).map_err(|e| match e {
+                            BrainboxError::InvalidKey => {
+                                BrainboxError::InvalidInput(format!("Unlock \"{}\" before importing an update to it", vault.name))
+                            }
+                            e => e,
+                        })?;
+                        arr
+                    }
+                };
+                Some((vault.id, envelope::content_key(&conn, vault.id, &arr)?))
+            }
+            None => None,
+        };
+
+        conn.execute("BEGIN IMMEDIATE", [])?;
+        let imported = (|| -> Result<(share::SharedImportResult, Option<VaultKey>), BrainboxError> {
+            if let Some((vault_id, arr)) = &existing {
+                return Ok((share::merge_into(&conn, *vault_id, arr, &shared, false)?, None));
+            }
+            // A trashed local copy keeps its UUID, so the new copy gets a fresh one
+            let vault_uuid = if local.is_some() { uuid::Uuid::new_v4().to_string() } else { shared.uuid.clone() };
+            let now = chrono::Utc::now().to_rfc3339();
+            conn.execute(
+                "INSERT INTO vaults (name, encrypted_password, created_at, cover_image, uuid, updated_at, has_password) VALUES (?1, ?2, ?3, NULL, ?4, ?5, 1)",
+                rusqlite::params![shared.name, Vec::<u8>::new(), shared.created_at, vault_uuid, now],
+            )?;
+            let vault_id = conn.last_insert_rowid();
+            let kek = derive_key_from_password(&password, &vault_id.to_string(), 100_000);
+            conn.execute(
+                "UPDATE vaults SET encrypted_password = ?1 WHERE id = ?2",
+                rusqlite::params![encrypt_password(&kek, &password)?, vault_id],
+            )?;
+            let arr = envelope::create_master_key(&conn, vault_id, &kek)?;
+            Ok((share::merge_into(&conn, vault_id, &arr, &shared, true)?, Some(kek)))
+        })();
+        match imported {
+            Ok((result, kek)) => {
+                conn.execute("COMMIT", [])?;
+                // The new vault opens with the share password, so keep it unlocked for this session
+                if let Some(kek) = kek {
+                    keystore::insert(result.vault_id, &kek);
+                }
+                Ok(result)
+            }
+            Err(e) => {
+                let _ = conn.execute("ROLLBACK", []);
+                Err(e)
+            }
+        }
+    }).await
+}
+
+/// Progress of re-sealing a legacy vault's items under a new master key, emitted as "vault-reencrypt-progress"
+#[derive(serde::Serialize, Clone)]
+#[allow(non_snake_case)]
+struct ReencryptProgress { vaultId: i64, done: usize, total: usize }
+
+/// Change a vault's password. Items are sealed under the vault's master key, so only that key is rewrapped under
+/// the new password; a vault from before envelope encryption is first moved onto a master key, which re-seals its
+/// items once. If new_has_password is false, the vault's password protection is removed.
+#[tauri::command]
+async fn change_vault_password(app: tauri::AppHandle, vault_id: i64, old_key: Option<Vec<u8>>, new_password: String, new_has_password: Option<bool>) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let new_password = Zeroizing::new(new_password);
+        let conn = db::open()?;
+        Vault::create_table(&conn)?;
+        VaultItem::create_table(&conn)?;
+        let old_arr = resolve_key(&conn, vault_id, old_key)?;
+
+        // Verify old key works
+        verify_password_attempt(&conn, vault_id, &old_arr)?;
+
+        // Determine if new vault should have password protection
+        let should_have_password = new_has_password.unwrap_or(!new_password.is_empty()) && !new_password.is_empty();
+
+        // Derive new key from new password (empty string if no password)
+        let new_key = derive_key_from_password(&new_password, &vault_id.to_string(), 100_000);
+
+        conn.execute("BEGIN IMMEDIATE", [])?;
+        let changed = (|| -> Result<usize, BrainboxError> {
+            // Vaults from before envelope encryption have their items re-sealed under a master key once,
+            // reporting progress for large vaults; from then on only the master key is rewrapped
+            let total = envelope::upgrade(&conn, vault_id, &old_arr, |done, total| {
+                let _ = app.emit("vault-reencrypt-progress", ReencryptProgress { vaultId: vault_id, done, total });
+            })?;
+            envelope::rewrap(&conn, vault_id, &old_arr, &new_key)?;
+
+            // Update vault's encrypted_password and has_password flag
+            let (new_encrypted_password, new_has_pw) = if should_have_password {
+                (encrypt_password(&new_key, &new_password)?, true)
+            } else {
+                (Vec::new(), false)
+            };
+            conn.execute(
+                "UPDATE vaults SET encrypted_password = ?1, has_password = ?2 WHERE id = ?3",
+                rusqlite::params![new_encrypted_password, new_has_pw, vault_id],
+            )?;
+            Ok(total)
+        })();
+        let total = match changed {
+            Ok(total) => {
+                conn.execute("COMMIT", [])?;
+                keystore::insert(vault_id, &new_key);
+                // A key wrapped for biometric unlock belongs to the old password
+                if let Err(e) = biometrics::disable(&conn, vault_id) {
+                    tracing::warn!("Failed to clear biometric unlock for vault {}: {}", vault_id, e);
+                }
+                // A vault that just got a password shouldn't keep its text in the index on disk
+                if let Err(e) = search::apply_locked_vault_indexing_to(&conn, vault_id) {
+                    tracing::warn!("Failed to reindex vault {} for its password: {}", vault_id, e);
+                }
+                total
+            }
+            Err(e) => {
+                let _ = conn.execute("ROLLBACK", []);
+                return Err(e);
+            }
+        };
+        let _ = app.emit("vault-reencrypt-progress", ReencryptProgress { vaultId: vault_id, done: total, total });
+
+        Ok(())
+    }).await
+}
+
+/// Move a vault created before envelope encryption onto a master key without changing its password.
+/// Returns the number of items re-sealed (0 if the vault already has a master key).
+#[tauri::command]
+async fn upgrade_vault_encryption(app: tauri::AppHandle, vault_id: i64, key: Option<Vec<u8>>) -> Result<usize, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        Vault::create_table(&conn)?;
+        VaultItem::create_table(&conn)?;
+        let arr = resolve_key(&conn, vault_id, key)?;
+        verify_vault_key(&conn, vault_id, &arr)?;
+
+        conn.execute("BEGIN IMMEDIATE", [])?;
+        let upgraded = envelope::upgrade(&conn, vault_id, &arr, |done, total| {
+            let _ = app.emit("vault-reencrypt-progress", ReencryptProgress { vaultId: vault_id, done, total });
+        });
+        match upgraded {
+            Ok(count) => {
+                conn.execute("COMMIT", [])?;
+                Ok(count)
+            }
+            Err(e) => {
+                let _ = conn.execute("ROLLBACK", []);
+                Err(e)
+            }
+        }
+    }).await
+}
+
+// --- Scheduled exports ---
+
+#[tauri::command]
+async fn get_scheduled_export() -> Result<scheduled_export::ScheduleStatus, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        scheduled_export::status(&conn)
+    }).await
+}
+
+#[tauri::command]
+async fn set_scheduled_export(config: scheduled_export::ScheduleConfig) -> Result<scheduled_export::ScheduleStatus, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        scheduled_export::set_config(&conn, &config)
+    }).await
+}
+
+/// Export now with the scheduled export settings; counts as the day's run
+#[tauri::command]
+async fn run_scheduled_export_now() -> Result<scheduled_export::ScheduledExportResult, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        scheduled_export::run_and_record(&conn)
+    }).await
+}
+
+/// Set the password encrypted exports are sealed with, or clear it with null
+#[tauri::command]
+async fn set_scheduled_export_password(password: Option<String>) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        scheduled_export::set_password(&conn, password.as_deref())
+    }).await
+}
+
+/// Decrypt an encrypted export into JSON for `import_vaults`
+#[tauri::command]
+async fn read_encrypted_export(path: String, password: String) -> Result<String, BrainboxError> {
+    run_blocking(move || scheduled_export::read_backup(Path::new(&path), &password)).await
+}
+
+// --- Weekly digest ---
+
+#[tauri::command]
+async fn get_digest_settings() -> Result<digest::DigestStatus, BrainboxError> {
+    run_blocking(|| {
+        let conn = db::open()?;
+        digest::status(&conn)
+    }).await
+}
+
+#[tauri::command]
+async fn set_digest_settings(config: digest::DigestConfig) -> Result<digest::DigestStatus, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        digest::set_config(&conn, &config)
+    }).await
+}
+
+/// Write a digest of the items created in a range (RFC 3339, default the past seven days) now. The vault and
+/// model default to the saved digest settings.
+#[tauri::command]
+async fn generate_digest(
+    since: Option<String>,
+    until: Option<String>,
+    vault_id: Option<i64>,
+    model: Option<String>,
+    base_url: Option<String>,
+) -> Result<digest::DigestResult, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        let parse = |at: &str| {
+            chrono::DateTime::parse_from_rfc3339(at)
+                .map(|at| at.with_timezone(&chrono::Utc))
+                .map_err(|_| BrainboxError::InvalidInput(format!("Invalid date: {}", at)))
+        };
+        let until = until.as_deref().map(parse).transpose()?.unwrap_or_else(chrono::Utc::now);
+        let since = since.as_deref().map(parse).transpose()?.unwrap_or(until - chrono::Duration::days(7));
+        let config = digest::config(&conn)?;
+        let vault_id = vault_id
+            .or(config.vault_id)
+            .ok_or_else(|| BrainboxError::InvalidInput("Choose a vault for the digest".to_string()))?;
+        let model = model.filter(|m| !m.trim().is_empty()).unwrap_or(config.model);
+        let base_url = sanitize_base_url(base_url.or(config.base_url));
+        digest::generate(&conn, since, until, vault_id, &model, &base_url)
+    }).await
+}
+
+// --- Sync Commands ---
+
+use std::collections::HashMap;
+
+/// Export all vaults to sync folder
+#[tauri::command]
+async fn sync_export_vaults(
+    app: tauri::AppHandle,
+    passwords: HashMap<i64, Vec<u8>>,
+    operation_id: Option<String>,
+) -> Result<sync::SyncExportResult, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        let op = progress::Operation::new(&app, progress::EXPORT_EVENT, operation_id);
+        sync::sync_export(&conn, passwords, &op).map_err(|e| sync_error(&op, e))
+    }).await
+}
+
+/// Get sync status information
+#[tauri::command]
+async fn get_sync_status() -> Result<sync::SyncStatus, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        sync::check_sync_status(&conn).map_err(BrainboxError::Sync)
+    }).await
+}
+
+/// Get list of vaults that need passwords for export
+#[tauri::command]
+async fn get_locked_vaults_for_sync() -> Result<Vec<(i64, String)>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        sync::get_locked_vaults(&conn).map_err(BrainboxError::Sync)
+    }).await
+}
+
+/// Get all sync settings
+#[tauri::command]
+async fn get_sync_settings() -> Result<HashMap<String, String>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        sync::get_sync_settings(&conn).map_err(BrainboxError::Sync)
+    }).await
+}
+
+/// Set a sync setting
+#[tauri::command]
+async fn set_sync_setting(key: String, value: String) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        sync::set_sync_setting(&conn, &key, &value).map_err(BrainboxError::Sync)
+    }).await
+}
+
+/// All app settings, with defaults for preferences that haven't been changed
+#[tauri::command]
+async fn get_all_settings() -> Result<HashMap<String, String>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        Ok(settings::all_with_defaults(&conn)?)
+    }).await
+}
+
+#[tauri::command]
+async fn get_setting(key: String) -> Result<Option<String>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        Ok(settings::get_or_default(&conn, &key)?)
+    }).await
+}
+
+#[tauri::command]
+async fn set_setting(key: String, value: String) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        settings::set(&conn, &key, &value)?;
+        Ok(())
+    }).await
+}
+
+/// Reset the given preferences, or all of them, to their defaults. Returns the keys reset.
+#[tauri::command]
+async fn reset_settings(keys: Option<Vec<String>>) -> Result<Vec<String>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        Ok(settings::reset(&conn, keys.as_deref())?)
+    }).await
+}
+
+/// Settings worth carrying to another machine, as JSON
+#[tauri::command]
+async fn export_settings() -> Result<String, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        Ok(serde_json::to_string_pretty(&settings::export(&conn)?)?)
+    }).await
+}
+
+/// Apply settings exported by `export_settings`; imported hotkeys are registered straight away
+#[tauri::command]
+async fn import_settings(app: tauri::AppHandle, json: String) -> Result<settings::SettingsImportResult, BrainboxError> {
+    let export: settings::SettingsExport = serde_json::from_str(&json)
+        .map_err(|e| BrainboxError::InvalidInput(format!("Invalid settings file: {}", e)))?;
+    let mut result = run_blocking(move || {
+        let conn = db::open()?;
+        settings::import(&conn, export)
+    }).await?;
+    let state = app.state::<HotkeyState>();
+    for (action, hotkey) in std::mem::take(&mut result.hotkeys) {
+        let bound = if hotkey.is_empty() {
+            hotkeys::unregister(&app, &state, &action)
+        } else {
+            hotkeys::register(&app, &state, &action, &hotkey)
+        };
+        match bound {
+            Ok(()) => result.settings_applied += 1,
+            Err(e) => result.warnings.push(format!("Hotkey for {}: {}", action, e)),
+        }
+    }
+    Ok(result)
+}
+
+#[tauri::command]
+fn get_data_dir() -> Result<data_dir::DataDirInfo, BrainboxError> {
+    data_dir::info()
+}
+
+/// Use an existing data folder (or the default one when `path` is None) and restart into it. Nothing is moved.
+#[tauri::command]
+fn set_data_dir(app: tauri::AppHandle, path: Option<String>) -> Result<String, BrainboxError> {
+    let path = path.map(|p| p.trim().to_string()).filter(|p| !p.is_empty());
+    let target = data_dir::switch_to(path.as_deref().map(std::path::Path::new))?;
+    app.request_restart();
+    Ok(target.to_string_lossy().to_string())
+}
+
+/// Move the database, search index, captures and caches to another folder, then restart to use them
+#[tauri::command]
+async fn migrate_data_dir(app: tauri::AppHandle, new_path: String) -> Result<data_dir::MigrationReport, BrainboxError> {
+    let report = run_blocking(move || data_dir::migrate(std::path::Path::new(new_path.trim()))).await?;
+    app.request_restart();
+    Ok(report)
+}
+
+/// Set sync folder path
+#[tauri::command]
+async fn set_sync_folder(path: String) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+    
+        // Validate the path exists
+        if !std::path::Path::new(&path).exists() {
+            return Err(BrainboxError::InvalidInput(format!("Path does not exist: {}", path)));
+        }
+    
+        sync::set_sync_folder(&conn, &path).map_err(BrainboxError::Sync)
+    }).await
+}
+
+/// Disk space used by the database, search index, captures, attachments and caches
+#[tauri::command]
+async fn get_storage_usage() -> Result<storage::StorageUsage, BrainboxError> {
+    run_blocking(storage::usage).await
+}
+
+/// Delete a rebuildable category ("thumbnails", "favicons" or "backups"), returning the bytes freed
+#[tauri::command]
+async fn clear_cache(kind: String) -> Result<u64, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        storage::clear_cache(&conn, &kind)
+    }).await
+}
+
+/// Capture files no item refers to, without deleting anything
+#[tauri::command]
+async fn find_orphan_captures() -> Result<capture_cleanup::OrphanCaptureReport, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        capture_cleanup::find(&conn, &|vault| resolve_key(&conn, vault.id, None).ok())
+    }).await
+}
+
+/// Delete orphaned capture files (or just report them with `dry_run`); needs every vault with items unlocked
+#[tauri::command]
+async fn clean_orphan_captures(dry_run: bool) -> Result<capture_cleanup::OrphanCaptureReport, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        capture_cleanup::clean(&conn, &|vault| resolve_key(&conn, vault.id, None).ok(), dry_run)
+    }).await
+}
+
+#[tauri::command]
+async fn get_capture_sync_settings() -> Result<sync_captures::CaptureSyncSettings, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        sync_captures::load_settings(&conn).map_err(BrainboxError::Sync)
+    }).await
+}
+
+/// Limits and inclusion rules for capture files copied by folder sync
+#[tauri::command]
+async fn set_capture_sync_settings(settings: sync_captures::CaptureSyncSettings) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        sync_captures::save_settings(&conn, &settings).map_err(BrainboxError::Sync)
+    }).await
+}
+
+/// Register this device with a self-hosted sync server and sync through it from now on
+#[tauri::command]
+async fn connect_sync_server(url: String, pairing_code: String) -> Result<sync_server::ServerStatus, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        sync_server::connect(&conn, &url, &pairing_code).map_err(BrainboxError::Sync)
+    }).await
 }
 
+/// Revoke this device on the sync server and go back to folder sync
 #[tauri::command]
-fn delete_vault(vault_id: i64) -> Result<(), String> {
-    let db_path = dirs::data_local_dir().ok_or("Failed to get app data dir")?.join("brainbox.sqlite");
-    let conn = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
-    Vault::delete(&conn, vault_id).map_err(|e| e.to_string())
+async fn disconnect_sync_server() -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        sync_server::disconnect(&conn).map_err(BrainboxError::Sync)
+    }).await
 }
 
 #[tauri::command]
-fn rename_vault(vault_id: i64, name: String) -> Result<(), String> {
-    let db_path = dirs::data_local_dir().ok_or("Failed to get app data dir")?.join("brainbox.sqlite");
-    let conn = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
-    Vault::rename(&conn, vault_id, &name).map_err(|e| e.to_string())
+async fn get_sync_server_status() -> Result<sync_server::ServerStatus, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        sync_server::status(&conn).map_err(BrainboxError::Sync)
+    }).await
 }
 
+/// Import vaults from sync folder
+/// passwords: Map of vault_uuid -> password
 #[tauri::command]
-fn update_vault_cover(vault_id: i64, cover_image: Option<String>) -> Result<(), String> {
-    let db_path = dirs::data_local_dir().ok_or("Failed to get app data dir")?.join("brainbox.sqlite");
-    let conn = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
-    Vault::update_cover_image(&conn, vault_id, cover_image.as_deref()).map_err(|e| e.to_string())
+async fn sync_import_vaults(
+    app: tauri::AppHandle,
+    passwords: HashMap<String, String>,
+    operation_id: Option<String>,
+) -> Result<sync::SyncImportResult, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        let op = progress::Operation::new(&app, progress::IMPORT_EVENT, operation_id);
+        sync::sync_import(&conn, passwords, &op).map_err(|e| sync_error(&op, e))
+    }).await
+}
+
+/// Sync reports failures as text; a cancellation gets its own error so the UI doesn't show it as a failure
+fn sync_error(op: &progress::Operation, message: String) -> BrainboxError {
+    if op.is_cancelled() {
+        BrainboxError::Cancelled
+    } else {
+        BrainboxError::Sync(message)
+    }
 }
 
+/// Stop a running import, export or sync started with `operation_id`; what it wrote so far is rolled back
 #[tauri::command]
-fn delete_vault_item(item_id: i64) -> Result<(), String> {
-    let db_path = dirs::data_local_dir().ok_or("Failed to get app data dir")?.join("brainbox.sqlite");
-    let conn = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
-    VaultItem::create_table(&conn).map_err(|e| e.to_string())?;
-    VaultItem::delete(&conn, item_id).map_err(|e| e.to_string())?;
-    Ok(())
+fn cancel_operation(operation_id: String) -> bool {
+    progress::cancel(&operation_id)
 }
 
+/// Get preview of sync file before importing
 #[tauri::command]
-fn update_vault_items_order(vault_id: i64, ordered_ids: Vec<i64>) -> Result<(), String> {
-    let db_path = dirs::data_local_dir().ok_or("Failed to get app data dir")?.join("brainbox.sqlite");
-    let conn = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
-    VaultItem::update_order(&conn, vault_id, &ordered_ids).map_err(|e| e.to_string())
+async fn get_sync_preview() -> Result<Option<sync::SyncPreview>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        sync::get_sync_preview(&conn).map_err(BrainboxError::Sync)
+    }).await
 }
 
+/// Purge soft-deleted items older than X days
 #[tauri::command]
-fn update_vault_item_title(item_id: i64, title: String) -> Result<(), String> {
-    let db_path = dirs::data_local_dir().ok_or("Failed to get app data dir")?.join("brainbox.sqlite");
-    let conn = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
-    VaultItem::update_title(&conn, item_id, &title).map_err(|e| e.to_string())
+async fn purge_deleted_items(app: tauri::AppHandle, days: Option<i32>) -> Result<sync::PurgeResult, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+    
+        // Use provided days or get from settings (default 30)
+        let purge_days = match days {
+            Some(d) => d,
+            None => sync::get_purge_days(&conn).map_err(BrainboxError::Sync)?,
+        };
+    
+        let result = sync::purge_deleted_items(&conn, purge_days).map_err(BrainboxError::Sync)?;
+        compact_after_purge(&app, &conn, &result);
+        Ok(result)
+    }).await
 }
 
+/// What a purge would remove now, for a confirmation before it runs
 #[tauri::command]
-fn move_vault_item(item_id: i64, target_vault_id: i64) -> Result<(), String> {
-    let db_path = dirs::data_local_dir().ok_or("Failed to get app data dir")?.join("brainbox.sqlite");
-    let conn = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
-    VaultItem::move_to_vault(&conn, item_id, target_vault_id).map_err(|e| e.to_string())
+async fn preview_purge(days: Option<i32>) -> Result<sync::PurgeResult, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        let purge_days = match days {
+            Some(d) => d,
+            None => sync::get_purge_days(&conn).map_err(BrainboxError::Sync)?,
+        };
+        sync::preview_purge(&conn, purge_days).map_err(BrainboxError::Sync)
+    }).await
 }
 
+/// Days deleted items are kept before a purge removes them
 #[tauri::command]
-fn update_vault_item_image(item_id: i64, image: Option<String>) -> Result<(), String> {
-    let db_path = dirs::data_local_dir().ok_or("Failed to get app data dir")?.join("brainbox.sqlite");
-    let conn = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
-    VaultItem::create_table(&conn).map_err(|e| e.to_string())?;
-    VaultItem::update_image(&conn, item_id, image.as_deref()).map_err(|e| e.to_string())
+async fn get_purge_days() -> Result<i32, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        sync::get_purge_days(&conn).map_err(BrainboxError::Sync)
+    }).await
 }
 
 #[tauri::command]
-fn update_vault_item_content(item_id: i64, content: String, key: Vec<u8>) -> Result<(), String> {
-    let db_path = dirs::data_local_dir().ok_or("Failed to get app data dir")?.join("brainbox.sqlite");
-    let conn = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
-    crate::vault::VaultItem::create_table(&conn).map_err(|e| e.to_string())?;
-    if key.len() != 32 { return Err("Key must be 32 bytes".into()); }
-    let mut arr = [0u8; 32];
-    arr.copy_from_slice(&key);
-    crate::vault::VaultItem::update_content(&conn, item_id, &content, &arr).map_err(|e| e.to_string())?;
-    // Best-effort: update search index
-    let it = crate::vault::VaultItem::get_by_id(&conn, item_id).map_err(|e| e.to_string())?;
-    let item_type = if content.starts_with("http://") || content.starts_with("https://") { "url" } else { "note" };
-    let _ = crate::search::index_document(
-        item_id.to_string(),
-        it.title.clone(),
-        content.clone(),
-        item_type.to_string(),
-        it.created_at.clone(),
-        it.updated_at.clone(),
-        None,
-        vec![]
-    );
-    Ok(())
+async fn set_purge_days(days: i32) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        sync::set_purge_days(&conn, days).map_err(BrainboxError::InvalidInput)
+    }).await
 }
 
+/// Run auto-purge if sync is enabled (called on app startup)
 #[tauri::command]
-fn update_vault_item_summary(item_id: i64, summary: String) -> Result<(), String> {
-    let db_path = dirs::data_local_dir().ok_or("Failed to get app data dir")?.join("brainbox.sqlite");
-    let conn = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
-    VaultItem::create_table(&conn).map_err(|e| e.to_string())?;
-    VaultItem::update_summary(&conn, item_id, &summary).map_err(|e| e.to_string())
+async fn auto_purge_if_enabled(app: tauri::AppHandle) -> Result<Option<sync::PurgeResult>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+    
+        if sync::should_auto_purge(&conn).map_err(BrainboxError::Sync)? {
+            let days = sync::get_purge_days(&conn).map_err(BrainboxError::Sync)?;
+            let result = sync::purge_deleted_items(&conn, days).map_err(BrainboxError::Sync)?;
+            compact_after_purge(&app, &conn, &result);
+            Ok(Some(result))
+        } else {
+            Ok(None)
+        }
+    }).await
 }
 
-/// Export vault data structure
-#[derive(serde::Serialize, serde::Deserialize)]
-struct ExportedVault {
-    name: String,
-    created_at: String,
-    cover_image: Option<String>,
-    items: Vec<ExportedItem>,
+#[derive(serde::Serialize, Clone)]
+struct CompactProgress { stage: String, done: bool }
+
+/// VACUUM the database and optimize the search index, reporting each step as a "compact-database-progress" event
+fn compact_with_progress(app: &tauri::AppHandle, conn: &rusqlite::Connection) -> Result<db::CompactResult, BrainboxError> {
+    let result = db::compact(conn, |stage| {
+        let _ = app.emit("compact-database-progress", CompactProgress { stage: stage.to_string(), done: false });
+    })?;
+    let _ = app.emit("compact-database-progress", CompactProgress { stage: "search_index".to_string(), done: false });
+    if let Err(e) = search::optimize_index() {
+        tracing::warn!("Failed to optimize search index: {}", e);
+    }
+    let _ = app.emit("compact-database-progress", CompactProgress { stage: "done".to_string(), done: true });
+    Ok(result)
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
-struct ExportedItem {
-    title: String,
-    content: String, // plaintext content
-    created_at: String,
-    updated_at: String,
-    image: Option<String>,
-    summary: Option<String>,
+/// Large purges leave the file as big as before, so they are followed by a compaction unless turned off
+fn compact_after_purge(app: &tauri::AppHandle, conn: &rusqlite::Connection, purge: &sync::PurgeResult) {
+    if purge.purged_items < db::AUTO_COMPACT_MIN_ITEMS && purge.purged_vaults == 0 {
+        return;
+    }
+    if !db::auto_compact_enabled(conn).unwrap_or(false) {
+        return;
+    }
+    if let Err(e) = compact_with_progress(app, conn) {
+        tracing::warn!("Compacting after purge failed: {}", e);
+    }
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
-struct ExportData {
-    version: String,
-    exported_at: String,
-    vaults: Vec<ExportedVault>,
+/// Shrink the database file after deletions; runs in the background and emits "compact-database-progress"
+#[tauri::command]
+async fn compact_database(app: tauri::AppHandle) -> Result<db::CompactResult, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        compact_with_progress(&app, &conn)
+    }).await
 }
 
-/// Export vaults to JSON (decrypts all items)
 #[tauri::command]
-fn export_vaults(vault_ids: Vec<i64>, keys: Vec<Vec<u8>>) -> Result<String, String> {
-    if vault_ids.len() != keys.len() {
-        return Err("Vault IDs and keys must have the same length".to_string());
-    }
+async fn get_auto_compact_enabled() -> Result<bool, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        db::auto_compact_enabled(&conn)
+    }).await
+}
 
-    let db_path = dirs::data_local_dir().ok_or("Failed to get app data dir")?.join("brainbox.sqlite");
-    let conn = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
-    Vault::create_table(&conn).map_err(|e| e.to_string())?;
-    VaultItem::create_table(&conn).map_err(|e| e.to_string())?;
+#[tauri::command]
+async fn set_auto_compact_enabled(enabled: bool) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        settings::set_bool(&conn, db::AUTO_COMPACT_KEY, enabled)?;
+        Ok(())
+    }).await
+}
 
-    let mut exported_vaults = Vec::new();
+/// Check if "sync on close" is enabled
+#[tauri::command]
+async fn is_sync_on_close_enabled() -> Result<bool, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        sync::is_sync_on_close_enabled(&conn).map_err(BrainboxError::Sync)
+    }).await
+}
 
-    for (vault_id, key) in vault_ids.iter().zip(keys.iter()) {
-        if key.len() != 32 {
-            return Err(format!("Key for vault {} must be 32 bytes", vault_id));
-        }
-        let mut arr = [0u8; 32];
-        arr.copy_from_slice(key);
+/// Set "sync on close" setting
+#[tauri::command]
+async fn set_sync_on_close(enabled: bool) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        sync::set_sync_on_close(&conn, enabled).map_err(BrainboxError::Sync)
+    }).await
+}
 
-        // Get vault info
-        let mut stmt = conn
-            .prepare("SELECT name, created_at, cover_image FROM vaults WHERE id = ?1")
-            .map_err(|e| e.to_string())?;
-        let (name, created_at, cover_image): (String, String, Option<String>) = stmt
-            .query_row([vault_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2).ok())))
-            .map_err(|e| e.to_string())?;
+/// Check if "check for sync on startup" is enabled
+#[tauri::command]
+async fn is_check_sync_on_startup_enabled() -> Result<bool, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        sync::is_check_sync_on_startup_enabled(&conn).map_err(BrainboxError::Sync)
+    }).await
+}
 
-        // Get and decrypt items
-        let items = VaultItem::list_by_vault(&conn, *vault_id).map_err(|e| e.to_string())?;
-        let mut exported_items = Vec::new();
+/// Set "check for sync on startup" setting
+#[tauri::command]
+async fn set_check_sync_on_startup(enabled: bool) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        sync::set_check_sync_on_startup(&conn, enabled).map_err(BrainboxError::Sync)
+    }).await
+}
 
-        for item in items {
-            let content = decrypt_content(&arr, &item.content)?;
-            exported_items.push(ExportedItem {
-                title: item.title,
-                content,
-                created_at: item.created_at,
-                updated_at: item.updated_at,
-                image: item.image,
-                summary: item.summary,
-            });
-        }
+/// Set device name for sync
+#[tauri::command]
+async fn set_device_name(name: String) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        sync::set_device_name(&conn, &name).map_err(BrainboxError::Sync)
+    }).await
+}
 
-        exported_vaults.push(ExportedVault {
-            name,
-            created_at,
-            cover_image,
-            items: exported_items,
-        });
-    }
+/// Get device hostname (for default device name)
+#[tauri::command]
+fn get_hostname() -> String {
+    whoami::fallible::hostname().unwrap_or_else(|_| "Unknown".to_string())
+}
 
-    let export_data = ExportData {
-        version: "1.0".to_string(),
-        exported_at: chrono::Utc::now().to_rfc3339(),
-        vaults: exported_vaults,
-    };
+// --- Quick switcher ---
+
+#[tauri::command]
+fn open_quick_switcher(app: tauri::AppHandle) -> Result<(), BrainboxError> {
+    switcher::show(&app)
+}
 
-    serde_json::to_string_pretty(&export_data).map_err(|e| e.to_string())
+#[tauri::command]
+fn close_quick_switcher(app: tauri::AppHandle) -> Result<(), BrainboxError> {
+    switcher::hide(&app)
 }
 
-/// Import vaults from JSON
+/// Instant search used by the switcher window (default 20 results)
 #[tauri::command]
-fn import_vaults(json_data: String, password: String) -> Result<Vec<i64>, String> {
-    let export_data: ExportData = serde_json::from_str(&json_data)
-        .map_err(|e| format!("Invalid export format: {}", e))?;
+async fn switcher_search(query: String, limit: Option<usize>) -> Result<Vec<switcher::SwitcherResult>, BrainboxError> {
+    run_blocking(move || switcher::search(&query, limit.unwrap_or(20).min(100))).await
+}
 
-    let db_path = dirs::data_local_dir().ok_or("Failed to get app data dir")?.join("brainbox.sqlite");
-    let conn = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
-    Vault::create_table(&conn).map_err(|e| e.to_string())?;
-    VaultItem::create_table(&conn).map_err(|e| e.to_string())?;
+/// Close the switcher and open the chosen item in the main window
+#[tauri::command]
+fn switcher_open_item(app: tauri::AppHandle, item_id: i64, vault_id: i64) -> Result<(), BrainboxError> {
+    switcher::open_item(&app, item_id, vault_id)
+}
 
-    let mut imported_vault_ids = Vec::new();
+// --- Note windows ---
 
-    for vault in export_data.vaults {
-        // Create new vault with UUID
-        let now = chrono::Utc::now().to_rfc3339();
-        let new_uuid = uuid::Uuid::new_v4().to_string();
-        conn.execute(
-            "INSERT INTO vaults (name, encrypted_password, created_at, cover_image, uuid, updated_at, has_password) VALUES (?1, ?2, ?3, ?4, ?5, ?6, 1)",
-            rusqlite::params![vault.name, Vec::<u8>::new(), now, vault.cover_image, new_uuid, now],
-        ).map_err(|e| e.to_string())?;
+/// Open an item in a window of its own, beside the main window
+#[tauri::command]
+async fn open_item_window(app: tauri::AppHandle, item_id: i64) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        item_windows::open(&app, &conn, item_id)
+    })
+    .await
+}
 
-        let vault_id = conn.last_insert_rowid();
-        imported_vault_ids.push(vault_id);
+/// Ids of the items open in windows of their own
+#[tauri::command]
+fn list_item_windows(app: tauri::AppHandle) -> Vec<i64> {
+    item_windows::list(&app)
+}
 
-        // Derive key for this vault
-        let key = derive_key_from_password(&password, &vault_id.to_string(), 100_000);
+/// Rebuild the tray menu (recent items) after changes the tray can't observe itself
+#[tauri::command]
+fn refresh_tray_menu(app: tauri::AppHandle) {
+    tray::refresh(&app);
+}
 
-        // Encrypt and store password verification
-        let encrypted_password = encrypt_password(&key, &password)?;
-        conn.execute(
-            "UPDATE vaults SET encrypted_password = ?1 WHERE id = ?2",
-            rusqlite::params![encrypted_password, vault_id],
-        ).map_err(|e| e.to_string())?;
-
-        // Import items
-        for item in vault.items {
-            // Encrypt content
-            use chacha20poly1305::{aead::Aead, KeyInit, XChaCha20Poly1305, Key, XNonce};
-            let cipher = XChaCha20Poly1305::new(Key::from_slice(&key));
-            let mut nonce_bytes = [0u8; 24];
-            OsRng.fill_bytes(&mut nonce_bytes);
-            let nonce = XNonce::from_slice(&nonce_bytes);
-            let ciphertext = cipher
-                .encrypt(nonce, item.content.as_bytes())
-                .map_err(|_| "Encryption failed".to_string())?;
-            let mut encrypted = nonce_bytes.to_vec();
-            encrypted.extend(ciphertext);
-
-            let item_uuid = uuid::Uuid::new_v4().to_string();
-            conn.execute(
-                "INSERT INTO vault_items (vault_id, title, content, created_at, updated_at, image, summary, uuid) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-                rusqlite::params![
-                    vault_id,
-                    item.title,
-                    encrypted,
-                    item.created_at,
-                    item.updated_at,
-                    item.image,
-                    item.summary,
-                    item_uuid
-                ],
-            ).map_err(|e| e.to_string())?;
-        }
-    }
+// --- Window lifecycle ---
 
-    Ok(imported_vault_ids)
+/// Hide the main window to the tray instead of quitting when it is closed
+#[tauri::command]
+async fn set_close_to_tray(enabled: bool) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        lifecycle::set_close_to_tray(&conn, enabled)
+    })
+    .await
 }
 
-/// Change vault password: re-encrypts all items with the new key
-/// If new_has_password is false, the vault will have password protection removed
 #[tauri::command]
-fn change_vault_password(vault_id: i64, old_key: Vec<u8>, new_password: String, new_has_password: Option<bool>) -> Result<(), String> {
-    if old_key.len() != 32 {
-        return Err("Old key must be 32 bytes".to_string());
-    }
-    let mut old_arr = [0u8; 32];
-    old_arr.copy_from_slice(&old_key);
-
-    let db_path = dirs::data_local_dir().ok_or("Failed to get app data dir")?.join("brainbox.sqlite");
-    let conn = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
-    Vault::create_table(&conn).map_err(|e| e.to_string())?;
-    VaultItem::create_table(&conn).map_err(|e| e.to_string())?;
-
-    // Verify old key works
-    verify_vault_key(&conn, vault_id, &old_arr)?;
-
-    // Determine if new vault should have password protection
-    let should_have_password = new_has_password.unwrap_or(!new_password.is_empty()) && !new_password.is_empty();
-
-    // Derive new key from new password (empty string if no password)
-    let new_key = derive_key_from_password(&new_password, &vault_id.to_string(), 100_000);
-
-    // Get all items for this vault
-    let items = VaultItem::list_by_vault(&conn, vault_id).map_err(|e| e.to_string())?;
-
-    // Start transaction
-    conn.execute("BEGIN IMMEDIATE", []).map_err(|e| e.to_string())?;
-
-    // Re-encrypt each item
-    for item in items {
-        // Decrypt with old key
-        let plaintext = decrypt_content(&old_arr, &item.content)?;
-
-        // Re-encrypt with new key
-        use chacha20poly1305::{aead::Aead, KeyInit, XChaCha20Poly1305, Key, XNonce};
-        let cipher = XChaCha20Poly1305::new(Key::from_slice(&new_key));
-        let mut nonce_bytes = [0u8; 24];
-        OsRng.fill_bytes(&mut nonce_bytes);
-        let nonce = XNonce::from_slice(&nonce_bytes);
-        let ciphertext = cipher
-            .encrypt(nonce, plaintext.as_bytes())
-            .map_err(|_| "Re-encryption failed".to_string())?;
-        let mut encrypted = nonce_bytes.to_vec();
-        encrypted.extend(ciphertext);
-
-        // Update item content
-        conn.execute(
-            "UPDATE vault_items SET content = ?1, updated_at = ?2 WHERE id = ?3",
-            rusqlite::params![encrypted, chrono::Utc::now().to_rfc3339(), item.id],
-        ).map_err(|e| {
-            let _ = conn.execute("ROLLBACK", []);
-            e.to_string()
-        })?;
-    }
+async fn is_close_to_tray_enabled() -> Result<bool, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        lifecycle::is_close_to_tray_enabled(&conn)
+    })
+    .await
+}
 
-    // Update vault's encrypted_password and has_password flag
-    let (new_encrypted_password, new_has_pw) = if should_have_password {
-        (encrypt_password(&new_key, &new_password)?, true)
-    } else {
-        (Vec::new(), false)
-    };
+// --- OS search integration ---
 
-    conn.execute(
-        "UPDATE vaults SET encrypted_password = ?1, has_password = ?2 WHERE id = ?3",
-        rusqlite::params![new_encrypted_password, new_has_pw, vault_id],
-    ).map_err(|e| {
-        let _ = conn.execute("ROLLBACK", []);
-        e.to_string()
-    })?;
+/// Whether items are listed in Spotlight or Windows Search, and how many
+#[tauri::command]
+async fn get_os_search_status() -> Result<os_search::OsSearchStatus, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        os_search::status(&conn)
+    })
+    .await
+}
 
-    // Commit transaction
-    conn.execute("COMMIT", []).map_err(|e| e.to_string())?;
+/// Add items of vaults without a password to the OS search index, or take them all out again
+#[tauri::command]
+async fn set_os_search_enabled(enabled: bool) -> Result<os_search::OsSearchStatus, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        os_search::set_enabled(&conn, enabled)
+    })
+    .await
+}
 
-    Ok(())
+// --- Autostart ---
+
+/// Launch brainbox when the user logs in
+#[tauri::command]
+fn set_autostart(enabled: bool) -> Result<(), BrainboxError> {
+    autostart::set_enabled(enabled)
 }
 
-// --- Sync Commands ---
+#[tauri::command]
+fn is_autostart_enabled() -> Result<bool, BrainboxError> {
+    autostart::is_enabled()
+}
 
-use std::collections::HashMap;
+/// Keep the window hidden in the tray when launched on login
+#[tauri::command]
+async fn set_start_minimized(enabled: bool) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        autostart::set_start_minimized(&conn, enabled)
+    })
+    .await
+}
 
-/// Export all vaults to sync folder
 #[tauri::command]
-fn sync_export_vaults(passwords: HashMap<i64, Vec<u8>>) -> Result<sync::SyncExportResult, String> {
-    let db_path = dirs::data_local_dir().ok_or("Failed to get app data dir")?.join("brainbox.sqlite");
-    let conn = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
-    sync::sync_export(&conn, passwords)
+async fn is_start_minimized_enabled() -> Result<bool, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        autostart::is_start_minimized_enabled(&conn)
+    })
+    .await
 }
 
-/// Get sync status information
+// --- Network ---
+
+/// User agent, request timeout and retry count used for all outgoing HTTP requests
 #[tauri::command]
-fn get_sync_status() -> Result<sync::SyncStatus, String> {
-    let db_path = dirs::data_local_dir().ok_or("Failed to get app data dir")?.join("brainbox.sqlite");
-    let conn = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
-    sync::check_sync_status(&conn)
+async fn get_http_settings() -> Result<http::HttpSettings, BrainboxError> {
+    run_blocking(|| {
+        let conn = db::open()?;
+        http::load_settings(&conn)
+    }).await
 }
 
-/// Get list of vaults that need passwords for export
 #[tauri::command]
-fn get_locked_vaults_for_sync() -> Result<Vec<(i64, String)>, String> {
-    let db_path = dirs::data_local_dir().ok_or("Failed to get app data dir")?.join("brainbox.sqlite");
-    let conn = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
-    sync::get_locked_vaults(&conn)
+async fn set_http_settings(settings: http::HttpSettings) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        http::save_settings(&conn, &settings)
+    }).await
 }
 
-/// Get all sync settings
+// --- Enrichment ---
+
+/// Queue URL items for background metadata enrichment; returns how many were queued
 #[tauri::command]
-fn get_sync_settings() -> Result<HashMap<String, String>, String> {
-    let db_path = dirs::data_local_dir().ok_or("Failed to get app data dir")?.join("brainbox.sqlite");
-    let conn = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
-    sync::get_sync_settings(&conn)
+async fn enqueue_url_enrichment(items: Vec<enrichment::EnrichmentRequest>) -> Result<usize, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        enrichment::enqueue(&conn, &items)
+    }).await
 }
 
-/// Set a sync setting
 #[tauri::command]
-fn set_sync_setting(key: String, value: String) -> Result<(), String> {
-    let db_path = dirs::data_local_dir().ok_or("Failed to get app data dir")?.join("brainbox.sqlite");
-    let conn = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
-    sync::set_sync_setting(&conn, &key, &value)
+async fn get_enrichment_status() -> Result<enrichment::EnrichmentStatus, BrainboxError> {
+    run_blocking(|| {
+        let conn = db::open()?;
+        enrichment::status(&conn)
+    }).await
 }
 
-/// Set sync folder path
+/// Stored enrichment result for an item, if it was ever queued
 #[tauri::command]
-fn set_sync_folder(path: String) -> Result<(), String> {
-    let db_path = dirs::data_local_dir().ok_or("Failed to get app data dir")?.join("brainbox.sqlite");
-    let conn = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
-    
-    // Validate the path exists
-    if !std::path::Path::new(&path).exists() {
-        return Err(format!("Path does not exist: {}", path));
-    }
-    
-    sync::set_sync_folder(&conn, &path)
+async fn get_url_metadata(item_id: i64) -> Result<Option<enrichment::EnrichedMetadata>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        enrichment::get(&conn, item_id)
+    }).await
 }
 
-/// Import vaults from sync folder
-/// passwords: Map of vault_uuid -> password
 #[tauri::command]
-fn sync_import_vaults(passwords: HashMap<String, String>) -> Result<sync::SyncImportResult, String> {
-    let db_path = dirs::data_local_dir().ok_or("Failed to get app data dir")?.join("brainbox.sqlite");
-    let conn = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
-    sync::sync_import(&conn, passwords)
+async fn get_enrichment_rate() -> Result<u32, BrainboxError> {
+    run_blocking(|| {
+        let conn = db::open()?;
+        enrichment::get_rate(&conn)
+    }).await
 }
 
-/// Get preview of sync file before importing
 #[tauri::command]
-fn get_sync_preview() -> Result<Option<sync::SyncPreview>, String> {
-    let db_path = dirs::data_local_dir().ok_or("Failed to get app data dir")?.join("brainbox.sqlite");
-    let conn = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
-    sync::get_sync_preview(&conn)
+async fn set_enrichment_rate(requests_per_minute: u32) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        enrichment::set_rate(&conn, requests_per_minute)
+    }).await
 }
 
-/// Purge soft-deleted items older than X days
+// --- Language ---
+
+/// The locale setting, the locale in use and the locales available
 #[tauri::command]
-fn purge_deleted_items(days: Option<i32>) -> Result<sync::PurgeResult, String> {
-    let db_path = dirs::data_local_dir().ok_or("Failed to get app data dir")?.join("brainbox.sqlite");
-    let conn = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
-    
-    // Use provided days or get from settings (default 30)
-    let purge_days = match days {
-        Some(d) => d,
-        None => sync::get_purge_days(&conn)?,
-    };
-    
-    sync::purge_deleted_items(&conn, purge_days)
+async fn get_locale() -> Result<i18n::LocaleInfo, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        i18n::info(&conn)
+    })
+    .await
 }
 
-/// Run auto-purge if sync is enabled (called on app startup)
+/// Show text from the backend in `locale`, or in the OS language with "system"
 #[tauri::command]
-fn auto_purge_if_enabled() -> Result<Option<sync::PurgeResult>, String> {
-    let db_path = dirs::data_local_dir().ok_or("Failed to get app data dir")?.join("brainbox.sqlite");
-    let conn = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
-    
-    if sync::should_auto_purge(&conn)? {
-        let days = sync::get_purge_days(&conn)?;
-        Ok(Some(sync::purge_deleted_items(&conn, days)?))
-    } else {
-        Ok(None)
-    }
+async fn set_locale(app: tauri::AppHandle, locale: String) -> Result<i18n::LocaleInfo, BrainboxError> {
+    let info = run_blocking(move || {
+        let conn = db::open()?;
+        i18n::set_locale(&conn, &locale)
+    })
+    .await?;
+    tray::refresh(&app);
+    Ok(info)
 }
 
-/// Check if "sync on close" is enabled
+// --- Logging ---
+
+/// Last `lines` lines of the log files (default 200), for attaching to bug reports
 #[tauri::command]
-fn is_sync_on_close_enabled() -> Result<bool, String> {
-    let db_path = dirs::data_local_dir().ok_or("Failed to get app data dir")?.join("brainbox.sqlite");
-    let conn = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
-    sync::is_sync_on_close_enabled(&conn)
+async fn get_recent_logs(lines: Option<usize>) -> Result<String, BrainboxError> {
+    let lines = lines.unwrap_or(200).clamp(1, 10_000);
+    run_blocking(move || Ok(logging::recent_lines(lines)?.join("\n"))).await
 }
 
-/// Set "sync on close" setting
+/// Zip recent logs, crash reports and system, database, settings and search index details into one file for a
+/// bug report; saved to `folder`, or the Downloads folder by default
 #[tauri::command]
-fn set_sync_on_close(enabled: bool) -> Result<(), String> {
-    let db_path = dirs::data_local_dir().ok_or("Failed to get app data dir")?.join("brainbox.sqlite");
-    let conn = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
-    sync::set_sync_on_close(&conn, enabled)
+async fn create_diagnostic_bundle(folder: Option<String>) -> Result<diagnostics::DiagnosticBundle, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        diagnostics::create_bundle(&conn, folder.as_deref().map(Path::new))
+    })
+    .await
 }
 
-/// Check if "check for sync on startup" is enabled
+/// Crash reports from panics and unexpected exits, newest first
 #[tauri::command]
-fn is_check_sync_on_startup_enabled() -> Result<bool, String> {
-    let db_path = dirs::data_local_dir().ok_or("Failed to get app data dir")?.join("brainbox.sqlite");
-    let conn = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
-    sync::is_check_sync_on_startup_enabled(&conn)
+fn list_crash_reports() -> Vec<diagnostics::CrashReport> {
+    diagnostics::list_crash_reports()
 }
 
-/// Set "check for sync on startup" setting
+/// Open the log folder in the system file manager
 #[tauri::command]
-fn set_check_sync_on_startup(enabled: bool) -> Result<(), String> {
-    let db_path = dirs::data_local_dir().ok_or("Failed to get app data dir")?.join("brainbox.sqlite");
-    let conn = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
-    sync::set_check_sync_on_startup(&conn, enabled)
+fn open_log_folder() -> Result<(), BrainboxError> {
+    let dir = logging::log_dir().ok_or("Failed to get app data dir")?;
+    std::fs::create_dir_all(&dir)?;
+    #[cfg(target_os = "windows")]
+    let opener = "explorer";
+    #[cfg(target_os = "macos")]
+    let opener = "open";
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let opener = "xdg-open";
+    Command::new(opener).arg(&dir).spawn()?;
+    Ok(())
 }
 
-/// Set device name for sync
 #[tauri::command]
-fn set_device_name(name: String) -> Result<(), String> {
-    let db_path = dirs::data_local_dir().ok_or("Failed to get app data dir")?.join("brainbox.sqlite");
-    let conn = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
-    sync::set_device_name(&conn, &name)
+async fn get_log_level() -> Result<String, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        Ok(settings::get(&conn, logging::LOG_LEVEL_KEY)?
+            .filter(|level| logging::is_valid_log_level(level))
+            .unwrap_or_else(|| logging::DEFAULT_LOG_LEVEL.to_string()))
+    }).await
 }
 
-/// Get device hostname (for default device name)
+/// Change the log level (error, warn, info, debug, trace); applied immediately and saved for next launch
 #[tauri::command]
-fn get_hostname() -> String {
-    whoami::fallible::hostname().unwrap_or_else(|_| "Unknown".to_string())
+async fn set_log_level(level: String) -> Result<(), BrainboxError> {
+    let level = level.trim().to_lowercase();
+    if !logging::is_valid_log_level(&level) {
+        return Err(BrainboxError::InvalidInput(format!("Unknown log level: {}", level)));
+    }
+    run_blocking(move || {
+        let conn = db::open()?;
+        settings::set(&conn, logging::LOG_LEVEL_KEY, &level)?;
+        logging::set_level(&level)?;
+        tracing::info!("Log level set to {}", level);
+        Ok(())
+    }).await
 }
 
 #[cfg(target_os = "windows")]
 #[tauri::command]
-fn register_brainbox_protocol() -> Result<(), String> {
+fn register_brainbox_protocol() -> Result<(), BrainboxError> {
     use winreg::enums::*;
     use winreg::RegKey;
     use std::env;
 
-    let exe_path = env::current_exe().map_err(|e| e.to_string())?;
+    let exe_path = env::current_exe()?;
     let exe_str = exe_path.to_str().ok_or("Invalid exe path")?;
 
     // Use HKEY_CURRENT_USER for per-user protocol registration (no admin rights needed)
     let hkcu = RegKey::predef(HKEY_CURRENT_USER);
-    let (classes, _) = hkcu.create_subkey("Software\\Classes").map_err(|e| e.to_string())?;
-    let (key, _) = classes.create_subkey("brainbox").map_err(|e| e.to_string())?;
-    key.set_value("", &"URL:brainbox Protocol").map_err(|e| e.to_string())?;
-    key.set_value("URL Protocol", &"").map_err(|e| e.to_string())?;
+    let (classes, _) = hkcu.create_subkey("Software\\Classes")?;
+    let (key, _) = classes.create_subkey("brainbox")?;
+    key.set_value("", &"URL:brainbox Protocol")?;
+    key.set_value("URL Protocol", &"")?;
 
     // Add "DefaultIcon" (optional but recommended)
-    let (icon_key, _) = key.create_subkey("DefaultIcon").map_err(|e| e.to_string())?;
-    icon_key.set_value("", &format!("\"{}\",0", exe_str)).map_err(|e| e.to_string())?;
+    let (icon_key, _) = key.create_subkey("DefaultIcon")?;
+    icon_key.set_value("", &format!("\"{}\",0", exe_str))?;
 
     // Create the command key and set the command to launch your app with the URL
-    let shell = key.create_subkey("shell").map_err(|e| e.to_string())?.0;
-    let open = shell.create_subkey("open").map_err(|e| e.to_string())?.0;
-    let command = open.create_subkey("command").map_err(|e| e.to_string())?.0;
+    let shell = key.create_subkey("shell")?.0;
+    let open = shell.create_subkey("open")?.0;
+    let command = open.create_subkey("command")?.0;
     
     // The key part: Use "--brainbox-protocol" flag to help with multiple instance handling
-    command.set_value("", &format!("\"{}\" --brainbox-protocol \"%1\"", exe_str)).map_err(|e| e.to_string())?;
+    command.set_value("", &format!("\"{}\" --brainbox-protocol \"%1\"", exe_str))?;
 
     Ok(())
 }
 
-// --- Protocol handler for brainbox://capture?url=...&title=...
-#[cfg(target_os = "windows")]
-fn handle_protocol_url<R: Runtime>(app: &tauri::AppHandle<R>, url: &str) {
-    // Only handle brainbox://capture?url=...&title=...
-    if let Some(rest) = url.strip_prefix("brainbox://capture?") {
-        let mut capture_url = String::new();
-        let mut title = String::new();
-        for param in rest.split('&') {
-            let mut parts = param.splitn(2, '=');
-            match (parts.next(), parts.next()) {
-                (Some("url"), Some(val)) => {
-                    capture_url = urlencoding::decode(val).unwrap_or_default().to_string();
-                }
-                (Some("title"), Some(val)) => {
-                    title = urlencoding::decode(val).unwrap_or_default().to_string();
-                }
-                _ => {}
-            }
-        }
-        // Emit event to frontend (or queue if window not ready yet)
-        if let Some(window) = app.get_webview_window("main") {
-            let _ = window.show();
-            let _ = window.set_focus();
+/// Register brainbox as the x-scheme-handler/brainbox handler through a user-level .desktop file
+#[cfg(target_os = "linux")]
+#[tauri::command]
+fn register_brainbox_protocol() -> Result<(), BrainboxError> {
+    let exe_path = autostart::launch_path()?;
+    let exe_str = exe_path.to_str().ok_or("Invalid exe path")?;
 
-            let _ = window.emit("capture-from-protocol", serde_json::json!({
-                "url": capture_url,
-                "title": title,
-            }));
+    let apps_dir = dirs::data_dir().ok_or("Failed to get data dir")?.join("applications");
+    std::fs::create_dir_all(&apps_dir)?;
+    let desktop_name = "brainbox-url-handler.desktop";
+    let desktop = format!(
+        "[Desktop Entry]\nType=Application\nName=brainbox\nExec=\"{}\" %u\nNoDisplay=true\nTerminal=false\nMimeType=x-scheme-handler/brainbox;\n",
+        exe_str
+    );
+    std::fs::write(apps_dir.join(desktop_name), desktop)?;
 
-            // no always-on-top (not available on this Webview type)
-        } else {
-            // queue it for when the window is available; delivery happens on page load
-            if let Some(state) = app.try_state::<ProtocolState>() {
-                let mut pending = state.pending.lock().unwrap();
-                *pending = Some((capture_url, title));
-            }
-        }
-    }
+    // Both tools are optional; without them the .desktop file is still found by most desktops
+    let _ = Command::new("update-desktop-database").arg(&apps_dir).status();
+    let _ = Command::new("xdg-mime")
+        .args(["default", desktop_name, "x-scheme-handler/brainbox"])
+        .status();
+    Ok(())
 }
 
 // Platform-specific builder functions
@@ -870,49 +3902,22 @@ fn handle_protocol_url<R: Runtime>(app: &tauri::AppHandle<R>, url: &str) {
 fn create_app_builder() -> tauri::Builder<tauri::Wry> {
     tauri::Builder::default()
         .on_page_load(|window, _| {
-            // Deliver any queued protocol capture when the main window finishes loading
-            if window.label() != "main" {
-                return;
-            }
-            let app = window.app_handle();
-            if let Some(state) = app.try_state::<ProtocolState>() {
-                let mut pending = state.pending.lock().unwrap();
-                if let Some((url, title)) = pending.take() {
-                    // ensure visibility
-                    let _ = window.show();
-                    let _ = window.set_focus();
-                    let _ = window.emit("capture-from-protocol", serde_json::json!({
-                        "url": url,
-                        "title": title,
-                    }));
-                    // no always-on-top toggle in this build
-                }
+            // Deliver any queued deep links when the main window finishes loading
+            if window.label() == "main" {
+                deeplink::flush_pending(window.app_handle());
             }
         })
+        .on_window_event(lifecycle::on_window_event)
         .plugin(
             tauri_plugin_shell::init()
         )
+        .plugin(tauri_plugin_notification::init())
         .plugin(
-            tauri_plugin_global_shortcut::Builder::new()
-                .with_shortcut("Alt+Shift+B")
-                .expect("Failed to register shortcut")
-                .build()
+            tauri_plugin_global_shortcut::Builder::new().build()
         )
-        .plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
-            // Forward protocol URLs to the existing instance
-            for arg in args.iter() {
-                if arg.starts_with("brainbox://capture?") {
-                    #[cfg(target_os = "windows")]
-                    {
-                        handle_protocol_url(&app, arg);
-                    }
-                    if let Some(window) = app.get_webview_window("main") {
-                        let _ = window.show();
-                        let _ = window.set_focus();
-                    }
-                    break;
-                }
-            }
+        .plugin(tauri_plugin_single_instance::init(|app, args, cwd| {
+            // Forward deep links and captures to the existing instance
+            deeplink::handle_args(app, args.get(1..).unwrap_or_default(), Some(std::path::Path::new(&cwd)));
         }))
 }
 
@@ -920,33 +3925,18 @@ fn create_app_builder() -> tauri::Builder<tauri::Wry> {
 fn create_app_builder() -> tauri::Builder<tauri::Wry> {
     tauri::Builder::default()
         .on_page_load(|window, _| {
-            // Deliver any queued protocol capture when the main window finishes loading
-            if window.label() != "main" {
-                return;
-            }
-            let app = window.app_handle();
-            if let Some(state) = app.try_state::<ProtocolState>() {
-                let mut pending = state.pending.lock().unwrap();
-                if let Some((url, title)) = pending.take() {
-                    // ensure visibility
-                    let _ = window.show();
-                    let _ = window.set_focus();
-                    let _ = window.emit("capture-from-protocol", serde_json::json!({
-                        "url": url,
-                        "title": title,
-                    }));
-                    // no always-on-top toggle in this build
-                }
+            // Deliver any queued deep links when the main window finishes loading
+            if window.label() == "main" {
+                deeplink::flush_pending(window.app_handle());
             }
         })
+        .on_window_event(lifecycle::on_window_event)
         .plugin(
             tauri_plugin_shell::init()
         )
+        .plugin(tauri_plugin_notification::init())
         .plugin(
-            tauri_plugin_global_shortcut::Builder::new()
-                .with_shortcut("Alt+Shift+B")
-                .expect("Failed to register shortcut")
-                .build()
+            tauri_plugin_global_shortcut::Builder::new().build()
         )
         // Note: Single instance plugin disabled on Windows due to null pointer bug
         // Users can run multiple instances, but protocol handling will still work
@@ -954,98 +3944,140 @@ fn create_app_builder() -> tauri::Builder<tauri::Wry> {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    if cli::requested() {
+        std::process::exit(cli::run());
+    }
+    // The single-instance plugin is disabled on Windows; forward captures to a running instance ourselves
+    #[cfg(target_os = "windows")]
+    if instance::forward_to_running() {
+        return;
+    }
+    logging::init();
+    diagnostics::install_panic_hook();
     create_app_builder()
+        .register_uri_scheme_protocol(image_cache::SCHEME, |_ctx, request| image_cache::handle_request(&request))
+        .manage(deeplink::DeepLinkState::default())
         .setup(|app| {
+            settings::init(app.handle());
+            diagnostics::start_session();
+            if let Ok(conn) = db::open() {
+                i18n::init(&conn);
+            }
+            if let Err(e) = db::migrate_to_wal() {
+                tracing::error!("Database WAL migration failed: {}", e);
+            }
+
             // Initialize the search service with a path for the index
-            let app_dir = dirs::data_local_dir().ok_or("Failed to get app data dir")?;
+            let app_dir = data_dir::root().ok_or("Failed to get app data dir")?;
             let index_dir = app_dir.join("search_index");
             
-            eprintln!("brainbox: Creating search index directory: {:?}", index_dir);
+            tracing::info!("Creating search index directory: {:?}", index_dir);
             
             // Create directory with better error handling
             if let Err(e) = std::fs::create_dir_all(&index_dir) {
-                eprintln!("brainbox: Failed to create index directory: {}", e);
-                eprintln!("brainbox: App will continue without search functionality");
+                tracing::error!("Failed to create index directory: {}", e);
+                tracing::warn!("App will continue without search functionality");
             } else {
-                eprintln!("brainbox: Initializing search service...");
+                tracing::info!("Initializing search service...");
                 
                 // Try to initialize search service with graceful fallback
                 match search::init_search_service(&index_dir) {
                     Ok(_) => {
-                        eprintln!("brainbox: Search service initialized successfully");
+                        tracing::info!("Search service initialized successfully");
                     },
                     Err(e) => {
-                        eprintln!("brainbox: Failed to initialize search service: {}", e);
+                        tracing::error!("Failed to initialize search service: {}", e);
                         
                         // Only attempt recovery on macOS where the issue is known to occur
                         #[cfg(target_os = "macos")]
                         {
-                            eprintln!("brainbox: Attempting automatic recovery (macOS-specific fix)...");
+                            tracing::warn!("Attempting automatic recovery (macOS-specific fix)...");
                             
                             // Try to recover by clearing the corrupted index
                             if let Err(recovery_err) = search::SearchService::recover_index(&index_dir) {
-                                eprintln!("brainbox: Index recovery failed: {}", recovery_err);
+                                tracing::error!("Index recovery failed: {}", recovery_err);
                             } else {
-                                eprintln!("brainbox: Index recovery completed, retrying initialization...");
+                                tracing::info!("Index recovery completed, retrying initialization...");
                                 
                                 // Retry initialization after recovery
                                 match search::init_search_service(&index_dir) {
                                     Ok(_) => {
-                                        eprintln!("brainbox: Search service initialized successfully after recovery");
+                                        tracing::info!("Search service initialized successfully after recovery");
                                         return Ok(());
                                     },
                                     Err(retry_err) => {
-                                        eprintln!("brainbox: Search service initialization failed even after recovery: {}", retry_err);
+                                        tracing::warn!("Search service initialization failed even after recovery: {}", retry_err);
                                     }
                                 }
                             }
                         }
                         
-                        eprintln!("brainbox: This may be due to:");
+                        tracing::warn!("This may be due to:");
                         #[cfg(target_os = "macos")]
-                        eprintln!("  - Memory mapping issues on macOS M4 systems");
+                        tracing::warn!("  - Memory mapping issues on macOS M4 systems");
                         #[cfg(not(target_os = "macos"))]
-                        eprintln!("  - Corrupted search index");
-                        eprintln!("  - Insufficient disk space or permissions");
-                        eprintln!("brainbox: App will continue without search functionality");
+                        tracing::warn!("  - Corrupted search index");
+                        tracing::warn!("  - Insufficient disk space or permissions");
+                        tracing::warn!("App will continue without search functionality");
                     }
                 }
             }
 
             // Initialize hotkey state
-            app.manage(HotkeyState {
-                current_hotkey: Mutex::new(Some("Alt+Shift+B".to_string())),
-            });
+            app.manage(HotkeyState::default());
 
-            // Initialize protocol state (pending capture queue)
-            app.manage(ProtocolState {
-                pending: Mutex::new(None),
-            });
-            // Register default hotkey
-            let app_handle = app.handle();
-            let hotkey_state = app.state::<HotkeyState>();
-            let _ = register_capture_hotkey(app_handle.clone(), hotkey_state, "Alt+Shift+B".to_string());
+            // Register saved (or default) action hotkeys
+            hotkeys::restore(app.handle(), &app.state::<HotkeyState>());
+
+            // Fire reminders and task due notifications in the background
+            reminders::spawn_scheduler(app.handle().clone());
+
+            // Nightly exports to the user's folder, when turned on
+            scheduled_export::spawn_scheduler(app.handle().clone());
+
+            // Weekly digest notes, when turned on
+            digest::spawn_scheduler(app.handle().clone());
+
+            // Periodic update checks (default daily)
+            spawn_update_scheduler(app.handle().clone());
+
+            // Fetch metadata for queued URL items at the configured rate
+            enrichment::spawn_worker(app.handle().clone());
 
             // spawn HTTP server to receive captures
             let app_handle_http = app.handle().clone();
             std::thread::spawn(move || {
-                let server = Server::http("127.0.0.1:51234").unwrap();
-                for request in server.incoming_requests() {
-                    if let Some(q) = request.url().strip_prefix("/capture?") {
-                        let mut url = String::new();
-                        let mut title = String::new();
-                        for param in q.split('&') {
-                            let mut parts = param.splitn(2, '=');
-                            match (parts.next(), parts.next()) {
-                                (Some("url"), Some(v)) => url = urlencoding::decode(v).unwrap_or_default().to_string(),
-                                (Some("title"), Some(v)) => title = urlencoding::decode(v).unwrap_or_default().to_string(),
-                                _ => {}
-                            }
+                let server = Server::http(instance::CAPTURE_SERVER_ADDR).unwrap();
+                instance::publish_token();
+                for mut request in server.incoming_requests() {
+                    if request.url() == instance::FORWARD_PATH {
+                        let status = instance::handle_forward(&app_handle_http, &mut request);
+                        let _ = request.respond(Response::empty(status));
+                        continue;
+                    }
+                    let (path, query) = request.url().split_once('?').unwrap_or((request.url(), ""));
+                    if path == "/capture" {
+                        if *request.method() == tiny_http::Method::Post && !instance::may_post_capture(&request) {
+                            let _ = request.respond(Response::empty(403));
+                            continue;
+                        }
+                        let mut query = query.to_string();
+                        // Long selections don't fit in a URL; the extension POSTs them form-encoded
+                        if *request.method() == tiny_http::Method::Post {
+                            use std::io::Read;
+                            let mut body = String::new();
+                            let _ = request.as_reader().take(inbox::MAX_CAPTURE_BODY_BYTES).read_to_string(&mut body);
+                            query = format!("{}&{}", query, body.trim());
                         }
-                        if let Some(window) = app_handle_http.get_webview_window("main") {
+                        let url = deeplink::query_param(&query, "url").unwrap_or_default();
+                        let title = deeplink::query_param(&query, "title").unwrap_or_default();
+                        let selection = inbox::Selection::from_query(&query);
+                        if inbox::receive_capture(&app_handle_http, &url, &title, selection.as_ref()) {
+                            // Saved straight to the Inbox; nothing to ask the user
+                        } else if let Some(window) = app_handle_http.get_webview_window("main") {
                             let _ = window.show();
                             let _ = window.set_focus();
-                            let _ = window.emit("capture-from-protocol", serde_json::json!({ "url": url, "title": title }));
+                            let _ = window.emit("capture-from-protocol", inbox::capture_event(&url, &title, selection.as_ref()));
                         }
                     }
                     // Respond with a tiny page that attempts to close itself if it was opened by script
@@ -1068,112 +4100,36 @@ pub fn run() {
                 }
             });
 
-            // Handle protocol URLs
-            #[cfg(target_os = "windows")]
+            // Register the brainbox:// protocol handler (macOS picks it up from Info.plist instead)
+            #[cfg(any(target_os = "windows", target_os = "linux"))]
             {
-                // Register custom protocol handler
                 if let Err(e) = register_brainbox_protocol() {
-                    eprintln!("Failed to register protocol: {}", e);
-                }
-                
-                // Handle command line arguments at startup for protocol URLs
-                // Check for our protocol URLs in the right format
-                let args: Vec<String> = std::env::args().collect();
-                
-                // Look for protocol URLs in arguments
-                let mut has_protocol_url = false;
-                let mut protocol_url = String::new();
-                
-                for i in 1..args.len() {
-                    if args[i] == "--brainbox-protocol" && i + 1 < args.len() && args[i + 1].starts_with("brainbox://capture?") {
-                        protocol_url = args[i + 1].clone();
-                        has_protocol_url = true;
-                        break;
-                    } else if args[i].starts_with("brainbox://capture?") {
-                        protocol_url = args[i].clone();
-                        has_protocol_url = true;
-                        break;
-                    }
-                }
-                
-                if has_protocol_url {
-                    // Process the URL immediately; if the window isn't ready yet, it will be queued
-                    handle_protocol_url(&app.handle(), &protocol_url);
+                    tracing::error!("Failed to register protocol: {}", e);
                 }
             }
 
+            // A deep link or capture passed on the command line at launch (Windows and Linux launch the app with
+            // the URL as an argument); it is queued until the main window has loaded
+            let args: Vec<String> = std::env::args().skip(1).collect();
+            deeplink::handle_args(app.handle(), &args, std::env::current_dir().ok().as_deref());
+
             // Initialize system tray in Rust so it works even when the webview is hidden/suspended
-            #[allow(unused_variables)]
-            {
-                use tauri::Manager;
-                // Create a simple menu with Show / Hide / Quit
-                #[allow(unused_imports)]
-                use tauri::menu::{Menu, MenuItem};
-                #[allow(unused_imports)]
-                use tauri::tray::{TrayIconBuilder, TrayIconEvent};
-                #[allow(unused_imports)]
-                use tauri::image::Image as TauriImage;
-
-                // Build menu and tray using current Tauri 2 API
-                let show = MenuItem::new(app, "show", true, None::<&str>)?;
-                show.set_text("Show Brainbox")?;
-                let hide = MenuItem::new(app, "hide", true, None::<&str>)?;
-                hide.set_text("Hide to Tray")?;
-                let quit = MenuItem::new(app, "quit", true, None::<&str>)?;
-                quit.set_text("Quit")?;
-
-                let menu = Menu::new(app)?;
-                menu.append(&show)?;
-                menu.append(&hide)?;
-                menu.append(&quit)?;
-
-                // Capture stable IDs for menu event comparison
-                let show_id = show.id().clone();
-                let hide_id = hide.id().clone();
-                let quit_id = quit.id().clone();
-                // Prefer the app's default window icon (honors platform formats: .ico on Windows, .icns on macOS)
-                let mut tray_builder = TrayIconBuilder::new();
-                if let Some(img) = app.default_window_icon() {
-                    tray_builder = tray_builder.icon(img.clone());
-                } else if let Ok(img) = TauriImage::from_path("icons/icon.png") {
-                    // Fallback to our bundled PNG if default icon isn't available
-                    tray_builder = tray_builder.icon(img);
+            tray::init(app.handle())?;
+            quick_actions::install(app.handle());
+            os_search::init(app.handle());
+            item_windows::init(app.handle());
+            item_windows::restore(app.handle());
+
+            // Launched from the login entry with "start minimized" on: stay in the tray until the user opens the window
+            if autostart::is_autostart_launch() {
+                let start_minimized = db::open()
+                    .and_then(|conn| autostart::is_start_minimized_enabled(&conn))
+                    .unwrap_or(false);
+                if start_minimized {
+                    if let Some(window) = app.get_webview_window("main") {
+                        let _ = window.hide();
+                    }
                 }
-
-                let tray = tray_builder
-                    .menu(&menu)
-                    .on_menu_event(move |app, event| {
-                        let id = event.id();
-                        eprintln!("[tray] menu event: {:?}", id);
-                        if id == &show_id {
-                            if let Some(w) = app.get_webview_window("main") {
-                                let _ = w.show();
-                                let _ = w.set_focus();
-                            }
-                        } else if id == &hide_id {
-                            if let Some(w) = app.get_webview_window("main") {
-                                let _ = w.hide();
-                            }
-                        } else if id == &quit_id {
-                            app.exit(0);
-                        }
-                    })
-                    .on_tray_icon_event(|tray, event| {
-                        // Show on double click
-                        if let TrayIconEvent::DoubleClick { .. } = event {
-                            eprintln!("[tray] double click");
-                            let app = tray.app_handle();
-                            if let Some(w) = app.get_webview_window("main") {
-                                let _ = w.show();
-                                let _ = w.set_focus();
-                            }
-                        }
-                    })
-                    .build(app)
-                    .expect("Failed to build tray icon");
-
-                // store tray handle so callbacks stay alive
-                app.manage(TrayState { tray: Mutex::new(Some(tray)) });
             }
 
             Ok(())
@@ -1185,36 +4141,208 @@ pub fn run() {
             delete_document,
             register_capture_hotkey,
             unregister_capture_hotkey,
+            register_action_hotkey,
+            unregister_action_hotkey,
+            list_action_hotkeys,
             create_vault,
             list_vaults,
             delete_vault,
             rename_vault,
             update_vault_cover,
+            update_vaults_order,
+            duplicate_vault,
+            share_vault,
+            import_shared_vault,
+            share_item,
+            set_vault_pinned,
+            set_vault_cover_file,
+            update_vault_color,
+            update_vault_icon,
+            get_vault_setting,
+            get_vault_settings,
+            set_vault_setting,
+            update_vault_description,
             add_vault_item,
             list_vault_items,
             verify_vault_password,
+            unlock_vault,
+            lock_vault,
+            lock_all_vaults,
+            list_unlocked_vaults,
+            emergency_lock,
+            get_emergency_lock_clears_index,
+            set_emergency_lock_clears_index,
+            wipe_all_data,
+            get_vault_lockout_status,
+            get_biometric_status,
+            enable_biometric_unlock,
+            unlock_with_biometrics,
+            disable_biometric_unlock,
             delete_vault_item,
+            get_item_tags,
+            set_item_tags,
+            list_inbox_items,
+            get_inbox_count,
+            add_inbox_item,
+            triage_item,
+            get_inbox_enabled,
+            set_inbox_enabled,
+            list_capture_rules,
+            create_capture_rule,
+            update_capture_rule,
+            delete_capture_rule,
+            find_duplicate_items,
+            merge_duplicates,
             update_vault_items_order,
             update_vault_item_title,
             update_vault_item_content,
             move_vault_item,
             update_vault_item_image,
+            cache_item_image,
+            get_image_metadata,
+            analyze_item_image,
+            analyze_vault_images,
+            get_image_analysis,
+            get_thumbnail,
+            import_dropped_files,
             update_vault_item_summary,
+            update_vault_item_type,
+            update_item_color,
+            update_item_icon,
+            set_item_no_sync,
+            // Tasks
+            set_item_due_date,
+            set_item_completed,
+            mark_item_read,
+            mark_item_unread,
+            list_unread,
+            get_unread_counts,
+            archive_to_wayback,
+            get_wayback_snapshot,
+            list_upcoming_tasks,
+            get_item_tasks,
+            toggle_item_checkbox,
+            // Reminders
+            set_reminder,
+            list_reminders,
+            dismiss_reminder,
+            // Templates
+            create_template,
+            list_templates,
+            delete_template,
+            create_item_from_template,
+            import_ics,
+            extract_entities,
+            extract_item_entities,
+            list_entities,
+            list_items_for_entity,
+            // Search languages
+            get_search_languages,
+            set_search_languages,
+            search_index_needs_rebuild,
+            mark_search_index_rebuilt,
+            // Access history
+            record_item_access,
+            list_recent_items,
+            clear_access_history,
+            // Search index maintenance
+            get_search_index_stats,
+            optimize_search_index,
+            get_locked_vault_indexing,
+            set_locked_vault_indexing,
+            // Search suggestions
+            suggest_search_terms,
+            get_recent_searches,
+            add_recent_search,
+            clear_recent_searches,
+            // Saved searches
+            create_saved_search,
+            list_saved_searches,
+            update_saved_search,
+            delete_saved_search,
+            run_saved_search,
+            search_with_filters,
             change_vault_password,
+            upgrade_vault_encryption,
             export_vaults,
             import_vaults,
+            get_scheduled_export,
+            set_scheduled_export,
+            run_scheduled_export_now,
+            set_scheduled_export_password,
+            get_digest_settings,
+            set_digest_settings,
+            generate_digest,
+            read_encrypted_export,
+            import_joplin,
+            import_standard_notes,
+            import_notes_folder,
             get_vault_item,
+            find_in_item,
+            parse_item_outline,
+            parse_item_markdown,
+            get_vault_code_languages,
+            list_item_annotations,
+            add_annotation,
+            update_annotation,
+            delete_annotation,
+            get_item_content,
+            count_vault_items,
+            get_writing_stats,
+            // Collections
+            create_collection,
+            list_collections,
+            rename_collection,
+            move_collection,
+            delete_collection,
+            update_collections_order,
+            move_item_to_collection,
+            // Boards
+            list_boards,
+            create_board,
+            rename_board,
+            delete_board,
+            add_board_column,
+            rename_board_column,
+            delete_board_column,
+            reorder_board_columns,
+            move_item_to_column,
             // Sync commands
             sync_export_vaults,
             sync_import_vaults,
+            cancel_operation,
+            get_storage_usage,
+            clear_cache,
+            find_orphan_captures,
+            clean_orphan_captures,
+            get_capture_sync_settings,
+            set_capture_sync_settings,
+            connect_sync_server,
+            disconnect_sync_server,
+            get_sync_server_status,
             get_sync_status,
             get_sync_preview,
             get_locked_vaults_for_sync,
             get_sync_settings,
             set_sync_setting,
+            get_all_settings,
+            get_setting,
+            set_setting,
+            reset_settings,
+            export_settings,
+            import_settings,
+            get_data_dir,
+            set_data_dir,
+            migrate_data_dir,
             set_sync_folder,
             purge_deleted_items,
+            preview_purge,
+            get_purge_days,
+            set_purge_days,
             auto_purge_if_enabled,
+            compact_database,
+            get_auto_compact_enabled,
+            set_auto_compact_enabled,
             is_sync_on_close_enabled,
             set_sync_on_close,
             is_check_sync_on_startup_enabled,
@@ -1222,28 +4350,106 @@ pub fn run() {
             set_device_name,
             get_hostname,
             fetch_url_metadata,
+            get_favicon,
+            // Quick switcher
+            open_quick_switcher,
+            close_quick_switcher,
+            switcher_search,
+            switcher_open_item,
+            // Network
+            get_http_settings,
+            set_http_settings,
+            // Enrichment
+            enqueue_url_enrichment,
+            get_enrichment_status,
+            get_url_metadata,
+            get_enrichment_rate,
+            set_enrichment_rate,
+            // Logging
+            get_locale,
+            set_locale,
+            get_recent_logs,
+            create_diagnostic_bundle,
+            list_crash_reports,
+            open_log_folder,
+            get_log_level,
+            set_log_level,
             // Scraping helpers
             fetch_url_text,
+            fetch_site_content,
             fetch_youtube_transcript,
+            fetch_media_transcript,
+            get_whisper_settings,
+            set_whisper_settings,
             // Ollama integration
             ollama_list_models,
+            ollama_status,
+            ollama_show_model,
             ollama_generate,
             ollama_generate_stream,
+            count_tokens,
+            get_ollama_options,
+            set_ollama_options,
+            generate_into_item,
+            create_prompt,
+            update_prompt,
+            list_prompts,
+            delete_prompt,
+            run_prompt,
             quit_app,
+            refresh_tray_menu,
+            open_item_window,
+            list_item_windows,
+            // Window lifecycle
+            set_close_to_tray,
+            get_os_search_status,
+            set_os_search_enabled,
+            is_close_to_tray_enabled,
+            // Autostart
+            set_autostart,
+            is_autostart_enabled,
+            set_start_minimized,
+            is_start_minimized_enabled,
             // Auto-updater commands (custom GitHub releases implementation)
             get_current_version,
+            get_update_channel,
+            set_update_channel,
+            get_update_check_settings,
+            set_update_check_settings,
             check_for_updates,
             download_update,
             apply_update,
+            stage_update,
+            get_staged_update,
+            rollback_update,
+            get_rollback_version,
             install_update,
-            #[cfg(target_os = "windows")]
+            #[cfg(any(target_os = "windows", target_os = "linux"))]
             register_brainbox_protocol,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            // macOS delivers brainbox:// links and files opened with the app as an Opened event rather than
+            // command-line arguments
+            #[cfg(target_os = "macos")]
+            if let tauri::RunEvent::Opened { urls } = &event {
+                let args: Vec<String> = urls
+                    .iter()
+                    .map(|url| url.to_file_path().map(|path| path.display().to_string()).unwrap_or_else(|_| url.to_string()))
+                    .collect();
+                deeplink::handle_args(app, &args, None);
+            }
+            // Install an update the user chose to apply on quit
+            if let tauri::RunEvent::Exit = &event {
+                diagnostics::end_session();
+                apply_staged_update();
+            }
+            let _ = (app, event);
+        });
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, Clone)]
 struct UrlMetadata {
     final_url: String,
     title: Option<String>,
@@ -1253,27 +4459,36 @@ struct UrlMetadata {
     favicon: Option<String>,
 }
 
+/// Local URL of a site's cached favicon (accepts a host or a full URL); None if the site has none
 #[tauri::command]
-fn fetch_url_metadata(url: String) -> Result<UrlMetadata, String> {
-    use regex::Regex;
-    use reqwest::blocking::Client;
-    use reqwest::header::{USER_AGENT, ACCEPT, ACCEPT_LANGUAGE};
+async fn get_favicon(host: String) -> Result<Option<String>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        favicons::get_or_fetch(&conn, &host)
+    }).await
+}
 
-    let client = Client::builder()
-        .redirect(reqwest::redirect::Policy::limited(10))
-        .build()
-        .map_err(|e| e.to_string())?;
+#[tauri::command]
+async fn fetch_url_metadata(url: String) -> Result<UrlMetadata, BrainboxError> {
+    run_blocking(move || fetch_metadata(&url)).await
+}
 
-    let resp = client
-        .get(&url)
-        .header(USER_AGENT, "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124 Safari/537.36")
-        .header(ACCEPT, "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,*/*;q=0.8")
-        .header(ACCEPT_LANGUAGE, "en-US,en;q=0.9")
-        .send()
-        .map_err(|e| e.to_string())?;
+/// Fetch a page and pull its OpenGraph/Twitter/title metadata; shared with the enrichment queue
+fn fetch_metadata(url: &str) -> Result<UrlMetadata, BrainboxError> {
+    use regex::Regex;
+    use reqwest::header::{USER_AGENT, ACCEPT, ACCEPT_LANGUAGE};
+
+    // Some sites only serve OpenGraph tags to browsers, so this request keeps a browser user agent
+    let resp = http::send(
+        http::client()
+            .get(url)
+            .header(USER_AGENT, "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124 Safari/537.36")
+            .header(ACCEPT, "text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,*/*;q=0.8")
+            .header(ACCEPT_LANGUAGE, "en-US,en;q=0.9"),
+    )?;
 
     let final_url = resp.url().to_string();
-    let text = resp.text().map_err(|e| e.to_string())?;
+    let text = resp.text()?;
 
     // Simple regex-based extraction to avoid heavy dependencies
     let re_meta = |name: &str| -> Regex {
@@ -1289,10 +4504,13 @@ fn fetch_url_metadata(url: String) -> Result<UrlMetadata, String> {
     let site_name = get(&re_meta("og:site_name"));
     let title_fallback = re_title.captures(&text).and_then(|c| c.get(1).map(|m| m.as_str().to_string()));
 
-    // Build favicon via Google S2 as a robust default
+    // The page's declared icon, else the conventional /favicon.ico. Use get_favicon for a cached local copy.
     let favicon = (|| {
-        let host = reqwest::Url::parse(&final_url).ok()?.host_str()?.to_string();
-        Some(format!("https://www.google.com/s2/favicons?sz=64&domain={}", host))
+        let base = reqwest::Url::parse(&final_url).ok()?;
+        favicons::icon_links(&text, &base)
+            .into_iter()
+            .next()
+            .or_else(|| base.join("/favicon.ico").ok().map(|u| u.to_string()))
     })();
 
     // Prefer og:image, fall back to twitter:image, and resolve relative URLs
@@ -1316,75 +4534,66 @@ fn fetch_url_metadata(url: String) -> Result<UrlMetadata, String> {
 
 // Extract readable text from a web page (best-effort)
 #[tauri::command]
-fn fetch_url_text(url: String) -> Result<String, String> {
-    use reqwest::blocking::Client;
-    let client = Client::builder()
-        .redirect(reqwest::redirect::Policy::limited(10))
-        .build()
-        .map_err(|e| e.to_string())?;
-    let resp = client.get(&url).send().map_err(|e| e.to_string())?;
-    let html = resp.text().map_err(|e| e.to_string())?;
-    let document = scraper::Html::parse_document(&html);
-    let selector = scraper::Selector::parse("body").unwrap();
-    let mut out = String::new();
-    for el in document.select(&selector) {
-        for txt in el.text() {
-            let t = txt.trim();
-            if !t.is_empty() {
-                out.push_str(t);
-                out.push('\n');
+async fn fetch_url_text(url: String) -> Result<String, BrainboxError> {
+    run_blocking(move || {
+        let resp = http::send(http::client().get(&url))?;
+        let html = resp.text()?;
+        let document = scraper::Html::parse_document(&html);
+        let selector = scraper::Selector::parse("body").unwrap();
+        let mut out = String::new();
+        for el in document.select(&selector) {
+            for txt in el.text() {
+                let t = txt.trim();
+                if !t.is_empty() {
+                    out.push_str(t);
+                    out.push('\n');
+                }
             }
         }
-    }
-    Ok(out)
+        Ok(out)
+    }).await
+}
+
+/// Post text and top comments for Twitter/X, Reddit and Hacker News links; None for other sites
+#[tauri::command]
+async fn fetch_site_content(url: String) -> Result<Option<site_content::SiteContent>, BrainboxError> {
+    run_blocking(move || site_content::extract(&url)).await
 }
 
 // Fetch YouTube transcript if available by scraping captionTracks
 #[tauri::command]
-fn fetch_youtube_transcript(url: String) -> Result<Option<String>, String> {
-    use regex::Regex;
-    use reqwest::blocking::Client;
-    let u = match reqwest::Url::parse(&url) { Ok(u) => u, Err(_) => return Ok(None) };
-    let host = u.host_str().unwrap_or("");
-    if !host.contains("youtube.com") && !host.contains("youtu.be") { return Ok(None); }
-
-    let client = Client::builder()
-        .redirect(reqwest::redirect::Policy::limited(10))
-        .build()
-        .map_err(|e| e.to_string())?;
-    let resp = client.get(u.clone()).send().map_err(|e| e.to_string())?;
-    let page = resp.text().map_err(|e| e.to_string())?;
-    // Find captionTracks JSON array
-    let re = Regex::new(r#""captionTracks"\s*:\s*(\[[^\]]+\])"#).map_err(|e| e.to_string())?;
-    let caps = match re.captures(&page) { Some(c) => c, None => return Ok(None) };
-    let tracks_json = caps.get(1).map(|m| m.as_str()).unwrap_or("");
-    let val: serde_json::Value = match serde_json::from_str(tracks_json) { Ok(v) => v, Err(_) => return Ok(None) };
-    let base = match val.get(0).and_then(|t| t.get("baseUrl")).and_then(|v| v.as_str()) { Some(s) => s, None => return Ok(None) };
-    let base_url = base.replace("\\u0026", "&");
-    let tr_resp = client.get(&base_url).send().map_err(|e| e.to_string())?;
-    let xml = tr_resp.text().map_err(|e| e.to_string())?;
-    // Parse XML transcript: collect <text> nodes
-    let mut reader = quick_xml::Reader::from_str(&xml);
-    reader.trim_text(true);
-    let mut buf = Vec::new();
-    let mut acc = String::new();
-    loop {
-        use quick_xml::events::Event;
-        match reader.read_event_into(&mut buf) {
-            Ok(Event::Eof) => break,
-            Ok(Event::Text(t)) => {
-                let txt = t.unescape().unwrap_or_default().to_string();
-                if !txt.trim().is_empty() {
-                    acc.push_str(&txt);
-                    acc.push('\n');
-                }
-            }
-            Ok(_) => {}
-            Err(_) => break,
-        }
-        buf.clear();
-    }
-    if acc.trim().is_empty() { Ok(None) } else { Ok(Some(acc)) }
+async fn fetch_youtube_transcript(url: String) -> Result<Option<String>, BrainboxError> {
+    run_blocking(move || {
+        let u = match reqwest::Url::parse(&url) { Ok(u) => u, Err(_) => return Ok(None) };
+        let host = u.host_str().unwrap_or("");
+        if !host.contains("youtube.com") && !host.contains("youtu.be") { return Ok(None); }
+        transcripts::youtube(&u)
+    }).await
+}
+
+/// Transcript for a YouTube/Vimeo video, podcast feed or audio file; falls back to local Whisper for audio without captions
+#[tauri::command]
+async fn fetch_media_transcript(url: String) -> Result<Option<transcripts::MediaTranscript>, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        transcripts::fetch(&conn, &url)
+    }).await
+}
+
+#[tauri::command]
+async fn get_whisper_settings() -> Result<transcripts::WhisperSettings, BrainboxError> {
+    run_blocking(|| {
+        let conn = db::open()?;
+        transcripts::get_whisper_settings(&conn)
+    }).await
+}
+
+#[tauri::command]
+async fn set_whisper_settings(settings: transcripts::WhisperSettings) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        transcripts::set_whisper_settings(&conn, &settings)
+    }).await
 }
 
 // --- Ollama Integration ---
@@ -1406,20 +4615,154 @@ fn sanitize_base_url(input: Option<String>) -> String {
 }
 
 #[tauri::command]
-fn ollama_list_models(base_url: Option<String>) -> Result<Vec<String>, String> {
-    use reqwest::blocking::Client;
-    let base = sanitize_base_url(base_url);
-    let url = format!("{}/api/tags", base);
-    let client = Client::builder()
-        .timeout(std::time::Duration::from_secs(8))
-        .build()
-        .map_err(|e| e.to_string())?;
-    let resp = client.get(&url).send().map_err(|e| e.to_string())?;
+async fn ollama_list_models(base_url: Option<String>) -> Result<Vec<String>, BrainboxError> {
+    run_blocking(move || {
+        let base = sanitize_base_url(base_url);
+        let url = format!("{}/api/tags", base);
+        let resp = http::send(http::client().get(&url).timeout(std::time::Duration::from_secs(8)))?;
+        if !resp.status().is_success() {
+            return Err(BrainboxError::Network(format!("Ollama returned status {}", resp.status())));
+        }
+        let tags: OllamaTagsResponse = resp.json()?;
+        Ok(tags.models.into_iter().map(|m| m.name).collect())
+    }).await
+}
+
+#[derive(serde::Deserialize)]
+struct OllamaVersionResponse {
+    version: String,
+}
+
+#[derive(serde::Deserialize)]
+struct OllamaPsResponse {
+    #[serde(default)]
+    models: Vec<OllamaRunningModel>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+struct OllamaRunningModel {
+    name: String,
+    /// Bytes the loaded model takes in total, and how much of it is in GPU memory
+    #[serde(default)]
+    size: u64,
+    #[serde(default)]
+    size_vram: u64,
+    /// When Ollama unloads it unless it is used again
+    #[serde(default)]
+    expires_at: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+struct OllamaStatus {
+    reachable: bool,
+    version: Option<String>,
+    /// Installed models
+    models: Vec<String>,
+    /// Models loaded in memory now; one only partly in VRAM runs slowly on the CPU
+    running: Vec<OllamaRunningModel>,
+    error: Option<String>,
+}
+
+fn ollama_get<T: serde::de::DeserializeOwned>(base: &str, path: &str) -> Result<T, BrainboxError> {
+    let resp = http::send(http::client().get(format!("{}{}", base, path)).timeout(std::time::Duration::from_secs(8)))?;
     if !resp.status().is_success() {
-        return Err(format!("Ollama returned status {}", resp.status()));
+        return Err(BrainboxError::Network(format!("Ollama returned status {}", resp.status())));
     }
-    let tags: OllamaTagsResponse = resp.json().map_err(|e| e.to_string())?;
-    Ok(tags.models.into_iter().map(|m| m.name).collect())
+    Ok(resp.json()?)
+}
+
+/// Whether Ollama answers at `base_url`, its version, installed models and what is loaded now (with VRAM use).
+/// An unreachable server is reported in the status rather than as an error.
+#[tauri::command]
+async fn ollama_status(base_url: Option<String>) -> Result<OllamaStatus, BrainboxError> {
+    run_blocking(move || {
+        let base = sanitize_base_url(base_url);
+        let version = match ollama_get::<OllamaVersionResponse>(&base, "/api/version") {
+            Ok(v) => v.version,
+            Err(e) => {
+                return Ok(OllamaStatus { reachable: false, version: None, models: Vec::new(), running: Vec::new(), error: Some(e.to_string()) });
+            }
+        };
+        let models = ollama_get::<OllamaTagsResponse>(&base, "/api/tags")?.models.into_iter().map(|m| m.name).collect();
+        // Servers older than /api/ps just don't report loaded models
+        let running = ollama_get::<OllamaPsResponse>(&base, "/api/ps").map(|ps| ps.models).unwrap_or_default();
+        Ok(OllamaStatus { reachable: true, version: Some(version), models, running, error: None })
+    }).await
+}
+
+#[derive(serde::Deserialize)]
+struct OllamaShowResponse {
+    /// "name value" lines, one per line
+    #[serde(default)]
+    parameters: String,
+    #[serde(default)]
+    details: OllamaModelDetails,
+    #[serde(default)]
+    model_info: HashMap<String, serde_json::Value>,
+    #[serde(default)]
+    capabilities: Vec<String>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Default)]
+struct OllamaModelDetails {
+    #[serde(default)]
+    family: Option<String>,
+    #[serde(default)]
+    parameter_size: Option<String>,
+    #[serde(default)]
+    quantization_level: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+struct OllamaModel {
+    name: String,
+    #[serde(flatten)]
+    details: OllamaModelDetails,
+    /// Longest context the model was trained for; Ollama only uses it all when num_ctx is set that high
+    context_length: Option<u64>,
+    /// Parameters from the Modelfile, such as num_ctx, temperature or stop; repeated ones (stop) are joined by newlines
+    parameters: HashMap<String, String>,
+    /// e.g. completion, vision, tools, embedding
+    capabilities: Vec<String>,
+}
+
+/// Size, quantization, context length and Modelfile parameters of an installed model
+#[tauri::command]
+async fn ollama_show_model(name: String, base_url: Option<String>) -> Result<OllamaModel, BrainboxError> {
+    run_blocking(move || {
+        let base = sanitize_base_url(base_url);
+        let resp = http::send(
+            http::client()
+                .post(format!("{}/api/show", base))
+                .json(&serde_json::json!({ "model": name }))
+                .timeout(std::time::Duration::from_secs(8)),
+        )?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(BrainboxError::NotFound(format!("Model {} is not installed", name)));
+        }
+        if !resp.status().is_success() {
+            return Err(BrainboxError::Network(format!("Ollama returned status {}", resp.status())));
+        }
+        let show: OllamaShowResponse = resp.json()?;
+        let context_length = show
+            .model_info
+            .iter()
+            .find(|(key, _)| key.ends_with(".context_length"))
+            .and_then(|(_, value)| value.as_u64());
+        let mut parameters: HashMap<String, String> = HashMap::new();
+        for line in show.parameters.lines() {
+            let Some((key, value)) = line.trim().split_once(char::is_whitespace) else { continue };
+            let value = value.trim().trim_matches('"').to_string();
+            parameters
+                .entry(key.to_string())
+                .and_modify(|existing| {
+                    existing.push('\n');
+                    existing.push_str(&value);
+                })
+                .or_insert(value);
+        }
+        Ok(OllamaModel { name, details: show.details, context_length, parameters, capabilities: show.capabilities })
+    }).await
 }
 
 #[derive(serde::Serialize)]
@@ -1429,6 +4772,8 @@ struct OllamaGenerateRequest<'a> {
     stream: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     system: Option<&'a str>,
+    #[serde(flatten)]
+    options: ollama_options::RequestOptions,
 }
 
 #[derive(serde::Deserialize)]
@@ -1436,67 +4781,181 @@ struct OllamaGenerateResponse {
     response: String,
 }
 
+/// `options` override the saved defaults for the "generate" use case
 #[tauri::command]
-fn ollama_generate(model: String, prompt: String, base_url: Option<String>, system: Option<String>) -> Result<String, String> {
-    use reqwest::blocking::Client;
-    let base = sanitize_base_url(base_url);
-    let url = format!("{}/api/generate", base);
-    let body = OllamaGenerateRequest { model: &model, prompt: &prompt, stream: false, system: system.as_deref() };
-    let client = Client::builder()
-        .timeout(std::time::Duration::from_secs(60))
-        .build()
-        .map_err(|e| e.to_string())?;
-    let resp = client
-        .post(&url)
-        .json(&body)
-        .send()
-        .map_err(|e| e.to_string())?;
-    if !resp.status().is_success() {
-        return Err(format!("Ollama returned status {}", resp.status()));
-    }
-    let gen: OllamaGenerateResponse = resp.json().map_err(|e| e.to_string())?;
-    Ok(gen.response)
+async fn ollama_generate(
+    model: String,
+    prompt: String,
+    base_url: Option<String>,
+    system: Option<String>,
+    options: Option<ollama_options::GenerationOptions>,
+) -> Result<String, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        let options = ollama_options::resolve(&conn, "generate", options)?;
+        let base = sanitize_base_url(base_url);
+        let url = format!("{}/api/generate", base);
+        let body = OllamaGenerateRequest { model: &model, prompt: &prompt, stream: false, system: system.as_deref(), options: options.request() };
+        // Generation is slow on modest hardware, so allow longer than the default timeout
+        let resp = http::send(
+            http::client()
+                .post(&url)
+                .json(&body)
+                .timeout(std::time::Duration::from_secs(60)),
+        )?;
+        if !resp.status().is_success() {
+            return Err(BrainboxError::Network(format!("Ollama returned status {}", resp.status())));
+        }
+        let gen: OllamaGenerateResponse = resp.json()?;
+        Ok(gen.response)
+    }).await
 }
 
 #[derive(serde::Serialize, Clone)]
 struct StreamEvent { streamId: String, #[serde(skip_serializing_if = "Option::is_none")] delta: Option<String>, done: bool }
 
+/// Estimated tokens `model` reads `text` as, with its context window, so the UI can warn before a prompt is cut
+#[tauri::command]
+fn count_tokens(text: String, model: String, num_ctx: Option<u32>) -> token_budget::TokenCount {
+    token_budget::measure(&text, &model, num_ctx)
+}
+
+/// Saved generation options for a use case (generate, prompts, drafts, digest, entities, image_labels)
+#[tauri::command]
+async fn get_ollama_options(use_case: String) -> Result<ollama_options::GenerationOptions, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        ollama_options::defaults(&conn, &use_case)
+    }).await
+}
+
+/// Save a use case's generation options; empty options go back to Ollama's defaults
+#[tauri::command]
+async fn set_ollama_options(use_case: String, options: ollama_options::GenerationOptions) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        ollama_options::set_defaults(&conn, &use_case, &options)
+    }).await
+}
+
 // Stream generate via events: emits "ollama-stream" with {streamId, delta} and a final {done:true}
 #[tauri::command]
-fn ollama_generate_stream(app: tauri::AppHandle, model: String, prompt: String, base_url: Option<String>, system: Option<String>, stream_id: String) -> Result<(), String> {
-    use reqwest::blocking::Client;
+async fn ollama_generate_stream(
+    app: tauri::AppHandle,
+    model: String,
+    prompt: String,
+    base_url: Option<String>,
+    system: Option<String>,
+    stream_id: String,
+    options: Option<ollama_options::GenerationOptions>,
+) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        let options = ollama_options::resolve(&conn, "generate", options)?;
+        let body = OllamaGenerateRequest { model: &model, prompt: &prompt, stream: true, system: system.as_deref(), options: options.request() };
+        stream_generate(&app, &sanitize_base_url(base_url), &body, &stream_id, |_| Ok(()))?;
+        Ok(())
+    }).await
+}
+
+/// Send a streaming generate request, emitting each delta as an "ollama-stream" event and passing it to `on_delta`.
+/// Returns the whole response.
+fn stream_generate(
+    app: &tauri::AppHandle,
+    base: &str,
+    body: &OllamaGenerateRequest,
+    stream_id: &str,
+    mut on_delta: impl FnMut(&str) -> Result<(), BrainboxError>,
+) -> Result<String, BrainboxError> {
     use std::io::{BufRead, BufReader};
-    let base = sanitize_base_url(base_url);
     let url = format!("{}/api/generate", base);
-    let body = OllamaGenerateRequest { model: &model, prompt: &prompt, stream: true, system: system.as_deref() };
-    let client = Client::builder().build().map_err(|e| e.to_string())?;
-    let resp = client.post(&url).json(&body).send().map_err(|e| e.to_string())?;
-    if !resp.status().is_success() { return Err(format!("Ollama returned status {}", resp.status())); }
+    let resp = http::send(http::streaming_client().post(&url).json(body))?;
+    if !resp.status().is_success() { return Err(BrainboxError::Network(format!("Ollama returned status {}", resp.status()))); }
     let mut reader = BufReader::new(resp);
     let mut line = String::new();
+    let mut response = String::new();
     loop {
         line.clear();
-        let n = reader.read_line(&mut line).map_err(|e| e.to_string())?;
+        let n = reader.read_line(&mut line)?;
         if n == 0 { break; }
         let trimmed = line.trim();
         if trimmed.is_empty() { continue; }
         if let Ok(v) = serde_json::from_str::<serde_json::Value>(trimmed) {
+            if let Some(error) = v.get("error").and_then(|e| e.as_str()) {
+                return Err(BrainboxError::Network(format!("Ollama: {}", error)));
+            }
             if v.get("done").and_then(|d| d.as_bool()).unwrap_or(false) {
-                let _ = app.emit("ollama-stream", StreamEvent { streamId: stream_id.clone(), delta: None, done: true });
+                let _ = app.emit("ollama-stream", StreamEvent { streamId: stream_id.to_string(), delta: None, done: true });
                 break;
             }
             if let Some(delta) = v.get("response").and_then(|s| s.as_str()) {
-                let _ = app.emit("ollama-stream", StreamEvent { streamId: stream_id.clone(), delta: Some(delta.to_string()), done: false });
+                response.push_str(delta);
+                on_delta(delta)?;
+                let _ = app.emit("ollama-stream", StreamEvent { streamId: stream_id.to_string(), delta: Some(delta.to_string()), done: false });
             }
         }
     }
-    Ok(())
+    Ok(response)
+}
+
+/// How often a draft being generated into an item is saved
+const DRAFT_SAVE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Generate into an item: the streamed answer is appended to its content and saved (encrypted) every couple of
+/// seconds, so a crash keeps what was written. Deltas are emitted as "ollama-stream" events under `stream_id` for
+/// the editor to show; it should not save the item itself until the final event. Returns the generated text.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn generate_into_item(
+    app: tauri::AppHandle,
+    item_id: i64,
+    key: Option<Vec<u8>>,
+    prompt: String,
+    model: String,
+    base_url: Option<String>,
+    system: Option<String>,
+    stream_id: String,
+    options: Option<ollama_options::GenerationOptions>,
+) -> Result<String, BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        let options = ollama_options::resolve(&conn, "drafts", options)?;
+        if model.trim().is_empty() {
+            return Err(BrainboxError::InvalidInput("Choose a model to generate with".to_string()));
+        }
+        let arr = item_content_key(&conn, item_id, key.clone())?;
+        let (_, mut content) = read_item_content(&conn, item_id, key.clone())?;
+        let trimmed_len = content.trim_end().len();
+        content.truncate(trimmed_len);
+        if !content.is_empty() {
+            content.push_str("\n\n");
+        }
+        let body = OllamaGenerateRequest { model: &model, prompt: &prompt, stream: true, system: system.as_deref(), options: options.request() };
+        let mut last_save = std::time::Instant::now();
+        let mut unsaved = false;
+        let generated = stream_generate(&app, &sanitize_base_url(base_url), &body, &stream_id, |delta| {
+            content.push_str(delta);
+            unsaved = true;
+            if last_save.elapsed() >= DRAFT_SAVE_INTERVAL {
+                VaultItem::update_content(&conn, item_id, &content, &arr)?;
+                last_save = std::time::Instant::now();
+                unsaved = false;
+            }
+            Ok(())
+        });
+        // Keep whatever arrived, also when the stream broke off
+        if unsaved {
+            VaultItem::update_content(&conn, item_id, &content, &arr)?;
+        }
+        let _ = reindex_item(&conn, item_id, key, None);
+        generated
+    }).await
 }
 
 // Command to quit the app from the frontend (e.g. tray menu)
 #[tauri::command]
 fn quit_app(app: tauri::AppHandle) -> Result<(), ()> {
-    app.exit(0);
+    lifecycle::quit(&app);
     Ok(())
 }
 
@@ -1510,6 +4969,15 @@ const GITHUB_REPO: &str = "oshtz/brainbox";
 struct GitHubRelease {
     tag_name: String,
     assets: Vec<GitHubAsset>,
+    /// Release notes (markdown)
+    #[serde(default)]
+    body: Option<String>,
+    #[serde(default)]
+    prerelease: bool,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    published_at: Option<String>,
 }
 
 #[derive(serde::Deserialize)]
@@ -1523,30 +4991,16 @@ struct UpdateInfo {
     version: String,
     download_url: String,
     asset_name: String,
-}
-
-/// Parse version string (strips 'v' prefix) and returns (major, minor, patch)
-fn parse_version(version: &str) -> Option<(u32, u32, u32)> {
-    let v = version.trim().trim_start_matches(|c| c == 'v' || c == 'V');
-    let parts: Vec<&str> = v.split('.').collect();
-    if parts.len() >= 3 {
-        let major = parts[0].parse().ok()?;
-        let minor = parts[1].parse().ok()?;
-        let patch = parts[2].parse().ok()?;
-        Some((major, minor, patch))
-    } else {
-        None
-    }
-}
-
-/// Compare two versions, returns true if new_version > current_version
-fn is_newer_version(current: &str, new_version: &str) -> bool {
-    match (parse_version(current), parse_version(new_version)) {
-        (Some((c_maj, c_min, c_pat)), Some((n_maj, n_min, n_pat))) => {
-            (n_maj, n_min, n_pat) > (c_maj, c_min, c_pat)
-        }
-        _ => false,
-    }
+    /// The minisign signature published alongside the asset; updates without one are not installed
+    #[serde(default)]
+    signature_url: Option<String>,
+    /// Release notes (markdown) for a "What's new" view
+    #[serde(default)]
+    notes: Option<String>,
+    #[serde(default)]
+    published_at: Option<String>,
+    #[serde(default)]
+    prerelease: bool,
 }
 
 /// Get the appropriate asset name for the current platform
@@ -1555,15 +5009,38 @@ fn get_platform_asset_pattern() -> &'static str {
     {
         ".app.tar.gz"
     }
-    #[cfg(not(target_os = "macos"))]
+    #[cfg(target_os = "linux")]
+    {
+        ".AppImage"
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
     {
         ""
     }
 }
 
+/// The running AppImage file. Only AppImage installs can update themselves; distro packages update through the package manager.
+#[cfg(target_os = "linux")]
+fn current_appimage() -> Option<std::path::PathBuf> {
+    std::env::var_os("APPIMAGE")
+        .map(std::path::PathBuf::from)
+        .filter(|path| path.is_file())
+}
+
+/// Architecture token used in Tauri's AppImage file names (brainbox_1.2.0_amd64.AppImage)
+#[cfg(target_os = "linux")]
+fn appimage_arch() -> &'static str {
+    match std::env::consts::ARCH {
+        "x86_64" => "amd64",
+        "x86" => "i386",
+        "arm" => "armhf",
+        other => other,
+    }
+}
+
 #[cfg(target_os = "windows")]
-fn is_portable_install() -> Result<bool, String> {
-    let exe_path = std::env::current_exe().map_err(|e| e.to_string())?;
+fn is_portable_install() -> Result<bool, BrainboxError> {
+    let exe_path = std::env::current_exe()?;
     let exe_name = exe_path
         .file_name()
         .and_then(|name| name.to_str())
@@ -1581,20 +5058,44 @@ fn matches_windows_asset(name: &str, portable: bool) -> bool {
     }
 }
 
+/// "stable" or "beta"
+#[tauri::command]
+async fn get_update_channel() -> Result<String, BrainboxError> {
+    run_blocking(|| {
+        let conn = db::open()?;
+        updater::get_channel(&conn)
+    }).await
+}
+
+#[tauri::command]
+async fn set_update_channel(channel: String) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        updater::set_channel(&conn, &channel)
+    }).await
+}
+
 #[tauri::command]
 fn get_current_version() -> String {
     env!("CARGO_PKG_VERSION").to_string()
 }
 
 #[tauri::command]
-async fn check_for_updates() -> Result<Option<UpdateInfo>, String> {
+async fn check_for_updates() -> Result<Option<UpdateInfo>, BrainboxError> {
     let current_version = env!("CARGO_PKG_VERSION");
-    let url = format!("https://api.github.com/repos/{}/releases/latest", GITHUB_REPO);
+    let channel = run_blocking(|| {
+        let conn = db::open()?;
+        updater::get_channel(&conn)
+    }).await?;
+    // /releases/latest never returns pre-releases, so the beta channel scans the recent release list instead
+    let include_prereleases = channel == "beta";
+    let url = if include_prereleases {
+        format!("https://api.github.com/repos/{}/releases?per_page=20", GITHUB_REPO)
+    } else {
+        format!("https://api.github.com/repos/{}/releases/latest", GITHUB_REPO)
+    };
     
-    let client = reqwest::Client::builder()
-        .user_agent("brainbox-updater")
-        .build()
-        .map_err(|e| e.to_string())?;
+    let client = http::async_client();
     
     let response = client
         .get(&url)
@@ -1603,17 +5104,38 @@ async fn check_for_updates() -> Result<Option<UpdateInfo>, String> {
         .map_err(|e| format!("Failed to fetch releases: {}", e))?;
     
     if !response.status().is_success() {
-        return Err(format!("GitHub API returned status: {}", response.status()));
+        return Err(BrainboxError::Network(format!("GitHub API returned status: {}", response.status())));
     }
     
-    let release: GitHubRelease = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse release info: {}", e))?;
+    let release: GitHubRelease = if include_prereleases {
+        let releases: Vec<GitHubRelease> = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse release info: {}", e))?;
+        // Newest release of any kind that is newer than what's running
+        let mut newest: Option<GitHubRelease> = None;
+        for release in releases.into_iter().filter(|r| !r.draft) {
+            let newer = newest
+                .as_ref()
+                .is_none_or(|best| updater::is_newer_version(&best.tag_name, &release.tag_name));
+            if newer {
+                newest = Some(release);
+            }
+        }
+        match newest {
+            Some(release) => release,
+            None => return Ok(None),
+        }
+    } else {
+        response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse release info: {}", e))?
+    };
     
     let new_version = release.tag_name.trim_start_matches('v');
     
-    if !is_newer_version(current_version, new_version) {
+    if !updater::is_newer_version(current_version, new_version) {
         return Ok(None);
     }
     
@@ -1638,7 +5160,7 @@ async fn check_for_updates() -> Result<Option<UpdateInfo>, String> {
     let asset = {
         let pattern = get_platform_asset_pattern();
         if pattern.is_empty() {
-            return Err("Auto-update not supported on this platform".to_string());
+            return Err("Auto-update not supported on this platform".into());
         }
         release
             .assets
@@ -1647,25 +5169,63 @@ async fn check_for_updates() -> Result<Option<UpdateInfo>, String> {
             .ok_or_else(|| "No suitable update asset found for this platform".to_string())?
     };
 
-    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
-    {
-        return Err("Auto-update not supported on this platform".to_string());
-    }
+    #[cfg(target_os = "linux")]
+    let asset = {
+        if current_appimage().is_none() {
+            return Err("Auto-update is only available for the AppImage build".into());
+        }
+        let pattern = get_platform_asset_pattern();
+        let arch = appimage_arch();
+        release
+            .assets
+            .iter()
+            .find(|a| a.name.ends_with(pattern) && a.name.contains(arch))
+            .ok_or_else(|| "No suitable AppImage found for this release".to_string())?
+    };
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+    let asset: &GitHubAsset = {
+        return Err("Auto-update not supported on this platform".into());
+    };
     
+    let signature_name = format!("{}{}", asset.name, updater::SIGNATURE_SUFFIX);
+    let signature_url = release
+        .assets
+        .iter()
+        .find(|a| a.name == signature_name)
+        .map(|a| a.browser_download_url.clone());
+
     Ok(Some(UpdateInfo {
         version: new_version.to_string(),
         download_url: asset.browser_download_url.clone(),
         asset_name: asset.name.clone(),
+        signature_url,
+        notes: release.body.clone().filter(|body| !body.trim().is_empty()),
+        published_at: release.published_at.clone(),
+        prerelease: release.prerelease,
     }))
 }
 
 #[tauri::command]
-async fn download_update(app: tauri::AppHandle, update_info: UpdateInfo) -> Result<String, String> {
-    let client = reqwest::Client::builder()
-        .user_agent("brainbox-updater")
-        .build()
-        .map_err(|e| e.to_string())?;
-    
+async fn download_update(app: tauri::AppHandle, update_info: UpdateInfo) -> Result<String, BrainboxError> {
+    // No overall timeout: release assets can take a while on slow connections
+    let client = http::async_streaming_client();
+
+    // Fetch the signature first so we don't download an asset we could never install
+    let signature_url = update_info
+        .signature_url
+        .as_deref()
+        .ok_or_else(|| BrainboxError::Update("This release has no signature for its update asset".to_string()))?;
+    let signature_response = client
+        .get(signature_url)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to download update signature: {}", e))?;
+    if !signature_response.status().is_success() {
+        return Err(BrainboxError::Network(format!("Signature download failed with status: {}", signature_response.status())));
+    }
+    let signature = signature_response.text().await?;
+
     let response = client
         .get(&update_info.download_url)
         .send()
@@ -1673,7 +5233,7 @@ async fn download_update(app: tauri::AppHandle, update_info: UpdateInfo) -> Resu
         .map_err(|e| format!("Failed to download update: {}", e))?;
     
     if !response.status().is_success() {
-        return Err(format!("Download failed with status: {}", response.status()));
+        return Err(BrainboxError::Network(format!("Download failed with status: {}", response.status())));
     }
     
     let total_size = response.content_length();
@@ -1702,7 +5262,16 @@ async fn download_update(app: tauri::AppHandle, update_info: UpdateInfo) -> Resu
             let _ = app.emit("update-progress", progress);
         }
     }
-    
+    drop(file);
+
+    if let Err(e) = updater::verify_file(&download_path, &signature) {
+        tracing::error!("Downloaded update failed verification: {}", e);
+        let _ = std::fs::remove_file(&download_path);
+        return Err(e);
+    }
+    // Kept next to the download so apply_update can verify the file again right before running it
+    std::fs::write(updater::signature_path(&download_path), &signature)?;
+
     let _ = app.emit("update-downloaded", ());
     
     Ok(download_path.to_string_lossy().to_string())
@@ -1714,28 +5283,52 @@ fn escape_powershell_literal(value: &str) -> String {
     value.replace('\'', "''")
 }
 
-#[cfg(target_os = "macos")]
+#[cfg(any(target_os = "macos", target_os = "linux"))]
 fn escape_bash_literal(value: &str) -> String {
     value.replace('\'', "'\\''")
 }
 
-#[tauri::command]
-fn apply_update(app: tauri::AppHandle, update_path: String) -> Result<(), String> {
-    if cfg!(debug_assertions) {
-        return Err("Auto-update is disabled in dev builds.".to_string());
-    }
+/// Name the current version's backup is stored under in the updates folder
+#[cfg(target_os = "windows")]
+const BACKUP_NAME: &str = "brainbox-previous.exe";
+#[cfg(target_os = "macos")]
+const BACKUP_NAME: &str = "brainbox.app";
+#[cfg(target_os = "linux")]
+const BACKUP_NAME: &str = "brainbox.AppImage";
+
+#[cfg(target_os = "macos")]
+fn current_app_bundle() -> Result<std::path::PathBuf, BrainboxError> {
+    let current_exe = std::env::current_exe()?;
+    // Get the .app bundle path (current_exe is inside .app/Contents/MacOS/)
+    Ok(current_exe
+        .parent()  // MacOS/
+        .and_then(|p| p.parent())  // Contents/
+        .and_then(|p| p.parent())  // .app bundle
+        .ok_or("Could not determine app bundle path")?
+        .to_path_buf())
+}
 
-    let update_file = Path::new(&update_path);
+/// Install a verified update once this process exits, keeping the running version as a backup for rollback_update.
+/// With `relaunch` false (a staged update applied on quit) the new version starts the next time brainbox is opened.
+fn spawn_update_installer(update_path: &str, relaunch: bool) -> Result<(), BrainboxError> {
+    let update_file = Path::new(update_path);
     if !update_file.exists() {
-        return Err("Update file not found.".to_string());
+        return Err("Update file not found.".into());
     }
+    updater::verify_downloaded(update_file)?;
+
+    let backup_dir = updater::backup_dir().ok_or("Failed to get app data dir")?;
+    std::fs::create_dir_all(&backup_dir)?;
+    let backup = backup_dir.join(BACKUP_NAME);
+    let pid = std::process::id();
 
     #[cfg(target_os = "windows")]
     {
+        let current_exe = std::env::current_exe()?;
+        // A running exe can still be read, so the backup is taken right away
+        std::fs::copy(&current_exe, &backup)?;
         let is_portable = is_portable_install()?;
         if is_portable {
-            let current_exe = std::env::current_exe().map_err(|e| e.to_string())?;
-            let pid = std::process::id();
             let script = format!(
                 r#"
                 $pid = {}
@@ -1744,36 +5337,27 @@ fn apply_update(app: tauri::AppHandle, update_path: String) -> Result<(), String
                 try {{ Wait-Process -Id $pid -ErrorAction SilentlyContinue }} catch {{}}
                 Start-Sleep -Milliseconds 200
                 Move-Item -Force $src $dst
-                Start-Process -FilePath $dst
+                if (${}) {{ Start-Process -FilePath $dst }}
                 "#,
                 pid,
-                escape_powershell_literal(&update_path),
+                escape_powershell_literal(update_path),
                 escape_powershell_literal(&current_exe.to_string_lossy()),
+                relaunch,
             );
 
             Command::new("powershell")
                 .args(["-NoProfile", "-ExecutionPolicy", "Bypass", "-Command", &script])
-                .spawn()
-                .map_err(|e| e.to_string())?;
+                .spawn()?;
         } else {
             // For Windows NSIS installer, just run it and exit
-            Command::new(&update_path)
-                .spawn()
-                .map_err(|e| e.to_string())?;
+            Command::new(update_path)
+                .spawn()?;
         }
     }
 
     #[cfg(target_os = "macos")]
     {
-        let current_exe = std::env::current_exe().map_err(|e| e.to_string())?;
-        let pid = std::process::id();
-        
-        // Get the .app bundle path (current_exe is inside .app/Contents/MacOS/)
-        let app_bundle = current_exe
-            .parent()  // MacOS/
-            .and_then(|p| p.parent())  // Contents/
-            .and_then(|p| p.parent())  // .app bundle
-            .ok_or("Could not determine app bundle path")?;
+        let app_bundle = current_app_bundle()?;
 
         // Extract the tar.gz and replace the app
         let temp_dir = std::env::temp_dir();
@@ -1781,7 +5365,7 @@ fn apply_update(app: tauri::AppHandle, update_path: String) -> Result<(), String
         
         // Clean up any previous extract
         let _ = std::fs::remove_dir_all(&extract_dir);
-        std::fs::create_dir_all(&extract_dir).map_err(|e| e.to_string())?;
+        std::fs::create_dir_all(&extract_dir)?;
 
         let script = format!(
             r#"
@@ -1789,6 +5373,8 @@ fn apply_update(app: tauri::AppHandle, update_path: String) -> Result<(), String
             archive='{}'
             extract_dir='{}'
             target='{}'
+            backup='{}'
+            relaunch={}
             
             # Wait for app to exit
             while kill -0 $pid 2>/dev/null; do sleep 0.2; done
@@ -1800,39 +5386,299 @@ fn apply_update(app: tauri::AppHandle, update_path: String) -> Result<(), String
             app_path=$(find "$extract_dir" -name "*.app" -maxdepth 1 | head -1)
             
             if [ -n "$app_path" ]; then
-                rm -rf "$target"
+                # Keep the current version for rollback instead of deleting it
+                rm -rf "$backup"
+                mv -f "$target" "$backup"
                 mv -f "$app_path" "$target"
                 xattr -cr "$target" 2>/dev/null || true
-                open "$target"
+                [ "$relaunch" = true ] && open "$target"
             fi
             
             # Cleanup
             rm -rf "$extract_dir"
-            rm -f "$archive"
+            rm -f "$archive" "$archive.sig"
             "#,
             pid,
-            escape_bash_literal(&update_path),
+            escape_bash_literal(update_path),
             escape_bash_literal(&extract_dir.to_string_lossy()),
             escape_bash_literal(&app_bundle.to_string_lossy()),
+            escape_bash_literal(&backup.to_string_lossy()),
+            relaunch,
         );
 
         Command::new("bash")
             .args(["-c", &script])
-            .spawn()
-            .map_err(|e| e.to_string())?;
+            .spawn()?;
     }
 
-    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    #[cfg(target_os = "linux")]
+    {
+        let target = current_appimage().ok_or("Auto-update is only available for the AppImage build")?;
+        std::fs::copy(&target, &backup)?;
+
+        // Stage the new AppImage next to the running one so the final mv is an atomic rename on the same file system
+        let script = format!(
+            r#"
+            pid={}
+            update='{}'
+            target='{}'
+            relaunch={}
+            staged="$target.new"
+
+            # Wait for app to exit
+            while kill -0 $pid 2>/dev/null; do sleep 0.2; done
+
+            if cp -f "$update" "$staged" && chmod +x "$staged" && mv -f "$staged" "$target"; then
+                rm -f "$update" "$update.sig"
+            else
+                rm -f "$staged"
+            fi
+
+            # Relaunch (the old AppImage if replacing failed)
+            [ "$relaunch" = true ] && nohup "$target" >/dev/null 2>&1 &
+            "#,
+            pid,
+            escape_bash_literal(update_path),
+            escape_bash_literal(&target.to_string_lossy()),
+            relaunch,
+        );
+
+        Command::new("bash")
+            .args(["-c", &script])
+            .spawn()?;
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+    {
+        let _ = (backup, pid, relaunch);
+        return Err("Auto-update is not supported on this platform.".into());
+    }
+
+    // The backup now holds the running version
+    let conn = db::open()?;
+    updater::set_previous_version(&conn, Some(env!("CARGO_PKG_VERSION")))?;
+    updater::clear_staged(&conn)?;
+    Ok(())
+}
+
+#[tauri::command]
+fn apply_update(app: tauri::AppHandle, update_path: String) -> Result<(), BrainboxError> {
+    if cfg!(debug_assertions) {
+        return Err("Auto-update is disabled in dev builds.".into());
+    }
+    spawn_update_installer(&update_path, true)?;
+    app.exit(0);
+    Ok(())
+}
+
+/// Install a downloaded update the next time the app exits instead of restarting now
+#[tauri::command]
+async fn stage_update(update_path: String) -> Result<(), BrainboxError> {
+    if cfg!(debug_assertions) {
+        return Err("Auto-update is disabled in dev builds.".into());
+    }
+    run_blocking(move || {
+        // Check now so a bad download is reported while the user is still here
+        updater::verify_downloaded(Path::new(&update_path))?;
+        let conn = db::open()?;
+        updater::stage(&conn, &update_path)
+    }).await
+}
+
+/// Path of the update waiting to be installed on exit, if any
+#[tauri::command]
+async fn get_staged_update() -> Result<Option<String>, BrainboxError> {
+    run_blocking(|| {
+        let conn = db::open()?;
+        updater::staged(&conn)
+    }).await
+}
+
+/// Called when the app exits: install a staged update without relaunching
+fn apply_staged_update() {
+    let staged = db::open().and_then(|conn| updater::staged(&conn));
+    match staged {
+        Ok(Some(path)) => {
+            tracing::info!("Installing staged update from {}", path);
+            if let Err(e) = spawn_update_installer(&path, false) {
+                tracing::error!("Failed to install staged update: {}", e);
+                // Don't retry a broken download on every exit
+                let _ = db::open().and_then(|conn| updater::clear_staged(&conn));
+            }
+        }
+        Ok(None) => {}
+        Err(e) => tracing::warn!("Couldn't check for a staged update: {}", e),
+    }
+}
+
+/// Version rollback_update would restore, if a backup exists
+#[tauri::command]
+async fn get_rollback_version() -> Result<Option<String>, BrainboxError> {
+    run_blocking(|| {
+        let conn = db::open()?;
+        let backup_exists = updater::backup_dir().is_some_and(|dir| dir.join(BACKUP_NAME).exists());
+        Ok(updater::previous_version(&conn)?.filter(|_| backup_exists))
+    }).await
+}
+
+/// Restore the version that was running before the last update, then restart
+#[tauri::command]
+fn rollback_update(app: tauri::AppHandle) -> Result<(), BrainboxError> {
+    if cfg!(debug_assertions) {
+        return Err("Auto-update is disabled in dev builds.".into());
+    }
+    let conn = db::open()?;
+    let version = updater::previous_version(&conn)?.ok_or("No previous version to roll back to")?;
+    let backup = updater::backup_dir().ok_or("Failed to get app data dir")?.join(BACKUP_NAME);
+    if !backup.exists() {
+        return Err(BrainboxError::NotFound("The previous version's backup is missing".to_string()));
+    }
+    tracing::info!("Rolling back to v{}", version);
+    let pid = std::process::id();
+
+    #[cfg(target_os = "windows")]
+    {
+        let current_exe = std::env::current_exe()?;
+        let script = format!(
+            r#"
+            $pid = {}
+            $src = '{}'
+            $dst = '{}'
+            try {{ Wait-Process -Id $pid -ErrorAction SilentlyContinue }} catch {{}}
+            Start-Sleep -Milliseconds 200
+            Move-Item -Force $src $dst
+            Start-Process -FilePath $dst
+            "#,
+            pid,
+            escape_powershell_literal(&backup.to_string_lossy()),
+            escape_powershell_literal(&current_exe.to_string_lossy()),
+        );
+        Command::new("powershell")
+            .args(["-NoProfile", "-ExecutionPolicy", "Bypass", "-Command", &script])
+            .spawn()?;
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let script = format!(
+            r#"
+            pid={}
+            backup='{}'
+            target='{}'
+            while kill -0 $pid 2>/dev/null; do sleep 0.2; done
+            rm -rf "$target"
+            mv -f "$backup" "$target"
+            open "$target"
+            "#,
+            pid,
+            escape_bash_literal(&backup.to_string_lossy()),
+            escape_bash_literal(&current_app_bundle()?.to_string_lossy()),
+        );
+        Command::new("bash")
+            .args(["-c", &script])
+            .spawn()?;
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        let target = current_appimage().ok_or("Rollback is only available for the AppImage build")?;
+        let script = format!(
+            r#"
+            pid={}
+            backup='{}'
+            target='{}'
+            staged="$target.new"
+            while kill -0 $pid 2>/dev/null; do sleep 0.2; done
+            if cp -f "$backup" "$staged" && chmod +x "$staged" && mv -f "$staged" "$target"; then
+                rm -f "$backup"
+            else
+                rm -f "$staged"
+            fi
+            nohup "$target" >/dev/null 2>&1 &
+            "#,
+            pid,
+            escape_bash_literal(&backup.to_string_lossy()),
+            escape_bash_literal(&target.to_string_lossy()),
+        );
+        Command::new("bash")
+            .args(["-c", &script])
+            .spawn()?;
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
     {
-        return Err("Auto-update is not supported on this platform.".to_string());
+        let _ = pid;
+        return Err("Auto-update is not supported on this platform.".into());
     }
 
+    updater::set_previous_version(&conn, None)?;
+    // Don't let a staged update undo the rollback on exit
+    updater::clear_staged(&conn)?;
     app.exit(0);
     Ok(())
 }
 
+/// How often the background scheduler wakes to see whether a check is due
+const UPDATE_SCHEDULER_POLL: std::time::Duration = std::time::Duration::from_secs(15 * 60);
+/// Give startup some room before the first check
+const UPDATE_SCHEDULER_DELAY: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Check for updates on the configured interval, emitting `update-available` (and a notification, once per version)
+fn spawn_update_scheduler(app: tauri::AppHandle) {
+    std::thread::spawn(move || {
+        std::thread::sleep(UPDATE_SCHEDULER_DELAY);
+        loop {
+            if let Err(e) = run_scheduled_update_check(&app) {
+                tracing::warn!("Scheduled update check failed: {}", e);
+            }
+            std::thread::sleep(UPDATE_SCHEDULER_POLL);
+        }
+    });
+}
+
+fn run_scheduled_update_check(app: &tauri::AppHandle) -> Result<(), BrainboxError> {
+    let conn = db::open()?;
+    if !updater::is_check_due(&conn)? {
+        return Ok(());
+    }
+    let result = tauri::async_runtime::block_on(check_for_updates());
+    // Record the attempt even on failure so an offline machine doesn't retry every poll
+    updater::mark_checked(&conn)?;
+    let Some(info) = result? else { return Ok(()) };
+    tracing::info!("Update available: v{}", info.version);
+    let _ = app.emit("update-available", info.clone());
+
+    if updater::get_check_settings(&conn)?.notify && updater::mark_notified(&conn, &info.version)? {
+        use tauri_plugin_notification::NotificationExt;
+        let _ = app
+            .notification()
+            .builder()
+            .title(i18n::tr("brainbox update available"))
+            .body(i18n::trf("Version {} is ready to install from Settings > Updates", &[&info.version]))
+            .show();
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_update_check_settings() -> Result<updater::UpdateCheckSettings, BrainboxError> {
+    run_blocking(|| {
+        let conn = db::open()?;
+        updater::get_check_settings(&conn)
+    }).await
+}
+
+/// Set the background check interval in hours (0 disables scheduled checks) and whether to notify
+#[tauri::command]
+async fn set_update_check_settings(settings: updater::UpdateCheckSettings) -> Result<(), BrainboxError> {
+    run_blocking(move || {
+        let conn = db::open()?;
+        updater::set_check_settings(&conn, &settings)
+    }).await
+}
+
 #[tauri::command]
-async fn install_update(app: tauri::AppHandle) -> Result<(), String> {
+async fn install_update(app: tauri::AppHandle) -> Result<(), BrainboxError> {
     // Check for update
     let update_info = check_for_updates()
         .await?
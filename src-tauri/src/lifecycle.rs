@@ -0,0 +1,72 @@
+// lifecycle.rs - Main window close and app quit for brainbox
+// Handled in Rust so close-to-tray and sync-on-close work even when the webview is busy or never finished loading
+
+use rusqlite::Connection;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Manager, Window, WindowEvent};
+
+use crate::error::BrainboxError;
+use crate::settings;
+
+const CLOSE_TO_TRAY_KEY: &str = "close_to_tray";
+
+/// Set once a quit has started so repeated close clicks don't run sync twice
+static QUITTING: AtomicBool = AtomicBool::new(false);
+
+pub fn is_close_to_tray_enabled(conn: &Connection) -> Result<bool, BrainboxError> {
+    Ok(settings::get_bool(conn, CLOSE_TO_TRAY_KEY)?)
+}
+
+pub fn set_close_to_tray(conn: &Connection, enabled: bool) -> Result<(), BrainboxError> {
+    settings::set_bool(conn, CLOSE_TO_TRAY_KEY, enabled)?;
+    Ok(())
+}
+
+/// Whether the app is on its way out; windows closing now weren't closed by the user
+pub fn is_quitting() -> bool {
+    QUITTING.load(Ordering::SeqCst)
+}
+
+/// Builder-level window event hook: closing the main window either hides it to the tray or quits
+pub fn on_window_event(window: &Window, event: &WindowEvent) {
+    if window.label() != "main" {
+        return;
+    }
+    let WindowEvent::CloseRequested { api, .. } = event else { return };
+    // Always take over the close: a hidden quick switcher window would otherwise keep the process alive
+    api.prevent_close();
+    let close_to_tray = crate::db::open()
+        .and_then(|conn| is_close_to_tray_enabled(&conn))
+        .unwrap_or(false);
+    if close_to_tray {
+        let _ = window.hide();
+    } else {
+        quit(window.app_handle());
+    }
+}
+
+/// Quit the app, exporting to the sync folder first when "sync on close" is enabled.
+/// Like the tray's Sync Now, only vaults that don't need a password are exported.
+pub fn quit(app: &AppHandle) {
+    if QUITTING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    crate::item_windows::save_on_quit(app);
+    let app = app.clone();
+    std::thread::spawn(move || {
+        if let Ok(conn) = crate::db::open() {
+            if crate::sync::is_sync_on_close_enabled(&conn).unwrap_or(false) {
+                // Hide first so quitting feels immediate while the export runs
+                if let Some(window) = app.get_webview_window("main") {
+                    let _ = window.hide();
+                }
+                match crate::sync::sync_export(&conn, Default::default(), &crate::progress::Operation::silent()) {
+                    Ok(_) => tracing::info!("Sync on close completed"),
+                    // Don't block the quit even if sync fails
+                    Err(e) => tracing::error!("Sync on close failed: {}", e),
+                }
+            }
+        }
+        app.exit(0);
+    });
+}
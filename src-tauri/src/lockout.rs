@@ -0,0 +1,131 @@
+// lockout.rs - Failed vault password attempts for brainbox
+// Vault keys are checked in the backend, so without a limit a password could be guessed as fast as IPC calls go.
+// Failures are counted per vault and, after a few free attempts, each one locks the vault twice as long as the last.
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::error::BrainboxError;
+
+/// Wrong passwords allowed before the first lockout
+const FREE_ATTEMPTS: i64 = 5;
+/// Length of the first lockout; each further failure doubles it
+const BASE_LOCKOUT_SECS: i64 = 30;
+const MAX_LOCKOUT_SECS: i64 = 60 * 60;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct LockoutStatus {
+    pub vault_id: i64,
+    pub failed_attempts: i64,
+    pub locked: bool,
+    /// Seconds until the next attempt is accepted; 0 when not locked
+    pub retry_after_secs: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locked_until: Option<String>,
+}
+
+pub fn create_table(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS vault_lockouts (
+            vault_id INTEGER PRIMARY KEY REFERENCES vaults(id) ON DELETE CASCADE,
+            failed_attempts INTEGER NOT NULL DEFAULT 0,
+            last_failed_at TEXT,
+            locked_until TEXT
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Lockout applied after the given number of consecutive failures
+fn lockout_secs(failed_attempts: i64) -> i64 {
+    if failed_attempts < FREE_ATTEMPTS {
+        return 0;
+    }
+    let doublings = (failed_attempts - FREE_ATTEMPTS).min(16) as u32;
+    (BASE_LOCKOUT_SECS << doublings).min(MAX_LOCKOUT_SECS)
+}
+
+pub fn status(conn: &Connection, vault_id: i64) -> Result<LockoutStatus, BrainboxError> {
+    create_table(conn)?;
+    let row: Option<(i64, Option<String>)> = conn
+        .query_row(
+            "SELECT failed_attempts, locked_until FROM vault_lockouts WHERE vault_id = ?1",
+            [vault_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+    let (failed_attempts, locked_until) = row.unwrap_or((0, None));
+    let retry_after_secs = locked_until
+        .as_deref()
+        .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+        .map(|until| (until.with_timezone(&Utc) - Utc::now()).num_seconds().max(0))
+        .unwrap_or(0);
+    let locked = retry_after_secs > 0;
+    Ok(LockoutStatus {
+        vault_id,
+        failed_attempts,
+        locked,
+        retry_after_secs,
+        locked_until: if locked { locked_until } else { None },
+    })
+}
+
+/// Refuse an attempt while the vault is locked out; otherwise returns the current status
+pub fn check(conn: &Connection, vault_id: i64) -> Result<LockoutStatus, BrainboxError> {
+    let status = status(conn, vault_id)?;
+    if status.locked {
+        return Err(BrainboxError::VaultLocked(status.retry_after_secs));
+    }
+    Ok(status)
+}
+
+pub fn record_failure(conn: &Connection, vault_id: i64) -> Result<LockoutStatus, BrainboxError> {
+    create_table(conn)?;
+    let now = Utc::now();
+    let failed_attempts: i64 = conn
+        .query_row("SELECT failed_attempts FROM vault_lockouts WHERE vault_id = ?1", [vault_id], |row| row.get(0))
+        .optional()?
+        .unwrap_or(0)
+        + 1;
+    let secs = lockout_secs(failed_attempts);
+    let locked_until = (secs > 0).then(|| (now + chrono::Duration::seconds(secs)).to_rfc3339());
+    conn.execute(
+        "INSERT OR REPLACE INTO vault_lockouts (vault_id, failed_attempts, last_failed_at, locked_until) VALUES (?1, ?2, ?3, ?4)",
+        params![vault_id, failed_attempts, now.to_rfc3339(), locked_until],
+    )?;
+    status(conn, vault_id)
+}
+
+/// Forget failures after a correct password
+pub fn reset(conn: &Connection, vault_id: i64) -> Result<(), BrainboxError> {
+    create_table(conn)?;
+    conn.execute("DELETE FROM vault_lockouts WHERE vault_id = ?1", [vault_id])?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn free_attempts_are_not_locked() {
+        for attempts in 0..FREE_ATTEMPTS {
+            assert_eq!(lockout_secs(attempts), 0);
+        }
+    }
+
+    #[test]
+    fn lockout_doubles_after_the_free_attempts() {
+        assert_eq!(lockout_secs(FREE_ATTEMPTS), BASE_LOCKOUT_SECS);
+        assert_eq!(lockout_secs(FREE_ATTEMPTS + 1), BASE_LOCKOUT_SECS * 2);
+        assert_eq!(lockout_secs(FREE_ATTEMPTS + 2), BASE_LOCKOUT_SECS * 4);
+    }
+
+    #[test]
+    fn lockout_is_capped() {
+        assert_eq!(lockout_secs(FREE_ATTEMPTS + 10), MAX_LOCKOUT_SECS);
+        assert_eq!(lockout_secs(i64::MAX), MAX_LOCKOUT_SECS);
+    }
+}
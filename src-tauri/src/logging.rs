@@ -0,0 +1,135 @@
+// logging.rs - Logging for brainbox
+// tracing output goes to stderr and to daily-rotated files under the app data dir so users can attach logs to bug reports
+
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{Builder as RollingBuilder, Rotation};
+use tracing_subscriber::{fmt, layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter, Registry};
+
+use crate::settings;
+
+/// Settings key holding the configured log level
+pub const LOG_LEVEL_KEY: &str = "log_level";
+pub const DEFAULT_LOG_LEVEL: &str = "info";
+pub const LOG_LEVELS: &[&str] = &["error", "warn", "info", "debug", "trace"];
+
+const LOG_FILE_PREFIX: &str = "brainbox";
+const LOG_FILE_SUFFIX: &str = "log";
+/// Number of daily log files kept before the oldest is deleted
+const MAX_LOG_FILES: usize = 7;
+
+lazy_static::lazy_static! {
+    // Keeps the background log writer alive; dropping it would stop file logging
+    static ref LOG_GUARD: Mutex<Option<WorkerGuard>> = Mutex::new(None);
+    static ref FILTER_HANDLE: Mutex<Option<reload::Handle<EnvFilter, Registry>>> = Mutex::new(None);
+}
+
+pub fn is_valid_log_level(level: &str) -> bool {
+    LOG_LEVELS.contains(&level)
+}
+
+/// Directory holding the log files
+pub fn log_dir() -> Option<PathBuf> {
+    Some(crate::data_dir::root()?.join("brainbox").join("logs"))
+}
+
+/// Filter for a level; tantivy is very chatty at info, so it is capped at warn
+fn make_filter(level: &str) -> EnvFilter {
+    EnvFilter::new(format!("{},tantivy=warn", level))
+}
+
+/// Log level saved in settings, falling back to the default
+fn saved_log_level() -> String {
+    crate::db::open()
+        .ok()
+        .and_then(|conn| settings::get(&conn, LOG_LEVEL_KEY).ok().flatten())
+        .filter(|level| is_valid_log_level(level))
+        .unwrap_or_else(|| DEFAULT_LOG_LEVEL.to_string())
+}
+
+/// Install the global subscriber. Call once at startup, before anything logs.
+pub fn init() {
+    let (filter, handle) = reload::Layer::new(make_filter(&saved_log_level()));
+    let stderr_layer = fmt::layer().with_writer(std::io::stderr);
+
+    let appender = log_dir().and_then(|dir| {
+        std::fs::create_dir_all(&dir).ok()?;
+        RollingBuilder::new()
+            .rotation(Rotation::DAILY)
+            .filename_prefix(LOG_FILE_PREFIX)
+            .filename_suffix(LOG_FILE_SUFFIX)
+            .max_log_files(MAX_LOG_FILES)
+            .build(dir)
+            .ok()
+    });
+    let file_layer = appender.map(|appender| {
+        let (writer, guard) = tracing_appender::non_blocking(appender);
+        *LOG_GUARD.lock().unwrap() = Some(guard);
+        fmt::layer().with_writer(writer).with_ansi(false)
+    });
+
+    if tracing_subscriber::registry()
+        .with(filter)
+        .with(stderr_layer)
+        .with(file_layer)
+        .try_init()
+        .is_ok()
+    {
+        *FILTER_HANDLE.lock().unwrap() = Some(handle);
+    }
+    if LOG_GUARD.lock().unwrap().is_none() {
+        tracing::warn!("File logging unavailable, logging to stderr only");
+    }
+}
+
+/// Change the active log level without restarting
+pub fn set_level(level: &str) -> Result<(), String> {
+    if !is_valid_log_level(level) {
+        return Err(format!("Unknown log level: {}", level));
+    }
+    let guard = FILTER_HANDLE.lock().unwrap();
+    let handle = guard.as_ref().ok_or("Logging is not initialized")?;
+    handle.reload(make_filter(level)).map_err(|e| e.to_string())
+}
+
+/// The log files on disk, oldest first
+pub fn log_files() -> Result<Vec<PathBuf>, String> {
+    let dir = log_dir().ok_or("Failed to get app data dir")?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    // Daily file names end in the date, so sorting by name sorts by age
+    let mut files: Vec<PathBuf> = std::fs::read_dir(&dir)
+        .map_err(|e| e.to_string())?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .map(|n| n.starts_with(LOG_FILE_PREFIX) && n.ends_with(LOG_FILE_SUFFIX))
+                .unwrap_or(false)
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Last `lines` lines across the most recent log files, oldest first
+pub fn recent_lines(lines: usize) -> Result<Vec<String>, String> {
+    let files = log_files()?;
+    let mut collected: Vec<String> = Vec::new();
+    for path in files.iter().rev() {
+        let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let mut file_lines: Vec<String> = text.lines().map(|l| l.to_string()).collect();
+        let needed = lines - collected.len();
+        if file_lines.len() > needed {
+            file_lines.drain(..file_lines.len() - needed);
+        }
+        file_lines.append(&mut collected);
+        collected = file_lines;
+        if collected.len() >= lines {
+            break;
+        }
+    }
+    Ok(collected)
+}
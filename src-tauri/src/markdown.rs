@@ -0,0 +1,372 @@
+// markdown.rs - Structure of markdown notes for brainbox
+// Outline panes, task lists, links between notes and code-language filters all need to know what a note is made
+// of, not just its text. One pass over the lines finds headings (ATX and setext), fenced code blocks and the lines
+// inside them, checkboxes and links, following CommonMark's block rules closely enough for notes; nothing inside a
+// code block counts as structure.
+
+use rusqlite::Connection;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+
+use crate::deeplink::{self, DeepLink};
+use crate::error::BrainboxError;
+use crate::tasks::{self, TaskCheckbox};
+use crate::vault::VaultItem;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Heading {
+    /// 1 to 6
+    pub level: u8,
+    pub text: String,
+    /// Zero-based line number, like task checkboxes
+    pub line: usize,
+    /// Headings of a deeper level up to the next heading of this level or higher
+    pub children: Vec<Heading>,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkKind {
+    /// `[text](url)`
+    Inline,
+    /// `![alt](src)`
+    Image,
+    /// `<https://...>`
+    Autolink,
+    /// `[[Title]]` or `[[Title|label]]`
+    Wiki,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct MarkdownLink {
+    pub kind: LinkKind,
+    /// The URL, or the note title of a wiki link
+    pub target: String,
+    pub text: String,
+    pub line: usize,
+    /// Set by the caller when the link points at another brainbox item
+    pub item_id: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct CodeBlock {
+    /// The info string's first word, lowercased; None for a fence without one
+    pub language: Option<String>,
+    /// Line of the opening fence
+    pub line: usize,
+    pub line_count: usize,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct MarkdownStructure {
+    pub outline: Vec<Heading>,
+    pub tasks: Vec<TaskCheckbox>,
+    pub links: Vec<MarkdownLink>,
+    pub code_blocks: Vec<CodeBlock>,
+}
+
+/// How often a language appears in code blocks
+#[derive(Debug, Serialize, Clone)]
+pub struct CodeLanguageCount {
+    pub language: String,
+    pub blocks: usize,
+    /// Items with at least one block in it
+    pub items: usize,
+}
+
+/// Up to three spaces of indentation are allowed before block markers
+fn strip_indent(line: &str) -> Option<&str> {
+    let trimmed = line.trim_start_matches(' ');
+    (line.len() - trimmed.len() <= 3).then_some(trimmed)
+}
+
+/// (fence character, fence length, info string) of an opening or closing code fence
+fn fence(line: &str) -> Option<(char, usize, &str)> {
+    let rest = strip_indent(line)?;
+    let marker = rest.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = rest.chars().take_while(|c| *c == marker).count();
+    if len < 3 {
+        return None;
+    }
+    let info = rest[len..].trim();
+    // A backtick fence's info string can't contain backticks
+    (marker == '~' || !info.contains('`')).then_some((marker, len, info))
+}
+
+fn atx_heading(line: &str) -> Option<(u8, String)> {
+    let rest = strip_indent(line)?;
+    let level = rest.chars().take_while(|c| *c == '#').count();
+    if level == 0 || level > 6 {
+        return None;
+    }
+    let text = &rest[level..];
+    if !text.is_empty() && !text.starts_with([' ', '\t']) {
+        return None;
+    }
+    // An optional closing run of #s, when set off by a space
+    let text = text.trim();
+    let without_closing = text.trim_end_matches('#');
+    let text = if without_closing.is_empty() || without_closing.ends_with([' ', '\t']) { without_closing.trim_end() } else { text };
+    Some((level as u8, text.to_string()))
+}
+
+/// Level of a setext underline: `===` makes a level 1 heading of the line above, `---` level 2
+fn setext_underline(line: &str) -> Option<u8> {
+    let rest = strip_indent(line)?.trim_end();
+    let marker = rest.chars().next()?;
+    if !rest.chars().all(|c| c == marker) {
+        return None;
+    }
+    match marker {
+        '=' => Some(1),
+        '-' => Some(2),
+        _ => None,
+    }
+}
+
+/// Whether a line can be the text of a setext heading (a paragraph line, not another block)
+fn is_paragraph_line(line: &str) -> bool {
+    let Some(rest) = strip_indent(line) else { return false };
+    !rest.trim().is_empty()
+        && atx_heading(line).is_none()
+        && fence(line).is_none()
+        && !rest.starts_with(['>', '-', '*', '+'])
+        && !rest.starts_with(|c: char| c.is_ascii_digit())
+}
+
+/// Move the deepest open heading into its parent, or to the top level
+fn close_heading(open: &mut Vec<Heading>, roots: &mut Vec<Heading>) {
+    if let Some(done) = open.pop() {
+        match open.last_mut() {
+            Some(parent) => parent.children.push(done),
+            None => roots.push(done),
+        }
+    }
+}
+
+/// Nest flat headings into a tree by level
+fn build_tree(flat: Vec<Heading>) -> Vec<Heading> {
+    let mut roots = Vec::new();
+    // Headings that can still take children, shallowest first
+    let mut open: Vec<Heading> = Vec::new();
+    for heading in flat {
+        while open.last().is_some_and(|last| last.level >= heading.level) {
+            close_heading(&mut open, &mut roots);
+        }
+        open.push(heading);
+    }
+    while !open.is_empty() {
+        close_heading(&mut open, &mut roots);
+    }
+    roots
+}
+
+/// Text of a line with inline code spans blanked out, so links inside them aren't picked up
+fn without_code_spans(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(start) = rest.find('`') {
+        let run = rest[start..].chars().take_while(|c| *c == '`').count();
+        let delimiter = &rest[start..start + run];
+        out.push_str(&rest[..start]);
+        match rest[start + run..].find(delimiter) {
+            Some(end) => {
+                out.push_str(&" ".repeat(run * 2 + end));
+                rest = &rest[start + run + end + run..];
+            }
+            None => {
+                out.push_str(delimiter);
+                rest = &rest[start + run..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Position of the bracket closing the one at the start of `text`, allowing nested pairs
+fn closing(text: &str, open: char, close: char) -> Option<usize> {
+    let mut depth = 0;
+    for (index, c) in text.char_indices() {
+        if c == open {
+            depth += 1;
+        } else if c == close {
+            depth -= 1;
+            if depth == 0 {
+                return Some(index);
+            }
+        }
+    }
+    None
+}
+
+fn line_links(line: &str, line_number: usize, links: &mut Vec<MarkdownLink>) {
+    let text = without_code_spans(line);
+    let mut at = 0;
+    while at < text.len() {
+        let rest = &text[at..];
+        if let Some(inner) = rest.strip_prefix("[[") {
+            if let Some(end) = inner.find("]]") {
+                let (target, label) = inner[..end].split_once('|').unwrap_or((&inner[..end], &inner[..end]));
+                if !target.trim().is_empty() {
+                    links.push(MarkdownLink {
+                        kind: LinkKind::Wiki,
+                        target: target.trim().to_string(),
+                        text: label.trim().to_string(),
+                        line: line_number,
+                        item_id: None,
+                    });
+                }
+                at += 2 + end + 2;
+                continue;
+            }
+        }
+        let image = rest.starts_with("![");
+        if image || rest.starts_with('[') {
+            let bracket = if image { 1 } else { 0 };
+            if let Some(close) = closing(&rest[bracket..], '[', ']') {
+                let after = &rest[bracket + close + 1..];
+                if after.starts_with('(') {
+                    if let Some(paren) = closing(after, '(', ')') {
+                        let destination = after[1..paren].trim();
+                        // An optional title follows the destination after whitespace
+                        let url = destination.split_whitespace().next().unwrap_or_default();
+                        let url = url.strip_prefix('<').and_then(|u| u.strip_suffix('>')).unwrap_or(url);
+                        if !url.is_empty() {
+                            links.push(MarkdownLink {
+                                kind: if image { LinkKind::Image } else { LinkKind::Inline },
+                                target: url.to_string(),
+                                text: rest[bracket + 1..bracket + close].to_string(),
+                                line: line_number,
+                                item_id: None,
+                            });
+                        }
+                        at += bracket + close + 1 + paren + 1;
+                        continue;
+                    }
+                }
+            }
+        }
+        if let Some(inner) = rest.strip_prefix('<') {
+            if let Some(end) = inner.find('>') {
+                let url = &inner[..end];
+                let is_url = url.split_once(':').is_some_and(|(scheme, rest)| {
+                    scheme.len() >= 2
+                        && scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+.-".contains(c))
+                        && !rest.is_empty()
+                        && !url.contains(char::is_whitespace)
+                });
+                if is_url {
+                    links.push(MarkdownLink {
+                        kind: LinkKind::Autolink,
+                        target: url.to_string(),
+                        text: url.to_string(),
+                        line: line_number,
+                        item_id: None,
+                    });
+                    at += 1 + end + 1;
+                    continue;
+                }
+            }
+        }
+        at += rest.chars().next().map_or(1, char::len_utf8);
+    }
+}
+
+/// Headings, checkboxes, links and code blocks of a note
+pub fn parse(content: &str) -> MarkdownStructure {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut headings = Vec::new();
+    let mut links = Vec::new();
+    let mut code_blocks = Vec::new();
+    let mut in_code = vec![false; lines.len()];
+    // Fence character, its length and the block being read
+    let mut open_fence: Option<(char, usize, CodeBlock)> = None;
+
+    for (number, line) in lines.iter().enumerate() {
+        if let Some((marker, len, block)) = open_fence.as_mut() {
+            in_code[number] = true;
+            let closes = fence(line).is_some_and(|(c, l, info)| c == *marker && l >= *len && info.is_empty());
+            if closes {
+                block.line_count = number - block.line - 1;
+                if let Some((_, _, block)) = open_fence.take() {
+                    code_blocks.push(block);
+                }
+            }
+            continue;
+        }
+        if let Some((marker, len, info)) = fence(line) {
+            in_code[number] = true;
+            let language = info.split_whitespace().next().map(|word| word.to_lowercase());
+            open_fence = Some((marker, len, CodeBlock { language, line: number, line_count: 0 }));
+            continue;
+        }
+        // Indented code: four spaces or a tab, and not continuing a paragraph
+        let indented = line.starts_with("    ") || line.starts_with('\t');
+        let after_blank = number == 0 || lines[number - 1].trim().is_empty() || in_code[number - 1];
+        if indented && after_blank && !line.trim().is_empty() {
+            in_code[number] = true;
+            continue;
+        }
+        if let Some((level, text)) = atx_heading(line) {
+            headings.push(Heading { level, text, line: number, children: Vec::new() });
+            continue;
+        }
+        if let Some(level) = setext_underline(line) {
+            let above = number.checked_sub(1).filter(|above| !in_code[*above] && is_paragraph_line(lines[*above]));
+            if let Some(above) = above.filter(|above| headings.last().is_none_or(|h: &Heading| h.line != *above)) {
+                headings.push(Heading { level, text: lines[above].trim().to_string(), line: above, children: Vec::new() });
+                continue;
+            }
+        }
+        line_links(line, number, &mut links);
+    }
+    // A fence left open runs to the end of the note
+    if let Some((_, _, mut block)) = open_fence {
+        block.line_count = lines.len() - block.line - 1;
+        code_blocks.push(block);
+    }
+
+    let tasks = tasks::parse_checkboxes(content).into_iter().filter(|task| !in_code.get(task.line).copied().unwrap_or(false)).collect();
+    MarkdownStructure { outline: build_tree(headings), tasks, links, code_blocks }
+}
+
+/// Just the headings tree
+pub fn outline(content: &str) -> Vec<Heading> {
+    parse(content).outline
+}
+
+/// Code block languages across several notes, most used first; blocks without a language are left out
+pub fn code_language_stats<'a>(notes: impl IntoIterator<Item = &'a str>) -> Vec<CodeLanguageCount> {
+    let mut counts: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+    for note in notes {
+        let mut seen = HashSet::new();
+        for language in parse(note).code_blocks.into_iter().filter_map(|block| block.language) {
+            let entry = counts.entry(language.clone()).or_default();
+            entry.0 += 1;
+            if seen.insert(language) {
+                entry.1 += 1;
+            }
+        }
+    }
+    let mut stats: Vec<CodeLanguageCount> =
+        counts.into_iter().map(|(language, (blocks, items))| CodeLanguageCount { language, blocks, items }).collect();
+    stats.sort_by(|a, b| b.blocks.cmp(&a.blocks).then_with(|| a.language.cmp(&b.language)));
+    stats
+}
+
+/// Fill in `item_id` for brainbox://item links and for wiki links naming a note in `vault_id`
+pub fn resolve_links(conn: &Connection, vault_id: i64, links: &mut [MarkdownLink]) -> Result<(), BrainboxError> {
+    for link in links.iter_mut() {
+        link.item_id = match link.kind {
+            LinkKind::Wiki => VaultItem::find_by_title(conn, vault_id, &link.target)?,
+            _ => match deeplink::parse(&link.target) {
+                Some(DeepLink::Item { uuid }) => {
+                    VaultItem::get_by_uuid(conn, &uuid)?.filter(|item| item.deleted_at.is_none()).map(|item| item.id)
+                }
+                _ => None,
+            },
+        };
+    }
+    Ok(())
+}
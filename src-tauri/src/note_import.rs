@@ -0,0 +1,555 @@
+// note_import.rs - Importers for other note apps in brainbox
+// Joplin JEX archives, Standard Notes decrypted backups and exported Apple Notes / OneNote folders are converted
+// into brainbox's own export format, so they go through the same import path (one transaction, merge support)
+// as a brainbox JSON export. Notebooks become vaults (nested notebooks become collections), tags stay tags and
+// timestamps are kept.
+
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::error::BrainboxError;
+use crate::{image_cache, rich_text};
+use crate::{ExportData, ExportedCollection, ExportedItem, ExportedVault};
+
+const TAR_BLOCK: usize = 512;
+/// Each Joplin item is one markdown file; anything bigger is a resource or not a JEX file
+const MAX_JOPLIN_ITEM_BYTES: u64 = 20 * 1024 * 1024;
+const MAX_BACKUP_BYTES: u64 = 500 * 1024 * 1024;
+/// Vault for notes whose notebook isn't in the archive, and for all Standard Notes notes
+const JOPLIN_FALLBACK_VAULT: &str = "Joplin";
+const STANDARD_NOTES_VAULT: &str = "Standard Notes";
+/// Files an exported notes folder can contain; images are only brought in when a note references them
+const NOTE_FILE_EXTENSIONS: &[&str] = &["html", "htm", "docx", "md", "markdown", "txt"];
+const MAX_NOTE_FILE_BYTES: u64 = 50 * 1024 * 1024;
+/// Folders nested deeper than this below a notebook are not imported
+const MAX_FOLDER_DEPTH: usize = 8;
+
+// Joplin item types (`type_` property)
+const JOPLIN_NOTE: i64 = 1;
+const JOPLIN_FOLDER: i64 = 2;
+const JOPLIN_TAG: i64 = 5;
+const JOPLIN_NOTE_TAG: i64 = 6;
+
+fn export_data(vaults: Vec<ExportedVault>) -> ExportData {
+    ExportData {
+        version: "1.0".to_string(),
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        vaults,
+    }
+}
+
+fn new_vault(uuid: Option<String>, name: &str, created_at: String) -> ExportedVault {
+    ExportedVault {
+        uuid,
+        name: name.to_string(),
+        created_at,
+        cover_image: None,
+        color: None,
+        icon: None,
+        description: None,
+        items: Vec::new(),
+        collections: Vec::new(),
+        boards: Vec::new(),
+    }
+}
+
+/// RFC 3339 timestamp from an ISO string or a millisecond epoch; None for empty values and Joplin's 0
+fn timestamp(value: Option<&str>) -> Option<String> {
+    let value = value?.trim();
+    if let Ok(millis) = value.parse::<i64>() {
+        if millis <= 0 {
+            return None;
+        }
+        return chrono::DateTime::from_timestamp_millis(millis).map(|dt| dt.to_rfc3339());
+    }
+    chrono::DateTime::parse_from_rfc3339(value).ok().map(|dt| dt.with_timezone(&chrono::Utc).to_rfc3339())
+}
+
+// --- Joplin ---
+
+/// A Joplin item: optional title and body followed by a block of `key: value` properties
+struct JoplinItem {
+    title: String,
+    body: String,
+    props: HashMap<String, String>,
+}
+
+impl JoplinItem {
+    fn prop(&self, key: &str) -> Option<&str> {
+        self.props.get(key).map(|v| v.as_str()).filter(|v| !v.is_empty())
+    }
+
+    fn item_type(&self) -> i64 {
+        self.prop("type_").and_then(|t| t.parse().ok()).unwrap_or(0)
+    }
+
+    /// Joplin ids are 32 hex characters; as UUIDs they let a merge import find the same notes again
+    fn uuid(&self, key: &str) -> Option<String> {
+        self.prop(key).and_then(|id| uuid::Uuid::parse_str(id).ok()).map(|u| u.to_string())
+    }
+}
+
+fn is_prop_line(line: &str) -> bool {
+    line.split_once(':').is_some_and(|(key, _)| !key.is_empty() && key.chars().all(|c| c.is_ascii_lowercase() || c == '_'))
+}
+
+/// Parse one serialized item the way Joplin does: properties are read up from the end to the first blank line
+fn parse_joplin_item(text: &str) -> Option<JoplinItem> {
+    let text = text.replace("\r\n", "\n");
+    let lines: Vec<&str> = text.trim_end().lines().collect();
+    let mut props = HashMap::new();
+    let mut split = lines.len();
+    while split > 0 && !lines[split - 1].trim().is_empty() {
+        let line = lines[split - 1];
+        if !is_prop_line(line) {
+            return None;
+        }
+        let (key, value) = line.split_once(':').unwrap_or((line, ""));
+        props.insert(key.to_string(), value.trim().to_string());
+        split -= 1;
+    }
+    if !props.contains_key("type_") {
+        return None;
+    }
+    let head = lines[..split].join("\n");
+    let head = head.trim_end();
+    let (title, body) = match head.split_once("\n\n") {
+        Some((title, body)) => (title, body),
+        None => (head, ""),
+    };
+    Some(JoplinItem { title: title.trim().to_string(), body: body.to_string(), props })
+}
+
+/// Name and size of the next tar entry, or None at the end of the archive
+fn tar_header(header: &[u8; TAR_BLOCK]) -> Option<(String, u8, u64)> {
+    if header.iter().all(|b| *b == 0) {
+        return None;
+    }
+    let field = |range: std::ops::Range<usize>| {
+        let bytes = &header[range];
+        let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+        String::from_utf8_lossy(&bytes[..end]).to_string()
+    };
+    let size = u64::from_str_radix(field(124..136).trim(), 8).unwrap_or(0);
+    let prefix = field(345..500);
+    let name = field(0..100);
+    let name = if prefix.is_empty() { name } else { format!("{}/{}", prefix, name) };
+    Some((name, header[156], size))
+}
+
+/// Markdown files at the root of a JEX archive (an uncompressed tar); resources are skipped
+fn read_jex_items(path: &Path) -> Result<Vec<JoplinItem>, BrainboxError> {
+    let file = std::fs::File::open(path).map_err(|_| BrainboxError::NotFound(format!("File not found: {}", path.display())))?;
+    let mut reader = std::io::BufReader::new(file);
+    let mut items = Vec::new();
+    let mut header = [0u8; TAR_BLOCK];
+    loop {
+        if reader.read_exact(&mut header).is_err() {
+            break;
+        }
+        let Some((name, kind, size)) = tar_header(&header) else { break };
+        let padded = size.div_ceil(TAR_BLOCK as u64) * TAR_BLOCK as u64;
+        let is_file = kind == b'0' || kind == 0;
+        if is_file && !name.contains('/') && name.ends_with(".md") && size <= MAX_JOPLIN_ITEM_BYTES {
+            let mut data = vec![0u8; padded as usize];
+            reader.read_exact(&mut data).map_err(|_| BrainboxError::InvalidInput("The JEX archive is truncated".to_string()))?;
+            data.truncate(size as usize);
+            if let Some(item) = parse_joplin_item(&String::from_utf8_lossy(&data)) {
+                items.push(item);
+            }
+        } else {
+            std::io::copy(&mut (&mut reader).take(padded), &mut std::io::sink())?;
+        }
+    }
+    if items.is_empty() {
+        return Err(BrainboxError::InvalidInput("No Joplin notes found; is this a JEX export?".to_string()));
+    }
+    Ok(items)
+}
+
+/// Top-level notebook of a folder id, guarding against cycles in a damaged export
+fn joplin_root<'a>(folders: &HashMap<&'a str, &'a JoplinItem>, mut id: &'a str) -> Option<&'a str> {
+    for _ in 0..folders.len() {
+        let folder = folders.get(id)?;
+        match folder.prop("parent_id").filter(|p| folders.contains_key(p)) {
+            Some(parent) => id = parent,
+            None => return Some(id),
+        }
+    }
+    None
+}
+
+/// Convert a Joplin JEX export. Top-level notebooks become vaults and their sub-notebooks collections.
+pub fn parse_joplin(path: &Path) -> Result<ExportData, BrainboxError> {
+    let items = read_jex_items(path)?;
+    joplin_export(&items)
+}
+
+fn joplin_export<'a>(items: &'a [JoplinItem]) -> Result<ExportData, BrainboxError> {
+    let now = chrono::Utc::now().to_rfc3339();
+
+    let folders: HashMap<&'a str, &'a JoplinItem> = items
+        .iter()
+        .filter(|i| i.item_type() == JOPLIN_FOLDER)
+        .filter_map(|i| i.prop("id").map(|id| (id, i)))
+        .collect();
+    let tag_names: HashMap<&str, &str> = items
+        .iter()
+        .filter(|i| i.item_type() == JOPLIN_TAG)
+        .filter_map(|i| i.prop("id").map(|id| (id, i.title.as_str())))
+        .collect();
+    let mut note_tags: HashMap<&'a str, Vec<String>> = HashMap::new();
+    for link in items.iter().filter(|i| i.item_type() == JOPLIN_NOTE_TAG) {
+        if let (Some(note), Some(tag)) = (link.prop("note_id"), link.prop("tag_id").and_then(|t| tag_names.get(t))) {
+            note_tags.entry(note).or_default().push(tag.to_string());
+        }
+    }
+
+    let root_of = |id: &'a str| joplin_root(&folders, id);
+
+    let mut vaults: Vec<ExportedVault> = Vec::new();
+    let mut vault_index: HashMap<&str, usize> = HashMap::new();
+    for (id, folder) in &folders {
+        if folder.prop("parent_id").is_some_and(|p| folders.contains_key(p)) {
+            continue;
+        }
+        let name = if folder.title.is_empty() { JOPLIN_FALLBACK_VAULT } else { folder.title.as_str() };
+        let created_at = timestamp(folder.prop("created_time")).unwrap_or_else(|| now.clone());
+        vault_index.insert(id, vaults.len());
+        vaults.push(new_vault(folder.uuid("id"), name, created_at));
+    }
+    for (id, folder) in &folders {
+        let Some(parent) = folder.prop("parent_id").filter(|p| folders.contains_key(p)) else { continue };
+        let Some(index) = root_of(id).and_then(|root| vault_index.get(root)) else { continue };
+        let parent_uuid = (Some(parent) != root_of(id)).then(|| parent.to_string());
+        vaults[*index].collections.push(ExportedCollection {
+            uuid: id.to_string(),
+            parent_uuid,
+            name: folder.title.clone(),
+            sort_order: None,
+        });
+    }
+
+    let mut fallback: Option<usize> = None;
+    for note in items.iter().filter(|i| i.item_type() == JOPLIN_NOTE) {
+        if note.prop("is_conflict") == Some("1") {
+            continue;
+        }
+        let folder = note.prop("parent_id").filter(|p| folders.contains_key(p));
+        let index = match folder.and_then(root_of).and_then(|root| vault_index.get(root)) {
+            Some(index) => *index,
+            None => *fallback.get_or_insert_with(|| {
+                vaults.push(new_vault(None, JOPLIN_FALLBACK_VAULT, now.clone()));
+                vaults.len() - 1
+            }),
+        };
+        let created_at = timestamp(note.prop("user_created_time").or(note.prop("created_time"))).unwrap_or_else(|| now.clone());
+        let updated_at = timestamp(note.prop("user_updated_time").or(note.prop("updated_time"))).unwrap_or_else(|| created_at.clone());
+        let is_todo = note.prop("is_todo") == Some("1");
+        let mut content = note.body.clone();
+        if let Some(source) = note.prop("source_url") {
+            if !content.contains(source) {
+                content = if content.is_empty() { source.to_string() } else { format!("{}\n\n{}", content, source) };
+            }
+        }
+        let collection_uuid = folder.filter(|f| root_of(f) != Some(*f)).map(|f| f.to_string());
+        vaults[index].items.push(ExportedItem {
+            uuid: note.uuid("id"),
+            title: if note.title.is_empty() { "Untitled".to_string() } else { note.title.clone() },
+            content,
+            created_at,
+            updated_at,
+            image: None,
+            summary: None,
+            collection_uuid,
+            item_type: is_todo.then(|| "task".to_string()),
+            due_at: if is_todo { timestamp(note.prop("todo_due")) } else { None },
+            completed_at: if is_todo { timestamp(note.prop("todo_completed")) } else { None },
+            read_at: None,
+            color: None,
+            icon: None,
+            tags: note.prop("id").and_then(|id| note_tags.remove(id)).unwrap_or_default(),
+            board_placements: Vec::new(),
+            annotations: Vec::new(),
+        });
+    }
+    Ok(export_data(vaults))
+}
+
+// --- Standard Notes ---
+
+#[derive(Deserialize)]
+struct StandardNotesBackup {
+    #[serde(default)]
+    items: Vec<StandardNotesItem>,
+}
+
+#[derive(Deserialize)]
+struct StandardNotesItem {
+    uuid: String,
+    content_type: String,
+    /// Encrypted backups carry a string here instead of an object
+    #[serde(default)]
+    content: serde_json::Value,
+    #[serde(default)]
+    created_at: Option<String>,
+    #[serde(default)]
+    updated_at: Option<String>,
+    #[serde(default)]
+    deleted: bool,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct StandardNotesContent {
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    text: Option<String>,
+    #[serde(default)]
+    references: Vec<StandardNotesReference>,
+    #[serde(default)]
+    trashed: bool,
+    #[serde(default)]
+    app_data: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct StandardNotesReference {
+    uuid: String,
+}
+
+/// Convert a Standard Notes decrypted backup (.txt or .json). All notes go into one vault; tags stay tags.
+pub fn parse_standard_notes(path: &Path) -> Result<ExportData, BrainboxError> {
+    let metadata = std::fs::metadata(path).map_err(|_| BrainboxError::NotFound(format!("File not found: {}", path.display())))?;
+    if metadata.len() > MAX_BACKUP_BYTES {
+        return Err(BrainboxError::InvalidInput("Backup file is too large".to_string()));
+    }
+    let backup: StandardNotesBackup = serde_json::from_str(&std::fs::read_to_string(path)?)
+        .map_err(|e| BrainboxError::InvalidInput(format!("Not a Standard Notes backup: {}", e)))?;
+    if backup.items.iter().any(|i| i.content.is_string()) {
+        return Err(BrainboxError::InvalidInput(
+            "This backup is encrypted; export a decrypted backup from Standard Notes instead".to_string(),
+        ));
+    }
+    let content = |item: &StandardNotesItem| -> StandardNotesContent {
+        serde_json::from_value(item.content.clone()).unwrap_or_default()
+    };
+
+    let mut note_tags: HashMap<String, Vec<String>> = HashMap::new();
+    for tag in backup.items.iter().filter(|i| i.content_type == "Tag" && !i.deleted) {
+        let tag_content = content(tag);
+        let Some(title) = tag_content.title.filter(|t| !t.trim().is_empty()) else { continue };
+        for reference in tag_content.references {
+            note_tags.entry(reference.uuid).or_default().push(title.clone());
+        }
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    let mut vault = new_vault(None, STANDARD_NOTES_VAULT, now.clone());
+    for note in backup.items.iter().filter(|i| i.content_type == "Note" && !i.deleted) {
+        let note_content = content(note);
+        if note_content.trashed {
+            continue;
+        }
+        // The client keeps the user-visible edit time in app data; the server timestamp changes on every sync
+        let client_updated = note_content
+            .app_data
+            .pointer("/org.standardnotes.sn/client_updated_at")
+            .and_then(|v| v.as_str());
+        let created_at = timestamp(note.created_at.as_deref()).unwrap_or_else(|| now.clone());
+        let updated_at = timestamp(client_updated.or(note.updated_at.as_deref())).unwrap_or_else(|| created_at.clone());
+        let title = note_content.title.filter(|t| !t.trim().is_empty()).unwrap_or_else(|| "Untitled".to_string());
+        vault.items.push(ExportedItem {
+            uuid: uuid::Uuid::parse_str(&note.uuid).ok().map(|u| u.to_string()),
+            title,
+            content: note_content.text.unwrap_or_default(),
+            created_at,
+            updated_at,
+            image: None,
+            summary: None,
+            collection_uuid: None,
+            item_type: Some("note".to_string()),
+            due_at: None,
+            completed_at: None,
+            read_at: None,
+            color: None,
+            icon: None,
+            tags: note_tags.remove(&note.uuid).unwrap_or_default(),
+            board_placements: Vec::new(),
+            annotations: Vec::new(),
+        });
+    }
+    if vault.items.is_empty() {
+        return Err(BrainboxError::InvalidInput("No notes found in the backup".to_string()));
+    }
+    Ok(export_data(vec![vault]))
+}
+
+// --- Apple Notes / OneNote exports ---
+
+fn file_time(time: std::io::Result<std::time::SystemTime>) -> Option<String> {
+    time.ok().map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339())
+}
+
+/// Image referenced from an exported HTML note: data URIs and files inside the export are cached,
+/// remote images are linked as they are. Paths escaping the export folder are ignored.
+fn resolve_html_image(root: &Path, note_dir: &Path, src: &str) -> Option<String> {
+    use base64::Engine;
+    let src = src.trim();
+    if src.starts_with("http://") || src.starts_with("https://") {
+        return Some(src.to_string());
+    }
+    let bytes = if let Some(data) = src.strip_prefix("data:") {
+        let (_, encoded) = data.split_once(";base64,")?;
+        base64::engine::general_purpose::STANDARD.decode(encoded.trim()).ok()?
+    } else {
+        let relative = urlencoding::decode(src.strip_prefix("file://").unwrap_or(src)).ok()?.to_string();
+        let path = note_dir.join(relative).canonicalize().ok()?;
+        if !path.starts_with(root) || std::fs::metadata(&path).ok()?.len() > MAX_NOTE_FILE_BYTES {
+            return None;
+        }
+        std::fs::read(path).ok()?
+    };
+    cache_image(&bytes)
+}
+
+fn cache_image(bytes: &[u8]) -> Option<String> {
+    match image_cache::cache_image_bytes(bytes) {
+        Ok(file_name) => Some(image_cache::asset_url(&file_name)),
+        Err(e) => {
+            tracing::warn!("Skipping an imported image: {}", e);
+            None
+        }
+    }
+}
+
+/// One exported note file as an item; None for files that aren't notes
+fn convert_note_file(root: &Path, path: &Path) -> Result<Option<ExportedItem>, BrainboxError> {
+    let ext = path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).unwrap_or_default();
+    if !NOTE_FILE_EXTENSIONS.contains(&ext.as_str()) {
+        return Ok(None);
+    }
+    let metadata = std::fs::metadata(path)?;
+    if metadata.len() > MAX_NOTE_FILE_BYTES {
+        tracing::warn!("Skipping {}: larger than {} MB", path.display(), MAX_NOTE_FILE_BYTES / (1024 * 1024));
+        return Ok(None);
+    }
+    let bytes = std::fs::read(path)?;
+    let note_dir = path.parent().unwrap_or(root);
+    let converted = match ext.as_str() {
+        "html" | "htm" => rich_text::html_to_markdown(&String::from_utf8_lossy(&bytes), &mut |src| {
+            resolve_html_image(root, note_dir, src)
+        }),
+        "docx" => rich_text::docx_to_markdown(&bytes, &mut |_, data| cache_image(data))?,
+        _ => rich_text::Converted {
+            title: None,
+            markdown: String::from_utf8_lossy(bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(&bytes)).trim().to_string(),
+            images: Vec::new(),
+        },
+    };
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("Untitled").to_string();
+    let now = chrono::Utc::now().to_rfc3339();
+    let updated_at = file_time(metadata.modified()).unwrap_or_else(|| now.clone());
+    let created_at = file_time(metadata.created()).filter(|c| *c <= updated_at).unwrap_or_else(|| updated_at.clone());
+    Ok(Some(ExportedItem {
+        uuid: None,
+        // Exporters name files after the note, which is more reliable than an HTML <title>
+        title: if ext == "docx" { converted.title.unwrap_or(stem) } else { stem },
+        content: converted.markdown,
+        created_at,
+        updated_at,
+        image: converted.images.into_iter().next(),
+        summary: None,
+        collection_uuid: None,
+        item_type: Some("note".to_string()),
+        due_at: None,
+        completed_at: None,
+        read_at: None,
+        color: None,
+        icon: None,
+        tags: Vec::new(),
+        board_placements: Vec::new(),
+        annotations: Vec::new(),
+    }))
+}
+
+fn sorted_entries(dir: &Path) -> Result<Vec<PathBuf>, BrainboxError> {
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| !p.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with('.')))
+        .collect();
+    entries.sort();
+    Ok(entries)
+}
+
+/// Add the notes in `dir` to `vault`, turning subfolders into collections below `collection`
+fn collect_folder(root: &Path, dir: &Path, vault: &mut ExportedVault, collection: Option<String>, depth: usize) -> Result<(), BrainboxError> {
+    for path in sorted_entries(dir)? {
+        if path.is_dir() {
+            if depth >= MAX_FOLDER_DEPTH {
+                continue;
+            }
+            let uuid = uuid::Uuid::new_v4().to_string();
+            let before = vault.items.len();
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("Folder").to_string();
+            vault.collections.push(ExportedCollection { uuid: uuid.clone(), parent_uuid: collection.clone(), name, sort_order: None });
+            collect_folder(root, &path, vault, Some(uuid.clone()), depth + 1)?;
+            // Attachment folders hold no notes of their own
+            if vault.items.len() == before && !vault.collections.iter().any(|c| c.parent_uuid.as_ref() == Some(&uuid)) {
+                vault.collections.retain(|c| c.uuid != uuid);
+            }
+            continue;
+        }
+        match convert_note_file(root, &path) {
+            Ok(Some(mut item)) => {
+                item.collection_uuid = collection.clone();
+                vault.items.push(item);
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Skipping {}: {}", path.display(), e),
+        }
+    }
+    Ok(())
+}
+
+/// Convert an Apple Notes or OneNote export: a folder whose subfolders are notebooks (each becoming a vault)
+/// holding HTML, .docx, markdown or text notes, or a single exported note file
+pub fn parse_notes_folder(path: &Path) -> Result<ExportData, BrainboxError> {
+    let path = path.canonicalize().map_err(|_| BrainboxError::NotFound(format!("Not found: {}", path.display())))?;
+    let now = chrono::Utc::now().to_rfc3339();
+    let folder_name = |p: &Path| p.file_name().and_then(|n| n.to_str()).unwrap_or("Imported notes").to_string();
+
+    let mut vaults = Vec::new();
+    if path.is_file() {
+        let root = path.parent().map(Path::to_path_buf).unwrap_or_else(|| path.clone());
+        let mut vault = new_vault(None, &folder_name(&root), now);
+        vault.items.extend(convert_note_file(&root, &path)?);
+        vaults.push(vault);
+    } else {
+        // Notes directly in the export folder go into a vault named after it
+        let mut loose = new_vault(None, &folder_name(&path), now.clone());
+        for entry in sorted_entries(&path)? {
+            if entry.is_dir() {
+                let mut vault = new_vault(None, &folder_name(&entry), now.clone());
+                collect_folder(&path, &entry, &mut vault, None, 1)?;
+                vaults.push(vault);
+            } else {
+                match convert_note_file(&path, &entry) {
+                    Ok(item) => loose.items.extend(item),
+                    Err(e) => tracing::warn!("Skipping {}: {}", entry.display(), e),
+                }
+            }
+        }
+        vaults.insert(0, loose);
+    }
+    vaults.retain(|v| !v.items.is_empty());
+    if vaults.is_empty() {
+        return Err(BrainboxError::InvalidInput("No HTML, Word, markdown or text notes found".to_string()));
+    }
+    Ok(export_data(vaults))
+}
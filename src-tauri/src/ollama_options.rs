@@ -0,0 +1,153 @@
+// ollama_options.rs - Generation options for Ollama requests for brainbox
+// Temperature, sampling, context size, stop sequences and keep-alive are passed through to Ollama instead of its
+// built-in defaults. Each use case (the assistant, prompt library, drafts, digests, entity extraction, image
+// labels) has saved defaults; a request may override any of them.
+
+use rusqlite::Connection;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::error::BrainboxError;
+use crate::settings;
+
+const KEY_PREFIX: &str = "ollama.options.";
+pub const USE_CASES: &[&str] = &["generate", "prompts", "drafts", "digest", "entities", "image_labels"];
+
+lazy_static! {
+    /// A Go duration such as 90s, 10m or 1h30m
+    static ref DURATION: Regex = Regex::new(r"^-?(\d+(\.\d+)?(ms|s|m|h))+$").unwrap();
+}
+
+/// Most stop sequences one request may set
+const MAX_STOP_SEQUENCES: usize = 8;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct GenerationOptions {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<u32>,
+    /// Context window Ollama allocates; prompts are trimmed to fit it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub num_ctx: Option<u32>,
+    /// Most tokens generated; -1 for no limit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub num_predict: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repeat_penalty: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stop: Vec<String>,
+    /// How long the model stays loaded after the request: a duration such as "10m", seconds, or "-1" for good.
+    /// Sent beside the options rather than in them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keep_alive: Option<String>,
+}
+
+/// The parts of a request GenerationOptions fill, flattened into each request body
+#[derive(Serialize, Default)]
+pub struct RequestOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_alive: Option<serde_json::Value>,
+}
+
+impl GenerationOptions {
+    /// `self` with the fields it leaves unset taken from `defaults`
+    pub fn or(self, defaults: &GenerationOptions) -> GenerationOptions {
+        GenerationOptions {
+            temperature: self.temperature.or(defaults.temperature),
+            top_p: self.top_p.or(defaults.top_p),
+            top_k: self.top_k.or(defaults.top_k),
+            num_ctx: self.num_ctx.or(defaults.num_ctx),
+            num_predict: self.num_predict.or(defaults.num_predict),
+            repeat_penalty: self.repeat_penalty.or(defaults.repeat_penalty),
+            seed: self.seed.or(defaults.seed),
+            stop: if self.stop.is_empty() { defaults.stop.clone() } else { self.stop },
+            keep_alive: self.keep_alive.or_else(|| defaults.keep_alive.clone()),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), BrainboxError> {
+        if self.temperature.is_some_and(|t| !(0.0..=2.0).contains(&t)) {
+            return Err(BrainboxError::InvalidInput("Temperature must be between 0 and 2".to_string()));
+        }
+        if self.top_p.is_some_and(|p| !(0.0..=1.0).contains(&p)) {
+            return Err(BrainboxError::InvalidInput("top_p must be between 0 and 1".to_string()));
+        }
+        if self.num_ctx.is_some_and(|n| n < 256) {
+            return Err(BrainboxError::InvalidInput("num_ctx must be at least 256".to_string()));
+        }
+        if self.num_predict.is_some_and(|n| n < -1 || n == 0) {
+            return Err(BrainboxError::InvalidInput("num_predict must be positive, or -1 for no limit".to_string()));
+        }
+        if self.stop.len() > MAX_STOP_SEQUENCES || self.stop.iter().any(|s| s.is_empty()) {
+            return Err(BrainboxError::InvalidInput(format!("Use up to {} non-empty stop sequences", MAX_STOP_SEQUENCES)));
+        }
+        if let Some(keep_alive) = &self.keep_alive {
+            keep_alive_value(keep_alive)?;
+        }
+        Ok(())
+    }
+
+    pub fn request(&self) -> RequestOptions {
+        let options = serde_json::to_value(GenerationOptions { keep_alive: None, ..self.clone() })
+            .ok()
+            .filter(|options| options.as_object().is_some_and(|o| !o.is_empty()));
+        RequestOptions {
+            options,
+            keep_alive: self.keep_alive.as_deref().and_then(|k| keep_alive_value(k).ok()),
+        }
+    }
+}
+
+/// Ollama takes keep_alive as seconds (a number) or a Go duration string
+fn keep_alive_value(keep_alive: &str) -> Result<serde_json::Value, BrainboxError> {
+    let keep_alive = keep_alive.trim();
+    if let Ok(seconds) = keep_alive.parse::<i64>() {
+        return Ok(serde_json::Value::from(seconds));
+    }
+    if !DURATION.is_match(keep_alive) {
+        return Err(BrainboxError::InvalidInput(format!("Invalid keep_alive: {} (use e.g. 30s, 10m, 1h or -1)", keep_alive)));
+    }
+    Ok(serde_json::Value::from(keep_alive))
+}
+
+fn check_use_case(use_case: &str) -> Result<(), BrainboxError> {
+    if !USE_CASES.contains(&use_case) {
+        return Err(BrainboxError::InvalidInput(format!("Unknown use case: {}", use_case)));
+    }
+    Ok(())
+}
+
+/// Saved defaults for `use_case`; nothing saved means Ollama's own
+pub fn defaults(conn: &Connection, use_case: &str) -> Result<GenerationOptions, BrainboxError> {
+    check_use_case(use_case)?;
+    Ok(settings::get(conn, &format!("{}{}", KEY_PREFIX, use_case))?
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default())
+}
+
+pub fn set_defaults(conn: &Connection, use_case: &str, options: &GenerationOptions) -> Result<(), BrainboxError> {
+    check_use_case(use_case)?;
+    options.validate()?;
+    let key = format!("{}{}", KEY_PREFIX, use_case);
+    if *options == GenerationOptions::default() {
+        settings::delete(conn, &key)?;
+    } else {
+        settings::set(conn, &key, &serde_json::to_string(options)?)?;
+    }
+    Ok(())
+}
+
+/// `explicit` options for one request over the saved defaults for `use_case`
+pub fn resolve(conn: &Connection, use_case: &str, explicit: Option<GenerationOptions>) -> Result<GenerationOptions, BrainboxError> {
+    let options = explicit.unwrap_or_default().or(&defaults(conn, use_case)?);
+    options.validate()?;
+    Ok(options)
+}
@@ -0,0 +1,474 @@
+// os_search.rs - Optional entries for brainbox items in the OS search index
+// When turned on, every item of a vault without a password gets a small stub in Spotlight (macOS) or in a folder
+// Windows Search indexes, holding its title, its vault and a brainbox:// link back to it; never its content.
+// Password-protected vaults are left out, since the stubs outlive the session that unlocked them. Stubs are kept
+// in step with the items whenever the frontend reports a change, using a manifest of what has been written.
+
+use rusqlite::Connection;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Listener};
+
+use crate::error::BrainboxError;
+use crate::settings;
+
+pub const ENABLED_KEY: &str = "os_search.enabled";
+/// Item uuid to the title and vault name its stub was written with
+const MANIFEST_FILE: &str = "os-search-manifest.json";
+
+/// A resync is running
+static SYNCING: AtomicBool = AtomicBool::new(false);
+/// Items changed while it ran, so it goes round again
+static DIRTY: AtomicBool = AtomicBool::new(false);
+
+/// One item as the OS index sees it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub uuid: String,
+    pub title: String,
+    pub vault_name: String,
+}
+
+#[cfg_attr(not(any(target_os = "windows", target_os = "macos")), allow(dead_code))]
+impl Entry {
+    fn link(&self) -> String {
+        format!("{}item/{}", crate::deeplink::SCHEME_PREFIX, self.uuid)
+    }
+
+    fn display_title(&self) -> &str {
+        let title = self.title.trim();
+        if title.is_empty() {
+            "Untitled"
+        } else {
+            title
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct OsSearchStatus {
+    /// This platform has an index brainbox can write to
+    pub supported: bool,
+    pub enabled: bool,
+    /// Items with a stub in the index
+    pub indexed: usize,
+    /// Where the stubs are, on platforms that keep them as files
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+}
+
+fn manifest_path() -> Option<PathBuf> {
+    Some(crate::data_dir::root()?.join("brainbox").join(MANIFEST_FILE))
+}
+
+/// Stubs written so far, as entries keyed by uuid
+fn load_manifest() -> BTreeMap<String, Entry> {
+    let stored: BTreeMap<String, (String, String)> = manifest_path()
+        .and_then(|path| std::fs::read(path).ok())
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default();
+    stored
+        .into_iter()
+        .map(|(uuid, (title, vault_name))| (uuid.clone(), Entry { uuid, title, vault_name }))
+        .collect()
+}
+
+fn save_manifest(entries: &BTreeMap<String, Entry>) -> Result<(), BrainboxError> {
+    let path = manifest_path().ok_or("Failed to get app data dir")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let stored: BTreeMap<&str, (&str, &str)> = entries
+        .values()
+        .map(|entry| (entry.uuid.as_str(), (entry.title.as_str(), entry.vault_name.as_str())))
+        .collect();
+    std::fs::write(path, serde_json::to_vec(&stored)?)?;
+    Ok(())
+}
+
+/// Every live item of a vault without a password
+fn wanted(conn: &Connection) -> Result<BTreeMap<String, Entry>, BrainboxError> {
+    crate::vault::Vault::create_table(conn)?;
+    crate::vault::VaultItem::create_table(conn)?;
+    let mut stmt = conn.prepare(
+        "SELECT i.uuid, i.title, v.name FROM vault_items i JOIN vaults v ON v.id = i.vault_id
+         WHERE i.deleted_at IS NULL AND v.deleted_at IS NULL AND v.has_password = 0 AND i.uuid IS NOT NULL",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(Entry { uuid: row.get(0)?, title: row.get(1)?, vault_name: row.get(2)? })
+    })?;
+    let mut entries = BTreeMap::new();
+    for entry in rows {
+        let entry = entry?;
+        entries.insert(entry.uuid.clone(), entry);
+    }
+    Ok(entries)
+}
+
+/// Bring the index in line with the items: write new and changed stubs and remove those of items that are gone
+pub fn sync(conn: &Connection) -> Result<usize, BrainboxError> {
+    let wanted = wanted(conn)?;
+    let mut indexed = load_manifest();
+    let stale: Vec<Entry> = indexed
+        .values()
+        .filter(|entry| wanted.get(&entry.uuid) != Some(*entry))
+        .cloned()
+        .collect();
+    let fresh: Vec<Entry> = wanted.values().filter(|entry| indexed.get(&entry.uuid) != Some(*entry)).cloned().collect();
+    if stale.is_empty() && fresh.is_empty() {
+        return Ok(indexed.len());
+    }
+    platform::remove(&stale)?;
+    for entry in &stale {
+        indexed.remove(&entry.uuid);
+    }
+    platform::index(&fresh)?;
+    for entry in fresh {
+        indexed.insert(entry.uuid.clone(), entry);
+    }
+    save_manifest(&indexed)?;
+    tracing::debug!("OS search index holds {} brainbox items", indexed.len());
+    Ok(indexed.len())
+}
+
+/// Remove every stub brainbox has written
+fn clear() -> Result<(), BrainboxError> {
+    platform::clear()?;
+    if let Some(path) = manifest_path().filter(|path| path.exists()) {
+        std::fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+pub fn status(conn: &Connection) -> Result<OsSearchStatus, BrainboxError> {
+    Ok(OsSearchStatus {
+        supported: platform::SUPPORTED,
+        enabled: settings::get_bool(conn, ENABLED_KEY)?,
+        indexed: load_manifest().len(),
+        location: platform::location().map(|path| path.to_string_lossy().to_string()),
+    })
+}
+
+pub fn set_enabled(conn: &Connection, enabled: bool) -> Result<OsSearchStatus, BrainboxError> {
+    if enabled && !platform::SUPPORTED {
+        return Err(BrainboxError::InvalidInput("OS search integration isn't available on this platform".to_string()));
+    }
+    settings::set_bool(conn, ENABLED_KEY, enabled)?;
+    if enabled {
+        sync(conn)?;
+    } else {
+        clear()?;
+    }
+    status(conn)
+}
+
+/// Resync on a background thread; changes arriving during a run are picked up by one more run
+fn schedule() {
+    DIRTY.store(true, Ordering::SeqCst);
+    if SYNCING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    std::thread::spawn(|| {
+        while DIRTY.swap(false, Ordering::SeqCst) {
+            let result = crate::db::open().and_then(|conn| {
+                if settings::get_bool(&conn, ENABLED_KEY)? {
+                    sync(&conn)?;
+                }
+                Ok(())
+            });
+            if let Err(e) = result {
+                tracing::warn!("Failed to update the OS search index: {}", e);
+            }
+        }
+        SYNCING.store(false, Ordering::SeqCst);
+    });
+}
+
+/// Follow item changes and catch up on anything missed while the app was closed
+pub fn init(app: &AppHandle) {
+    platform::install(app);
+    // The frontend emits these after creating, editing or deleting items and vaults
+    for event in ["items-changed", "vaults-changed"] {
+        app.listen_any(event, |_| schedule());
+    }
+    schedule();
+}
+
+/// Windows: an Internet Shortcut per item in a folder under the user profile, which Windows Search indexes
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::path::PathBuf;
+    use tauri::AppHandle;
+
+    use super::Entry;
+    use crate::error::BrainboxError;
+
+    pub const SUPPORTED: bool = true;
+    const FOLDER: &str = "Brainbox Items";
+    /// Longest title kept in a file name
+    const MAX_NAME_CHARS: usize = 80;
+
+    pub fn install(_app: &AppHandle) {}
+
+    pub fn location() -> Option<PathBuf> {
+        Some(dirs::home_dir()?.join(FOLDER))
+    }
+
+    /// `<title> (<uuid prefix>).url`, the uuid keeping items of the same title apart
+    fn file_name(entry: &Entry) -> String {
+        let title: String = entry
+            .display_title()
+            .chars()
+            .map(|c| if c.is_control() || r#"<>:"/\|?*"#.contains(c) { ' ' } else { c })
+            .take(MAX_NAME_CHARS)
+            .collect();
+        let title = title.trim().trim_end_matches('.');
+        format!("{} ({}).url", title, entry.uuid.chars().take(8).collect::<String>())
+    }
+
+    pub fn index(entries: &[Entry]) -> Result<(), BrainboxError> {
+        let Some(folder) = location() else { return Err("Failed to find the home folder".into()) };
+        std::fs::create_dir_all(&folder)?;
+        for entry in entries {
+            let stub = format!("[InternetShortcut]\r\nURL={}\r\n", entry.link());
+            std::fs::write(folder.join(file_name(entry)), stub)?;
+        }
+        Ok(())
+    }
+
+    pub fn remove(entries: &[Entry]) -> Result<(), BrainboxError> {
+        let Some(folder) = location() else { return Ok(()) };
+        for entry in entries {
+            let path = folder.join(file_name(entry));
+            if path.exists() {
+                std::fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn clear() -> Result<(), BrainboxError> {
+        match location() {
+            Some(folder) if folder.exists() => Ok(std::fs::remove_dir_all(folder)?),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// macOS: Core Spotlight items in brainbox's own domain; choosing one opens the item through a deep link
+#[cfg(target_os = "macos")]
+mod platform {
+    use block2::RcBlock;
+    use objc2::rc::{autoreleasepool, Allocated, Retained};
+    use objc2::runtime::{AnyClass, AnyObject, Bool, Sel};
+    use objc2::{msg_send, sel};
+    use std::ffi::{CStr, CString};
+    use std::path::PathBuf;
+    use std::sync::OnceLock;
+    use tauri::AppHandle;
+
+    use super::Entry;
+    use crate::error::BrainboxError;
+
+    #[link(name = "CoreSpotlight", kind = "framework")]
+    extern "C" {}
+
+    pub const SUPPORTED: bool = true;
+    const DOMAIN: &str = "brainbox.items";
+    /// NSUserActivity type and userInfo key Spotlight uses when a result is opened
+    const SPOTLIGHT_ACTIVITY: &str = "com.apple.corespotlightitem";
+    const ACTIVITY_IDENTIFIER_KEY: &str = "kCSSearchableItemActivityIdentifier";
+
+    static APP: OnceLock<AppHandle> = OnceLock::new();
+
+    fn unavailable() -> BrainboxError {
+        BrainboxError::Other("Core Spotlight is unavailable".to_string())
+    }
+
+    fn ns_string(text: &str) -> Result<*mut AnyObject, BrainboxError> {
+        let class = AnyClass::get(c"NSString").ok_or_else(unavailable)?;
+        let text = CString::new(text.replace('\0', "")).map_err(|_| unavailable())?;
+        Ok(unsafe { msg_send![class, stringWithUTF8String: text.as_ptr()] })
+    }
+
+    fn rust_string(string: *mut AnyObject) -> Option<String> {
+        if string.is_null() {
+            return None;
+        }
+        let utf8: *const std::ffi::c_char = unsafe { msg_send![string, UTF8String] };
+        (!utf8.is_null()).then(|| unsafe { CStr::from_ptr(utf8) }.to_string_lossy().to_string())
+    }
+
+    fn search_index() -> Result<*mut AnyObject, BrainboxError> {
+        let class = AnyClass::get(c"CSSearchableIndex").ok_or_else(unavailable)?;
+        Ok(unsafe { msg_send![class, defaultSearchableIndex] })
+    }
+
+    fn ns_array(objects: impl IntoIterator<Item = *mut AnyObject>) -> Result<Retained<AnyObject>, BrainboxError> {
+        let array: Retained<AnyObject> =
+            unsafe { msg_send![AnyClass::get(c"NSMutableArray").ok_or_else(unavailable)?, new] };
+        for object in objects {
+            let _: () = unsafe { msg_send![&array, addObject: object] };
+        }
+        Ok(array)
+    }
+
+    /// Index calls finish asynchronously; failures are only logged
+    fn completion() -> RcBlock<dyn Fn(*mut AnyObject)> {
+        RcBlock::new(|error: *mut AnyObject| {
+            if !error.is_null() {
+                let description: *mut AnyObject = unsafe { msg_send![error, localizedDescription] };
+                tracing::warn!("Spotlight update failed: {}", rust_string(description).unwrap_or_default());
+            }
+        })
+    }
+
+    fn searchable_item(entry: &Entry) -> Result<Retained<AnyObject>, BrainboxError> {
+        let set_class = AnyClass::get(c"CSSearchableItemAttributeSet").ok_or_else(unavailable)?;
+        let item_class = AnyClass::get(c"CSSearchableItem").ok_or_else(unavailable)?;
+        unsafe {
+            let attributes: Allocated<AnyObject> = msg_send![set_class, alloc];
+            let attributes: Retained<AnyObject> =
+                msg_send![attributes, initWithItemContentType: ns_string("public.text")?];
+            let _: () = msg_send![&attributes, setTitle: ns_string(entry.display_title())?];
+            let _: () = msg_send![&attributes, setContentDescription: ns_string(&entry.vault_name)?];
+            let _: () = msg_send![&attributes, setContentURL: ns_url(&entry.link())?];
+            let item: Allocated<AnyObject> = msg_send![item_class, alloc];
+            Ok(msg_send![
+                item,
+                initWithUniqueIdentifier: ns_string(&entry.uuid)?,
+                domainIdentifier: ns_string(DOMAIN)?,
+                attributeSet: &*attributes
+            ])
+        }
+    }
+
+    fn ns_url(url: &str) -> Result<*mut AnyObject, BrainboxError> {
+        let class = AnyClass::get(c"NSURL").ok_or_else(unavailable)?;
+        Ok(unsafe { msg_send![class, URLWithString: ns_string(url)?] })
+    }
+
+    pub fn location() -> Option<PathBuf> {
+        None
+    }
+
+    pub fn index(entries: &[Entry]) -> Result<(), BrainboxError> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        autoreleasepool(|_| {
+            let items = entries.iter().map(searchable_item).collect::<Result<Vec<_>, _>>()?;
+            let array = ns_array(items.iter().map(|item| Retained::as_ptr(item) as *mut AnyObject))?;
+            let handler = completion();
+            let _: () = unsafe { msg_send![search_index()?, indexSearchableItems: &*array, completionHandler: &*handler] };
+            Ok(())
+        })
+    }
+
+    pub fn remove(entries: &[Entry]) -> Result<(), BrainboxError> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        autoreleasepool(|_| {
+            let ids = entries.iter().map(|entry| ns_string(&entry.uuid)).collect::<Result<Vec<_>, _>>()?;
+            let array = ns_array(ids)?;
+            let handler = completion();
+            let _: () = unsafe {
+                msg_send![search_index()?, deleteSearchableItemsWithIdentifiers: &*array, completionHandler: &*handler]
+            };
+            Ok(())
+        })
+    }
+
+    pub fn clear() -> Result<(), BrainboxError> {
+        autoreleasepool(|_| {
+            let domains = ns_array([ns_string(DOMAIN)?])?;
+            let handler = completion();
+            let _: () = unsafe {
+                msg_send![search_index()?, deleteSearchableItemsWithDomainIdentifiers: &*domains, completionHandler: &*handler]
+            };
+            Ok(())
+        })
+    }
+
+    /// `application:continueUserActivity:restorationHandler:`, added to the app delegate
+    extern "C-unwind" fn continue_activity(
+        _this: &AnyObject,
+        _cmd: Sel,
+        _application: *mut AnyObject,
+        activity: *mut AnyObject,
+        _restoration: *mut AnyObject,
+    ) -> Bool {
+        let uuid = autoreleasepool(|_| -> Option<String> {
+            let activity_type: *mut AnyObject = unsafe { msg_send![activity, activityType] };
+            if rust_string(activity_type)? != SPOTLIGHT_ACTIVITY {
+                return None;
+            }
+            let user_info: *mut AnyObject = unsafe { msg_send![activity, userInfo] };
+            let key = ns_string(ACTIVITY_IDENTIFIER_KEY).ok()?;
+            rust_string(unsafe { msg_send![user_info, objectForKey: key] })
+        });
+        match (APP.get(), uuid) {
+            (Some(app), Some(uuid)) => {
+                crate::deeplink::handle_args(app, &[format!("{}item/{}", crate::deeplink::SCHEME_PREFIX, uuid)], None);
+                Bool::YES
+            }
+            _ => Bool::NO,
+        }
+    }
+
+    /// Open items chosen in Spotlight; must run on the main thread, after the app delegate is in place
+    pub fn install(app: &AppHandle) {
+        if APP.set(app.clone()).is_err() {
+            return;
+        }
+        let Some(ns_app) = AnyClass::get(c"NSApplication") else { return };
+        unsafe {
+            let shared: *mut AnyObject = msg_send![ns_app, sharedApplication];
+            let delegate: *mut AnyObject = msg_send![shared, delegate];
+            let Some(delegate) = delegate.as_ref() else { return };
+            let handler: extern "C-unwind" fn(&AnyObject, Sel, *mut AnyObject, *mut AnyObject, *mut AnyObject) -> Bool =
+                continue_activity;
+            let added = objc2::ffi::class_addMethod(
+                delegate.class() as *const AnyClass as *mut AnyClass,
+                sel!(application:continueUserActivity:restorationHandler:),
+                std::mem::transmute::<_, objc2::runtime::Imp>(handler),
+                c"c@:@@@?".as_ptr(),
+            );
+            if !added.as_bool() {
+                tracing::warn!("The app delegate already handles user activities; Spotlight results won't open items");
+            }
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use std::path::PathBuf;
+    use tauri::AppHandle;
+
+    use super::Entry;
+    use crate::error::BrainboxError;
+
+    pub const SUPPORTED: bool = false;
+
+    pub fn install(_app: &AppHandle) {}
+
+    pub fn location() -> Option<PathBuf> {
+        None
+    }
+
+    pub fn index(_entries: &[Entry]) -> Result<(), BrainboxError> {
+        Ok(())
+    }
+
+    pub fn remove(_entries: &[Entry]) -> Result<(), BrainboxError> {
+        Ok(())
+    }
+
+    pub fn clear() -> Result<(), BrainboxError> {
+        Ok(())
+    }
+}
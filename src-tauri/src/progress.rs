@@ -0,0 +1,98 @@
+// progress.rs - Progress and cancellation of long imports and exports for brainbox
+// Loops over many items report "item N of M" as events so the UI doesn't look frozen. The frontend may pass an
+// operation id; `cancel` flags it, the loop stops at its next check and the caller rolls back what it wrote.
+
+use lazy_static::lazy_static;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter};
+
+use crate::error::BrainboxError;
+
+pub const IMPORT_EVENT: &str = "import-progress";
+pub const EXPORT_EVENT: &str = "export-progress";
+/// Progress is emitted every this many items, plus the last one
+const EMIT_EVERY: usize = 25;
+
+lazy_static! {
+    /// Running operations by id, and whether they were asked to stop
+    static ref OPERATIONS: Mutex<HashMap<String, bool>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Serialize, Clone)]
+#[allow(non_snake_case)]
+struct ProgressEvent {
+    operationId: Option<String>,
+    /// Name of the vault being processed
+    vault: String,
+    done: usize,
+    total: usize,
+}
+
+/// A running import or export. Dropping it forgets the operation id.
+pub struct Operation {
+    app: Option<AppHandle>,
+    event: &'static str,
+    id: Option<String>,
+}
+
+impl Operation {
+    pub fn new(app: &AppHandle, event: &'static str, id: Option<String>) -> Operation {
+        if let Some(id) = &id {
+            if let Ok(mut operations) = OPERATIONS.lock() {
+                operations.insert(id.clone(), false);
+            }
+        }
+        Operation { app: Some(app.clone()), event, id }
+    }
+
+    /// For background runs nobody watches or cancels, like sync on close
+    pub fn silent() -> Operation {
+        Operation { app: None, event: "", id: None }
+    }
+
+    /// Report item `done` of `total` in `vault`
+    pub fn progress(&self, vault: &str, done: usize, total: usize) {
+        let Some(app) = &self.app else { return };
+        if !done.is_multiple_of(EMIT_EVERY) && done != total {
+            return;
+        }
+        let _ = app.emit(self.event, ProgressEvent { operationId: self.id.clone(), vault: vault.to_string(), done, total });
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        let Some(id) = &self.id else { return false };
+        OPERATIONS.lock().map(|operations| operations.get(id).copied().unwrap_or(false)).unwrap_or(false)
+    }
+
+    /// Fail with `Cancelled` once `cancel` was called for this operation
+    pub fn check(&self) -> Result<(), BrainboxError> {
+        if self.is_cancelled() {
+            return Err(BrainboxError::Cancelled);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Operation {
+    fn drop(&mut self) {
+        if let Some(id) = &self.id {
+            if let Ok(mut operations) = OPERATIONS.lock() {
+                operations.remove(id);
+            }
+        }
+    }
+}
+
+/// Ask a running operation to stop; false if no operation has this id
+pub fn cancel(id: &str) -> bool {
+    let Ok(mut operations) = OPERATIONS.lock() else { return false };
+    match operations.get_mut(id) {
+        Some(cancelled) => {
+            *cancelled = true;
+            true
+        }
+        None => false,
+    }
+}
@@ -0,0 +1,167 @@
+// prompts.rs - Prompt templates for brainbox
+// User-defined AI actions such as "summarize", "extract action items" or "translate". Like note templates they are
+// stored unencrypted (they are not vault content); the items a prompt runs on are filled in at run time.
+
+use rusqlite::{params, Connection, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::templates;
+use crate::token_budget;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Prompt {
+    pub id: i64,
+    pub name: String,
+    /// Prompt text; may contain {{content}}, {{title}}, {{titles}}, {{count}} and the template built-ins
+    pub template: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+    /// Model this prompt always uses, instead of the one chosen in the AI settings
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct PromptInput {
+    pub name: String,
+    pub template: String,
+    #[serde(default)]
+    pub system: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+impl PromptInput {
+    fn system(&self) -> Option<&str> {
+        self.system.as_deref().map(str::trim).filter(|s| !s.is_empty())
+    }
+
+    fn model(&self) -> Option<&str> {
+        self.model.as_deref().map(str::trim).filter(|m| !m.is_empty())
+    }
+}
+
+const PROMPT_COLUMNS: &str = "id, name, template, system, model, created_at, updated_at";
+
+impl Prompt {
+    pub fn create_table(conn: &Connection) -> Result<()> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS prompts (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                template TEXT NOT NULL,
+                system TEXT,
+                model TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+        Ok(())
+    }
+
+    fn from_row(row: &rusqlite::Row) -> Result<Prompt> {
+        Ok(Prompt {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            template: row.get(2)?,
+            system: row.get(3)?,
+            model: row.get(4)?,
+            created_at: row.get(5)?,
+            updated_at: row.get(6)?,
+        })
+    }
+
+    pub fn insert(conn: &Connection, input: &PromptInput) -> Result<Prompt> {
+        Self::create_table(conn)?;
+        let now = chrono::Utc::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO prompts (name, template, system, model, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![input.name.trim(), input.template, input.system(), input.model(), now, now],
+        )?;
+        Ok(Prompt {
+            id: conn.last_insert_rowid(),
+            name: input.name.trim().to_string(),
+            template: input.template.clone(),
+            system: input.system().map(str::to_string),
+            model: input.model().map(str::to_string),
+            created_at: now.clone(),
+            updated_at: now,
+        })
+    }
+
+    /// Replace a prompt's fields; None if it doesn't exist
+    pub fn update(conn: &Connection, prompt_id: i64, input: &PromptInput) -> Result<Option<Prompt>> {
+        Self::create_table(conn)?;
+        let now = chrono::Utc::now().to_rfc3339();
+        conn.execute(
+            "UPDATE prompts SET name = ?1, template = ?2, system = ?3, model = ?4, updated_at = ?5 WHERE id = ?6",
+            params![input.name.trim(), input.template, input.system(), input.model(), now, prompt_id],
+        )?;
+        Self::get_by_id(conn, prompt_id)
+    }
+
+    pub fn list(conn: &Connection) -> Result<Vec<Prompt>> {
+        Self::create_table(conn)?;
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM prompts ORDER BY name COLLATE NOCASE ASC", PROMPT_COLUMNS))?;
+        let rows = stmt.query_map([], Self::from_row)?;
+        rows.collect()
+    }
+
+    pub fn get_by_id(conn: &Connection, prompt_id: i64) -> Result<Option<Prompt>> {
+        Self::create_table(conn)?;
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM prompts WHERE id = ?1", PROMPT_COLUMNS))?;
+        let mut rows = stmt.query([prompt_id])?;
+        if let Some(row) = rows.next()? {
+            Ok(Some(Self::from_row(row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn delete(conn: &Connection, prompt_id: i64) -> Result<usize> {
+        Self::create_table(conn)?;
+        conn.execute("DELETE FROM prompts WHERE id = ?1", [prompt_id])
+    }
+}
+
+/// An item a prompt runs on, already decrypted
+pub struct PromptItem {
+    pub title: String,
+    pub content: String,
+}
+
+/// Fill `template` with the items. {{content}} is every item's text under its title; a template without it gets
+/// the content appended, so "Translate to French" works as written. Content beyond what fits `model`'s context
+/// window (`num_ctx` for local models that set it) next to the prompt and `system` is cut at the end.
+pub fn fill(template: &str, items: &[PromptItem], system: Option<&str>, model: &str, num_ctx: Option<u32>) -> String {
+    let mut content = String::new();
+    for item in items {
+        if !content.is_empty() {
+            content.push_str("\n\n---\n\n");
+        }
+        if items.len() > 1 && !item.title.trim().is_empty() {
+            content.push_str(&format!("# {}\n\n", item.title.trim()));
+        }
+        content.push_str(item.content.trim());
+    }
+    let titles: Vec<&str> = items.iter().map(|item| item.title.trim()).filter(|t| !t.is_empty()).collect();
+    let mut variables = HashMap::new();
+    variables.insert("title".to_string(), titles.first().copied().unwrap_or_default().to_string());
+    variables.insert("titles".to_string(), titles.join(", "));
+    variables.insert("count".to_string(), items.len().to_string());
+    let has_content = template.contains("{{content}}") || template.contains("{{ content }}");
+    let used = token_budget::count(&templates::render(template, &variables), model)
+        + system.map(|system| token_budget::count(system, model)).unwrap_or(0);
+    let content = token_budget::truncate(&content, model, token_budget::prompt_budget(model, num_ctx).saturating_sub(used));
+    variables.insert("content".to_string(), content.to_string());
+    let filled = templates::render(template, &variables);
+    if has_content || content.is_empty() {
+        filled
+    } else {
+        format!("{}\n\n{}", filled.trim_end(), content)
+    }
+}
@@ -0,0 +1,216 @@
+// quick_actions.rs - Task shortcuts in the Windows taskbar jump list and the macOS dock menu
+// Each shortcut raises the same frontend event as its hotkey or tray entry. Jump list tasks relaunch the executable
+// with a brainbox://action/<id> link, which reaches the running instance like any other deep link; the dock menu
+// calls back into the process directly.
+
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+pub struct QuickAction {
+    pub id: &'static str,
+    #[cfg_attr(not(any(target_os = "windows", target_os = "macos")), allow(dead_code))]
+    /// In English; translated when shown
+    pub title: &'static str,
+    /// Event emitted to the main window
+    pub event: &'static str,
+}
+
+pub const ACTIONS: &[QuickAction] = &[
+    QuickAction { id: "new_note", title: "New Note", event: "new-note-requested" },
+    QuickAction { id: "quick_capture", title: "Quick Capture", event: "capture-hotkey-pressed" },
+    QuickAction { id: "quick_search", title: "Search", event: "quick-search-hotkey-pressed" },
+];
+
+/// The deep link a jump list task launches with
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn action_url(id: &str) -> String {
+    format!("{}action/{}", crate::deeplink::SCHEME_PREFIX, id)
+}
+
+pub fn find(id: &str) -> Option<&'static QuickAction> {
+    ACTIONS.iter().find(|action| action.id == id)
+}
+
+/// Bring up the main window and raise the action's event
+pub fn run<R: Runtime>(app: &AppHandle<R>, id: &str) {
+    let Some(action) = find(id) else {
+        tracing::warn!("Unknown quick action: {}", id);
+        return;
+    };
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.set_focus();
+        let _ = window.emit(action.event, ());
+    }
+}
+
+/// Add the shortcuts to the jump list or dock menu; a no-op on other platforms
+pub fn install(app: &AppHandle) {
+    platform::install(app);
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use tauri::AppHandle;
+    use windows::core::{Interface, HSTRING, PROPVARIANT};
+    use windows::Win32::Storage::EnhancedStorage::PKEY_Title;
+    use windows::Win32::System::Com::{
+        CoCreateInstance, CoInitializeEx, CoUninitialize, CLSCTX_INPROC_SERVER, COINIT_APARTMENTTHREADED,
+    };
+    use windows::Win32::UI::Shell::Common::{IObjectArray, IObjectCollection};
+    use windows::Win32::UI::Shell::PropertiesSystem::IPropertyStore;
+    use windows::Win32::UI::Shell::{
+        DestinationList, EnumerableObjectCollection, ICustomDestinationList, IShellLinkW, ShellLink,
+    };
+
+    use super::{action_url, ACTIONS};
+
+    /// Replace the jump list's tasks with ours
+    fn build(exe: &std::path::Path) -> windows::core::Result<()> {
+        unsafe {
+            let list: ICustomDestinationList = CoCreateInstance(&DestinationList, None, CLSCTX_INPROC_SERVER)?;
+            let mut max_slots = 0u32;
+            let _removed: IObjectArray = list.BeginList(&mut max_slots)?;
+            let tasks: IObjectCollection = CoCreateInstance(&EnumerableObjectCollection, None, CLSCTX_INPROC_SERVER)?;
+            for action in ACTIONS {
+                let link: IShellLinkW = CoCreateInstance(&ShellLink, None, CLSCTX_INPROC_SERVER)?;
+                link.SetPath(&HSTRING::from(exe))?;
+                link.SetArguments(&HSTRING::from(action_url(action.id)))?;
+                link.SetDescription(&HSTRING::from(crate::i18n::tr(action.title)))?;
+                link.SetIconLocation(&HSTRING::from(exe), 0)?;
+                // The jump list shows the link's title property, not its description
+                let properties: IPropertyStore = link.cast()?;
+                properties.SetValue(&PKEY_Title, &PROPVARIANT::from(crate::i18n::tr(action.title).as_str()))?;
+                properties.Commit()?;
+                tasks.AddObject(&link)?;
+            }
+            list.AddUserTasks(&tasks.cast::<IObjectArray>()?)?;
+            list.CommitList()
+        }
+    }
+
+    pub fn install(_app: &AppHandle) {
+        let exe = match std::env::current_exe() {
+            Ok(exe) => exe,
+            Err(e) => {
+                tracing::warn!("Jump list not updated, executable path unknown: {}", e);
+                return;
+            }
+        };
+        // COM wants a thread of its own; the main thread's apartment belongs to the webview
+        std::thread::spawn(move || unsafe {
+            let initialized = CoInitializeEx(None, COINIT_APARTMENTTHREADED).is_ok();
+            if let Err(e) = build(&exe) {
+                tracing::warn!("Failed to update the jump list: {}", e);
+            }
+            if initialized {
+                CoUninitialize();
+            }
+        });
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use objc2::rc::Retained;
+    use objc2::runtime::{AnyClass, AnyObject, ClassBuilder, Sel};
+    use objc2::{msg_send, sel};
+    use std::ffi::CString;
+    use std::sync::atomic::{AtomicPtr, Ordering};
+    use std::sync::OnceLock;
+    use tauri::AppHandle;
+
+    use super::ACTIONS;
+
+    static APP: OnceLock<AppHandle> = OnceLock::new();
+    /// Built once and kept for the life of the app; AppKit doesn't take ownership of the dock menu
+    static DOCK_MENU: AtomicPtr<AnyObject> = AtomicPtr::new(std::ptr::null_mut());
+
+    /// Menu items carry the index of their action in ACTIONS as their tag
+    extern "C-unwind" fn perform_action(_this: &AnyObject, _cmd: Sel, sender: *mut AnyObject) {
+        let tag: isize = unsafe { msg_send![sender, tag] };
+        let action = usize::try_from(tag).ok().and_then(|index| ACTIONS.get(index));
+        if let (Some(app), Some(action)) = (APP.get(), action) {
+            super::run(app, action.id);
+        }
+    }
+
+    /// `applicationDockMenu:`, added to the app delegate
+    extern "C-unwind" fn dock_menu(_this: &AnyObject, _cmd: Sel, _sender: *mut AnyObject) -> *mut AnyObject {
+        DOCK_MENU.load(Ordering::Acquire)
+    }
+
+    fn target_class() -> Option<&'static AnyClass> {
+        let mut builder = ClassBuilder::new(c"BrainboxDockMenuTarget", AnyClass::get(c"NSObject")?)?;
+        unsafe {
+            builder.add_method(
+                sel!(performQuickAction:),
+                perform_action as extern "C-unwind" fn(_, _, _),
+            );
+        }
+        Some(builder.register())
+    }
+
+    fn ns_string(text: &str) -> Option<*mut AnyObject> {
+        let class = AnyClass::get(c"NSString")?;
+        let text = CString::new(text).ok()?;
+        Some(unsafe { msg_send![class, stringWithUTF8String: text.as_ptr()] })
+    }
+
+    fn build_menu() -> Option<Retained<AnyObject>> {
+        let target: Retained<AnyObject> = unsafe { msg_send![target_class()?, new] };
+        let menu: Retained<AnyObject> = unsafe { msg_send![AnyClass::get(c"NSMenu")?, new] };
+        let item_class = AnyClass::get(c"NSMenuItem")?;
+        for (index, action) in ACTIONS.iter().enumerate() {
+            let title = ns_string(&crate::i18n::tr(action.title))?;
+            unsafe {
+                let item: Retained<AnyObject> = msg_send![item_class, new];
+                let _: () = msg_send![&item, setTitle: title];
+                let _: () = msg_send![&item, setAction: sel!(performQuickAction:)];
+                let _: () = msg_send![&item, setTarget: &*target];
+                let _: () = msg_send![&item, setTag: index as isize];
+                let _: () = msg_send![&menu, addItem: &*item];
+            }
+        }
+        // Menu items only hold their target weakly
+        std::mem::forget(target);
+        Some(menu)
+    }
+
+    /// Must run on the main thread, after the app delegate is in place
+    pub fn install(app: &AppHandle) {
+        if APP.set(app.clone()).is_err() {
+            return;
+        }
+        let Some(menu) = build_menu() else {
+            tracing::warn!("Failed to build the dock menu");
+            return;
+        };
+        DOCK_MENU.store(Retained::into_raw(menu), Ordering::Release);
+        let Some(ns_app) = AnyClass::get(c"NSApplication") else { return };
+        unsafe {
+            let shared: *mut AnyObject = msg_send![ns_app, sharedApplication];
+            let delegate: *mut AnyObject = msg_send![shared, delegate];
+            let Some(delegate) = delegate.as_ref() else {
+                tracing::warn!("No app delegate to attach the dock menu to");
+                return;
+            };
+            let dock_menu: extern "C-unwind" fn(&AnyObject, Sel, *mut AnyObject) -> *mut AnyObject = dock_menu;
+            let added = objc2::ffi::class_addMethod(
+                delegate.class() as *const AnyClass as *mut AnyClass,
+                sel!(applicationDockMenu:),
+                std::mem::transmute::<_, objc2::runtime::Imp>(dock_menu),
+                c"@@:@".as_ptr(),
+            );
+            if !added.as_bool() {
+                tracing::warn!("The app delegate already provides a dock menu");
+            }
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use tauri::AppHandle;
+
+    pub fn install(_app: &AppHandle) {}
+}
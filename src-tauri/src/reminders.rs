@@ -0,0 +1,291 @@
+// reminders.rs - Item reminders for brainbox
+// Stores reminders in SQLite and fires them as native notifications from a background scheduler
+
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, Local, Months, NaiveDateTime, TimeZone, Utc, Weekday};
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_notification::NotificationExt;
+
+/// How often the scheduler wakes up to look for due reminders, tasks and meeting notes
+const SCHEDULER_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Supported repeat rules; None/"none" means the reminder fires once
+pub const REPEAT_RULES: &[&str] = &["none", "daily", "weekdays", "weekly", "monthly", "yearly"];
+
+pub fn is_valid_repeat_rule(rule: &str) -> bool {
+    REPEAT_RULES.contains(&rule)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Reminder {
+    pub id: i64,
+    pub item_id: i64,
+    /// Next time the reminder fires (UTC RFC 3339)
+    pub remind_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repeat: Option<String>,
+    pub created_at: String,
+    /// Set once a one-shot reminder has fired
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fired_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dismissed_at: Option<String>,
+    /// Title of the item, joined in for display
+    pub item_title: String,
+    pub vault_id: i64,
+    /// First time the reminder was set for; monthly and yearly repeats keep its day of the month
+    pub starts_at: String,
+}
+
+const REMINDER_COLUMNS: &str =
+    "r.id, r.item_id, r.remind_at, r.repeat, r.created_at, r.fired_at, r.dismissed_at, i.title, i.vault_id, COALESCE(r.starts_at, r.remind_at)";
+
+const CREATE_TABLE: &str = "CREATE TABLE IF NOT EXISTS reminders (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    item_id INTEGER NOT NULL,
+    remind_at TEXT NOT NULL,
+    repeat TEXT,
+    created_at TEXT NOT NULL,
+    fired_at TEXT,
+    dismissed_at TEXT,
+    starts_at TEXT,
+    FOREIGN KEY(item_id) REFERENCES vault_items(id) ON DELETE CASCADE
+)";
+
+impl Reminder {
+    pub fn create_table(conn: &Connection) -> Result<()> {
+        // Reminders used to block deleting their item; SQLite can't change a foreign key, so rebuild the table
+        let existing: Option<String> = conn
+            .query_row("SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'reminders'", [], |row| row.get(0))
+            .optional()?;
+        if existing.is_some_and(|sql| !sql.contains("ON DELETE CASCADE")) {
+            conn.execute_batch(&format!(
+                "SAVEPOINT reminders_cascade;
+                 ALTER TABLE reminders RENAME TO reminders_old;
+                 {};
+                 INSERT INTO reminders (id, item_id, remind_at, repeat, created_at, fired_at, dismissed_at)
+                     SELECT id, item_id, remind_at, repeat, created_at, fired_at, dismissed_at FROM reminders_old
+                     WHERE item_id IN (SELECT id FROM vault_items);
+                 DROP TABLE reminders_old;
+                 RELEASE reminders_cascade;",
+                CREATE_TABLE
+            ))?;
+        }
+        conn.execute(CREATE_TABLE, [])?;
+        let has_starts_at: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM pragma_table_info('reminders') WHERE name = 'starts_at')",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_starts_at {
+            // Older reminders count their repeats from the occurrence they are on
+            conn.execute("ALTER TABLE reminders ADD COLUMN starts_at TEXT", [])?;
+        }
+        conn.execute("CREATE INDEX IF NOT EXISTS idx_reminders_remind_at ON reminders(remind_at)", [])?;
+        Ok(())
+    }
+
+    fn from_row(row: &rusqlite::Row) -> Result<Reminder> {
+        Ok(Reminder {
+            id: row.get(0)?,
+            item_id: row.get(1)?,
+            remind_at: row.get(2)?,
+            repeat: row.get(3)?,
+            created_at: row.get(4)?,
+            fired_at: row.get(5)?,
+            dismissed_at: row.get(6)?,
+            item_title: row.get(7)?,
+            vault_id: row.get(8)?,
+            starts_at: row.get(9)?,
+        })
+    }
+
+    pub fn insert(conn: &Connection, item_id: i64, remind_at: &str, repeat: Option<&str>) -> Result<Reminder> {
+        Self::create_table(conn)?;
+        let now = Utc::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO reminders (item_id, remind_at, repeat, created_at, starts_at) VALUES (?1, ?2, ?3, ?4, ?2)",
+            params![item_id, remind_at, repeat, now],
+        )?;
+        Self::get_by_id(conn, conn.last_insert_rowid())
+    }
+
+    pub fn get_by_id(conn: &Connection, reminder_id: i64) -> Result<Reminder> {
+        conn.query_row(
+            &format!(
+                "SELECT {} FROM reminders r JOIN vault_items i ON i.id = r.item_id WHERE r.id = ?1",
+                REMINDER_COLUMNS
+            ),
+            [reminder_id],
+            Self::from_row,
+        )
+    }
+
+    /// Reminders that haven't been dismissed, soonest first. Optionally limited to one item.
+    pub fn list_active(conn: &Connection, item_id: Option<i64>) -> Result<Vec<Reminder>> {
+        Self::create_table(conn)?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM reminders r JOIN vault_items i ON i.id = r.item_id \
+             WHERE r.dismissed_at IS NULL AND i.deleted_at IS NULL AND (?1 IS NULL OR r.item_id = ?1) \
+             ORDER BY r.remind_at ASC",
+            REMINDER_COLUMNS
+        ))?;
+        let rows = stmt.query_map([item_id], Self::from_row)?;
+        rows.collect()
+    }
+
+    /// Reminders whose time has come and that haven't fired yet
+    fn list_due(conn: &Connection, now: &str) -> Result<Vec<Reminder>> {
+        Self::create_table(conn)?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM reminders r JOIN vault_items i ON i.id = r.item_id \
+             WHERE r.remind_at <= ?1 AND r.fired_at IS NULL AND r.dismissed_at IS NULL AND i.deleted_at IS NULL \
+             ORDER BY r.remind_at ASC",
+            REMINDER_COLUMNS
+        ))?;
+        let rows = stmt.query_map([now], Self::from_row)?;
+        rows.collect()
+    }
+
+    pub fn dismiss(conn: &Connection, reminder_id: i64) -> Result<()> {
+        Self::create_table(conn)?;
+        let now = Utc::now().to_rfc3339();
+        conn.execute("UPDATE reminders SET dismissed_at = ?1 WHERE id = ?2", params![now, reminder_id])?;
+        Ok(())
+    }
+
+    /// Record a firing: repeating reminders move to their next occurrence, one-shots are marked fired
+    fn mark_fired(conn: &Connection, reminder: &Reminder, now: DateTime<Utc>) -> Result<()> {
+        let parse = |at: &str| DateTime::parse_from_rfc3339(at).ok().map(|at| at.with_timezone(&Utc));
+        let next = match (reminder.repeat.as_deref(), parse(&reminder.starts_at), parse(&reminder.remind_at)) {
+            (Some(rule), Some(start), Some(at)) => next_occurrence(start, at, rule, now),
+            _ => None,
+        };
+        match next {
+            Some(next) => conn.execute(
+                "UPDATE reminders SET remind_at = ?1 WHERE id = ?2",
+                params![next.to_rfc3339(), reminder.id],
+            )?,
+            None => conn.execute(
+                "UPDATE reminders SET fired_at = ?1 WHERE id = ?2",
+                params![now.to_rfc3339(), reminder.id],
+            )?,
+        };
+        Ok(())
+    }
+}
+
+/// Advance `at` by one step of a daily or weekly `rule`. Steps are taken in local time, so a reminder keeps its time
+/// of day across DST changes and "weekdays" means the user's weekdays.
+fn step(at: DateTime<Utc>, rule: &str) -> Option<DateTime<Utc>> {
+    let local = at.with_timezone(&Local).naive_local();
+    let next = match rule {
+        "daily" => local + ChronoDuration::days(1),
+        "weekly" => local + ChronoDuration::weeks(1),
+        "weekdays" => {
+            let mut next = local + ChronoDuration::days(1);
+            while matches!(next.weekday(), Weekday::Sat | Weekday::Sun) {
+                next += ChronoDuration::days(1);
+            }
+            next
+        }
+        _ => return None,
+    };
+    from_local(next)
+}
+
+/// A local time as UTC; one skipped by a DST change moves to the hour after
+fn from_local(local: NaiveDateTime) -> Option<DateTime<Utc>> {
+    Local
+        .from_local_datetime(&local)
+        .earliest()
+        .or_else(|| Local.from_local_datetime(&(local + ChronoDuration::hours(1))).earliest())
+        .map(|at| at.with_timezone(&Utc))
+}
+
+/// First occurrence of a repeating reminder after both `at`, the one that fired, and `now`. Missed occurrences (app
+/// closed) are skipped, not replayed. Monthly and yearly repeats are counted from `start`, so a reminder on the 31st
+/// is back on the 31st after a shorter month.
+fn next_occurrence(start: DateTime<Utc>, mut at: DateTime<Utc>, rule: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let months = match rule {
+        "monthly" => 1,
+        "yearly" => 12,
+        _ => loop {
+            at = step(at, rule)?;
+            if at > now {
+                return Some(at);
+            }
+        },
+    };
+    let after = at.max(now);
+    let start = start.with_timezone(&Local).naive_local();
+    let local_after = after.with_timezone(&Local);
+    let elapsed = (local_after.year() - start.year()) * 12 + local_after.month() as i32 - start.month() as i32;
+    // Occurrences before this one fall in earlier months than `after`
+    let mut n = (elapsed.max(0) as u32 / months).max(1);
+    loop {
+        let next = from_local(start.checked_add_months(Months::new(n * months))?)?;
+        if next > after {
+            return Some(next);
+        }
+        n += 1;
+    }
+}
+
+/// Normalize a user-supplied reminder time to UTC RFC 3339 so it compares correctly as text in SQLite
+pub fn normalize_remind_at(remind_at: &str) -> std::result::Result<String, String> {
+    DateTime::parse_from_rfc3339(remind_at)
+        .map(|dt| dt.with_timezone(&Utc).to_rfc3339())
+        .map_err(|_| format!("Invalid reminder time: {}", remind_at))
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[allow(non_snake_case)]
+struct ReminderFiredEvent {
+    reminderId: i64,
+    itemId: i64,
+    vaultId: i64,
+    title: String,
+    remindAt: String,
+}
+
+fn fire_due_reminders(app: &AppHandle) -> std::result::Result<(), String> {
+    let conn = crate::db::open().map_err(|e| e.to_string())?;
+    let now = Utc::now();
+    let due = Reminder::list_due(&conn, &now.to_rfc3339()).map_err(|e| e.to_string())?;
+    for reminder in due {
+        let _ = app
+            .notification()
+            .builder()
+            .title(crate::i18n::tr("Reminder"))
+            .body(&reminder.item_title)
+            .show();
+        let _ = app.emit("reminder-fired", ReminderFiredEvent {
+            reminderId: reminder.id,
+            itemId: reminder.item_id,
+            vaultId: reminder.vault_id,
+            title: reminder.item_title.clone(),
+            remindAt: reminder.remind_at.clone(),
+        });
+        Reminder::mark_fired(&conn, &reminder, now).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Start the background scheduler that fires reminders and task due notifications and makes meeting notes
+pub fn spawn_scheduler(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        if let Err(e) = fire_due_reminders(&app) {
+            tracing::error!("Reminder check failed: {}", e);
+        }
+        if let Err(e) = crate::tasks::notify_due_tasks(&app) {
+            tracing::error!("Due task check failed: {}", e);
+        }
+        if let Err(e) = crate::calendar::create_due_meeting_notes(&app) {
+            tracing::error!("Meeting notes check failed: {}", e);
+        }
+        std::thread::sleep(SCHEDULER_INTERVAL);
+    });
+}
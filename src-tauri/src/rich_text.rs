@@ -0,0 +1,664 @@
+// rich_text.rs - Rich text to markdown conversion for brainbox imports
+// HTML (Apple Notes and OneNote exports) and Word .docx documents become markdown notes. Images are handed to a
+// caller-supplied resolver, which copies them into the image cache and returns the URL the markdown links to.
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::reader::Reader;
+use scraper::{ElementRef, Html, Node, Selector};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::io::Read;
+
+use crate::error::BrainboxError;
+
+/// Largest file inflated out of a .docx archive
+const MAX_ZIP_ENTRY_BYTES: u64 = 100 * 1024 * 1024;
+/// Most bytes inflated out of one archive, however many entries it has
+const MAX_ZIP_TOTAL_BYTES: u64 = 256 * 1024 * 1024;
+/// Most entries read from a central directory; Word writes a few dozen
+const MAX_ZIP_ENTRIES: usize = 10_000;
+
+pub struct Converted {
+    /// Document title (<title>, the first heading, or a Word "Title" paragraph)
+    pub title: Option<String>,
+    pub markdown: String,
+    /// URLs of images the markdown references, in order
+    pub images: Vec<String>,
+}
+
+/// Collapse runs of three or more newlines and trailing spaces left by block elements
+fn tidy(markdown: &str) -> String {
+    let mut out = String::new();
+    let mut blank_lines = 0;
+    for line in markdown.lines().map(|l| l.trim_end()) {
+        if line.is_empty() {
+            blank_lines += 1;
+            if blank_lines > 1 {
+                continue;
+            }
+        } else {
+            blank_lines = 0;
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    out.trim().to_string()
+}
+
+// --- HTML ---
+
+struct HtmlWriter<'r> {
+    out: String,
+    /// Open lists, innermost last: None for bullets, Some(next number) for ordered lists
+    lists: Vec<Option<usize>>,
+    in_pre: bool,
+    /// Maps an img src to the URL used in the markdown; None drops the image
+    resolve_image: &'r mut dyn FnMut(&str) -> Option<String>,
+    images: Vec<String>,
+}
+
+impl HtmlWriter<'_> {
+    fn text(&mut self, text: &str) {
+        if self.in_pre {
+            self.out.push_str(text);
+            return;
+        }
+        let mut collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if text.starts_with(char::is_whitespace) {
+            collapsed.insert(0, ' ');
+        }
+        if text.ends_with(char::is_whitespace) && collapsed.len() > 1 {
+            collapsed.push(' ');
+        }
+        if self.out.is_empty() || self.out.ends_with('\n') || self.out.ends_with(' ') {
+            collapsed = collapsed.trim_start().to_string();
+        }
+        self.out.push_str(&collapsed);
+    }
+
+    /// End the current line and make sure `newlines` line breaks separate it from what follows
+    fn block(&mut self, newlines: usize) {
+        let trimmed = self.out.trim_end_matches(' ').len();
+        self.out.truncate(trimmed);
+        if self.out.is_empty() {
+            return;
+        }
+        let existing = self.out.len() - self.out.trim_end_matches('\n').len();
+        for _ in existing..newlines {
+            self.out.push('\n');
+        }
+    }
+
+    fn children(&mut self, el: ElementRef) {
+        for child in el.children() {
+            match child.value() {
+                Node::Text(text) => self.text(text),
+                Node::Element(_) => {
+                    if let Some(child) = ElementRef::wrap(child) {
+                        self.element(child);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Render children, then hand their markdown to `wrap` to replace it
+    fn wrapped(&mut self, el: ElementRef, wrap: impl FnOnce(&str) -> String) {
+        let start = self.out.len();
+        self.children(el);
+        let inner = self.out.split_off(start);
+        if inner.trim().is_empty() {
+            self.out.push_str(&inner);
+            return;
+        }
+        if inner.starts_with(' ') {
+            self.out.push(' ');
+        }
+        self.out.push_str(&wrap(inner.trim()));
+        if inner.ends_with(' ') {
+            self.out.push(' ');
+        }
+    }
+
+    fn element(&mut self, el: ElementRef) {
+        let name = el.value().name();
+        match name {
+            "script" | "style" | "head" | "title" | "meta" | "link" | "noscript" => {}
+            "br" => {
+                let trimmed = self.out.trim_end_matches(' ').len();
+                self.out.truncate(trimmed);
+                self.out.push('\n');
+            }
+            "p" => {
+                self.block(2);
+                self.children(el);
+                self.block(2);
+            }
+            "div" | "section" | "article" | "header" | "footer" | "main" | "figure" | "figcaption" => {
+                self.block(1);
+                self.children(el);
+                self.block(1);
+            }
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = name[1..].parse::<usize>().unwrap_or(1);
+                self.block(2);
+                self.out.push_str(&"#".repeat(level));
+                self.out.push(' ');
+                self.children(el);
+                self.block(2);
+            }
+            "strong" | "b" => self.wrapped(el, |t| format!("**{}**", t)),
+            "em" | "i" => self.wrapped(el, |t| format!("*{}*", t)),
+            "s" | "strike" | "del" => self.wrapped(el, |t| format!("~~{}~~", t)),
+            "code" if !self.in_pre => self.wrapped(el, |t| format!("`{}`", t)),
+            "pre" => {
+                self.block(2);
+                self.out.push_str("```\n");
+                self.in_pre = true;
+                self.children(el);
+                self.in_pre = false;
+                self.block(1);
+                self.out.push_str("```");
+                self.block(2);
+            }
+            "a" => {
+                let href = el.value().attr("href").map(str::trim).unwrap_or("").to_string();
+                if href.is_empty() || href.starts_with('#') || href.starts_with("javascript:") {
+                    self.children(el);
+                } else {
+                    let start = self.out.len();
+                    self.children(el);
+                    if self.out[start..].trim().is_empty() {
+                        self.out.truncate(start);
+                        self.out.push_str(&format!("<{}>", href));
+                    } else {
+                        self.wrapped_from(start, |t| format!("[{}]({})", t, href));
+                    }
+                }
+            }
+            "img" => {
+                let Some(src) = el.value().attr("src") else { return };
+                if let Some(url) = (self.resolve_image)(src) {
+                    let alt = el.value().attr("alt").unwrap_or("").trim();
+                    self.out.push_str(&format!("![{}]({})", alt, url));
+                    self.images.push(url);
+                }
+            }
+            "ul" | "ol" => {
+                let start = el.value().attr("start").and_then(|s| s.parse().ok()).unwrap_or(1);
+                self.lists.push((name == "ol").then_some(start));
+                self.block(if self.lists.len() > 1 { 1 } else { 2 });
+                self.children(el);
+                self.lists.pop();
+                self.block(if self.lists.is_empty() { 2 } else { 1 });
+            }
+            "li" => {
+                self.block(1);
+                let depth = self.lists.len().saturating_sub(1);
+                let marker = match self.lists.last_mut() {
+                    Some(Some(n)) => {
+                        *n += 1;
+                        format!("{}. ", *n - 1)
+                    }
+                    _ => "- ".to_string(),
+                };
+                self.out.push_str(&"  ".repeat(depth));
+                self.out.push_str(&marker);
+                self.children(el);
+                self.block(1);
+            }
+            "blockquote" => {
+                self.block(2);
+                let start = self.out.len();
+                self.children(el);
+                let inner = self.out.split_off(start);
+                let quoted: Vec<String> = inner.trim().lines().map(|l| format!("> {}", l).trim_end().to_string()).collect();
+                self.out.push_str(&quoted.join("\n"));
+                self.block(2);
+            }
+            "hr" => {
+                self.block(2);
+                self.out.push_str("---");
+                self.block(2);
+            }
+            "table" => self.table(el),
+            _ => self.children(el),
+        }
+    }
+
+    /// Like `wrapped`, for markdown already written from `start`
+    fn wrapped_from(&mut self, start: usize, wrap: impl FnOnce(&str) -> String) {
+        let inner = self.out.split_off(start);
+        self.out.push_str(&wrap(inner.trim()));
+    }
+
+    fn table(&mut self, el: ElementRef) {
+        let (Ok(rows), Ok(cells)) = (Selector::parse("tr"), Selector::parse("th, td")) else { return };
+        self.block(2);
+        for (index, row) in el.select(&rows).enumerate() {
+            let texts: Vec<String> = row
+                .select(&cells)
+                .map(|cell| cell.text().collect::<Vec<_>>().join(" ").split_whitespace().collect::<Vec<_>>().join(" ").replace('|', "\\|"))
+                .collect();
+            if texts.is_empty() {
+                continue;
+            }
+            self.out.push_str(&format!("| {} |\n", texts.join(" | ")));
+            if index == 0 {
+                self.out.push_str(&format!("|{}\n", " --- |".repeat(texts.len())));
+            }
+        }
+        self.block(2);
+    }
+}
+
+/// Convert an HTML document or fragment to markdown
+pub fn html_to_markdown(html: &str, resolve_image: &mut dyn FnMut(&str) -> Option<String>) -> Converted {
+    let document = Html::parse_document(html);
+    let select_first = |css: &str| {
+        Selector::parse(css).ok().and_then(|sel| {
+            document
+                .select(&sel)
+                .map(|el| el.text().collect::<String>().split_whitespace().collect::<Vec<_>>().join(" "))
+                .find(|t| !t.is_empty())
+        })
+    };
+    let title = select_first("title").or_else(|| select_first("h1"));
+    let mut writer = HtmlWriter { out: String::new(), lists: Vec::new(), in_pre: false, resolve_image, images: Vec::new() };
+    let body = Selector::parse("body").ok().and_then(|sel| document.select(&sel).next());
+    writer.children(body.unwrap_or_else(|| document.root_element()));
+    Converted { title, markdown: tidy(&writer.out), images: writer.images }
+}
+
+// --- .docx ---
+
+struct ZipEntry {
+    method: u16,
+    compressed_size: usize,
+    uncompressed_size: u64,
+    local_offset: usize,
+}
+
+/// Just enough of the zip format to read the parts of a .docx. Zip64 archives (over 4 GB or 65,535 entries) are
+/// refused rather than misread. Entries must inflate to exactly their declared size, within the caps above.
+struct ZipArchive<'a> {
+    data: &'a [u8],
+    entries: HashMap<String, ZipEntry>,
+    inflated: Cell<u64>,
+}
+
+fn le_u16(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn le_u32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+impl<'a> ZipArchive<'a> {
+    fn open(data: &'a [u8]) -> Option<Self> {
+        // The end-of-central-directory record sits at the end, before a comment of up to 64 KB
+        let search_from = data.len().saturating_sub(22 + 65_535);
+        let eocd = (search_from..data.len().saturating_sub(21)).rev().find(|&i| data[i..].starts_with(b"PK\x05\x06"))?;
+        let count = le_u16(data, eocd + 10)?;
+        let directory = le_u32(data, eocd + 16)?;
+        if count == u16::MAX || directory == u32::MAX || count as usize > MAX_ZIP_ENTRIES {
+            return None;
+        }
+        let mut at = directory as usize;
+        let mut entries = HashMap::new();
+        for _ in 0..count {
+            if !data.get(at..)?.starts_with(b"PK\x01\x02") {
+                return None;
+            }
+            let name_len = le_u16(data, at + 28)? as usize;
+            let extra_len = le_u16(data, at + 30)? as usize;
+            let comment_len = le_u16(data, at + 32)? as usize;
+            let name = String::from_utf8_lossy(data.get(at + 46..at + 46 + name_len)?).to_string();
+            let (compressed_size, uncompressed_size, local_offset) =
+                (le_u32(data, at + 20)?, le_u32(data, at + 24)?, le_u32(data, at + 42)?);
+            if [compressed_size, uncompressed_size, local_offset].contains(&u32::MAX) {
+                return None;
+            }
+            entries.insert(
+                name,
+                ZipEntry {
+                    method: le_u16(data, at + 10)?,
+                    compressed_size: compressed_size as usize,
+                    uncompressed_size: uncompressed_size as u64,
+                    local_offset: local_offset as usize,
+                },
+            );
+            at += 46 + name_len + extra_len + comment_len;
+        }
+        Some(ZipArchive { data, entries, inflated: Cell::new(0) })
+    }
+
+    fn read(&self, name: &str) -> Option<Vec<u8>> {
+        let entry = self.entries.get(name)?;
+        let inflated = self.inflated.get() + entry.uncompressed_size;
+        if entry.uncompressed_size > MAX_ZIP_ENTRY_BYTES || inflated > MAX_ZIP_TOTAL_BYTES {
+            return None;
+        }
+        let local = entry.local_offset;
+        if !self.data.get(local..)?.starts_with(b"PK\x03\x04") {
+            return None;
+        }
+        let start = local + 30 + le_u16(self.data, local + 26)? as usize + le_u16(self.data, local + 28)? as usize;
+        let raw = self.data.get(start..start + entry.compressed_size)?;
+        let out = match entry.method {
+            0 => raw.to_vec(),
+            8 => {
+                // One byte past the declared size is enough to catch an entry that lies about it
+                let mut out = Vec::new();
+                flate2::read::DeflateDecoder::new(raw)
+                    .take(entry.uncompressed_size + 1)
+                    .read_to_end(&mut out)
+                    .ok()?;
+                out
+            }
+            _ => return None,
+        };
+        if out.len() as u64 != entry.uncompressed_size {
+            return None;
+        }
+        self.inflated.set(inflated);
+        Some(out)
+    }
+}
+
+fn attr(e: &BytesStart, name: &str) -> Option<String> {
+    e.try_get_attribute(name).ok().flatten().and_then(|a| a.unescape_value().ok().map(|v| v.to_string()))
+}
+
+/// Relationship id -> target from word/_rels/document.xml.rels (image paths and hyperlink URLs)
+fn docx_relationships(zip: &ZipArchive) -> HashMap<String, String> {
+    let mut rels = HashMap::new();
+    let Some(xml) = zip.read("word/_rels/document.xml.rels") else { return rels };
+    let xml = String::from_utf8_lossy(&xml);
+    let mut reader = Reader::from_str(&xml);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Empty(e)) | Ok(Event::Start(e)) if e.local_name().as_ref() == b"Relationship" => {
+                if let (Some(id), Some(target)) = (attr(&e, "Id"), attr(&e, "Target")) {
+                    rels.insert(id, target);
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    rels
+}
+
+#[derive(Default)]
+struct DocxParagraph {
+    text: String,
+    heading: Option<usize>,
+    list_level: Option<usize>,
+}
+
+/// Convert a Word document to markdown: headings, lists, bold/italic runs, hyperlinks and embedded images.
+/// `resolve_image` gets each embedded image's archive path and bytes.
+pub fn docx_to_markdown(bytes: &[u8], resolve_image: &mut dyn FnMut(&str, &[u8]) -> Option<String>) -> Result<Converted, BrainboxError> {
+    let invalid = || BrainboxError::InvalidInput("Not a valid .docx document".to_string());
+    let zip = ZipArchive::open(bytes).ok_or_else(invalid)?;
+    let xml = zip.read("word/document.xml").ok_or_else(invalid)?;
+    let xml = String::from_utf8_lossy(&xml);
+    let rels = docx_relationships(&zip);
+
+    let mut paragraphs: Vec<DocxParagraph> = Vec::new();
+    let mut images = Vec::new();
+    let mut title = None;
+    let mut para = DocxParagraph::default();
+    let mut run = String::new();
+    let (mut in_run, mut in_text, mut bold, mut italic) = (false, false, false, false);
+    let mut link: Option<(String, usize)> = None;
+
+    let mut reader = Reader::from_str(&xml);
+    loop {
+        let event = reader.read_event().map_err(|_| invalid())?;
+        let (e, empty) = match &event {
+            Event::Start(e) => (Some(e.clone()), false),
+            Event::Empty(e) => (Some(e.clone()), true),
+            _ => (None, false),
+        };
+        if let Some(e) = e {
+            match e.name().as_ref() {
+                b"w:p" if !empty => para = DocxParagraph::default(),
+                b"w:pStyle" => {
+                    let style = attr(&e, "w:val").unwrap_or_default().to_lowercase();
+                    para.heading = match style.as_str() {
+                        "title" => Some(1),
+                        "subtitle" => Some(2),
+                        s => s.strip_prefix("heading").and_then(|n| n.parse::<usize>().ok()).map(|n| n.clamp(1, 6)),
+                    };
+                }
+                b"w:numPr" => para.list_level = Some(para.list_level.unwrap_or(0)),
+                b"w:ilvl" => para.list_level = attr(&e, "w:val").and_then(|v| v.parse().ok()).or(Some(0)),
+                b"w:r" if !empty => {
+                    in_run = true;
+                    bold = false;
+                    italic = false;
+                    run.clear();
+                }
+                b"w:b" | b"w:i" if in_run => {
+                    let on = !matches!(attr(&e, "w:val").as_deref(), Some("0") | Some("false"));
+                    if e.name().as_ref() == b"w:b" { bold = on } else { italic = on }
+                }
+                b"w:t" if !empty => in_text = true,
+                b"w:tab" if in_run => run.push('\t'),
+                b"w:br" | b"w:cr" if in_run => run.push('\n'),
+                b"w:hyperlink" if !empty => {
+                    let target = attr(&e, "r:id").and_then(|id| rels.get(&id).cloned());
+                    link = target.map(|t| (t, para.text.len()));
+                }
+                b"a:blip" => {
+                    let target = attr(&e, "r:embed").and_then(|id| rels.get(&id).cloned());
+                    if let Some(target) = target {
+                        let path = match target.strip_prefix('/') {
+                            Some(absolute) => absolute.to_string(),
+                            None => format!("word/{}", target),
+                        };
+                        if let Some(url) = zip.read(&path).and_then(|data| resolve_image(&path, &data)) {
+                            para.text.push_str(&format!("![]({})", url));
+                            images.push(url);
+                        }
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+        match event {
+            Event::Text(t) if in_text => run.push_str(&t.unescape().map_err(|_| invalid())?),
+            Event::End(e) => match e.name().as_ref() {
+                b"w:t" => in_text = false,
+                b"w:r" => {
+                    in_run = false;
+                    let text = run.trim();
+                    if text.is_empty() {
+                        para.text.push_str(&run);
+                    } else {
+                        let marker = match (bold, italic) {
+                            (true, true) => "***",
+                            (true, false) => "**",
+                            (false, true) => "*",
+                            _ => "",
+                        };
+                        if run.starts_with(' ') {
+                            para.text.push(' ');
+                        }
+                        para.text.push_str(&format!("{}{}{}", marker, text, marker));
+                        if run.ends_with(' ') {
+                            para.text.push(' ');
+                        }
+                    }
+                }
+                b"w:hyperlink" => {
+                    if let Some((target, start)) = link.take() {
+                        let inner = para.text.split_off(start);
+                        let inner = inner.trim();
+                        para.text.push_str(&if inner.is_empty() { format!("<{}>", target) } else { format!("[{}]({})", inner, target) });
+                    }
+                }
+                b"w:p" => {
+                    if para.heading == Some(1) && title.is_none() && !para.text.trim().is_empty() {
+                        title = Some(para.text.trim().replace('*', ""));
+                    }
+                    paragraphs.push(std::mem::take(&mut para));
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    let mut markdown = String::new();
+    let mut previous_was_list = false;
+    for p in paragraphs {
+        let text = p.text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        let line = match (p.heading, p.list_level) {
+            (Some(level), _) => format!("{} {}", "#".repeat(level), text),
+            (None, Some(level)) => format!("{}- {}", "  ".repeat(level), text),
+            _ => text.to_string(),
+        };
+        if !markdown.is_empty() {
+            markdown.push_str(if previous_was_list && p.list_level.is_some() { "\n" } else { "\n\n" });
+        }
+        previous_was_list = p.list_level.is_some() && p.heading.is_none();
+        markdown.push_str(&line);
+    }
+    Ok(Converted { title, markdown: tidy(&markdown), images })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// Deflated zip of `entries`, each claiming `declared_size(data)` uncompressed bytes
+    fn build_zip(entries: &[(&str, &[u8])], declared_size: impl Fn(&[u8]) -> u32) -> Vec<u8> {
+        let (mut out, mut directory) = (Vec::new(), Vec::new());
+        for (name, data) in entries {
+            let mut encoder = flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data).unwrap();
+            let compressed = encoder.finish().unwrap();
+            let mut crc = flate2::Crc::new();
+            crc.update(data);
+            let mut fields = Vec::new();
+            for value in [crc.sum(), compressed.len() as u32, declared_size(data)] {
+                fields.extend(value.to_le_bytes());
+            }
+            fields.extend((name.len() as u16).to_le_bytes());
+            fields.extend(0u16.to_le_bytes());
+            // version needed, flags, method, time, date
+            let header: Vec<u8> = [20u16, 0, 8, 0, 0].iter().flat_map(|v| v.to_le_bytes()).collect();
+
+            directory.extend(b"PK\x01\x02\x14\x00");
+            directory.extend(&header);
+            directory.extend(&fields);
+            directory.extend([0u8; 10]); // comment length, disk, attributes
+            directory.extend((out.len() as u32).to_le_bytes());
+            directory.extend(name.as_bytes());
+
+            out.extend(b"PK\x03\x04");
+            out.extend(&header);
+            out.extend(&fields);
+            out.extend(name.as_bytes());
+            out.extend(&compressed);
+        }
+        let offset = out.len() as u32;
+        out.extend(&directory);
+        out.extend(b"PK\x05\x06\x00\x00\x00\x00");
+        out.extend((entries.len() as u16).to_le_bytes());
+        out.extend((entries.len() as u16).to_le_bytes());
+        out.extend((directory.len() as u32).to_le_bytes());
+        out.extend(offset.to_le_bytes());
+        out.extend(0u16.to_le_bytes());
+        out
+    }
+
+    const CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Default Extension="png" ContentType="image/png"/><Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/></Types>"#;
+    const PACKAGE_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/></Relationships>"#;
+    const DOCUMENT_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId5" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/hyperlink" Target="https://example.com/" TargetMode="External"/><Relationship Id="rId6" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/image" Target="media/image1.png"/></Relationships>"#;
+    const DOCUMENT: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships" xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main"><w:body>
+<w:p><w:pPr><w:pStyle w:val="Title"/></w:pPr><w:r><w:t>Trip notes</w:t></w:r></w:p>
+<w:p><w:r><w:t xml:space="preserve">Pack the </w:t></w:r><w:r><w:rPr><w:b/></w:rPr><w:t>passport</w:t></w:r><w:r><w:t xml:space="preserve"> and read </w:t></w:r><w:hyperlink r:id="rId5"><w:r><w:t>the guide</w:t></w:r></w:hyperlink></w:p>
+<w:p><w:pPr><w:numPr><w:ilvl w:val="0"/><w:numId w:val="1"/></w:numPr></w:pPr><w:r><w:t>Tickets</w:t></w:r></w:p>
+<w:p><w:r><w:drawing><a:graphic><a:graphicData><a:blip r:embed="rId6"/></a:graphicData></a:graphic></w:drawing></w:r></w:p>
+</w:body></w:document>"#;
+
+    fn docx() -> Vec<u8> {
+        build_zip(
+            &[
+                ("[Content_Types].xml", CONTENT_TYPES.as_bytes()),
+                ("_rels/.rels", PACKAGE_RELS.as_bytes()),
+                ("word/document.xml", DOCUMENT.as_bytes()),
+                ("word/_rels/document.xml.rels", DOCUMENT_RELS.as_bytes()),
+                ("word/media/image1.png", b"\x89PNG\r\n\x1a\n"),
+            ],
+            |data| data.len() as u32,
+        )
+    }
+
+    fn convert(bytes: &[u8]) -> Result<Converted, BrainboxError> {
+        docx_to_markdown(bytes, &mut |path, data| {
+            assert_eq!(data, b"\x89PNG\r\n\x1a\n");
+            Some(format!("cache/{}", path.rsplit('/').next().unwrap_or(path)))
+        })
+    }
+
+    #[test]
+    fn docx_becomes_markdown() {
+        let converted = convert(&docx()).unwrap();
+        assert_eq!(converted.title.as_deref(), Some("Trip notes"));
+        assert_eq!(converted.images, vec!["cache/image1.png".to_string()]);
+        assert_eq!(
+            converted.markdown,
+            "# Trip notes\n\nPack the **passport** and read [the guide](https://example.com/)\n\n- Tickets\n\n![](cache/image1.png)"
+        );
+    }
+
+    #[test]
+    fn truncated_docx_is_rejected() {
+        let bytes = docx();
+        for len in [0, 30, bytes.len() / 2, bytes.len() - 1] {
+            assert!(convert(&bytes[..len]).is_err(), "accepted {} of {} bytes", len, bytes.len());
+        }
+        // Directory intact, but the entries it points at are gone
+        let eocd = bytes.len() - 22;
+        let mut gutted = bytes[..100].to_vec();
+        gutted.extend(&bytes[eocd..]);
+        assert!(convert(&gutted).is_err());
+    }
+
+    #[test]
+    fn oversized_entry_is_not_inflated() {
+        let document = |declared: &dyn Fn(&[u8]) -> u32| {
+            let parts = [
+                ("word/document.xml", DOCUMENT.as_bytes()),
+                ("word/_rels/document.xml.rels", DOCUMENT_RELS.as_bytes()),
+            ];
+            build_zip(&parts, declared)
+        };
+        let over_cap = document(&|_| MAX_ZIP_ENTRY_BYTES as u32 + 1);
+        assert!(convert(&over_cap).is_err());
+
+        // A bomb claiming to be small is cut off one byte past its claim
+        let bomb = build_zip(&[("word/document.xml", &vec![b' '; 1 << 20])], |_| 1024);
+        assert!(convert(&bomb).is_err());
+
+        let honest = document(&|data| data.len() as u32);
+        assert!(convert(&honest).is_ok());
+    }
+}
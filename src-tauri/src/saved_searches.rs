@@ -0,0 +1,163 @@
+// saved_searches.rs - Saved searches (smart collections) for brainbox
+// Only the query and filters are stored; results are evaluated against the search index each time the view is opened.
+// Recent queries typed into the search box are kept in settings for the suggestion dropdown.
+
+use rusqlite::{params, Connection, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::error::BrainboxError;
+use crate::search::{SearchFilters, SearchResult};
+use crate::settings;
+
+/// Results returned by run() when no limit is given
+pub const DEFAULT_RESULT_LIMIT: usize = 100;
+
+const RECENT_QUERIES_KEY: &str = "search.recent_queries";
+/// Recent queries kept, newest first
+const MAX_RECENT_QUERIES: usize = 20;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SavedSearch {
+    pub id: i64,
+    pub name: String,
+    pub query: String,
+    pub filters: SearchFilters,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+const SAVED_SEARCH_COLUMNS: &str = "id, name, query, filters, created_at, updated_at";
+
+impl SavedSearch {
+    pub fn create_table(conn: &Connection) -> Result<()> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS saved_searches (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                query TEXT NOT NULL,
+                filters TEXT NOT NULL DEFAULT '{}',
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+        Ok(())
+    }
+
+    fn from_row(row: &rusqlite::Row) -> Result<SavedSearch> {
+        let filters: String = row.get(3)?;
+        Ok(SavedSearch {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            query: row.get(2)?,
+            // Unknown or malformed filters (e.g. from a newer version) degrade to no filters
+            filters: serde_json::from_str(&filters).unwrap_or_default(),
+            created_at: row.get(4)?,
+            updated_at: row.get(5)?,
+        })
+    }
+
+    pub fn insert(conn: &Connection, name: &str, query: &str, filters: &SearchFilters) -> Result<SavedSearch> {
+        Self::create_table(conn)?;
+        let now = chrono::Utc::now().to_rfc3339();
+        let filters_json = serde_json::to_string(filters).unwrap_or_else(|_| "{}".to_string());
+        conn.execute(
+            "INSERT INTO saved_searches (name, query, filters, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![name, query, filters_json, now, now],
+        )?;
+        Ok(SavedSearch {
+            id: conn.last_insert_rowid(),
+            name: name.to_string(),
+            query: query.to_string(),
+            filters: filters.clone(),
+            created_at: now.clone(),
+            updated_at: now,
+        })
+    }
+
+    pub fn list(conn: &Connection) -> Result<Vec<SavedSearch>> {
+        Self::create_table(conn)?;
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM saved_searches ORDER BY name COLLATE NOCASE ASC", SAVED_SEARCH_COLUMNS))?;
+        let rows = stmt.query_map([], Self::from_row)?;
+        rows.collect()
+    }
+
+    pub fn get_by_id(conn: &Connection, search_id: i64) -> Result<Option<SavedSearch>> {
+        Self::create_table(conn)?;
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM saved_searches WHERE id = ?1", SAVED_SEARCH_COLUMNS))?;
+        let mut rows = stmt.query([search_id])?;
+        if let Some(row) = rows.next()? {
+            Ok(Some(Self::from_row(row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn update(conn: &Connection, search_id: i64, name: &str, query: &str, filters: &SearchFilters) -> Result<usize> {
+        Self::create_table(conn)?;
+        let filters_json = serde_json::to_string(filters).unwrap_or_else(|_| "{}".to_string());
+        conn.execute(
+            "UPDATE saved_searches SET name = ?1, query = ?2, filters = ?3, updated_at = ?4 WHERE id = ?5",
+            params![name, query, filters_json, chrono::Utc::now().to_rfc3339(), search_id],
+        )
+    }
+
+    pub fn delete(conn: &Connection, search_id: i64) -> Result<usize> {
+        Self::create_table(conn)?;
+        conn.execute("DELETE FROM saved_searches WHERE id = ?1", [search_id])
+    }
+}
+
+/// Evaluate a saved search
+pub fn run(conn: &Connection, saved: &SavedSearch, limit: usize) -> std::result::Result<Vec<SearchResult>, BrainboxError> {
+    run_filtered(conn, &saved.query, &saved.filters, limit)
+}
+
+/// Filtered search. The index doesn't know which vault an item is in or its color, so those filters
+/// are applied against the database, which also drops hits for items deleted since they were indexed.
+pub fn run_filtered(conn: &Connection, query: &str, filters: &SearchFilters, limit: usize) -> std::result::Result<Vec<SearchResult>, BrainboxError> {
+    let color = filters.color.as_deref().map(|c| c.trim().to_lowercase()).filter(|c| !c.is_empty());
+    if filters.vault_id.is_none() && color.is_none() {
+        return crate::search::search_filtered(query, limit, filters);
+    }
+    let candidates = crate::search::search_filtered(query, limit.saturating_mul(5).max(500), filters)?;
+    let mut stmt = conn.prepare(
+        "SELECT 1 FROM vault_items WHERE id = ?1 AND (?2 IS NULL OR vault_id = ?2) AND (?3 IS NULL OR color = ?3) AND deleted_at IS NULL",
+    )?;
+    let mut results = Vec::new();
+    for result in candidates {
+        let Ok(item_id) = result.id.parse::<i64>() else { continue };
+        if stmt.exists(params![item_id, filters.vault_id, color])? {
+            results.push(result);
+            if results.len() >= limit {
+                break;
+            }
+        }
+    }
+    Ok(results)
+}
+
+pub fn recent_queries(conn: &Connection) -> std::result::Result<Vec<String>, BrainboxError> {
+    Ok(settings::get(conn, RECENT_QUERIES_KEY)?
+        .and_then(|v| serde_json::from_str(&v).ok())
+        .unwrap_or_default())
+}
+
+/// Move `query` to the front of the recent list, dropping the oldest beyond MAX_RECENT_QUERIES
+pub fn record_recent_query(conn: &Connection, query: &str) -> std::result::Result<(), BrainboxError> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Ok(());
+    }
+    let mut recent = recent_queries(conn)?;
+    recent.retain(|q| !q.eq_ignore_ascii_case(query));
+    recent.insert(0, query.to_string());
+    recent.truncate(MAX_RECENT_QUERIES);
+    settings::set(conn, RECENT_QUERIES_KEY, &serde_json::to_string(&recent)?)?;
+    Ok(())
+}
+
+pub fn clear_recent_queries(conn: &Connection) -> std::result::Result<(), BrainboxError> {
+    settings::delete(conn, RECENT_QUERIES_KEY)?;
+    Ok(())
+}
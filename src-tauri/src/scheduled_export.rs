@@ -0,0 +1,390 @@
+// scheduled_export.rs - Nightly exports to a folder of the user's choosing
+// A safety net independent of sync: once a day at the configured time, or at the next launch when the app was
+// closed then, every vault whose key is available (no password, or unlocked) is written to a dated file. The file
+// is the JSON import format, a zip of markdown notes, or that JSON sealed under a backup password. The newest
+// `keep` exports are kept and older ones deleted.
+
+use base64::Engine;
+use chrono::{DateTime, Local, NaiveTime, TimeZone, Utc};
+use rand::{rngs::OsRng, RngCore};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+
+use crate::error::BrainboxError;
+use crate::settings;
+use crate::vault::Vault;
+
+const ENABLED_KEY: &str = "scheduled_export.enabled";
+const FOLDER_KEY: &str = "scheduled_export.folder";
+const FORMAT_KEY: &str = "scheduled_export.format";
+const TIME_KEY: &str = "scheduled_export.time";
+const KEEP_KEY: &str = "scheduled_export.keep";
+/// "<salt>:<key>" derived from the backup password, both base64; a secret setting
+pub const PASSWORD_KEY: &str = "scheduled_export.key";
+const LAST_RUN_KEY: &str = "scheduled_export.last_run";
+const LAST_ERROR_KEY: &str = "scheduled_export.last_error";
+
+pub const FORMATS: &[&str] = &["json", "markdown", "encrypted"];
+const FILE_PREFIX: &str = "brainbox-export-";
+const BACKUP_FORMAT: &str = "brainbox-backup";
+const BACKUP_VERSION: u32 = 1;
+/// Exports sit in folders that are often synced to the cloud, so the password is stretched like a shared note's
+const KDF_ITERATIONS: u32 = 310_000;
+const MAX_KEEP: u32 = 365;
+const CHECK_INTERVAL: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScheduleConfig {
+    pub enabled: bool,
+    pub folder: Option<String>,
+    /// One of FORMATS
+    pub format: String,
+    /// Local time of day, "HH:MM"
+    pub time: String,
+    /// Exports kept in the folder
+    pub keep: u32,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ScheduleStatus {
+    #[serde(flatten)]
+    pub config: ScheduleConfig,
+    /// A backup password is set, so the encrypted format can be used
+    pub has_password: bool,
+    pub last_run: Option<String>,
+    pub last_error: Option<String>,
+    pub next_run: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ScheduledExportResult {
+    pub path: String,
+    pub vaults: usize,
+    pub items: usize,
+    /// Password-protected vaults that were locked, so were left out
+    pub skipped_vaults: Vec<String>,
+    /// Older exports deleted to stay within `keep`
+    pub removed: Vec<String>,
+}
+
+/// An encrypted export; `data` is the sealed JSON export
+#[derive(Serialize, Deserialize)]
+struct BackupFile {
+    format: String,
+    version: u32,
+    created_at: String,
+    salt: String,
+    iterations: u32,
+    data: String,
+}
+
+fn parse_time(time: &str) -> Result<NaiveTime, BrainboxError> {
+    NaiveTime::parse_from_str(time.trim(), "%H:%M")
+        .map_err(|_| BrainboxError::InvalidInput(format!("Invalid time of day: {} (use HH:MM)", time)))
+}
+
+pub fn config(conn: &Connection) -> Result<ScheduleConfig, BrainboxError> {
+    Ok(ScheduleConfig {
+        enabled: settings::get_bool(conn, ENABLED_KEY)?,
+        folder: settings::get(conn, FOLDER_KEY)?.filter(|folder| !folder.trim().is_empty()),
+        format: settings::get_or_default(conn, FORMAT_KEY)?.unwrap_or_else(|| FORMATS[0].to_string()),
+        time: settings::get_or_default(conn, TIME_KEY)?.unwrap_or_default(),
+        keep: settings::get_parsed(conn, KEEP_KEY)?.unwrap_or(7),
+    })
+}
+
+fn last_run(conn: &Connection) -> Result<Option<DateTime<Utc>>, BrainboxError> {
+    Ok(settings::get(conn, LAST_RUN_KEY)?
+        .and_then(|at| DateTime::parse_from_rfc3339(&at).ok())
+        .map(|at| at.with_timezone(&Utc)))
+}
+
+/// Today's slot at `time`; None in the hour skipped by a DST change
+fn slot_on(day: chrono::NaiveDate, time: NaiveTime) -> Option<DateTime<Local>> {
+    Local.from_local_datetime(&day.and_time(time)).earliest()
+}
+
+/// When the next export is due: today's slot unless it already ran, else tomorrow's
+fn next_run(config: &ScheduleConfig, last_run: Option<DateTime<Utc>>, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    if !config.enabled || config.folder.is_none() {
+        return None;
+    }
+    let time = parse_time(&config.time).ok()?;
+    let today = slot_on(now.date_naive(), time)?;
+    if last_run.is_none_or(|last| last < today.with_timezone(&Utc)) {
+        Some(today)
+    } else {
+        slot_on(now.date_naive().succ_opt()?, time)
+    }
+}
+
+pub fn status(conn: &Connection) -> Result<ScheduleStatus, BrainboxError> {
+    let config = config(conn)?;
+    let last = last_run(conn)?;
+    Ok(ScheduleStatus {
+        next_run: next_run(&config, last, Local::now()).map(|at| at.to_rfc3339()),
+        has_password: settings::get(conn, PASSWORD_KEY)?.is_some(),
+        last_run: last.map(|at| at.to_rfc3339()),
+        last_error: settings::get(conn, LAST_ERROR_KEY)?,
+        config,
+    })
+}
+
+pub fn set_config(conn: &Connection, config: &ScheduleConfig) -> Result<ScheduleStatus, BrainboxError> {
+    if !FORMATS.contains(&config.format.as_str()) {
+        return Err(BrainboxError::InvalidInput(format!("Unknown export format: {}", config.format)));
+    }
+    let time = parse_time(&config.time)?;
+    if !(1..=MAX_KEEP).contains(&config.keep) {
+        return Err(BrainboxError::InvalidInput(format!("Keep between 1 and {} exports", MAX_KEEP)));
+    }
+    let folder = config.folder.as_deref().map(str::trim).filter(|folder| !folder.is_empty());
+    if let Some(folder) = folder {
+        if !Path::new(folder).is_absolute() {
+            return Err(BrainboxError::InvalidInput("Choose a full folder path".to_string()));
+        }
+    }
+    if config.enabled && folder.is_none() {
+        return Err(BrainboxError::InvalidInput("Choose a folder for the exports".to_string()));
+    }
+    if config.enabled && config.format == "encrypted" && settings::get(conn, PASSWORD_KEY)?.is_none() {
+        return Err(BrainboxError::InvalidInput("Set a backup password for encrypted exports".to_string()));
+    }
+    settings::set_bool(conn, ENABLED_KEY, config.enabled)?;
+    match folder {
+        Some(folder) => settings::set(conn, FOLDER_KEY, folder)?,
+        None => settings::delete(conn, FOLDER_KEY)?,
+    }
+    settings::set(conn, FORMAT_KEY, &config.format)?;
+    settings::set(conn, TIME_KEY, &time.format("%H:%M").to_string())?;
+    settings::set_value(conn, KEEP_KEY, config.keep)?;
+    status(conn)
+}
+
+fn backup_key(password: &str, salt: &[u8], iterations: u32) -> crate::keystore::VaultKey {
+    crate::share::bundle_key(password, salt, iterations)
+}
+
+/// Set the password encrypted exports are sealed with, or clear it with None. Only a key derived from it is kept.
+pub fn set_password(conn: &Connection, password: Option<&str>) -> Result<(), BrainboxError> {
+    let Some(password) = password else {
+        if settings::get_or_default(conn, FORMAT_KEY)?.as_deref() == Some("encrypted") && settings::get_bool(conn, ENABLED_KEY)? {
+            return Err(BrainboxError::InvalidInput("Switch scheduled exports to another format first".to_string()));
+        }
+        return Ok(settings::delete(conn, PASSWORD_KEY)?);
+    };
+    if password.is_empty() {
+        return Err(BrainboxError::InvalidInput("The backup password can't be empty".to_string()));
+    }
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    let key = backup_key(password, &salt, KDF_ITERATIONS);
+    let b64 = base64::engine::general_purpose::STANDARD;
+    settings::set(conn, PASSWORD_KEY, &format!("{}:{}", b64.encode(salt), b64.encode(key.as_slice())))?;
+    Ok(())
+}
+
+/// Decrypt an encrypted export into the JSON `import_vaults` reads. A wrong password is `InvalidKey`.
+pub fn read_backup(path: &Path, password: &str) -> Result<String, BrainboxError> {
+    let backup: BackupFile = serde_json::from_slice(&std::fs::read(path)?)
+        .map_err(|e| BrainboxError::InvalidInput(format!("Not a brainbox backup: {}", e)))?;
+    if backup.format != BACKUP_FORMAT {
+        return Err(BrainboxError::InvalidInput("Not a brainbox backup".to_string()));
+    }
+    if backup.version > BACKUP_VERSION {
+        return Err(BrainboxError::InvalidInput(format!(
+            "Backup version {} is newer than this brainbox supports",
+            backup.version
+        )));
+    }
+    let b64 = base64::engine::general_purpose::STANDARD;
+    let corrupt = || BrainboxError::InvalidInput("Corrupt backup file".to_string());
+    let salt = b64.decode(&backup.salt).map_err(|_| corrupt())?;
+    let data = b64.decode(&backup.data).map_err(|_| corrupt())?;
+    let key = backup_key(password, &salt, backup.iterations);
+    let plaintext = crate::envelope::open(&key, &data)?;
+    String::from_utf8(plaintext).map_err(|_| corrupt())
+}
+
+fn seal(conn: &Connection, json: &[u8]) -> Result<Vec<u8>, BrainboxError> {
+    let stored = settings::get(conn, PASSWORD_KEY)?.ok_or("No backup password is set")?;
+    let b64 = base64::engine::general_purpose::STANDARD;
+    let (salt, key) = stored.split_once(':').ok_or("Corrupt backup password setting")?;
+    let key: [u8; 32] = b64
+        .decode(key)
+        .ok()
+        .and_then(|key| key.try_into().ok())
+        .ok_or("Corrupt backup password setting")?;
+    let key = zeroize::Zeroizing::new(key);
+    let backup = BackupFile {
+        format: BACKUP_FORMAT.to_string(),
+        version: BACKUP_VERSION,
+        created_at: Utc::now().to_rfc3339(),
+        salt: salt.to_string(),
+        iterations: KDF_ITERATIONS,
+        data: b64.encode(crate::envelope::seal(&key, json)?),
+    };
+    Ok(serde_json::to_vec_pretty(&backup)?)
+}
+
+/// A title or folder name that is safe on every OS
+fn file_stem(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| if c.is_control() || r#"<>:"/\|?*"#.contains(c) { '_' } else { c })
+        .take(100)
+        .collect();
+    let cleaned = cleaned.trim().trim_end_matches('.').to_string();
+    if cleaned.is_empty() {
+        "Untitled".to_string()
+    } else {
+        cleaned
+    }
+}
+
+/// One markdown file per item, in a folder per vault and collection, with the metadata as front matter
+fn markdown_zip(export: &crate::ExportData) -> Result<Vec<u8>, BrainboxError> {
+    let mut zip = crate::zip_writer::ZipWriter::new(Vec::new());
+    for vault in &export.vaults {
+        let collections: HashMap<&str, &crate::ExportedCollection> =
+            vault.collections.iter().map(|c| (c.uuid.as_str(), c)).collect();
+        let mut used = std::collections::HashSet::new();
+        for item in &vault.items {
+            let mut folders = Vec::new();
+            let mut next = item.collection_uuid.as_deref();
+            // Parent links come from the file; the depth cap guards against a cycle
+            while let Some(collection) = next.and_then(|uuid| collections.get(uuid)).filter(|_| folders.len() < 32) {
+                folders.push(file_stem(&collection.name));
+                next = collection.parent_uuid.as_deref();
+            }
+            folders.push(file_stem(&vault.name));
+            folders.reverse();
+            let folder = folders.join("/");
+            let stem = file_stem(&item.title);
+            let mut path = format!("{}/{}.md", folder, stem);
+            let mut n = 2;
+            while !used.insert(path.clone()) {
+                path = format!("{}/{} {}.md", folder, stem, n);
+                n += 1;
+            }
+            let mut front = vec![
+                format!("title: {}", serde_json::to_string(&item.title)?),
+                format!("created: {}", item.created_at),
+                format!("updated: {}", item.updated_at),
+            ];
+            if let Some(item_type) = &item.item_type {
+                front.push(format!("type: {}", item_type));
+            }
+            if !item.tags.is_empty() {
+                front.push(format!("tags: {}", serde_json::to_string(&item.tags)?));
+            }
+            let note = format!("---\n{}\n---\n\n{}\n", front.join("\n"), item.content);
+            zip.add(&path, note.as_bytes())?;
+        }
+    }
+    Ok(zip.finish()?)
+}
+
+/// Our exports in `folder`, oldest first; names carry the date, so sorting by name sorts by age
+fn existing_exports(folder: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(folder)
+        .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+        .unwrap_or_default();
+    files.retain(|path| {
+        let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+        name.starts_with(FILE_PREFIX) && [".json", ".zip", ".brainbox-backup"].iter().any(|ext| name.ends_with(ext))
+    });
+    files.sort();
+    files
+}
+
+/// Export now with the saved settings, whether or not the schedule is on
+pub fn run(conn: &Connection) -> Result<ScheduledExportResult, BrainboxError> {
+    let config = config(conn)?;
+    let folder = PathBuf::from(config.folder.as_deref().ok_or("Choose a folder for the exports")?);
+    std::fs::create_dir_all(&folder)?;
+
+    let mut vault_ids = Vec::new();
+    let mut skipped_vaults = Vec::new();
+    for vault in Vault::list(conn)? {
+        if vault.has_password && crate::keystore::get(vault.id).is_none() {
+            skipped_vaults.push(vault.name);
+        } else {
+            vault_ids.push(vault.id);
+        }
+    }
+    let keys = vec![None; vault_ids.len()];
+    let export = crate::build_export(conn, &vault_ids, keys, &crate::progress::Operation::silent())?;
+    let items = export.vaults.iter().map(|vault| vault.items.len()).sum();
+
+    let stamp = Local::now().format("%Y-%m-%d-%H%M");
+    let (extension, bytes) = match config.format.as_str() {
+        "markdown" => ("zip", markdown_zip(&export)?),
+        "encrypted" => ("brainbox-backup", seal(conn, &zeroize::Zeroizing::new(serde_json::to_vec(&export)?))?),
+        _ => ("json", serde_json::to_vec_pretty(&export)?),
+    };
+    let path = folder.join(format!("{}{}.{}", FILE_PREFIX, stamp, extension));
+    crate::image_cache::write_atomic(&path, &bytes)?;
+
+    let existing = existing_exports(&folder);
+    let mut removed = Vec::new();
+    for old in existing.iter().take(existing.len().saturating_sub(config.keep.max(1) as usize)) {
+        match std::fs::remove_file(old) {
+            Ok(()) => removed.push(old.to_string_lossy().to_string()),
+            Err(e) => tracing::warn!("Failed to remove old export {}: {}", old.display(), e),
+        }
+    }
+    tracing::info!("Scheduled export of {} vaults written to {}", vault_ids.len(), path.display());
+    Ok(ScheduledExportResult {
+        path: path.to_string_lossy().to_string(),
+        vaults: vault_ids.len(),
+        items,
+        skipped_vaults,
+        removed,
+    })
+}
+
+/// Run and record the outcome, for the scheduler and "export now"
+pub fn run_and_record(conn: &Connection) -> Result<ScheduledExportResult, BrainboxError> {
+    let result = run(conn);
+    settings::set(conn, LAST_RUN_KEY, &Utc::now().to_rfc3339())?;
+    match &result {
+        Ok(_) => settings::delete(conn, LAST_ERROR_KEY)?,
+        Err(e) => settings::set(conn, LAST_ERROR_KEY, &e.to_string())?,
+    }
+    result
+}
+
+fn run_if_due(app: &AppHandle) -> Result<(), BrainboxError> {
+    let conn = crate::db::open()?;
+    let config = config(&conn)?;
+    let Some(due) = next_run(&config, last_run(&conn)?, Local::now()) else { return Ok(()) };
+    if due > Local::now() {
+        return Ok(());
+    }
+    if let Err(e) = run_and_record(&conn) {
+        tracing::error!("Scheduled export failed: {}", e);
+        let _ = app
+            .notification()
+            .builder()
+            .title(crate::i18n::tr("Scheduled export failed"))
+            .body(e.localized_message())
+            .show();
+    }
+    Ok(())
+}
+
+/// Check every few minutes whether an export is due
+pub fn spawn_scheduler(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        if let Err(e) = run_if_due(&app) {
+            tracing::error!("Scheduled export check failed: {}", e);
+        }
+        std::thread::sleep(CHECK_INTERVAL);
+    });
+}
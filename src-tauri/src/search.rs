@@ -1,10 +1,12 @@
+use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use tantivy::collector::TopDocs;
-use tantivy::query::QueryParser;
-use tantivy::schema::{Schema, Field, TEXT, STORED, Value};
-use tantivy::{IndexReader, ReloadPolicy, TantivyDocument};
+use tantivy::query::{AllQuery, BooleanQuery, Occur, PhraseQuery, Query, QueryParser, TermQuery};
+use tantivy::schema::{Schema, Field, IndexRecordOption, TextFieldIndexing, TextOptions, STRING, TEXT, STORED, Value};
+use tantivy::{DocAddress, IndexReader, ReloadPolicy, Searcher, TantivyDocument};
 use tantivy::doc;
 
 #[cfg(target_os = "macos")]
@@ -14,6 +16,29 @@ use std::thread;
 
 use serde::{Serialize, Deserialize};
 
+use crate::error::BrainboxError;
+use crate::keystore;
+use crate::settings;
+use crate::tokenizers::{self, SearchLanguages};
+use crate::vault::{Vault, VaultItem};
+
+/// Records which schema version and tokenizers the index on disk was built with
+const LANGUAGE_MARKER: &str = "brainbox_languages.json";
+/// Bumped whenever fields are added to the schema; older indexes are cleared and rebuilt
+pub const SCHEMA_VERSION: u32 = 2;
+
+#[derive(Serialize, Deserialize, Default)]
+struct IndexMarker {
+    /// Missing in markers written before the schema was versioned
+    #[serde(default)]
+    schema_version: u32,
+    #[serde(flatten)]
+    languages: SearchLanguages,
+}
+
+/// Set when the index was cleared because the tokenizer settings changed, until the frontend rebuilds it
+static NEEDS_REBUILD: AtomicBool = AtomicBool::new(false);
+
 // Search result item
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SearchResult {
@@ -34,7 +59,73 @@ pub struct SearchResultMetadata {
     pub tags: Vec<String>,
 }
 
+/// Structured filters applied on top of a text query (used by saved searches).
+/// Dates accept RFC 3339 or YYYY-MM-DD; tags must all be present.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SearchFilters {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub item_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vault_id: Option<i64>,
+    /// Only items updated in the last N days
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_within_days: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_after: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_before: Option<String>,
+    /// Only items with this label color (#rgb or #rrggbb)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+}
+
+/// Parse a filter date; a bare date means midnight UTC
+fn parse_filter_date(value: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    let value = value.trim();
+    if let Ok(t) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Some(t.with_timezone(&chrono::Utc));
+    }
+    let date = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+    Some(date.and_hms_opt(0, 0, 0)?.and_utc())
+}
+
+/// Upper bound on hits fetched when date filters are applied after scoring
+const MAX_FILTERED_CANDIDATES: usize = 5000;
+
 // Fields for the search schema
+/// One item as the index stores it
+#[derive(Debug, Clone, Copy)]
+pub struct IndexDoc<'a> {
+    pub id: &'a str,
+    pub title: &'a str,
+    pub content: &'a str,
+    pub item_type: &'a str,
+    pub created_at: &'a str,
+    pub updated_at: &'a str,
+    pub path: Option<&'a str>,
+    pub tags: &'a [&'a str],
+    pub vault_id: Option<i64>,
+}
+
+/// A document to index, as the frontend and the commands that create items send it
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct DocumentInput {
+    pub id: String,
+    pub title: String,
+    pub content: String,
+    pub item_type: String,
+    pub created_at: String,
+    pub updated_at: String,
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub vault_id: Option<i64>,
+}
+
 #[derive(Debug, Clone)]
 pub struct SearchFields {
     pub id: Field,
@@ -45,6 +136,7 @@ pub struct SearchFields {
     pub updated_at: Field,
     pub path: Field,
     pub tags: Field,
+    pub vault_id: Field,
 }
 
 // Search service for managing the Tantivy index
@@ -54,30 +146,72 @@ pub struct SearchService {
     reader: IndexReader,
     fields: SearchFields,
     schema: Schema,
+    index_path: PathBuf,
 }
 
 impl SearchService {
     // Create a new search service with a BM25 configuration
-    pub fn new(index_path: &Path) -> Result<Self, tantivy::TantivyError> {
-        eprintln!("brainbox: Creating search schema...");
-        
+    fn build_schema(languages: &SearchLanguages) -> (Schema, SearchFields) {
         // Create the schema
         let mut schema_builder = Schema::builder();
+
+        // Same as TEXT, with the tokenizer picked for the field's language ("default" is exactly TEXT)
+        let text_options = |language: &str| {
+            TextOptions::default().set_indexing_options(
+                TextFieldIndexing::default()
+                    .set_tokenizer(&tokenizers::tokenizer_name(language))
+                    .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+            )
+        };
         
         // Define the schema fields
         let id = schema_builder.add_text_field("id", TEXT | STORED);
-        let title = schema_builder.add_text_field("title", TEXT | STORED);
-        let content = schema_builder.add_text_field("content", TEXT);
+        let title = schema_builder.add_text_field("title", text_options(&languages.title).set_stored());
+        let content = schema_builder.add_text_field("content", text_options(&languages.content));
         let item_type = schema_builder.add_text_field("item_type", TEXT | STORED);
         let created_at = schema_builder.add_text_field("created_at", TEXT | STORED);
         let updated_at = schema_builder.add_text_field("updated_at", TEXT | STORED);
         let path = schema_builder.add_text_field("path", TEXT | STORED);
         let tags = schema_builder.add_text_field("tags", TEXT | STORED);
+        // Untokenized so every document of a vault can be deleted with one term
+        let vault_id = schema_builder.add_text_field("vault_id", STRING | STORED);
         
         let schema = schema_builder.build();
+        let fields = SearchFields {
+            id,
+            title,
+            content,
+            item_type,
+            created_at,
+            updated_at,
+            path,
+            tags,
+            vault_id,
+        };
+        (schema, fields)
+    }
+
+    pub fn new(index_path: &Path, languages: &SearchLanguages) -> Result<Self, tantivy::TantivyError> {
+        tracing::debug!("Creating search schema...");
+        let (schema, fields) = Self::build_schema(languages);
         
-        eprintln!("brainbox: Creating index directory if needed...");
+        tracing::debug!("Creating index directory if needed...");
         
+        // An index built with other fields or tokenizers has a different schema and can't be opened; start over
+        // (indexes without a marker predate language settings and the vault_id field)
+        let marker = index_path.join(LANGUAGE_MARKER);
+        if index_path.join("meta.json").exists() {
+            let built_with: IndexMarker = fs::read_to_string(&marker)
+                .ok()
+                .and_then(|text| serde_json::from_str(&text).ok())
+                .unwrap_or_default();
+            if built_with.schema_version != SCHEMA_VERSION || built_with.languages != *languages {
+                tracing::warn!("Search schema or languages changed; clearing the index so it can be rebuilt");
+                fs::remove_dir_all(index_path)?;
+                NEEDS_REBUILD.store(true, Ordering::SeqCst);
+            }
+        }
+
         // Create index directory if it doesn't exist
         if !index_path.exists() {
             fs::create_dir_all(index_path)?;
@@ -87,30 +221,24 @@ impl SearchService {
         let index = {
             #[cfg(target_os = "macos")]
             {
-                eprintln!("brainbox: Opening/creating search index with timeout protection (macOS)...");
+                tracing::debug!("Opening/creating search index with timeout protection (macOS)...");
                 Self::create_index_with_timeout(index_path, schema.clone())?
             }
             
             #[cfg(not(target_os = "macos"))]
             {
-                eprintln!("brainbox: Opening/creating search index...");
+                tracing::debug!("Opening/creating search index...");
                 tantivy::Index::open_or_create(tantivy::directory::MmapDirectory::open(index_path)?, schema.clone())?
             }
         };
         
-        // Create the fields structure for easy access
-        let fields = SearchFields {
-            id,
-            title,
-            content,
-            item_type,
-            created_at,
-            updated_at,
-            path,
-            tags,
-        };
-        
-        eprintln!("brainbox: Initializing index writer...");
+        tokenizers::register(&index);
+        let marker_contents = IndexMarker { schema_version: SCHEMA_VERSION, languages: languages.clone() };
+        if let Ok(json) = serde_json::to_string(&marker_contents) {
+            let _ = fs::write(&marker, json);
+        }
+
+        tracing::debug!("Initializing index writer...");
         
         // Initialize the index writer
         let mut index_writer: tantivy::IndexWriter = index.writer(50_000_000)?; // 50MB buffer
@@ -119,20 +247,38 @@ impl SearchService {
         
         index_writer.commit()?;
 
-        eprintln!("brainbox: Creating index reader...");
+        tracing::debug!("Creating index reader...");
         
         // Create the reader (manual reload; we call reload() after commits)
         let reader = index.reader_builder()
             .reload_policy(ReloadPolicy::Manual)
             .try_into()?;
 
-        eprintln!("brainbox: Search service created successfully");
+        tracing::info!("Search service created successfully");
+
+        Ok(SearchService {
+            index,
+            reader,
+            fields,
+            schema,
+            index_path: index_path.to_path_buf(),
+        })
+    }
 
+    /// An index kept only in memory, for the content of an unlocked password-protected vault
+    pub fn in_ram(languages: &SearchLanguages) -> Result<Self, tantivy::TantivyError> {
+        let (schema, fields) = Self::build_schema(languages);
+        let index = tantivy::Index::create_in_ram(schema.clone());
+        tokenizers::register(&index);
+        let reader = index.reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
         Ok(SearchService {
             index,
             reader,
             fields,
             schema,
+            index_path: PathBuf::new(),
         })
     }
 
@@ -153,7 +299,7 @@ impl SearchService {
                     tantivy::Index::open_or_create(dir, schema_clone)
                 },
                 Err(e) => {
-                    eprintln!("brainbox: Failed to open MmapDirectory: {}", e);
+                    tracing::error!("Failed to open MmapDirectory: {}", e);
                     Err(tantivy::TantivyError::from(e))
                 }
             };
@@ -163,15 +309,15 @@ impl SearchService {
         // Wait for result with timeout
         match rx.recv_timeout(Duration::from_secs(10)) {
             Ok(Ok(index)) => {
-                eprintln!("brainbox: Successfully created index with MmapDirectory");
+                tracing::info!("Successfully created index with MmapDirectory");
                 Ok(index)
             },
             Ok(Err(e)) => {
-                eprintln!("brainbox: MmapDirectory failed: {}", e);
+                tracing::error!("MmapDirectory failed: {}", e);
                 Self::create_fallback_index(schema)
             },
             Err(_) => {
-                eprintln!("brainbox: Index creation timed out after 10 seconds, trying fallback...");
+                tracing::warn!("Index creation timed out after 10 seconds, trying fallback...");
                 Self::create_fallback_index(schema)
             }
         }
@@ -180,38 +326,49 @@ impl SearchService {
     // Fallback to RAMDirectory when MmapDirectory fails (macOS-specific)
     #[cfg(target_os = "macos")]
     fn create_fallback_index(schema: Schema) -> Result<tantivy::Index, tantivy::TantivyError> {
-        eprintln!("brainbox: Falling back to RAMDirectory (search index will not persist between sessions)");
+        tracing::warn!("Falling back to RAMDirectory (search index will not persist between sessions)");
         Ok(tantivy::Index::create_in_ram(schema))
     }
     
     // Method to attempt index recovery by clearing corrupted data
     pub fn recover_index(index_path: &Path) -> Result<(), std::io::Error> {
-        eprintln!("brainbox: Attempting to recover search index by clearing corrupted data...");
+        tracing::warn!("Attempting to recover search index by clearing corrupted data...");
         
         if index_path.exists() {
             // Remove the entire index directory
             std::fs::remove_dir_all(index_path)?;
-            eprintln!("brainbox: Removed corrupted index directory");
+            tracing::warn!("Removed corrupted index directory");
         }
         
         // Recreate the directory
         std::fs::create_dir_all(index_path)?;
-        eprintln!("brainbox: Recreated index directory");
+        tracing::info!("Recreated index directory");
         
         Ok(())
     }
 
     // Add or update a document in the index
-    pub fn index_document(&self, 
-        id: &str, 
-        title: &str, 
-        content: &str, 
-        item_type: &str,
-        created_at: &str,
-        updated_at: &str,
-        path: Option<&str>,
-        tags: &[&str]
-    ) -> Result<(), tantivy::TantivyError> {
+    pub fn index_document(&self, doc: &IndexDoc) -> Result<(), tantivy::TantivyError> {
+        let id = doc.id;
+        let doc = self.make_document(doc);
+
+        let mut index_writer: tantivy::IndexWriter = self.index.writer(50_000_000)?;
+        
+        // Delete existing document with same ID if exists
+        let term = tantivy::Term::from_field_text(self.fields.id, id);
+        index_writer.delete_term(term);
+        
+        // Add the new document
+        index_writer.add_document(doc)?;
+        index_writer.commit()?;
+        // Ensure the reader sees the latest commit
+        let _ = self.reader.reload();
+        
+        Ok(())
+    }
+
+    fn make_document(&self, doc: &IndexDoc) -> TantivyDocument {
+        let IndexDoc { id, title, content, item_type, created_at, updated_at, path, tags, vault_id } = *doc;
         // Create a new document using the doc! macro
         let mut doc = doc!(
             self.fields.id => id,
@@ -230,18 +387,21 @@ impl SearchService {
             doc.add_text(self.fields.tags, tag);
         }
 
+        if let Some(vault_id) = vault_id {
+            doc.add_text(self.fields.vault_id, vault_id.to_string());
+        }
+        doc
+    }
+
+    /// Swap every document of a vault for `docs` in one commit
+    fn replace_vault_documents(&self, vault_id: i64, docs: Vec<TantivyDocument>) -> Result<(), tantivy::TantivyError> {
         let mut index_writer: tantivy::IndexWriter = self.index.writer(50_000_000)?;
-        
-        // Delete existing document with same ID if exists
-        let term = tantivy::Term::from_field_text(self.fields.id, id);
-        index_writer.delete_term(term);
-        
-        // Add the new document
-        index_writer.add_document(doc)?;
+        index_writer.delete_term(tantivy::Term::from_field_text(self.fields.vault_id, &vault_id.to_string()));
+        for doc in docs {
+            index_writer.add_document(doc)?;
+        }
         index_writer.commit()?;
-        // Ensure the reader sees the latest commit
         let _ = self.reader.reload();
-        
         Ok(())
     }
 
@@ -255,8 +415,23 @@ impl SearchService {
         Ok(())
     }
 
-    // Search documents using BM25 ranking
-    pub fn search(&self, query_str: &str, limit: usize) -> Result<Vec<SearchResult>, tantivy::TantivyError> {
+    // Search documents using BM25 ranking, optionally restricted to one item type
+    /// Remove every document belonging to a vault; returns how many were deleted
+    pub fn delete_documents_by_vault(&self, vault_id: i64) -> Result<u64, tantivy::TantivyError> {
+        let term = tantivy::Term::from_field_text(self.fields.vault_id, &vault_id.to_string());
+        let _ = self.reader.reload();
+        let matching = self.reader.searcher().doc_freq(&term)?;
+        if matching == 0 {
+            return Ok(0);
+        }
+        let mut index_writer: tantivy::IndexWriter = self.index.writer(50_000_000)?;
+        index_writer.delete_term(term);
+        index_writer.commit()?;
+        let _ = self.reader.reload();
+        Ok(matching)
+    }
+
+    pub fn search(&self, query_str: &str, limit: usize, item_type: Option<&str>) -> Result<Vec<SearchResult>, tantivy::TantivyError> {
         // Best-effort reload so searches see newly committed docs
         let _ = self.reader.reload();
         let searcher = self.reader.searcher();
@@ -271,90 +446,257 @@ impl SearchService {
 
         // Parse query and search
         let query = query_parser.parse_query(query_str)?;
+        let query: Box<dyn Query> = match item_type {
+            Some(t) => {
+                let type_term = tantivy::Term::from_field_text(self.fields.item_type, &t.to_lowercase());
+                Box::new(BooleanQuery::new(vec![
+                    (Occur::Must, query),
+                    (Occur::Must, Box::new(TermQuery::new(type_term, IndexRecordOption::Basic))),
+                ]))
+            }
+            None => query,
+        };
         let top_docs = searcher.search(&query, &TopDocs::with_limit(limit))?;
 
-        // Process results
-        let mut results = Vec::with_capacity(top_docs.len());
+        top_docs
+            .into_iter()
+            .map(|(score, doc_address)| self.to_result(&searcher, score, doc_address))
+            .collect()
+    }
+
+    /// Terms for `text` as the field's tokenizer would index it
+    fn field_terms(&self, field: Field, text: &str) -> Vec<tantivy::Term> {
+        let Ok(mut analyzer) = self.index.tokenizer_for_field(field) else { return Vec::new() };
+        let mut stream = analyzer.token_stream(text);
+        let mut terms = Vec::new();
+        stream.process(&mut |token| terms.push(tantivy::Term::from_field_text(field, &token.text)));
+        terms
+    }
+
+    /// Search with structured filters. An empty query matches everything, newest first.
+    /// Type and tags are part of the index query; date ranges are checked on the stored timestamps.
+    pub fn search_filtered(&self, query_str: &str, limit: usize, filters: &SearchFilters) -> Result<Vec<SearchResult>, tantivy::TantivyError> {
+        let _ = self.reader.reload();
+        let searcher = self.reader.searcher();
+
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+        if query_str.trim().is_empty() {
+            clauses.push((Occur::Must, Box::new(AllQuery)));
+        } else {
+            let mut query_parser = QueryParser::for_index(&self.index, vec![self.fields.title, self.fields.content, self.fields.tags]);
+            query_parser.set_field_boost(self.fields.title, 2.0);
+            query_parser.set_field_boost(self.fields.tags, 1.5);
+            clauses.push((Occur::Must, query_parser.parse_query(query_str)?));
+        }
+        if let Some(t) = filters.item_type.as_deref().filter(|t| !t.is_empty()) {
+            let type_term = tantivy::Term::from_field_text(self.fields.item_type, &t.to_lowercase());
+            clauses.push((Occur::Must, Box::new(TermQuery::new(type_term, IndexRecordOption::Basic))));
+        }
+        for tag in &filters.tags {
+            let mut terms = self.field_terms(self.fields.tags, tag);
+            let query: Box<dyn Query> = match terms.len() {
+                0 => continue,
+                1 => Box::new(TermQuery::new(terms.remove(0), IndexRecordOption::Basic)),
+                _ => Box::new(PhraseQuery::new(terms)),
+            };
+            clauses.push((Occur::Must, query));
+        }
+        let query = BooleanQuery::new(clauses);
+
+        let updated_after = filters
+            .updated_within_days
+            .map(|days| chrono::Utc::now() - chrono::Duration::days(days as i64));
+        let created_after = filters.created_after.as_deref().and_then(parse_filter_date);
+        let created_before = filters.created_before.as_deref().and_then(parse_filter_date);
+        let has_date_filter = updated_after.is_some() || created_after.is_some() || created_before.is_some();
+        let candidates = if has_date_filter { MAX_FILTERED_CANDIDATES.max(limit) } else { limit };
+
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(candidates))?;
+        let mut results = Vec::new();
         for (score, doc_address) in top_docs {
-            // Retrieve the actual document content using the DocAddress
-            let retrieved_doc = searcher.doc::<TantivyDocument>(doc_address)?;
+            let result = self.to_result(&searcher, score, doc_address)?;
+            let updated = parse_filter_date(&result.metadata.updated_at);
+            let created = parse_filter_date(&result.metadata.created_at);
+            let keep = updated_after.is_none_or(|after| updated.is_some_and(|t| t >= after))
+                && created_after.is_none_or(|after| created.is_some_and(|t| t >= after))
+                && created_before.is_none_or(|before| created.is_some_and(|t| t < before));
+            if keep {
+                results.push(result);
+            }
+        }
+        if query_str.trim().is_empty() {
+            results.sort_by(|a, b| b.metadata.updated_at.cmp(&a.metadata.updated_at));
+        }
+        results.truncate(limit);
+        Ok(results)
+    }
 
-            // Convert TantivyDocument to your application's Document struct
-            let id = retrieved_doc
-                .get_first(self.fields.id)
-                .and_then(|f| f.as_str())
-                .unwrap_or_default()
-                .to_string();
-                
-            let title = retrieved_doc
-                .get_first(self.fields.title)
-                .and_then(|f| f.as_str())
-                .unwrap_or_default()
-                .to_string();
-                
-            let item_type = retrieved_doc
-                .get_first(self.fields.item_type)
-                .and_then(|f| f.as_str())
-                .unwrap_or_default()
-                .to_string();
-                
-            let created_at = retrieved_doc
-                .get_first(self.fields.created_at)
-                .and_then(|f| f.as_str())
-                .unwrap_or_default()
-                .to_string();
-                
-            let updated_at = retrieved_doc
-                .get_first(self.fields.updated_at)
+    /// Indexed title and tag terms starting with `prefix`, most common first.
+    /// Counts include documents deleted since the last merge, which is fine for ranking suggestions.
+    pub fn suggest_terms(&self, prefix: &str, limit: usize) -> Result<Vec<(String, u64)>, tantivy::TantivyError> {
+        let _ = self.reader.reload();
+        let searcher = self.reader.searcher();
+        let prefix = prefix.to_lowercase();
+        let mut counts: std::collections::HashMap<String, u64> = std::collections::HashMap::new();
+        for segment in searcher.segment_readers() {
+            for field in [self.fields.title, self.fields.tags] {
+                let inverted = segment.inverted_index(field)?;
+                let mut stream = inverted.terms().range().ge(prefix.as_bytes()).into_stream()?;
+                while stream.advance() {
+                    if !stream.key().starts_with(prefix.as_bytes()) {
+                        break;
+                    }
+                    if let Ok(term) = std::str::from_utf8(stream.key()) {
+                        *counts.entry(term.to_string()).or_default() += stream.value().doc_freq as u64;
+                    }
+                }
+            }
+        }
+        let mut terms: Vec<(String, u64)> = counts.into_iter().collect();
+        terms.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        terms.truncate(limit);
+        Ok(terms)
+    }
+
+    /// Document, segment and on-disk size figures for the index
+    pub fn stats(&self) -> Result<SearchIndexStats, tantivy::TantivyError> {
+        let _ = self.reader.reload();
+        let searcher = self.reader.searcher();
+        let deleted_docs = searcher
+            .segment_readers()
+            .iter()
+            .map(|segment| segment.num_deleted_docs() as u64)
+            .sum();
+        Ok(SearchIndexStats {
+            doc_count: searcher.num_docs(),
+            deleted_docs,
+            segment_count: searcher.segment_readers().len(),
+            disk_size_bytes: dir_size(&self.index_path),
+        })
+    }
+
+    /// Merge all segments into one, dropping deleted documents, then remove files no longer referenced
+    pub fn optimize(&self) -> Result<SearchIndexStats, tantivy::TantivyError> {
+        let mut index_writer: tantivy::IndexWriter = self.index.writer(50_000_000)?;
+        let segment_ids = self.index.searchable_segment_ids()?;
+        if !segment_ids.is_empty() {
+            index_writer.merge(&segment_ids).wait()?;
+        }
+        index_writer.garbage_collect_files().wait()?;
+        index_writer.wait_merging_threads()?;
+        self.stats()
+    }
+
+    fn to_result(&self, searcher: &Searcher, score: f32, doc_address: DocAddress) -> Result<SearchResult, tantivy::TantivyError> {
+        // Retrieve the actual document content using the DocAddress
+        let retrieved_doc = searcher.doc::<TantivyDocument>(doc_address)?;
+        let text = |field: Field| {
+            retrieved_doc
+                .get_first(field)
                 .and_then(|f| f.as_str())
                 .unwrap_or_default()
-                .to_string();
-                
-            let path = retrieved_doc
-                .get_first(self.fields.path)
-                .and_then(|f| f.as_str())
-                .map(|s| s.to_string());
-                
-            let tags: Vec<String> = retrieved_doc
-                .get_all(self.fields.tags)
-                .filter_map(|f| f.as_str().map(|s| s.to_string()))
-                .collect();
+                .to_string()
+        };
+
+        let path = retrieved_doc
+            .get_first(self.fields.path)
+            .and_then(|f| f.as_str())
+            .map(|s| s.to_string());
+
+        let tags: Vec<String> = retrieved_doc
+            .get_all(self.fields.tags)
+            .filter_map(|f| f.as_str().map(|s| s.to_string()))
+            .collect();
 
+        Ok(SearchResult {
+            id: text(self.fields.id),
+            title: text(self.fields.title),
             // Create preview text (simulated since we don't store content)
-            let content_preview = format!("Matched with score: {:.3}", score);
-                
-            let result = SearchResult {
-                id,
-                title,
-                content_preview,
-                score,
-                metadata: SearchResultMetadata {
-                    item_type,
-                    created_at,
-                    updated_at,
-                    path,
-                    tags,
-                },
-            };
-            
-            results.push(result);
-        }
-        
-        Ok(results)
+            content_preview: format!("Matched with score: {:.3}", score),
+            score,
+            metadata: SearchResultMetadata {
+                item_type: text(self.fields.item_type),
+                created_at: text(self.fields.created_at),
+                updated_at: text(self.fields.updated_at),
+                path,
+                tags,
+            },
+        })
     }
 }
 
+#[derive(Debug, Serialize, Clone)]
+pub struct SearchIndexStats {
+    pub doc_count: u64,
+    /// Deleted or replaced documents still taking space until segments are merged
+    pub deleted_docs: u64,
+    pub segment_count: usize,
+    pub disk_size_bytes: u64,
+}
+
+/// Total size of the files in the index directory (0 for the in-memory fallback index)
+fn dir_size(path: &Path) -> u64 {
+    fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| entry.metadata().ok())
+                .filter(|meta| meta.is_file())
+                .map(|meta| meta.len())
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
 // Singleton instance of the search service
 lazy_static::lazy_static! {
     static ref SEARCH_SERVICE: Arc<Mutex<Option<SearchService>>> = Arc::new(Mutex::new(None));
+    /// In-memory indexes holding the content of unlocked password-protected vaults, by vault id
+    static ref SESSION_INDEXES: Mutex<HashMap<i64, SearchService>> = Mutex::new(HashMap::new());
 }
 
-// Initialize the search service
+// Initialize the search service with the configured search languages
 pub fn init_search_service(index_path: &Path) -> Result<(), tantivy::TantivyError> {
-    let service = SearchService::new(index_path)?;
+    let languages = crate::db::open()
+        .and_then(|conn| tokenizers::get_languages(&conn))
+        .unwrap_or_default();
+    let service = SearchService::new(index_path, &languages)?;
+    *SEARCH_SERVICE.lock().unwrap() = Some(service);
+    // Indexes written by older versions hold the content of password-protected vaults
+    if let Err(e) = crate::db::open().and_then(|conn| ensure_locked_vault_indexing(&conn)) {
+        tracing::warn!("Failed to apply the locked vault indexing policy: {}", e);
+    }
+    Ok(())
+}
+
+/// Reopen the index with new tokenizers. Returns true if the index was cleared and needs a rebuild.
+pub fn apply_languages(languages: &SearchLanguages) -> Result<bool, BrainboxError> {
     let mut service_ref = SEARCH_SERVICE.lock().unwrap();
+    let index_path = match &*service_ref {
+        Some(service) => service.index_path.clone(),
+        None => return Err(BrainboxError::Search("Search service not initialized".to_string())),
+    };
+    // Drop the old service first so its memory maps are released before the files are removed
+    *service_ref = None;
+    let service = SearchService::new(&index_path, languages).map_err(|e| BrainboxError::Search(e.to_string()))?;
     *service_ref = Some(service);
-    Ok(())
+    drop(service_ref);
+    // Session indexes were built with the old tokenizers too
+    let unlocked: Vec<i64> = SESSION_INDEXES.lock().unwrap().drain().map(|(vault_id, _)| vault_id).collect();
+    for vault_id in unlocked {
+        open_session(vault_id);
+    }
+    Ok(NEEDS_REBUILD.load(Ordering::SeqCst))
+}
+
+/// Whether the index was cleared (e.g. after a tokenizer change) and should be rebuilt
+pub fn needs_rebuild() -> bool {
+    NEEDS_REBUILD.load(Ordering::SeqCst)
+}
+
+/// Called by the frontend once it has re-added every item
+pub fn mark_rebuilt() {
+    NEEDS_REBUILD.store(false, Ordering::SeqCst);
 }
 
 // Get a reference to the search service
@@ -367,53 +709,407 @@ pub fn get_search_service() -> Option<Arc<SearchService>> {
     }
 }
 
-// Tauri command for searching
+/// Extra candidates fetched when boosting, so frequently opened items just outside the top `limit` can move up
+const BOOST_CANDIDATE_FACTOR: usize = 3;
+
+// Tauri command for searching (runs off the main thread; the index lives on disk).
+// With `boost_by_access`, items opened often in the last few months rank higher.
 #[tauri::command]
-pub fn search(query: String, limit: usize) -> Result<Vec<SearchResult>, String> {
+pub async fn search(query: String, limit: usize, item_type: Option<String>, boost_by_access: Option<bool>) -> Result<Vec<SearchResult>, BrainboxError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let boost = boost_by_access.unwrap_or(false);
+        let candidates = if boost { limit.saturating_mul(BOOST_CANDIDATE_FACTOR) } else { limit };
+        let mut results = {
+            let service_ref = SEARCH_SERVICE.lock().unwrap();
+            match &*service_ref {
+                Some(service) => service.search(&query, candidates, item_type.as_deref()).map_err(|e| BrainboxError::Search(e.to_string()))?,
+                None => return Err(BrainboxError::Search("Search service not initialized".to_string())),
+            }
+        };
+        let unlocked = search_sessions(None, |session| session.search(&query, candidates, item_type.as_deref()))?;
+        if !unlocked.is_empty() {
+            results = merge_results(results, unlocked);
+            results.truncate(candidates);
+        }
+        if boost {
+            let conn = crate::db::open()?;
+            crate::access_log::boost_results(&conn, &mut results)?;
+            results.truncate(limit);
+        }
+        Ok(results)
+    })
+    .await
+    .map_err(|e| BrainboxError::Other(e.to_string()))?
+}
+
+/// Filtered search through the shared service (for saved searches)
+pub fn search_filtered(query: &str, limit: usize, filters: &SearchFilters) -> Result<Vec<SearchResult>, BrainboxError> {
+    let results = {
+        let service_ref = SEARCH_SERVICE.lock().unwrap();
+        match &*service_ref {
+            Some(service) => service.search_filtered(query, limit, filters).map_err(|e| BrainboxError::Search(e.to_string()))?,
+            None => return Err(BrainboxError::Search("Search service not initialized".to_string())),
+        }
+    };
+    let unlocked = search_sessions(filters.vault_id, |session| session.search_filtered(query, limit, filters))?;
+    if unlocked.is_empty() {
+        return Ok(results);
+    }
+    let mut results = merge_results(results, unlocked);
+    if query.trim().is_empty() {
+        results.sort_by(|a, b| b.metadata.updated_at.cmp(&a.metadata.updated_at));
+    }
+    results.truncate(limit);
+    Ok(results)
+}
+
+pub fn index_stats() -> Result<SearchIndexStats, BrainboxError> {
+    let service_ref = SEARCH_SERVICE.lock().unwrap();
+    match &*service_ref {
+        Some(service) => service.stats().map_err(|e| BrainboxError::Search(e.to_string())),
+        None => Err(BrainboxError::Search("Search service not initialized".to_string())),
+    }
+}
+
+/// Merge segments and garbage-collect deleted documents; returns the stats afterwards
+pub fn optimize_index() -> Result<SearchIndexStats, BrainboxError> {
     let service_ref = SEARCH_SERVICE.lock().unwrap();
     match &*service_ref {
-        Some(service) => service.search(&query, limit).map_err(|e| e.to_string()),
-        None => Err("Search service not initialized".to_string()),
+        Some(service) => service.optimize().map_err(|e| BrainboxError::Search(e.to_string())),
+        None => Err(BrainboxError::Search("Search service not initialized".to_string())),
+    }
+}
+
+/// Completion for the search box: the input with its last word completed from titles and tags
+#[derive(Debug, Serialize, Clone)]
+pub struct SearchSuggestion {
+    pub text: String,
+    pub term: String,
+    pub doc_count: u64,
+}
+
+/// Suggest completions for the last word of `prefix`; earlier words are kept as typed
+pub fn suggest(prefix: &str, limit: usize) -> Result<Vec<SearchSuggestion>, BrainboxError> {
+    let trimmed = prefix.trim_start();
+    let (head, last) = match trimmed.rfind(char::is_whitespace) {
+        Some(i) => (&trimmed[..=i], &trimmed[i + 1..]),
+        None => ("", trimmed),
+    };
+    if last.is_empty() {
+        return Ok(Vec::new());
     }
+    let service_ref = SEARCH_SERVICE.lock().unwrap();
+    let terms = match &*service_ref {
+        Some(service) => service.suggest_terms(last, limit).map_err(|e| BrainboxError::Search(e.to_string()))?,
+        None => return Err(BrainboxError::Search("Search service not initialized".to_string())),
+    };
+    Ok(terms
+        .into_iter()
+        .map(|(term, doc_count)| SearchSuggestion { text: format!("{}{}", head, term), term, doc_count })
+        .collect())
+}
+
+/// Content key of the vault an item is in; None while it is locked
+fn item_content_key(conn: &rusqlite::Connection, item_id: i64) -> Option<keystore::VaultKey> {
+    let item = VaultItem::get_by_id(conn, item_id).ok()?;
+    let kek = crate::resolve_key(conn, item.vault_id, None).ok()?;
+    crate::envelope::content_key(conn, item.vault_id, &kek).ok()
+}
+
+/// Stored EXIF and image analysis text for an image item
+fn image_search_text(id: &str) -> Option<String> {
+    let item_id = id.parse().ok()?;
+    let conn = crate::db::open().ok()?;
+    let key = item_content_key(&conn, item_id)?;
+    let parts: Vec<String> = [crate::exif::search_text(&conn, item_id, &key), crate::image_labels::search_text(&conn, item_id, &key)]
+        .into_iter()
+        .flatten()
+        .collect();
+    (!parts.is_empty()).then(|| parts.join("\n"))
+}
+
+/// Quotes and notes of an item's annotations; None while its vault is locked
+fn annotation_search_text(id: &str) -> Option<String> {
+    let item_id = id.parse().ok()?;
+    let conn = crate::db::open().ok()?;
+    let key = item_content_key(&conn, item_id)?;
+    crate::annotations::search_text(&conn, item_id, &key).ok().flatten()
 }
 
 // Tauri command to index a document
 #[tauri::command]
-pub fn index_document(
-    id: String,
-    title: String,
-    content: String,
-    item_type: String,
-    created_at: String,
-    updated_at: String,
-    path: Option<String>,
-    tags: Vec<String>,
-) -> Result<(), String> {
+pub fn index_document(doc: DocumentInput) -> Result<(), BrainboxError> {
+    let DocumentInput { id, title, content, item_type, created_at, updated_at, path, tags, vault_id } = doc;
+    // Frontend callers don't pass the vault: it is the item's, or failing that read from a "vault/{id}/item/{id}" path
+    let conn = crate::db::open()?;
+    let vault_id = vault_id
+        .or_else(|| Some(VaultItem::get_by_id(&conn, id.parse().ok()?).ok()?.vault_id))
+        .or_else(|| {
+            path.as_deref()
+                .and_then(|p| p.strip_prefix("vault/"))
+                .and_then(|rest| rest.split('/').next())
+                .and_then(|id| id.parse().ok())
+        });
+    // Without the vault there is no telling whether the text may go to disk, so nothing is written
+    let Some(vault) = vault_id.map(|vault_id| Vault::get_by_id(&conn, vault_id)).transpose()?.flatten() else {
+        tracing::debug!("Not indexing document {}: its vault is unknown", id);
+        return Ok(());
+    };
+    let vault_id = vault.id;
+    // Camera, coordinates, recognised text and labels make photos findable by them
+    let content = match Some(id.as_str()).filter(|_| item_type == "image").and_then(image_search_text) {
+        Some(extra) => format!("{}\n{}", content, extra),
+        None => content,
+    };
+    // Text inside highlights is findable too
+    let content = match annotation_search_text(&id) {
+        Some(extra) => format!("{}\n{}", content, extra),
+        None => content,
+    };
+    let tags_refs: Vec<&str> = tags.iter().map(|s| s.as_str()).collect();
+    let doc = IndexDoc {
+        id: &id,
+        title: &title,
+        content: &content,
+        item_type: &item_type,
+        created_at: &created_at,
+        updated_at: &updated_at,
+        path: path.as_deref(),
+        tags: &tags_refs,
+        vault_id: Some(vault_id),
+    };
+    // Password-protected vaults only get their full text into the session index, which is gone once they lock
+    let locked_policy = if vault.has_password { Some(locked_vault_indexing(&conn)?) } else { None };
+    if locked_policy.is_some() {
+        if let Some(session) = SESSION_INDEXES.lock().unwrap().get(&vault_id) {
+            session.index_document(&doc).map_err(|e| BrainboxError::Search(e.to_string()))?;
+        }
+    }
     let service_ref = SEARCH_SERVICE.lock().unwrap();
     match &*service_ref {
         Some(service) => {
-            let tags_refs: Vec<&str> = tags.iter().map(|s| s.as_str()).collect();
-            service.index_document(
-                &id,
-                &title,
-                &content,
-                &item_type,
-                &created_at,
-                &updated_at,
-                path.as_deref(),
-                &tags_refs,
-            ).map_err(|e| e.to_string())
+            let content = match locked_policy.as_deref() {
+                None => content.as_str(),
+                Some(TITLE_ONLY) => "",
+                Some(_) => return service.delete_document(&id).map_err(|e| BrainboxError::Search(e.to_string())),
+            };
+            service.index_document(&IndexDoc { content, ..doc }).map_err(|e| BrainboxError::Search(e.to_string()))
         },
-        None => Err("Search service not initialized".to_string()),
+        None => Err(BrainboxError::Search("Search service not initialized".to_string())),
     }
 }
 
 // Tauri command to delete a document
 #[tauri::command]
-pub fn delete_document(id: String) -> Result<(), String> {
+pub fn delete_document(id: String) -> Result<(), BrainboxError> {
+    for session in SESSION_INDEXES.lock().unwrap().values() {
+        session.delete_document(&id).map_err(|e| BrainboxError::Search(e.to_string()))?;
+    }
     let service_ref = SEARCH_SERVICE.lock().unwrap();
     match &*service_ref {
-        Some(service) => service.delete_document(&id).map_err(|e| e.to_string()),
-        None => Err("Search service not initialized".to_string()),
+        Some(service) => service.delete_document(&id).map_err(|e| BrainboxError::Search(e.to_string())),
+        None => Err(BrainboxError::Search("Search service not initialized".to_string())),
+    }
+}
+
+/// Drop a vault's documents from the index once the vault is deleted
+pub fn delete_documents_by_vault(vault_id: i64) -> Result<u64, BrainboxError> {
+    close_session(vault_id);
+    let service_ref = SEARCH_SERVICE.lock().unwrap();
+    match &*service_ref {
+        Some(service) => service.delete_documents_by_vault(vault_id).map_err(|e| BrainboxError::Search(e.to_string())),
+        None => Err(BrainboxError::Search("Search service not initialized".to_string())),
+    }
+}
+
+/// Drop the search service so its index files can be removed
+pub fn close_search_service() {
+    *SEARCH_SERVICE.lock().unwrap() = None;
+}
+
+/// Setting for what the index on disk keeps of items in password-protected vaults; one of LOCKED_VAULT_POLICIES
+pub const LOCKED_VAULTS_KEY: &str = "search.locked_vaults";
+/// The policy the index on disk was last brought in line with
+const LOCKED_VAULTS_APPLIED_KEY: &str = "search.locked_vaults_applied";
+/// Titles and tags only, so locked items still turn up by name
+pub const TITLE_ONLY: &str = "title_only";
+/// Nothing at all until the vault is unlocked
+pub const NOT_INDEXED: &str = "none";
+pub const LOCKED_VAULT_POLICIES: &[&str] = &[TITLE_ONLY, NOT_INDEXED];
+
+pub fn locked_vault_indexing(conn: &rusqlite::Connection) -> Result<String, BrainboxError> {
+    Ok(settings::get_or_default(conn, LOCKED_VAULTS_KEY)?
+        .filter(|policy| LOCKED_VAULT_POLICIES.contains(&policy.as_str()))
+        .unwrap_or_else(|| TITLE_ONLY.to_string()))
+}
+
+fn is_protected(conn: &rusqlite::Connection, vault_id: i64) -> Result<bool, BrainboxError> {
+    Ok(Vault::get_by_id(conn, vault_id)?.is_some_and(|vault| vault.has_password))
+}
+
+/// Save the policy and rewrite the locked vaults' documents on disk to match; returns how many were written
+pub fn set_locked_vault_indexing(conn: &rusqlite::Connection, policy: &str) -> Result<u64, BrainboxError> {
+    if !LOCKED_VAULT_POLICIES.contains(&policy) {
+        return Err(BrainboxError::InvalidInput(format!("Unknown locked vault indexing policy: {}", policy)));
+    }
+    settings::set(conn, LOCKED_VAULTS_KEY, policy)?;
+    apply_locked_vault_indexing(conn)
+}
+
+/// Apply the policy unless the index on disk already follows it
+fn ensure_locked_vault_indexing(conn: &rusqlite::Connection) -> Result<(), BrainboxError> {
+    let policy = locked_vault_indexing(conn)?;
+    if settings::get(conn, LOCKED_VAULTS_APPLIED_KEY)?.as_deref() != Some(policy.as_str()) {
+        let written = apply_locked_vault_indexing(conn)?;
+        tracing::info!("Reindexed password-protected vaults as {}: {} documents", policy, written);
+    }
+    Ok(())
+}
+
+/// Rewrite every password-protected vault's documents on disk under the current policy
+pub fn apply_locked_vault_indexing(conn: &rusqlite::Connection) -> Result<u64, BrainboxError> {
+    Vault::create_table(conn)?;
+    let policy = locked_vault_indexing(conn)?;
+    let mut written = 0;
+    for vault in Vault::list(conn)?.into_iter().filter(|vault| vault.has_password) {
+        written += reindex_locked_vault(conn, vault.id, &policy)?;
+    }
+    settings::set(conn, LOCKED_VAULTS_APPLIED_KEY, &policy)?;
+    Ok(written)
+}
+
+/// Bring one vault's documents on disk in line with the policy, e.g. after it was given a password.
+/// Does nothing for a vault without one.
+pub fn apply_locked_vault_indexing_to(conn: &rusqlite::Connection, vault_id: i64) -> Result<u64, BrainboxError> {
+    if !is_protected(conn, vault_id)? {
+        return Ok(0);
+    }
+    reindex_locked_vault(conn, vault_id, &locked_vault_indexing(conn)?)
+}
+
+/// The path the frontend indexes items under
+fn item_path(vault_id: i64, item_id: i64) -> String {
+    format!("vault/{}/item/{}", vault_id, item_id)
+}
+
+/// Replace a vault's documents on disk with title-only ones, or with none
+fn reindex_locked_vault(conn: &rusqlite::Connection, vault_id: i64, policy: &str) -> Result<u64, BrainboxError> {
+    let service = get_search_service().ok_or_else(|| BrainboxError::Search("Search service not initialized".to_string()))?;
+    let mut docs = Vec::new();
+    if policy == TITLE_ONLY {
+        for item in VaultItem::list_by_vault(conn, vault_id)? {
+            let tags = crate::tags::get(conn, item.id)?;
+            let tags_refs: Vec<&str> = tags.iter().map(|s| s.as_str()).collect();
+            let item_type = item.item_type.as_deref().unwrap_or("note");
+            let path = item_path(vault_id, item.id);
+            docs.push(service.make_document(&IndexDoc {
+                id: &item.id.to_string(),
+                title: &item.title,
+                content: "",
+                item_type,
+                created_at: &item.created_at,
+                updated_at: &item.updated_at,
+                path: Some(&path),
+                tags: &tags_refs,
+                vault_id: Some(vault_id),
+            }));
+        }
+    }
+    let written = docs.len() as u64;
+    service.replace_vault_documents(vault_id, docs).map_err(|e| BrainboxError::Search(e.to_string()))?;
+    Ok(written)
+}
+
+/// Build the session index for a vault that was just unlocked, in the background. Decrypting every item takes a
+/// while for a large vault; searches cover the titles on disk until it is ready.
+pub fn open_session(vault_id: i64) {
+    std::thread::spawn(move || {
+        if let Err(e) = build_session(vault_id) {
+            tracing::warn!("Failed to index unlocked vault {}: {}", vault_id, e);
+        }
+    });
+}
+
+fn build_session(vault_id: i64) -> Result<(), BrainboxError> {
+    let conn = crate::db::open()?;
+    if !is_protected(&conn, vault_id)? {
+        return Ok(());
+    }
+    let Some(key) = keystore::get(vault_id) else { return Ok(()) };
+    let key = crate::envelope::content_key(&conn, vault_id, &key)?;
+    let session = SearchService::in_ram(&tokenizers::get_languages(&conn)?).map_err(|e| BrainboxError::Search(e.to_string()))?;
+    let mut docs = Vec::new();
+    for item in VaultItem::list_by_vault(&conn, vault_id)? {
+        let Ok(plaintext) = crate::envelope::open(&key, &item.content) else { continue };
+        let mut content = String::from_utf8_lossy(&plaintext).to_string();
+        let item_type = item.item_type.as_deref().unwrap_or("note");
+        if item_type == "image" {
+            if let Some(extra) = image_search_text(&item.id.to_string()) {
+                content = format!("{}\n{}", content, extra);
+            }
+        }
+        if let Ok(Some(extra)) = crate::annotations::search_text(&conn, item.id, &key) {
+            content = format!("{}\n{}", content, extra);
+        }
+        let tags = crate::tags::get(&conn, item.id)?;
+        let tags_refs: Vec<&str> = tags.iter().map(|s| s.as_str()).collect();
+        let path = item_path(vault_id, item.id);
+        docs.push(session.make_document(&IndexDoc {
+            id: &item.id.to_string(),
+            title: &item.title,
+            content: &content,
+            item_type,
+            created_at: &item.created_at,
+            updated_at: &item.updated_at,
+            path: Some(&path),
+            tags: &tags_refs,
+            vault_id: Some(vault_id),
+        }));
+    }
+    let count = docs.len();
+    session.replace_vault_documents(vault_id, docs).map_err(|e| BrainboxError::Search(e.to_string()))?;
+    let mut sessions = SESSION_INDEXES.lock().unwrap();
+    // The vault may have been locked again while its items were decrypted
+    if keystore::get(vault_id).is_some() {
+        sessions.insert(vault_id, session);
+        tracing::debug!("Indexed {} items of unlocked vault {} in memory", count, vault_id);
+    }
+    Ok(())
+}
+
+/// Drop an unlocked vault's session index
+pub fn close_session(vault_id: i64) {
+    SESSION_INDEXES.lock().unwrap().remove(&vault_id);
+}
+
+pub fn close_all_sessions() {
+    SESSION_INDEXES.lock().unwrap().clear();
+}
+
+/// Run a search on the session index of `vault_id`, or on every one when None
+fn search_sessions(
+    vault_id: Option<i64>,
+    search: impl Fn(&SearchService) -> Result<Vec<SearchResult>, tantivy::TantivyError>,
+) -> Result<Vec<SearchResult>, BrainboxError> {
+    let sessions = SESSION_INDEXES.lock().unwrap();
+    let mut results = Vec::new();
+    for (id, session) in sessions.iter() {
+        if vault_id.is_none_or(|vault_id| vault_id == *id) {
+            results.extend(search(session).map_err(|e| BrainboxError::Search(e.to_string()))?);
+        }
+    }
+    Ok(results)
+}
+
+/// Disk and session hits as one list, best first. An unlocked item is in both indexes; its better hit is kept.
+fn merge_results(results: Vec<SearchResult>, unlocked: Vec<SearchResult>) -> Vec<SearchResult> {
+    let mut best: HashMap<String, SearchResult> = HashMap::new();
+    for result in results.into_iter().chain(unlocked) {
+        if best.get(&result.id).is_none_or(|existing| result.score > existing.score) {
+            best.insert(result.id.clone(), result);
+        }
     }
+    let mut merged: Vec<SearchResult> = best.into_values().collect();
+    merged.sort_by(|a, b| b.score.total_cmp(&a.score));
+    merged
 }
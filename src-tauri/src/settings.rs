@@ -0,0 +1,316 @@
+// settings.rs - App-wide settings for brainbox
+// One key-value table holds every preference and piece of local state. It replaces the sync-specific
+// sync_settings table it grew out of, whose rows are moved over the first time it is opened. Values are
+// strings; the typed helpers parse them and fall back to the defaults below. Every change is announced to
+// the frontend with a "settings-changed" event.
+
+use lazy_static::lazy_static;
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter};
+
+use crate::capture_rules::{CaptureRule, CaptureRuleInput};
+use crate::error::BrainboxError;
+use crate::vault::Vault;
+
+/// Emitted with `{ key, value }` whenever a setting changes; value is null once a key is reset
+pub const CHANGED_EVENT: &str = "settings-changed";
+
+/// User preferences the app reads, with the value it uses while the key is unset.
+/// `reset_settings` clears exactly these; local state such as device ids or sync cursors is left alone.
+pub const DEFAULTS: &[(&str, &str)] = &[
+    ("auto_compact_after_purge", "true"),
+    ("check_sync_on_startup", "true"),
+    ("close_to_tray", "false"),
+    ("digest.enabled", "false"),
+    ("digest.model", ""),
+    ("digest.time", "09:00"),
+    ("digest.weekday", "mon"),
+    ("emergency_lock.clear_index", "false"),
+    ("enrichment.requests_per_minute", "20"),
+    ("http.max_retries", "2"),
+    ("http.timeout_secs", "30"),
+    ("http.user_agent", ""),
+    ("inbox.enabled", "false"),
+    ("locale", "system"),
+    ("log_level", "info"),
+    ("os_search.enabled", "false"),
+    ("purge_deleted_after_days", "30"),
+    ("scheduled_export.enabled", "false"),
+    ("scheduled_export.format", "json"),
+    ("scheduled_export.keep", "7"),
+    ("scheduled_export.time", "02:00"),
+    ("search.language.content", "default"),
+    ("search.language.title", "default"),
+    ("search.locked_vaults", "title_only"),
+    ("start_minimized", "false"),
+    ("sync_on_close", "false"),
+    ("transcripts.whisper_command", "whisper"),
+    ("transcripts.whisper_model", "base"),
+    ("update_channel", "stable"),
+    ("update_check_interval_hours", "24"),
+    ("update_notify", "true"),
+];
+
+/// Never returned by `get_all_settings` or exported; nor is any key ending in ".token" or ".api_key"
+const SECRET_KEYS: &[&str] = &["sync_server.token", "scheduled_export.key"];
+
+/// Exported along with the preferences in DEFAULTS. LLM provider options are kept under "llm.".
+const EXPORT_PREFIXES: &[&str] = &["hotkey.", "llm.", "sync.captures."];
+const EXPORT_KEYS: &[&str] = &["sync_backend", "sync_folder", "sync_server.url"];
+
+/// Marks a file written by `export`
+pub const EXPORT_FORMAT: &str = "brainbox-settings";
+const EXPORT_VERSION: u32 = 1;
+
+lazy_static! {
+    static ref APP: Mutex<Option<AppHandle>> = Mutex::new(None);
+}
+
+#[derive(Serialize, Clone)]
+struct ChangedEvent<'a> {
+    key: &'a str,
+    value: Option<&'a str>,
+}
+
+/// Remember the app handle so changes can be announced
+pub fn init(app: &AppHandle) {
+    *APP.lock().unwrap() = Some(app.clone());
+}
+
+fn notify(key: &str, value: Option<&str>) {
+    if let Some(app) = APP.lock().unwrap().as_ref() {
+        let _ = app.emit(CHANGED_EVENT, ChangedEvent { key, value });
+    }
+}
+
+pub fn create_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY,
+            value TEXT
+        )",
+        [],
+    )?;
+    // Settings used to live in sync_settings; move them over once
+    let legacy: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'sync_settings')",
+        [],
+        |row| row.get(0),
+    )?;
+    if legacy {
+        conn.execute_batch(
+            "SAVEPOINT migrate_settings;
+             INSERT OR IGNORE INTO settings (key, value) SELECT key, value FROM sync_settings;
+             DROP TABLE sync_settings;
+             RELEASE migrate_settings;",
+        )?;
+    }
+    Ok(())
+}
+
+/// The stored value of a key, without defaults
+pub fn get(conn: &Connection, key: &str) -> Result<Option<String>> {
+    create_table(conn)?;
+    let value = conn
+        .query_row("SELECT value FROM settings WHERE key = ?1", [key], |row| row.get::<_, Option<String>>(0))
+        .optional()?;
+    Ok(value.flatten())
+}
+
+pub fn is_secret(key: &str) -> bool {
+    SECRET_KEYS.contains(&key) || key.ends_with(".token") || key.ends_with(".api_key")
+}
+
+/// Settings that mean the same on another machine; device ids, cursors and vault ids don't
+fn is_exportable(key: &str) -> bool {
+    !is_secret(key)
+        && (default_value(key).is_some()
+            || EXPORT_KEYS.contains(&key)
+            || EXPORT_PREFIXES.iter().any(|prefix| key.starts_with(prefix)))
+}
+
+pub fn default_value(key: &str) -> Option<&'static str> {
+    DEFAULTS.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+}
+
+/// The stored value of a key, else its default
+pub fn get_or_default(conn: &Connection, key: &str) -> Result<Option<String>> {
+    Ok(get(conn, key)?.or_else(|| default_value(key).map(str::to_string)))
+}
+
+pub fn set(conn: &Connection, key: &str, value: &str) -> Result<()> {
+    create_table(conn)?;
+    conn.execute("INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)", params![key, value])?;
+    notify(key, Some(value));
+    Ok(())
+}
+
+pub fn delete(conn: &Connection, key: &str) -> Result<()> {
+    create_table(conn)?;
+    if conn.execute("DELETE FROM settings WHERE key = ?1", [key])? > 0 {
+        notify(key, None);
+    }
+    Ok(())
+}
+
+/// Delete every key starting with `prefix`
+pub fn delete_prefix(conn: &Connection, prefix: &str) -> Result<()> {
+    for (key, _) in get_all(conn)? {
+        if key.starts_with(prefix) {
+            delete(conn, &key)?;
+        }
+    }
+    Ok(())
+}
+
+/// Every stored key-value pair
+pub fn get_all(conn: &Connection) -> Result<Vec<(String, String)>> {
+    create_table(conn)?;
+    let mut stmt = conn.prepare("SELECT key, value FROM settings WHERE value IS NOT NULL")?;
+    let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    rows.collect()
+}
+
+/// A boolean setting; "true" and "1" are true, other stored values false, unset keys their default
+pub fn get_bool(conn: &Connection, key: &str) -> Result<bool> {
+    Ok(get_or_default(conn, key)?.is_some_and(|v| v == "true" || v == "1"))
+}
+
+pub fn set_bool(conn: &Connection, key: &str, value: bool) -> Result<()> {
+    set(conn, key, if value { "true" } else { "false" })
+}
+
+/// A setting parsed as `T`; None when unset without a default or unparseable
+pub fn get_parsed<T: std::str::FromStr>(conn: &Connection, key: &str) -> Result<Option<T>> {
+    Ok(get_or_default(conn, key)?.and_then(|v| v.trim().parse().ok()))
+}
+
+pub fn set_value<T: ToString>(conn: &Connection, key: &str, value: T) -> Result<()> {
+    set(conn, key, &value.to_string())
+}
+
+/// Stored settings over the defaults, minus secrets, for the settings screen
+pub fn all_with_defaults(conn: &Connection) -> Result<HashMap<String, String>> {
+    let mut all: HashMap<String, String> = DEFAULTS.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    all.extend(get_all(conn)?);
+    all.retain(|key, _| !is_secret(key));
+    Ok(all)
+}
+
+/// Clear the given preferences, or every preference in DEFAULTS, back to their defaults. Returns the keys reset.
+pub fn reset(conn: &Connection, keys: Option<&[String]>) -> Result<Vec<String>> {
+    let keys: Vec<String> = match keys {
+        Some(keys) => keys.to_vec(),
+        None => DEFAULTS.iter().map(|(k, _)| k.to_string()).collect(),
+    };
+    for key in &keys {
+        delete(conn, key)?;
+    }
+    Ok(keys)
+}
+
+/// A capture rule in an export; its vault is referred to by uuid, since ids differ between machines
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportedCaptureRule {
+    #[serde(flatten)]
+    pub rule: CaptureRuleInput,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_vault_uuid: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SettingsExport {
+    pub format: String,
+    pub version: u32,
+    pub exported_at: String,
+    pub settings: BTreeMap<String, String>,
+    #[serde(default)]
+    pub capture_rules: Vec<ExportedCaptureRule>,
+}
+
+#[derive(Debug, Serialize, Default)]
+pub struct SettingsImportResult {
+    pub settings_applied: usize,
+    pub capture_rules_added: usize,
+    pub capture_rules_updated: usize,
+    pub warnings: Vec<String>,
+    /// Imported hotkey bindings (action, hotkey), for the caller to register
+    #[serde(skip)]
+    pub hotkeys: Vec<(String, String)>,
+}
+
+/// Hotkeys, sync configuration without secrets, LLM provider options, preferences and capture rules
+pub fn export(conn: &Connection) -> std::result::Result<SettingsExport, BrainboxError> {
+    let settings = get_all(conn)?.into_iter().filter(|(key, _)| is_exportable(key)).collect();
+    let mut capture_rules = Vec::new();
+    for rule in CaptureRule::list(conn)? {
+        let target_vault_uuid = match rule.target_vault_id {
+            Some(id) => Vault::get_by_id(conn, id)?.and_then(|vault| vault.uuid),
+            None => None,
+        };
+        let mut input = rule.to_input();
+        input.target_vault_id = None;
+        capture_rules.push(ExportedCaptureRule { rule: input, target_vault_uuid });
+    }
+    Ok(SettingsExport {
+        format: EXPORT_FORMAT.to_string(),
+        version: EXPORT_VERSION,
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        settings,
+        capture_rules,
+    })
+}
+
+/// Apply an export. Keys that aren't exportable are skipped, capture rules replace rules of the same name
+/// and rules whose vault doesn't exist here capture into the default vault instead.
+pub fn import(conn: &Connection, export: SettingsExport) -> std::result::Result<SettingsImportResult, BrainboxError> {
+    if export.format != EXPORT_FORMAT {
+        return Err(BrainboxError::InvalidInput("Not a brainbox settings file".to_string()));
+    }
+    if export.version > EXPORT_VERSION {
+        return Err(BrainboxError::InvalidInput(format!(
+            "Settings file version {} is newer than this version of brainbox supports",
+            export.version
+        )));
+    }
+    let mut result = SettingsImportResult::default();
+    for (key, value) in export.settings {
+        if !is_exportable(&key) {
+            result.warnings.push(format!("Skipped setting {}", key));
+            continue;
+        }
+        if let Some(action) = key.strip_prefix(crate::hotkeys::SETTING_PREFIX) {
+            result.hotkeys.push((action.to_string(), value));
+            continue;
+        }
+        set(conn, &key, &value)?;
+        result.settings_applied += 1;
+    }
+
+    let existing = CaptureRule::list(conn)?;
+    for ExportedCaptureRule { mut rule, target_vault_uuid } in export.capture_rules {
+        rule.target_vault_id = match target_vault_uuid {
+            Some(uuid) => {
+                let vault = Vault::get_by_uuid(conn, &uuid)?.filter(|vault| vault.deleted_at.is_none());
+                if vault.is_none() {
+                    result.warnings.push(format!("Rule \"{}\" targets a vault that isn't on this device", rule.name));
+                }
+                vault.map(|vault| vault.id)
+            }
+            None => None,
+        };
+        let outcome = match existing.iter().find(|other| other.name == rule.name.trim()) {
+            Some(other) => CaptureRule::update(conn, other.id, &rule).map(|_| true),
+            None => CaptureRule::insert(conn, &rule).map(|_| false),
+        };
+        match outcome {
+            Ok(true) => result.capture_rules_updated += 1,
+            Ok(false) => result.capture_rules_added += 1,
+            Err(e) => result.warnings.push(format!("Skipped rule \"{}\": {}", rule.name, e)),
+        }
+    }
+    Ok(result)
+}
@@ -0,0 +1,530 @@
+// share.rs - Encrypted vault bundles and single-note HTML pages for sharing with other people
+// A shared bundle holds one vault in the sync format, sealed under a password chosen for the recipient rather than
+// the vault's own. Vault, collection and item UUIDs are kept, so importing a later share of the same vault updates
+// the recipient's copy instead of creating a second one.
+// A shared note is one HTML file that decrypts itself in the browser. Browsers' WebCrypto has no XChaCha20, so
+// notes use AES-256-CTR with an HMAC-SHA256 tag, both keyed from the passphrase with PBKDF2.
+
+use aes::cipher::{generic_array::GenericArray, BlockEncrypt, KeyInit};
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use rand::{rngs::OsRng, RngCore};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::envelope;
+use crate::error::BrainboxError;
+use crate::keystore::VaultKey;
+use crate::sync::{self, ImportItemResult, SyncCollection, SyncItem, SyncVault};
+use crate::vault::{Collection, Vault, VaultItem};
+
+const BUNDLE_FORMAT: &str = "brainbox-share";
+const BUNDLE_VERSION: u32 = 1;
+const KDF_ITERATIONS: u32 = 100_000;
+/// Work factors a bundle may ask for; the file is untrusted, and outside this it is either weak or hangs the import
+const BUNDLE_KDF_ITERATIONS: std::ops::RangeInclusive<u32> = KDF_ITERATIONS..=10_000_000;
+/// Shared notes can be attacked offline by anyone holding the file, so they use a slower derivation
+const NOTE_KDF_ITERATIONS: u32 = 310_000;
+/// Replaced with the sealed note in `NOTE_TEMPLATE`
+const NOTE_PAYLOAD_MARKER: &str = "__BRAINBOX_PAYLOAD__";
+
+/// File written by `share_vault`; `data` is the sealed JSON of a `SyncVault`
+#[derive(Serialize, Deserialize)]
+struct SharedBundle {
+    format: String,
+    version: u32,
+    shared_at: String,
+    salt: String,
+    iterations: u32,
+    data: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ShareResult {
+    pub path: String,
+    pub items: usize,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SharedImportResult {
+    pub vault_id: i64,
+    pub vault_name: String,
+    /// False when an earlier share of the same vault was updated
+    pub created: bool,
+    pub imported_items: usize,
+    pub updated_items: usize,
+    pub deleted_items: usize,
+}
+
+pub fn bundle_key(password: &str, salt: &[u8], iterations: u32) -> VaultKey {
+    use pbkdf2::pbkdf2_hmac;
+    use sha2::Sha256;
+    let mut key = zeroize::Zeroizing::new([0u8; 32]);
+    pbkdf2_hmac::<Sha256>(password.as_bytes(), salt, iterations, &mut *key);
+    key
+}
+
+/// Plaintext snapshot of a vault. Deleted items and collections carry only their tombstone, not their content.
+fn snapshot(conn: &Connection, vault: &Vault, key: &[u8; 32]) -> Result<SyncVault, BrainboxError> {
+    let uuid = vault
+        .uuid
+        .clone()
+        .ok_or_else(|| BrainboxError::InvalidInput("Vault has no UUID; run a sync export first".to_string()))?;
+    let collections = Collection::list_all_by_vault_for_sync(conn, vault.id)?;
+    let collection_uuids: HashMap<i64, String> = collections.iter().map(|c| (c.id, c.uuid.clone())).collect();
+    let collections = collections
+        .iter()
+        .map(|c| SyncCollection {
+            uuid: c.uuid.clone(),
+            parent_uuid: c.parent_id.and_then(|pid| collection_uuids.get(&pid).cloned()),
+            name: c.name.clone(),
+            sort_order: c.sort_order,
+            created_at: c.created_at.clone(),
+            updated_at: c.updated_at.clone(),
+            deleted_at: c.deleted_at.clone(),
+        })
+        .collect();
+
+    let mut items = Vec::new();
+    for item in VaultItem::list_all_by_vault_for_sync(conn, vault.id)? {
+        let Some(item_uuid) = item.uuid.clone() else { continue };
+        let deleted = item.deleted_at.is_some();
+        let content = if deleted {
+            String::new()
+        } else {
+            String::from_utf8(envelope::open(key, &item.content)?)
+                .map_err(|_| BrainboxError::Crypto("Invalid UTF-8 in decrypted content".to_string()))?
+        };
+        items.push(SyncItem {
+            uuid: item_uuid,
+            title: if deleted { String::new() } else { item.title },
+            content,
+            created_at: item.created_at,
+            updated_at: item.updated_at,
+            deleted_at: item.deleted_at,
+            image: if deleted { None } else { crate::image_cache::portable_image(conn, item.image) },
+            summary: if deleted { None } else { item.summary },
+            sort_order: item.sort_order,
+            collection_uuid: item.collection_id.and_then(|cid| collection_uuids.get(&cid).cloned()),
+            item_type: item.item_type,
+            due_at: item.due_at,
+            completed_at: item.completed_at,
+            read_at: item.read_at,
+            archive_url: item.archive_url,
+            color: item.color,
+            icon: item.icon,
+            board_placements: Vec::new(),
+            annotations: None,
+            // Shares carry no sync history, so the recipient goes by timestamps
+            version: None,
+        });
+    }
+
+    Ok(SyncVault {
+        uuid,
+        name: vault.name.clone(),
+        created_at: vault.created_at.clone(),
+        updated_at: vault.updated_at.clone().unwrap_or_else(|| chrono::Utc::now().to_rfc3339()),
+        deleted_at: None,
+        cover_image: crate::covers::portable(vault.cover_image.clone()),
+        color: vault.color.clone(),
+        icon: vault.icon.clone(),
+        description: vault.description.clone(),
+        has_password: true,
+        items,
+        collections,
+        boards: Vec::new(),
+        settings: Vec::new(),
+    })
+}
+
+/// Write a vault to `dest` sealed under `recipient_password`. `key` is the vault's content key.
+pub fn write_bundle(
+    conn: &Connection,
+    vault: &Vault,
+    key: &[u8; 32],
+    recipient_password: &str,
+    dest: &Path,
+) -> Result<ShareResult, BrainboxError> {
+    if recipient_password.is_empty() {
+        return Err(BrainboxError::InvalidInput("A password for the recipient is required".to_string()));
+    }
+    let shared = snapshot(conn, vault, key)?;
+    let items = shared.items.iter().filter(|i| i.deleted_at.is_none()).count();
+    let plaintext = zeroize::Zeroizing::new(serde_json::to_vec(&shared)?);
+
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    let bundle_key = bundle_key(recipient_password, &salt, KDF_ITERATIONS);
+    let b64 = base64::engine::general_purpose::STANDARD;
+    let bundle = SharedBundle {
+        format: BUNDLE_FORMAT.to_string(),
+        version: BUNDLE_VERSION,
+        shared_at: chrono::Utc::now().to_rfc3339(),
+        salt: b64.encode(salt),
+        iterations: KDF_ITERATIONS,
+        data: b64.encode(envelope::seal(&bundle_key, &plaintext)?),
+    };
+    crate::image_cache::write_atomic(dest, &serde_json::to_vec_pretty(&bundle)?)?;
+    Ok(ShareResult { path: dest.to_string_lossy().to_string(), items })
+}
+
+/// Read and decrypt a bundle. A wrong password is `InvalidKey`.
+pub fn read_bundle(path: &Path, password: &str) -> Result<SyncVault, BrainboxError> {
+    let bundle: SharedBundle = serde_json::from_slice(&std::fs::read(path)?)
+        .map_err(|e| BrainboxError::InvalidInput(format!("Not a brainbox share file: {}", e)))?;
+    if bundle.format != BUNDLE_FORMAT {
+        return Err(BrainboxError::InvalidInput("Not a brainbox share file".to_string()));
+    }
+    if bundle.version > BUNDLE_VERSION {
+        return Err(BrainboxError::InvalidInput(format!(
+            "Share file version {} is newer than this brainbox supports",
+            bundle.version
+        )));
+    }
+    if !BUNDLE_KDF_ITERATIONS.contains(&bundle.iterations) {
+        return Err(BrainboxError::InvalidInput(format!(
+            "Share file asks for {} key derivation rounds; expected {} to {}",
+            bundle.iterations,
+            BUNDLE_KDF_ITERATIONS.start(),
+            BUNDLE_KDF_ITERATIONS.end()
+        )));
+    }
+    let b64 = base64::engine::general_purpose::STANDARD;
+    let salt = b64
+        .decode(&bundle.salt)
+        .map_err(|_| BrainboxError::InvalidInput("Corrupt share file".to_string()))?;
+    let data = b64
+        .decode(&bundle.data)
+        .map_err(|_| BrainboxError::InvalidInput("Corrupt share file".to_string()))?;
+    let key = bundle_key(password, &salt, bundle.iterations);
+    let plaintext = zeroize::Zeroizing::new(envelope::open(&key, &data)?);
+    Ok(serde_json::from_slice(&plaintext)?)
+}
+
+/// Merge a shared vault's metadata, collections and items into local vault `vault_id`, matching by UUID.
+/// Newer shared items replace local ones; local items missing from the share are left alone.
+pub fn merge_into(
+    conn: &Connection,
+    vault_id: i64,
+    key: &[u8; 32],
+    shared: &SyncVault,
+    created: bool,
+) -> Result<SharedImportResult, BrainboxError> {
+    let cover_image = crate::covers::normalize(shared.cover_image.as_deref()).unwrap_or(shared.cover_image.clone());
+    conn.execute(
+        "UPDATE vaults SET name = ?1, cover_image = ?2, color = ?3, icon = ?4, description = ?5, updated_at = ?6 WHERE id = ?7",
+        rusqlite::params![
+            shared.name,
+            cover_image,
+            shared.color,
+            shared.icon,
+            shared.description,
+            shared.updated_at,
+            vault_id
+        ],
+    )?;
+
+    let collection_ids = sync::import_collections(conn, vault_id, &shared.collections)?;
+    let mut result = SharedImportResult {
+        vault_id,
+        vault_name: shared.name.clone(),
+        created,
+        imported_items: 0,
+        updated_items: 0,
+        deleted_items: 0,
+    };
+    for item in &shared.items {
+        // An item UUID that already belongs to another local vault is not taken over
+        if let Some(existing) = VaultItem::get_by_uuid(conn, &item.uuid)? {
+            if existing.vault_id != vault_id {
+                continue;
+            }
+        }
+        let collection_id = item.collection_uuid.as_ref().and_then(|u| collection_ids.get(u)).copied();
+        // Without a last-sync time nothing counts as a conflict; the newer side wins
+        match sync::import_item(conn, vault_id, item, key, &None, collection_id)? {
+            ImportItemResult::Imported => result.imported_items += 1,
+            ImportItemResult::Updated | ImportItemResult::Conflict(_) => result.updated_items += 1,
+            ImportItemResult::Deleted => result.deleted_items += 1,
+            ImportItemResult::Skipped => {}
+        }
+    }
+    Ok(result)
+}
+
+/// What the shared note page decrypts to
+#[derive(Serialize)]
+struct SharedNote<'a> {
+    title: &'a str,
+    content: &'a str,
+    created_at: &'a str,
+    updated_at: &'a str,
+}
+
+/// Sealed note embedded in the page; binary fields are base64
+#[derive(Serialize)]
+struct SealedNote {
+    v: u32,
+    iterations: u32,
+    salt: String,
+    iv: String,
+    ciphertext: String,
+    tag: String,
+}
+
+/// AES-256-CTR with the low 64 bits of `iv` as the counter, matching WebCrypto's `{ length: 64 }`
+fn aes_ctr(key: &[u8], iv: &[u8; 16], data: &mut [u8]) {
+    let cipher = aes::Aes256::new(GenericArray::from_slice(key));
+    let start = u64::from_be_bytes(iv[8..].try_into().unwrap_or_default());
+    for (i, chunk) in data.chunks_mut(16).enumerate() {
+        let mut block = *iv;
+        block[8..].copy_from_slice(&start.wrapping_add(i as u64).to_be_bytes());
+        let mut block = GenericArray::from(block);
+        cipher.encrypt_block(&mut block);
+        for (byte, pad) in chunk.iter_mut().zip(block.iter()) {
+            *byte ^= pad;
+        }
+    }
+}
+
+fn seal_note(passphrase: &str, plaintext: &[u8]) -> Result<SealedNote, BrainboxError> {
+    let mut salt = [0u8; 16];
+    let mut iv = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut iv);
+    seal_note_with(passphrase, plaintext, &salt, &iv, NOTE_KDF_ITERATIONS)
+}
+
+/// First half of the derived bits encrypts, second half authenticates
+fn note_keys(passphrase: &str, salt: &[u8], iterations: u32) -> zeroize::Zeroizing<[u8; 64]> {
+    use pbkdf2::pbkdf2_hmac;
+    use sha2::Sha256;
+    let mut keys = zeroize::Zeroizing::new([0u8; 64]);
+    pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, iterations, &mut *keys);
+    keys
+}
+
+fn seal_note_with(
+    passphrase: &str,
+    plaintext: &[u8],
+    salt: &[u8; 16],
+    iv: &[u8; 16],
+    iterations: u32,
+) -> Result<SealedNote, BrainboxError> {
+    use sha2::Sha256;
+    let keys = note_keys(passphrase, salt, iterations);
+
+    let mut ciphertext = plaintext.to_vec();
+    aes_ctr(&keys[..32], iv, &mut ciphertext);
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&keys[32..])
+        .map_err(|_| BrainboxError::Crypto("Invalid MAC key".to_string()))?;
+    mac.update(iv);
+    mac.update(&ciphertext);
+    let tag = mac.finalize().into_bytes();
+
+    let b64 = base64::engine::general_purpose::STANDARD;
+    Ok(SealedNote {
+        v: 1,
+        iterations,
+        salt: b64.encode(salt),
+        iv: b64.encode(iv),
+        ciphertext: b64.encode(ciphertext),
+        tag: b64.encode(tag),
+    })
+}
+
+/// A standalone HTML page holding `item` encrypted under `passphrase`. The title is encrypted too;
+/// the page shows nothing about the note until the passphrase is entered.
+pub fn item_html(item: &VaultItem, content: &str, passphrase: &str) -> Result<String, BrainboxError> {
+    if passphrase.chars().count() < 8 {
+        return Err(BrainboxError::InvalidInput("Passphrase must be at least 8 characters".to_string()));
+    }
+    let note = zeroize::Zeroizing::new(serde_json::to_vec(&SharedNote {
+        title: &item.title,
+        content,
+        created_at: &item.created_at,
+        updated_at: &item.updated_at,
+    })?);
+    let sealed = serde_json::to_string(&seal_note(passphrase, &note)?)?;
+    Ok(NOTE_TEMPLATE.replace(NOTE_PAYLOAD_MARKER, &sealed))
+}
+
+const NOTE_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<meta name="referrer" content="no-referrer">
+<title>Shared note</title>
+<style>
+  body { font-family: system-ui, -apple-system, sans-serif; max-width: 760px; margin: 48px auto; padding: 0 20px; color: #1f2328; background: #fafafa; }
+  h1 { font-size: 1.6em; margin-bottom: 4px; }
+  .meta { color: #6b7280; font-size: 0.9em; margin-bottom: 24px; }
+  #content { white-space: pre-wrap; word-wrap: break-word; line-height: 1.6; }
+  form { display: flex; gap: 8px; }
+  input { flex: 1; padding: 8px 10px; font-size: 1em; border: 1px solid #d0d7de; border-radius: 6px; }
+  button { padding: 8px 16px; font-size: 1em; border: 0; border-radius: 6px; background: #4f46e5; color: #fff; cursor: pointer; }
+  #error { color: #b91c1c; min-height: 1.4em; }
+  footer { margin-top: 48px; color: #9ca3af; font-size: 0.8em; }
+</style>
+</head>
+<body>
+<div id="locked">
+  <h1>Shared note</h1>
+  <p class="meta">This note is encrypted. Enter the passphrase you were given to read it.</p>
+  <form id="unlock">
+    <input id="passphrase" type="password" autocomplete="off" placeholder="Passphrase" autofocus>
+    <button type="submit">Open</button>
+  </form>
+  <p id="error"></p>
+</div>
+<div id="note" hidden>
+  <h1 id="title"></h1>
+  <p class="meta" id="dates"></p>
+  <div id="content"></div>
+</div>
+<footer>Shared from brainbox. Decrypted in your browser; nothing is sent anywhere.</footer>
+<script id="payload" type="application/json">__BRAINBOX_PAYLOAD__</script>
+<script>
+(function () {
+  var payload = JSON.parse(document.getElementById('payload').textContent);
+  var bytes = function (b64) { return Uint8Array.from(atob(b64), function (c) { return c.charCodeAt(0); }); };
+
+  async function decrypt(passphrase) {
+    var subtle = window.crypto.subtle;
+    var iv = bytes(payload.iv), ciphertext = bytes(payload.ciphertext);
+    var base = await subtle.importKey('raw', new TextEncoder().encode(passphrase), 'PBKDF2', false, ['deriveBits']);
+    var keys = new Uint8Array(await subtle.deriveBits(
+      { name: 'PBKDF2', hash: 'SHA-256', salt: bytes(payload.salt), iterations: payload.iterations }, base, 512));
+    var macKey = await subtle.importKey('raw', keys.slice(32), { name: 'HMAC', hash: 'SHA-256' }, false, ['verify']);
+    var signed = new Uint8Array(iv.length + ciphertext.length);
+    signed.set(iv);
+    signed.set(ciphertext, iv.length);
+    if (!(await subtle.verify('HMAC', macKey, bytes(payload.tag), signed))) {
+      throw new Error('Wrong passphrase');
+    }
+    var aesKey = await subtle.importKey('raw', keys.slice(0, 32), 'AES-CTR', false, ['decrypt']);
+    var plain = await subtle.decrypt({ name: 'AES-CTR', counter: iv, length: 64 }, aesKey, ciphertext);
+    return JSON.parse(new TextDecoder().decode(plain));
+  }
+
+  document.getElementById('unlock').addEventListener('submit', async function (e) {
+    e.preventDefault();
+    var error = document.getElementById('error');
+    error.textContent = '';
+    if (!window.crypto || !window.crypto.subtle) {
+      error.textContent = 'This browser cannot decrypt the note.';
+      return;
+    }
+    try {
+      var note = await decrypt(document.getElementById('passphrase').value);
+      document.title = note.title || 'Shared note';
+      document.getElementById('title').textContent = note.title;
+      document.getElementById('dates').textContent = 'Updated ' + new Date(note.updated_at).toLocaleString();
+      document.getElementById('content').textContent = note.content;
+      document.getElementById('locked').hidden = true;
+      document.getElementById('note').hidden = false;
+    } catch (err) {
+      error.textContent = 'That passphrase did not open the note.';
+    }
+  });
+})();
+</script>
+</body>
+</html>
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unhex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+
+    /// What the page script does: check the tag over iv ‖ ciphertext, then decrypt
+    fn open_note(passphrase: &str, sealed: &SealedNote) -> Option<Vec<u8>> {
+        use sha2::Sha256;
+        let b64 = base64::engine::general_purpose::STANDARD;
+        let salt = b64.decode(&sealed.salt).ok()?;
+        let iv: [u8; 16] = b64.decode(&sealed.iv).ok()?.try_into().ok()?;
+        let mut data = b64.decode(&sealed.ciphertext).ok()?;
+        let keys = note_keys(passphrase, &salt, sealed.iterations);
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&keys[32..]).ok()?;
+        mac.update(&iv);
+        mac.update(&data);
+        mac.verify_slice(&b64.decode(&sealed.tag).ok()?).ok()?;
+        aes_ctr(&keys[..32], &iv, &mut data);
+        Some(data)
+    }
+
+    #[test]
+    fn aes_ctr_matches_webcrypto_when_the_counter_wraps() {
+        // subtle.encrypt({ name: 'AES-CTR', counter: iv, length: 64 }, key, plaintext)
+        let key: Vec<u8> = (0..32).collect();
+        let iv: [u8; 16] = unhex("f0f1f2f3f4f5f6f7fffffffffffffffe").try_into().unwrap();
+        let mut data = b"The counter wraps inside the low 64 bits of the block.".to_vec();
+        aes_ctr(&key, &iv, &mut data);
+        assert_eq!(
+            data,
+            unhex(
+                "5d86edd2704d034432b64ac859970300c221ad58cfb43a56d8fd2ac3efb6c543\
+                 fb3a8e9232b8b68c4eb27ffb9c834bc51334f9acd4ae"
+            )
+        );
+    }
+
+    #[test]
+    fn sealed_note_matches_webcrypto() {
+        // PBKDF2-SHA256 to 512 bits, AES-CTR over the first half, HMAC-SHA256 over iv ‖ ciphertext with the second
+        let salt: [u8; 16] = std::array::from_fn(|i| 0xa0 + i as u8);
+        let iv: [u8; 16] = std::array::from_fn(|i| 0x10 + i as u8);
+        let sealed = seal_note_with(
+            "correct horse battery",
+            br#"{"title":"Hi","content":"Shared"}"#,
+            &salt,
+            &iv,
+            1000,
+        )
+        .unwrap();
+        let b64 = base64::engine::general_purpose::STANDARD;
+        assert_eq!(
+            b64.decode(&sealed.ciphertext).unwrap(),
+            unhex("3c450a44e4908a65452ea3760c3d3e3f4b58a6260465874d59a4656dfd19ed4f29")
+        );
+        assert_eq!(
+            b64.decode(&sealed.tag).unwrap(),
+            unhex("76296f02d38e1640946c7be18e3b9b98e6beef282768adbad3a9e5dd0e3530b5")
+        );
+    }
+
+    #[test]
+    fn sealed_note_opens_with_the_passphrase() {
+        let sealed = seal_note("correct horse battery", b"note body").unwrap();
+        assert_eq!(sealed.iterations, NOTE_KDF_ITERATIONS);
+        assert_eq!(open_note("correct horse battery", &sealed).as_deref(), Some(&b"note body"[..]));
+        assert_eq!(open_note("wrong horse battery", &sealed), None);
+    }
+
+    #[test]
+    fn tampered_note_is_rejected() {
+        let b64 = base64::engine::general_purpose::STANDARD;
+        let seal = || seal_note_with("correct horse battery", b"note body", &[7; 16], &[9; 16], 1000).unwrap();
+        let flip = |field: &str| {
+            let mut bytes = b64.decode(field).unwrap();
+            bytes[0] ^= 1;
+            b64.encode(bytes)
+        };
+
+        let sealed = seal();
+        let bad_tag = SealedNote { tag: flip(&sealed.tag), ..sealed };
+        assert_eq!(open_note("correct horse battery", &bad_tag), None);
+
+        let sealed = seal();
+        let bad_ciphertext = SealedNote { ciphertext: flip(&sealed.ciphertext), ..sealed };
+        assert_eq!(open_note("correct horse battery", &bad_ciphertext), None);
+    }
+}
@@ -0,0 +1,246 @@
+// site_content.rs - Full post text for captured Twitter/X, Reddit and Hacker News links
+// OpenGraph tags only carry a title and a snippet, so for these sites the post itself and its top comments
+// are pulled from each site's public JSON endpoints and rendered as markdown for the item content
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::error::BrainboxError;
+use crate::http;
+
+/// Top-level comments kept per post
+const MAX_COMMENTS: usize = 10;
+/// Longer comments are cut off at this many characters
+const MAX_COMMENT_CHARS: usize = 2000;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SiteContent {
+    /// "twitter", "reddit" or "hackernews"
+    pub site: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// Post and comments as markdown
+    pub text: String,
+    pub comment_count: usize,
+}
+
+struct Comment {
+    author: String,
+    score: Option<i64>,
+    text: String,
+}
+
+/// Post text and top comments for a supported URL; None for other sites or pages that aren't posts
+pub fn extract(url: &str) -> Result<Option<SiteContent>, BrainboxError> {
+    let parsed = reqwest::Url::parse(url).map_err(|_| BrainboxError::InvalidInput(format!("Invalid URL: {}", url)))?;
+    let host = parsed.host_str().unwrap_or("").to_lowercase();
+    let host = host.trim_start_matches("www.").trim_start_matches("mobile.");
+    match host {
+        "news.ycombinator.com" => hacker_news(&parsed),
+        "reddit.com" | "old.reddit.com" | "new.reddit.com" => reddit(&parsed),
+        "twitter.com" | "x.com" => twitter(&parsed),
+        _ => Ok(None),
+    }
+}
+
+/// Plain text from the small HTML fragments these APIs return (paragraphs, links, entities)
+fn html_to_text(html: &str) -> String {
+    let breaks = Regex::new(r"(?i)<\s*(p|br)\s*/?>").map(|re| re.replace_all(html, "\n\n").to_string());
+    let html = breaks.unwrap_or_else(|_| html.to_string());
+    let fragment = scraper::Html::parse_fragment(&html);
+    let text: String = fragment.root_element().text().collect();
+    text.lines().map(|l| l.trim_end()).collect::<Vec<_>>().join("\n").trim().to_string()
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let mut out: String = text.chars().take(max_chars).collect();
+    out.push('…');
+    out
+}
+
+fn render(title: Option<&str>, byline: &str, body: &str, comments: &[Comment]) -> String {
+    let mut out = String::new();
+    if let Some(title) = title {
+        out.push_str(&format!("# {}\n\n", title));
+    }
+    if !byline.is_empty() {
+        out.push_str(&format!("*{}*\n\n", byline));
+    }
+    if !body.trim().is_empty() {
+        out.push_str(body.trim());
+        out.push_str("\n\n");
+    }
+    if !comments.is_empty() {
+        out.push_str("## Top comments\n\n");
+        for comment in comments {
+            match comment.score {
+                Some(score) => out.push_str(&format!("**{}** ({} points):\n", comment.author, score)),
+                None => out.push_str(&format!("**{}**:\n", comment.author)),
+            }
+            out.push_str(&truncate(comment.text.trim(), MAX_COMMENT_CHARS));
+            out.push_str("\n\n");
+        }
+    }
+    out.trim_end().to_string()
+}
+
+fn get_json(url: &str) -> Result<serde_json::Value, BrainboxError> {
+    let resp = http::send(http::client().get(url))?;
+    if !resp.status().is_success() {
+        return Err(BrainboxError::Network(format!("Request failed with status {}", resp.status())));
+    }
+    Ok(resp.json()?)
+}
+
+fn str_field(value: &serde_json::Value, key: &str) -> Option<String> {
+    value.get(key).and_then(|v| v.as_str()).map(|s| s.to_string()).filter(|s| !s.is_empty())
+}
+
+/// Hacker News item via the Algolia API, which returns the story and its whole comment tree in one request
+fn hacker_news(url: &reqwest::Url) -> Result<Option<SiteContent>, BrainboxError> {
+    if url.path() != "/item" {
+        return Ok(None);
+    }
+    let Some(id) = url.query_pairs().find(|(k, _)| k == "id").map(|(_, v)| v.to_string()) else {
+        return Ok(None);
+    };
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_digit()) {
+        return Ok(None);
+    }
+    let item = get_json(&format!("https://hn.algolia.com/api/v1/items/{}", id))?;
+    let title = str_field(&item, "title");
+    let author = str_field(&item, "author");
+    let mut body = str_field(&item, "text").map(|t| html_to_text(&t)).unwrap_or_default();
+    if let Some(link) = str_field(&item, "url") {
+        body = format!("{}\n\n{}", link, body);
+    }
+    // Algolia keeps HN's ranking order for children
+    let comments: Vec<Comment> = item
+        .get("children")
+        .and_then(|c| c.as_array())
+        .map(|children| {
+            children
+                .iter()
+                .filter_map(|c| {
+                    Some(Comment {
+                        author: str_field(c, "author")?,
+                        score: None,
+                        text: html_to_text(&str_field(c, "text")?),
+                    })
+                })
+                .take(MAX_COMMENTS)
+                .collect()
+        })
+        .unwrap_or_default();
+    let points = item.get("points").and_then(|p| p.as_i64());
+    let byline = match (&author, points) {
+        (Some(a), Some(p)) => format!("by {} · {} points on Hacker News", a, p),
+        (Some(a), None) => format!("by {} on Hacker News", a),
+        _ => "Hacker News".to_string(),
+    };
+    Ok(Some(SiteContent {
+        site: "hackernews".to_string(),
+        text: render(title.as_deref(), &byline, &body, &comments),
+        comment_count: comments.len(),
+        title,
+        author,
+    }))
+}
+
+/// Reddit post via the .json view of the permalink, with top-sorted comments
+fn reddit(url: &reqwest::Url) -> Result<Option<SiteContent>, BrainboxError> {
+    let path = url.path().trim_end_matches('/');
+    if !path.contains("/comments/") {
+        return Ok(None);
+    }
+    let api = format!(
+        "https://www.reddit.com{}.json?limit={}&sort=top&depth=1&raw_json=1",
+        path, MAX_COMMENTS
+    );
+    let listing = get_json(&api)?;
+    let Some(post) = listing.pointer("/0/data/children/0/data") else {
+        return Ok(None);
+    };
+    let title = str_field(post, "title");
+    let author = str_field(post, "author");
+    let mut body = str_field(post, "selftext").unwrap_or_default();
+    // Link posts point somewhere else; keep that target alongside the discussion
+    if !post.get("is_self").and_then(|v| v.as_bool()).unwrap_or(true) {
+        if let Some(link) = str_field(post, "url") {
+            body = format!("{}\n\n{}", link, body);
+        }
+    }
+    let comments: Vec<Comment> = listing
+        .pointer("/1/data/children")
+        .and_then(|c| c.as_array())
+        .map(|children| {
+            children
+                .iter()
+                .filter(|c| c.get("kind").and_then(|k| k.as_str()) == Some("t1"))
+                .filter_map(|c| {
+                    let data = c.get("data")?;
+                    Some(Comment {
+                        author: str_field(data, "author")?,
+                        score: data.get("score").and_then(|s| s.as_i64()),
+                        text: str_field(data, "body")?,
+                    })
+                })
+                .take(MAX_COMMENTS)
+                .collect()
+        })
+        .unwrap_or_default();
+    let subreddit = str_field(post, "subreddit_name_prefixed").unwrap_or_else(|| "Reddit".to_string());
+    let byline = match &author {
+        Some(a) => format!("by u/{} in {}", a, subreddit),
+        None => subreddit,
+    };
+    Ok(Some(SiteContent {
+        site: "reddit".to_string(),
+        text: render(title.as_deref(), &byline, &body, &comments),
+        comment_count: comments.len(),
+        title,
+        author,
+    }))
+}
+
+/// Tweet text via the public oEmbed endpoint. Replies aren't available without an API key,
+/// so only the post itself is captured.
+fn twitter(url: &reqwest::Url) -> Result<Option<SiteContent>, BrainboxError> {
+    let segments: Vec<&str> = url.path_segments().map(|s| s.collect()).unwrap_or_default();
+    let (Some(user), Some("status"), Some(id)) = (segments.first(), segments.get(1).copied(), segments.get(2)) else {
+        return Ok(None);
+    };
+    if !id.chars().all(|c| c.is_ascii_digit()) {
+        return Ok(None);
+    }
+    let status_url = format!("https://twitter.com/{}/status/{}", user, id);
+    let api = format!(
+        "https://publish.twitter.com/oembed?omit_script=1&dnt=1&url={}",
+        urlencoding::encode(&status_url)
+    );
+    let embed = get_json(&api)?;
+    let Some(html) = str_field(&embed, "html") else { return Ok(None) };
+    // The embed is a blockquote: the tweet paragraph followed by an "— Name (@user) date" line
+    let fragment = scraper::Html::parse_fragment(&html);
+    let text = scraper::Selector::parse("blockquote > p")
+        .ok()
+        .and_then(|sel| fragment.select(&sel).next().map(|p| html_to_text(&p.inner_html())))
+        .unwrap_or_else(|| html_to_text(&html));
+    let author = str_field(&embed, "author_name");
+    let byline = match &author {
+        Some(a) => format!("{} (@{}) on X", a, user),
+        None => format!("@{} on X", user),
+    };
+    Ok(Some(SiteContent {
+        site: "twitter".to_string(),
+        text: render(None, &byline, &text, &[]),
+        comment_count: 0,
+        title: None,
+        author,
+    }))
+}
@@ -0,0 +1,99 @@
+// storage.rs - Disk usage report for brainbox
+// Sizes of everything brainbox keeps on disk, grouped the way the settings page shows them. Thumbnails, favicons and
+// the update rollback copy can be rebuilt or are optional, so those categories can be cleared from here.
+
+use rusqlite::Connection;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::error::BrainboxError;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct StorageUsage {
+    /// Database file plus its WAL and shared-memory files
+    pub database_bytes: u64,
+    pub search_index_bytes: u64,
+    /// Screenshots and captures brought in by sync
+    pub captures_bytes: u64,
+    /// Cover images and cached copies of item images
+    pub attachments_bytes: u64,
+    pub thumbnails_bytes: u64,
+    pub favicons_bytes: u64,
+    /// The previous version kept for rolling back an update
+    pub backups_bytes: u64,
+    pub logs_bytes: u64,
+    pub total_bytes: u64,
+}
+
+/// Total size of the files under `path`, or of `path` itself when it is a file
+fn size_of(path: &Path) -> u64 {
+    let Ok(meta) = std::fs::symlink_metadata(path) else { return 0 };
+    if !meta.is_dir() {
+        return meta.len();
+    }
+    std::fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|entry| size_of(&entry.path())).sum())
+        .unwrap_or(0)
+}
+
+fn sum(paths: &[Option<PathBuf>]) -> u64 {
+    paths.iter().flatten().map(|p| size_of(p)).sum()
+}
+
+pub fn usage() -> Result<StorageUsage, BrainboxError> {
+    let app_dir = crate::data_dir::root().ok_or("Failed to get app data dir")?;
+    let db_path = crate::db::db_path()?;
+    let mut database_bytes = size_of(&db_path);
+    for suffix in ["-wal", "-shm"] {
+        let mut name = db_path.clone().into_os_string();
+        name.push(suffix);
+        database_bytes += size_of(Path::new(&name));
+    }
+    let mut usage = StorageUsage {
+        database_bytes,
+        search_index_bytes: size_of(&app_dir.join("search_index")),
+        captures_bytes: sum(&[Some(app_dir.join("brainbox").join("captures")), Some(app_dir.join("brainbox_captures"))]),
+        attachments_bytes: sum(&[crate::covers::cover_dir(), crate::image_cache::cache_dir()]),
+        thumbnails_bytes: sum(&[crate::thumbnails::thumbnail_dir()]),
+        favicons_bytes: sum(&[crate::favicons::cache_dir()]),
+        backups_bytes: sum(&[crate::updater::backup_dir()]),
+        logs_bytes: sum(&[crate::logging::log_dir()]),
+        total_bytes: 0,
+    };
+    usage.total_bytes = usage.database_bytes
+        + usage.search_index_bytes
+        + usage.captures_bytes
+        + usage.attachments_bytes
+        + usage.thumbnails_bytes
+        + usage.favicons_bytes
+        + usage.backups_bytes
+        + usage.logs_bytes;
+    Ok(usage)
+}
+
+/// Delete a reclaimable category: "thumbnails", "favicons" or "backups". Returns the bytes freed.
+pub fn clear_cache(conn: &Connection, kind: &str) -> Result<u64, BrainboxError> {
+    let dir = match kind {
+        "thumbnails" => crate::thumbnails::thumbnail_dir(),
+        "favicons" => crate::favicons::cache_dir(),
+        "backups" => crate::updater::backup_dir(),
+        _ => return Err(BrainboxError::InvalidInput(format!("Cache '{}' can't be cleared", kind))),
+    }
+    .ok_or("Failed to get app data dir")?;
+    let freed = size_of(&dir);
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir)?;
+    }
+    match kind {
+        // Hosts that had no favicon are asked again too
+        "favicons" => {
+            crate::favicons::create_table(conn)?;
+            conn.execute("DELETE FROM favicons", [])?;
+        }
+        // Without its backup the previous version can no longer be restored
+        "backups" => crate::updater::set_previous_version(conn, None)?,
+        _ => {}
+    }
+    tracing::info!("Cleared {} cache, {} bytes freed", kind, freed);
+    Ok(freed)
+}
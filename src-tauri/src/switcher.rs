@@ -0,0 +1,113 @@
+// switcher.rs - Quick switcher window for brainbox
+// A small always-on-top search window opened by hotkey; picking a result opens the item in the main window
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, WebviewUrl, WebviewWindowBuilder, WindowEvent};
+
+use crate::error::BrainboxError;
+use crate::vault::VaultItem;
+
+pub const WINDOW_LABEL: &str = "switcher";
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SwitcherResult {
+    pub item_id: i64,
+    pub vault_id: i64,
+    pub title: String,
+    pub preview: String,
+    pub item_type: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[allow(non_snake_case)]
+struct OpenItemEvent {
+    itemId: i64,
+    vaultId: i64,
+}
+
+/// Show the switcher, creating its window on first use
+pub fn show(app: &AppHandle) -> Result<(), BrainboxError> {
+    let window = match app.get_webview_window(WINDOW_LABEL) {
+        Some(window) => window,
+        None => {
+            let window = WebviewWindowBuilder::new(app, WINDOW_LABEL, WebviewUrl::App("index.html?window=switcher".into()))
+                .title(crate::i18n::tr("brainbox quick switcher"))
+                .inner_size(640.0, 420.0)
+                .resizable(false)
+                .decorations(false)
+                .always_on_top(true)
+                .skip_taskbar(true)
+                .center()
+                .build()?;
+            // Behave like a popup: dismiss as soon as focus moves elsewhere
+            let handle = window.clone();
+            window.on_window_event(move |event| {
+                if let WindowEvent::Focused(false) = event {
+                    let _ = handle.hide();
+                }
+            });
+            window
+        }
+    };
+    window.center()?;
+    window.show()?;
+    window.set_focus()?;
+    // Lets the frontend clear the previous query and focus the input
+    let _ = app.emit_to(WINDOW_LABEL, "switcher-opened", ());
+    Ok(())
+}
+
+pub fn hide(app: &AppHandle) -> Result<(), BrainboxError> {
+    if let Some(window) = app.get_webview_window(WINDOW_LABEL) {
+        window.hide()?;
+    }
+    Ok(())
+}
+
+pub fn toggle(app: &AppHandle) -> Result<(), BrainboxError> {
+    match app.get_webview_window(WINDOW_LABEL) {
+        Some(window) if window.is_visible().unwrap_or(false) => hide(app),
+        _ => show(app),
+    }
+}
+
+/// Hide the switcher and ask the main window to open the item
+pub fn open_item(app: &AppHandle, item_id: i64, vault_id: i64) -> Result<(), BrainboxError> {
+    hide(app)?;
+    if let Some(main) = app.get_webview_window("main") {
+        let _ = main.unminimize();
+        main.show()?;
+        main.set_focus()?;
+    }
+    app.emit_to("main", "open-item", OpenItemEvent { itemId: item_id, vaultId: vault_id })?;
+    Ok(())
+}
+
+/// Instant search for the switcher: index hits joined with their vault, skipping deleted items
+pub fn search(query: &str, limit: usize) -> Result<Vec<SwitcherResult>, BrainboxError> {
+    if query.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let service = crate::search::get_search_service()
+        .ok_or_else(|| BrainboxError::Search("Search service not initialized".to_string()))?;
+    let hits = service
+        .search(query, limit, None)
+        .map_err(|e| BrainboxError::Search(e.to_string()))?;
+    let conn = crate::db::open()?;
+    let mut results = Vec::new();
+    for hit in hits {
+        let Ok(item_id) = hit.id.parse::<i64>() else { continue };
+        let Ok(item) = VaultItem::get_by_id(&conn, item_id) else { continue };
+        if item.deleted_at.is_some() {
+            continue;
+        }
+        results.push(SwitcherResult {
+            item_id,
+            vault_id: item.vault_id,
+            title: hit.title,
+            preview: hit.content_preview,
+            item_type: hit.metadata.item_type,
+        });
+    }
+    Ok(results)
+}
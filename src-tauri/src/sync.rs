@@ -1,12 +1,22 @@
 // sync.rs - Sync functionality for brainbox
-// Handles export/import of vaults to sync folder for cross-device synchronization
+// Handles export/import of vaults for cross-device synchronization. Building and merging snapshots lives here;
+// moving them between devices is a `SyncBackend`: a shared folder (below) or a self-hosted server (sync_server.rs).
 
 use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
-use crate::vault::{Vault, VaultItem, SyncSettings};
+use crate::settings;
+use crate::vault::{self, Vault, VaultItem, Collection, VaultSettings};
+use crate::reminders::Reminder;
+use crate::envelope;
+use crate::sync_captures;
+use crate::sync_manifest;
+use crate::tombstones;
+use crate::progress::Operation;
+use crate::keystore::{self, VaultKey};
+use zeroize::Zeroizing;
 use chacha20poly1305::{aead::Aead, KeyInit, XChaCha20Poly1305, Key, XNonce};
 
 /// Sync file format version
@@ -14,9 +24,17 @@ pub const SYNC_FORMAT_VERSION: &str = "1.0";
 
 /// Sync file name
 pub const SYNC_FILE_NAME: &str = "brainbox.sync";
+/// The previous sync file, read when the current one is missing or damaged
+pub const SYNC_BACKUP_NAME: &str = "brainbox.sync.bak";
+/// A new sync file is written here in full, then renamed over the old one
+const SYNC_TEMP_NAME: &str = "brainbox.sync.tmp";
 
 /// Captures subfolder name
 pub const CAPTURES_FOLDER_NAME: &str = "captures";
+/// Vault cover files referenced as "covers/<file>" by synced vaults
+pub const COVERS_FOLDER_NAME: &str = "covers";
+/// Error text of a sync stopped through `progress::cancel`
+const CANCELLED: &str = "Sync cancelled";
 
 // --- Sync Data Structures ---
 
@@ -40,8 +58,91 @@ pub struct SyncVault {
     pub deleted_at: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cover_image: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
     pub has_password: bool,
     pub items: Vec<SyncItem>,
+    /// Older sync files predate collections, so this defaults to empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub collections: Vec<SyncCollection>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub boards: Vec<SyncBoard>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub settings: Vec<SyncVaultSetting>,
+}
+
+/// A per-vault setting; a None value is a cleared key
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SyncVaultSetting {
+    pub key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SyncCollection {
+    pub uuid: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_uuid: Option<String>,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort_order: Option<i64>,
+    pub created_at: String,
+    pub updated_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<String>,
+}
+
+/// A kanban board with its columns; merged by UUID like collections
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SyncBoard {
+    pub uuid: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort_order: Option<i64>,
+    pub created_at: String,
+    pub updated_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<String>,
+    pub columns: Vec<SyncBoardColumn>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SyncBoardColumn {
+    pub uuid: String,
+    pub name: String,
+    pub sort_order: i64,
+    pub created_at: String,
+    pub updated_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<String>,
+}
+
+/// The column an item sits in on one board
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SyncBoardPlacement {
+    pub column_uuid: String,
+    pub position: i64,
+}
+
+/// A highlight on an item, decrypted like its content
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SyncAnnotation {
+    pub uuid: String,
+    pub quote: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anchor: Option<serde_json::Value>,
+    pub created_at: String,
+    pub updated_at: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -59,6 +160,30 @@ pub struct SyncItem {
     pub summary: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sort_order: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collection_uuid: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub item_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub read_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub board_placements: Vec<SyncBoardPlacement>,
+    /// None in files from before annotations synced, which leaves the local ones alone
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<Vec<SyncAnnotation>>,
+    /// The exporting device's logical clock for the item; None in files from before items were versioned
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<i64>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -89,38 +214,37 @@ pub struct SyncImportResult {
     pub conflicts: Vec<String>, // Item titles that had conflicts
     pub warnings: Vec<String>,
     pub skipped_vaults: Vec<String>, // Names of vaults skipped due to password mismatch
+    /// Vaults whose merge failed and was rolled back; the rest of the import still applied
+    pub failed_vaults: Vec<SyncVaultFailure>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SyncVaultFailure {
+    pub name: String,
+    pub error: String,
 }
 
 // --- Helper Functions ---
 
 /// Decrypt content using XChaCha20-Poly1305
 fn decrypt_content(key: &[u8; 32], encrypted: &[u8]) -> Result<String, String> {
-    if encrypted.len() < 24 {
-        return Err("Invalid ciphertext".into());
-    }
-    let mut nonce_bytes = [0u8; 24];
-    nonce_bytes.copy_from_slice(&encrypted[..24]);
-    let nonce = XNonce::from_slice(&nonce_bytes);
-    let cipher = XChaCha20Poly1305::new(Key::from_slice(key));
-    let plaintext = cipher
-        .decrypt(nonce, &encrypted[24..])
-        .map_err(|_| "Decryption failed".to_string())?;
+    let plaintext = envelope::open(key, encrypted).map_err(|_| "Decryption failed".to_string())?;
     String::from_utf8(plaintext).map_err(|_| "Invalid UTF-8".to_string())
 }
 
 /// Get or create device ID
-fn get_or_create_device_id(conn: &Connection) -> Result<String, String> {
-    if let Some(id) = SyncSettings::get(conn, "device_id").map_err(|e| e.to_string())? {
+pub(crate) fn get_or_create_device_id(conn: &Connection) -> Result<String, String> {
+    if let Some(id) = settings::get(conn, "device_id").map_err(|e| e.to_string())? {
         return Ok(id);
     }
     let new_id = uuid::Uuid::new_v4().to_string();
-    SyncSettings::set(conn, "device_id", &new_id).map_err(|e| e.to_string())?;
+    settings::set(conn, "device_id", &new_id).map_err(|e| e.to_string())?;
     Ok(new_id)
 }
 
 /// Get device name (hostname or custom name)
-fn get_device_name(conn: &Connection) -> Result<String, String> {
-    if let Some(name) = SyncSettings::get(conn, "device_name").map_err(|e| e.to_string())? {
+pub(crate) fn get_device_name(conn: &Connection) -> Result<String, String> {
+    if let Some(name) = settings::get(conn, "device_name").map_err(|e| e.to_string())? {
         return Ok(name);
     }
     // Default to hostname
@@ -129,20 +253,225 @@ fn get_device_name(conn: &Connection) -> Result<String, String> {
 
 /// Get sync folder path from settings
 pub fn get_sync_folder(conn: &Connection) -> Result<Option<String>, String> {
-    SyncSettings::get(conn, "sync_folder").map_err(|e| e.to_string())
+    settings::get(conn, "sync_folder").map_err(|e| e.to_string())
 }
 
 /// Set sync folder path in settings
 pub fn set_sync_folder(conn: &Connection, path: &str) -> Result<(), String> {
-    SyncSettings::set(conn, "sync_folder", path).map_err(|e| e.to_string())
+    settings::set(conn, "sync_folder", path).map_err(|e| e.to_string())
 }
 
 /// Get captures folder path (from app data directory)
 fn get_captures_folder() -> Result<PathBuf, String> {
-    let app_dir = dirs::data_local_dir().ok_or("Failed to get app data dir")?;
+    let app_dir = crate::data_dir::root().ok_or("Failed to get app data dir")?;
     Ok(app_dir.join("brainbox_captures"))
 }
 
+// --- Sync Backends ---
+
+/// Transport for sync snapshots. Export builds a `SyncFile` and pushes it; import pulls one and merges it.
+pub trait SyncBackend {
+    /// Publish this device's vaults. Backends that carry capture files list them in `file.captures`.
+    /// Returns the number of captures sent.
+    fn push(&self, conn: &Connection, file: &mut SyncFile, warnings: &mut Vec<String>) -> Result<usize, String>;
+
+    /// Changes from other devices, or None when there is no sync data yet. Must not change local state,
+    /// since previews pull too.
+    fn pull(&self, conn: &Connection) -> Result<Option<SyncFile>, String>;
+
+    /// Bring in capture and cover files after a pull has been merged. Returns the number of captures copied.
+    fn pull_files(&self, _conn: &Connection, _warnings: &mut Vec<String>) -> Result<usize, String> {
+        Ok(0)
+    }
+
+    /// Called once a pull has been merged, so incremental backends can advance their cursor
+    fn finish_pull(&self, _conn: &Connection) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Setting choosing the backend: "folder" (default) or "server"
+pub const SYNC_BACKEND_KEY: &str = "sync_backend";
+
+/// Backend selected in settings
+pub fn configured_backend(conn: &Connection) -> Result<Box<dyn SyncBackend>, String> {
+    match settings::get(conn, SYNC_BACKEND_KEY).map_err(|e| e.to_string())?.as_deref() {
+        Some("server") => Ok(Box::new(crate::sync_server::ServerBackend::from_settings(conn)?)),
+        _ => Ok(Box::new(FolderBackend::from_settings(conn)?)),
+    }
+}
+
+/// The sync file in `folder`, or the previous one when it is missing or damaged, say by a crash or a cloud client
+/// syncing it mid-write. None when neither exists.
+pub fn read_sync_file(folder: &Path) -> Result<Option<SyncFile>, String> {
+    let path = folder.join(SYNC_FILE_NAME);
+    let backup = folder.join(SYNC_BACKUP_NAME);
+    let primary_error = if path.exists() {
+        match sync_manifest::load(folder, &path) {
+            Ok(sync_file) => return Ok(Some(sync_file)),
+            Err(e) => Some(e),
+        }
+    } else {
+        None
+    };
+    if !backup.exists() {
+        return primary_error.map_or(Ok(None), Err);
+    }
+    match sync_manifest::load(folder, &backup) {
+        Ok(sync_file) => {
+            let reason = primary_error.as_deref().unwrap_or("it is missing");
+            tracing::warn!("Using {} because {} can't be used: {}", SYNC_BACKUP_NAME, SYNC_FILE_NAME, reason);
+            Ok(Some(sync_file))
+        }
+        Err(e) => Err(match primary_error {
+            Some(primary) => format!("{} (the backup is unreadable too: {})", primary, e),
+            None => e,
+        }),
+    }
+}
+
+/// Publish `file` in format v2 without ever leaving a partial sync file: the changed vault files are written first,
+/// then the manifest goes to a temp file, is flushed to disk and renamed into place. The previous sync file is kept
+/// as the backup unless it doesn't load, so it can't replace a good one; a v1 file becomes the backup on the first
+/// v2 export.
+fn write_sync_file(folder: &Path, file: &SyncFile, warnings: &mut Vec<String>) -> Result<(), String> {
+    let path = folder.join(SYNC_FILE_NAME);
+    let backup = folder.join(SYNC_BACKUP_NAME);
+    let manifest = sync_manifest::write_vaults(folder, file)?;
+    let temp = folder.join(SYNC_TEMP_NAME);
+    write_temp(&temp, &manifest).map_err(|e| format!("Failed to write sync file: {}", e))?;
+    if sync_manifest::load(folder, &path).is_ok() {
+        if let Err(e) = fs::copy(&path, &backup) {
+            warnings.push(format!("Failed to back up the previous sync file: {}", e));
+        }
+    }
+    fs::rename(&temp, &path).map_err(|e| format!("Failed to write sync file: {}", e))?;
+    if let Err(e) = sync_manifest::remove_unreferenced(folder, &path, &backup) {
+        warnings.push(format!("Failed to remove old vault files: {}", e));
+    }
+    Ok(())
+}
+
+fn write_temp(temp: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    let mut file = fs::File::create(temp)?;
+    file.write_all(bytes)?;
+    file.sync_all()
+}
+
+/// A folder shared between devices by a cloud drive or network share, holding one sync file
+pub struct FolderBackend {
+    folder: PathBuf,
+}
+
+impl FolderBackend {
+    pub fn from_settings(conn: &Connection) -> Result<Self, String> {
+        let folder = get_sync_folder(conn)?
+            .ok_or("Sync folder not configured. Please set a sync folder in settings.")?;
+        Ok(FolderBackend { folder: PathBuf::from(folder) })
+    }
+}
+
+impl SyncBackend for FolderBackend {
+    fn push(&self, conn: &Connection, file: &mut SyncFile, warnings: &mut Vec<String>) -> Result<usize, String> {
+        // Validate sync folder exists
+        if !self.folder.exists() {
+            return Err(format!("Sync folder does not exist: {}", self.folder.display()));
+        }
+
+        // Create captures subfolder if missing
+        let captures_dest = self.folder.join(CAPTURES_FOLDER_NAME);
+        if !captures_dest.exists() {
+            fs::create_dir_all(&captures_dest)
+                .map_err(|e| format!("Failed to create captures folder: {}", e))?;
+        }
+
+        // Copy captures allowed by the capture sync settings to the sync folder
+        let mut sync_captures = Vec::new();
+        let local_captures_folder = get_captures_folder()?;
+        let capture_settings = sync_captures::load_settings(conn)?;
+        for capture in sync_captures::select(conn, &capture_settings, &local_captures_folder, warnings)? {
+            let dest_path = captures_dest.join(&capture.filename);
+
+            // Only copy if file doesn't exist or is newer
+            let should_copy = match fs::metadata(&dest_path) {
+                Ok(dest_meta) => capture.modified > dest_meta.modified().ok(),
+                Err(_) => true,
+            };
+            if should_copy {
+                if let Err(e) = fs::copy(&capture.path, &dest_path) {
+                    warnings.push(format!("Failed to copy capture '{}': {}", capture.filename, e));
+                    continue;
+                }
+            }
+
+            sync_captures.push(SyncCapture {
+                created_at: capture.created
+                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                    .map(|d| chrono::DateTime::from_timestamp(d.as_secs() as i64, 0)
+                        .map(|dt| dt.to_rfc3339())
+                        .unwrap_or_default())
+                    .unwrap_or_else(|| chrono::Utc::now().to_rfc3339()),
+                filename: capture.filename,
+                size_bytes: capture.size,
+            });
+        }
+
+        // Copy cover files; they are content-addressed, so existing ones never need updating
+        if let Some(local_covers) = crate::covers::cover_dir() {
+            if let Err(e) = crate::covers::copy_missing(&local_covers, &self.folder.join(COVERS_FOLDER_NAME)) {
+                warnings.push(format!("Failed to copy cover images: {}", e));
+            }
+        }
+
+        file.captures = sync_captures;
+
+        write_sync_file(&self.folder, file, warnings)?;
+        Ok(file.captures.len())
+    }
+
+    fn pull(&self, _conn: &Connection) -> Result<Option<SyncFile>, String> {
+        read_sync_file(&self.folder)
+    }
+
+    fn pull_files(&self, conn: &Connection, warnings: &mut Vec<String>) -> Result<usize, String> {
+        // Copy captures allowed by the capture sync settings from the sync folder
+        let captures_src = self.folder.join(CAPTURES_FOLDER_NAME);
+        let local_captures_folder = get_captures_folder()?;
+        let mut imported_captures = 0;
+
+        if captures_src.exists() {
+            // Create local captures folder if it doesn't exist
+            if !local_captures_folder.exists() {
+                fs::create_dir_all(&local_captures_folder)
+                    .map_err(|e| format!("Failed to create local captures folder: {}", e))?;
+            }
+
+            let capture_settings = sync_captures::load_settings(conn)?;
+            for capture in sync_captures::select(conn, &capture_settings, &captures_src, warnings)? {
+                let dest_path = local_captures_folder.join(&capture.filename);
+                // Only copy if file doesn't exist locally
+                if dest_path.exists() {
+                    continue;
+                }
+                if let Err(e) = fs::copy(&capture.path, &dest_path) {
+                    warnings.push(format!("Failed to copy capture '{}': {}", capture.filename, e));
+                } else {
+                    imported_captures += 1;
+                }
+            }
+        }
+
+        // Copy cover files referenced by the imported vaults
+        if let Some(local_covers) = crate::covers::cover_dir() {
+            if let Err(e) = crate::covers::copy_missing(&self.folder.join(COVERS_FOLDER_NAME), &local_covers) {
+                warnings.push(format!("Failed to copy cover images: {}", e));
+            }
+        }
+        Ok(imported_captures)
+    }
+}
+
 // --- Export Functions ---
 
 /// Export all vaults and captures to sync folder
@@ -150,28 +479,25 @@ fn get_captures_folder() -> Result<PathBuf, String> {
 pub fn sync_export(
     conn: &Connection,
     passwords: HashMap<i64, Vec<u8>>,
+    op: &Operation,
+) -> Result<SyncExportResult, String> {
+    settings::create_table(conn).map_err(|e| e.to_string())?;
+    let backend = configured_backend(conn)?;
+    sync_export_to(conn, backend.as_ref(), passwords, op)
+}
+
+/// Export all vaults through `backend`. Cancelling stops before anything is pushed.
+pub fn sync_export_to(
+    conn: &Connection,
+    backend: &dyn SyncBackend,
+    mut passwords: HashMap<i64, Vec<u8>>,
+    op: &Operation,
 ) -> Result<SyncExportResult, String> {
     // Ensure tables exist
     Vault::create_table(conn).map_err(|e| e.to_string())?;
     VaultItem::create_table(conn).map_err(|e| e.to_string())?;
-    SyncSettings::create_table(conn).map_err(|e| e.to_string())?;
-
-    // Get sync folder
-    let sync_folder_str = get_sync_folder(conn)?
-        .ok_or("Sync folder not configured. Please set a sync folder in settings.")?;
-    let sync_folder = Path::new(&sync_folder_str);
-
-    // Validate sync folder exists
-    if !sync_folder.exists() {
-        return Err(format!("Sync folder does not exist: {}", sync_folder_str));
-    }
-
-    // Create captures subfolder if missing
-    let captures_dest = sync_folder.join(CAPTURES_FOLDER_NAME);
-    if !captures_dest.exists() {
-        fs::create_dir_all(&captures_dest)
-            .map_err(|e| format!("Failed to create captures folder: {}", e))?;
-    }
+    Collection::create_table(conn).map_err(|e| e.to_string())?;
+    settings::create_table(conn).map_err(|e| e.to_string())?;
 
     // Get device info
     let device_id = get_or_create_device_id(conn)?;
@@ -183,6 +509,7 @@ pub fn sync_export(
     let mut sync_vaults = Vec::new();
     let mut skipped_vaults = Vec::new();
     let mut exported_items = 0;
+    let mut synced_versions = Vec::new();
     let mut warnings = Vec::new();
 
     for vault in vaults {
@@ -192,17 +519,19 @@ pub fn sync_export(
         });
 
         // Check if vault has password and we have the key
-        let key: Option<[u8; 32]> = if vault.has_password {
-            if let Some(key_vec) = passwords.get(&vault.id) {
-                if key_vec.len() == 32 {
-                    let mut arr = [0u8; 32];
-                    arr.copy_from_slice(key_vec);
-                    Some(arr)
-                } else {
-                    skipped_vaults.push(vault.name.clone());
-                    warnings.push(format!("Skipped vault '{}': invalid key length", vault.name));
-                    continue;
+        let key: Option<VaultKey> = if vault.has_password {
+            if let Some(key_vec) = passwords.remove(&vault.id) {
+                match keystore::key_from_bytes(key_vec) {
+                    Ok(key) => Some(key),
+                    Err(_) => {
+                        skipped_vaults.push(vault.name.clone());
+                        warnings.push(format!("Skipped vault '{}': invalid key length", vault.name));
+                        continue;
+                    }
                 }
+            } else if let Some(key) = keystore::get(vault.id) {
+                // Vaults unlocked this session can sync without their key being passed
+                Some(key)
             } else {
                 skipped_vaults.push(vault.name.clone());
                 warnings.push(format!("Skipped vault '{}': password required but not provided", vault.name));
@@ -215,13 +544,71 @@ pub fn sync_export(
         };
 
         let key = key.unwrap();
+        let key = envelope::content_key(conn, vault.id, &key).map_err(|e| e.to_string())?;
+
+        // Collections travel by UUID so parent links and item placement survive re-numbering
+        let collections = Collection::list_all_by_vault_for_sync(conn, vault.id)
+            .map_err(|e| e.to_string())?;
+        let collection_uuids: HashMap<i64, String> = collections
+            .iter()
+            .map(|c| (c.id, c.uuid.clone()))
+            .collect();
+        let sync_collections: Vec<SyncCollection> = collections
+            .iter()
+            .map(|c| SyncCollection {
+                uuid: c.uuid.clone(),
+                parent_uuid: c.parent_id.and_then(|pid| collection_uuids.get(&pid).cloned()),
+                name: c.name.clone(),
+                sort_order: c.sort_order,
+                created_at: c.created_at.clone(),
+                updated_at: c.updated_at.clone(),
+                deleted_at: c.deleted_at.clone(),
+            })
+            .collect();
+
+        let vault_settings = VaultSettings::list_for_sync(conn, vault.id)
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|(key, value, updated_at)| SyncVaultSetting { key, value, updated_at })
+            .collect();
+        let sync_boards = crate::boards::list_all_for_sync(conn, vault.id)
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|b| SyncBoard {
+                uuid: b.uuid,
+                name: b.name,
+                sort_order: b.sort_order,
+                created_at: b.created_at,
+                updated_at: b.updated_at,
+                deleted_at: b.deleted_at,
+                columns: b.columns.into_iter().map(|c| SyncBoardColumn {
+                    uuid: c.uuid,
+                    name: c.name,
+                    sort_order: c.sort_order,
+                    created_at: c.created_at,
+                    updated_at: c.updated_at,
+                    deleted_at: c.deleted_at,
+                }).collect(),
+            })
+            .collect();
+        let mut placements = crate::boards::placements_by_vault(conn, vault.id).map_err(|e| e.to_string())?;
+        let mut annotations = crate::annotations::by_vault(conn, vault.id, &key).map_err(|e| e.to_string())?;
 
         // Get all items for this vault (including soft-deleted)
         let items = VaultItem::list_all_by_vault_for_sync(conn, vault.id)
             .map_err(|e| e.to_string())?;
+        let local_only = VaultItem::count_no_sync_by_vault(conn, vault.id).map_err(|e| e.to_string())?;
+        if local_only > 0 {
+            warnings.push(format!("Kept {} local-only item(s) in vault '{}' out of the sync file", local_only, vault.name));
+        }
 
         let mut sync_items = Vec::new();
-        for item in items {
+        let total = items.len();
+        for (done, item) in items.into_iter().enumerate() {
+            if op.is_cancelled() {
+                return Err(CANCELLED.to_string());
+            }
+            op.progress(&vault.name, done, total);
             let item_uuid = item.uuid.clone().unwrap_or_else(|| {
                 warnings.push(format!("Item '{}' has no UUID, generating one", item.title));
                 uuid::Uuid::new_v4().to_string()
@@ -244,10 +631,24 @@ pub fn sync_export(
                 created_at: item.created_at,
                 updated_at: item.updated_at,
                 deleted_at: item.deleted_at,
-                image: item.image,
+                image: crate::image_cache::portable_image(conn, item.image),
                 summary: item.summary,
                 sort_order: item.sort_order,
+                collection_uuid: item.collection_id.and_then(|cid| collection_uuids.get(&cid).cloned()),
+                item_type: item.item_type,
+                due_at: item.due_at,
+                completed_at: item.completed_at,
+                read_at: item.read_at,
+                archive_url: item.archive_url,
+                color: item.color,
+                icon: item.icon,
+                board_placements: placements.remove(&item.id).unwrap_or_default().into_iter()
+                    .map(|(column_uuid, position)| SyncBoardPlacement { column_uuid, position })
+                    .collect(),
+                annotations: Some(annotations.remove(&item.id).unwrap_or_default()),
+                version: Some(item.version),
             });
+            synced_versions.push((item.id, item.version));
             exported_items += 1;
         }
 
@@ -258,85 +659,40 @@ pub fn sync_export(
             updated_at: vault.updated_at.unwrap_or_else(|| chrono::Utc::now().to_rfc3339()),
             deleted_at: vault.deleted_at,
             cover_image: vault.cover_image,
+            color: vault.color,
+            icon: vault.icon,
+            description: vault.description,
             has_password: vault.has_password,
             items: sync_items,
+            collections: sync_collections,
+            boards: sync_boards,
+            settings: vault_settings,
         });
     }
 
-    // Copy captures to sync folder
-    let mut sync_captures = Vec::new();
-    let local_captures_folder = get_captures_folder()?;
-    if local_captures_folder.exists() {
-        if let Ok(entries) = fs::read_dir(&local_captures_folder) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.is_file() {
-                    if let Some(filename) = path.file_name().and_then(|n| n.to_str()) {
-                        let dest_path = captures_dest.join(filename);
-                        
-                        // Only copy if file doesn't exist or is newer
-                        let should_copy = if dest_path.exists() {
-                            if let (Ok(src_meta), Ok(dest_meta)) = (fs::metadata(&path), fs::metadata(&dest_path)) {
-                                src_meta.modified().ok() > dest_meta.modified().ok()
-                            } else {
-                                true
-                            }
-                        } else {
-                            true
-                        };
-
-                        if should_copy {
-                            if let Err(e) = fs::copy(&path, &dest_path) {
-                                warnings.push(format!("Failed to copy capture '{}': {}", filename, e));
-                            }
-                        }
-
-                        // Get file metadata for sync file
-                        if let Ok(meta) = fs::metadata(&path) {
-                            sync_captures.push(SyncCapture {
-                                filename: filename.to_string(),
-                                created_at: meta.created()
-                                    .ok()
-                                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                                    .map(|d| chrono::DateTime::from_timestamp(d.as_secs() as i64, 0)
-                                        .map(|dt| dt.to_rfc3339())
-                                        .unwrap_or_default())
-                                    .unwrap_or_else(|| chrono::Utc::now().to_rfc3339()),
-                                size_bytes: meta.len(),
-                            });
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    // Create sync file
-    let sync_file = SyncFile {
+    let mut sync_file = SyncFile {
         format_version: SYNC_FORMAT_VERSION.to_string(),
         device_id,
         device_name: device_name.clone(),
         exported_at: chrono::Utc::now().to_rfc3339(),
-        vaults: sync_vaults.clone(),
-        captures: sync_captures.clone(),
+        vaults: sync_vaults,
+        captures: Vec::new(),
     };
-
-    // Write sync file
-    let sync_file_path = sync_folder.join(SYNC_FILE_NAME);
-    let json = serde_json::to_string_pretty(&sync_file)
-        .map_err(|e| format!("Failed to serialize sync file: {}", e))?;
-    fs::write(&sync_file_path, json)
-        .map_err(|e| format!("Failed to write sync file: {}", e))?;
+    if op.is_cancelled() {
+        return Err(CANCELLED.to_string());
+    }
+    let exported_captures = backend.push(conn, &mut sync_file, &mut warnings)?;
+    VaultItem::mark_synced(conn, &synced_versions).map_err(|e| e.to_string())?;
 
     // Update last_sync_at
     let now = chrono::Utc::now().to_rfc3339();
-    SyncSettings::set(conn, "last_sync_at", &now).map_err(|e| e.to_string())?;
-    SyncSettings::set(conn, "last_sync_device", &device_name).map_err(|e| e.to_string())?;
+    settings::set(conn, "last_sync_at", &now).map_err(|e| e.to_string())?;
+    settings::set(conn, "last_sync_device", &device_name).map_err(|e| e.to_string())?;
 
     Ok(SyncExportResult {
-        exported_vaults: sync_vaults.len(),
+        exported_vaults: sync_file.vaults.len(),
         exported_items,
-        exported_captures: sync_captures.len(),
+        exported_captures,
         skipped_vaults,
         warnings,
     })
@@ -360,12 +716,12 @@ pub fn check_sync_status(conn: &Connection) -> Result<SyncStatus, String> {
     // Ensure tables exist and are migrated before any queries
     Vault::create_table(conn).map_err(|e| e.to_string())?;
     VaultItem::create_table(conn).map_err(|e| e.to_string())?;
-    SyncSettings::create_table(conn).map_err(|e| e.to_string())?;
+    settings::create_table(conn).map_err(|e| e.to_string())?;
 
     let sync_folder = get_sync_folder(conn)?;
     let device_name = get_device_name(conn)?;
-    let last_sync_at = SyncSettings::get(conn, "last_sync_at").map_err(|e| e.to_string())?;
-    let last_sync_device = SyncSettings::get(conn, "last_sync_device").map_err(|e| e.to_string())?;
+    let last_sync_at = settings::get(conn, "last_sync_at").map_err(|e| e.to_string())?;
+    let last_sync_device = settings::get(conn, "last_sync_device").map_err(|e| e.to_string())?;
 
     let mut remote_file_exists = false;
     let mut remote_exported_at = None;
@@ -373,29 +729,28 @@ pub fn check_sync_status(conn: &Connection) -> Result<SyncStatus, String> {
     let mut has_changes = false;
 
     if let Some(ref folder) = sync_folder {
-        let sync_file_path = Path::new(folder).join(SYNC_FILE_NAME);
-        if sync_file_path.exists() {
+        let folder = Path::new(folder);
+        if folder.join(SYNC_FILE_NAME).exists() || folder.join(SYNC_BACKUP_NAME).exists() {
             remote_file_exists = true;
-            
+
             // Try to read the sync file to get metadata
-            if let Ok(contents) = fs::read_to_string(&sync_file_path) {
-                if let Ok(sync_file) = serde_json::from_str::<SyncFile>(&contents) {
-                    remote_exported_at = Some(sync_file.exported_at.clone());
-                    remote_device_name = Some(sync_file.device_name.clone());
-                    
-                    // Check if remote is newer than last sync
-                    if let Some(ref last) = last_sync_at {
-                        has_changes = sync_file.exported_at > *last;
-                    } else {
-                        has_changes = true; // Never synced before
-                    }
+            if let Ok(Some(sync_file)) = read_sync_file(folder) {
+                remote_exported_at = Some(sync_file.exported_at.clone());
+                remote_device_name = Some(sync_file.device_name.clone());
+
+                // Check if remote is newer than last sync
+                if let Some(ref last) = last_sync_at {
+                    has_changes = sync_file.exported_at > *last;
+                } else {
+                    has_changes = true; // Never synced before
                 }
             }
         }
     }
 
     Ok(SyncStatus {
-        sync_enabled: sync_folder.is_some(),
+        sync_enabled: sync_folder.is_some()
+            || settings::get(conn, SYNC_BACKEND_KEY).map_err(|e| e.to_string())?.as_deref() == Some("server"),
         sync_folder,
         device_name,
         last_sync_at,
@@ -421,50 +776,48 @@ pub fn get_locked_vaults(conn: &Connection) -> Result<Vec<(i64, String)>, String
     Ok(locked)
 }
 
-/// Get all sync settings
+/// Get all sync settings, minus secrets such as the server token
 pub fn get_sync_settings(conn: &Connection) -> Result<HashMap<String, String>, String> {
-    SyncSettings::create_table(conn).map_err(|e| e.to_string())?;
-    
-    let settings = SyncSettings::get_all(conn).map_err(|e| e.to_string())?;
-    Ok(settings.into_iter().collect())
+    settings::create_table(conn).map_err(|e| e.to_string())?;
+    settings::all_with_defaults(conn).map_err(|e| e.to_string())
 }
 
 /// Set a sync setting
 pub fn set_sync_setting(conn: &Connection, key: &str, value: &str) -> Result<(), String> {
-    SyncSettings::create_table(conn).map_err(|e| e.to_string())?;
-    SyncSettings::set(conn, key, value).map_err(|e| e.to_string())
+    settings::create_table(conn).map_err(|e| e.to_string())?;
+    settings::set(conn, key, value).map_err(|e| e.to_string())
 }
 
 // --- Import Functions ---
 
 use rand::{rngs::OsRng, RngCore};
 
-/// Encrypt content using XChaCha20-Poly1305
+/// Seal item content under a fresh data key wrapped by the vault's content key
 fn encrypt_content(key: &[u8; 32], plaintext: &str) -> Result<Vec<u8>, String> {
-    let cipher = XChaCha20Poly1305::new(Key::from_slice(key));
-    let mut nonce_bytes = [0u8; 24];
-    OsRng.fill_bytes(&mut nonce_bytes);
-    let nonce = XNonce::from_slice(&nonce_bytes);
-    let ciphertext = cipher
-        .encrypt(nonce, plaintext.as_bytes())
-        .map_err(|_| "Encryption failed".to_string())?;
-    let mut encrypted = nonce_bytes.to_vec();
-    encrypted.extend(ciphertext);
-    Ok(encrypted)
+    envelope::seal(key, plaintext.as_bytes()).map_err(|e| e.to_string())
 }
 
 /// Derive key from password using PBKDF2
-fn derive_key_from_password(password: &str, salt: &str, iterations: u32) -> [u8; 32] {
+fn derive_key_from_password(password: &str, salt: &str, iterations: u32) -> VaultKey {
     use pbkdf2::pbkdf2_hmac;
     use sha2::Sha256;
-    let mut key = [0u8; 32];
-    pbkdf2_hmac::<Sha256>(password.as_bytes(), salt.as_bytes(), iterations, &mut key);
+    let mut key = Zeroizing::new([0u8; 32]);
+    pbkdf2_hmac::<Sha256>(password.as_bytes(), salt.as_bytes(), iterations, &mut *key);
     key
 }
 
 /// Encrypt password for vault storage
 fn encrypt_password(key: &[u8; 32], password: &str) -> Result<Vec<u8>, String> {
-    encrypt_content(key, password)
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(key));
+    let mut nonce_bytes = [0u8; 24];
+    OsRng.fill_bytes(&mut nonce_bytes);
+    let nonce = XNonce::from_slice(&nonce_bytes);
+    let ciphertext = cipher
+        .encrypt(nonce, password.as_bytes())
+        .map_err(|_| "Encryption failed".to_string())?;
+    let mut encrypted = nonce_bytes.to_vec();
+    encrypted.extend(ciphertext);
+    Ok(encrypted)
 }
 
 /// Import sync file and merge with local database
@@ -472,27 +825,30 @@ fn encrypt_password(key: &[u8; 32], password: &str) -> Result<Vec<u8>, String> {
 pub fn sync_import(
     conn: &Connection,
     passwords: HashMap<String, String>,
+    op: &Operation,
 ) -> Result<SyncImportResult, String> {
+    settings::create_table(conn).map_err(|e| e.to_string())?;
+    let backend = configured_backend(conn)?;
+    sync_import_from(conn, backend.as_ref(), passwords, op)
+}
+
+/// Import changes pulled through `backend`. Vaults and items are merged in one transaction,
+/// so a failure or cancellation leaves the database as it was.
+pub fn sync_import_from(
+    conn: &Connection,
+    backend: &dyn SyncBackend,
+    passwords: HashMap<String, String>,
+    op: &Operation,
+) -> Result<SyncImportResult, String> {
+    let passwords: HashMap<String, Zeroizing<String>> =
+        passwords.into_iter().map(|(uuid, pwd)| (uuid, Zeroizing::new(pwd))).collect();
     // Ensure tables exist
     Vault::create_table(conn).map_err(|e| e.to_string())?;
     VaultItem::create_table(conn).map_err(|e| e.to_string())?;
-    SyncSettings::create_table(conn).map_err(|e| e.to_string())?;
+    settings::create_table(conn).map_err(|e| e.to_string())?;
 
-    // Get sync folder
-    let sync_folder_str = get_sync_folder(conn)?
-        .ok_or("Sync folder not configured. Please set a sync folder in settings.")?;
-    let sync_folder = Path::new(&sync_folder_str);
-
-    // Read sync file
-    let sync_file_path = sync_folder.join(SYNC_FILE_NAME);
-    if !sync_file_path.exists() {
-        return Err("Sync file not found. No sync data available.".to_string());
-    }
-
-    let contents = fs::read_to_string(&sync_file_path)
-        .map_err(|e| format!("Failed to read sync file: {}", e))?;
-    let sync_file: SyncFile = serde_json::from_str(&contents)
-        .map_err(|e| format!("Failed to parse sync file: {}", e))?;
+    let sync_file = backend.pull(conn)?
+        .ok_or("Sync file not found. No sync data available.")?;
 
     // Validate format version
     if sync_file.format_version != SYNC_FORMAT_VERSION {
@@ -502,232 +858,273 @@ pub fn sync_import(
         ));
     }
 
-    let last_sync_at = SyncSettings::get(conn, "last_sync_at").map_err(|e| e.to_string())?;
+    let last_sync_at = settings::get(conn, "last_sync_at").map_err(|e| e.to_string())?;
 
     let mut imported_vaults = 0;
     let mut imported_items = 0;
     let mut conflicts = Vec::new();
     let mut warnings = Vec::new();
     let mut skipped_vaults = Vec::new();
-
-    // Process each vault from sync file
-    for sync_vault in &sync_file.vaults {
-        // Check if we have a password for this vault (if it has password protection)
-        let password_opt = passwords.get(&sync_vault.uuid);
+    let mut failed_vaults = Vec::new();
+
+    conn.execute("BEGIN IMMEDIATE", []).map_err(|e| e.to_string())?;
+    let merged = (|| -> Result<(), String> {
+        // Process each vault from sync file
+        for sync_vault in &sync_file.vaults {
+            // Each vault merges under its own savepoint, so one that fails is rolled back and reported
+            // while the others still import
+            let counts_before = (imported_vaults, imported_items, conflicts.len());
+            conn.execute("SAVEPOINT sync_vault", []).map_err(|e| e.to_string())?;
+            let vault_merged = (|| -> Result<(), String> {
+                // Check if we have a password for this vault (if it has password protection)
+                let password_opt = passwords.get(&sync_vault.uuid);
         
-        // Check if vault exists locally by UUID
-        let local_vault = Vault::get_by_uuid(conn, &sync_vault.uuid).map_err(|e| e.to_string())?;
+                // Check if vault exists locally by UUID
+                let local_vault = Vault::get_by_uuid(conn, &sync_vault.uuid).map_err(|e| e.to_string())?;
 
-        match local_vault {
-            Some(existing_vault) => {
-                // Vault exists - check if we need to update
-                let local_updated_at = existing_vault.updated_at.clone().unwrap_or_default();
+                match local_vault {
+                    Some(existing_vault) => {
+                        // Vault exists - check if we need to update
+                        let local_updated_at = existing_vault.updated_at.clone().unwrap_or_default();
                 
-                // Handle soft delete sync
-                if sync_vault.deleted_at.is_some() && existing_vault.deleted_at.is_none() {
-                    // Remote is deleted, apply locally
-                    let now = chrono::Utc::now().to_rfc3339();
-                    conn.execute(
-                        "UPDATE vaults SET deleted_at = ?1, updated_at = ?2 WHERE id = ?3",
-                        rusqlite::params![sync_vault.deleted_at, now, existing_vault.id],
-                    ).map_err(|e| e.to_string())?;
+                        // Handle soft delete sync
+                        if sync_vault.deleted_at.is_some() && existing_vault.deleted_at.is_none() {
+                            // Remote is deleted, apply locally
+                            let now = chrono::Utc::now().to_rfc3339();
+                            conn.execute(
+                                "UPDATE vaults SET deleted_at = ?1, updated_at = ?2 WHERE id = ?3",
+                                rusqlite::params![sync_vault.deleted_at, now, existing_vault.id],
+                            ).map_err(|e| e.to_string())?;
                     
-                    // Also soft-delete all items
-                    conn.execute(
-                        "UPDATE vault_items SET deleted_at = ?1 WHERE vault_id = ?2 AND deleted_at IS NULL",
-                        rusqlite::params![sync_vault.deleted_at, existing_vault.id],
-                    ).map_err(|e| e.to_string())?;
+                            // Also soft-delete all items
+                            conn.execute(
+                                "UPDATE vault_items SET deleted_at = ?1 WHERE vault_id = ?2 AND deleted_at IS NULL",
+                                rusqlite::params![sync_vault.deleted_at, existing_vault.id],
+                            ).map_err(|e| e.to_string())?;
                     
-                    imported_vaults += 1;
-                    continue;
-                }
+                            imported_vaults += 1;
+                            return Ok(());
+                        }
 
-                // Check if remote is newer
-                if sync_vault.updated_at > local_updated_at {
-                    // Update vault metadata
-                    conn.execute(
-                        "UPDATE vaults SET name = ?1, cover_image = ?2, updated_at = ?3 WHERE id = ?4",
-                        rusqlite::params![
-                            sync_vault.name,
-                            sync_vault.cover_image,
-                            sync_vault.updated_at,
-                            existing_vault.id
-                        ],
-                    ).map_err(|e| e.to_string())?;
-                    imported_vaults += 1;
-                }
+                        // Check if remote is newer
+                        if sync_vault.updated_at > local_updated_at {
+                            // Update vault metadata
+                            conn.execute(
+                                "UPDATE vaults SET name = ?1, cover_image = ?2, updated_at = ?3, color = ?4, icon = ?5, description = ?6 WHERE id = ?7",
+                                rusqlite::params![
+                                    sync_vault.name,
+                                    sync_vault.cover_image,
+                                    sync_vault.updated_at,
+                                    sync_vault.color,
+                                    sync_vault.icon,
+                                    sync_vault.description,
+                                    existing_vault.id
+                                ],
+                            ).map_err(|e| e.to_string())?;
+                            imported_vaults += 1;
+                        }
 
-                // Get local key for re-encryption
-                let local_key = if existing_vault.has_password {
-                    if let Some(pwd) = password_opt {
-                        derive_key_from_password(pwd, &existing_vault.id.to_string(), 100_000)
-                    } else {
-                        skipped_vaults.push(sync_vault.name.clone());
-                        warnings.push(format!("Skipped vault '{}': password required but not provided", sync_vault.name));
-                        continue;
-                    }
-                } else {
-                    // No password protection - derive key from empty password and vault ID
-                    // This matches how the frontend derives keys for passwordless vaults
-                    derive_key_from_password("", &existing_vault.id.to_string(), 100_000)
-                };
-
-                // Process items
-                for sync_item in &sync_vault.items {
-                    let import_result = import_item(
-                        conn,
-                        existing_vault.id,
-                        sync_item,
-                        &local_key,
-                        &last_sync_at,
-                    )?;
+                        // Get local key for re-encryption
+                        let local_key = if existing_vault.has_password {
+                            if let Some(pwd) = password_opt {
+                                derive_key_from_password(pwd, &existing_vault.id.to_string(), 100_000)
+                            } else if let Some(key) = keystore::get(existing_vault.id) {
+                                key
+                            } else {
+                                skipped_vaults.push(sync_vault.name.clone());
+                                warnings.push(format!("Skipped vault '{}': password required but not provided", sync_vault.name));
+                                return Ok(());
+                            }
+                        } else {
+                            // No password protection - derive key from empty password and vault ID
+                            // This matches how the frontend derives keys for passwordless vaults
+                            derive_key_from_password("", &existing_vault.id.to_string(), 100_000)
+                        };
+                        let Ok(local_key) = envelope::content_key(conn, existing_vault.id, &local_key) else {
+                            skipped_vaults.push(sync_vault.name.clone());
+                            warnings.push(format!("Skipped vault '{}': wrong password", sync_vault.name));
+                            return Ok(());
+                        };
+
+                        let collection_ids = import_collections(conn, existing_vault.id, &sync_vault.collections)?;
+                        let board_columns = import_boards(conn, existing_vault.id, &sync_vault.boards)?;
+                        import_vault_settings(conn, existing_vault.id, &sync_vault.settings)?;
+
+                        // Process items
+                        let total = sync_vault.items.len();
+                        for (done, sync_item) in sync_vault.items.iter().enumerate() {
+                            if op.is_cancelled() {
+                                return Err(CANCELLED.to_string());
+                            }
+                            op.progress(&sync_vault.name, done, total);
+                            let collection_id = sync_item.collection_uuid.as_ref()
+                                .and_then(|u| collection_ids.get(u))
+                                .copied();
+                            let import_result = import_item(
+                                conn,
+                                existing_vault.id,
+                                sync_item,
+                                &local_key,
+                                &last_sync_at,
+                                collection_id,
+                            )?;
+                            if matches!(import_result, ImportItemResult::Imported | ImportItemResult::Updated) {
+                                if let Some(local) = VaultItem::get_by_uuid(conn, &sync_item.uuid).map_err(|e| e.to_string())? {
+                                    apply_board_placements(conn, local.id, sync_item, &board_columns)?;
+                                    apply_annotations(conn, local.id, sync_item, &local_key)?;
+                                }
+                            }
                     
-                    match import_result {
-                        ImportItemResult::Imported => imported_items += 1,
-                        ImportItemResult::Updated => imported_items += 1,
-                        ImportItemResult::Conflict(title) => {
-                            conflicts.push(title);
-                            imported_items += 1;
+                            match import_result {
+                                ImportItemResult::Imported => imported_items += 1,
+                                ImportItemResult::Updated => imported_items += 1,
+                                ImportItemResult::Conflict(title) => {
+                                    conflicts.push(title);
+                                    imported_items += 1;
+                                }
+                                ImportItemResult::Skipped => {}
+                                ImportItemResult::Deleted => imported_items += 1,
+                            }
                         }
-                        ImportItemResult::Skipped => {}
-                        ImportItemResult::Deleted => imported_items += 1,
-                    }
-                }
-            }
-            None => {
-                // New vault - create it
-                if sync_vault.deleted_at.is_some() {
-                    // Don't import deleted vaults that don't exist locally
-                    continue;
-                }
-
-                // Get password for new vault
-                // For passwordless vaults, we'll derive the key after we have the vault ID
-                let (temp_key, has_password, encrypted_password) = if sync_vault.has_password {
-                    if let Some(pwd) = password_opt {
-                        // Create new vault with the provided password
-                        let now = chrono::Utc::now();
-                        let temp_id = now.timestamp_nanos_opt().unwrap_or(0);
-                        let key = derive_key_from_password(pwd, &temp_id.to_string(), 100_000);
-                        let enc_pwd = encrypt_password(&key, pwd)?;
-                        (key, true, enc_pwd)
-                    } else {
-                        skipped_vaults.push(sync_vault.name.clone());
-                        warnings.push(format!("Skipped vault '{}': password required for new vault", sync_vault.name));
-                        continue;
                     }
-                } else {
-                    // Temporary key - will be replaced after vault creation with proper derivation
-                    ([0u8; 32], false, Vec::new())
-                };
-
-                // Insert new vault
-                let now = chrono::Utc::now().to_rfc3339();
-                conn.execute(
-                    "INSERT INTO vaults (name, encrypted_password, created_at, cover_image, has_password, uuid, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-                    rusqlite::params![
-                        sync_vault.name,
-                        encrypted_password,
-                        sync_vault.created_at,
-                        sync_vault.cover_image,
-                        has_password,
-                        sync_vault.uuid,
-                        now
-                    ],
-                ).map_err(|e| e.to_string())?;
+                    None => {
+                        // New vault - create it
+                        if sync_vault.deleted_at.is_some() {
+                            // Don't import deleted vaults that don't exist locally
+                            return Ok(());
+                        }
+                        // Purged here; the sync file predates the deletion
+                        if tombstones::contains(conn, &sync_vault.uuid).map_err(|e| e.to_string())? {
+                            return Ok(());
+                        }
 
-                let vault_id = conn.last_insert_rowid();
+                        // Get password for new vault
+                        // For passwordless vaults, we'll derive the key after we have the vault ID
+                        let (temp_key, has_password, encrypted_password) = if sync_vault.has_password {
+                            if let Some(pwd) = password_opt {
+                                // Create new vault with the provided password
+                                let now = chrono::Utc::now();
+                                let temp_id = now.timestamp_nanos_opt().unwrap_or(0);
+                                let key = derive_key_from_password(pwd, &temp_id.to_string(), 100_000);
+                                let enc_pwd = encrypt_password(&key, pwd)?;
+                                (key, true, enc_pwd)
+                            } else {
+                                skipped_vaults.push(sync_vault.name.clone());
+                                warnings.push(format!("Skipped vault '{}': password required for new vault", sync_vault.name));
+                                return Ok(());
+                            }
+                        } else {
+                            // Temporary key - will be replaced after vault creation with proper derivation
+                            (Zeroizing::new([0u8; 32]), false, Vec::new())
+                        };
 
-                // Re-derive key with actual vault ID
-                let final_key = if has_password {
-                    if let Some(pwd) = password_opt {
-                        let key = derive_key_from_password(pwd, &vault_id.to_string(), 100_000);
-                        // Update encrypted password with correct key
-                        let enc_pwd = encrypt_password(&key, pwd)?;
+                        // Insert new vault
+                        let now = chrono::Utc::now().to_rfc3339();
                         conn.execute(
-                            "UPDATE vaults SET encrypted_password = ?1 WHERE id = ?2",
-                            rusqlite::params![enc_pwd, vault_id],
+                            "INSERT INTO vaults (name, encrypted_password, created_at, cover_image, has_password, uuid, updated_at, color, icon, description) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                            rusqlite::params![
+                                sync_vault.name,
+                                encrypted_password,
+                                sync_vault.created_at,
+                                sync_vault.cover_image,
+                                has_password,
+                                sync_vault.uuid,
+                                now,
+                                sync_vault.color,
+                                sync_vault.icon,
+                                sync_vault.description
+                            ],
                         ).map_err(|e| e.to_string())?;
-                        key
-                    } else {
-                        temp_key
-                    }
-                } else {
-                    // No password protection - derive key from empty password and vault ID
-                    // This matches how the frontend derives keys for passwordless vaults
-                    derive_key_from_password("", &vault_id.to_string(), 100_000)
-                };
-
-                imported_vaults += 1;
 
-                // Import all items
-                for sync_item in &sync_vault.items {
-                    if sync_item.deleted_at.is_some() {
-                        continue; // Don't import deleted items for new vaults
-                    }
-
-                    // Encrypt content with local key
-                    let encrypted_content = encrypt_content(&final_key, &sync_item.content)?;
-
-                    // Insert item
-                    conn.execute(
-                        "INSERT INTO vault_items (vault_id, title, content, created_at, updated_at, image, summary, sort_order, uuid) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-                        rusqlite::params![
-                            vault_id,
-                            sync_item.title,
-                            encrypted_content,
-                            sync_item.created_at,
-                            sync_item.updated_at,
-                            sync_item.image,
-                            sync_item.summary,
-                            sync_item.sort_order,
-                            sync_item.uuid
-                        ],
-                    ).map_err(|e| e.to_string())?;
-
-                    imported_items += 1;
-                }
-            }
-        }
-    }
+                        let vault_id = conn.last_insert_rowid();
+
+                        // Re-derive key with actual vault ID
+                        let final_key = if has_password {
+                            if let Some(pwd) = password_opt {
+                                let key = derive_key_from_password(pwd, &vault_id.to_string(), 100_000);
+                                // Update encrypted password with correct key
+                                let enc_pwd = encrypt_password(&key, pwd)?;
+                                conn.execute(
+                                    "UPDATE vaults SET encrypted_password = ?1 WHERE id = ?2",
+                                    rusqlite::params![enc_pwd, vault_id],
+                                ).map_err(|e| e.to_string())?;
+                                key
+                            } else {
+                                temp_key
+                            }
+                        } else {
+                            // No password protection - derive key from empty password and vault ID
+                            // This matches how the frontend derives keys for passwordless vaults
+                            derive_key_from_password("", &vault_id.to_string(), 100_000)
+                        };
+                        let final_key = envelope::create_master_key(conn, vault_id, &final_key).map_err(|e| e.to_string())?;
 
-    // Copy captures from sync folder
-    let captures_src = sync_folder.join(CAPTURES_FOLDER_NAME);
-    let local_captures_folder = get_captures_folder()?;
-    let mut imported_captures = 0;
+                        imported_vaults += 1;
 
-    if captures_src.exists() {
-        // Create local captures folder if it doesn't exist
-        if !local_captures_folder.exists() {
-            fs::create_dir_all(&local_captures_folder)
-                .map_err(|e| format!("Failed to create local captures folder: {}", e))?;
-        }
+                        let collection_ids = import_collections(conn, vault_id, &sync_vault.collections)?;
+                        let board_columns = import_boards(conn, vault_id, &sync_vault.boards)?;
+                        import_vault_settings(conn, vault_id, &sync_vault.settings)?;
 
-        if let Ok(entries) = fs::read_dir(&captures_src) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.is_file() {
-                    if let Some(filename) = path.file_name().and_then(|n| n.to_str()) {
-                        let dest_path = local_captures_folder.join(filename);
-                        
-                        // Only copy if file doesn't exist locally
-                        if !dest_path.exists() {
-                            if let Err(e) = fs::copy(&path, &dest_path) {
-                                warnings.push(format!("Failed to copy capture '{}': {}", filename, e));
-                            } else {
-                                imported_captures += 1;
+                        // Import all items
+                        let total = sync_vault.items.len();
+                        for (done, sync_item) in sync_vault.items.iter().enumerate() {
+                            if op.is_cancelled() {
+                                return Err(CANCELLED.to_string());
                             }
+                            op.progress(&sync_vault.name, done, total);
+                            let collection_id = sync_item.collection_uuid.as_ref()
+                                .and_then(|u| collection_ids.get(u))
+                                .copied();
+                            // Skips deleted and purged items like any other import
+                            let import_result = import_item(conn, vault_id, sync_item, &final_key, &last_sync_at, collection_id)?;
+                            if !matches!(import_result, ImportItemResult::Imported) {
+                                continue;
+                            }
+                            if let Some(local) = VaultItem::get_by_uuid(conn, &sync_item.uuid).map_err(|e| e.to_string())? {
+                                apply_board_placements(conn, local.id, sync_item, &board_columns)?;
+                                apply_annotations(conn, local.id, sync_item, &final_key)?;
+                            }
+
+                            imported_items += 1;
                         }
                     }
                 }
+                Ok(())
+            })();
+            match vault_merged {
+                Ok(()) => {
+                    conn.execute("RELEASE sync_vault", []).map_err(|e| e.to_string())?;
+                }
+                Err(e) if op.is_cancelled() => return Err(e),
+                Err(e) => {
+                    conn.execute_batch("ROLLBACK TO sync_vault; RELEASE sync_vault").map_err(|e| e.to_string())?;
+                    (imported_vaults, imported_items) = (counts_before.0, counts_before.1);
+                    conflicts.truncate(counts_before.2);
+                    warnings.push(format!("Failed to import vault '{}', it was left unchanged: {}", sync_vault.name, e));
+                    failed_vaults.push(SyncVaultFailure { name: sync_vault.name.clone(), error: e });
+                }
             }
         }
+        Ok(())
+    })();
+    match merged {
+        Ok(()) => conn.execute("COMMIT", []).map(|_| ()).map_err(|e| e.to_string())?,
+        Err(e) => {
+            let _ = conn.execute("ROLLBACK", []);
+            return Err(e);
+        }
+    }
+
+    let imported_captures = backend.pull_files(conn, &mut warnings)?;
+    // A failed vault's changes must be pulled again next time
+    if failed_vaults.is_empty() {
+        backend.finish_pull(conn)?;
     }
 
     // Update last_sync_at
     let now = chrono::Utc::now().to_rfc3339();
-    SyncSettings::set(conn, "last_sync_at", &now).map_err(|e| e.to_string())?;
-    SyncSettings::set(conn, "last_sync_device", &sync_file.device_name).map_err(|e| e.to_string())?;
+    settings::set(conn, "last_sync_at", &now).map_err(|e| e.to_string())?;
+    settings::set(conn, "last_sync_device", &sync_file.device_name).map_err(|e| e.to_string())?;
 
     // Note: Search index rebuild should be triggered by the frontend after import
 
@@ -738,11 +1135,184 @@ pub fn sync_import(
         conflicts,
         warnings,
         skipped_vaults,
+        failed_vaults,
     })
 }
 
+/// Merge a vault's boards and their columns from the sync file, newest change winning.
+/// Returns a map of column UUID -> local (board id, column id) used to place imported items.
+pub(crate) fn import_boards(
+    conn: &Connection,
+    vault_id: i64,
+    sync_boards: &[SyncBoard],
+) -> Result<HashMap<String, (i64, i64)>, String> {
+    use crate::boards;
+    let mut columns = HashMap::new();
+    for sync_board in sync_boards {
+        let board_id = match boards::get_by_uuid(conn, &sync_board.uuid).map_err(|e| e.to_string())? {
+            Some(existing) => {
+                if sync_board.updated_at > existing.updated_at {
+                    conn.execute(
+                        "UPDATE boards SET name = ?1, sort_order = ?2, updated_at = ?3, deleted_at = ?4 WHERE id = ?5",
+                        rusqlite::params![sync_board.name, sync_board.sort_order, sync_board.updated_at, sync_board.deleted_at, existing.id],
+                    ).map_err(|e| e.to_string())?;
+                    if sync_board.deleted_at.is_some() {
+                        conn.execute("DELETE FROM board_items WHERE board_id = ?1", [existing.id]).map_err(|e| e.to_string())?;
+                    }
+                }
+                existing.id
+            }
+            None if sync_board.deleted_at.is_some() => continue,
+            None => boards::insert_board(
+                conn,
+                vault_id,
+                &sync_board.uuid,
+                &sync_board.name,
+                sync_board.sort_order,
+                &sync_board.created_at,
+                &sync_board.updated_at,
+            ).map_err(|e| e.to_string())?,
+        };
+        for sync_column in &sync_board.columns {
+            let (column_id, deleted) = match boards::get_column_by_uuid(conn, &sync_column.uuid).map_err(|e| e.to_string())? {
+                Some(existing) if sync_column.updated_at > existing.updated_at => {
+                    conn.execute(
+                        "UPDATE board_columns SET name = ?1, sort_order = ?2, updated_at = ?3, deleted_at = ?4 WHERE id = ?5",
+                        rusqlite::params![sync_column.name, sync_column.sort_order, sync_column.updated_at, sync_column.deleted_at, existing.id],
+                    ).map_err(|e| e.to_string())?;
+                    if sync_column.deleted_at.is_some() {
+                        conn.execute("DELETE FROM board_items WHERE column_id = ?1", [existing.id]).map_err(|e| e.to_string())?;
+                    }
+                    (existing.id, sync_column.deleted_at.is_some())
+                }
+                Some(existing) => (existing.id, existing.deleted_at.is_some()),
+                None if sync_column.deleted_at.is_some() => continue,
+                None => {
+                    let id = boards::insert_column(
+                        conn,
+                        board_id,
+                        &sync_column.uuid,
+                        &sync_column.name,
+                        sync_column.sort_order,
+                        &sync_column.created_at,
+                        &sync_column.updated_at,
+                    ).map_err(|e| e.to_string())?;
+                    (id, false)
+                }
+            };
+            if !deleted {
+                columns.insert(sync_column.uuid.clone(), (board_id, column_id));
+            }
+        }
+    }
+    Ok(columns)
+}
+
+/// Merge per-vault settings from the sync file, newest change per key winning
+fn import_vault_settings(conn: &Connection, vault_id: i64, settings: &[SyncVaultSetting]) -> Result<(), String> {
+    for setting in settings.iter().filter(|s| vault::is_valid_setting_key(&s.key)) {
+        VaultSettings::merge(conn, vault_id, &setting.key, setting.value.as_deref(), &setting.updated_at)
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Put an imported item on the board columns it sits in on the other device
+fn apply_board_placements(
+    conn: &Connection,
+    item_id: i64,
+    sync_item: &SyncItem,
+    board_columns: &HashMap<String, (i64, i64)>,
+) -> Result<(), String> {
+    let placements: Vec<(i64, i64, i64)> = sync_item.board_placements
+        .iter()
+        .filter_map(|p| board_columns.get(&p.column_uuid).map(|&(board_id, column_id)| (board_id, column_id, p.position)))
+        .collect();
+    crate::boards::set_placements(conn, item_id, &placements).map_err(|e| e.to_string())
+}
+
+/// Replace an item's annotations with the file's, unless the file predates annotations
+fn apply_annotations(conn: &Connection, item_id: i64, sync_item: &SyncItem, key: &[u8; 32]) -> Result<(), String> {
+    match &sync_item.annotations {
+        Some(annotations) => crate::annotations::set_for_item(conn, item_id, key, annotations, false).map_err(|e| e.to_string()),
+        None => Ok(()),
+    }
+}
+
+/// Merge a vault's collections from the sync file.
+/// Returns a map of collection UUID -> local collection id used to place imported items.
+pub(crate) fn import_collections(
+    conn: &Connection,
+    vault_id: i64,
+    sync_collections: &[SyncCollection],
+) -> Result<HashMap<String, i64>, String> {
+    Collection::create_table(conn).map_err(|e| e.to_string())?;
+
+    let mut ids = HashMap::new();
+    let mut applied = std::collections::HashSet::new();
+
+    // First pass: create/update rows without parent links, since a child may precede its parent
+    for sync_collection in sync_collections {
+        match Collection::get_by_uuid(conn, &sync_collection.uuid).map_err(|e| e.to_string())? {
+            Some(existing) => {
+                if sync_collection.updated_at > existing.updated_at {
+                    conn.execute(
+                        "UPDATE collections SET name = ?1, sort_order = ?2, updated_at = ?3, deleted_at = ?4 WHERE id = ?5",
+                        rusqlite::params![
+                            sync_collection.name,
+                            sync_collection.sort_order,
+                            sync_collection.updated_at,
+                            sync_collection.deleted_at,
+                            existing.id
+                        ],
+                    ).map_err(|e| e.to_string())?;
+                    applied.insert(existing.id);
+                }
+                ids.insert(sync_collection.uuid.clone(), existing.id);
+            }
+            None => {
+                if sync_collection.deleted_at.is_some() {
+                    continue; // Don't import deleted collections that don't exist locally
+                }
+                conn.execute(
+                    "INSERT INTO collections (vault_id, name, sort_order, uuid, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    rusqlite::params![
+                        vault_id,
+                        sync_collection.name,
+                        sync_collection.sort_order,
+                        sync_collection.uuid,
+                        sync_collection.created_at,
+                        sync_collection.updated_at
+                    ],
+                ).map_err(|e| e.to_string())?;
+                let id = conn.last_insert_rowid();
+                applied.insert(id);
+                ids.insert(sync_collection.uuid.clone(), id);
+            }
+        }
+    }
+
+    // Second pass: resolve parent links for the rows we took from the remote
+    for sync_collection in sync_collections {
+        if let Some(&id) = ids.get(&sync_collection.uuid) {
+            if !applied.contains(&id) {
+                continue;
+            }
+            let parent_id = sync_collection.parent_uuid.as_ref()
+                .and_then(|p| ids.get(p))
+                .copied();
+            conn.execute(
+                "UPDATE collections SET parent_id = ?1 WHERE id = ?2",
+                rusqlite::params![parent_id, id],
+            ).map_err(|e| e.to_string())?;
+        }
+    }
+
+    Ok(ids)
+}
+
 /// Result of importing a single item
-enum ImportItemResult {
+pub(crate) enum ImportItemResult {
     Imported,
     Updated,
     Conflict(String),
@@ -750,25 +1320,94 @@ enum ImportItemResult {
     Deleted,
 }
 
+/// Whose copy of an item changed since this device and the sync file last agreed on it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ItemChange {
+    Neither,
+    Remote,
+    Both,
+}
+
+/// The remote item's logical clock, when both copies are versioned. Items neither side has changed since versioning
+/// began are both at 0 and fall back to timestamps like unversioned files.
+fn remote_clock(local: &VaultItem, remote: &SyncItem) -> Option<i64> {
+    remote.version.filter(|v| *v > 0 || local.version > 0)
+}
+
+/// Decide with the items' logical clocks. Clocks on different machines drift, so timestamps only decide without one.
+fn item_change(local: &VaultItem, remote: &SyncItem, last_sync_at: &Option<String>) -> ItemChange {
+    if let Some(remote_version) = remote_clock(local, remote) {
+        let local_changed = local.version > local.synced_version;
+        return match (local_changed, remote_version > local.synced_version) {
+            (true, true) => ItemChange::Both,
+            (false, true) => ItemChange::Remote,
+            _ => ItemChange::Neither,
+        };
+    }
+    let local_updated_at = &local.updated_at;
+    let remote_updated_at = &remote.updated_at;
+    // Conflict: both modified since last sync
+    let is_conflict = if let Some(ref last) = last_sync_at {
+        local_updated_at > last && remote_updated_at > last && local_updated_at != remote_updated_at
+    } else {
+        false
+    };
+    if is_conflict {
+        ItemChange::Both
+    } else if remote_updated_at > local_updated_at {
+        ItemChange::Remote
+    } else {
+        ItemChange::Neither
+    }
+}
+
+/// (version, synced_version) to store for an item after the merge. Taking the remote copy adopts its clock; keeping
+/// the local one moves past both so the next export wins. The new version always differs from the stored one, so the
+/// bump trigger leaves the write alone.
+fn merged_version(local: &VaultItem, remote: &SyncItem, change: ItemChange) -> (i64, i64) {
+    match (remote_clock(local, remote), change) {
+        (Some(remote_version), ItemChange::Both) => (local.version.max(remote_version) + 1, remote_version),
+        (Some(remote_version), _) if remote_version != local.version => (remote_version, remote_version),
+        _ => (local.version + 1, local.version + 1),
+    }
+}
+
+/// The synced item type when this version knows it, else one inferred from the content
+fn synced_item_type(sync_item: &SyncItem) -> String {
+    sync_item.item_type.clone()
+        .filter(|t| vault::is_valid_item_type(t))
+        .unwrap_or_else(|| vault::infer_item_type(&sync_item.content).to_string())
+}
+
 /// Import a single item, handling merge logic
-fn import_item(
+pub(crate) fn import_item(
     conn: &Connection,
     vault_id: i64,
     sync_item: &SyncItem,
     key: &[u8; 32],
     last_sync_at: &Option<String>,
+    collection_id: Option<i64>,
 ) -> Result<ImportItemResult, String> {
     // Check if item exists locally by UUID
     let local_item = VaultItem::get_by_uuid(conn, &sync_item.uuid).map_err(|e| e.to_string())?;
 
     match local_item {
+        // Local-only items keep their local state, whatever an older synced copy says
+        Some(existing_item) if existing_item.no_sync => Ok(ImportItemResult::Skipped),
         Some(existing_item) => {
+            let change = item_change(&existing_item, sync_item, last_sync_at);
+
             // Handle soft delete sync
             if sync_item.deleted_at.is_some() && existing_item.deleted_at.is_none() {
+                // A versioned deletion this device already had, since undone here
+                if remote_clock(&existing_item, sync_item).is_some() && change == ItemChange::Neither {
+                    return Ok(ImportItemResult::Skipped);
+                }
                 // Remote is deleted, apply locally
+                let (version, synced_version) = merged_version(&existing_item, sync_item, change);
                 conn.execute(
-                    "UPDATE vault_items SET deleted_at = ?1, updated_at = ?2 WHERE id = ?3",
-                    rusqlite::params![sync_item.deleted_at, sync_item.updated_at, existing_item.id],
+                    "UPDATE vault_items SET deleted_at = ?1, updated_at = ?2, version = ?3, synced_version = ?4 WHERE id = ?5",
+                    rusqlite::params![sync_item.deleted_at, sync_item.updated_at, version, synced_version, existing_item.id],
                 ).map_err(|e| e.to_string())?;
                 return Ok(ImportItemResult::Deleted);
             }
@@ -778,24 +1417,14 @@ fn import_item(
                 return Ok(ImportItemResult::Skipped);
             }
 
-            let local_updated_at = existing_item.updated_at.clone();
-            let remote_updated_at = &sync_item.updated_at;
-
-            // Check for conflict: both modified since last sync
-            let is_conflict = if let Some(ref last) = last_sync_at {
-                local_updated_at > *last && *remote_updated_at > *last && local_updated_at != *remote_updated_at
-            } else {
-                false
-            };
-
-            if is_conflict {
+            if change == ItemChange::Both {
                 // Create conflict copy
-                let conflict_title = format!("{} [Conflict]", sync_item.title);
+                let conflict_title = format!("{} {}", sync_item.title, crate::i18n::tr("[Conflict]"));
                 let encrypted_content = encrypt_content(key, &sync_item.content)?;
+                let (word_count, char_count) = vault::text_stats(&sync_item.content);
                 let new_uuid = uuid::Uuid::new_v4().to_string();
-
                 conn.execute(
-                    "INSERT INTO vault_items (vault_id, title, content, created_at, updated_at, image, summary, sort_order, uuid) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                    "INSERT INTO vault_items (vault_id, title, content, created_at, updated_at, image, summary, sort_order, uuid, collection_id, item_type, due_at, completed_at, word_count, char_count, content_hash, read_at, archive_url, color, icon) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20)",
                     rusqlite::params![
                         vault_id,
                         conflict_title,
@@ -805,20 +1434,41 @@ fn import_item(
                         sync_item.image,
                         sync_item.summary,
                         sync_item.sort_order,
-                        new_uuid
+                        new_uuid,
+                        collection_id,
+                        synced_item_type(sync_item),
+                        sync_item.due_at,
+                        sync_item.completed_at,
+                        word_count,
+                        char_count,
+                        vault::content_hash(key, &sync_item.content),
+                        sync_item.read_at,
+                        sync_item.archive_url,
+                        sync_item.color,
+                        sync_item.icon
                     ],
                 ).map_err(|e| e.to_string())?;
 
+                // The local copy stays; it now supersedes both versions
+                if remote_clock(&existing_item, sync_item).is_some() {
+                    let (version, synced_version) = merged_version(&existing_item, sync_item, change);
+                    conn.execute(
+                        "UPDATE vault_items SET version = ?1, synced_version = ?2 WHERE id = ?3",
+                        rusqlite::params![version, synced_version, existing_item.id],
+                    ).map_err(|e| e.to_string())?;
+                }
+
                 return Ok(ImportItemResult::Conflict(sync_item.title.clone()));
             }
 
-            // Check if remote is newer
-            if *remote_updated_at > local_updated_at {
+            if change == ItemChange::Remote {
                 // Update with remote content
                 let encrypted_content = encrypt_content(key, &sync_item.content)?;
+                let (word_count, char_count) = vault::text_stats(&sync_item.content);
+                let (version, synced_version) = merged_version(&existing_item, sync_item, change);
 
                 conn.execute(
-                    "UPDATE vault_items SET title = ?1, content = ?2, updated_at = ?3, image = ?4, summary = ?5, sort_order = ?6 WHERE id = ?7",
+                    "UPDATE vault_items SET title = ?1, content = ?2, updated_at = ?3, image = ?4, summary = ?5, sort_order = ?6, collection_id = ?7, item_type = COALESCE(?8, item_type), due_notified_at = CASE WHEN due_at IS ?9 THEN due_notified_at ELSE NULL END, due_at = ?9, completed_at = ?10, word_count = ?11, char_count = ?12, preview = NULL, content_hash = ?13, read_at = ?15, archive_url = ?16, color = ?17, icon = ?18, version = ?19, synced_version = ?20 WHERE id = ?14",
                     rusqlite::params![
                         sync_item.title,
                         encrypted_content,
@@ -826,7 +1476,20 @@ fn import_item(
                         sync_item.image,
                         sync_item.summary,
                         sync_item.sort_order,
-                        existing_item.id
+                        collection_id,
+                        sync_item.item_type.as_ref().map(|_| synced_item_type(sync_item)),
+                        sync_item.due_at,
+                        sync_item.completed_at,
+                        word_count,
+                        char_count,
+                        vault::content_hash(key, &sync_item.content),
+                        existing_item.id,
+                        sync_item.read_at,
+                        sync_item.archive_url,
+                        sync_item.color,
+                        sync_item.icon,
+                        version,
+                        synced_version
                     ],
                 ).map_err(|e| e.to_string())?;
 
@@ -842,11 +1505,17 @@ fn import_item(
                 return Ok(ImportItemResult::Skipped);
             }
 
+            // Purged here; the sync file predates the deletion
+            if tombstones::contains(conn, &sync_item.uuid).map_err(|e| e.to_string())? {
+                return Ok(ImportItemResult::Skipped);
+            }
+
             // Encrypt content with local key
             let encrypted_content = encrypt_content(key, &sync_item.content)?;
+            let (word_count, char_count) = vault::text_stats(&sync_item.content);
 
             conn.execute(
-                "INSERT INTO vault_items (vault_id, title, content, created_at, updated_at, image, summary, sort_order, uuid) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                "INSERT INTO vault_items (vault_id, title, content, created_at, updated_at, image, summary, sort_order, uuid, collection_id, item_type, due_at, completed_at, word_count, char_count, content_hash, read_at, archive_url, color, icon, version, synced_version) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?21)",
                 rusqlite::params![
                     vault_id,
                     sync_item.title,
@@ -856,7 +1525,19 @@ fn import_item(
                     sync_item.image,
                     sync_item.summary,
                     sync_item.sort_order,
-                    sync_item.uuid
+                    sync_item.uuid,
+                    collection_id,
+                    synced_item_type(sync_item),
+                    sync_item.due_at,
+                    sync_item.completed_at,
+                    word_count,
+                    char_count,
+                    vault::content_hash(key, &sync_item.content),
+                    sync_item.read_at,
+                    sync_item.archive_url,
+                    sync_item.color,
+                    sync_item.icon,
+                    sync_item.version.unwrap_or(0)
                 ],
             ).map_err(|e| e.to_string())?;
 
@@ -885,23 +1566,108 @@ pub struct SyncPreview {
 
 // --- Purge Functions ---
 
+/// Purge keeps deleted items for between 1 day and 10 years
+pub const PURGE_DAYS_RANGE: std::ops::RangeInclusive<i32> = 1..=3650;
+
 /// Result of purging deleted items
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PurgeResult {
     pub purged_vaults: usize,
     pub purged_items: usize,
+    /// Every item removed, including those of purged vaults, so the user can see what went
+    #[serde(default)]
+    pub items: Vec<PurgedItem>,
+    /// Names of the purged vaults
+    #[serde(default)]
+    pub vaults: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PurgedItem {
+    pub uuid: Option<String>,
+    pub title: String,
+    pub vault_id: i64,
+    /// When it was deleted; the vault's deletion for items of a purged vault that were never deleted themselves
+    pub deleted_at: Option<String>,
+}
+
+/// Items and vaults a purge with this cutoff removes
+fn purge_candidates(conn: &Connection, cutoff: &str) -> Result<(Vec<PurgedItem>, Vec<String>), String> {
+    let mut stmt = conn.prepare(
+        "SELECT i.uuid, i.title, i.vault_id, COALESCE(i.deleted_at, v.deleted_at) FROM vault_items i
+         JOIN vaults v ON v.id = i.vault_id
+         WHERE (i.deleted_at IS NOT NULL AND i.deleted_at < ?1) OR (v.deleted_at IS NOT NULL AND v.deleted_at < ?1)
+         ORDER BY i.deleted_at, i.id",
+    ).map_err(|e| e.to_string())?;
+    let items = stmt
+        .query_map([cutoff], |row| {
+            Ok(PurgedItem { uuid: row.get(0)?, title: row.get(1)?, vault_id: row.get(2)?, deleted_at: row.get(3)? })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    let mut stmt = conn.prepare("SELECT name FROM vaults WHERE deleted_at IS NOT NULL AND deleted_at < ?1 ORDER BY name")
+        .map_err(|e| e.to_string())?;
+    let vaults = stmt
+        .query_map([cutoff], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<String>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok((items, vaults))
+}
+
+fn purge_cutoff(days: i32) -> String {
+    (chrono::Utc::now() - chrono::Duration::days(days as i64)).to_rfc3339()
+}
+
+/// What `purge_deleted_items` would remove right now, without removing anything
+pub fn preview_purge(conn: &Connection, days: i32) -> Result<PurgeResult, String> {
+    Vault::create_table(conn).map_err(|e| e.to_string())?;
+    VaultItem::create_table(conn).map_err(|e| e.to_string())?;
+    let cutoff = purge_cutoff(days);
+    let (items, vaults) = purge_candidates(conn, &cutoff)?;
+    // Counted like the purge counts them: items deleted themselves, not the rest of a purged vault
+    let purged_items: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM vault_items WHERE deleted_at IS NOT NULL AND deleted_at < ?1",
+        [&cutoff],
+        |row| row.get(0),
+    ).map_err(|e| e.to_string())?;
+    Ok(PurgeResult {
+        purged_vaults: vaults.len(),
+        purged_items: purged_items as usize,
+        items,
+        vaults,
+    })
 }
 
 /// Purge items and vaults that have been soft-deleted for more than X days
 pub fn purge_deleted_items(conn: &Connection, days: i32) -> Result<PurgeResult, String> {
     Vault::create_table(conn).map_err(|e| e.to_string())?;
     VaultItem::create_table(conn).map_err(|e| e.to_string())?;
+    Collection::create_table(conn).map_err(|e| e.to_string())?;
 
     // Calculate cutoff date
-    let cutoff = chrono::Utc::now() - chrono::Duration::days(days as i64);
-    let cutoff_str = cutoff.to_rfc3339();
+    let cutoff_str = purge_cutoff(days);
+    let (items, vault_names) = purge_candidates(conn, &cutoff_str)?;
 
-    // First, hard delete items that were soft-deleted before cutoff
+    // Drop soft-deleted collections; items were already moved out of them on delete
+    conn.execute(
+        "UPDATE vault_items SET collection_id = NULL WHERE collection_id IN (SELECT id FROM collections WHERE deleted_at IS NOT NULL AND deleted_at < ?1)",
+        rusqlite::params![cutoff_str],
+    ).map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE collections SET parent_id = NULL WHERE parent_id IN (SELECT id FROM collections WHERE deleted_at IS NOT NULL AND deleted_at < ?1)",
+        rusqlite::params![cutoff_str],
+    ).map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM collections WHERE deleted_at IS NOT NULL AND deleted_at < ?1",
+        rusqlite::params![cutoff_str],
+    ).map_err(|e| e.to_string())?;
+
+    // First, hard delete items that were soft-deleted before cutoff; their reminders cascade
+    Reminder::create_table(conn).map_err(|e| e.to_string())?;
+    // Keep their UUIDs so an older sync file can't bring them back
+    tombstones::record_deleted_before(conn, &cutoff_str).map_err(|e| e.to_string())?;
     let purged_items = conn.execute(
         "DELETE FROM vault_items WHERE deleted_at IS NOT NULL AND deleted_at < ?1",
         rusqlite::params![cutoff_str],
@@ -909,49 +1675,60 @@ pub fn purge_deleted_items(conn: &Connection, days: i32) -> Result<PurgeResult,
 
     // Then, hard delete vaults (and their remaining items) that were soft-deleted before cutoff
     // First get the vault IDs to delete
-    let mut stmt = conn.prepare("SELECT id FROM vaults WHERE deleted_at IS NOT NULL AND deleted_at < ?1")
+    let mut stmt = conn.prepare("SELECT id, deleted_at FROM vaults WHERE deleted_at IS NOT NULL AND deleted_at < ?1")
         .map_err(|e| e.to_string())?;
-    let vault_ids: Vec<i64> = stmt.query_map([&cutoff_str], |row| row.get(0))
+    let vaults: Vec<(i64, String)> = stmt.query_map([&cutoff_str], |row| Ok((row.get(0)?, row.get(1)?)))
         .map_err(|e| e.to_string())?
         .filter_map(|r| r.ok())
         .collect();
 
-    let purged_vaults = vault_ids.len();
+    let purged_vaults = vaults.len();
 
     // Delete items belonging to these vaults, then the vaults themselves
-    for vault_id in vault_ids {
+    for (vault_id, deleted_at) in vaults {
+        tombstones::record_vault(conn, vault_id, &deleted_at).map_err(|e| e.to_string())?;
         conn.execute("DELETE FROM vault_items WHERE vault_id = ?1", [vault_id])
             .map_err(|e| e.to_string())?;
+        conn.execute("DELETE FROM collections WHERE vault_id = ?1", [vault_id])
+            .map_err(|e| e.to_string())?;
         conn.execute("DELETE FROM vaults WHERE id = ?1", [vault_id])
             .map_err(|e| e.to_string())?;
+        // Best-effort: the vault is gone, so are its search results
+        let _ = crate::search::delete_documents_by_vault(vault_id);
     }
 
+    tombstones::prune(conn, days).map_err(|e| e.to_string())?;
+
     Ok(PurgeResult {
         purged_vaults,
         purged_items,
+        items,
+        vaults: vault_names,
     })
 }
 
 /// Get the configured purge days (default 30)
 pub fn get_purge_days(conn: &Connection) -> Result<i32, String> {
-    SyncSettings::create_table(conn).map_err(|e| e.to_string())?;
-    
-    if let Some(days_str) = SyncSettings::get(conn, "purge_deleted_after_days").map_err(|e| e.to_string())? {
-        days_str.parse().map_err(|_| "Invalid purge days value".to_string())
-    } else {
-        Ok(30) // Default
-    }
+    settings::get_parsed(conn, "purge_deleted_after_days")
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Invalid purge days value".to_string())
 }
 
 /// Set the configured purge days
 pub fn set_purge_days(conn: &Connection, days: i32) -> Result<(), String> {
-    SyncSettings::create_table(conn).map_err(|e| e.to_string())?;
-    SyncSettings::set(conn, "purge_deleted_after_days", &days.to_string()).map_err(|e| e.to_string())
+    if !PURGE_DAYS_RANGE.contains(&days) {
+        return Err(format!(
+            "Keep deleted items between {} and {} days",
+            PURGE_DAYS_RANGE.start(),
+            PURGE_DAYS_RANGE.end()
+        ));
+    }
+    settings::set_value(conn, "purge_deleted_after_days", days).map_err(|e| e.to_string())
 }
 
 /// Check if sync is enabled and auto-purge should run
 pub fn should_auto_purge(conn: &Connection) -> Result<bool, String> {
-    SyncSettings::create_table(conn).map_err(|e| e.to_string())?;
+    settings::create_table(conn).map_err(|e| e.to_string())?;
     
     // Purge is only relevant if sync is enabled
     let sync_folder = get_sync_folder(conn)?;
@@ -962,64 +1739,40 @@ pub fn should_auto_purge(conn: &Connection) -> Result<bool, String> {
 
 /// Check if "sync on close" is enabled
 pub fn is_sync_on_close_enabled(conn: &Connection) -> Result<bool, String> {
-    SyncSettings::create_table(conn).map_err(|e| e.to_string())?;
-    
-    if let Some(val) = SyncSettings::get(conn, "sync_on_close").map_err(|e| e.to_string())? {
-        Ok(val == "true" || val == "1")
-    } else {
-        Ok(false) // Default to disabled
-    }
+    settings::get_bool(conn, "sync_on_close").map_err(|e| e.to_string())
 }
 
 /// Set "sync on close" setting
 pub fn set_sync_on_close(conn: &Connection, enabled: bool) -> Result<(), String> {
-    SyncSettings::create_table(conn).map_err(|e| e.to_string())?;
-    SyncSettings::set(conn, "sync_on_close", if enabled { "true" } else { "false" })
-        .map_err(|e| e.to_string())
+    settings::set_bool(conn, "sync_on_close", enabled).map_err(|e| e.to_string())
 }
 
 /// Check if "check for sync on startup" is enabled
 pub fn is_check_sync_on_startup_enabled(conn: &Connection) -> Result<bool, String> {
-    SyncSettings::create_table(conn).map_err(|e| e.to_string())?;
-    
-    if let Some(val) = SyncSettings::get(conn, "check_sync_on_startup").map_err(|e| e.to_string())? {
-        Ok(val == "true" || val == "1")
-    } else {
-        Ok(true) // Default to enabled
-    }
+    settings::get_bool(conn, "check_sync_on_startup").map_err(|e| e.to_string())
 }
 
 /// Set "check for sync on startup" setting  
 pub fn set_check_sync_on_startup(conn: &Connection, enabled: bool) -> Result<(), String> {
-    SyncSettings::create_table(conn).map_err(|e| e.to_string())?;
-    SyncSettings::set(conn, "check_sync_on_startup", if enabled { "true" } else { "false" })
-        .map_err(|e| e.to_string())
+    settings::set_bool(conn, "check_sync_on_startup", enabled).map_err(|e| e.to_string())
 }
 
 /// Set device name
 pub fn set_device_name(conn: &Connection, name: &str) -> Result<(), String> {
-    SyncSettings::create_table(conn).map_err(|e| e.to_string())?;
-    SyncSettings::set(conn, "device_name", name).map_err(|e| e.to_string())
+    settings::create_table(conn).map_err(|e| e.to_string())?;
+    settings::set(conn, "device_name", name).map_err(|e| e.to_string())
 }
 
 pub fn get_sync_preview(conn: &Connection) -> Result<Option<SyncPreview>, String> {
-    SyncSettings::create_table(conn).map_err(|e| e.to_string())?;
+    settings::create_table(conn).map_err(|e| e.to_string())?;
 
-    let sync_folder_str = match get_sync_folder(conn)? {
-        Some(f) => f,
-        None => return Ok(None),
+    // Not configured yet: nothing to preview
+    let Ok(backend) = configured_backend(conn) else {
+        return Ok(None);
     };
-    let sync_folder = Path::new(&sync_folder_str);
-    let sync_file_path = sync_folder.join(SYNC_FILE_NAME);
-
-    if !sync_file_path.exists() {
+    let Some(sync_file) = backend.pull(conn)? else {
         return Ok(None);
-    }
-
-    let contents = fs::read_to_string(&sync_file_path)
-        .map_err(|e| format!("Failed to read sync file: {}", e))?;
-    let sync_file: SyncFile = serde_json::from_str(&contents)
-        .map_err(|e| format!("Failed to parse sync file: {}", e))?;
+    };
 
     // Count only non-deleted items from non-deleted vaults
     let item_count: usize = sync_file.vaults
@@ -0,0 +1,183 @@
+// sync_captures.rs - Which capture files sync for brainbox
+// Sync used to copy every capture file both ways. Files can now be limited by size, by a total budget (newest
+// files win), by the vaults whose items use them and by kind of file. Skipped files are reported as warnings.
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::settings;
+
+const MAX_FILE_MB_KEY: &str = "sync.captures.max_file_mb";
+const MAX_TOTAL_MB_KEY: &str = "sync.captures.max_total_mb";
+const VAULTS_KEY: &str = "sync.captures.vaults";
+const TYPES_KEY: &str = "sync.captures.types";
+const UNLINKED_KEY: &str = "sync.captures.include_unlinked";
+
+/// Kinds of capture file, by extension
+pub const CAPTURE_TYPES: &[&str] = &["image", "video", "audio", "document", "other"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureSyncSettings {
+    /// Largest file synced, in MB; 0 for no limit
+    pub max_file_mb: u64,
+    /// Total size of synced captures, in MB; 0 for no limit
+    pub max_total_mb: u64,
+    /// UUIDs of vaults whose captures sync; empty for all vaults
+    pub vaults: Vec<String>,
+    /// Entries of `CAPTURE_TYPES` to sync; empty for all kinds
+    pub types: Vec<String>,
+    /// Whether captures no item refers to sync when `vaults` is set
+    pub include_unlinked: bool,
+}
+
+impl Default for CaptureSyncSettings {
+    fn default() -> Self {
+        CaptureSyncSettings {
+            max_file_mb: 0,
+            max_total_mb: 0,
+            vaults: Vec::new(),
+            types: Vec::new(),
+            include_unlinked: true,
+        }
+    }
+}
+
+fn list_setting(conn: &Connection, key: &str) -> Result<Vec<String>, String> {
+    Ok(settings::get(conn, key)
+        .map_err(|e| e.to_string())?
+        .and_then(|v| serde_json::from_str(&v).ok())
+        .unwrap_or_default())
+}
+
+pub fn load_settings(conn: &Connection) -> Result<CaptureSyncSettings, String> {
+    settings::create_table(conn).map_err(|e| e.to_string())?;
+    let get = |key: &str| settings::get(conn, key).map_err(|e| e.to_string());
+    let defaults = CaptureSyncSettings::default();
+    Ok(CaptureSyncSettings {
+        max_file_mb: get(MAX_FILE_MB_KEY)?.and_then(|v| v.parse().ok()).unwrap_or(defaults.max_file_mb),
+        max_total_mb: get(MAX_TOTAL_MB_KEY)?.and_then(|v| v.parse().ok()).unwrap_or(defaults.max_total_mb),
+        vaults: list_setting(conn, VAULTS_KEY)?,
+        types: list_setting(conn, TYPES_KEY)?,
+        include_unlinked: get(UNLINKED_KEY)?.map(|v| v == "true").unwrap_or(defaults.include_unlinked),
+    })
+}
+
+pub fn save_settings(conn: &Connection, settings: &CaptureSyncSettings) -> Result<(), String> {
+    if let Some(unknown) = settings.types.iter().find(|t| !CAPTURE_TYPES.contains(&t.as_str())) {
+        return Err(format!("Unknown capture type: {}", unknown));
+    }
+    settings::create_table(conn).map_err(|e| e.to_string())?;
+    let set = |key: &str, value: &str| settings::set(conn, key, value).map_err(|e| e.to_string());
+    set(MAX_FILE_MB_KEY, &settings.max_file_mb.to_string())?;
+    set(MAX_TOTAL_MB_KEY, &settings.max_total_mb.to_string())?;
+    set(VAULTS_KEY, &serde_json::to_string(&settings.vaults).map_err(|e| e.to_string())?)?;
+    set(TYPES_KEY, &serde_json::to_string(&settings.types).map_err(|e| e.to_string())?)?;
+    set(UNLINKED_KEY, if settings.include_unlinked { "true" } else { "false" })
+}
+
+pub fn capture_type(filename: &str) -> &'static str {
+    let ext = Path::new(filename)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+    match ext.as_str() {
+        "png" | "jpg" | "jpeg" | "gif" | "webp" | "bmp" | "heic" | "avif" | "svg" => "image",
+        "mp4" | "mov" | "webm" | "mkv" | "avi" => "video",
+        "mp3" | "wav" | "m4a" | "ogg" | "flac" | "opus" => "audio",
+        "pdf" | "doc" | "docx" | "txt" | "md" | "html" | "rtf" => "document",
+        _ => "other",
+    }
+}
+
+/// A capture file chosen for sync
+pub struct CaptureFile {
+    pub path: PathBuf,
+    pub filename: String,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    pub created: Option<SystemTime>,
+}
+
+/// Vault UUIDs whose (non-deleted) items refer to each capture file name
+fn vaults_by_file(conn: &Connection) -> Result<HashMap<String, HashSet<String>>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT v.uuid, i.image FROM vault_items i JOIN vaults v ON v.id = i.vault_id
+             WHERE i.image IS NOT NULL AND i.deleted_at IS NULL AND v.uuid IS NOT NULL",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| e.to_string())?;
+    let mut map: HashMap<String, HashSet<String>> = HashMap::new();
+    for (vault_uuid, image) in rows.flatten() {
+        if let Some(name) = image.rsplit(['/', '\\']).next().filter(|n| !n.is_empty()) {
+            map.entry(name.to_string()).or_default().insert(vault_uuid);
+        }
+    }
+    Ok(map)
+}
+
+/// Capture files in `folder` allowed by `settings`, newest first. Each skipped file adds a warning.
+pub fn select(
+    conn: &Connection,
+    settings: &CaptureSyncSettings,
+    folder: &Path,
+    warnings: &mut Vec<String>,
+) -> Result<Vec<CaptureFile>, String> {
+    let Ok(entries) = fs::read_dir(folder) else {
+        return Ok(Vec::new());
+    };
+    let mut files: Vec<CaptureFile> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let meta = fs::metadata(&path).ok().filter(|m| m.is_file())?;
+            let filename = path.file_name()?.to_str()?.to_string();
+            Some(CaptureFile { path, filename, size: meta.len(), modified: meta.modified().ok(), created: meta.created().ok() })
+        })
+        .collect();
+    files.sort_by_key(|f| std::cmp::Reverse(f.modified));
+
+    let linked = if settings.vaults.is_empty() { HashMap::new() } else { vaults_by_file(conn)? };
+    let max_file = settings.max_file_mb * 1024 * 1024;
+    let max_total = settings.max_total_mb * 1024 * 1024;
+    let mut total = 0;
+    let mut selected = Vec::new();
+    for file in files {
+        let kind = capture_type(&file.filename);
+        if !settings.types.is_empty() && !settings.types.iter().any(|t| t == kind) {
+            warnings.push(format!("Skipped capture '{}': {} files are not synced", file.filename, kind));
+            continue;
+        }
+        if !settings.vaults.is_empty() {
+            let included = match linked.get(&file.filename) {
+                Some(vaults) => settings.vaults.iter().any(|v| vaults.contains(v)),
+                None => settings.include_unlinked,
+            };
+            if !included {
+                warnings.push(format!("Skipped capture '{}': not used by a synced vault", file.filename));
+                continue;
+            }
+        }
+        if max_file > 0 && file.size > max_file {
+            warnings.push(format!("Skipped capture '{}': larger than {} MB", file.filename, settings.max_file_mb));
+            continue;
+        }
+        if max_total > 0 && total + file.size > max_total {
+            warnings.push(format!(
+                "Skipped capture '{}': synced captures would exceed {} MB",
+                file.filename, settings.max_total_mb
+            ));
+            continue;
+        }
+        total += file.size;
+        selected.push(file);
+    }
+    Ok(selected)
+}
@@ -0,0 +1,170 @@
+// sync_manifest.rs - Folder sync format v2: a manifest plus one file per vault
+// A single JSON sync file grows with every note, and cloud clients upload all of it again for one edit. Format v2
+// keeps brainbox.sync as a small manifest that points at a file per vault under vaults/, named by a hash of its
+// contents, so an export only writes the vaults that changed. Because files are never rewritten in place, the
+// manifest swap is the single step that publishes a new state. Format v1 files are still read; the first export
+// replaces one with a v2 manifest.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs;
+use std::io::Write;
+use std::path::{Component, Path};
+
+use crate::sync::{SyncCapture, SyncFile, SyncVault, SYNC_FORMAT_VERSION};
+
+/// `format_version` of a manifest
+pub const MANIFEST_FORMAT_VERSION: &str = "2.0";
+/// Per-vault files, relative to the sync folder
+pub const VAULTS_FOLDER_NAME: &str = "vaults";
+/// Hex digits of the content hash put in a vault file's name
+const NAME_HASH_LEN: usize = 16;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    format_version: String,
+    device_id: String,
+    device_name: String,
+    exported_at: String,
+    /// Always empty. Versions that only know format v1 still parse the manifest and report the newer format
+    /// instead of a parse error.
+    #[serde(default)]
+    vaults: Vec<serde_json::Value>,
+    captures: Vec<SyncCapture>,
+    vault_files: Vec<VaultFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct VaultFile {
+    uuid: String,
+    /// Relative to the sync folder, with forward slashes
+    path: String,
+    /// SHA-256 of the file, checked on read so a half-synced file isn't imported
+    sha256: String,
+}
+
+/// Just enough of a sync file to tell the formats apart
+#[derive(Deserialize)]
+struct Header {
+    format_version: String,
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Write a file under a temporary name, flush it to disk, then rename it into place
+pub fn write_durably(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp = path.with_file_name(temp_name);
+    let mut file = fs::File::create(&temp)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    drop(file);
+    fs::rename(&temp, path)
+}
+
+/// The file name in a manifest path of the form vaults/<name>. The manifest comes from a shared folder, so any
+/// other path (absolute, with "..", or in another folder) is refused rather than read.
+fn vault_file_name(path: &str) -> Option<&str> {
+    let name = path.strip_prefix(VAULTS_FOLDER_NAME)?.strip_prefix('/')?;
+    let mut components = Path::new(name).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(_)), None) if !name.contains(['/', '\\', ':']) => Some(name),
+        _ => None,
+    }
+}
+
+/// Read a sync file of either format; a v2 manifest is assembled with its vault files into one `SyncFile`
+pub fn load(folder: &Path, path: &Path) -> Result<SyncFile, String> {
+    let contents = fs::read_to_string(path).map_err(|e| format!("Failed to read sync file: {}", e))?;
+    let header: Header = serde_json::from_str(&contents).map_err(|e| format!("Failed to parse sync file: {}", e))?;
+    if header.format_version != MANIFEST_FORMAT_VERSION {
+        // Format v1 holds everything; the importer checks its version
+        return serde_json::from_str(&contents).map_err(|e| format!("Failed to parse sync file: {}", e));
+    }
+    let manifest: Manifest =
+        serde_json::from_str(&contents).map_err(|e| format!("Failed to parse sync manifest: {}", e))?;
+    let mut vaults = Vec::with_capacity(manifest.vault_files.len());
+    for entry in &manifest.vault_files {
+        let name = vault_file_name(&entry.path)
+            .ok_or_else(|| format!("Vault file {} is outside the {} folder", entry.path, VAULTS_FOLDER_NAME))?;
+        let bytes = fs::read(folder.join(VAULTS_FOLDER_NAME).join(name))
+            .map_err(|e| format!("Failed to read vault file {}: {}", entry.path, e))?;
+        if sha256_hex(&bytes) != entry.sha256 {
+            return Err(format!("Vault file {} doesn't match the manifest; it may still be syncing", entry.path));
+        }
+        let vault: SyncVault = serde_json::from_slice(&bytes)
+            .map_err(|e| format!("Failed to parse vault file {}: {}", entry.path, e))?;
+        vaults.push(vault);
+    }
+    Ok(SyncFile {
+        format_version: SYNC_FORMAT_VERSION.to_string(),
+        device_id: manifest.device_id,
+        device_name: manifest.device_name,
+        exported_at: manifest.exported_at,
+        vaults,
+        captures: manifest.captures,
+    })
+}
+
+/// Write the vault files a manifest for `file` needs, skipping those already there, and return the manifest.
+/// Nothing refers to the new files until the manifest is written.
+pub fn write_vaults(folder: &Path, file: &SyncFile) -> Result<Vec<u8>, String> {
+    let vaults_dir = folder.join(VAULTS_FOLDER_NAME);
+    fs::create_dir_all(&vaults_dir).map_err(|e| format!("Failed to create vaults folder: {}", e))?;
+    let mut vault_files = Vec::with_capacity(file.vaults.len());
+    for vault in &file.vaults {
+        let bytes = serde_json::to_vec(vault).map_err(|e| format!("Failed to serialize vault {}: {}", vault.name, e))?;
+        let sha256 = sha256_hex(&bytes);
+        let name = format!("{}-{}.json", vault.uuid, &sha256[..NAME_HASH_LEN]);
+        let path = vaults_dir.join(&name);
+        // Same name, same contents: an unchanged vault isn't written, so there is nothing new to upload
+        let unchanged = fs::read(&path).is_ok_and(|existing| sha256_hex(&existing) == sha256);
+        if !unchanged {
+            write_durably(&path, &bytes).map_err(|e| format!("Failed to write vault file {}: {}", name, e))?;
+        }
+        vault_files.push(VaultFile { uuid: vault.uuid.clone(), path: format!("{}/{}", VAULTS_FOLDER_NAME, name), sha256 });
+    }
+    let manifest = Manifest {
+        format_version: MANIFEST_FORMAT_VERSION.to_string(),
+        device_id: file.device_id.clone(),
+        device_name: file.device_name.clone(),
+        exported_at: file.exported_at.clone(),
+        vaults: Vec::new(),
+        captures: file.captures.clone(),
+        vault_files,
+    };
+    serde_json::to_vec_pretty(&manifest).map_err(|e| format!("Failed to serialize sync manifest: {}", e))
+}
+
+/// Vault files a v2 manifest points at; None for a v1 file or one that can't be read
+fn referenced(path: &Path) -> Option<HashSet<String>> {
+    let manifest: Manifest = serde_json::from_str(&fs::read_to_string(path).ok()?).ok()?;
+    (manifest.format_version == MANIFEST_FORMAT_VERSION).then(|| {
+        manifest
+            .vault_files
+            .into_iter()
+            .filter_map(|entry| Some(Path::new(&entry.path).file_name()?.to_string_lossy().to_string()))
+            .collect()
+    })
+}
+
+/// Delete vault files that neither the current manifest nor the backup names. Does nothing unless the current
+/// manifest reads back, so a failed write never costs the files an older manifest needs.
+pub fn remove_unreferenced(folder: &Path, current: &Path, backup: &Path) -> std::io::Result<usize> {
+    let Some(mut keep) = referenced(current) else { return Ok(0) };
+    keep.extend(referenced(backup).unwrap_or_default());
+    let Ok(entries) = fs::read_dir(folder.join(VAULTS_FOLDER_NAME)) else { return Ok(0) };
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.ends_with(".json") && !keep.contains(&name) {
+            fs::remove_file(entry.path())?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
@@ -0,0 +1,362 @@
+// sync_server.rs - Self-hosted sync server backend for brainbox
+// Instead of a shared folder, devices can relay changes through a small HTTP server that keeps an append-only log
+// of records. Each push sends the vaults, collections and items changed since that vault was last pushed; each pull
+// asks for everything after the cursor it last saw and is merged exactly like a folder sync file.
+//
+// REST contract (all bodies JSON; every request except registration sends `Authorization: Bearer <token>`):
+//
+//   POST   /v1/devices          {device_id, device_name, pairing_code}     -> {token}
+//          Register this device. The pairing code is whatever the server operator hands out.
+//   DELETE /v1/devices/{id}                                                -> 204
+//          Revoke the device's token.
+//   POST   /v1/ops              {device_id, ops: [SyncOp]}                 -> {cursor}
+//          Append records to the log. The server assigns the order; cursors are opaque strings.
+//   GET    /v1/ops?since=<cursor>&limit=<n>                                -> {ops: [SyncOp], cursor, has_more}
+//          Records appended after `since` (from the start when omitted), oldest first.
+//
+// A SyncOp is {kind: "vault" | "collection" | "item", uuid, vault_uuid, updated_at, device_id, data}, where `data`
+// is the record in the sync file format. Like the sync file, item content is plaintext, so run the server somewhere
+// you trust. Deletes travel as records with `deleted_at` set. 401 means the token was revoked.
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use crate::sync::{SyncBackend, SyncBoard, SyncCollection, SyncFile, SyncItem, SyncVault, SYNC_FORMAT_VERSION};
+use crate::settings;
+use crate::vault::Vault;
+
+const URL_KEY: &str = "sync_server.url";
+const TOKEN_KEY: &str = "sync_server.token";
+const CURSOR_KEY: &str = "sync_server.cursor";
+/// Per-vault prefix; the value is the export time of the last successful push of that vault
+const PUSHED_AT_PREFIX: &str = "sync_server.pushed_at.";
+/// Records requested per pull page
+const PAGE_SIZE: usize = 500;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SyncOp {
+    pub kind: String,
+    pub uuid: String,
+    pub vault_uuid: String,
+    pub updated_at: String,
+    pub device_id: String,
+    pub data: serde_json::Value,
+}
+
+#[derive(Serialize)]
+struct PushRequest<'a> {
+    device_id: &'a str,
+    ops: &'a [SyncOp],
+}
+
+#[derive(Deserialize)]
+struct PullResponse {
+    ops: Vec<SyncOp>,
+    cursor: String,
+    #[serde(default)]
+    has_more: bool,
+}
+
+#[derive(Serialize)]
+struct RegisterRequest<'a> {
+    device_id: &'a str,
+    device_name: &'a str,
+    pairing_code: &'a str,
+}
+
+#[derive(Deserialize)]
+struct RegisterResponse {
+    token: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ServerStatus {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Whether this device holds a token for the server
+    pub connected: bool,
+    /// Whether the server (rather than the sync folder) is the active backend
+    pub active: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
+fn setting(conn: &Connection, key: &str) -> Result<Option<String>, String> {
+    settings::get(conn, key).map_err(|e| e.to_string())
+}
+
+fn set_setting(conn: &Connection, key: &str, value: &str) -> Result<(), String> {
+    settings::set(conn, key, value).map_err(|e| e.to_string())
+}
+
+fn normalize_url(url: &str) -> Result<String, String> {
+    let url = url.trim().trim_end_matches('/');
+    if !(url.starts_with("https://") || url.starts_with("http://")) {
+        return Err("Sync server URL must start with http:// or https://".to_string());
+    }
+    Ok(url.to_string())
+}
+
+fn check_status(resp: reqwest::blocking::Response) -> Result<reqwest::blocking::Response, String> {
+    match resp.status() {
+        s if s.is_success() => Ok(resp),
+        reqwest::StatusCode::UNAUTHORIZED => {
+            Err("The sync server rejected this device. Connect it to the server again.".to_string())
+        }
+        s => Err(format!("Sync server returned {}: {}", s, resp.text().unwrap_or_default())),
+    }
+}
+
+/// Register this device with a server and make the server the active sync backend
+pub fn connect(conn: &Connection, url: &str, pairing_code: &str) -> Result<ServerStatus, String> {
+    settings::create_table(conn).map_err(|e| e.to_string())?;
+    let url = normalize_url(url)?;
+    let device_id = crate::sync::get_or_create_device_id(conn)?;
+    let device_name = crate::sync::get_device_name(conn)?;
+    let resp = crate::http::send(crate::http::client().post(format!("{}/v1/devices", url)).json(&RegisterRequest {
+        device_id: &device_id,
+        device_name: &device_name,
+        pairing_code: pairing_code.trim(),
+    }))
+    .map_err(|e| format!("Failed to reach sync server: {}", e))?;
+    let registered: RegisterResponse = check_status(resp)?
+        .json()
+        .map_err(|e| format!("Unexpected sync server response: {}", e))?;
+
+    // A different server has its own log, so start from its beginning
+    if setting(conn, URL_KEY)?.as_deref() != Some(url.as_str()) {
+        forget_progress(conn)?;
+    }
+    set_setting(conn, URL_KEY, &url)?;
+    set_setting(conn, TOKEN_KEY, &registered.token)?;
+    set_setting(conn, crate::sync::SYNC_BACKEND_KEY, "server")?;
+    status(conn)
+}
+
+/// Revoke this device's token (best-effort) and switch back to folder sync
+pub fn disconnect(conn: &Connection) -> Result<(), String> {
+    settings::create_table(conn).map_err(|e| e.to_string())?;
+    if let (Some(url), Some(token)) = (setting(conn, URL_KEY)?, setting(conn, TOKEN_KEY)?) {
+        let device_id = crate::sync::get_or_create_device_id(conn)?;
+        let request = crate::http::client()
+            .delete(format!("{}/v1/devices/{}", url, device_id))
+            .bearer_auth(token);
+        if let Err(e) = crate::http::send(request) {
+            tracing::warn!("Failed to revoke sync server token: {}", e);
+        }
+    }
+    set_setting(conn, TOKEN_KEY, "")?;
+    set_setting(conn, crate::sync::SYNC_BACKEND_KEY, "folder")
+}
+
+pub fn status(conn: &Connection) -> Result<ServerStatus, String> {
+    settings::create_table(conn).map_err(|e| e.to_string())?;
+    Ok(ServerStatus {
+        url: setting(conn, URL_KEY)?,
+        connected: setting(conn, TOKEN_KEY)?.is_some_and(|t| !t.is_empty()),
+        active: setting(conn, crate::sync::SYNC_BACKEND_KEY)?.as_deref() == Some("server"),
+        cursor: setting(conn, CURSOR_KEY)?.filter(|c| !c.is_empty()),
+    })
+}
+
+fn forget_progress(conn: &Connection) -> Result<(), String> {
+    settings::delete(conn, CURSOR_KEY).map_err(|e| e.to_string())?;
+    settings::delete_prefix(conn, PUSHED_AT_PREFIX).map_err(|e| e.to_string())
+}
+
+/// Only records changed after `since` (all of them when None)
+fn changed(updated_at: &str, since: &Option<String>) -> bool {
+    since.as_deref().is_none_or(|since| updated_at > since)
+}
+
+/// Whether `op` is a later record of the same thing than `prev`: versioned items go by their logical clock, since
+/// device clocks drift; everything else by timestamp. Ties go to `op`, the one that reached the server later.
+fn supersedes(op: &SyncOp, prev: &SyncOp) -> bool {
+    let version = |op: &SyncOp| op.data.get("version").and_then(|v| v.as_i64());
+    match (version(op), version(prev)) {
+        (Some(version), Some(prev_version)) => version >= prev_version,
+        _ => op.updated_at >= prev.updated_at,
+    }
+}
+
+fn op(kind: &str, uuid: &str, vault_uuid: &str, updated_at: &str, device_id: &str, data: impl Serialize) -> Result<SyncOp, String> {
+    Ok(SyncOp {
+        kind: kind.to_string(),
+        uuid: uuid.to_string(),
+        vault_uuid: vault_uuid.to_string(),
+        updated_at: updated_at.to_string(),
+        device_id: device_id.to_string(),
+        data: serde_json::to_value(data).map_err(|e| e.to_string())?,
+    })
+}
+
+pub struct ServerBackend {
+    url: String,
+    token: String,
+    /// Cursor reached by the last pull, saved by `finish_pull`
+    pulled_cursor: RefCell<Option<String>>,
+}
+
+impl ServerBackend {
+    pub fn from_settings(conn: &Connection) -> Result<Self, String> {
+        let url = setting(conn, URL_KEY)?.ok_or("Sync server not configured. Connect to a server in settings.")?;
+        let token = setting(conn, TOKEN_KEY)?
+            .filter(|t| !t.is_empty())
+            .ok_or("This device is not connected to the sync server. Connect it in settings.")?;
+        Ok(ServerBackend { url, token, pulled_cursor: RefCell::new(None) })
+    }
+}
+
+impl SyncBackend for ServerBackend {
+    fn push(&self, conn: &Connection, file: &mut SyncFile, _warnings: &mut Vec<String>) -> Result<usize, String> {
+        let mut ops = Vec::new();
+        let mut pushed = Vec::new();
+        for vault in &file.vaults {
+            let pushed_key = format!("{}{}", PUSHED_AT_PREFIX, vault.uuid);
+            let since = setting(conn, &pushed_key)?;
+            let before = ops.len();
+            if changed(&vault.updated_at, &since) {
+                let meta = SyncVault {
+                    cover_image: crate::covers::portable(vault.cover_image.clone()),
+                    items: Vec::new(),
+                    collections: Vec::new(),
+                    boards: Vec::new(),
+                    ..vault.clone()
+                };
+                ops.push(op("vault", &vault.uuid, &vault.uuid, &vault.updated_at, &file.device_id, meta)?);
+            }
+            for collection in vault.collections.iter().filter(|c| changed(&c.updated_at, &since)) {
+                ops.push(op("collection", &collection.uuid, &vault.uuid, &collection.updated_at, &file.device_id, collection)?);
+            }
+            // A board travels whole, stamped with its latest change including its columns
+            for board in &vault.boards {
+                let updated_at = board.columns.iter().map(|c| &c.updated_at).fold(&board.updated_at, |a, b| a.max(b));
+                if changed(updated_at, &since) {
+                    ops.push(op("board", &board.uuid, &vault.uuid, updated_at, &file.device_id, board)?);
+                }
+            }
+            for item in vault.items.iter().filter(|i| changed(&i.updated_at, &since)) {
+                ops.push(op("item", &item.uuid, &vault.uuid, &item.updated_at, &file.device_id, item)?);
+            }
+            if ops.len() > before || since.is_none() {
+                pushed.push(pushed_key);
+            }
+        }
+
+        if !ops.is_empty() {
+            let request = crate::http::client()
+                .post(format!("{}/v1/ops", self.url))
+                .bearer_auth(&self.token)
+                .json(&PushRequest { device_id: &file.device_id, ops: &ops });
+            check_status(crate::http::send(request).map_err(|e| format!("Failed to reach sync server: {}", e))?)?;
+        }
+        // Vaults skipped for lack of a key aren't in the file, so they are pushed in full once unlocked
+        for key in pushed {
+            set_setting(conn, &key, &file.exported_at)?;
+        }
+        // Capture files aren't relayed
+        Ok(0)
+    }
+
+    fn pull(&self, conn: &Connection) -> Result<Option<SyncFile>, String> {
+        let device_id = crate::sync::get_or_create_device_id(conn)?;
+        let mut cursor = setting(conn, CURSOR_KEY)?.filter(|c| !c.is_empty());
+        // Latest record per (kind, uuid): the log may hold several versions of one item
+        let mut latest: HashMap<(String, String), SyncOp> = HashMap::new();
+        loop {
+            let mut request = crate::http::client()
+                .get(format!("{}/v1/ops", self.url))
+                .bearer_auth(&self.token)
+                .query(&[("limit", PAGE_SIZE.to_string())]);
+            if let Some(since) = &cursor {
+                request = request.query(&[("since", since)]);
+            }
+            let page: PullResponse = check_status(
+                crate::http::send(request).map_err(|e| format!("Failed to reach sync server: {}", e))?,
+            )?
+            .json()
+            .map_err(|e| format!("Unexpected sync server response: {}", e))?;
+            for op in page.ops.into_iter().filter(|op| op.device_id != device_id) {
+                let key = (op.kind.clone(), op.uuid.clone());
+                if latest.get(&key).is_none_or(|prev| supersedes(&op, prev)) {
+                    latest.insert(key, op);
+                }
+            }
+            let advanced = cursor.as_deref() != Some(page.cursor.as_str());
+            cursor = Some(page.cursor);
+            if !page.has_more || !advanced {
+                break;
+            }
+        }
+        *self.pulled_cursor.borrow_mut() = cursor;
+
+        // Regroup the records into vaults so they merge like a folder sync file
+        let mut vaults: HashMap<String, SyncVault> = HashMap::new();
+        let mut children = Vec::new();
+        for op in latest.into_values() {
+            if op.kind == "vault" {
+                match serde_json::from_value::<SyncVault>(op.data) {
+                    Ok(vault) => {
+                        vaults.insert(op.vault_uuid, vault);
+                    }
+                    Err(e) => tracing::warn!("Ignoring malformed vault record {}: {}", op.uuid, e),
+                }
+            } else {
+                children.push(op);
+            }
+        }
+        for op in children {
+            if !vaults.contains_key(&op.vault_uuid) {
+                // Vault unchanged since this device last pulled: merge into the local copy, keeping its metadata
+                let Some(local) = Vault::get_by_uuid(conn, &op.vault_uuid).map_err(|e| e.to_string())? else {
+                    tracing::warn!("Ignoring {} {} for unknown vault {}", op.kind, op.uuid, op.vault_uuid);
+                    continue;
+                };
+                vaults.insert(op.vault_uuid.clone(), SyncVault {
+                    uuid: op.vault_uuid.clone(),
+                    name: local.name,
+                    created_at: local.created_at,
+                    updated_at: local.updated_at.unwrap_or_default(),
+                    deleted_at: local.deleted_at,
+                    cover_image: local.cover_image,
+                    color: local.color,
+                    icon: local.icon,
+                    description: local.description,
+                    has_password: local.has_password,
+                    items: Vec::new(),
+                    collections: Vec::new(),
+                    boards: Vec::new(),
+                    settings: Vec::new(),
+                });
+            }
+            let Some(vault) = vaults.get_mut(&op.vault_uuid) else { continue };
+            let parsed = match op.kind.as_str() {
+                "item" => serde_json::from_value::<SyncItem>(op.data).map(|item| vault.items.push(item)),
+                "collection" => serde_json::from_value::<SyncCollection>(op.data).map(|c| vault.collections.push(c)),
+                "board" => serde_json::from_value::<SyncBoard>(op.data).map(|b| vault.boards.push(b)),
+                _ => Ok(()),
+            };
+            if let Err(e) = parsed {
+                tracing::warn!("Ignoring malformed {} record {}: {}", op.kind, op.uuid, e);
+            }
+        }
+
+        Ok(Some(SyncFile {
+            format_version: SYNC_FORMAT_VERSION.to_string(),
+            device_id: String::new(),
+            device_name: self.url.clone(),
+            exported_at: chrono::Utc::now().to_rfc3339(),
+            vaults: vaults.into_values().collect(),
+            captures: Vec::new(),
+        }))
+    }
+
+    fn finish_pull(&self, conn: &Connection) -> Result<(), String> {
+        if let Some(cursor) = self.pulled_cursor.borrow_mut().take() {
+            set_setting(conn, CURSOR_KEY, &cursor)?;
+        }
+        Ok(())
+    }
+}
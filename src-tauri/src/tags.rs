@@ -0,0 +1,55 @@
+// tags.rs - Item tags for brainbox
+// Tags are short labels kept in their own table, lowercased and trimmed so "Reading" and "reading " are one tag.
+// The search index stores a copy so tag filters work in search.
+
+use rusqlite::{params, Connection};
+
+use crate::error::BrainboxError;
+
+pub fn create_table(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS item_tags (
+            item_id INTEGER NOT NULL REFERENCES vault_items(id) ON DELETE CASCADE,
+            tag TEXT NOT NULL,
+            PRIMARY KEY (item_id, tag)
+        )",
+        [],
+    )?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_item_tags_tag ON item_tags(tag)", [])?;
+    Ok(())
+}
+
+/// Lowercased, trimmed and de-duplicated tags with empty ones dropped
+pub fn normalize(tags: &[String]) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().trim_start_matches('#').to_lowercase();
+        if !tag.is_empty() && !out.contains(&tag) {
+            out.push(tag);
+        }
+    }
+    out
+}
+
+pub fn get(conn: &Connection, item_id: i64) -> Result<Vec<String>, BrainboxError> {
+    create_table(conn)?;
+    let mut stmt = conn.prepare("SELECT tag FROM item_tags WHERE item_id = ?1 ORDER BY tag")?;
+    let tags = stmt.query_map([item_id], |row| row.get(0))?.collect::<rusqlite::Result<_>>()?;
+    Ok(tags)
+}
+
+/// Add tags to an item, keeping the ones it already has. Returns the item's tags afterwards.
+pub fn add(conn: &Connection, item_id: i64, tags: &[String]) -> Result<Vec<String>, BrainboxError> {
+    create_table(conn)?;
+    for tag in normalize(tags) {
+        conn.execute("INSERT OR IGNORE INTO item_tags (item_id, tag) VALUES (?1, ?2)", params![item_id, tag])?;
+    }
+    get(conn, item_id)
+}
+
+/// Replace an item's tags
+pub fn set(conn: &Connection, item_id: i64, tags: &[String]) -> Result<Vec<String>, BrainboxError> {
+    create_table(conn)?;
+    conn.execute("DELETE FROM item_tags WHERE item_id = ?1", [item_id])?;
+    add(conn, item_id, tags)
+}
@@ -0,0 +1,113 @@
+// tasks.rs - Task support for brainbox
+// Markdown checkbox parsing and due-date notifications for task items
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::vault::VaultItem;
+
+/// A markdown checkbox (`- [ ] text` / `- [x] text`) found in an item's content
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TaskCheckbox {
+    /// Zero-based line number in the content, used to toggle the box
+    pub line: usize,
+    pub text: String,
+    pub checked: bool,
+}
+
+/// Parse a checkbox list entry into (byte offset of the mark, checked, text)
+fn split_checkbox(line: &str) -> Option<(usize, bool, &str)> {
+    let trimmed = line.trim_start();
+    let indent = line.len() - trimmed.len();
+    let rest = trimmed
+        .strip_prefix("- [")
+        .or_else(|| trimmed.strip_prefix("* ["))
+        .or_else(|| trimmed.strip_prefix("+ ["))?;
+    let mut chars = rest.chars();
+    let mark = chars.next()?;
+    if chars.next()? != ']' {
+        return None;
+    }
+    let checked = match mark {
+        ' ' => false,
+        'x' | 'X' => true,
+        _ => return None,
+    };
+    // Byte offset of the mark character within the original line
+    let mark_offset = indent + 3;
+    Some((mark_offset, checked, rest[2..].trim()))
+}
+
+/// Extract all checkboxes from markdown content
+pub fn parse_checkboxes(content: &str) -> Vec<TaskCheckbox> {
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(line, text)| {
+            split_checkbox(text).map(|(_, checked, text)| TaskCheckbox {
+                line,
+                text: text.to_string(),
+                checked,
+            })
+        })
+        .collect()
+}
+
+/// Flip the checkbox on `line`. Returns None if that line isn't a checkbox.
+pub fn toggle_checkbox(content: &str, line: usize) -> Option<String> {
+    let mut lines: Vec<String> = content.split('\n').map(|l| l.to_string()).collect();
+    let target = lines.get_mut(line)?;
+    let (offset, checked, _) = split_checkbox(target)?;
+    target.replace_range(offset..offset + 1, if checked { " " } else { "x" });
+    Some(lines.join("\n"))
+}
+
+/// Normalize a user-supplied due date to UTC RFC 3339 so it compares correctly as text in SQLite
+pub fn normalize_due_date(due_at: &str) -> Result<String, String> {
+    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(due_at) {
+        return Ok(dt.with_timezone(&chrono::Utc).to_rfc3339());
+    }
+    // Date-only values are due at the end of that local day
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(due_at, "%Y-%m-%d") {
+        let local = date
+            .and_hms_opt(23, 59, 59)
+            .and_then(|dt| dt.and_local_timezone(chrono::Local).earliest())
+            .ok_or("Invalid due date")?;
+        return Ok(local.with_timezone(&chrono::Utc).to_rfc3339());
+    }
+    Err(format!("Invalid due date: {}", due_at))
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[allow(non_snake_case)]
+struct TaskDueEvent {
+    itemId: i64,
+    vaultId: i64,
+    title: String,
+    dueAt: String,
+}
+
+/// Show a system notification for each task that became due since the last check (run by the reminder scheduler)
+pub fn notify_due_tasks(app: &AppHandle) -> Result<(), String> {
+    let conn = crate::db::open().map_err(|e| e.to_string())?;
+    let now = chrono::Utc::now().to_rfc3339();
+    let due = VaultItem::list_due_unnotified(&conn, &now).map_err(|e| e.to_string())?;
+    for item in due {
+        let due_at = item.due_at.clone().unwrap_or_default();
+        let _ = app
+            .notification()
+            .builder()
+            .title(crate::i18n::tr("Task due"))
+            .body(&item.title)
+            .show();
+        let _ = app.emit("task-due", TaskDueEvent {
+            itemId: item.id,
+            vaultId: item.vault_id,
+            title: item.title.clone(),
+            dueAt: due_at,
+        });
+        VaultItem::mark_due_notified(&conn, item.id).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
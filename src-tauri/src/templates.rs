@@ -0,0 +1,125 @@
+// templates.rs - Note templates for brainbox
+// Templates are stored unencrypted (they are not vault content) and rendered into new items
+
+use rusqlite::{params, Connection, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Template {
+    pub id: i64,
+    pub name: String,
+    /// Title for items created from the template; may contain placeholders
+    pub title: String,
+    pub content: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item_type: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+const TEMPLATE_COLUMNS: &str = "id, name, title, content, item_type, created_at, updated_at";
+
+impl Template {
+    pub fn create_table(conn: &Connection) -> Result<()> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS templates (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                title TEXT NOT NULL,
+                content TEXT NOT NULL,
+                item_type TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+        Ok(())
+    }
+
+    fn from_row(row: &rusqlite::Row) -> Result<Template> {
+        Ok(Template {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            title: row.get(2)?,
+            content: row.get(3)?,
+            item_type: row.get(4)?,
+            created_at: row.get(5)?,
+            updated_at: row.get(6)?,
+        })
+    }
+
+    pub fn insert(conn: &Connection, name: &str, title: &str, content: &str, item_type: Option<&str>) -> Result<Template> {
+        Self::create_table(conn)?;
+        let now = chrono::Utc::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO templates (name, title, content, item_type, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![name, title, content, item_type, now, now],
+        )?;
+        Ok(Template {
+            id: conn.last_insert_rowid(),
+            name: name.to_string(),
+            title: title.to_string(),
+            content: content.to_string(),
+            item_type: item_type.map(|t| t.to_string()),
+            created_at: now.clone(),
+            updated_at: now,
+        })
+    }
+
+    pub fn list(conn: &Connection) -> Result<Vec<Template>> {
+        Self::create_table(conn)?;
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM templates ORDER BY name COLLATE NOCASE ASC", TEMPLATE_COLUMNS))?;
+        let rows = stmt.query_map([], Self::from_row)?;
+        rows.collect()
+    }
+
+    pub fn get_by_id(conn: &Connection, template_id: i64) -> Result<Option<Template>> {
+        Self::create_table(conn)?;
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM templates WHERE id = ?1", TEMPLATE_COLUMNS))?;
+        let mut rows = stmt.query([template_id])?;
+        if let Some(row) = rows.next()? {
+            Ok(Some(Self::from_row(row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn delete(conn: &Connection, template_id: i64) -> Result<usize> {
+        Self::create_table(conn)?;
+        conn.execute("DELETE FROM templates WHERE id = ?1", [template_id])
+    }
+}
+
+/// Replace `{{name}}` placeholders. Built-ins: date, time, datetime (local time).
+/// Anything else, e.g. clipboard or url, comes from `variables`; unknown placeholders are left untouched.
+pub fn render(text: &str, variables: &HashMap<String, String>) -> String {
+    let now = chrono::Local::now();
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            out.push_str(&rest[start..]);
+            return out;
+        };
+        let name = after[..end].trim();
+        let value = match variables.get(name) {
+            Some(v) => Some(v.clone()),
+            None => match name {
+                "date" => Some(now.format("%Y-%m-%d").to_string()),
+                "time" => Some(now.format("%H:%M").to_string()),
+                "datetime" => Some(now.format("%Y-%m-%d %H:%M").to_string()),
+                _ => None,
+            },
+        };
+        match value {
+            Some(v) => out.push_str(&v),
+            None => out.push_str(&rest[start..start + 2 + end + 2]),
+        }
+        rest = &after[end + 2..];
+    }
+    out.push_str(rest);
+    out
+}
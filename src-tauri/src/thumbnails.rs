@@ -0,0 +1,53 @@
+// thumbnails.rs - Thumbnail generation for brainbox
+// Screenshots and image attachments get small cached WebP copies so grid views don't decode full-size PNGs
+
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+use crate::error::BrainboxError;
+use crate::image_cache;
+
+pub const DEFAULT_THUMBNAIL_SIZE: u32 = 256;
+const MIN_THUMBNAIL_SIZE: u32 = 32;
+const MAX_THUMBNAIL_SIZE: u32 = 1024;
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp", "bmp", "ico", "tif", "tiff"];
+
+pub fn thumbnail_dir() -> Option<PathBuf> {
+    Some(crate::data_dir::root()?.join("brainbox").join("thumbnails"))
+}
+
+fn is_image_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Return a protocol URL for a thumbnail of `source` that fits in `size`x`size`, generating it on first use.
+/// The cache key includes the file's modification time so edited images get a fresh thumbnail.
+pub fn get_or_create(source: &Path, size: u32) -> Result<String, BrainboxError> {
+    let size = size.clamp(MIN_THUMBNAIL_SIZE, MAX_THUMBNAIL_SIZE);
+    if !is_image_path(source) {
+        return Err(BrainboxError::InvalidInput("Not an image file".to_string()));
+    }
+    let metadata = std::fs::metadata(source)
+        .map_err(|_| BrainboxError::NotFound(format!("File not found: {}", source.display())))?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let key = format!("{}|{}|{}", source.display(), modified, size);
+    let file_name = format!("{:x}.webp", Sha256::digest(key.as_bytes()));
+    let dir = thumbnail_dir().ok_or("Failed to get app data dir")?;
+    let path = dir.join(&file_name);
+    if !path.exists() {
+        std::fs::create_dir_all(&dir)?;
+        let bytes = std::fs::read(source)?;
+        let webp = image_cache::to_webp(&bytes, size)?;
+        image_cache::write_atomic(&path, &webp)?;
+    }
+    Ok(image_cache::asset_url(&format!("{}{}", image_cache::THUMBNAIL_PREFIX, file_name)))
+}
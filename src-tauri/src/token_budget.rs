@@ -0,0 +1,181 @@
+// token_budget.rs - Token counting and context budgeting for brainbox
+// Prompts built from notes can be far longer than a model's context window. Ollama silently drops the start of
+// an oversized prompt and OpenAI rejects it, so the prompts brainbox builds (prompt library, digests, entity
+// extraction) are trimmed against the model's window here instead of by a fixed character count. Counts are
+// estimates: OpenAI models split text the way tiktoken's pre-tokenizer does and estimate the BPE merges per
+// piece; Ollama models, whose tokenizers differ per model family, use a characters-per-token rule.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Serialize;
+
+/// The window Ollama allocates unless the request sets num_ctx, whatever the model itself supports
+pub const DEFAULT_OLLAMA_CONTEXT: usize = 4096;
+/// Most of the window kept free for the answer
+const MAX_RESPONSE_RESERVE: usize = 2048;
+
+lazy_static! {
+    /// tiktoken's cl100k/o200k pre-tokenizer pattern, without the lookahead the regex crate doesn't support
+    static ref PIECE: Regex = Regex::new(
+        r"(?i:'s|'t|'re|'ve|'m|'ll|'d)|[^\r\n\p{L}\p{N}]?\p{L}+|\p{N}{1,3}| ?[^\s\p{L}\p{N}]+[\r\n]*|\s*[\r\n]+|\s+"
+    ).unwrap();
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Method {
+    /// tiktoken-style pieces with estimated merges, for OpenAI models
+    Bpe,
+    /// Characters per token, for local models
+    Heuristic,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct TokenCount {
+    pub tokens: usize,
+    pub method: Method,
+    pub context_window: usize,
+    /// Tokens a prompt may use and still leave room for the answer
+    pub prompt_budget: usize,
+}
+
+pub fn is_openai_model(model: &str) -> bool {
+    let model = model.trim().to_lowercase();
+    let model = model.strip_prefix("openai/").unwrap_or(&model);
+    ["gpt-", "chatgpt-", "o1", "o3", "o4", "text-embedding-", "davinci", "babbage"]
+        .iter()
+        .any(|prefix| model.starts_with(prefix))
+}
+
+pub fn method(model: &str) -> Method {
+    if is_openai_model(model) { Method::Bpe } else { Method::Heuristic }
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32, 0x3040..=0x30FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xAC00..=0xD7AF | 0xF900..=0xFAFF)
+}
+
+/// Estimated tokens in one pre-tokenizer piece. Common English words are one token; longer words, other scripts
+/// and punctuation runs take more.
+fn piece_tokens(piece: &str) -> usize {
+    let trimmed = piece.trim_start();
+    if trimmed.is_empty() {
+        return 1;
+    }
+    let chars = trimmed.chars().count();
+    let first = trimmed.chars().next().unwrap_or(' ');
+    if first.is_alphabetic() || trimmed.starts_with('\'') {
+        if trimmed.is_ascii() {
+            1 + chars.saturating_sub(8) / 5
+        } else {
+            let cjk = trimmed.chars().filter(|c| is_cjk(*c)).count();
+            (cjk + (chars - cjk).div_ceil(2)).max(1)
+        }
+    } else if first.is_numeric() {
+        1
+    } else {
+        chars.div_ceil(3).max(1)
+    }
+}
+
+fn heuristic_tokens(text: &str) -> usize {
+    let (mut ascii, mut cjk, mut other) = (0usize, 0usize, 0usize);
+    for c in text.chars() {
+        if c.is_ascii() {
+            ascii += 1;
+        } else if is_cjk(c) {
+            cjk += 1;
+        } else {
+            other += 1;
+        }
+    }
+    ascii.div_ceil(4) + cjk + other.div_ceil(2)
+}
+
+/// Estimated number of tokens `model` reads `text` as
+pub fn count(text: &str, model: &str) -> usize {
+    match method(model) {
+        Method::Bpe => PIECE.find_iter(text).map(|piece| piece_tokens(piece.as_str())).sum(),
+        Method::Heuristic => heuristic_tokens(text),
+    }
+}
+
+/// The context window of `model`. Local models get what Ollama allocates unless num_ctx is set.
+pub fn context_window(model: &str) -> usize {
+    if !is_openai_model(model) {
+        return DEFAULT_OLLAMA_CONTEXT;
+    }
+    let model = model.trim().to_lowercase();
+    let model = model.strip_prefix("openai/").unwrap_or(&model);
+    if model.starts_with("gpt-4.1") {
+        1_047_576
+    } else if model.starts_with("gpt-5") || model.starts_with("o1") || model.starts_with("o3") || model.starts_with("o4") {
+        200_000
+    } else if model.starts_with("gpt-4o") || model.starts_with("gpt-4-turbo") || model.starts_with("chatgpt-") {
+        128_000
+    } else if model.starts_with("gpt-4-32k") {
+        32_768
+    } else if model.starts_with("gpt-4") {
+        8_192
+    } else if model.starts_with("gpt-3.5") {
+        16_385
+    } else {
+        8_192
+    }
+}
+
+/// The window a request runs in: a local model's num_ctx when the request sets one
+fn window(model: &str, num_ctx: Option<u32>) -> usize {
+    match num_ctx {
+        Some(num_ctx) if !is_openai_model(model) => num_ctx as usize,
+        _ => context_window(model),
+    }
+}
+
+/// Tokens a prompt for `model` may use, leaving a quarter of the window (at most MAX_RESPONSE_RESERVE) for the answer
+pub fn prompt_budget(model: &str, num_ctx: Option<u32>) -> usize {
+    let window = window(model, num_ctx);
+    window - (window / 4).min(MAX_RESPONSE_RESERVE)
+}
+
+pub fn measure(text: &str, model: &str, num_ctx: Option<u32>) -> TokenCount {
+    TokenCount {
+        tokens: count(text, model),
+        method: method(model),
+        context_window: window(model, num_ctx),
+        prompt_budget: prompt_budget(model, num_ctx),
+    }
+}
+
+/// The longest start of `text` within `max_tokens` for `model`, cut at a word boundary where possible
+pub fn truncate<'a>(text: &'a str, model: &str, max_tokens: usize) -> &'a str {
+    if count(text, model) <= max_tokens {
+        return text;
+    }
+    match method(model) {
+        Method::Bpe => {
+            let mut used = 0;
+            for piece in PIECE.find_iter(text) {
+                used += piece_tokens(piece.as_str());
+                if used > max_tokens {
+                    return &text[..piece.start()];
+                }
+            }
+            text
+        }
+        Method::Heuristic => {
+            // Tokens never shrink as text grows, so search for the longest prefix that fits
+            let bounds: Vec<usize> = text.char_indices().map(|(i, _)| i).chain([text.len()]).collect();
+            let (mut low, mut high) = (0, bounds.len() - 1);
+            while low < high {
+                let mid = (low + high).div_ceil(2);
+                if heuristic_tokens(&text[..bounds[mid]]) <= max_tokens { low = mid } else { high = mid - 1 }
+            }
+            let cut = &text[..bounds[low]];
+            match cut.rfind(char::is_whitespace) {
+                Some(space) if space > cut.len() / 2 => &cut[..space],
+                _ => cut,
+            }
+        }
+    }
+}
@@ -0,0 +1,205 @@
+// tokenizers.rs - Language-aware search tokenizers for brainbox
+// Tantivy's default tokenizer splits on whitespace and punctuation, which leaves Chinese/Japanese/Korean text as one
+// giant token. Titles and content can instead use a CJK bigram tokenizer or a stemming analyzer for European languages.
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tantivy::tokenizer::{
+    Language, LowerCaser, RemoveLongFilter, SimpleTokenizer, Stemmer, TextAnalyzer, Token, TokenStream, Tokenizer,
+};
+
+use crate::error::BrainboxError;
+use crate::settings;
+
+const TITLE_LANGUAGE_KEY: &str = "search.language.title";
+const CONTENT_LANGUAGE_KEY: &str = "search.language.content";
+/// Tantivy's built-in tokenizer; existing indexes were built with it, so it stays the default
+pub const DEFAULT_LANGUAGE: &str = "default";
+/// Languages selectable per field. "cjk" indexes CJK runs as overlapping character pairs and other text as words.
+pub const SEARCH_LANGUAGES: &[&str] = &[
+    "default", "cjk", "danish", "dutch", "english", "finnish", "french", "german", "greek", "hungarian", "italian",
+    "norwegian", "portuguese", "romanian", "russian", "spanish", "swedish", "turkish",
+];
+/// Tokens longer than this are dropped, matching Tantivy's default analyzer
+const MAX_TOKEN_LEN: usize = 40;
+
+/// Tokenizer choice for each text field. Changing it requires rebuilding the index.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchLanguages {
+    pub title: String,
+    pub content: String,
+}
+
+impl Default for SearchLanguages {
+    fn default() -> Self {
+        SearchLanguages { title: DEFAULT_LANGUAGE.to_string(), content: DEFAULT_LANGUAGE.to_string() }
+    }
+}
+
+fn valid_language(value: Option<String>) -> String {
+    value
+        .filter(|l| SEARCH_LANGUAGES.contains(&l.as_str()))
+        .unwrap_or_else(|| DEFAULT_LANGUAGE.to_string())
+}
+
+pub fn get_languages(conn: &Connection) -> Result<SearchLanguages, BrainboxError> {
+    Ok(SearchLanguages {
+        title: valid_language(settings::get(conn, TITLE_LANGUAGE_KEY)?),
+        content: valid_language(settings::get(conn, CONTENT_LANGUAGE_KEY)?),
+    })
+}
+
+pub fn set_languages(conn: &Connection, languages: &SearchLanguages) -> Result<(), BrainboxError> {
+    for language in [&languages.title, &languages.content] {
+        if !SEARCH_LANGUAGES.contains(&language.as_str()) {
+            return Err(BrainboxError::InvalidInput(format!("Unknown search language: {}", language)));
+        }
+    }
+    settings::set(conn, TITLE_LANGUAGE_KEY, &languages.title)?;
+    settings::set(conn, CONTENT_LANGUAGE_KEY, &languages.content)?;
+    Ok(())
+}
+
+fn stemmer_language(language: &str) -> Option<Language> {
+    Some(match language {
+        "danish" => Language::Danish,
+        "dutch" => Language::Dutch,
+        "english" => Language::English,
+        "finnish" => Language::Finnish,
+        "french" => Language::French,
+        "german" => Language::German,
+        "greek" => Language::Greek,
+        "hungarian" => Language::Hungarian,
+        "italian" => Language::Italian,
+        "norwegian" => Language::Norwegian,
+        "portuguese" => Language::Portuguese,
+        "romanian" => Language::Romanian,
+        "russian" => Language::Russian,
+        "spanish" => Language::Spanish,
+        "swedish" => Language::Swedish,
+        "turkish" => Language::Turkish,
+        _ => return None,
+    })
+}
+
+/// Name a field's tokenizer is registered under in the index schema
+pub fn tokenizer_name(language: &str) -> String {
+    if language == DEFAULT_LANGUAGE {
+        "default".to_string()
+    } else {
+        format!("brainbox_{}", language)
+    }
+}
+
+/// Register the analyzers for every selectable language on an index; must run before indexing or searching
+pub fn register(index: &tantivy::Index) {
+    let manager = index.tokenizers();
+    manager.register(
+        &tokenizer_name("cjk"),
+        TextAnalyzer::builder(CjkBigramTokenizer)
+            .filter(RemoveLongFilter::limit(MAX_TOKEN_LEN))
+            .filter(LowerCaser)
+            .build(),
+    );
+    for language in SEARCH_LANGUAGES {
+        if let Some(stem) = stemmer_language(language) {
+            manager.register(
+                &tokenizer_name(language),
+                TextAnalyzer::builder(SimpleTokenizer::default())
+                    .filter(RemoveLongFilter::limit(MAX_TOKEN_LEN))
+                    .filter(LowerCaser)
+                    .filter(Stemmer::new(stem))
+                    .build(),
+            );
+        }
+    }
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x1100..=0x11FF     // Hangul Jamo
+        | 0x3040..=0x30FF   // Hiragana, Katakana
+        | 0x3130..=0x318F   // Hangul compatibility Jamo
+        | 0x31F0..=0x31FF   // Katakana phonetic extensions
+        | 0x3400..=0x4DBF   // CJK extension A
+        | 0x4E00..=0x9FFF   // CJK unified ideographs
+        | 0xAC00..=0xD7AF   // Hangul syllables
+        | 0xF900..=0xFAFF   // CJK compatibility ideographs
+        | 0xFF66..=0xFF9F   // Halfwidth Katakana
+        | 0x20000..=0x2A6DF // CJK extension B
+    )
+}
+
+/// Words for non-CJK text (like SimpleTokenizer) and overlapping character bigrams for CJK runs,
+/// the same approach as Lucene's CJK analyzer. A lone CJK character is kept as a single token.
+#[derive(Clone, Default)]
+pub struct CjkBigramTokenizer;
+
+pub struct CjkBigramTokenStream {
+    tokens: Vec<Token>,
+    index: Option<usize>,
+}
+
+impl Tokenizer for CjkBigramTokenizer {
+    type TokenStream<'a> = CjkBigramTokenStream;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> CjkBigramTokenStream {
+        let mut tokens = Vec::new();
+        let mut push = |from: usize, to: usize| {
+            tokens.push(Token {
+                offset_from: from,
+                offset_to: to,
+                position: tokens.len(),
+                text: text[from..to].to_string(),
+                position_length: 1,
+            });
+        };
+
+        let chars: Vec<(usize, char)> = text.char_indices().collect();
+        let end_of = |i: usize| chars.get(i + 1).map(|(offset, _)| *offset).unwrap_or(text.len());
+        let mut i = 0;
+        while i < chars.len() {
+            let (start, c) = chars[i];
+            if is_cjk(c) {
+                let mut j = i;
+                while j + 1 < chars.len() && is_cjk(chars[j + 1].1) {
+                    j += 1;
+                }
+                if i == j {
+                    push(start, end_of(i));
+                } else {
+                    for (k, (offset, _)) in chars.iter().enumerate().take(j).skip(i) {
+                        push(*offset, end_of(k + 1));
+                    }
+                }
+                i = j + 1;
+            } else if c.is_alphanumeric() {
+                let mut j = i;
+                while j + 1 < chars.len() && chars[j + 1].1.is_alphanumeric() && !is_cjk(chars[j + 1].1) {
+                    j += 1;
+                }
+                push(start, end_of(j));
+                i = j + 1;
+            } else {
+                i += 1;
+            }
+        }
+        CjkBigramTokenStream { tokens, index: None }
+    }
+}
+
+impl TokenStream for CjkBigramTokenStream {
+    fn advance(&mut self) -> bool {
+        let next = self.index.map_or(0, |i| i + 1);
+        self.index = Some(next);
+        next < self.tokens.len()
+    }
+
+    fn token(&self) -> &Token {
+        &self.tokens[self.index.unwrap_or(0)]
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        &mut self.tokens[self.index.unwrap_or(0)]
+    }
+}
@@ -0,0 +1,58 @@
+// tombstones.rs - Records of purged items and vaults for brainbox
+// Purging hard-deletes soft-deleted rows, and with them the deleted_at marker that stopped an older sync file from
+// bringing the item back. Each purged item's UUID is kept here instead, long after its data is gone, and imports
+// skip any UUID listed.
+
+use rusqlite::{params, Connection};
+
+/// Tombstones outlive the purge window by this many days, so a sync file that old still can't resurrect an item
+pub const EXTRA_RETENTION_DAYS: i64 = 365;
+
+pub fn create_table(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS tombstones (
+            uuid TEXT PRIMARY KEY,
+            deleted_at TEXT NOT NULL
+        ) WITHOUT ROWID",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Record the items soft-deleted before `cutoff` that a purge is about to remove
+pub fn record_deleted_before(conn: &Connection, cutoff: &str) -> rusqlite::Result<usize> {
+    create_table(conn)?;
+    conn.execute(
+        "INSERT OR REPLACE INTO tombstones (uuid, deleted_at)
+         SELECT uuid, deleted_at FROM vault_items WHERE uuid IS NOT NULL AND deleted_at IS NOT NULL AND deleted_at < ?1",
+        [cutoff],
+    )
+}
+
+/// Record a vault a purge is about to remove and every item in it; items still live in it get the vault's deletion time
+pub fn record_vault(conn: &Connection, vault_id: i64, deleted_at: &str) -> rusqlite::Result<usize> {
+    create_table(conn)?;
+    conn.execute(
+        "INSERT OR REPLACE INTO tombstones (uuid, deleted_at)
+         SELECT uuid, deleted_at FROM vaults WHERE id = ?1 AND uuid IS NOT NULL",
+        [vault_id],
+    )?;
+    conn.execute(
+        "INSERT OR REPLACE INTO tombstones (uuid, deleted_at)
+         SELECT uuid, COALESCE(deleted_at, ?2) FROM vault_items WHERE vault_id = ?1 AND uuid IS NOT NULL",
+        params![vault_id, deleted_at],
+    )
+}
+
+/// Whether a vault or item with this UUID was purged here
+pub fn contains(conn: &Connection, uuid: &str) -> rusqlite::Result<bool> {
+    create_table(conn)?;
+    conn.query_row("SELECT EXISTS (SELECT 1 FROM tombstones WHERE uuid = ?1)", [uuid], |row| row.get(0))
+}
+
+/// Drop tombstones of items deleted more than `purge_days` + EXTRA_RETENTION_DAYS ago
+pub fn prune(conn: &Connection, purge_days: i32) -> rusqlite::Result<usize> {
+    create_table(conn)?;
+    let cutoff = chrono::Utc::now() - chrono::Duration::days(purge_days.max(0) as i64 + EXTRA_RETENTION_DAYS);
+    conn.execute("DELETE FROM tombstones WHERE deleted_at < ?1", [cutoff.to_rfc3339()])
+}
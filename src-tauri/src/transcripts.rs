@@ -0,0 +1,399 @@
+// transcripts.rs - Video and podcast transcripts for brainbox
+// Captions are taken from the source when it publishes them (YouTube caption tracks, Vimeo text tracks,
+// podcast:transcript tags in RSS feeds); audio without captions falls back to a locally installed Whisper
+
+use quick_xml::events::Event;
+use regex::Regex;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::error::BrainboxError;
+use crate::http;
+use crate::settings;
+
+const WHISPER_COMMAND_KEY: &str = "transcripts.whisper_command";
+const WHISPER_MODEL_KEY: &str = "transcripts.whisper_model";
+pub const DEFAULT_WHISPER_COMMAND: &str = "whisper";
+pub const DEFAULT_WHISPER_MODEL: &str = "base";
+/// Largest audio/video file downloaded for local transcription, in bytes
+const MAX_MEDIA_BYTES: u64 = 500 * 1024 * 1024;
+const MEDIA_EXTENSIONS: &[&str] = &["mp3", "m4a", "aac", "wav", "ogg", "oga", "opus", "flac", "mp4", "m4v", "webm", "mov"];
+
+#[derive(Debug, Serialize, Clone)]
+pub struct MediaTranscript {
+    /// Where the text came from: "youtube", "vimeo", "podcast" or "whisper"
+    pub source: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    pub text: String,
+}
+
+impl MediaTranscript {
+    fn new(source: &str, language: Option<String>, text: String) -> Option<Self> {
+        let text = text.trim().to_string();
+        (!text.is_empty()).then(|| MediaTranscript { source: source.to_string(), language, text })
+    }
+}
+
+/// Local Whisper used when no captions exist. `command` is either the openai-whisper CLI or whisper.cpp's
+/// whisper-cli; a `model` ending in .bin is treated as a whisper.cpp model file, anything else as a model name.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WhisperSettings {
+    pub command: String,
+    pub model: String,
+}
+
+pub fn get_whisper_settings(conn: &Connection) -> Result<WhisperSettings, BrainboxError> {
+    Ok(WhisperSettings {
+        command: settings::get(conn, WHISPER_COMMAND_KEY)?
+            .filter(|c| !c.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_WHISPER_COMMAND.to_string()),
+        model: settings::get(conn, WHISPER_MODEL_KEY)?
+            .filter(|m| !m.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_WHISPER_MODEL.to_string()),
+    })
+}
+
+pub fn set_whisper_settings(conn: &Connection, settings: &WhisperSettings) -> Result<(), BrainboxError> {
+    settings::set(conn, WHISPER_COMMAND_KEY, settings.command.trim())?;
+    settings::set(conn, WHISPER_MODEL_KEY, settings.model.trim())?;
+    Ok(())
+}
+
+fn has_media_extension(url: &reqwest::Url) -> bool {
+    Path::new(url.path())
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| MEDIA_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Transcript for a media URL: a YouTube or Vimeo video, a podcast RSS feed (its latest episode)
+/// or a direct audio/video file. Returns None when nothing could be found.
+pub fn fetch(conn: &Connection, url: &str) -> Result<Option<MediaTranscript>, BrainboxError> {
+    let parsed = reqwest::Url::parse(url).map_err(|_| BrainboxError::InvalidInput(format!("Invalid URL: {}", url)))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(BrainboxError::InvalidInput("Only http(s) URLs are supported".to_string()));
+    }
+    let host = parsed.host_str().unwrap_or("").to_lowercase();
+    if host.contains("youtube.com") || host.contains("youtu.be") {
+        return Ok(youtube(&parsed)?.and_then(|text| MediaTranscript::new("youtube", None, text)));
+    }
+    if host == "vimeo.com" || host.ends_with(".vimeo.com") {
+        return vimeo(&parsed);
+    }
+    if has_media_extension(&parsed) {
+        return whisper(conn, &parsed);
+    }
+
+    let resp = http::send(http::client().get(parsed.clone()))?;
+    if !resp.status().is_success() {
+        return Err(BrainboxError::Network(format!("Request failed with status {}", resp.status())));
+    }
+    let content_type = resp
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_lowercase();
+    if content_type.starts_with("audio/") || content_type.starts_with("video/") {
+        drop(resp);
+        return whisper(conn, &parsed);
+    }
+    let body = resp.text()?;
+    let head = body.trim_start();
+    if content_type.contains("xml") || head.starts_with("<?xml") || head.starts_with("<rss") {
+        return podcast(conn, &body, &parsed);
+    }
+    Ok(None)
+}
+
+/// YouTube caption text, scraped from the watch page's captionTracks
+pub fn youtube(url: &reqwest::Url) -> Result<Option<String>, BrainboxError> {
+    let client = http::client();
+    let resp = http::send(client.get(url.clone()))?;
+    let page = resp.text()?;
+    // Find captionTracks JSON array
+    let re = Regex::new(r#""captionTracks"\s*:\s*(\[[^\]]+\])"#).map_err(|e| BrainboxError::Other(e.to_string()))?;
+    let caps = match re.captures(&page) { Some(c) => c, None => return Ok(None) };
+    let tracks_json = caps.get(1).map(|m| m.as_str()).unwrap_or("");
+    let val: serde_json::Value = match serde_json::from_str(tracks_json) { Ok(v) => v, Err(_) => return Ok(None) };
+    let base = match val.get(0).and_then(|t| t.get("baseUrl")).and_then(|v| v.as_str()) { Some(s) => s, None => return Ok(None) };
+    let base_url = base.replace("\\u0026", "&");
+    let tr_resp = http::send(client.get(&base_url))?;
+    let xml = tr_resp.text()?;
+    // Parse XML transcript: collect <text> nodes
+    let mut reader = quick_xml::Reader::from_str(&xml);
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+    let mut acc = String::new();
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Eof) => break,
+            Ok(Event::Text(t)) => {
+                let txt = t.unescape().unwrap_or_default().to_string();
+                if !txt.trim().is_empty() {
+                    acc.push_str(&txt);
+                    acc.push('\n');
+                }
+            }
+            Ok(_) => {}
+            Err(_) => break,
+        }
+        buf.clear();
+    }
+    if acc.trim().is_empty() { Ok(None) } else { Ok(Some(acc)) }
+}
+
+/// Vimeo text tracks, listed in the public player config
+fn vimeo(url: &reqwest::Url) -> Result<Option<MediaTranscript>, BrainboxError> {
+    let Some(id) = url
+        .path_segments()
+        .and_then(|mut segments| segments.rfind(|s| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit())))
+    else {
+        return Ok(None);
+    };
+    let player = reqwest::Url::parse("https://player.vimeo.com/").map_err(|e| BrainboxError::Other(e.to_string()))?;
+    let config_url = player.join(&format!("video/{}/config", id)).map_err(|e| BrainboxError::Other(e.to_string()))?;
+    let resp = http::send(http::client().get(config_url))?;
+    if !resp.status().is_success() {
+        return Ok(None);
+    }
+    let config: serde_json::Value = resp.json()?;
+    let tracks = config
+        .pointer("/request/text_tracks")
+        .and_then(|t| t.as_array())
+        .cloned()
+        .unwrap_or_default();
+    // Prefer real captions over auto-generated or translated subtitles
+    let track = tracks
+        .iter()
+        .find(|t| t.get("kind").and_then(|k| k.as_str()) == Some("captions"))
+        .or_else(|| tracks.first());
+    let Some(track) = track else { return Ok(None) };
+    let Some(track_url) = track.get("url").and_then(|u| u.as_str()).and_then(|u| player.join(u).ok()) else {
+        return Ok(None);
+    };
+    let language = track.get("lang").and_then(|l| l.as_str()).map(|l| l.to_string());
+    let vtt = http::send(http::client().get(track_url))?.text()?;
+    Ok(MediaTranscript::new("vimeo", language, cues_to_text(&vtt)))
+}
+
+/// Plain text from WebVTT or SRT captions: timing lines, cue numbers and markup are dropped,
+/// and lines repeated by rolling captions are collapsed
+fn cues_to_text(captions: &str) -> String {
+    let tags = Regex::new(r"<[^>]+>").ok();
+    let mut out: Vec<String> = Vec::new();
+    let mut in_note = false;
+    for line in captions.lines() {
+        let line = line.trim().trim_start_matches('\u{feff}');
+        if line.is_empty() {
+            in_note = false;
+            continue;
+        }
+        if in_note || line == "WEBVTT" || line.starts_with("WEBVTT ") || line.contains("-->") || line.chars().all(|c| c.is_ascii_digit()) {
+            continue;
+        }
+        if line.starts_with("NOTE") || line.starts_with("STYLE") || line.starts_with("REGION") {
+            in_note = true;
+            continue;
+        }
+        let text = match &tags {
+            Some(re) => re.replace_all(line, "").to_string(),
+            None => line.to_string(),
+        };
+        let text = text.replace("&amp;", "&").replace("&lt;", "<").replace("&gt;", ">").replace("&nbsp;", " ");
+        if !text.trim().is_empty() && out.last().map(|l| l.as_str()) != Some(text.trim()) {
+            out.push(text.trim().to_string());
+        }
+    }
+    out.join("\n")
+}
+
+/// Text of a podcast:transcript document in any of the formats the podcast namespace allows
+fn transcript_to_text(body: &str, mime: &str) -> String {
+    let mime = mime.to_lowercase();
+    if mime.contains("json") {
+        let segments = serde_json::from_str::<serde_json::Value>(body)
+            .ok()
+            .and_then(|v| v.get("segments").and_then(|s| s.as_array()).cloned())
+            .unwrap_or_default();
+        return segments
+            .iter()
+            .filter_map(|s| s.get("body").and_then(|b| b.as_str()))
+            .map(|b| b.trim())
+            .filter(|b| !b.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+    if mime.contains("html") {
+        let document = scraper::Html::parse_document(body);
+        return document.root_element().text().map(|t| t.trim()).filter(|t| !t.is_empty()).collect::<Vec<_>>().join("\n");
+    }
+    if mime.contains("vtt") || mime.contains("srt") || mime.contains("subrip") {
+        return cues_to_text(body);
+    }
+    body.to_string()
+}
+
+/// Rank transcript formats by how cleanly they convert to text
+fn transcript_rank(mime: &str) -> u8 {
+    match mime {
+        m if m.contains("vtt") => 0,
+        m if m.contains("srt") || m.contains("subrip") => 1,
+        m if m.contains("json") => 2,
+        m if m.contains("plain") => 3,
+        _ => 4,
+    }
+}
+
+/// The latest episode of a feed: its podcast:transcript if it has one, otherwise Whisper on the enclosure
+fn podcast(conn: &Connection, feed: &str, feed_url: &reqwest::Url) -> Result<Option<MediaTranscript>, BrainboxError> {
+    let mut reader = quick_xml::Reader::from_str(feed);
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+    let mut in_item = false;
+    // (rank, url, type, language)
+    let mut transcripts: Vec<(u8, String, String, Option<String>)> = Vec::new();
+    let mut enclosure: Option<String> = None;
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Eof) | Err(_) => break,
+            Ok(Event::Start(e)) | Ok(Event::Empty(e)) => {
+                let name = e.name();
+                let name = name.as_ref();
+                if name == b"item" {
+                    in_item = true;
+                } else if in_item && (name == b"podcast:transcript" || name == b"enclosure") {
+                    let mut url = None;
+                    let mut mime = String::new();
+                    let mut language = None;
+                    for attr in e.attributes().flatten() {
+                        let value = attr.unescape_value().map(|v| v.to_string()).unwrap_or_default();
+                        match attr.key.as_ref() {
+                            b"url" => url = Some(value),
+                            b"type" => mime = value,
+                            b"language" => language = Some(value),
+                            _ => {}
+                        }
+                    }
+                    if let Some(url) = url {
+                        if name == b"enclosure" {
+                            enclosure.get_or_insert(url);
+                        } else {
+                            transcripts.push((transcript_rank(&mime.to_lowercase()), url, mime, language));
+                        }
+                    }
+                }
+            }
+            // Feeds list the newest episode first; stop after it
+            Ok(Event::End(e)) if e.name().as_ref() == b"item" => break,
+            Ok(_) => {}
+        }
+        buf.clear();
+    }
+
+    transcripts.sort_by_key(|(rank, ..)| *rank);
+    for (_, url, mime, language) in transcripts {
+        let Ok(url) = feed_url.join(&url) else { continue };
+        match http::send(http::client().get(url.clone())).and_then(|r| r.error_for_status()).and_then(|r| r.text()) {
+            Ok(body) => {
+                if let Some(transcript) = MediaTranscript::new("podcast", language, transcript_to_text(&body, &mime)) {
+                    return Ok(Some(transcript));
+                }
+            }
+            Err(e) => tracing::debug!("Podcast transcript {} unavailable: {}", url, e),
+        }
+    }
+    match enclosure.and_then(|url| feed_url.join(&url).ok()) {
+        Some(audio) => whisper(conn, &audio),
+        None => Ok(None),
+    }
+}
+
+/// Download `url` into the temp dir, refusing anything larger than MAX_MEDIA_BYTES
+fn download_media(url: &reqwest::Url, dir: &Path) -> Result<PathBuf, BrainboxError> {
+    let resp = http::send(http::streaming_client().get(url.clone()))?;
+    if !resp.status().is_success() {
+        return Err(BrainboxError::Network(format!("Media download failed with status {}", resp.status())));
+    }
+    if resp.content_length().is_some_and(|len| len > MAX_MEDIA_BYTES) {
+        return Err(BrainboxError::InvalidInput("Media file is larger than 500 MB".to_string()));
+    }
+    let ext = Path::new(url.path())
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .filter(|ext| MEDIA_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        .unwrap_or("mp3")
+        .to_lowercase();
+    let path = dir.join(format!("media.{}", ext));
+    let mut file = std::fs::File::create(&path)?;
+    let copied = std::io::copy(&mut resp.take(MAX_MEDIA_BYTES + 1), &mut file)?;
+    if copied > MAX_MEDIA_BYTES {
+        return Err(BrainboxError::InvalidInput("Media file is larger than 500 MB".to_string()));
+    }
+    Ok(path)
+}
+
+fn whisper_command(settings: &WhisperSettings, media: &Path, out_dir: &Path) -> Command {
+    let mut cmd = Command::new(&settings.command);
+    if settings.model.to_lowercase().ends_with(".bin") {
+        // whisper.cpp: -of takes the output path without extension
+        cmd.arg("-m").arg(&settings.model)
+            .arg("-f").arg(media)
+            .arg("-otxt")
+            .arg("-of").arg(out_dir.join("media"));
+    } else {
+        cmd.arg(media)
+            .args(["--model", &settings.model, "--output_format", "txt", "--output_dir"])
+            .arg(out_dir);
+    }
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        // CREATE_NO_WINDOW: don't flash a console window
+        cmd.creation_flags(0x0800_0000);
+    }
+    cmd
+}
+
+/// Transcribe downloaded audio with the configured local Whisper. Can take minutes for long episodes.
+fn whisper(conn: &Connection, url: &reqwest::Url) -> Result<Option<MediaTranscript>, BrainboxError> {
+    let settings = get_whisper_settings(conn)?;
+    let work_dir = std::env::temp_dir().join(format!(
+        "brainbox-transcribe-{:x}",
+        Sha256::digest(url.as_str().as_bytes())
+    ));
+    std::fs::create_dir_all(&work_dir)?;
+    let result = (|| {
+        let media = download_media(url, &work_dir)?;
+        tracing::info!("Transcribing {} with {}", url, settings.command);
+        let output = whisper_command(&settings, &media, &work_dir).output().map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                BrainboxError::NotFound(format!(
+                    "No captions were found and Whisper ('{}') isn't installed",
+                    settings.command
+                ))
+            } else {
+                BrainboxError::Io(e)
+            }
+        })?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(BrainboxError::Other(format!(
+                "Whisper failed: {}",
+                stderr.lines().next_back().unwrap_or("unknown error")
+            )));
+        }
+        let mut text = String::new();
+        std::fs::File::open(work_dir.join("media.txt"))?.read_to_string(&mut text)?;
+        Ok(MediaTranscript::new("whisper", None, text))
+    })();
+    if let Err(e) = std::fs::remove_dir_all(&work_dir) {
+        tracing::warn!("Failed to clean up {}: {}", work_dir.display(), e);
+    }
+    result
+}
@@ -0,0 +1,178 @@
+// tray.rs - System tray for brainbox
+// Built in Rust so it works even when the webview is hidden/suspended; the menu lists recent items and is rebuilt when items change
+
+use std::sync::Mutex;
+use tauri::image::Image as TauriImage;
+use tauri::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::{TrayIcon, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Emitter, Listener, Manager, Wry};
+
+use crate::i18n::tr;
+use crate::vault::VaultItem;
+
+/// Number of recent items listed in the tray menu
+const RECENT_ITEM_COUNT: usize = 5;
+/// Longest item title shown before truncating
+const MAX_TITLE_CHARS: usize = 40;
+/// Menu ids for recent items are this prefix followed by "<item_id>:<vault_id>"
+const RECENT_PREFIX: &str = "recent:";
+
+// Keep the tray icon alive (otherwise events may not fire)
+pub struct TrayState {
+    tray: Mutex<Option<TrayIcon>>,
+}
+
+fn show_main(app: &AppHandle) {
+    if let Some(w) = app.get_webview_window("main") {
+        let _ = w.show();
+        let _ = w.set_focus();
+    }
+}
+
+fn menu_title(title: &str) -> String {
+    let title = title.trim();
+    let title = if title.is_empty() { tr("Untitled") } else { title.to_string() };
+    if title.chars().count() > MAX_TITLE_CHARS {
+        format!("{}…", title.chars().take(MAX_TITLE_CHARS - 1).collect::<String>())
+    } else {
+        title
+    }
+}
+
+fn build_menu(app: &AppHandle) -> tauri::Result<Menu<Wry>> {
+    let recent = crate::db::open()
+        .ok()
+        .and_then(|conn| VaultItem::list_recent(&conn, RECENT_ITEM_COUNT).ok())
+        .unwrap_or_default();
+    let recent_menu = Submenu::with_id(app, "recent", tr("Recent Items"), !recent.is_empty())?;
+    for item in &recent {
+        recent_menu.append(&MenuItem::with_id(
+            app,
+            format!("{}{}:{}", RECENT_PREFIX, item.id, item.vault_id),
+            menu_title(&item.title),
+            true,
+            None::<&str>,
+        )?)?;
+    }
+
+    let menu = Menu::new(app)?;
+    menu.append(&MenuItem::with_id(app, "show", tr("Show Brainbox"), true, None::<&str>)?)?;
+    menu.append(&MenuItem::with_id(app, "hide", tr("Hide to Tray"), true, None::<&str>)?)?;
+    menu.append(&PredefinedMenuItem::separator(app)?)?;
+    menu.append(&MenuItem::with_id(app, "quick_capture", tr("Quick Capture"), true, None::<&str>)?)?;
+    menu.append(&MenuItem::with_id(app, "screenshot", tr("Capture Screenshot"), true, None::<&str>)?)?;
+    menu.append(&MenuItem::with_id(app, "sync_now", tr("Sync Now"), true, None::<&str>)?)?;
+    menu.append(&PredefinedMenuItem::separator(app)?)?;
+    menu.append(&recent_menu)?;
+    menu.append(&PredefinedMenuItem::separator(app)?)?;
+    menu.append(&MenuItem::with_id(app, "quit", tr("Quit"), true, None::<&str>)?)?;
+    Ok(menu)
+}
+
+/// Take a screenshot on a background thread and hand it to the main window
+fn capture_screenshot(app: &AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || match crate::capture::capture_screenshot_and_metadata() {
+        Some(capture) => {
+            show_main(&app);
+            let _ = app.emit_to("main", "screenshot-captured", capture);
+        }
+        None => tracing::warn!("Screenshot capture from tray failed"),
+    });
+}
+
+/// Export to the sync folder like sync-on-close does: only vaults that don't need a password
+fn sync_now(app: &AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let result = crate::db::open()
+            .map_err(|e| e.to_string())
+            .and_then(|conn| crate::sync::sync_export(&conn, Default::default(), &crate::progress::Operation::silent()));
+        let (title, body) = match result {
+            Ok(_) => (tr("Sync complete"), tr("Vaults exported to the sync folder")),
+            Err(e) => {
+                tracing::error!("Tray sync failed: {}", e);
+                (tr("Sync failed"), e)
+            }
+        };
+        use tauri_plugin_notification::NotificationExt;
+        let _ = app.notification().builder().title(title).body(body).show();
+    });
+}
+
+fn on_menu_event(app: &AppHandle, event: MenuEvent) {
+    let id = event.id().as_ref();
+    tracing::debug!("Tray menu event: {}", id);
+    match id {
+        "show" => show_main(app),
+        "hide" => {
+            if let Some(w) = app.get_webview_window("main") {
+                let _ = w.hide();
+            }
+        }
+        "quick_capture" => {
+            show_main(app);
+            let _ = app.emit("capture-hotkey-pressed", ());
+        }
+        "screenshot" => capture_screenshot(app),
+        "sync_now" => sync_now(app),
+        "quit" => crate::lifecycle::quit(app),
+        _ => {
+            let Some((item_id, vault_id)) = id
+                .strip_prefix(RECENT_PREFIX)
+                .and_then(|ids| ids.split_once(':'))
+                .and_then(|(item, vault)| Some((item.parse::<i64>().ok()?, vault.parse::<i64>().ok()?)))
+            else {
+                return;
+            };
+            show_main(app);
+            let _ = app.emit_to("main", "open-item", serde_json::json!({ "itemId": item_id, "vaultId": vault_id }));
+        }
+    }
+}
+
+/// Rebuild the tray menu, e.g. after items were added or renamed
+pub fn refresh(app: &AppHandle) {
+    let Some(state) = app.try_state::<TrayState>() else { return };
+    let guard = state.tray.lock().unwrap();
+    let Some(tray) = guard.as_ref() else { return };
+    match build_menu(app) {
+        Ok(menu) => {
+            let _ = tray.set_menu(Some(menu));
+        }
+        Err(e) => tracing::warn!("Failed to rebuild tray menu: {}", e),
+    }
+}
+
+pub fn init(app: &AppHandle) -> tauri::Result<()> {
+    // Prefer the app's default window icon (honors platform formats: .ico on Windows, .icns on macOS)
+    let mut tray_builder = TrayIconBuilder::new();
+    if let Some(img) = app.default_window_icon() {
+        tray_builder = tray_builder.icon(img.clone());
+    } else if let Ok(img) = TauriImage::from_path("icons/icon.png") {
+        // Fallback to our bundled PNG if default icon isn't available
+        tray_builder = tray_builder.icon(img);
+    }
+
+    let tray = tray_builder
+        .menu(&build_menu(app)?)
+        .on_menu_event(on_menu_event)
+        .on_tray_icon_event(|tray, event| {
+            // Show on double click
+            if let TrayIconEvent::DoubleClick { .. } = event {
+                tracing::debug!("Tray double click");
+                show_main(tray.app_handle());
+            }
+        })
+        .build(app)?;
+
+    // store tray handle so callbacks stay alive
+    app.manage(TrayState { tray: Mutex::new(Some(tray)) });
+
+    // The frontend emits these after creating, editing or deleting items and vaults
+    for event in ["items-changed", "vaults-changed"] {
+        let handle = app.clone();
+        app.listen_any(event, move |_| refresh(&handle));
+    }
+    Ok(())
+}
@@ -0,0 +1,272 @@
+// updater.rs - App update helpers for brainbox
+// Release channels and version ordering for GitHub releases, plus signature checks: assets are signed with minisign
+// (the same format `tauri signer sign` produces), the public key is embedded at build time and every download must verify
+
+use base64::Engine;
+use minisign_verify::{PublicKey, Signature};
+use rusqlite::Connection;
+use std::cmp::Ordering;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::error::BrainboxError;
+use crate::settings;
+
+/// Minisign public key, set by the release workflow. Builds without it refuse to install updates.
+const UPDATE_PUBLIC_KEY: Option<&str> = option_env!("BRAINBOX_UPDATER_PUBKEY");
+/// Suffix of the signature asset published next to each release asset
+pub const SIGNATURE_SUFFIX: &str = ".sig";
+
+const UPDATE_CHANNEL_KEY: &str = "update_channel";
+/// "stable" only considers full releases; "beta" also considers GitHub pre-releases
+pub const UPDATE_CHANNELS: &[&str] = &["stable", "beta"];
+pub const DEFAULT_UPDATE_CHANNEL: &str = "stable";
+
+pub fn get_channel(conn: &Connection) -> Result<String, BrainboxError> {
+    Ok(settings::get(conn, UPDATE_CHANNEL_KEY)?
+        .filter(|channel| UPDATE_CHANNELS.contains(&channel.as_str()))
+        .unwrap_or_else(|| DEFAULT_UPDATE_CHANNEL.to_string()))
+}
+
+pub fn set_channel(conn: &Connection, channel: &str) -> Result<(), BrainboxError> {
+    if !UPDATE_CHANNELS.contains(&channel) {
+        return Err(BrainboxError::InvalidInput(format!("Unknown update channel: {}", channel)));
+    }
+    settings::set(conn, UPDATE_CHANNEL_KEY, channel)?;
+    Ok(())
+}
+
+const CHECK_INTERVAL_KEY: &str = "update_check_interval_hours";
+const NOTIFY_KEY: &str = "update_notify";
+const LAST_CHECKED_KEY: &str = "update_last_checked";
+const NOTIFIED_VERSION_KEY: &str = "update_notified_version";
+/// Background checks run daily unless configured otherwise; 0 turns them off
+pub const DEFAULT_CHECK_INTERVAL_HOURS: u32 = 24;
+
+/// How often the scheduler looks for updates and whether it shows a native notification
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct UpdateCheckSettings {
+    pub interval_hours: u32,
+    pub notify: bool,
+}
+
+pub fn get_check_settings(conn: &Connection) -> Result<UpdateCheckSettings, BrainboxError> {
+    let interval_hours = settings::get_parsed(conn, CHECK_INTERVAL_KEY)?
+        .unwrap_or(DEFAULT_CHECK_INTERVAL_HOURS);
+    let notify = settings::get_bool(conn, NOTIFY_KEY)?;
+    Ok(UpdateCheckSettings { interval_hours, notify })
+}
+
+pub fn set_check_settings(conn: &Connection, settings: &UpdateCheckSettings) -> Result<(), BrainboxError> {
+    settings::set_value(conn, CHECK_INTERVAL_KEY, settings.interval_hours)?;
+    settings::set_bool(conn, NOTIFY_KEY, settings.notify)?;
+    Ok(())
+}
+
+/// Whether a scheduled check is due. The last check time is stored so the interval survives restarts.
+pub fn is_check_due(conn: &Connection) -> Result<bool, BrainboxError> {
+    let settings = get_check_settings(conn)?;
+    if settings.interval_hours == 0 {
+        return Ok(false);
+    }
+    let last_checked = settings::get(conn, LAST_CHECKED_KEY)?
+        .and_then(|v| chrono::DateTime::parse_from_rfc3339(&v).ok())
+        .map(|t| t.with_timezone(&chrono::Utc));
+    Ok(match last_checked {
+        Some(last) => chrono::Utc::now() - last >= chrono::Duration::hours(settings.interval_hours as i64),
+        None => true,
+    })
+}
+
+pub fn mark_checked(conn: &Connection) -> Result<(), BrainboxError> {
+    settings::set(conn, LAST_CHECKED_KEY, &chrono::Utc::now().to_rfc3339())?;
+    Ok(())
+}
+
+/// Record that the user was told about `version`; returns false if they already were
+pub fn mark_notified(conn: &Connection, version: &str) -> Result<bool, BrainboxError> {
+    if settings::get(conn, NOTIFIED_VERSION_KEY)?.as_deref() == Some(version) {
+        return Ok(false);
+    }
+    settings::set(conn, NOTIFIED_VERSION_KEY, version)?;
+    Ok(true)
+}
+
+const STAGED_UPDATE_KEY: &str = "update_staged_path";
+const PREVIOUS_VERSION_KEY: &str = "update_previous_version";
+
+/// Where the version being replaced is kept so an update can be rolled back
+pub fn backup_dir() -> Option<PathBuf> {
+    Some(crate::data_dir::root()?.join("brainbox").join("updates").join("previous"))
+}
+
+/// Remember a verified download to install when the app exits
+pub fn stage(conn: &Connection, update_path: &str) -> Result<(), BrainboxError> {
+    settings::set(conn, STAGED_UPDATE_KEY, update_path)?;
+    Ok(())
+}
+
+pub fn staged(conn: &Connection) -> Result<Option<String>, BrainboxError> {
+    Ok(settings::get(conn, STAGED_UPDATE_KEY)?.filter(|path| Path::new(path).exists()))
+}
+
+pub fn clear_staged(conn: &Connection) -> Result<(), BrainboxError> {
+    settings::delete(conn, STAGED_UPDATE_KEY)?;
+    Ok(())
+}
+
+/// Version that was running before the last update, if its backup is still around
+pub fn previous_version(conn: &Connection) -> Result<Option<String>, BrainboxError> {
+    Ok(settings::get(conn, PREVIOUS_VERSION_KEY)?)
+}
+
+pub fn set_previous_version(conn: &Connection, version: Option<&str>) -> Result<(), BrainboxError> {
+    match version {
+        Some(version) => settings::set(conn, PREVIOUS_VERSION_KEY, version)?,
+        None => settings::delete(conn, PREVIOUS_VERSION_KEY)?,
+    }
+    Ok(())
+}
+
+/// A release version such as "1.2.0" or "1.3.0-beta.2"
+#[derive(Debug, PartialEq, Eq)]
+struct ReleaseVersion {
+    major: u32,
+    minor: u32,
+    patch: u32,
+    /// Pre-release part after '-', e.g. "beta.2"; None for full releases
+    pre: Option<String>,
+}
+
+impl ReleaseVersion {
+    /// Parse a tag, ignoring a leading 'v' and any "+build" metadata
+    fn parse(version: &str) -> Option<Self> {
+        let v = version.trim().trim_start_matches(['v', 'V']);
+        let v = v.split('+').next().unwrap_or(v);
+        let (core, pre) = match v.split_once('-') {
+            Some((core, pre)) => (core, Some(pre.to_string())),
+            None => (v, None),
+        };
+        let mut parts = core.split('.');
+        let major = parts.next()?.parse().ok()?;
+        let minor = parts.next()?.parse().ok()?;
+        let patch = parts.next()?.parse().ok()?;
+        Some(ReleaseVersion { major, minor, patch, pre })
+    }
+}
+
+/// Compare pre-release identifiers: numeric parts numerically, others as text ("beta.10" > "beta.9")
+fn compare_pre(a: &str, b: &str) -> Ordering {
+    let mut left = a.split('.');
+    let mut right = b.split('.');
+    loop {
+        match (left.next(), right.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(l), Some(r)) => {
+                let ord = match (l.parse::<u64>(), r.parse::<u64>()) {
+                    (Ok(l), Ok(r)) => l.cmp(&r),
+                    (Ok(_), Err(_)) => Ordering::Less,
+                    (Err(_), Ok(_)) => Ordering::Greater,
+                    _ => l.cmp(r),
+                };
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+            }
+        }
+    }
+}
+
+impl Ord for ReleaseVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (&self.pre, &other.pre) {
+                (None, None) => Ordering::Equal,
+                // A full release ranks above its own pre-releases
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(a), Some(b)) => compare_pre(a, b),
+            })
+    }
+}
+
+impl PartialOrd for ReleaseVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Compare two versions, returns true if new_version > current_version
+pub fn is_newer_version(current: &str, new_version: &str) -> bool {
+    match (ReleaseVersion::parse(current), ReleaseVersion::parse(new_version)) {
+        (Some(current), Some(new_version)) => new_version > current,
+        _ => false,
+    }
+}
+
+/// Where the signature of a downloaded update is kept, so apply_update can re-check it
+pub fn signature_path(update_path: &Path) -> PathBuf {
+    let mut name = update_path.as_os_str().to_os_string();
+    name.push(SIGNATURE_SUFFIX);
+    PathBuf::from(name)
+}
+
+/// Tauri wraps minisign files in an extra layer of base64; accept both that and the plain text form
+fn unwrap_base64(text: &str) -> String {
+    let text = text.trim();
+    if text.starts_with("untrusted comment:") {
+        return text.to_string();
+    }
+    base64::engine::general_purpose::STANDARD
+        .decode(text)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .unwrap_or_else(|| text.to_string())
+}
+
+fn public_key() -> Result<PublicKey, BrainboxError> {
+    let key = UPDATE_PUBLIC_KEY
+        .filter(|key| !key.trim().is_empty())
+        .ok_or_else(|| BrainboxError::Update("This build has no update signing key".to_string()))?;
+    let key = unwrap_base64(key);
+    // Either a full public key file or just its base64 key line
+    let parsed = if key.starts_with("untrusted comment:") {
+        PublicKey::decode(&key)
+    } else {
+        PublicKey::from_base64(&key)
+    };
+    parsed.map_err(|e| BrainboxError::Update(format!("Invalid update signing key: {}", e)))
+}
+
+/// Check `file` against a minisign signature (as published in the .sig asset)
+pub fn verify_file(file: &Path, signature: &str) -> Result<(), BrainboxError> {
+    let public_key = public_key()?;
+    let signature = Signature::decode(&unwrap_base64(signature))
+        .map_err(|e| BrainboxError::Update(format!("Invalid update signature: {}", e)))?;
+    let mut verifier = public_key
+        .verify_stream(&signature)
+        .map_err(|e| BrainboxError::Update(format!("Update signature rejected: {}", e)))?;
+
+    let mut reader = std::fs::File::open(file)?;
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let read = reader.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        verifier.update(&buf[..read]);
+    }
+    verifier
+        .finalize()
+        .map_err(|_| BrainboxError::Update("Update signature does not match the downloaded file".to_string()))
+}
+
+/// Verify a downloaded update using the signature saved next to it by download_update
+pub fn verify_downloaded(update_path: &Path) -> Result<(), BrainboxError> {
+    let signature = std::fs::read_to_string(signature_path(update_path))
+        .map_err(|_| BrainboxError::Update("Update signature is missing; download the update again".to_string()))?;
+    verify_file(update_path, &signature)
+}
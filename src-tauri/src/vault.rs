@@ -1,13 +1,16 @@
 // vault.rs - Vault management for brainbox
 // Handles creation, encryption, and storage of vaults in SQLite
 
-use rusqlite::{params, Connection, Result};
+use rusqlite::{params, Connection, OptionalExtension, Result};
 use serde::{Deserialize, Serialize};
 use chacha20poly1305::{aead::{Aead, KeyInit}, XChaCha20Poly1305, Key, XNonce};
 use rand::{rngs::OsRng, RngCore};
 use chrono;
+use std::collections::HashMap;
 use uuid::Uuid;
 
+use crate::envelope;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Vault {
     pub id: i64,
@@ -28,6 +31,35 @@ pub struct Vault {
     /// Soft delete timestamp for sync
     #[serde(skip_serializing_if = "Option::is_none")]
     pub deleted_at: Option<String>,
+    /// Accent color for the vault card, as #rrggbb
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// An emoji or the name of a builtin icon
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Position on the vault dashboard; None sorts after ordered vaults, newest first
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort_order: Option<i64>,
+    /// Pinned vaults are listed before all others
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+const VAULT_COLUMNS: &str =
+    "id, name, encrypted_password, created_at, cover_image, has_password, uuid, updated_at, deleted_at, color, icon, description, sort_order, pinned";
+/// Dashboard order: pinned first, then the user's arrangement, then newest
+const VAULT_ORDER: &str = "pinned DESC, sort_order IS NULL, sort_order, created_at DESC";
+/// Longest accepted icon value (a builtin name or an emoji sequence)
+pub const MAX_ICON_LEN: usize = 32;
+pub const MAX_DESCRIPTION_LEN: usize = 2000;
+
+/// Vault colors are stored as #rgb or #rrggbb
+pub fn is_valid_color(color: &str) -> bool {
+    color
+        .strip_prefix('#')
+        .is_some_and(|hex| (hex.len() == 3 || hex.len() == 6) && hex.chars().all(|c| c.is_ascii_hexdigit()))
 }
 
 impl Vault {
@@ -47,6 +79,11 @@ impl Vault {
         let mut has_uuid = false;
         let mut has_updated_at = false;
         let mut has_deleted_at = false;
+        let mut has_color = false;
+        let mut has_icon = false;
+        let mut has_description = false;
+        let mut has_sort_order = false;
+        let mut has_pinned = false;
         let mut stmt = conn.prepare("PRAGMA table_info(vaults)")?;
         let mut rows = stmt.query([])?;
         while let Some(row) = rows.next()? {
@@ -56,6 +93,11 @@ impl Vault {
             if col_name == "uuid" { has_uuid = true; }
             if col_name == "updated_at" { has_updated_at = true; }
             if col_name == "deleted_at" { has_deleted_at = true; }
+            if col_name == "color" { has_color = true; }
+            if col_name == "icon" { has_icon = true; }
+            if col_name == "description" { has_description = true; }
+            if col_name == "sort_order" { has_sort_order = true; }
+            if col_name == "pinned" { has_pinned = true; }
         }
         if !has_cover {
             let _ = conn.execute("ALTER TABLE vaults ADD COLUMN cover_image TEXT", []);
@@ -80,9 +122,45 @@ impl Vault {
         if !has_deleted_at {
             conn.execute("ALTER TABLE vaults ADD COLUMN deleted_at TEXT", [])?;
         }
+        // Card appearance
+        if !has_color {
+            conn.execute("ALTER TABLE vaults ADD COLUMN color TEXT", [])?;
+        }
+        if !has_icon {
+            conn.execute("ALTER TABLE vaults ADD COLUMN icon TEXT", [])?;
+        }
+        if !has_description {
+            conn.execute("ALTER TABLE vaults ADD COLUMN description TEXT", [])?;
+        }
+        // Dashboard arrangement
+        if !has_sort_order {
+            conn.execute("ALTER TABLE vaults ADD COLUMN sort_order INTEGER", [])?;
+        }
+        if !has_pinned {
+            conn.execute("ALTER TABLE vaults ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0", [])?;
+        }
         Ok(())
     }
 
+    fn from_row(row: &rusqlite::Row) -> Result<Vault> {
+        Ok(Vault {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            encrypted_password: row.get(2)?,
+            created_at: row.get(3)?,
+            cover_image: row.get(4).ok(),
+            has_password: row.get::<_, i64>(5).unwrap_or(1) != 0, // Default to true for safety
+            uuid: row.get(6).ok(),
+            updated_at: row.get(7).ok(),
+            deleted_at: row.get(8).ok(),
+            color: row.get(9).ok(),
+            icon: row.get(10).ok(),
+            description: row.get(11).ok(),
+            sort_order: row.get(12).ok().flatten(),
+            pinned: row.get::<_, i64>(13).unwrap_or(0) != 0,
+        })
+    }
+
     /// Generate UUIDs for existing vaults that don't have one
     fn migrate_generate_uuids(conn: &Connection) -> Result<()> {
         let mut stmt = conn.prepare("SELECT id FROM vaults WHERE uuid IS NULL")?;
@@ -130,53 +208,26 @@ impl Vault {
             uuid: Some(new_uuid),
             updated_at: Some(now),
             deleted_at: None,
+            color: None,
+            icon: None,
+            description: None,
+            sort_order: None,
+            pinned: false,
         })
     }
 
     /// Fetch all non-deleted vaults from the database
     pub fn list(conn: &Connection) -> Result<Vec<Vault>> {
-        let mut stmt = conn.prepare("SELECT id, name, encrypted_password, created_at, cover_image, has_password, uuid, updated_at, deleted_at FROM vaults WHERE deleted_at IS NULL ORDER BY created_at DESC")?;
-        let vault_iter = stmt.query_map([], |row| {
-            Ok(Vault {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                encrypted_password: row.get(2)?,
-                created_at: row.get(3)?,
-                cover_image: row.get(4).ok(),
-                has_password: row.get::<_, i64>(5).unwrap_or(1) != 0, // Default to true for safety
-                uuid: row.get(6).ok(),
-                updated_at: row.get(7).ok(),
-                deleted_at: row.get(8).ok(),
-            })
-        })?;
-        let mut vaults = Vec::new();
-        for vault in vault_iter {
-            vaults.push(vault?);
-        }
-        Ok(vaults)
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM vaults WHERE deleted_at IS NULL ORDER BY {}", VAULT_COLUMNS, VAULT_ORDER))?;
+        let vaults = stmt.query_map([], Self::from_row)?.collect();
+        vaults
     }
 
     /// Fetch all vaults including soft-deleted ones (for sync)
     pub fn list_all_for_sync(conn: &Connection) -> Result<Vec<Vault>> {
-        let mut stmt = conn.prepare("SELECT id, name, encrypted_password, created_at, cover_image, has_password, uuid, updated_at, deleted_at FROM vaults ORDER BY created_at DESC")?;
-        let vault_iter = stmt.query_map([], |row| {
-            Ok(Vault {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                encrypted_password: row.get(2)?,
-                created_at: row.get(3)?,
-                cover_image: row.get(4).ok(),
-                has_password: row.get::<_, i64>(5).unwrap_or(1) != 0,
-                uuid: row.get(6).ok(),
-                updated_at: row.get(7).ok(),
-                deleted_at: row.get(8).ok(),
-            })
-        })?;
-        let mut vaults = Vec::new();
-        for vault in vault_iter {
-            vaults.push(vault?);
-        }
-        Ok(vaults)
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM vaults ORDER BY {}", VAULT_COLUMNS, VAULT_ORDER))?;
+        let vaults = stmt.query_map([], Self::from_row)?.collect();
+        vaults
     }
 
     /// Soft delete a vault and all its items (marks as deleted rather than removing)
@@ -184,11 +235,13 @@ impl Vault {
         // Ensure tables exist
         Self::create_table(conn)?;
         VaultItem::create_table(conn)?;
+        Collection::create_table(conn)?;
         let now = chrono::Utc::now().to_rfc3339();
         // Start a transaction to keep things consistent
         conn.execute("BEGIN IMMEDIATE", [])?;
         // Soft delete items first
         conn.execute("UPDATE vault_items SET deleted_at = ?1 WHERE vault_id = ?2 AND deleted_at IS NULL", params![now, vault_id])?;
+        conn.execute("UPDATE collections SET deleted_at = ?1 WHERE vault_id = ?2 AND deleted_at IS NULL", params![now, vault_id])?;
         // Then soft delete the vault
         conn.execute("UPDATE vaults SET deleted_at = ?1, updated_at = ?2 WHERE id = ?3", params![now, now, vault_id])?;
         conn.execute("COMMIT", [])?;
@@ -199,8 +252,11 @@ impl Vault {
     pub fn hard_delete(conn: &Connection, vault_id: i64) -> Result<()> {
         Self::create_table(conn)?;
         VaultItem::create_table(conn)?;
+        Collection::create_table(conn)?;
         conn.execute("BEGIN IMMEDIATE", [])?;
+        crate::reminders::Reminder::create_table(conn)?;
         conn.execute("DELETE FROM vault_items WHERE vault_id = ?1", [vault_id])?;
+        conn.execute("DELETE FROM collections WHERE vault_id = ?1", [vault_id])?;
         conn.execute("DELETE FROM vaults WHERE id = ?1", [vault_id])?;
         conn.execute("COMMIT", [])?;
         Ok(())
@@ -232,46 +288,64 @@ impl Vault {
         Ok(())
     }
 
+    /// Set the card color, or clear it with None (validate with `is_valid_color` first)
+    pub fn update_color(conn: &Connection, vault_id: i64, color: Option<&str>) -> Result<()> {
+        Self::update_text_column(conn, vault_id, "color", color)
+    }
+
+    /// Set the card icon (an emoji or a builtin icon name), or clear it with None
+    pub fn update_icon(conn: &Connection, vault_id: i64, icon: Option<&str>) -> Result<()> {
+        Self::update_text_column(conn, vault_id, "icon", icon)
+    }
+
+    pub fn update_description(conn: &Connection, vault_id: i64, description: Option<&str>) -> Result<()> {
+        Self::update_text_column(conn, vault_id, "description", description)
+    }
+
+    /// Arrange the dashboard. The order is a local layout preference, so it doesn't bump updated_at or sync.
+    pub fn update_order(conn: &Connection, ordered_ids: &[i64]) -> Result<()> {
+        Self::create_table(conn)?;
+        conn.execute("BEGIN IMMEDIATE", [])?;
+        for (idx, vault_id) in ordered_ids.iter().enumerate() {
+            if let Err(e) = conn.execute(
+                "UPDATE vaults SET sort_order = ?1 WHERE id = ?2",
+                params![idx as i64, vault_id],
+            ) {
+                let _ = conn.execute("ROLLBACK", []);
+                return Err(e);
+            }
+        }
+        conn.execute("COMMIT", [])?;
+        Ok(())
+    }
+
+    pub fn set_pinned(conn: &Connection, vault_id: i64, pinned: bool) -> Result<()> {
+        Self::create_table(conn)?;
+        conn.execute("UPDATE vaults SET pinned = ?1 WHERE id = ?2", params![pinned, vault_id])?;
+        Ok(())
+    }
+
+    /// `column` is always one of the literals above, never user input
+    fn update_text_column(conn: &Connection, vault_id: i64, column: &str, value: Option<&str>) -> Result<()> {
+        Self::create_table(conn)?;
+        let now = chrono::Utc::now().to_rfc3339();
+        conn.execute(
+            &format!("UPDATE vaults SET {} = ?1, updated_at = ?2 WHERE id = ?3", column),
+            params![value, now, vault_id],
+        )?;
+        Ok(())
+    }
+
     /// Get a vault by its UUID (for sync operations)
     pub fn get_by_uuid(conn: &Connection, uuid: &str) -> Result<Option<Vault>> {
-        let mut stmt = conn.prepare("SELECT id, name, encrypted_password, created_at, cover_image, has_password, uuid, updated_at, deleted_at FROM vaults WHERE uuid = ?1")?;
-        let mut rows = stmt.query([uuid])?;
-        if let Some(row) = rows.next()? {
-            Ok(Some(Vault {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                encrypted_password: row.get(2)?,
-                created_at: row.get(3)?,
-                cover_image: row.get(4).ok(),
-                has_password: row.get::<_, i64>(5).unwrap_or(1) != 0,
-                uuid: row.get(6).ok(),
-                updated_at: row.get(7).ok(),
-                deleted_at: row.get(8).ok(),
-            }))
-        } else {
-            Ok(None)
-        }
+        conn.query_row(&format!("SELECT {} FROM vaults WHERE uuid = ?1", VAULT_COLUMNS), [uuid], Self::from_row)
+            .optional()
     }
 
     /// Get a vault by its ID
     pub fn get_by_id(conn: &Connection, vault_id: i64) -> Result<Option<Vault>> {
-        let mut stmt = conn.prepare("SELECT id, name, encrypted_password, created_at, cover_image, has_password, uuid, updated_at, deleted_at FROM vaults WHERE id = ?1")?;
-        let mut rows = stmt.query([vault_id])?;
-        if let Some(row) = rows.next()? {
-            Ok(Some(Vault {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                encrypted_password: row.get(2)?,
-                created_at: row.get(3)?,
-                cover_image: row.get(4).ok(),
-                has_password: row.get::<_, i64>(5).unwrap_or(1) != 0,
-                uuid: row.get(6).ok(),
-                updated_at: row.get(7).ok(),
-                deleted_at: row.get(8).ok(),
-            }))
-        } else {
-            Ok(None)
-        }
+        conn.query_row(&format!("SELECT {} FROM vaults WHERE id = ?1", VAULT_COLUMNS), [vault_id], Self::from_row)
+            .optional()
     }
 }
 
@@ -300,6 +374,135 @@ pub struct VaultItem {
     /// Soft delete timestamp for sync
     #[serde(skip_serializing_if = "Option::is_none")]
     pub deleted_at: Option<String>,
+    /// Collection (folder) the item lives in; None means the vault root
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub collection_id: Option<i64>,
+    /// One of `ITEM_TYPES`. None for rows created before the column existed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item_type: Option<String>,
+    /// Due date for task items (RFC 3339)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due_at: Option<String>,
+    /// When the task was marked done; None while it is still open
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<String>,
+    /// Plaintext statistics, kept alongside the encrypted content so they can be aggregated without a key.
+    /// None for items whose content hasn't been written since the columns were added.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub word_count: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub char_count: Option<i64>,
+    /// Encrypted excerpt of the content for list views; None until generated
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preview: Option<Vec<u8>>,
+    /// When a URL item was read; None while it is still in the read-later queue
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read_at: Option<String>,
+    /// Wayback Machine snapshot of a URL item, kept as a fallback if the page disappears
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub archive_url: Option<String>,
+    /// Label color for grids (#rgb or #rrggbb), set independently of the cover image
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// An emoji or builtin icon name shown with the item
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    /// Local-only: left out of sync exports and shared snapshots even when its vault syncs
+    #[serde(default)]
+    pub no_sync: bool,
+    /// Logical clock for sync, bumped by a trigger on every local change; see `create_version_trigger`
+    #[serde(default)]
+    pub version: i64,
+    /// `version` when this device and the sync file last agreed on the item
+    #[serde(default)]
+    pub synced_version: i64,
+}
+
+/// Columns selected for every VaultItem query, in the order `VaultItem::from_row` reads them
+const ITEM_COLUMNS: &str = "id, vault_id, title, content, created_at, updated_at, sort_order, image, summary, uuid, deleted_at, collection_id, item_type, due_at, completed_at, word_count, char_count, preview, read_at, archive_url, color, icon, no_sync, version, synced_version";
+
+/// Maximum characters kept in an item preview
+pub const PREVIEW_CHARS: usize = 200;
+
+/// Short single-line excerpt of content for list views
+pub fn make_preview(content: &str) -> String {
+    let collapsed = content.split_whitespace().collect::<Vec<_>>().join(" ");
+    if collapsed.chars().count() <= PREVIEW_CHARS {
+        return collapsed;
+    }
+    let mut preview: String = collapsed.chars().take(PREVIEW_CHARS).collect();
+    preview.push('…');
+    preview
+}
+
+/// Seal item content or a preview under a fresh data key wrapped by the vault's content key
+fn encrypt_bytes(key: &[u8; 32], plaintext: &[u8]) -> Result<Vec<u8>> {
+    envelope::seal(key, plaintext).map_err(|_| rusqlite::Error::ExecuteReturnedResults)
+}
+
+/// Sort orders for paginated item listing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemSort {
+    /// Custom drag-and-drop order (sort_order), newest first for unordered items
+    Manual,
+    Created,
+    Updated,
+    Title,
+}
+
+impl ItemSort {
+    pub fn parse(value: &str) -> Option<ItemSort> {
+        match value {
+            "manual" => Some(ItemSort::Manual),
+            "created" => Some(ItemSort::Created),
+            "updated" => Some(ItemSort::Updated),
+            "title" => Some(ItemSort::Title),
+            _ => None,
+        }
+    }
+
+    /// Dates sort newest first, titles A-Z
+    fn default_descending(self) -> bool {
+        matches!(self, ItemSort::Created | ItemSort::Updated)
+    }
+}
+
+/// Words per minute used for reading time estimates
+pub const READING_WPM: i64 = 200;
+
+/// (word count, character count) of plain text
+pub fn text_stats(content: &str) -> (i64, i64) {
+    (content.split_whitespace().count() as i64, content.chars().count() as i64)
+}
+
+/// Hash of an item's plaintext for spotting exact duplicates. It is keyed by the vault's content key so the stored
+/// value says nothing about the content to someone without the key; hashes only compare within one vault.
+pub fn content_hash(key: &[u8; 32], content: &str) -> String {
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+    let mut hash_key = <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC accepts any key length");
+    hash_key.update(b"brainbox content hash v1");
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&hash_key.finalize().into_bytes())
+        .expect("HMAC accepts any key length");
+    mac.update(content.trim().as_bytes());
+    mac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Estimated minutes to read `word_count` words, rounded up (0 for empty content)
+pub fn reading_time_minutes(word_count: i64) -> i64 {
+    (word_count + READING_WPM - 1) / READING_WPM
+}
+
+/// First-class item types
+pub const ITEM_TYPES: &[&str] = &["note", "url", "image", "file", "task"];
+
+pub fn is_valid_item_type(item_type: &str) -> bool {
+    ITEM_TYPES.contains(&item_type)
+}
+
+/// Best-effort type for content that wasn't given one explicitly
+pub fn infer_item_type(content: &str) -> &'static str {
+    if content.starts_with("http://") || content.starts_with("https://") { "url" } else { "note" }
 }
 
 impl VaultItem {
@@ -322,6 +525,22 @@ impl VaultItem {
         let mut has_summary = false;
         let mut has_uuid = false;
         let mut has_deleted_at = false;
+        let mut has_collection_id = false;
+        let mut has_item_type = false;
+        let mut has_due_at = false;
+        let mut has_completed_at = false;
+        let mut has_due_notified_at = false;
+        let mut has_word_count = false;
+        let mut has_char_count = false;
+        let mut has_preview = false;
+        let mut has_content_hash = false;
+        let mut has_read_at = false;
+        let mut has_archive_url = false;
+        let mut has_color = false;
+        let mut has_icon = false;
+        let mut has_no_sync = false;
+        let mut has_version = false;
+        let mut has_synced_version = false;
         let mut stmt = conn.prepare("PRAGMA table_info(vault_items)")?;
         let mut rows = stmt.query([])?;
         while let Some(row) = rows.next()? {
@@ -331,6 +550,22 @@ impl VaultItem {
             if col_name == "summary" { has_summary = true; }
             if col_name == "uuid" { has_uuid = true; }
             if col_name == "deleted_at" { has_deleted_at = true; }
+            if col_name == "collection_id" { has_collection_id = true; }
+            if col_name == "item_type" { has_item_type = true; }
+            if col_name == "due_at" { has_due_at = true; }
+            if col_name == "completed_at" { has_completed_at = true; }
+            if col_name == "due_notified_at" { has_due_notified_at = true; }
+            if col_name == "word_count" { has_word_count = true; }
+            if col_name == "char_count" { has_char_count = true; }
+            if col_name == "preview" { has_preview = true; }
+            if col_name == "content_hash" { has_content_hash = true; }
+            if col_name == "read_at" { has_read_at = true; }
+            if col_name == "archive_url" { has_archive_url = true; }
+            if col_name == "color" { has_color = true; }
+            if col_name == "icon" { has_icon = true; }
+            if col_name == "no_sync" { has_no_sync = true; }
+            if col_name == "version" { has_version = true; }
+            if col_name == "synced_version" { has_synced_version = true; }
         }
         if !has_sort_order {
             let _ = conn.execute("ALTER TABLE vault_items ADD COLUMN sort_order INTEGER", []);
@@ -352,9 +587,134 @@ impl VaultItem {
         if !has_deleted_at {
             let _ = conn.execute("ALTER TABLE vault_items ADD COLUMN deleted_at TEXT", []);
         }
+        if !has_collection_id {
+            let _ = conn.execute("ALTER TABLE vault_items ADD COLUMN collection_id INTEGER REFERENCES collections(id)", []);
+        }
+        if !has_item_type {
+            // Existing content is encrypted, so the type is inferred on read rather than backfilled here
+            let _ = conn.execute("ALTER TABLE vault_items ADD COLUMN item_type TEXT", []);
+        }
+        // Task columns
+        if !has_due_at {
+            let _ = conn.execute("ALTER TABLE vault_items ADD COLUMN due_at TEXT", []);
+        }
+        if !has_completed_at {
+            let _ = conn.execute("ALTER TABLE vault_items ADD COLUMN completed_at TEXT", []);
+        }
+        if !has_due_notified_at {
+            // Local-only bookkeeping so a due notification fires once per due date
+            let _ = conn.execute("ALTER TABLE vault_items ADD COLUMN due_notified_at TEXT", []);
+        }
+        // Text statistics (backfilled lazily when items are next decrypted)
+        if !has_word_count {
+            let _ = conn.execute("ALTER TABLE vault_items ADD COLUMN word_count INTEGER", []);
+        }
+        if !has_char_count {
+            let _ = conn.execute("ALTER TABLE vault_items ADD COLUMN char_count INTEGER", []);
+        }
+        if !has_preview {
+            let _ = conn.execute("ALTER TABLE vault_items ADD COLUMN preview BLOB", []);
+        }
+        // Keyed plaintext hash for duplicate detection (backfilled when duplicates are looked for)
+        if !has_content_hash {
+            let _ = conn.execute("ALTER TABLE vault_items ADD COLUMN content_hash TEXT", []);
+            let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_vault_items_content_hash ON vault_items(vault_id, content_hash)", []);
+        }
+        // Read-later state for URL items
+        if !has_read_at {
+            let _ = conn.execute("ALTER TABLE vault_items ADD COLUMN read_at TEXT", []);
+        }
+        if !has_archive_url {
+            let _ = conn.execute("ALTER TABLE vault_items ADD COLUMN archive_url TEXT", []);
+        }
+        // Visual labels for item cards
+        if !has_color {
+            let _ = conn.execute("ALTER TABLE vault_items ADD COLUMN color TEXT", []);
+        }
+        if !has_icon {
+            let _ = conn.execute("ALTER TABLE vault_items ADD COLUMN icon TEXT", []);
+        }
+        if !has_no_sync {
+            let _ = conn.execute("ALTER TABLE vault_items ADD COLUMN no_sync INTEGER NOT NULL DEFAULT 0", []);
+        }
+        // Logical clocks for sync conflict detection
+        if !has_version {
+            let _ = conn.execute("ALTER TABLE vault_items ADD COLUMN version INTEGER NOT NULL DEFAULT 0", []);
+        }
+        if !has_synced_version {
+            let _ = conn.execute("ALTER TABLE vault_items ADD COLUMN synced_version INTEGER NOT NULL DEFAULT 0", []);
+        }
+        Self::create_version_trigger(conn)?;
         Ok(())
     }
 
+    /// Bump `version` whenever an update moves `updated_at` or `deleted_at`, so every write path counts as a local
+    /// change without having to know about versions. An update that sets `version` itself, as sync does when it
+    /// takes a remote copy, is left alone.
+    fn create_version_trigger(conn: &Connection) -> Result<()> {
+        conn.execute(
+            "CREATE TRIGGER IF NOT EXISTS vault_items_bump_version
+             AFTER UPDATE OF updated_at, deleted_at ON vault_items
+             WHEN NEW.version = OLD.version
+                AND (NEW.updated_at IS NOT OLD.updated_at OR NEW.deleted_at IS NOT OLD.deleted_at)
+             BEGIN
+                UPDATE vault_items SET version = OLD.version + 1 WHERE id = NEW.id;
+             END",
+            [],
+        )?;
+        Ok(())
+    }
+
+    /// Record the versions a sync export just sent
+    pub fn mark_synced(conn: &Connection, versions: &[(i64, i64)]) -> Result<()> {
+        conn.execute("BEGIN IMMEDIATE", [])?;
+        let marked = (|| {
+            let mut stmt = conn.prepare("UPDATE vault_items SET synced_version = ?2 WHERE id = ?1")?;
+            for (item_id, version) in versions {
+                stmt.execute(params![item_id, version])?;
+            }
+            Ok(())
+        })();
+        match marked {
+            Ok(()) => conn.execute("COMMIT", []).map(|_| ()),
+            Err(e) => {
+                let _ = conn.execute("ROLLBACK", []);
+                Err(e)
+            }
+        }
+    }
+
+    /// Build a VaultItem from a row selected with `ITEM_COLUMNS`
+    fn from_row(row: &rusqlite::Row) -> Result<VaultItem> {
+        Ok(VaultItem {
+            id: row.get(0)?,
+            vault_id: row.get(1)?,
+            title: row.get(2)?,
+            content: row.get(3)?,
+            created_at: row.get(4)?,
+            updated_at: row.get(5)?,
+            sort_order: row.get(6).ok(),
+            image: row.get(7).ok(),
+            summary: row.get(8).ok(),
+            uuid: row.get(9).ok(),
+            deleted_at: row.get(10).ok(),
+            collection_id: row.get(11).ok(),
+            item_type: row.get(12).ok(),
+            due_at: row.get(13).ok(),
+            completed_at: row.get(14).ok(),
+            word_count: row.get(15).ok(),
+            char_count: row.get(16).ok(),
+            preview: row.get(17).ok(),
+            read_at: row.get(18).ok(),
+            archive_url: row.get(19).ok(),
+            color: row.get(20).ok(),
+            icon: row.get(21).ok(),
+            no_sync: row.get::<_, i64>(22).unwrap_or(0) != 0,
+            version: row.get(23).unwrap_or(0),
+            synced_version: row.get(24).unwrap_or(0),
+        })
+    }
+
     /// Generate UUIDs for existing items that don't have one
     fn migrate_generate_uuids(conn: &Connection) -> Result<()> {
         let mut stmt = conn.prepare("SELECT id FROM vault_items WHERE uuid IS NULL")?;
@@ -373,25 +733,17 @@ impl VaultItem {
         vault_id: i64,
         title: &str,
         content: &str,
+        item_type: &str,
         key: &[u8; 32],
     ) -> Result<VaultItem> {
-        use chacha20poly1305::{aead::Aead, XChaCha20Poly1305, Key, XNonce};
-        use rand::{rngs::OsRng, RngCore};
-        let cipher = XChaCha20Poly1305::new(Key::from_slice(key));
-        let mut nonce_bytes = [0u8; 24];
-        let mut rng = OsRng;
-        rng.fill_bytes(&mut nonce_bytes);
-        let nonce = XNonce::from_slice(&nonce_bytes);
-        let ciphertext = cipher
-            .encrypt(nonce, content.as_bytes())
-            .map_err(|_| rusqlite::Error::ExecuteReturnedResults)?;
-        let mut encrypted = nonce_bytes.to_vec();
-        encrypted.extend(ciphertext);
+        let encrypted = encrypt_bytes(key, content.as_bytes())?;
         let now = chrono::Utc::now().to_rfc3339();
         let new_uuid = Uuid::new_v4().to_string();
+        let (word_count, char_count) = text_stats(content);
+        let preview = encrypt_bytes(key, make_preview(content).as_bytes())?;
         conn.execute(
-            "INSERT INTO vault_items (vault_id, title, content, created_at, updated_at, uuid) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            rusqlite::params![vault_id, title, encrypted, now, now, new_uuid],
+            "INSERT INTO vault_items (vault_id, title, content, created_at, updated_at, uuid, item_type, word_count, char_count, preview, content_hash) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            rusqlite::params![vault_id, title, encrypted, now, now, new_uuid, item_type, word_count, char_count, preview, content_hash(key, content)],
         )?;
         let id = conn.last_insert_rowid();
         // Also update the vault's updated_at timestamp
@@ -411,31 +763,60 @@ impl VaultItem {
             sort_order: None,
             uuid: Some(new_uuid),
             deleted_at: None,
+            collection_id: None,
+            item_type: Some(item_type.to_string()),
+            due_at: None,
+            completed_at: None,
+            word_count: Some(word_count),
+            char_count: Some(char_count),
+            preview: Some(preview),
+            read_at: None,
+            archive_url: None,
+            color: None,
+            icon: None,
+            no_sync: false,
+            version: 0,
+            synced_version: 0,
         })
     }
 
     /// List non-deleted items in a vault
     pub fn list_by_vault(conn: &Connection, vault_id: i64) -> Result<Vec<VaultItem>> {
-        let mut stmt = conn.prepare(
-            "SELECT id, vault_id, title, content, created_at, updated_at, sort_order, image, summary, uuid, deleted_at \
-             FROM vault_items WHERE vault_id = ?1 AND deleted_at IS NULL \
-             ORDER BY CASE WHEN sort_order IS NULL THEN 1 ELSE 0 END, sort_order ASC, created_at DESC"
-        )?;
-        let item_iter = stmt.query_map([vault_id], |row| {
-            Ok(VaultItem {
-                id: row.get(0)?,
-                vault_id: row.get(1)?,
-                title: row.get(2)?,
-                content: row.get(3)?,
-                created_at: row.get(4)?,
-                updated_at: row.get(5)?,
-                sort_order: row.get(6).ok(),
-                image: row.get(7).ok(),
-                summary: row.get(8).ok(),
-                uuid: row.get(9).ok(),
-                deleted_at: row.get(10).ok(),
-            })
-        })?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM vault_items WHERE vault_id = ?1 AND deleted_at IS NULL \
+             ORDER BY CASE WHEN sort_order IS NULL THEN 1 ELSE 0 END, sort_order ASC, created_at DESC",
+            ITEM_COLUMNS
+        ))?;
+        let item_iter = stmt.query_map([vault_id], Self::from_row)?;
+        let mut items = Vec::new();
+        for item in item_iter {
+            items.push(item?);
+        }
+        Ok(items)
+    }
+
+    /// One page of non-deleted items in a vault. `limit` None returns everything after `offset`.
+    pub fn list_page_by_vault(
+        conn: &Connection,
+        vault_id: i64,
+        sort: ItemSort,
+        descending: Option<bool>,
+        offset: i64,
+        limit: Option<i64>,
+    ) -> Result<Vec<VaultItem>> {
+        let dir = if descending.unwrap_or(sort.default_descending()) { "DESC" } else { "ASC" };
+        let order = match sort {
+            ItemSort::Manual => "CASE WHEN sort_order IS NULL THEN 1 ELSE 0 END, sort_order ASC, created_at DESC".to_string(),
+            ItemSort::Created => format!("created_at {}, id {}", dir, dir),
+            ItemSort::Updated => format!("updated_at {}, id {}", dir, dir),
+            ItemSort::Title => format!("title COLLATE NOCASE {}, id {}", dir, dir),
+        };
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM vault_items WHERE vault_id = ?1 AND deleted_at IS NULL ORDER BY {} LIMIT ?2 OFFSET ?3",
+            ITEM_COLUMNS, order
+        ))?;
+        // SQLite treats a negative LIMIT as "no limit"
+        let item_iter = stmt.query_map(params![vault_id, limit.unwrap_or(-1), offset.max(0)], Self::from_row)?;
         let mut items = Vec::new();
         for item in item_iter {
             items.push(item?);
@@ -443,28 +824,31 @@ impl VaultItem {
         Ok(items)
     }
 
-    /// List all items in a vault including soft-deleted ones (for sync)
+    pub fn count_by_vault(conn: &Connection, vault_id: i64) -> Result<i64> {
+        conn.query_row(
+            "SELECT COUNT(*) FROM vault_items WHERE vault_id = ?1 AND deleted_at IS NULL",
+            [vault_id],
+            |row| row.get(0),
+        )
+    }
+
+    /// List all items in a vault including soft-deleted ones, local-only items too
+    pub fn list_all_by_vault(conn: &Connection, vault_id: i64) -> Result<Vec<VaultItem>> {
+        Self::list_all_matching(conn, vault_id, "")
+    }
+
+    /// List all items in a vault including soft-deleted ones (for sync), leaving out local-only items
     pub fn list_all_by_vault_for_sync(conn: &Connection, vault_id: i64) -> Result<Vec<VaultItem>> {
-        let mut stmt = conn.prepare(
-            "SELECT id, vault_id, title, content, created_at, updated_at, sort_order, image, summary, uuid, deleted_at \
-             FROM vault_items WHERE vault_id = ?1 \
-             ORDER BY CASE WHEN sort_order IS NULL THEN 1 ELSE 0 END, sort_order ASC, created_at DESC"
-        )?;
-        let item_iter = stmt.query_map([vault_id], |row| {
-            Ok(VaultItem {
-                id: row.get(0)?,
-                vault_id: row.get(1)?,
-                title: row.get(2)?,
-                content: row.get(3)?,
-                created_at: row.get(4)?,
-                updated_at: row.get(5)?,
-                sort_order: row.get(6).ok(),
-                image: row.get(7).ok(),
-                summary: row.get(8).ok(),
-                uuid: row.get(9).ok(),
-                deleted_at: row.get(10).ok(),
-            })
-        })?;
+        Self::list_all_matching(conn, vault_id, "AND no_sync = 0")
+    }
+
+    fn list_all_matching(conn: &Connection, vault_id: i64, filter: &str) -> Result<Vec<VaultItem>> {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM vault_items WHERE vault_id = ?1 {} \
+             ORDER BY CASE WHEN sort_order IS NULL THEN 1 ELSE 0 END, sort_order ASC, created_at DESC",
+            ITEM_COLUMNS, filter
+        ))?;
+        let item_iter = stmt.query_map([vault_id], Self::from_row)?;
         let mut items = Vec::new();
         for item in item_iter {
             items.push(item?);
@@ -490,8 +874,9 @@ impl VaultItem {
         Ok(affected)
     }
 
-    /// Hard delete an item (permanent removal, used for purging)
+    /// Hard delete an item (permanent removal, used for purging); its reminders go with it
     pub fn hard_delete(conn: &Connection, item_id: i64) -> Result<usize> {
+        crate::reminders::Reminder::create_table(conn)?;
         let affected = conn.execute("DELETE FROM vault_items WHERE id = ?1", [item_id])?;
         Ok(affected)
     }
@@ -552,26 +937,17 @@ impl VaultItem {
     }
 
     pub fn update_content(conn: &Connection, item_id: i64, content: &str, key: &[u8; 32]) -> Result<()> {
-        use chacha20poly1305::{aead::Aead, XChaCha20Poly1305, Key, XNonce};
-        use rand::{rngs::OsRng, RngCore};
-        let cipher = XChaCha20Poly1305::new(Key::from_slice(key));
-        let mut nonce_bytes = [0u8; 24];
-        let mut rng = OsRng;
-        rng.fill_bytes(&mut nonce_bytes);
-        let nonce = XNonce::from_slice(&nonce_bytes);
-        let ciphertext = cipher
-            .encrypt(nonce, content.as_bytes())
-            .map_err(|_| rusqlite::Error::ExecuteReturnedResults)?;
-        let mut encrypted = nonce_bytes.to_vec();
-        encrypted.extend(ciphertext);
+        let encrypted = encrypt_bytes(key, content.as_bytes())?;
         let now = chrono::Utc::now().to_rfc3339();
         // Get vault_id to update its updated_at
         let vault_id: Option<i64> = conn
             .query_row("SELECT vault_id FROM vault_items WHERE id = ?1", [item_id], |row| row.get(0))
             .ok();
+        let (word_count, char_count) = text_stats(content);
+        let preview = encrypt_bytes(key, make_preview(content).as_bytes())?;
         conn.execute(
-            "UPDATE vault_items SET content = ?1, updated_at = ?2 WHERE id = ?3",
-            rusqlite::params![encrypted, now, item_id],
+            "UPDATE vault_items SET content = ?1, updated_at = ?2, word_count = ?3, char_count = ?4, preview = ?5, content_hash = ?6 WHERE id = ?7",
+            rusqlite::params![encrypted, now, word_count, char_count, preview, content_hash(key, content), item_id],
         )?;
         if let Some(vid) = vault_id {
             conn.execute("UPDATE vaults SET updated_at = ?1 WHERE id = ?2", params![now, vid])?;
@@ -586,7 +962,7 @@ impl VaultItem {
             .query_row("SELECT vault_id FROM vault_items WHERE id = ?1", [item_id], |row| row.get(0))
             .ok();
         conn.execute(
-            "UPDATE vault_items SET vault_id = ?1, sort_order = NULL, updated_at = ?2 WHERE id = ?3",
+            "UPDATE vault_items SET vault_id = ?1, sort_order = NULL, collection_id = NULL, updated_at = ?2 WHERE id = ?3",
             rusqlite::params![target_vault_id, now, item_id],
         )?;
         // Update both source and target vault's updated_at
@@ -597,6 +973,263 @@ impl VaultItem {
         Ok(())
     }
 
+    pub fn update_item_type(conn: &Connection, item_id: i64, item_type: &str) -> Result<()> {
+        Self::create_table(conn)?;
+        let now = chrono::Utc::now().to_rfc3339();
+        let vault_id: Option<i64> = conn
+            .query_row("SELECT vault_id FROM vault_items WHERE id = ?1", [item_id], |row| row.get(0))
+            .ok();
+        conn.execute(
+            "UPDATE vault_items SET item_type = ?1, updated_at = ?2 WHERE id = ?3",
+            params![item_type, now, item_id],
+        )?;
+        if let Some(vid) = vault_id {
+            conn.execute("UPDATE vaults SET updated_at = ?1 WHERE id = ?2", params![now, vid])?;
+        }
+        Ok(())
+    }
+
+    /// Store text statistics computed from decrypted content (backfill; does not touch updated_at)
+    pub fn set_text_stats(conn: &Connection, item_id: i64, word_count: i64, char_count: i64) -> Result<()> {
+        conn.execute(
+            "UPDATE vault_items SET word_count = ?1, char_count = ?2 WHERE id = ?3",
+            params![word_count, char_count, item_id],
+        )?;
+        Ok(())
+    }
+
+    /// Store a backfilled content hash; does not touch updated_at
+    pub fn set_content_hash(conn: &Connection, item_id: i64, hash: &str) -> Result<()> {
+        conn.execute("UPDATE vault_items SET content_hash = ?1 WHERE id = ?2", params![hash, item_id])?;
+        Ok(())
+    }
+
+    /// Store a freshly generated preview (backfill; does not touch updated_at)
+    pub fn set_preview(conn: &Connection, item_id: i64, content: &str, key: &[u8; 32]) -> Result<()> {
+        let preview = encrypt_bytes(key, make_preview(content).as_bytes())?;
+        conn.execute("UPDATE vault_items SET preview = ?1 WHERE id = ?2", params![preview, item_id])?;
+        Ok(())
+    }
+
+    /// Aggregate writing statistics for a vault, optionally limited to items updated since `since`
+    pub fn writing_stats(conn: &Connection, vault_id: i64, since: Option<&str>) -> Result<WritingStats> {
+        Self::create_table(conn)?;
+        conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(word_count), 0), COALESCE(SUM(char_count), 0), \
+             COALESCE(SUM(CASE WHEN ?2 IS NULL OR created_at >= ?2 THEN 1 ELSE 0 END), 0), \
+             COALESCE(SUM(CASE WHEN word_count IS NULL THEN 1 ELSE 0 END), 0) \
+             FROM vault_items WHERE vault_id = ?1 AND deleted_at IS NULL AND (?2 IS NULL OR updated_at >= ?2)",
+            params![vault_id, since],
+            |row| {
+                let total_words: i64 = row.get(1)?;
+                Ok(WritingStats {
+                    item_count: row.get(0)?,
+                    total_words,
+                    total_chars: row.get(2)?,
+                    reading_time_minutes: reading_time_minutes(total_words),
+                    items_created: row.get(3)?,
+                    items_without_stats: row.get(4)?,
+                })
+            },
+        )
+    }
+
+    /// Set or clear an item's due date. Changing it re-arms the due notification.
+    pub fn set_due_date(conn: &Connection, item_id: i64, due_at: Option<&str>) -> Result<()> {
+        Self::create_table(conn)?;
+        let now = chrono::Utc::now().to_rfc3339();
+        let vault_id: Option<i64> = conn
+            .query_row("SELECT vault_id FROM vault_items WHERE id = ?1", [item_id], |row| row.get(0))
+            .ok();
+        conn.execute(
+            "UPDATE vault_items SET due_at = ?1, due_notified_at = NULL, updated_at = ?2 WHERE id = ?3",
+            params![due_at, now, item_id],
+        )?;
+        if let Some(vid) = vault_id {
+            conn.execute("UPDATE vaults SET updated_at = ?1 WHERE id = ?2", params![now, vid])?;
+        }
+        Ok(())
+    }
+
+    /// Mark a task done (stamps completed_at) or reopen it
+    pub fn set_completed(conn: &Connection, item_id: i64, completed: bool) -> Result<()> {
+        Self::create_table(conn)?;
+        let now = chrono::Utc::now().to_rfc3339();
+        let vault_id: Option<i64> = conn
+            .query_row("SELECT vault_id FROM vault_items WHERE id = ?1", [item_id], |row| row.get(0))
+            .ok();
+        let completed_at = if completed { Some(now.as_str()) } else { None };
+        conn.execute(
+            "UPDATE vault_items SET completed_at = ?1, updated_at = ?2 WHERE id = ?3",
+            params![completed_at, now, item_id],
+        )?;
+        if let Some(vid) = vault_id {
+            conn.execute("UPDATE vaults SET updated_at = ?1 WHERE id = ?2", params![now, vid])?;
+        }
+        Ok(())
+    }
+
+    /// Mark a URL item read (stamps read_at) or put it back in the read-later queue
+    pub fn set_read(conn: &Connection, item_id: i64, read: bool) -> Result<()> {
+        Self::create_table(conn)?;
+        let now = chrono::Utc::now().to_rfc3339();
+        let vault_id: Option<i64> = conn
+            .query_row("SELECT vault_id FROM vault_items WHERE id = ?1", [item_id], |row| row.get(0))
+            .ok();
+        let read_at = if read { Some(now.as_str()) } else { None };
+        conn.execute(
+            "UPDATE vault_items SET read_at = ?1, updated_at = ?2 WHERE id = ?3",
+            params![read_at, now, item_id],
+        )?;
+        if let Some(vid) = vault_id {
+            conn.execute("UPDATE vaults SET updated_at = ?1 WHERE id = ?2", params![now, vid])?;
+        }
+        Ok(())
+    }
+
+    /// Re-date an item, e.g. to when an imported photo was taken
+    pub fn set_created_at(conn: &Connection, item_id: i64, created_at: &str) -> Result<()> {
+        Self::create_table(conn)?;
+        conn.execute("UPDATE vault_items SET created_at = ?1 WHERE id = ?2", params![created_at, item_id])?;
+        Ok(())
+    }
+
+    /// Record (or clear) an item's Wayback Machine snapshot
+    pub fn set_archive_url(conn: &Connection, item_id: i64, archive_url: Option<&str>) -> Result<()> {
+        Self::create_table(conn)?;
+        let now = chrono::Utc::now().to_rfc3339();
+        conn.execute(
+            "UPDATE vault_items SET archive_url = ?1, updated_at = ?2 WHERE id = ?3",
+            params![archive_url, now, item_id],
+        )?;
+        Ok(())
+    }
+
+    /// Set an item's label color (#rgb or #rrggbb), or clear it with None
+    pub fn set_color(conn: &Connection, item_id: i64, color: Option<&str>) -> Result<()> {
+        Self::set_text_column(conn, item_id, "color", color)
+    }
+
+    /// Set an item's icon (an emoji or a builtin icon name), or clear it with None
+    pub fn set_icon(conn: &Connection, item_id: i64, icon: Option<&str>) -> Result<()> {
+        Self::set_text_column(conn, item_id, "icon", icon)
+    }
+
+    /// Keep an item local-only, or let it sync with its vault again. Clearing the flag bumps `updated_at` so
+    /// the item is sent at the next sync.
+    pub fn set_no_sync(conn: &Connection, item_id: i64, no_sync: bool) -> Result<()> {
+        Self::create_table(conn)?;
+        let now = chrono::Utc::now().to_rfc3339();
+        conn.execute(
+            "UPDATE vault_items SET no_sync = ?1, updated_at = CASE WHEN ?1 THEN updated_at ELSE ?2 END WHERE id = ?3",
+            params![no_sync, now, item_id],
+        )?;
+        Ok(())
+    }
+
+    /// Non-deleted local-only items in a vault
+    pub fn count_no_sync_by_vault(conn: &Connection, vault_id: i64) -> Result<i64> {
+        conn.query_row(
+            "SELECT COUNT(*) FROM vault_items WHERE vault_id = ?1 AND no_sync = 1 AND deleted_at IS NULL",
+            [vault_id],
+            |row| row.get(0),
+        )
+    }
+
+    fn set_text_column(conn: &Connection, item_id: i64, column: &str, value: Option<&str>) -> Result<()> {
+        Self::create_table(conn)?;
+        let now = chrono::Utc::now().to_rfc3339();
+        conn.execute(
+            &format!("UPDATE vault_items SET {} = ?1, updated_at = ?2 WHERE id = ?3", column),
+            params![value, now, item_id],
+        )?;
+        Ok(())
+    }
+
+    /// Unread URL items in a vault, oldest first so the queue reads in the order things were saved
+    pub fn list_unread(conn: &Connection, vault_id: i64) -> Result<Vec<VaultItem>> {
+        Self::create_table(conn)?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM vault_items WHERE vault_id = ?1 AND item_type = 'url' \
+             AND read_at IS NULL AND deleted_at IS NULL ORDER BY created_at ASC",
+            ITEM_COLUMNS
+        ))?;
+        let rows = stmt.query_map([vault_id], Self::from_row)?;
+        rows.collect()
+    }
+
+    /// Unread URL item count for every vault that has any
+    pub fn unread_counts(conn: &Connection) -> Result<Vec<(i64, i64)>> {
+        Self::create_table(conn)?;
+        let mut stmt = conn.prepare(
+            "SELECT vault_id, COUNT(*) FROM vault_items WHERE item_type = 'url' AND read_at IS NULL \
+             AND deleted_at IS NULL AND vault_id IN (SELECT id FROM vaults WHERE deleted_at IS NULL) GROUP BY vault_id",
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    /// Open tasks with a due date up to `until` (RFC 3339), soonest first. Overdue tasks are included.
+    pub fn list_upcoming_tasks(conn: &Connection, until: &str) -> Result<Vec<VaultItem>> {
+        Self::create_table(conn)?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM vault_items WHERE due_at IS NOT NULL AND due_at <= ?1 \
+             AND completed_at IS NULL AND deleted_at IS NULL ORDER BY due_at ASC",
+            ITEM_COLUMNS
+        ))?;
+        let rows = stmt.query_map([until], Self::from_row)?;
+        rows.collect()
+    }
+
+    /// Most recently updated items across all vaults (excluding deleted items and vaults)
+    pub fn list_recent(conn: &Connection, limit: usize) -> Result<Vec<VaultItem>> {
+        Self::create_table(conn)?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM vault_items WHERE deleted_at IS NULL \
+             AND vault_id IN (SELECT id FROM vaults WHERE deleted_at IS NULL) \
+             ORDER BY updated_at DESC LIMIT ?1",
+            ITEM_COLUMNS
+        ))?;
+        let rows = stmt.query_map([limit as i64], Self::from_row)?;
+        rows.collect()
+    }
+
+    /// Open tasks that became due at or before `now` and haven't been notified yet
+    pub fn list_due_unnotified(conn: &Connection, now: &str) -> Result<Vec<VaultItem>> {
+        Self::create_table(conn)?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM vault_items WHERE due_at IS NOT NULL AND due_at <= ?1 \
+             AND due_notified_at IS NULL AND completed_at IS NULL AND deleted_at IS NULL ORDER BY due_at ASC",
+            ITEM_COLUMNS
+        ))?;
+        let rows = stmt.query_map([now], Self::from_row)?;
+        rows.collect()
+    }
+
+    /// Record that the due notification for an item was shown (does not touch updated_at)
+    pub fn mark_due_notified(conn: &Connection, item_id: i64) -> Result<()> {
+        let now = chrono::Utc::now().to_rfc3339();
+        conn.execute("UPDATE vault_items SET due_notified_at = ?1 WHERE id = ?2", params![now, item_id])?;
+        Ok(())
+    }
+
+    /// Place an item in a collection of its vault (None moves it back to the vault root)
+    pub fn set_collection(conn: &Connection, item_id: i64, collection_id: Option<i64>) -> Result<()> {
+        Self::create_table(conn)?;
+        let now = chrono::Utc::now().to_rfc3339();
+        let vault_id: Option<i64> = conn
+            .query_row("SELECT vault_id FROM vault_items WHERE id = ?1", [item_id], |row| row.get(0))
+            .ok();
+        conn.execute(
+            "UPDATE vault_items SET collection_id = ?1, sort_order = NULL, updated_at = ?2 WHERE id = ?3",
+            params![collection_id, now, item_id],
+        )?;
+        if let Some(vid) = vault_id {
+            conn.execute("UPDATE vaults SET updated_at = ?1 WHERE id = ?2", params![now, vid])?;
+        }
+        Ok(())
+    }
+
     pub fn update_image(conn: &Connection, item_id: i64, image: Option<&str>) -> Result<()> {
         let now = chrono::Utc::now().to_rfc3339();
         // Get vault_id to update its updated_at
@@ -620,24 +1253,10 @@ impl VaultItem {
     }
 
     pub fn get_by_id(conn: &Connection, item_id: i64) -> Result<VaultItem> {
-        let mut stmt = conn.prepare(
-            "SELECT id, vault_id, title, content, created_at, updated_at, sort_order, image, summary, uuid, deleted_at FROM vault_items WHERE id = ?1"
-        )?;
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM vault_items WHERE id = ?1", ITEM_COLUMNS))?;
         let mut rows = stmt.query([item_id])?;
         if let Some(row) = rows.next()? {
-            Ok(VaultItem {
-                id: row.get(0)?,
-                vault_id: row.get(1)?,
-                title: row.get(2)?,
-                content: row.get(3)?,
-                created_at: row.get(4)?,
-                updated_at: row.get(5)?,
-                sort_order: row.get(6).ok(),
-                image: row.get(7).ok(),
-                summary: row.get(8).ok(),
-                uuid: row.get(9).ok(),
-                deleted_at: row.get(10).ok(),
-            })
+            Self::from_row(row)
         } else {
             Err(rusqlite::Error::QueryReturnedNoRows)
         }
@@ -645,82 +1264,364 @@ impl VaultItem {
 
     /// Get an item by its UUID (for sync operations)
     pub fn get_by_uuid(conn: &Connection, uuid: &str) -> Result<Option<VaultItem>> {
-        let mut stmt = conn.prepare(
-            "SELECT id, vault_id, title, content, created_at, updated_at, sort_order, image, summary, uuid, deleted_at FROM vault_items WHERE uuid = ?1"
-        )?;
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM vault_items WHERE uuid = ?1", ITEM_COLUMNS))?;
         let mut rows = stmt.query([uuid])?;
         if let Some(row) = rows.next()? {
-            Ok(Some(VaultItem {
-                id: row.get(0)?,
-                vault_id: row.get(1)?,
-                title: row.get(2)?,
-                content: row.get(3)?,
-                created_at: row.get(4)?,
-                updated_at: row.get(5)?,
-                sort_order: row.get(6).ok(),
-                image: row.get(7).ok(),
-                summary: row.get(8).ok(),
-                uuid: row.get(9).ok(),
-                deleted_at: row.get(10).ok(),
-            }))
+            Ok(Some(Self::from_row(row)?))
         } else {
             Ok(None)
         }
     }
+
+    /// Id of the live item in a vault with this title, ignoring case; the most recently updated when several match
+    pub fn find_by_title(conn: &Connection, vault_id: i64, title: &str) -> Result<Option<i64>> {
+        conn.query_row(
+            "SELECT id FROM vault_items WHERE vault_id = ?1 AND deleted_at IS NULL AND title = ?2 COLLATE NOCASE \
+             ORDER BY updated_at DESC LIMIT 1",
+            params![vault_id, title.trim()],
+            |row| row.get(0),
+        )
+        .optional()
+    }
 }
 
-// --- SyncSettings table and helpers ---
-pub struct SyncSettings;
+/// Result of `VaultItem::writing_stats`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WritingStats {
+    /// Items updated in the period (all items when no start date is given)
+    pub item_count: i64,
+    pub total_words: i64,
+    pub total_chars: i64,
+    pub reading_time_minutes: i64,
+    /// Items created in the period
+    pub items_created: i64,
+    /// Items whose statistics haven't been computed yet (not counted in the totals)
+    pub items_without_stats: i64,
+}
+
+// --- Collection struct and impl ---
+/// A folder inside a vault. Collections nest via `parent_id`; a None parent is the vault root.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Collection {
+    pub id: i64,
+    pub vault_id: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<i64>,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort_order: Option<i64>,
+    /// Unique identifier for sync
+    pub uuid: String,
+    pub created_at: String,
+    pub updated_at: String,
+    /// Soft delete timestamp for sync
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<String>,
+}
 
-impl SyncSettings {
+/// Columns selected for every Collection query, in the order `Collection::from_row` reads them
+const COLLECTION_COLUMNS: &str = "id, vault_id, parent_id, name, sort_order, uuid, created_at, updated_at, deleted_at";
+
+impl Collection {
     pub fn create_table(conn: &Connection) -> Result<()> {
         conn.execute(
-            "CREATE TABLE IF NOT EXISTS sync_settings (
-                key TEXT PRIMARY KEY,
-                value TEXT
+            "CREATE TABLE IF NOT EXISTS collections (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                vault_id INTEGER NOT NULL,
+                parent_id INTEGER,
+                name TEXT NOT NULL,
+                sort_order INTEGER,
+                uuid TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                deleted_at TEXT,
+                FOREIGN KEY(vault_id) REFERENCES vaults(id),
+                FOREIGN KEY(parent_id) REFERENCES collections(id)
             )",
             [],
         )?;
+        conn.execute("CREATE UNIQUE INDEX IF NOT EXISTS idx_collections_uuid ON collections(uuid)", [])?;
+        conn.execute("CREATE INDEX IF NOT EXISTS idx_collections_vault ON collections(vault_id)", [])?;
         Ok(())
     }
 
-    pub fn get(conn: &Connection, key: &str) -> Result<Option<String>> {
+    fn from_row(row: &rusqlite::Row) -> Result<Collection> {
+        Ok(Collection {
+            id: row.get(0)?,
+            vault_id: row.get(1)?,
+            parent_id: row.get(2).ok(),
+            name: row.get(3)?,
+            sort_order: row.get(4).ok(),
+            uuid: row.get(5)?,
+            created_at: row.get(6)?,
+            updated_at: row.get(7)?,
+            deleted_at: row.get(8).ok(),
+        })
+    }
+
+    pub fn insert(conn: &Connection, vault_id: i64, parent_id: Option<i64>, name: &str) -> Result<Collection> {
         Self::create_table(conn)?;
-        let mut stmt = conn.prepare("SELECT value FROM sync_settings WHERE key = ?1")?;
-        let mut rows = stmt.query([key])?;
+        let now = chrono::Utc::now().to_rfc3339();
+        let new_uuid = Uuid::new_v4().to_string();
+        conn.execute(
+            "INSERT INTO collections (vault_id, parent_id, name, uuid, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![vault_id, parent_id, name, new_uuid, now, now],
+        )?;
+        let id = conn.last_insert_rowid();
+        conn.execute("UPDATE vaults SET updated_at = ?1 WHERE id = ?2", params![now, vault_id])?;
+        Ok(Collection {
+            id,
+            vault_id,
+            parent_id,
+            name: name.to_string(),
+            sort_order: None,
+            uuid: new_uuid,
+            created_at: now.clone(),
+            updated_at: now,
+            deleted_at: None,
+        })
+    }
+
+    /// List non-deleted collections in a vault, parents before children
+    pub fn list_by_vault(conn: &Connection, vault_id: i64) -> Result<Vec<Collection>> {
+        Self::create_table(conn)?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM collections WHERE vault_id = ?1 AND deleted_at IS NULL \
+             ORDER BY CASE WHEN parent_id IS NULL THEN 0 ELSE 1 END, parent_id, \
+             CASE WHEN sort_order IS NULL THEN 1 ELSE 0 END, sort_order ASC, name COLLATE NOCASE",
+            COLLECTION_COLUMNS
+        ))?;
+        let rows = stmt.query_map([vault_id], Self::from_row)?;
+        let mut collections = Vec::new();
+        for c in rows {
+            collections.push(c?);
+        }
+        Ok(collections)
+    }
+
+    /// List all collections in a vault including soft-deleted ones (for sync)
+    pub fn list_all_by_vault_for_sync(conn: &Connection, vault_id: i64) -> Result<Vec<Collection>> {
+        Self::create_table(conn)?;
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM collections WHERE vault_id = ?1", COLLECTION_COLUMNS))?;
+        let rows = stmt.query_map([vault_id], Self::from_row)?;
+        let mut collections = Vec::new();
+        for c in rows {
+            collections.push(c?);
+        }
+        Ok(collections)
+    }
+
+    pub fn get_by_id(conn: &Connection, collection_id: i64) -> Result<Option<Collection>> {
+        Self::create_table(conn)?;
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM collections WHERE id = ?1", COLLECTION_COLUMNS))?;
+        let mut rows = stmt.query([collection_id])?;
         if let Some(row) = rows.next()? {
-            Ok(Some(row.get(0)?))
+            Ok(Some(Self::from_row(row)?))
         } else {
             Ok(None)
         }
     }
 
-    pub fn set(conn: &Connection, key: &str, value: &str) -> Result<()> {
+    /// Get a collection by its UUID (for sync operations)
+    pub fn get_by_uuid(conn: &Connection, uuid: &str) -> Result<Option<Collection>> {
         Self::create_table(conn)?;
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM collections WHERE uuid = ?1", COLLECTION_COLUMNS))?;
+        let mut rows = stmt.query([uuid])?;
+        if let Some(row) = rows.next()? {
+            Ok(Some(Self::from_row(row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn rename(conn: &Connection, collection_id: i64, name: &str) -> Result<()> {
+        Self::create_table(conn)?;
+        let now = chrono::Utc::now().to_rfc3339();
         conn.execute(
-            "INSERT OR REPLACE INTO sync_settings (key, value) VALUES (?1, ?2)",
-            params![key, value],
+            "UPDATE collections SET name = ?1, updated_at = ?2 WHERE id = ?3",
+            params![name, now, collection_id],
+        )?;
+        conn.execute(
+            "UPDATE vaults SET updated_at = ?1 WHERE id = (SELECT vault_id FROM collections WHERE id = ?2)",
+            params![now, collection_id],
         )?;
         Ok(())
     }
 
-    pub fn delete(conn: &Connection, key: &str) -> Result<()> {
+    /// Re-parent a collection (None moves it to the vault root). Callers must reject cycles
+    /// with `is_descendant` first.
+    pub fn move_to_parent(conn: &Connection, collection_id: i64, parent_id: Option<i64>) -> Result<()> {
         Self::create_table(conn)?;
-        conn.execute("DELETE FROM sync_settings WHERE key = ?1", [key])?;
+        let now = chrono::Utc::now().to_rfc3339();
+        conn.execute(
+            "UPDATE collections SET parent_id = ?1, sort_order = NULL, updated_at = ?2 WHERE id = ?3",
+            params![parent_id, now, collection_id],
+        )?;
+        conn.execute(
+            "UPDATE vaults SET updated_at = ?1 WHERE id = (SELECT vault_id FROM collections WHERE id = ?2)",
+            params![now, collection_id],
+        )?;
         Ok(())
     }
 
-    /// Get all sync settings as key-value pairs
-    pub fn get_all(conn: &Connection) -> Result<Vec<(String, String)>> {
+    /// True if `candidate_id` is `ancestor_id` itself or nested anywhere below it
+    pub fn is_descendant(conn: &Connection, ancestor_id: i64, candidate_id: i64) -> Result<bool> {
         Self::create_table(conn)?;
-        let mut stmt = conn.prepare("SELECT key, value FROM sync_settings")?;
-        let rows = stmt.query_map([], |row| {
-            Ok((row.get(0)?, row.get(1)?))
-        })?;
-        let mut settings = Vec::new();
-        for row in rows {
-            settings.push(row?);
+        let mut current = Some(candidate_id);
+        // Bounded walk up the parent chain so a corrupted cycle can't loop forever
+        for _ in 0..1024 {
+            match current {
+                Some(id) if id == ancestor_id => return Ok(true),
+                Some(id) => {
+                    current = conn
+                        .query_row("SELECT parent_id FROM collections WHERE id = ?1", [id], |row| row.get::<_, Option<i64>>(0))
+                        .unwrap_or(None);
+                }
+                None => return Ok(false),
+            }
+        }
+        Ok(false)
+    }
+
+    /// Soft delete a collection and everything nested below it. Items inside are moved up
+    /// to the deleted collection's parent so no content disappears with the folder.
+    pub fn delete(conn: &Connection, collection_id: i64) -> Result<()> {
+        Self::create_table(conn)?;
+        VaultItem::create_table(conn)?;
+        let Some(collection) = Self::get_by_id(conn, collection_id)? else {
+            return Ok(());
+        };
+        let now = chrono::Utc::now().to_rfc3339();
+        let subtree = "WITH RECURSIVE tree(id) AS ( \
+                SELECT ?1 UNION ALL \
+                SELECT c.id FROM collections c JOIN tree t ON c.parent_id = t.id WHERE c.deleted_at IS NULL \
+            )";
+        conn.execute("BEGIN IMMEDIATE", [])?;
+        let result = (|| {
+            conn.execute(
+                &format!("{} UPDATE vault_items SET collection_id = ?2, updated_at = ?3 WHERE collection_id IN (SELECT id FROM tree)", subtree),
+                params![collection_id, collection.parent_id, now],
+            )?;
+            conn.execute(
+                &format!("{} UPDATE collections SET deleted_at = ?2, updated_at = ?2 WHERE id IN (SELECT id FROM tree)", subtree),
+                params![collection_id, now],
+            )?;
+            conn.execute("UPDATE vaults SET updated_at = ?1 WHERE id = ?2", params![now, collection.vault_id])?;
+            Ok(())
+        })();
+        match result {
+            Ok(()) => {
+                conn.execute("COMMIT", [])?;
+                Ok(())
+            }
+            Err(e) => {
+                let _ = conn.execute("ROLLBACK", []);
+                Err(e)
+            }
         }
-        Ok(settings)
+    }
+
+    /// Persist manual ordering for sibling collections
+    pub fn update_order(conn: &Connection, vault_id: i64, ordered_ids: &[i64]) -> Result<()> {
+        Self::create_table(conn)?;
+        let now = chrono::Utc::now().to_rfc3339();
+        conn.execute("BEGIN IMMEDIATE", [])?;
+        for (idx, collection_id) in ordered_ids.iter().enumerate() {
+            if let Err(e) = conn.execute(
+                "UPDATE collections SET sort_order = ?1, updated_at = ?2 WHERE id = ?3 AND vault_id = ?4",
+                params![idx as i64, now, collection_id, vault_id],
+            ) {
+                let _ = conn.execute("ROLLBACK", []);
+                return Err(e);
+            }
+        }
+        conn.execute("UPDATE vaults SET updated_at = ?1 WHERE id = ?2", params![now, vault_id])?;
+        conn.execute("COMMIT", [])?;
+        Ok(())
+    }
+}
+
+// --- Per-vault settings ---
+/// Default capture tags for a vault, stored as a JSON array of strings
+pub const CAPTURE_TAGS_SETTING: &str = "capture_tags";
+/// "true" to summarize links captured into a vault
+pub const AUTO_SUMMARIZE_SETTING: &str = "auto_summarize";
+/// Longest accepted setting value
+pub const MAX_SETTING_VALUE_LEN: usize = 10_000;
+
+/// Setting keys are short lowercase identifiers such as "default_sort"
+pub fn is_valid_setting_key(key: &str) -> bool {
+    !key.is_empty() && key.len() <= 64 && key.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '.')
+}
+
+/// Preferences that differ per vault (default sort and view, capture tags, auto-summarize). Each key keeps
+/// when it last changed so sync merges them newest first; clearing a key leaves a NULL row so that syncs too.
+pub struct VaultSettings;
+
+impl VaultSettings {
+    pub fn create_table(conn: &Connection) -> Result<()> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS vault_settings (
+                vault_id INTEGER NOT NULL REFERENCES vaults(id) ON DELETE CASCADE,
+                key TEXT NOT NULL,
+                value TEXT,
+                updated_at TEXT NOT NULL,
+                PRIMARY KEY (vault_id, key)
+            )",
+            [],
+        )?;
+        Ok(())
+    }
+
+    pub fn get(conn: &Connection, vault_id: i64, key: &str) -> Result<Option<String>> {
+        Self::create_table(conn)?;
+        let value = conn
+            .query_row(
+                "SELECT value FROM vault_settings WHERE vault_id = ?1 AND key = ?2",
+                params![vault_id, key],
+                |row| row.get::<_, Option<String>>(0),
+            )
+            .optional()?;
+        Ok(value.flatten())
+    }
+
+    /// Set a setting, or clear it with None
+    pub fn set(conn: &Connection, vault_id: i64, key: &str, value: Option<&str>) -> Result<()> {
+        Self::create_table(conn)?;
+        let now = chrono::Utc::now().to_rfc3339();
+        conn.execute(
+            "INSERT OR REPLACE INTO vault_settings (vault_id, key, value, updated_at) VALUES (?1, ?2, ?3, ?4)",
+            params![vault_id, key, value, now],
+        )?;
+        conn.execute("UPDATE vaults SET updated_at = ?1 WHERE id = ?2", params![now, vault_id])?;
+        Ok(())
+    }
+
+    /// Every set value for a vault
+    pub fn get_all(conn: &Connection, vault_id: i64) -> Result<HashMap<String, String>> {
+        Self::create_table(conn)?;
+        let mut stmt = conn.prepare("SELECT key, value FROM vault_settings WHERE vault_id = ?1 AND value IS NOT NULL")?;
+        let rows = stmt.query_map([vault_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    /// Every key including cleared ones, as (key, value, updated_at), for sync
+    pub fn list_for_sync(conn: &Connection, vault_id: i64) -> Result<Vec<(String, Option<String>, String)>> {
+        Self::create_table(conn)?;
+        let mut stmt = conn.prepare("SELECT key, value, updated_at FROM vault_settings WHERE vault_id = ?1 ORDER BY key")?;
+        let rows = stmt.query_map([vault_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+        rows.collect()
+    }
+
+    /// Apply a setting from another device if it changed after the local one
+    pub fn merge(conn: &Connection, vault_id: i64, key: &str, value: Option<&str>, updated_at: &str) -> Result<()> {
+        Self::create_table(conn)?;
+        conn.execute(
+            "INSERT INTO vault_settings (vault_id, key, value, updated_at) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(vault_id, key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at
+             WHERE excluded.updated_at > vault_settings.updated_at",
+            params![vault_id, key, value, updated_at],
+        )?;
+        Ok(())
     }
 }
@@ -0,0 +1,121 @@
+// wayback.rs - Internet Archive Wayback Machine snapshots for brainbox
+// Captured links rot; saving a snapshot to the Wayback Machine and keeping its URL on the item gives every
+// bookmark a durable fallback. Uses the public Save Page Now and availability endpoints (no account needed).
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::error::BrainboxError;
+use crate::http;
+use crate::vault::VaultItem;
+
+const SAVE_ENDPOINT: &str = "https://web.archive.org/save/";
+const AVAILABILITY_ENDPOINT: &str = "https://archive.org/wayback/available";
+/// Save Page Now crawls the page before answering, which can take well over the usual timeout
+const SAVE_TIMEOUT: Duration = Duration::from_secs(120);
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Serialize, Clone)]
+pub struct WaybackSnapshot {
+    pub url: String,
+    /// Wayback timestamp (YYYYMMDDhhmmss), when known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AvailabilityResponse {
+    #[serde(default)]
+    archived_snapshots: ArchivedSnapshots,
+}
+
+#[derive(Deserialize, Default)]
+struct ArchivedSnapshots {
+    closest: Option<ClosestSnapshot>,
+}
+
+#[derive(Deserialize)]
+struct ClosestSnapshot {
+    #[serde(default)]
+    available: bool,
+    url: String,
+    timestamp: Option<String>,
+}
+
+fn check_url(url: &str) -> Result<String, BrainboxError> {
+    let url = url.trim();
+    match reqwest::Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => Ok(url.to_string()),
+        _ => Err(BrainboxError::InvalidInput(format!("Not an http(s) URL: {}", url))),
+    }
+}
+
+/// Timestamp segment of a snapshot URL like https://web.archive.org/web/20240101120000/https://example.com
+fn snapshot_from_url(snapshot_url: &str) -> Option<WaybackSnapshot> {
+    let rest = snapshot_url.split("/web/").nth(1)?;
+    let timestamp: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+    if timestamp.is_empty() {
+        return None;
+    }
+    Some(WaybackSnapshot { url: snapshot_url.replacen("http://", "https://", 1), timestamp: Some(timestamp) })
+}
+
+/// The most recent existing snapshot of `url`, if the Wayback Machine has one
+pub fn lookup(url: &str) -> Result<Option<WaybackSnapshot>, BrainboxError> {
+    let url = check_url(url)?;
+    let resp = http::send(http::client().get(AVAILABILITY_ENDPOINT).query(&[("url", url.as_str())]).timeout(LOOKUP_TIMEOUT))?;
+    if !resp.status().is_success() {
+        return Err(BrainboxError::Network(format!("Wayback Machine returned status {}", resp.status())));
+    }
+    let body: AvailabilityResponse = resp.json()?;
+    Ok(body.archived_snapshots.closest.filter(|c| c.available).map(|c| WaybackSnapshot {
+        url: c.url.replacen("http://", "https://", 1),
+        timestamp: c.timestamp,
+    }))
+}
+
+/// Ask the Wayback Machine to archive `url` now and return the new snapshot
+pub fn save(url: &str) -> Result<WaybackSnapshot, BrainboxError> {
+    let url = check_url(url)?;
+    let resp = http::send(http::client().get(format!("{}{}", SAVE_ENDPOINT, url)).timeout(SAVE_TIMEOUT))?;
+    let status = resp.status();
+    if status.as_u16() == 429 {
+        return Err(BrainboxError::Network("The Wayback Machine is rate limiting saves; try again in a few minutes".to_string()));
+    }
+    if !status.is_success() {
+        return Err(BrainboxError::Network(format!("Wayback Machine returned status {}", status)));
+    }
+    // The save request redirects to the snapshot; older deployments only name it in Content-Location
+    if let Some(snapshot) = snapshot_from_url(resp.url().as_str()) {
+        return Ok(snapshot);
+    }
+    let location = resp
+        .headers()
+        .get(reqwest::header::CONTENT_LOCATION)
+        .and_then(|v| v.to_str().ok())
+        .map(|path| format!("https://web.archive.org{}", path));
+    if let Some(snapshot) = location.as_deref().and_then(snapshot_from_url) {
+        return Ok(snapshot);
+    }
+    lookup(&url)?.ok_or_else(|| BrainboxError::Network("The Wayback Machine did not return a snapshot".to_string()))
+}
+
+/// Remember a snapshot on an item
+pub fn store(conn: &Connection, item_id: i64, snapshot: &WaybackSnapshot) -> Result<(), BrainboxError> {
+    VaultItem::set_archive_url(conn, item_id, Some(&snapshot.url))?;
+    Ok(())
+}
+
+/// Archive `url` in the background and record the snapshot on `item_id` (used by capture rules)
+pub fn archive_item_in_background(item_id: i64, url: String) {
+    std::thread::spawn(move || {
+        let result = save(&url).and_then(|snapshot| {
+            let conn = crate::db::open()?;
+            store(&conn, item_id, &snapshot)
+        });
+        if let Err(e) = result {
+            tracing::warn!("Failed to archive {} to the Wayback Machine: {}", url, e);
+        }
+    });
+}
@@ -0,0 +1,131 @@
+// zip_writer.rs - Minimal .zip writer for brainbox
+// Enough of the format for the files brainbox hands out: deflated entries with UTF-8 names, no zip64, so an
+// archive stays under 4 GB and 65535 entries. rich_text.rs has the matching reader.
+
+use chrono::{Datelike, Local, Timelike};
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use std::io::{self, Write};
+
+const LOCAL_HEADER: u32 = 0x04034b50;
+const CENTRAL_HEADER: u32 = 0x02014b50;
+const END_OF_CENTRAL_DIR: u32 = 0x06054b50;
+/// 2.0: deflate
+const VERSION: u16 = 20;
+/// Bit 11: names are UTF-8
+const FLAG_UTF8: u16 = 0x0800;
+const METHOD_DEFLATE: u16 = 8;
+
+struct Entry {
+    name: String,
+    crc: u32,
+    compressed: u32,
+    size: u32,
+    offset: u32,
+}
+
+pub struct ZipWriter<W: Write> {
+    out: W,
+    written: u64,
+    entries: Vec<Entry>,
+    /// MS-DOS time and date stamped on every entry
+    time: u16,
+    date: u16,
+}
+
+fn too_large() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, "Archive too large")
+}
+
+impl<W: Write> ZipWriter<W> {
+    pub fn new(out: W) -> Self {
+        let now = Local::now();
+        let time = ((now.hour() << 11) | (now.minute() << 5) | (now.second() / 2)) as u16;
+        let date = (((now.year().clamp(1980, 2107) - 1980) as u32) << 9 | now.month() << 5 | now.day()) as u16;
+        ZipWriter { out, written: 0, entries: Vec::new(), time, date }
+    }
+
+    fn put(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.out.write_all(bytes)?;
+        self.written += bytes.len() as u64;
+        Ok(())
+    }
+
+    fn offset(&self) -> io::Result<u32> {
+        u32::try_from(self.written).map_err(|_| too_large())
+    }
+
+    /// Add a file; `name` uses forward slashes for folders
+    pub fn add(&mut self, name: &str, data: &[u8]) -> io::Result<()> {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data)?;
+        let compressed = encoder.finish()?;
+        let mut crc = crc32fast::Hasher::new();
+        crc.update(data);
+        let entry = Entry {
+            name: name.to_string(),
+            crc: crc.finalize(),
+            compressed: u32::try_from(compressed.len()).map_err(|_| too_large())?,
+            size: u32::try_from(data.len()).map_err(|_| too_large())?,
+            offset: self.offset()?,
+        };
+        if self.entries.len() == u16::MAX as usize || entry.name.len() > u16::MAX as usize {
+            return Err(too_large());
+        }
+
+        let mut header = Vec::with_capacity(30 + entry.name.len());
+        header.extend_from_slice(&LOCAL_HEADER.to_le_bytes());
+        for field in [VERSION, FLAG_UTF8, METHOD_DEFLATE, self.time, self.date] {
+            header.extend_from_slice(&field.to_le_bytes());
+        }
+        for field in [entry.crc, entry.compressed, entry.size] {
+            header.extend_from_slice(&field.to_le_bytes());
+        }
+        header.extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(entry.name.as_bytes());
+        self.put(&header)?;
+        self.put(&compressed)?;
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Write the central directory and return the underlying writer
+    pub fn finish(mut self) -> io::Result<W> {
+        let directory_offset = self.offset()?;
+        let entries = std::mem::take(&mut self.entries);
+        for entry in &entries {
+            let mut header = Vec::with_capacity(46 + entry.name.len());
+            header.extend_from_slice(&CENTRAL_HEADER.to_le_bytes());
+            for field in [VERSION, VERSION, FLAG_UTF8, METHOD_DEFLATE, self.time, self.date] {
+                header.extend_from_slice(&field.to_le_bytes());
+            }
+            for field in [entry.crc, entry.compressed, entry.size] {
+                header.extend_from_slice(&field.to_le_bytes());
+            }
+            // Name length, then no extra field, comment, disk number or internal attributes
+            for field in [entry.name.len() as u16, 0, 0, 0, 0] {
+                header.extend_from_slice(&field.to_le_bytes());
+            }
+            // No external attributes
+            header.extend_from_slice(&0u32.to_le_bytes());
+            header.extend_from_slice(&entry.offset.to_le_bytes());
+            header.extend_from_slice(entry.name.as_bytes());
+            self.put(&header)?;
+        }
+        let directory_size = self.offset()? - directory_offset;
+
+        let mut end = Vec::with_capacity(22);
+        end.extend_from_slice(&END_OF_CENTRAL_DIR.to_le_bytes());
+        let count = entries.len() as u16;
+        for field in [0, 0, count, count] {
+            end.extend_from_slice(&field.to_le_bytes());
+        }
+        end.extend_from_slice(&directory_size.to_le_bytes());
+        end.extend_from_slice(&directory_offset.to_le_bytes());
+        end.extend_from_slice(&0u16.to_le_bytes());
+        self.put(&end)?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
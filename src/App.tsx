@@ -8,7 +8,7 @@ import SearchBar from './components/SearchBar/SearchBar';
 import Settings from './components/Settings/Settings';
 import CreateVaultModal from './components/CreateVaultModal/CreateVaultModal';
 import BrainyChat from './components/BrainyChat';
-import { useState, useEffect } from 'react';
+import { useState, useEffect, useRef } from 'react';
 import { listen } from '@tauri-apps/api/event';
 import { emit } from '@tauri-apps/api/event';
 import { invoke } from '@tauri-apps/api/core';
@@ -45,28 +45,30 @@ import { useToast } from './contexts/ToastContext';
 import { useConfirm } from './contexts/ConfirmContext';
 import { usePrompt } from './contexts/PromptContext';
 import { useSyncManager } from './utils/useSyncManager';
+import { getErrorMessage, isBrainboxError } from './utils/errors';
 
-const getErrorMessage = (err: unknown): string => {
-  if (typeof err === 'string') return err;
-  if (err instanceof Error) return err.message;
-  try { return JSON.stringify(err); } catch { return String(err); }
+// URL of a link item: the whole content, or its first line when captured post text follows it
+const contentUrl = (rawContent: string): string | null => {
+  const firstLine = rawContent.trim().split('\n')[0].trim();
+  return /^https?:\/\/[^\s]+$/.test(firstLine) ? firstLine : null;
 };
 
 // Transform backend vault item to frontend VaultItem
 const transformBackendItem = (item: BackendVaultItem): VaultItem => {
   const rawContent = typeof item.content === 'string' ? item.content : '';
-  const isUrl = /^https?:\/\/[^\s]+$/.test(rawContent.trim());
+  const linkUrl = contentUrl(rawContent);
+  const isUrl = linkUrl !== null;
   const meta: ItemMetadata = {
     item_type: isUrl ? 'url' : 'note',
-    url: isUrl ? rawContent : undefined,
+    url: linkUrl ?? undefined,
     created_at: item.created_at,
     updated_at: item.updated_at,
     ...(item.metadata as Partial<ItemMetadata> || {}),
   };
 
   let cover = item.image ?? undefined;
-  if (!cover && isUrl) {
-    const yt = getYouTubeId(rawContent);
+  if (!cover && linkUrl) {
+    const yt = getYouTubeId(linkUrl);
     if (yt) {
       cover = youtubeThumbnailUrl(yt, 'hq');
       meta.provider = 'youtube';
@@ -211,7 +213,12 @@ function App() {
           return {
             id: idStr,
             title: v.name || '',
-            backgroundImage: v.cover_image || meshGradientForId(idStr, 640, 420),
+            // A vault color replaces the generated gradient when there's no cover
+            backgroundImage: v.cover_image || (v.color ? undefined : meshGradientForId(idStr, 640, 420)),
+            color: v.color ?? undefined,
+            icon: v.icon ?? undefined,
+            description: v.description ?? undefined,
+            pinned: Boolean(v.pinned),
             has_password: v.has_password,
           };
         })
@@ -253,14 +260,12 @@ function App() {
 
         // Prefetch URL metadata for non-YouTube links to enrich previews
         const urlItems = result.filter((item) => {
-          const rawContent = typeof item.content === 'string' ? item.content : '';
-          const isUrl = /^https?:\/\/[^\s]+$/.test(rawContent.trim());
-          const yt = isUrl ? getYouTubeId(rawContent) : null;
-          return isUrl && !yt;
+          const url = contentUrl(typeof item.content === 'string' ? item.content : '');
+          return url !== null && !getYouTubeId(url);
         });
         urlItems.forEach(async (it) => {
           if (cancelled) return;
-          const url = typeof it.content === 'string' ? it.content : '';
+          const url = contentUrl(typeof it.content === 'string' ? it.content : '') ?? '';
           try {
             const meta = await invoke<BackendUrlMetadata>('fetch_url_metadata', { url });
             if (cancelled) return;
@@ -296,6 +301,12 @@ function App() {
           setSelectedVaultId(null);
           return;
         }
+        if (isBrainboxError(err) && err.code === 'vault_locked') {
+          setVaultItems([]);
+          setSelectedVaultId(null);
+          showError(message);
+          return;
+        }
         if (/invalid password/i.test(message) || /decryption failed/i.test(message)) {
           if (selectedVaultId) {
             clearKey(selectedVaultId);
@@ -324,6 +335,15 @@ function App() {
       const isUrl = typeof captureData.content === 'string' &&
                    /^https?:\/\/[^\s]+$/.test(captureData.content.trim());
 
+      // For Twitter/X, Reddit and Hacker News, keep the post and top comments rather than just the link
+      let content = captureData.content;
+      if (isUrl) {
+        try {
+          const site = await invoke<{ text: string } | null>('fetch_site_content', { url: captureData.content.trim() });
+          if (site?.text) content = `${captureData.content.trim()}\n\n${site.text}`;
+        } catch { /* keep the plain link */ }
+      }
+
       // Get vault info for password handling
       const vault = vaults.find(v => v.id === captureData.vaultId);
       const key = await getVaultKey(captureData.vaultId, vault?.title, vault?.has_password);
@@ -331,7 +351,7 @@ function App() {
       const result = await invoke<BackendVaultItem>('add_vault_item', {
         vaultId: Number(captureData.vaultId),
         title: captureData.title,
-        content: captureData.content,
+        content,
         metadata: isUrl ? {
           item_type: 'url',
           url: captureData.content
@@ -344,7 +364,7 @@ function App() {
         addToIndex({
           id: result.id?.toString() || undefined,
           title: result.title,
-          content: captureData.content,
+          content,
           itemType: isUrl ? 'url' : 'note',
           createdAt: new Date(result.created_at),
           updatedAt: new Date(result.updated_at),
@@ -363,13 +383,11 @@ function App() {
 
             // Prefetch metadata for non-YouTube URLs
             const urlItems = items.filter((item) => {
-              const rawContent = typeof item.content === 'string' ? item.content : '';
-              const isUrlContent = /^https?:\/\/[^\s]+$/.test(rawContent.trim());
-              const yt = isUrlContent ? getYouTubeId(rawContent) : null;
-              return isUrlContent && !yt;
+              const url = contentUrl(typeof item.content === 'string' ? item.content : '');
+              return url !== null && !getYouTubeId(url);
             });
             urlItems.forEach(async (it) => {
-              const url = typeof it.content === 'string' ? it.content : '';
+              const url = contentUrl(typeof it.content === 'string' ? it.content : '') ?? '';
               try {
                 const meta = await invoke<BackendUrlMetadata>('fetch_url_metadata', { url });
                 setVaultItems(prev => prev.map(p => {
@@ -401,7 +419,7 @@ function App() {
   const handleSearch = (query: string) => {
     setSearchQuery(query);
     setIsSearching(true);
-    invoke<BackendSearchResult[]>('search', { query, limit: 50 })
+    invoke<BackendSearchResult[]>('search', { query, limit: 50, boostByAccess: true })
       .then((results) => {
         setSearchSelectedItem(null);
         setSearchCards([]);
@@ -445,6 +463,10 @@ function App() {
       .finally(() => setIsSearching(false));
   };
 
+  // Latest handleSearch for event listeners registered once on mount
+  const handleSearchRef = useRef(handleSearch);
+  handleSearchRef.current = handleSearch;
+
   // Handle navigation between views
   const handleNavigation = (view: AppView | undefined) => {
     setCurrentView(view ?? 'vaults');
@@ -468,22 +490,45 @@ function App() {
   }, []);
 
   useEffect(() => {
-    fetchVaults();
-    
-    // Check for updates on app startup (silent check)
-    const checkForUpdatesOnStartup = async () => {
-      try {
-        const result = await invoke('check_for_updates');
-        if (result) {
-          // Update available - users can check manually in settings
-        }
-      } catch (e) {
-        // Silent failure - don't bother the user on startup
-      }
+    // Quick switcher and brainbox:// deep links ask the main window to open an item, a vault or a search
+    const unlisteners: Array<() => void> = [];
+    listen<{ itemId: number; vaultId: number }>('open-item', (event) => {
+      setCurrentView('vaults');
+      setSelectedVaultId(String(event.payload.vaultId));
+      setPendingOpenItemId(String(event.payload.itemId));
+    }).then((fn: () => void) => unlisteners.push(fn));
+    listen<{ vaultId: number }>('open-vault', (event) => {
+      setCurrentView('vaults');
+      setSelectedVaultId(String(event.payload.vaultId));
+    }).then((fn: () => void) => unlisteners.push(fn));
+    listen<{ query: string }>('deep-link-search', (event) => {
+      setCurrentView('search');
+      if (event.payload.query) handleSearchRef.current(event.payload.query);
+    }).then((fn: () => void) => unlisteners.push(fn));
+    return () => {
+      unlisteners.forEach((fn) => fn());
     };
-    
-    // Check for updates after a short delay to not block app startup
-    setTimeout(checkForUpdatesOnStartup, 3000);
+  }, []);
+
+  useEffect(() => {
+    fetchVaults();
+    // Update checks run on a schedule in the backend, which emits `update-available`
+  }, []);
+
+  // Log opened items for the recent list and the search ranking boost
+  const openedItemId = selectedItem?.id ?? searchSelectedItem?.id;
+  useEffect(() => {
+    if (openedItemId === undefined) return;
+    invoke('record_item_access', { itemId: Number(openedItemId) }).catch(() => {});
+  }, [openedItemId]);
+
+  useEffect(() => {
+    // The index is cleared when the search language settings no longer match it; refill it
+    invoke<boolean>('search_index_needs_rebuild').then(async (needsRebuild) => {
+      if (!needsRebuild) return;
+      const { rebuildIndex } = await import('./utils/searchIndexer');
+      await rebuildIndex();
+    }).catch((error) => console.error('Failed to rebuild search index:', error));
   }, []);
 
   useEffect(() => {
@@ -1046,6 +1091,18 @@ function App() {
                       title={vault.title}
                       backgroundImage={vault.backgroundImage}
                       color={vault.color}
+                      icon={vault.icon}
+                      description={vault.description}
+                      pinned={vault.pinned}
+                      onTogglePin={async () => {
+                        try {
+                          await invoke('set_vault_pinned', { vaultId: Number(vault.id), pinned: !vault.pinned });
+                          await fetchVaults();
+                        } catch (e) {
+                          console.error(e);
+                          showError('Failed to update vault.');
+                        }
+                      }}
                       priceTag={vault.priceTag}
                       onClick={() => setSelectedVaultId(vault.id)}
                     onRename={async () => {
@@ -1067,6 +1124,65 @@ function App() {
                           showError('Failed to rename vault.');
                         }
                       }}
+                      onDuplicate={async () => {
+                        const newName = await promptDialog({
+                          title: 'Duplicate vault',
+                          label: 'Name for the copy',
+                          defaultValue: `${vault.title} copy`,
+                          confirmLabel: 'Next'
+                        });
+                        if (newName === null || !newName.trim()) return;
+                        const newPassword = await promptDialog({
+                          title: 'Password for the copy',
+                          message: 'Leave it blank for a vault without a password.',
+                          label: 'New password',
+                          inputType: 'password',
+                          autoComplete: 'new-password',
+                          confirmLabel: 'Duplicate'
+                        });
+                        if (newPassword === null) return;
+                        try {
+                          const key = await getVaultKey(vault.id, vault.title, vault.has_password);
+                          await invoke('duplicate_vault', {
+                            vaultId: Number(vault.id),
+                            key,
+                            newName: newName.trim(),
+                            newPassword
+                          });
+                          await fetchVaults();
+                          try { emit('vaults-changed'); } catch {}
+                          showSuccess('Vault duplicated.');
+                        } catch (e) {
+                          console.error(e);
+                          showError('Failed to duplicate vault.');
+                        }
+                      }}
+                      onBiometricUnlock={vault.has_password ? async () => {
+                        try {
+                          const status = await invoke<{ available: boolean; enabled: boolean }>('get_biometric_status', { vaultId: Number(vault.id) });
+                          if (status.enabled) {
+                            const confirmed = await confirmDialog({
+                              title: 'Turn off biometric unlock?',
+                              message: `"${vault.title}" will ask for its password again.`,
+                              confirmLabel: 'Turn off'
+                            });
+                            if (!confirmed) return;
+                            await invoke('disable_biometric_unlock', { vaultId: Number(vault.id) });
+                            showSuccess('Biometric unlock turned off.');
+                            return;
+                          }
+                          if (!status.available) {
+                            showError('Touch ID or Windows Hello is not available on this device.');
+                            return;
+                          }
+                          const key = await getVaultKey(vault.id, vault.title, vault.has_password);
+                          await invoke('enable_biometric_unlock', { vaultId: Number(vault.id), key });
+                          showSuccess(`Biometric unlock enabled for "${vault.title}".`);
+                        } catch (e) {
+                          console.error(e);
+                          showError(`Failed to set up biometric unlock: ${getErrorMessage(e)}`);
+                        }
+                      } : undefined}
                       onChangeCover={() => setCoverVault({ id: vault.id, title: vault.title })}
                       onChangePassword={() => setChangePasswordVault({ id: Number(vault.id), name: vault.title, has_password: vault.has_password })}
                     onDelete={async () => {
@@ -10,6 +10,7 @@
 import React, { useState, useEffect, useMemo } from 'react';
 import { aiService, ProviderType, PROVIDER_CONFIGS, BrainyUIMode } from '../../utils/ai';
 import styles from './AISettings.module.css';
+import { getErrorMessage } from '../../utils/errors';
 
 export const AISettings: React.FC = () => {
   const [activeProvider, setActiveProvider] = useState<ProviderType>(
@@ -54,7 +55,7 @@ export const AISettings: React.FC = () => {
       const list = await aiService.listModels(activeProvider);
       setModels(list);
     } catch (e) {
-      setError(String(e));
+      setError(getErrorMessage(e));
       setModels([]);
     } finally {
       setLoading(false);
@@ -69,7 +70,7 @@ export const AISettings: React.FC = () => {
       const result = await aiService.generate({ prompt: testPrompt });
       setTestResult(result);
     } catch (e) {
-      setError(String(e));
+      setError(getErrorMessage(e));
     } finally {
       setLoading(false);
     }
@@ -7,15 +7,17 @@
  * - New password with confirmation
  * - Password strength indicator
  * - Progress indicator for large vaults
+ * - Cooldown after too many wrong passwords
  */
 
-import React, { useState, useCallback } from 'react';
+import React, { useState, useCallback, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { ExclamationTriangleIcon } from '@heroicons/react/24/outline';
 import { useVaultPassword } from '../../contexts/VaultPasswordContext';
 import { useToast } from '../../contexts/ToastContext';
 import { deriveKeyFromPassword, keyToArray } from '../../utils/crypto';
 import styles from './ChangePasswordDialog.module.css';
+import { getErrorMessage, isBrainboxError } from '../../utils/errors';
 
 interface Vault {
   id: number;
@@ -47,6 +49,18 @@ function getPasswordStrength(password: string): PasswordStrength {
   return 'strong';
 }
 
+interface LockoutStatus {
+  failed_attempts: number;
+  locked: boolean;
+  retry_after_secs: number;
+}
+
+function formatCooldown(secs: number): string {
+  const minutes = Math.floor(secs / 60);
+  const seconds = secs % 60;
+  return `${minutes}:${String(seconds).padStart(2, '0')}`;
+}
+
 export const ChangePasswordDialog: React.FC<ChangePasswordDialogProps> = ({
   vault,
   onClose,
@@ -63,6 +77,27 @@ export const ChangePasswordDialog: React.FC<ChangePasswordDialogProps> = ({
   const [enablePassword, setEnablePassword] = useState(hasExistingPassword);
   const [isChanging, setIsChanging] = useState(false);
   const [error, setError] = useState('');
+  const [lockedFor, setLockedFor] = useState(0);
+
+  const refreshLockout = useCallback(async () => {
+    try {
+      const status = await invoke<LockoutStatus>('get_vault_lockout_status', { vaultId: vault.id });
+      setLockedFor(status.locked ? status.retry_after_secs : 0);
+    } catch {
+      setLockedFor(0);
+    }
+  }, [vault.id]);
+
+  useEffect(() => {
+    if (hasExistingPassword) refreshLockout();
+  }, [hasExistingPassword, refreshLockout]);
+
+  // Count the cooldown down locally; the backend stays the source of truth for the next attempt
+  useEffect(() => {
+    if (lockedFor <= 0) return;
+    const timer = setTimeout(() => setLockedFor(s => Math.max(0, s - 1)), 1000);
+    return () => clearTimeout(timer);
+  }, [lockedFor]);
 
   const passwordStrength = getPasswordStrength(newPassword);
   const passwordsMatch = newPassword === confirmPassword;
@@ -70,6 +105,7 @@ export const ChangePasswordDialog: React.FC<ChangePasswordDialogProps> = ({
   // Validation logic
   const canSubmit = (() => {
     if (isChanging) return false;
+    if (lockedFor > 0) return false;
 
     // If vault has password, current password is required
     if (hasExistingPassword && !currentPassword) return false;
@@ -130,16 +166,20 @@ export const ChangePasswordDialog: React.FC<ChangePasswordDialogProps> = ({
       onSuccess?.();
       onClose();
     } catch (err) {
-      const message = err instanceof Error ? err.message : String(err);
-      if (message.includes('Invalid password') || message.includes('Decryption failed')) {
+      const message = getErrorMessage(err);
+      if (isBrainboxError(err) && err.code === 'vault_locked') {
+        setError('');
+        await refreshLockout();
+      } else if (isBrainboxError(err) ? err.code === 'invalid_key' : message.includes('Invalid password')) {
         setError('Current password is incorrect');
+        await refreshLockout();
       } else {
         setError(`Failed to change password: ${message}`);
       }
     } finally {
       setIsChanging(false);
     }
-  }, [vault, currentPassword, newPassword, confirmPassword, passwordsMatch, enablePassword, hasExistingPassword, clearKey, setVaultPassword, showSuccess, onSuccess, onClose]);
+  }, [vault, currentPassword, newPassword, confirmPassword, passwordsMatch, enablePassword, hasExistingPassword, clearKey, setVaultPassword, showSuccess, onSuccess, onClose, refreshLockout]);
 
   const handleBackdropClick = (e: React.MouseEvent) => {
     if (e.target === e.currentTarget && !isChanging) {
@@ -247,6 +287,12 @@ export const ChangePasswordDialog: React.FC<ChangePasswordDialogProps> = ({
             </>
           )}
 
+          {lockedFor > 0 && (
+            <div className={styles.error} role="alert">
+              Too many failed attempts. Try again in {formatCooldown(lockedFor)}.
+            </div>
+          )}
+
           {error && (
             <div className={styles.error} role="alert">
               {error}
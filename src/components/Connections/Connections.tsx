@@ -26,6 +26,7 @@ import {
 } from '@heroicons/react/24/outline';
 import { SparklesIcon as SparklesSolid } from '@heroicons/react/24/solid';
 import styles from './Connections.module.css';
+import { getErrorMessage } from '../../utils/errors';
 
 interface ConnectionsProps {
   onOpenAISettings?: () => void;
@@ -425,7 +426,7 @@ Always keep normal prose separate from ACTION lines. Never execute changes yours
         }
       );
     } catch (e) {
-      setError(String(e));
+      setError(getErrorMessage(e));
       setChatBusy(false);
     }
   }
@@ -561,7 +562,7 @@ Always keep normal prose separate from ACTION lines. Never execute changes yours
         }
       } catch (e) {
         hadError = true;
-        setActionsError(String(e));
+        setActionsError(getErrorMessage(e));
       }
     }
     setActionsStatus(hadError ? 'error' : 'done');
@@ -5,13 +5,16 @@
  * - Export selected vaults to JSON
  * - Import vaults from JSON file
  * - Set password for imported vaults
+ * - Merge into matching vaults instead of creating duplicates
  */
 
 import React, { useState, useEffect, useRef } from 'react';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { useVaultPassword } from '../../contexts/VaultPasswordContext';
 import { useToast } from '../../contexts/ToastContext';
 import styles from './ExportImport.module.css';
+import { getErrorMessage, isBrainboxError } from '../../utils/errors';
 
 interface Vault {
   id: number;
@@ -19,6 +22,13 @@ interface Vault {
   has_password?: boolean;
 }
 
+interface ImportProgress {
+  operationId: string | null;
+  vault: string;
+  done: number;
+  total: number;
+}
+
 interface ExportImportProps {
   onImportComplete?: () => void;
 }
@@ -34,9 +44,12 @@ export const ExportImport: React.FC<ExportImportProps> = ({ onImportComplete })
   const [isExporting, setIsExporting] = useState(false);
   const [isImporting, setIsImporting] = useState(false);
   const [importPassword, setImportPassword] = useState('');
+  const [mergeImport, setMergeImport] = useState(true);
   const [showImportDialog, setShowImportDialog] = useState(false);
   const [importData, setImportData] = useState<string | null>(null);
   const [importPreview, setImportPreview] = useState<{ vaultCount: number; itemCount: number } | null>(null);
+  const [importProgress, setImportProgress] = useState<ImportProgress | null>(null);
+  const importOperationId = useRef<string | null>(null);
 
   useEffect(() => {
     loadVaults();
@@ -111,7 +124,7 @@ export const ExportImport: React.FC<ExportImportProps> = ({ onImportComplete })
 
       showSuccess(`Exported ${vaultIds.length} vault(s) successfully`);
     } catch (error) {
-      const message = error instanceof Error ? error.message : String(error);
+      const message = getErrorMessage(error);
       if (!message.includes('Password is required')) {
         showError(`Export failed: ${message}`);
       }
@@ -158,14 +171,28 @@ export const ExportImport: React.FC<ExportImportProps> = ({ onImportComplete })
     }
   };
 
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+    listen<ImportProgress>('import-progress', (event) => {
+      if (event.payload.operationId === importOperationId.current) {
+        setImportProgress(event.payload);
+      }
+    }).then((fn) => { unlisten = fn; });
+    return () => { if (unlisten) unlisten(); };
+  }, []);
+
   const handleImport = async () => {
     if (!importData) return;
 
     setIsImporting(true);
+    const operationId = crypto.randomUUID();
+    importOperationId.current = operationId;
     try {
       const importedIds = await invoke<number[]>('import_vaults', {
         jsonData: importData,
         password: importPassword,
+        merge: mergeImport,
+        operationId,
       });
 
       showSuccess(`Imported ${importedIds.length} vault(s) successfully`);
@@ -176,14 +203,24 @@ export const ExportImport: React.FC<ExportImportProps> = ({ onImportComplete })
       loadVaults();
       onImportComplete?.();
     } catch (error) {
-      const message = error instanceof Error ? error.message : String(error);
-      showError(`Import failed: ${message}`);
+      if (isBrainboxError(error) && error.code === 'cancelled') {
+        showSuccess('Import cancelled, nothing was imported');
+      } else {
+        const message = getErrorMessage(error);
+        showError(`Import failed: ${message}`);
+      }
     } finally {
+      importOperationId.current = null;
+      setImportProgress(null);
       setIsImporting(false);
     }
   };
 
   const cancelImport = () => {
+    if (isImporting && importOperationId.current) {
+      invoke('cancel_operation', { operationId: importOperationId.current });
+      return;
+    }
     setShowImportDialog(false);
     setImportData(null);
     setImportPreview(null);
@@ -308,12 +345,21 @@ export const ExportImport: React.FC<ExportImportProps> = ({ onImportComplete })
               </span>
             </div>
 
+            <label className={styles.vaultItem}>
+              <input
+                type="checkbox"
+                checked={mergeImport}
+                onChange={(e) => setMergeImport(e.target.checked)}
+                className={styles.checkbox}
+              />
+              <span className={styles.vaultName}>Merge into existing vaults with the same name</span>
+            </label>
+
             <div className={styles.dialogActions}>
               <button
                 type="button"
                 onClick={cancelImport}
                 className={styles.cancelButton}
-                disabled={isImporting}
               >
                 Cancel
               </button>
@@ -323,7 +369,11 @@ export const ExportImport: React.FC<ExportImportProps> = ({ onImportComplete })
                 className={styles.primaryButton}
                 disabled={isImporting}
               >
-                {isImporting ? 'Importing...' : 'Import'}
+                {isImporting
+                  ? importProgress
+                    ? `Importing ${importProgress.vault} (${importProgress.done}/${importProgress.total})...`
+                    : 'Importing...'
+                  : 'Import'}
               </button>
             </div>
           </div>
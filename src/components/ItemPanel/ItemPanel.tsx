@@ -9,6 +9,7 @@ import { invoke } from '@tauri-apps/api/core';
 import { useVaultPassword } from '../../contexts/VaultPasswordContext';
 import { useToast } from '../../contexts/ToastContext';
 import { usePrompt } from '../../contexts/PromptContext';
+import { getErrorMessage } from '../../utils/errors';
 
 type Item = any;
 
@@ -154,7 +155,7 @@ const ItemPanel: React.FC<Props> = ({ item, vaults, currentVaultId, onClose, onR
       });
       // stop() can be used to cancel if needed later
     } catch (e) {
-      setSumError(String(e));
+      setSumError(getErrorMessage(e));
     } finally {
       setSummarizing(false);
     }
@@ -3,6 +3,7 @@ import Masonry from '../Masonry/Masonry';
 import ItemPanel from '../ItemPanel/ItemPanel';
 import styles from './Library.module.css';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { meshGradientForId } from '../../utils/meshGradient';
 import { getYouTubeId, youtubeThumbnailUrl } from '../../utils/urlPreview';
 import { useVaultPassword } from '../../contexts/VaultPasswordContext';
@@ -43,6 +44,30 @@ const Library: React.FC<Props> = ({ vaults }) => {
   const [q, setQ] = useState('');
   const [sortBy, setSortBy] = useState<'updated' | 'created'>('updated');
 
+  const applyPreview = (id: string, meta: BackendUrlMetadata) => {
+    setItems((prev) => prev.map(p => p.id === id ? ({
+      ...p,
+      metadata: {
+        ...p.metadata,
+        preview_title: meta?.title || p.metadata?.preview_title,
+        preview_description: meta?.description || p.metadata?.preview_description,
+        preview_image: meta?.image || p.metadata?.preview_image,
+      }
+    }) : p));
+  };
+
+  // Pick up queued enrichment results as the backend finishes them
+  useEffect(() => {
+    const unlisten = listen<{ itemId: number; status: string }>('enrichment-progress', async (event) => {
+      if (event.payload.status !== 'done') return;
+      try {
+        const meta = await invoke<BackendUrlMetadata | null>('get_url_metadata', { itemId: event.payload.itemId });
+        if (meta) applyPreview(String(event.payload.itemId), meta);
+      } catch { /* ignore */ }
+    });
+    return () => { unlisten.then((fn) => fn()); };
+  }, []);
+
   // Load all items across vaults
   useEffect(() => {
     if (!vaults || vaults.length === 0) {
@@ -94,23 +119,21 @@ const Library: React.FC<Props> = ({ vaults }) => {
         );
         if (!alive) return;
         const flat = arrays.flat();
-        // Enrich non-YouTube URLs with preview metadata
+        // Enrich non-YouTube URLs with preview metadata. Stored results are used directly; everything else
+        // goes to the backend queue, which fetches at a limited rate and reports back via enrichment-progress.
         setItems(flat.sort((a, b) => +b.updatedAt - +a.updatedAt));
-        flat.filter((it) => it.metadata?.item_type === 'url' && it.metadata?.provider !== 'youtube').forEach(async (it) => {
+        const urlItems = flat.filter((it) => it.metadata?.item_type === 'url' && it.metadata?.provider !== 'youtube');
+        const missing: { item_id: number; url: string }[] = [];
+        await Promise.all(urlItems.map(async (it) => {
           try {
-            const meta = await invoke<BackendUrlMetadata>('fetch_url_metadata', { url: it.content });
-            if (!alive) return;
-            setItems((prev) => prev.map(p => p.id === it.id ? ({
-              ...p,
-              metadata: {
-                ...p.metadata,
-                preview_title: meta?.title || p.metadata?.preview_title,
-                preview_description: meta?.description || p.metadata?.preview_description,
-                preview_image: meta?.image || p.metadata?.preview_image,
-              }
-            }) : p));
+            const stored = await invoke<BackendUrlMetadata & { status: string } | null>('get_url_metadata', { itemId: Number(it.id) });
+            if (stored?.status === 'done') applyPreview(it.id, stored);
+            else if (!stored) missing.push({ item_id: Number(it.id), url: it.content });
           } catch { /* ignore */ }
-        });
+        }));
+        if (alive && missing.length > 0) {
+          invoke('enqueue_url_enrichment', { items: missing }).catch(() => { /* ignore */ });
+        }
       } finally {
         if (alive) setLoading(false);
       }
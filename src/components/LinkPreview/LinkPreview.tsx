@@ -1,5 +1,6 @@
 import React, { useEffect, useState } from 'react';
 import { invoke } from '@tauri-apps/api/core';
+import { useFavicon } from '../../utils/useFavicon';
 
 type Props = {
   url: string;
@@ -36,6 +37,8 @@ const LinkPreview: React.FC<Props> = ({ url, onImageFound, compact }) => {
     return () => { cancelled = true; };
   }, [url]);
 
+  const favicon = useFavicon(meta?.final_url || url);
+
   const host = (() => {
     try { return new URL(meta?.final_url || url).hostname; } catch { return url; }
   })();
@@ -58,7 +61,7 @@ const LinkPreview: React.FC<Props> = ({ url, onImageFound, compact }) => {
       )}
       <div style={{ minWidth: 0, flex: 1 }}>
         <div style={{ display: 'flex', alignItems: 'center', gap: 8, marginBottom: 6 }}>
-          {meta?.favicon && <img src={meta.favicon} alt="" style={{ width: 16, height: 16 }} />}
+          {favicon && <img src={favicon} alt="" style={{ width: 16, height: 16 }} />}
           <span style={{ fontSize: 12, color: 'var(--color-text-secondary)' }}>{host}</span>
         </div>
         <div style={{ fontWeight: 600, fontSize: compact ? 13 : 15, color: 'var(--color-text-primary)', marginBottom: 4, overflow: 'hidden', textOverflow: 'ellipsis', whiteSpace: 'nowrap' }}>
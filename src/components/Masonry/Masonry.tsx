@@ -2,7 +2,12 @@ import React, { useState, useEffect, useMemo, useRef } from "react";
 import { useTransition, a } from "@react-spring/web";
 import { PlayIcon, ArrowUpIcon, ArrowDownIcon, TrashIcon, EllipsisVerticalIcon } from "@heroicons/react/24/solid";
 import "./Masonry.css";
-import { faviconForUrl } from "../../utils/urlPreview";
+import { useFavicon } from "../../utils/useFavicon";
+
+const Favicon: React.FC<{ url?: string }> = ({ url }) => {
+  const fav = useFavicon(url);
+  return fav ? <img src={fav} alt="" /> : null;
+};
 
 export interface MasonryItem {
   id: string | number;
@@ -211,7 +216,7 @@ const Masonry: React.FC<MasonryProps> = ({ data, onCardClick, onDeleteItem, onMo
                 }}
               >
                 <div className="mlp-host">
-                  {(() => { const fav = faviconForUrl(item?.metadata?.url); return fav ? <img src={fav} alt="" /> : null; })()}
+                  <Favicon url={item?.metadata?.url} />
                   <span>{(() => { try { return new URL(item?.metadata?.url || '').hostname; } catch { return 'link'; } })()}</span>
                 </div>
                 {item?.metadata?.preview_image && (
@@ -0,0 +1,102 @@
+import React, { useEffect, useRef, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import styles from './QuickSwitcher.module.css';
+
+interface SwitcherResult {
+  item_id: number;
+  vault_id: number;
+  title: string;
+  preview: string;
+  item_type: string;
+}
+
+// Runs in its own window (index.html?window=switcher), opened from Rust by hotkey
+const QuickSwitcher: React.FC = () => {
+  const [query, setQuery] = useState('');
+  const [results, setResults] = useState<SwitcherResult[]>([]);
+  const [selectedIndex, setSelectedIndex] = useState(0);
+  const inputRef = useRef<HTMLInputElement>(null);
+  const searchTimerRef = useRef<number | null>(null);
+
+  // Reset each time the window is shown
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+    listen('switcher-opened', () => {
+      setQuery('');
+      setResults([]);
+      setSelectedIndex(0);
+      inputRef.current?.focus();
+    }).then((fn) => { unlisten = fn; });
+    inputRef.current?.focus();
+    return () => { if (unlisten) unlisten(); };
+  }, []);
+
+  useEffect(() => {
+    if (searchTimerRef.current) window.clearTimeout(searchTimerRef.current);
+    searchTimerRef.current = window.setTimeout(async () => {
+      try {
+        const found: SwitcherResult[] = await invoke('switcher_search', { query, limit: 20 });
+        setResults(found);
+        setSelectedIndex(0);
+      } catch (error) {
+        console.error('Quick switcher search failed:', error);
+        setResults([]);
+      }
+    }, 80);
+    return () => {
+      if (searchTimerRef.current) window.clearTimeout(searchTimerRef.current);
+    };
+  }, [query]);
+
+  const openResult = (result: SwitcherResult) => {
+    invoke('switcher_open_item', { itemId: result.item_id, vaultId: result.vault_id })
+      .catch((error) => console.error('Failed to open item:', error));
+  };
+
+  const handleKeyDown = (e: React.KeyboardEvent) => {
+    if (e.key === 'Escape') {
+      e.preventDefault();
+      invoke('close_quick_switcher');
+    } else if (e.key === 'ArrowDown') {
+      e.preventDefault();
+      setSelectedIndex((i) => Math.min(i + 1, results.length - 1));
+    } else if (e.key === 'ArrowUp') {
+      e.preventDefault();
+      setSelectedIndex((i) => Math.max(i - 1, 0));
+    } else if (e.key === 'Enter' && results[selectedIndex]) {
+      e.preventDefault();
+      openResult(results[selectedIndex]);
+    }
+  };
+
+  return (
+    <div className={styles.switcher} onKeyDown={handleKeyDown}>
+      <input
+        ref={inputRef}
+        className={styles.input}
+        value={query}
+        onChange={(e) => setQuery(e.target.value)}
+        placeholder="Jump to an item..."
+        autoFocus
+      />
+      <ul className={styles.results}>
+        {results.map((result, index) => (
+          <li
+            key={result.item_id}
+            className={`${styles.result} ${index === selectedIndex ? styles.selected : ''}`}
+            onMouseEnter={() => setSelectedIndex(index)}
+            onClick={() => openResult(result)}
+          >
+            <span className={styles.title}>{result.title || 'Untitled'}</span>
+            <span className={styles.type}>{result.item_type}</span>
+            {result.preview && <span className={styles.preview}>{result.preview}</span>}
+          </li>
+        ))}
+        {query.trim() && results.length === 0 && <li className={styles.empty}>No matches</li>}
+      </ul>
+    </div>
+  );
+};
+
+export default QuickSwitcher;
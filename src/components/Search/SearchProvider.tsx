@@ -86,14 +86,16 @@ export const SearchProvider: React.FC<SearchProviderProps> = ({ children }) => {
   }: IndexDocumentParams): Promise<void> => {
     try {
       await invoke('index_document', {
-        id,
-        title,
-        content,
-        item_type: itemType,
-        created_at: createdAt,
-        updated_at: updatedAt,
-        path,
-        tags,
+        doc: {
+          id,
+          title,
+          content,
+          item_type: itemType,
+          created_at: createdAt,
+          updated_at: updatedAt,
+          path,
+          tags,
+        },
       });
     } catch (error) {
       console.error('Error indexing document:', error);
@@ -1,13 +1,30 @@
-import React, { useState } from 'react';
+import React, { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
 import { MagnifyingGlassIcon, XMarkIcon } from '@heroicons/react/24/outline';
 import styles from './SearchBar.module.css';
 
 const SearchBar = ({ onSearch }) => {
   const [query, setQuery] = useState('');
-  
+  const [suggestions, setSuggestions] = useState([]);
+
+  // Recent queries while the box is empty, completions from indexed titles and tags while typing
+  useEffect(() => {
+    let cancelled = false;
+    const t = setTimeout(() => {
+      const request = query.trim()
+        ? invoke('suggest_search_terms', { prefix: query, limit: 8 }).then((s) => (s || []).map((x) => x.text))
+        : invoke('get_recent_searches');
+      request
+        .then((list) => { if (!cancelled) setSuggestions(list || []); })
+        .catch(() => { if (!cancelled) setSuggestions([]); });
+    }, 150);
+    return () => { cancelled = true; clearTimeout(t); };
+  }, [query]);
+
   const handleSubmit = (e) => {
     e.preventDefault();
     if (query.trim()) {
+      invoke('add_recent_search', { query }).catch(() => {});
       onSearch(query);
     }
   };
@@ -25,7 +42,12 @@ const SearchBar = ({ onSearch }) => {
             placeholder="Search your knowledge vaults..."
             aria-label="Search"
             data-testid="search-input"
+            list="search-suggestions"
+            autoComplete="off"
           />
+          <datalist id="search-suggestions">
+            {suggestions.map((s) => <option key={s} value={s} />)}
+          </datalist>
           {Boolean(query) && (
             <button
               type="button"
@@ -371,6 +371,98 @@ function AppearanceSettings() {
   );
 }
 
+const SEARCH_LANGUAGE_OPTIONS = [
+  ['default', 'Default (word splitting)'],
+  ['cjk', 'Chinese / Japanese / Korean'],
+  ['danish', 'Danish'], ['dutch', 'Dutch'], ['english', 'English'], ['finnish', 'Finnish'],
+  ['french', 'French'], ['german', 'German'], ['greek', 'Greek'], ['hungarian', 'Hungarian'],
+  ['italian', 'Italian'], ['norwegian', 'Norwegian'], ['portuguese', 'Portuguese'], ['romanian', 'Romanian'],
+  ['russian', 'Russian'], ['spanish', 'Spanish'], ['swedish', 'Swedish'], ['turkish', 'Turkish'],
+];
+
+// Search language (tokenizer) per indexed field
+function SearchSettings() {
+  const [languages, setLanguages] = useState({ title: 'default', content: 'default' });
+  const [status, setStatus] = useState('');
+  const [stats, setStats] = useState(null);
+  const [isOptimizing, setIsOptimizing] = useState(false);
+
+  useEffect(() => {
+    invoke('get_search_languages').then(setLanguages).catch(() => {});
+    invoke('get_search_index_stats').then(setStats).catch(() => {});
+  }, []);
+
+  const optimizeIndex = async () => {
+    setIsOptimizing(true);
+    try {
+      setStats(await invoke('optimize_search_index'));
+      setStatus('Search index optimized.');
+    } catch (error) {
+      setStatus(`Failed to optimize search index: ${error}`);
+    } finally {
+      setIsOptimizing(false);
+    }
+  };
+
+  const changeLanguage = async (field, value) => {
+    const next = { ...languages, [field]: value };
+    setLanguages(next);
+    try {
+      setStatus('Rebuilding search index...');
+      const needsRebuild = await invoke('set_search_languages', { languages: next });
+      if (needsRebuild) {
+        const { rebuildIndex } = await import('../../utils/searchIndexer');
+        await rebuildIndex();
+      }
+      setStatus('Search index updated.');
+    } catch (error) {
+      setStatus(`Failed to update search language: ${error}`);
+    }
+  };
+
+  return (
+    <SettingCard
+      title="Search"
+      description="Pick how titles and content are split into words. Changing this rebuilds the search index."
+    >
+      <div style={cardSectionStackStyle}>
+        {[['title', 'Title language'], ['content', 'Content language']].map(([field, label]) => (
+          <div key={field}>
+            <label style={labelStyle} htmlFor={`search-language-${field}`}>{label}</label>
+            <select
+              id={`search-language-${field}`}
+              value={languages[field]}
+              onChange={(e) => changeLanguage(field, e.target.value)}
+              style={inputStyle}
+            >
+              {SEARCH_LANGUAGE_OPTIONS.map(([value, name]) => (
+                <option key={value} value={value}>{name}</option>
+              ))}
+            </select>
+          </div>
+        ))}
+        {stats && (
+          <p style={bodyTextMutedStyle}>
+            {stats.doc_count} documents ({stats.deleted_docs} deleted) in {stats.segment_count} segments,{' '}
+            {(stats.disk_size_bytes / (1024 * 1024)).toFixed(1)} MB on disk
+          </p>
+        )}
+        <div style={inlineActionRowStyle}>
+          <button
+            type="button"
+            onClick={optimizeIndex}
+            disabled={isOptimizing}
+            style={{ ...buttonStyle, opacity: isOptimizing ? 0.6 : 1 }}
+          >
+            {isOptimizing ? 'Optimizing...' : 'Optimize index'}
+          </button>
+        </div>
+        {status && <div style={statusBubbleStyle('info')} role="status">{status}</div>}
+      </div>
+    </SettingCard>
+  );
+}
+
 // Security Settings Panel
 function SecuritySettings() {
   return (
@@ -450,7 +542,12 @@ const Settings = ({ scrollToSection, onScrollComplete }) => {
       case 'capture':
         return <CaptureSettings />;
       case 'appearance':
-        return <AppearanceSettings />;
+        return (
+          <>
+            <AppearanceSettings />
+            <SearchSettings />
+          </>
+        );
       case 'security':
         return <SecuritySettings />;
       case 'backup':
@@ -990,6 +1087,12 @@ function UpdateSettings() {
   const [isChecking, setIsChecking] = useState(false)
   const [isUpdating, setIsUpdating] = useState(false)
   const [updateProgress, setUpdateProgress] = useState(0)
+  const [updateChannel, setUpdateChannel] = useState('stable')
+  const [releaseNotes, setReleaseNotes] = useState('')
+  const [checkSettings, setCheckSettings] = useState({ interval_hours: 24, notify: true })
+  const [availableUpdate, setAvailableUpdate] = useState(null)
+  const [isStaged, setIsStaged] = useState(false)
+  const [rollbackVersion, setRollbackVersion] = useState(null)
 
   useEffect(() => {
     // Get current version on component mount
@@ -1000,10 +1103,26 @@ function UpdateSettings() {
       } catch (e) {
         console.error('Failed to get current version:', e)
       }
+      try {
+        setUpdateChannel(await invoke('get_update_channel'))
+      } catch (e) {
+        console.error('Failed to get update channel:', e)
+      }
+      try {
+        setCheckSettings(await invoke('get_update_check_settings'))
+      } catch (e) {
+        console.error('Failed to get update check settings:', e)
+      }
+      try {
+        setIsStaged(Boolean(await invoke('get_staged_update')))
+        setRollbackVersion(await invoke('get_rollback_version'))
+      } catch (e) {
+        console.error('Failed to get update install state:', e)
+      }
     })()
 
     // Listen for update progress events
-    let unlisten1, unlisten2;
+    let unlisten1, unlisten2, unlisten3;
     
     listen('update-progress', (event) => {
       setUpdateProgress(event.payload)
@@ -1013,19 +1132,31 @@ function UpdateSettings() {
       setUpdateStatus('Update downloaded! Restarting application...')
     }).then(fn => { unlisten2 = fn }).catch(() => {})
 
+    // Found by the backend's scheduled check
+    listen('update-available', (event) => {
+      const info = event.payload
+      setAvailableUpdate(info)
+      setUpdateStatus(`Update available: v${info.version}${info.prerelease ? ' (beta)' : ''}`)
+      setReleaseNotes(info.notes || '')
+    }).then(fn => { unlisten3 = fn }).catch(() => {})
+
     return () => {
       if (unlisten1) unlisten1()
       if (unlisten2) unlisten2()
+      if (unlisten3) unlisten3()
     }
   }, [])
 
   async function checkForUpdates() {
     setIsChecking(true)
     setUpdateStatus('')
+    setReleaseNotes('')
     try {
       const result = await invoke('check_for_updates')
+      setAvailableUpdate(result && result.version ? result : null)
       if (result && result.version) {
-        setUpdateStatus(`Update available: v${result.version}`)
+        setUpdateStatus(`Update available: v${result.version}${result.prerelease ? ' (beta)' : ''}`)
+        setReleaseNotes(result.notes || '')
       } else {
         setUpdateStatus('You are running the latest version!')
       }
@@ -1036,6 +1167,27 @@ function UpdateSettings() {
     }
   }
 
+  async function changeUpdateChannel(channel) {
+    setUpdateChannel(channel)
+    setUpdateStatus('')
+    setReleaseNotes('')
+    try {
+      await invoke('set_update_channel', { channel })
+    } catch (e) {
+      console.error('Failed to set update channel:', e)
+    }
+  }
+
+  async function changeCheckSettings(changes) {
+    const next = { ...checkSettings, ...changes }
+    setCheckSettings(next)
+    try {
+      await invoke('set_update_check_settings', { settings: next })
+    } catch (e) {
+      console.error('Failed to save update check settings:', e)
+    }
+  }
+
   async function installUpdate() {
     setIsUpdating(true)
     setUpdateProgress(0)
@@ -1049,6 +1201,33 @@ function UpdateSettings() {
     }
   }
 
+  // Download now, install when the user quits brainbox
+  async function installOnQuit() {
+    if (!availableUpdate) return
+    setIsUpdating(true)
+    setUpdateProgress(0)
+    setUpdateStatus('Downloading update...')
+    try {
+      const updatePath = await invoke('download_update', { updateInfo: availableUpdate })
+      await invoke('stage_update', { updatePath })
+      setIsStaged(true)
+      setUpdateStatus(`v${availableUpdate.version} will be installed when you quit brainbox.`)
+    } catch (e) {
+      setUpdateStatus(`Error downloading update: ${e}`)
+    } finally {
+      setIsUpdating(false)
+    }
+  }
+
+  async function rollbackUpdate() {
+    setUpdateStatus(`Restoring v${rollbackVersion}...`)
+    try {
+      await invoke('rollback_update')
+    } catch (e) {
+      setUpdateStatus(`Error rolling back: ${e}`)
+    }
+  }
+
   const hasUpdate = typeof updateStatus === 'string' && updateStatus.includes('Update available')
   const releaseName = formatReleaseName(currentVersion)
 
@@ -1064,6 +1243,43 @@ function UpdateSettings() {
       }
     >
       <div style={cardSectionStackStyle}>
+        <div>
+          <label style={labelStyle} htmlFor="update-channel">Update channel</label>
+          <select
+            id="update-channel"
+            value={updateChannel}
+            onChange={(e) => changeUpdateChannel(e.target.value)}
+            style={inputStyle}
+            disabled={isChecking || isUpdating}
+          >
+            <option value="stable">Stable</option>
+            <option value="beta">Beta (includes pre-releases)</option>
+          </select>
+        </div>
+
+        <div>
+          <label style={labelStyle} htmlFor="update-interval">Check automatically</label>
+          <select
+            id="update-interval"
+            value={checkSettings.interval_hours}
+            onChange={(e) => changeCheckSettings({ interval_hours: Number(e.target.value) })}
+            style={inputStyle}
+          >
+            <option value={0}>Never</option>
+            <option value={6}>Every 6 hours</option>
+            <option value={24}>Daily</option>
+            <option value={168}>Weekly</option>
+          </select>
+          <label style={{ ...bodyTextMutedStyle, display: 'flex', alignItems: 'center', gap: 8, marginTop: 8 }}>
+            <input
+              type="checkbox"
+              checked={checkSettings.notify}
+              onChange={(e) => changeCheckSettings({ notify: e.target.checked })}
+            />
+            Show a notification when an update is found
+          </label>
+        </div>
+
         <div style={inlineActionRowStyle}>
           <button
             type="button"
@@ -1090,6 +1306,28 @@ function UpdateSettings() {
               {isUpdating ? 'Installing...' : 'Install update'}
             </button>
           )}
+
+          {hasUpdate && availableUpdate && !isStaged && (
+            <button
+              type="button"
+              onClick={installOnQuit}
+              style={{ ...buttonStyle, opacity: isUpdating ? 0.6 : 1 }}
+              disabled={isUpdating}
+            >
+              Install on quit
+            </button>
+          )}
+
+          {rollbackVersion && (
+            <button
+              type="button"
+              onClick={rollbackUpdate}
+              style={{ ...buttonStyle, opacity: isChecking || isUpdating ? 0.6 : 1 }}
+              disabled={isChecking || isUpdating}
+            >
+              Roll back to v{rollbackVersion}
+            </button>
+          )}
         </div>
 
         {isUpdating && updateProgress > 0 && (
@@ -1108,6 +1346,13 @@ function UpdateSettings() {
             {updateStatus}
           </div>
         )}
+
+        {hasUpdate && releaseNotes && (
+          <div>
+            <span style={subtleLabelStyle}>What's new</span>
+            <p style={{ ...bodyTextMutedStyle, whiteSpace: 'pre-wrap', marginTop: 8 }}>{releaseNotes}</p>
+          </div>
+        )}
       </div>
     </SettingCard>
   )
@@ -30,6 +30,7 @@ interface SyncImportResult {
   conflicts: string[];
   warnings: string[];
   skipped_vaults: string[];
+  failed_vaults: { name: string; error: string }[];
 }
 
 interface VaultPasswordInfo {
@@ -261,9 +262,16 @@ export function SyncSettings() {
       if (result.skipped_vaults.length > 0) {
         message += `. Skipped: ${result.skipped_vaults.join(', ')}`;
       }
+      if (result.failed_vaults.length > 0) {
+        message += `. Failed: ${result.failed_vaults.map((f) => `${f.name} (${f.error})`).join(', ')}`;
+      }
       
       setSyncMessage(message);
-      setSyncMessageType(result.conflicts.length > 0 || result.skipped_vaults.length > 0 ? 'warning' : 'accent');
+      setSyncMessageType(
+        result.conflicts.length > 0 || result.skipped_vaults.length > 0 || result.failed_vaults.length > 0
+          ? 'warning'
+          : 'accent'
+      );
       await loadSyncStatus();
       
       // Notify the rest of the app that data has changed
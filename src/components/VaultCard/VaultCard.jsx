@@ -6,16 +6,22 @@ const VaultCard = ({
   title,
   backgroundImage,
   color = '#f0f0f0',
+  icon,
+  description,
+  pinned = false,
   children,
   priceTag,
   onClick,
   onDelete,
   onRename,
   onChangeCover,
-  onChangePassword
+  onChangePassword,
+  onTogglePin,
+  onDuplicate,
+  onBiometricUnlock
 }) => {
   const cardStyle = backgroundImage 
-    ? { backgroundImage: `url(${backgroundImage})` }
+    ? { backgroundImage: `url(${backgroundImage})`, borderColor: color }
     : { backgroundColor: color };
 
   const [menuOpen, setMenuOpen] = useState(false);
@@ -42,8 +48,11 @@ const VaultCard = ({
         {children && <div className={styles.body}>{children}</div>}
       </div>
       <div className={styles.footer}>
-        <span className={styles.label}>{title}</span>
-        {(onDelete || onRename || onChangeCover || onChangePassword) && (
+        <span className={styles.label} title={description || undefined}>
+          {icon && <span className={styles.icon} aria-hidden="true">{icon}</span>}
+          {title}
+        </span>
+        {(onDelete || onRename || onChangeCover || onChangePassword || onTogglePin || onDuplicate || onBiometricUnlock) && (
           <div className={styles.menuWrap}>
             <button
               type="button"
@@ -58,15 +67,24 @@ const VaultCard = ({
             </button>
             {menuOpen && (
               <div className={styles.menu} role="menu" onClick={(e) => e.stopPropagation()}>
+                {onTogglePin && (
+                  <button className={styles.menuItem} role="menuitem" onClick={(e) => { e.stopPropagation(); closeMenu(e); onTogglePin?.(); }}>{pinned ? 'Unpin' : 'Pin to top'}</button>
+                )}
                 {onRename && (
                   <button className={styles.menuItem} role="menuitem" onClick={(e) => { e.stopPropagation(); closeMenu(e); onRename?.(); }}>Rename</button>
                 )}
+                {onDuplicate && (
+                  <button className={styles.menuItem} role="menuitem" onClick={(e) => { e.stopPropagation(); closeMenu(e); onDuplicate?.(); }}>Duplicate</button>
+                )}
                 {onChangeCover && (
                   <button className={styles.menuItem} role="menuitem" onClick={(e) => { e.stopPropagation(); closeMenu(e); onChangeCover?.(); }}>Change cover image</button>
                 )}
                 {onChangePassword && (
                   <button className={styles.menuItem} role="menuitem" onClick={(e) => { e.stopPropagation(); closeMenu(e); onChangePassword?.(); }}>Change password</button>
                 )}
+                {onBiometricUnlock && (
+                  <button className={styles.menuItem} role="menuitem" onClick={(e) => { e.stopPropagation(); closeMenu(e); onBiometricUnlock?.(); }}>Biometric unlock</button>
+                )}
                 {onDelete && (
                   <button className={styles.menuItemDanger} role="menuitem" onClick={(e) => { e.stopPropagation(); closeMenu(e); onDelete?.(); }}>Delete</button>
                 )}
@@ -1,43 +1,46 @@
 /**
  * Vault Password Context
  *
- * Manages vault passwords and derived encryption keys for the current session.
- * Keys are cached in memory to avoid re-deriving on every operation.
+ * Manages which vaults are unlocked for the current session.
+ * Keys are derived and held by the backend keystore; the frontend only tracks vault IDs
+ * and passes `key: null` so commands use the stored key.
  * Supports auto-lock timeout for security.
  */
 
 import React, { createContext, useContext, useState, useCallback, useEffect, useRef, ReactNode } from 'react';
-import { deriveKeyFromPassword, keyToArray } from '../utils/crypto';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { usePrompt } from './PromptContext';
 
 interface VaultPasswordContextType {
   /**
-   * Get the derived key for a vault (prompts for password if not cached and vault is password-protected)
+   * Make sure a vault is unlocked (prompts for password if not unlocked and vault is password-protected).
+   * Resolves to null; pass it as the command's `key` and the backend uses its stored key.
    * @param vaultId - The vault ID
    * @param vaultName - Optional display name for the prompt
    * @param hasPassword - Whether the vault is password-protected (false = auto-unlock)
    */
-  getVaultKey: (vaultId: string, vaultName?: string, hasPassword?: boolean) => Promise<number[]>;
+  getVaultKey: (vaultId: string, vaultName?: string, hasPassword?: boolean) => Promise<number[] | null>;
 
   /**
-   * Check if we have a key cached for a vault
+   * Check if a vault is unlocked
    */
   hasKey: (vaultId: string) => boolean;
 
   /**
-   * Clear the cached key for a vault (e.g., on vault close/lock)
+   * Lock a vault (e.g., on vault close/lock)
    */
   clearKey: (vaultId: string) => void;
 
   /**
-   * Clear all cached keys
+   * Lock all vaults
    */
   clearAllKeys: () => void;
 
   /**
-   * Set the password for a vault (used when creating or accessing)
+   * Unlock a vault with its password (used when creating or accessing)
    */
-  setVaultPassword: (vaultId: string, password: string) => Promise<number[]>;
+  setVaultPassword: (vaultId: string, password: string) => Promise<void>;
 
   /**
    * Get the list of currently unlocked vault IDs
@@ -58,11 +61,8 @@ interface VaultPasswordProviderProps {
 
 export const VaultPasswordProvider: React.FC<VaultPasswordProviderProps> = ({ children }) => {
   const promptDialog = usePrompt();
-  // Store derived keys (as number arrays for Tauri) indexed by vaultId
-  const [keys, setKeys] = useState<Map<string, number[]>>(new Map());
-
-  // Store vault passwords temporarily (for re-deriving keys if needed)
-  const [, setPasswords] = useState<Map<string, string>>(new Map());
+  // IDs of vaults whose keys are in the backend keystore
+  const [unlocked, setUnlocked] = useState<Set<string>>(new Set());
 
   // Last activity timestamp for inactivity timeout
   const lastActivityRef = useRef<number>(Date.now());
@@ -83,13 +83,13 @@ export const VaultPasswordProvider: React.FC<VaultPasswordProviderProps> = ({ ch
   useEffect(() => {
     const checkInactivity = () => {
       const timeoutMs = getTimeoutMs();
-      if (timeoutMs === 0 || keys.size === 0) return;
+      if (timeoutMs === 0 || unlocked.size === 0) return;
 
       const elapsed = Date.now() - lastActivityRef.current;
       if (elapsed >= timeoutMs) {
-        // Clear all keys due to inactivity
-        setKeys(new Map());
-        setPasswords(new Map());
+        // Lock all vaults due to inactivity
+        invoke('lock_all_vaults').catch(err => console.warn('Failed to lock vaults', err));
+        setUnlocked(new Set());
       }
     };
 
@@ -113,30 +113,42 @@ export const VaultPasswordProvider: React.FC<VaultPasswordProviderProps> = ({ ch
       window.removeEventListener('click', handleActivity);
       window.removeEventListener('scroll', handleActivity);
     };
-  }, [keys.size, getTimeoutMs]);
+  }, [unlocked.size, getTimeoutMs]);
 
-  const setVaultPassword = useCallback(async (vaultId: string, password: string) => {
-    // Derive key from password
-    const keyUint8 = await deriveKeyFromPassword(password, vaultId);
-    const keyArray = keyToArray(keyUint8);
-
-    // Store both password and derived key
-    setPasswords(prev => new Map(prev).set(vaultId, password));
-    setKeys(prev => new Map(prev).set(vaultId, keyArray));
-    return keyArray;
+  // The emergency lock hotkey clears the backend keystore directly
+  useEffect(() => {
+    const unlisten = listen('emergency-locked', () => setUnlocked(new Set()));
+    return () => {
+      unlisten.then(fn => fn());
+    };
   }, []);
 
-  const getVaultKey = useCallback(async (vaultId: string, vaultName?: string, hasPassword?: boolean): Promise<number[]> => {
-    // Check if we already have a cached key
-    const cachedKey = keys.get(vaultId);
-    if (cachedKey) {
-      return cachedKey;
+  const markUnlocked = useCallback((vaultId: string) => {
+    setUnlocked(prev => new Set(prev).add(vaultId));
+  }, []);
+
+  const setVaultPassword = useCallback(async (vaultId: string, password: string) => {
+    // The backend verifies the password and keeps the derived key
+    await invoke('unlock_vault', { vaultId: Number(vaultId), password });
+    markUnlocked(vaultId);
+  }, [markUnlocked]);
+
+  const getVaultKey = useCallback(async (vaultId: string, vaultName?: string, hasPassword?: boolean): Promise<number[] | null> => {
+    // Already unlocked, or not password-protected: the backend resolves the key itself
+    if (unlocked.has(vaultId) || hasPassword === false) {
+      return null;
     }
 
-    // If vault is not password-protected, auto-derive key with empty password
-    if (hasPassword === false) {
-      const key = await setVaultPassword(vaultId, '');
-      return key;
+    // Vaults with Touch ID / Windows Hello set up try that before asking for the password
+    try {
+      const status = await invoke<{ available: boolean; enabled: boolean }>('get_biometric_status', { vaultId: Number(vaultId) });
+      if (status.available && status.enabled) {
+        await invoke('unlock_with_biometrics', { vaultId: Number(vaultId) });
+        markUnlocked(vaultId);
+        return null;
+      }
+    } catch (err) {
+      console.warn('Biometric unlock failed, falling back to the password', err);
     }
 
     // Prompt user for password (for password-protected vaults or when hasPassword is undefined)
@@ -154,36 +166,31 @@ export const VaultPasswordProvider: React.FC<VaultPasswordProviderProps> = ({ ch
       throw new Error('Password is required to access this vault');
     }
 
-    // Derive and cache the key
-    const key = await setVaultPassword(vaultId, password);
-    return key;
-  }, [keys, setVaultPassword, promptDialog]);
+    await setVaultPassword(vaultId, password);
+    return null;
+  }, [unlocked, setVaultPassword, markUnlocked, promptDialog]);
 
   const hasKey = useCallback((vaultId: string): boolean => {
-    return keys.has(vaultId);
-  }, [keys]);
+    return unlocked.has(vaultId);
+  }, [unlocked]);
 
   const clearKey = useCallback((vaultId: string) => {
-    setKeys(prev => {
-      const newMap = new Map(prev);
-      newMap.delete(vaultId);
-      return newMap;
-    });
-    setPasswords(prev => {
-      const newMap = new Map(prev);
-      newMap.delete(vaultId);
-      return newMap;
+    invoke('lock_vault', { vaultId: Number(vaultId) }).catch(err => console.warn('Failed to lock vault', err));
+    setUnlocked(prev => {
+      const next = new Set(prev);
+      next.delete(vaultId);
+      return next;
     });
   }, []);
 
   const clearAllKeys = useCallback(() => {
-    setKeys(new Map());
-    setPasswords(new Map());
+    invoke('lock_all_vaults').catch(err => console.warn('Failed to lock vaults', err));
+    setUnlocked(new Set());
   }, []);
 
   const getUnlockedVaultIds = useCallback((): string[] => {
-    return Array.from(keys.keys());
-  }, [keys]);
+    return Array.from(unlocked);
+  }, [unlocked]);
 
   const contextValue: VaultPasswordContextType = {
     getVaultKey,
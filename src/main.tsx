@@ -14,6 +14,7 @@ import { ConfirmProvider } from "./contexts/ConfirmContext";
 import { VaultPasswordProvider } from "./contexts/VaultPasswordContext";
 import { PromptProvider } from "./contexts/PromptContext";
 import ErrorBoundary from "./components/ErrorBoundary/ErrorBoundary";
+import QuickSwitcher from "./components/QuickSwitcher/QuickSwitcher";
 // System tray handled in Rust (see src-tauri/src/lib.rs)
 
 // Trackpad click/tap normalizer disabled while investigating input/drag issues
@@ -21,7 +22,17 @@ import ErrorBoundary from "./components/ErrorBoundary/ErrorBoundary";
 // import { initializeTrackpadFix } from "./utils/trackpadFix";
 // initializeTrackpadFix();
 
+// The quick switcher window loads this bundle with ?window=switcher and only needs the theme
+const isSwitcherWindow = new URLSearchParams(window.location.search).get("window") === "switcher";
+
 ReactDOM.createRoot(document.getElementById("root") as HTMLElement).render(
+  isSwitcherWindow ? (
+    <React.StrictMode>
+      <ThemeProvider>
+        <QuickSwitcher />
+      </ThemeProvider>
+    </React.StrictMode>
+  ) : (
   <React.StrictMode>
     <ErrorBoundary>
       <HotkeyProvider>
@@ -43,4 +54,5 @@ ReactDOM.createRoot(document.getElementById("root") as HTMLElement).render(
       </HotkeyProvider>
     </ErrorBoundary>
   </React.StrictMode>
+  )
 );
@@ -4,6 +4,10 @@ export interface Vault {
   title: string;
   name?: string;
   color?: string;
+  /** Emoji or builtin icon name shown on the vault card */
+  icon?: string;
+  description?: string;
+  pinned?: boolean;
   backgroundImage?: string;
   cover_image?: string;
   children?: React.ReactNode;
@@ -125,6 +129,11 @@ export interface BackendVault {
   id: number;
   name: string;
   cover_image?: string | null;
+  color?: string | null;
+  icon?: string | null;
+  description?: string | null;
+  sort_order?: number | null;
+  pinned?: boolean;
   has_password?: boolean;
   created_at?: string;
   updated_at?: string;
@@ -8,6 +8,7 @@
 import { invoke } from '@tauri-apps/api/core';
 import { BackendSearchResult, BackendVault, BackendVaultItem } from '../../types';
 import { ToolCall, ToolResult, DESTRUCTIVE_TOOLS } from './tools';
+import { getErrorMessage } from '../errors';
 
 export interface VaultInfo {
   id: string;
@@ -17,7 +18,7 @@ export interface VaultInfo {
 
 export interface ToolExecutorConfig {
   /** Function to get vault key (prompts user if needed) */
-  getVaultKey: (vaultId: string, vaultName?: string, hasPassword?: boolean) => Promise<number[]>;
+  getVaultKey: (vaultId: string, vaultName?: string, hasPassword?: boolean) => Promise<number[] | null>;
   /** Function to get vault info by ID */
   getVaultInfo: (vaultId: string) => VaultInfo | undefined;
   /** Function to get all vaults */
@@ -96,7 +97,7 @@ export class ToolExecutor {
       return {
         tool_use_id: id,
         success: false,
-        error: error instanceof Error ? error.message : String(error),
+        error: getErrorMessage(error),
       };
     }
   }
@@ -327,6 +328,21 @@ export class ToolExecutor {
         };
       }
 
+      case 'fetch_media_transcript': {
+        const result = await invoke<{ source: string; language?: string; text: string } | null>('fetch_media_transcript', {
+          url: args.url as string,
+        });
+        if (!result) {
+          return { url: args.url, transcript: null, message: 'No transcript available' };
+        }
+        return {
+          url: args.url,
+          source: result.source,
+          transcript: result.text.slice(0, 15000),
+          truncated: result.text.length > 15000,
+        };
+      }
+
       // === Summarization ===
       case 'summarize_item': {
         // This would trigger the AI summarization flow
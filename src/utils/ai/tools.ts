@@ -252,6 +252,20 @@ export const BRAINY_TOOLS: ToolDefinition[] = [
       required: ['url'],
     },
   },
+  {
+    name: 'fetch_media_transcript',
+    description: 'Get the transcript of a YouTube or Vimeo video, a podcast feed (latest episode) or an audio file. Audio without captions is transcribed locally, which can take a while.',
+    parameters: {
+      type: 'object',
+      properties: {
+        url: {
+          type: 'string',
+          description: 'Video, podcast feed or audio file URL',
+        },
+      },
+      required: ['url'],
+    },
+  },
 
   // === Summarization ===
   {
@@ -0,0 +1,40 @@
+// Error helpers for values rejected by Tauri commands
+
+/** Error object returned by brainbox commands (see src-tauri/src/error.rs) */
+export interface BrainboxError {
+  code:
+    | 'invalid_key'
+    | 'vault_locked'
+    | 'not_found'
+    | 'invalid_input'
+    | 'database_locked'
+    | 'database'
+    | 'network'
+    | 'io'
+    | 'crypto'
+    | 'search'
+    | 'sync'
+    | 'update'
+    | 'cancelled'
+    | 'internal';
+  message: string;
+  details?: string;
+  retryable: boolean;
+}
+
+export function isBrainboxError(err: unknown): err is BrainboxError {
+  return (
+    typeof err === 'object' &&
+    err !== null &&
+    typeof (err as BrainboxError).code === 'string' &&
+    typeof (err as BrainboxError).message === 'string'
+  );
+}
+
+/** Human-readable message for anything caught from invoke() or elsewhere */
+export function getErrorMessage(err: unknown): string {
+  if (typeof err === 'string') return err;
+  if (isBrainboxError(err)) return err.message;
+  if (err instanceof Error) return err.message;
+  try { return JSON.stringify(err); } catch { return String(err); }
+}
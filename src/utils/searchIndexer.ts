@@ -31,14 +31,16 @@ export async function addToIndex(content: IndexableContent): Promise<string> {
   
   try {
     await invoke('index_document', {
-      id,
-      title: content.title,
-      content: content.content,
-      item_type: content.itemType,
-      created_at: createdAt.toISOString(),
-      updated_at: updatedAt.toISOString(),
-      path: content.path,
-      tags: content.tags || [],
+      doc: {
+        id,
+        title: content.title,
+        content: content.content,
+        item_type: content.itemType,
+        created_at: createdAt.toISOString(),
+        updated_at: updatedAt.toISOString(),
+        path: content.path,
+        tags: content.tags || [],
+      },
     });
     
     return id;
@@ -166,6 +168,7 @@ export async function rebuildIndex(): Promise<void> {
     const vaults = await invoke<BackendVault[]>('list_vaults');
     
     if (!vaults || vaults.length === 0) {
+      await invoke('mark_search_index_rebuilt');
       console.log('No vaults found. Index rebuild complete.');
       return;
     }
@@ -210,6 +213,7 @@ export async function rebuildIndex(): Promise<void> {
       }
     }
     
+    await invoke('mark_search_index_rebuilt');
     console.log(`Search index rebuild complete. Indexed ${totalIndexed} items from ${vaults.length} vaults.`);
   } catch (error) {
     console.error('Error rebuilding search index:', error);
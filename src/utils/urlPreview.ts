@@ -2,7 +2,7 @@
  * URL Preview Utilities for brainbox
  *
  * This module provides functions for URL validation, YouTube detection,
- * thumbnail generation, and favicon retrieval.
+ * and thumbnail generation. Favicons come from the backend cache (see useFavicon).
  */
 
 /**
@@ -108,23 +108,3 @@ export function youtubeEmbedUrl(id: string): string {
   return `https://www.youtube.com/embed/${id}`;
 }
 
-/**
- * Gets a favicon URL for a given website URL using Google's favicon service
- *
- * @param input - The website URL
- * @returns The favicon URL, or null if the input is not a valid URL
- *
- * @example
- * faviconForUrl('https://github.com')
- * // 'https://www.google.com/s2/favicons?sz=64&domain=github.com'
- */
-export function faviconForUrl(input?: string): string | null {
-  if (!isUrl(input)) return null;
-  try {
-    const u = new URL(input!);
-    return `https://www.google.com/s2/favicons?sz=64&domain=${u.hostname}`;
-  } catch {
-    return null;
-  }
-}
-
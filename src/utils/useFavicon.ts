@@ -0,0 +1,49 @@
+/**
+ * Favicon Hook
+ *
+ * Resolves a site's favicon through the backend cache (get_favicon), which
+ * fetches it from the site once and serves a local copy afterwards.
+ */
+
+import { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { isUrl } from './urlPreview';
+
+// One lookup per host per session, shared by every card showing that host
+const lookups = new Map<string, Promise<string | null>>();
+
+function hostOf(input?: string): string | null {
+  if (!isUrl(input)) return null;
+  try {
+    return new URL(input!).hostname.toLowerCase();
+  } catch {
+    return null;
+  }
+}
+
+export function getFavicon(input?: string): Promise<string | null> {
+  const host = hostOf(input);
+  if (!host) return Promise.resolve(null);
+  let lookup = lookups.get(host);
+  if (!lookup) {
+    lookup = invoke<string | null>('get_favicon', { host }).catch(() => null);
+    lookups.set(host, lookup);
+  }
+  return lookup;
+}
+
+/**
+ * Local favicon URL for a website URL, or null while loading / if the site has none
+ */
+export function useFavicon(input?: string): string | null {
+  const [favicon, setFavicon] = useState<string | null>(null);
+
+  useEffect(() => {
+    let cancelled = false;
+    setFavicon(null);
+    getFavicon(input).then((url) => { if (!cancelled) setFavicon(url); });
+    return () => { cancelled = true; };
+  }, [input]);
+
+  return favicon;
+}
@@ -3,12 +3,12 @@
  * 
  * Handles:
  * - Checking for remote sync on startup
- * - Triggering sync export on app close (if enabled)
+ * - Manual sync export (sync on close runs in the backend)
  */
 
 import { useEffect, useRef, useState, useCallback } from 'react';
 import { invoke } from '@tauri-apps/api/core';
-import { getCurrentWindow } from '@tauri-apps/api/window';
+import { getErrorMessage } from './errors';
 
 interface SyncStatus {
   sync_enabled: boolean;
@@ -61,7 +61,6 @@ export function useSyncManager(options: UseSyncManagerOptions = {}): UseSyncMana
   const [pendingSync, setPendingSync] = useState<SyncPreview | null>(null);
   
   const hasCheckedStartup = useRef(false);
-  const closeListenerSetup = useRef(false);
 
   // Check for remote sync availability
   const checkForRemoteSync = useCallback(async (): Promise<SyncPreview | null> => {
@@ -87,7 +86,7 @@ export function useSyncManager(options: UseSyncManagerOptions = {}): UseSyncMana
       }
       return null;
     } catch (error) {
-      const message = error instanceof Error ? error.message : String(error);
+      const message = getErrorMessage(error);
       console.error('Failed to check remote sync:', message);
       onSyncError?.(message);
       return null;
@@ -104,7 +103,7 @@ export function useSyncManager(options: UseSyncManagerOptions = {}): UseSyncMana
       showToast?.('success', 'Sync export completed');
       return true;
     } catch (error) {
-      const message = error instanceof Error ? error.message : String(error);
+      const message = getErrorMessage(error);
       console.error('Sync export failed:', message);
       showToast?.('error', `Sync export failed: ${message}`);
       return false;
@@ -131,60 +130,7 @@ export function useSyncManager(options: UseSyncManagerOptions = {}): UseSyncMana
     return () => clearTimeout(timer);
   }, [checkForRemoteSync]);
 
-  // Setup window close listener for sync on close
-  useEffect(() => {
-    if (closeListenerSetup.current) return;
-    closeListenerSetup.current = true;
-
-    const setupCloseListener = async () => {
-      try {
-        const window = getCurrentWindow();
-        
-        // Listen for close request
-        const unlisten = await window.onCloseRequested(async (event) => {
-          // Check if sync on close is enabled
-          let syncOnCloseEnabled = false;
-          try {
-            syncOnCloseEnabled = await invoke<boolean>('is_sync_on_close_enabled');
-          } catch {
-            // If we can't check, don't sync
-          }
-
-          if (syncOnCloseEnabled) {
-            // Prevent the close temporarily
-            event.preventDefault();
-            
-            try {
-              // Try to export (without passwords for password-protected vaults)
-              // Users should have unlocked vaults during their session if they want them synced
-              await invoke('sync_export_vaults', { passwords: {} });
-            } catch (error) {
-              console.error('Sync on close failed:', error);
-              // Don't block the close even if sync fails
-            }
-
-            // Now actually close the window
-            await window.close();
-          }
-          // If sync on close is not enabled, the window will close normally
-        });
-
-        // Store unlisten function for cleanup
-        return unlisten;
-      } catch (error) {
-        console.error('Failed to setup close listener:', error);
-      }
-    };
-
-    let unlisten: (() => void) | undefined;
-    setupCloseListener().then((fn) => {
-      unlisten = fn;
-    });
-
-    return () => {
-      if (unlisten) unlisten();
-    };
-  }, []);
+  // Sync on close is handled by the backend when the main window closes (see lifecycle.rs)
 
   return {
     checkForRemoteSync,